@@ -0,0 +1,16 @@
+import { NextResponse } from 'next/server';
+import { getPublicJwks } from '@/lib/ohfixit/jwt';
+
+export const dynamic = 'force-dynamic';
+
+// Public keys the desktop helper verifies approval tokens with
+export async function GET() {
+  try {
+    return NextResponse.json(await getPublicJwks(), {
+      headers: { 'Cache-Control': 'public, max-age=300' },
+    });
+  } catch (error) {
+    console.error('Failed to build JWKS', error);
+    return NextResponse.json({ error: 'Signing key not configured' }, { status: 500 });
+  }
+}
@@ -8,7 +8,9 @@ import { db } from '@/lib/db/client';
 import { actionLog, rollbackPoint } from '@/lib/db/schema';
 import { desc, eq } from 'drizzle-orm';
 import { resolveActorIds } from '@/lib/ohfixit/logger';
-import { signAutomationToken } from '@/lib/ohfixit/jwt';
+import { signAutomationToken, tokenScope } from '@/lib/ohfixit/jwt';
+import { approvalTextHash } from '@/lib/ohfixit/receipt';
+import { attestationProblem, freezeState, resolveDevice } from '@/lib/ohfixit/devices';
 
 const ActionOperation = z.enum(['preview', 'approve', 'execute', 'rollback']);
 
@@ -19,6 +21,7 @@ const actionRequestSchema = z.object({
   chatId: z.string().optional(),
   approvalId: z.string().optional(),
   sessionId: z.string().optional(),
+  deviceId: z.string().uuid().optional(),
 });
 
 export async function POST(request: NextRequest) {
@@ -29,7 +32,7 @@ export async function POST(request: NextRequest) {
     }
 
     const body = await request.json();
-    const { operation, actionId, parameters, chatId, approvalId } = actionRequestSchema.parse(body);
+    const { operation, actionId, parameters, chatId, approvalId, deviceId: requestedDeviceId } = actionRequestSchema.parse(body);
 
     if (operation === 'preview') {
       const preview = generateActionPreview(actionId, parameters ?? {});
@@ -49,11 +52,27 @@ export async function POST(request: NextRequest) {
       });
     }
 
+    // Helper tokens minted below only work on the user's paired helper
+    const device = await resolveDevice(session.user.id, requestedDeviceId);
+    if (!device) {
+      return NextResponse.json({ error: 'No paired desktop helper' }, { status: 409 });
+    }
+    const buildProblem = attestationProblem(device);
+    if (buildProblem) {
+      return NextResponse.json({ error: buildProblem }, { status: 403 });
+    }
+    // The helper refuses anyway; saying so here spares minting a dead token
+    const freeze = freezeState(device);
+    if (freeze.frozen) {
+      return NextResponse.json({ error: `Execution is frozen: ${freeze.reason}` }, { status: 423 });
+    }
+
     if (operation === 'approve') {
       const id = uuidv4();
       const expiresAt = new Date(Date.now() + 10 * 60 * 1000); // 10 minutes
       // Validate action is allowlisted by generating a preview
       const preview = generateActionPreview(actionId, parameters ?? {});
+      const textHash = approvalTextHash(preview);
       // Mint a token for the desktop helper to begin handshake if needed
       const { userId, anonymousId } = await resolveActorIds();
       const helperToken = await signAutomationToken(
@@ -63,7 +82,10 @@ export async function POST(request: NextRequest) {
           anonymousId,
           actionId,
           approvalId: id,
-          scope: 'both',
+          approvalTextHash: textHash,
+          deviceId: device.id,
+          deviceFingerprint: device.fingerprint ?? undefined,
+          scope: tokenScope(['automation:execute', 'diagnostics:read']),
         },
         60 * 10,
       );
@@ -74,7 +96,7 @@ export async function POST(request: NextRequest) {
         actionType: 'script_recommendation',
         status: 'approved',
         summary: `Approved ${actionId}`,
-        payload: { actionId, approvalId: id, expiresAt: expiresAt.toISOString(), preview },
+        payload: { actionId, approvalId: id, approvalTextHash: textHash, expiresAt: expiresAt.toISOString(), preview },
       }).catch(() => [] as any);
       const actionLogId = rows?.[0]?.id ?? null;
       // Create a rollback point stub at approval time (pre-execution snapshot)
@@ -124,7 +146,10 @@ export async function POST(request: NextRequest) {
           anonymousId,
           actionId,
           approvalId,
-          scope: 'both',
+          approvalTextHash: (matched.payload as Record<string, any> | null)?.approvalTextHash,
+          deviceId: device.id,
+          deviceFingerprint: device.fingerprint ?? undefined,
+          scope: tokenScope(['automation:execute', 'diagnostics:read']),
         },
         60 * 10,
       );
@@ -162,7 +187,9 @@ export async function POST(request: NextRequest) {
           anonymousId,
           actionId,
           approvalId: approvalId ?? undefined,
-          scope: 'both',
+          deviceId: device.id,
+          deviceFingerprint: device.fingerprint ?? undefined,
+          scope: tokenScope(['automation:execute', 'diagnostics:read']),
         },
         60 * 10,
       );
@@ -0,0 +1,36 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { redeemDeepLink } from '@/lib/ohfixit/devices';
+
+export const dynamic = 'force-dynamic';
+
+// Called by the desktop helper (no session) when it's opened with an
+// ohfixit:// link: once to check it was issued here, then again to redeem it
+// after the user confirmed
+const schema = z.object({
+  deviceId: z.string().uuid(),
+  nonce: z.string().regex(/^[A-Za-z0-9_-]{16,64}$/),
+  kind: z.enum(['action', 'pair']),
+  actionId: z.string().max(64).nullish(),
+  signature: z.string().min(1),
+  consume: z.boolean(),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const result = await redeemDeepLink(schema.parse(await req.json()));
+    switch (result.status) {
+      case 'invalid':
+        return NextResponse.json({ error: 'Invalid device signature' }, { status: 401 });
+      case 'unknown':
+        return NextResponse.json({ error: 'Unknown, expired or already used link' }, { status: 404 });
+      case 'conflict':
+        return NextResponse.json({ error: 'Device is paired to another account' }, { status: 409 });
+      default:
+        return NextResponse.json({ status: result.status, helperToken: result.helperToken ?? null });
+    }
+  } catch (err: any) {
+    console.error('helper/deep-link/redeem error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to redeem deep link' }, { status: 400 });
+  }
+}
@@ -0,0 +1,50 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { auth } from '@/app/(auth)/auth';
+import { issueDeepLink } from '@/lib/ohfixit/devices';
+import { verifyAutomationToken } from '@/lib/ohfixit/jwt';
+
+export const dynamic = 'force-dynamic';
+
+// An ohfixit:// link for the signed-in user. Action links wrap the helper
+// token from an approval (POST /api/automation/action), so a link can only
+// start what the user already approved.
+const schema = z.discriminatedUnion('kind', [
+  z.object({
+    kind: z.literal('action'),
+    actionId: z.string().regex(/^[a-z0-9_-]{1,64}$/),
+    helperToken: z.string().min(1),
+  }),
+  z.object({ kind: z.literal('pair') }),
+]);
+
+export async function POST(req: NextRequest) {
+  try {
+    const session = await auth();
+    if (!session?.user?.id) {
+      return NextResponse.json({ error: 'Unauthorized' }, { status: 401 });
+    }
+
+    const request = schema.parse(await req.json());
+    if (request.kind === 'pair') {
+      const link = await issueDeepLink({ userId: session.user.id, kind: 'pair' });
+      return NextResponse.json({ url: link.url, expiresAt: link.expiresAt.toISOString() });
+    }
+
+    const claims = await verifyAutomationToken(request.helperToken).catch(() => null);
+    if (!claims || claims.userId !== session.user.id || claims.actionId !== request.actionId || !claims.deviceId) {
+      return NextResponse.json({ error: 'Helper token does not match this action' }, { status: 403 });
+    }
+    const link = await issueDeepLink({
+      userId: session.user.id,
+      kind: 'action',
+      actionId: request.actionId,
+      deviceId: claims.deviceId,
+      helperToken: request.helperToken,
+    });
+    return NextResponse.json({ url: link.url, expiresAt: link.expiresAt.toISOString() });
+  } catch (err: any) {
+    console.error('helper/deep-link error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to create deep link' }, { status: 400 });
+  }
+}
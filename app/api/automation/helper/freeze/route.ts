@@ -0,0 +1,33 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { auth } from '@/app/(auth)/auth';
+import { setFreeze } from '@/lib/ohfixit/devices';
+
+export const dynamic = 'force-dynamic';
+
+// Freeze (or lift the freeze on) the signed-in user's helpers: one device, or
+// all of them when deviceId is omitted. Helpers poll for it every minute.
+const schema = z.object({
+  deviceId: z.string().uuid().optional(),
+  frozen: z.boolean(),
+  reason: z.string().max(256).optional(),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const session = await auth();
+    if (!session?.user?.id) {
+      return NextResponse.json({ error: 'Unauthorized' }, { status: 401 });
+    }
+
+    const request = schema.parse(await req.json());
+    const devices = await setFreeze(session.user.id, request);
+    if (devices === 0) {
+      return NextResponse.json({ error: 'No paired helper found' }, { status: 404 });
+    }
+    return NextResponse.json({ frozen: request.frozen, devices });
+  } catch (err: any) {
+    console.error('helper/freeze error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to change execution freeze' }, { status: 400 });
+  }
+}
@@ -0,0 +1,29 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { pollFreeze } from '@/lib/ohfixit/devices';
+
+export const dynamic = 'force-dynamic';
+
+// Polled by a paired desktop helper (no session) every minute, signed with its
+// device key over `${deviceId}.${timestamp}`
+const schema = z.object({
+  deviceId: z.string().uuid(),
+  timestamp: z.number().int(),
+  signature: z.string().min(1),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const state = await pollFreeze(schema.parse(await req.json()));
+    if (state === 'unpaired') {
+      return NextResponse.json({ error: 'Device is not paired' }, { status: 409 });
+    }
+    if (state === 'invalid') {
+      return NextResponse.json({ error: 'Invalid timestamp or signature' }, { status: 401 });
+    }
+    return NextResponse.json(state);
+  } catch (err: any) {
+    console.error('helper/freeze/status error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to check execution freeze' }, { status: 400 });
+  }
+}
@@ -0,0 +1,33 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { recordInterruption } from '@/lib/ohfixit/devices';
+
+export const dynamic = 'force-dynamic';
+
+// Called by a paired desktop helper (no session, no approval token) at
+// startup for each execution it didn't live to finish, signed with its
+// device key and keyed by the approval it ran under
+const schema = z.object({
+  deviceId: z.string().uuid(),
+  payload: z.string().min(1).max(4096),
+  signature: z.string().min(1),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const status = await recordInterruption(schema.parse(await req.json()));
+    if (status === 'unpaired') {
+      return NextResponse.json({ error: 'Device is not paired' }, { status: 409 });
+    }
+    if (status === 'invalid') {
+      return NextResponse.json({ error: 'Invalid interruption report or signature' }, { status: 401 });
+    }
+    if (status === 'unknown') {
+      return NextResponse.json({ error: 'No action log for this approval' }, { status: 404 });
+    }
+    return NextResponse.json({ status });
+  } catch (err: any) {
+    console.error('helper/interrupted error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to record interruption' }, { status: 400 });
+  }
+}
@@ -0,0 +1,29 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { recordAttestation } from '@/lib/ohfixit/devices';
+
+export const dynamic = 'force-dynamic';
+
+// Called hourly by a paired desktop helper (no session): the hash and code
+// signature state of the build it's running, signed with its device key
+const schema = z.object({
+  deviceId: z.string().uuid(),
+  payload: z.string().min(1).max(4096),
+  signature: z.string().min(1),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const status = await recordAttestation(schema.parse(await req.json()));
+    if (status === 'unpaired') {
+      return NextResponse.json({ error: 'Device is not paired' }, { status: 409 });
+    }
+    if (status === 'invalid') {
+      return NextResponse.json({ error: 'Invalid attestation signature' }, { status: 401 });
+    }
+    return NextResponse.json({ status });
+  } catch (err: any) {
+    console.error('helper/pair/attestation error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to record attestation' }, { status: 400 });
+  }
+}
@@ -0,0 +1,30 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { enrollCertificate } from '@/lib/ohfixit/devices';
+
+export const dynamic = 'force-dynamic';
+
+// Called by a paired desktop helper (no session): pin the client certificate
+// it will present on reports
+const schema = z.object({
+  deviceId: z.string().uuid(),
+  certificate: z.string().min(1).max(8192),
+  signature: z.string().min(1),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const enrollment = schema.parse(await req.json());
+    const { status, expiresAt } = await enrollCertificate(enrollment);
+    if (status === 'unpaired') {
+      return NextResponse.json({ error: 'Device is not paired' }, { status: 409 });
+    }
+    if (status === 'invalid') {
+      return NextResponse.json({ error: 'Invalid certificate or signature' }, { status: 401 });
+    }
+    return NextResponse.json({ status, expiresAt: expiresAt?.toISOString() ?? null });
+  } catch (err: any) {
+    console.error('helper/pair/certificate error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to enroll certificate' }, { status: 400 });
+  }
+}
@@ -0,0 +1,42 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { auth } from '@/app/(auth)/auth';
+import { confirmPairing } from '@/lib/ohfixit/devices';
+import { logConsent } from '@/lib/ohfixit/logger';
+
+export const dynamic = 'force-dynamic';
+
+// The signed-in user enters the code their desktop helper shows
+const schema = z.object({
+  code: z.string().min(4).max(16),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const session = await auth();
+    if (!session?.user?.id) {
+      return NextResponse.json({ error: 'Unauthorized' }, { status: 401 });
+    }
+
+    const { code } = schema.parse(await req.json());
+    const device = await confirmPairing(code, session.user.id);
+    if (!device) {
+      return NextResponse.json({ error: 'Unknown or expired pairing code' }, { status: 404 });
+    }
+
+    await logConsent({
+      chatId: 'provisional',
+      kind: 'automation',
+      payload: { event: 'device_paired', deviceId: device.id, name: device.name, os: device.os },
+    }).catch(() => {});
+    return NextResponse.json({
+      deviceId: device.id,
+      name: device.name,
+      os: device.os,
+      pairedAt: device.pairedAt?.toISOString() ?? null,
+    });
+  } catch (err: any) {
+    console.error('helper/pair/confirm error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to pair device' }, { status: 400 });
+  }
+}
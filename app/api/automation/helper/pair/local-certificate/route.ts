@@ -0,0 +1,29 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { shareLocalCertificate } from '@/lib/ohfixit/devices';
+
+export const dynamic = 'force-dynamic';
+
+// Called by a paired desktop helper (no session): the certificate its local
+// API serves, which calls to it then require
+const schema = z.object({
+  deviceId: z.string().uuid(),
+  certificate: z.string().min(1).max(8192),
+  signature: z.string().min(1),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const status = await shareLocalCertificate(schema.parse(await req.json()));
+    if (status === 'unpaired') {
+      return NextResponse.json({ error: 'Device is not paired' }, { status: 409 });
+    }
+    if (status === 'invalid') {
+      return NextResponse.json({ error: 'Invalid certificate or signature' }, { status: 401 });
+    }
+    return NextResponse.json({ status });
+  } catch (err: any) {
+    console.error('helper/pair/local-certificate error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to store local certificate' }, { status: 400 });
+  }
+}
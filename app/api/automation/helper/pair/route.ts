@@ -0,0 +1,34 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { registerDevice, verifyRegistrationSignature } from '@/lib/ohfixit/devices';
+
+export const dynamic = 'force-dynamic';
+
+// Called by the desktop helper (no session): announce its key and pairing code
+const schema = z.object({
+  deviceId: z.string().uuid(),
+  publicKey: z.string().min(1),
+  pairingCode: z.string().min(4).max(16),
+  fingerprint: z.string().regex(/^[0-9a-f]{64}$/),
+  signature: z.string().min(1),
+  os: z.string().min(1),
+  name: z.string().min(1),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const registration = schema.parse(await req.json());
+    if (!verifyRegistrationSignature(registration)) {
+      return NextResponse.json({ error: 'Invalid registration signature' }, { status: 401 });
+    }
+
+    const { status, pairedAt } = await registerDevice(registration);
+    if (status === 'conflict') {
+      return NextResponse.json({ error: 'Device is paired with a different key or on different hardware' }, { status: 409 });
+    }
+    return NextResponse.json({ status, pairedAt: pairedAt?.toISOString() ?? null });
+  } catch (err: any) {
+    console.error('helper/pair error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to register device' }, { status: 400 });
+  }
+}
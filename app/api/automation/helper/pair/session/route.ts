@@ -0,0 +1,29 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { shareSessionToken } from '@/lib/ohfixit/devices';
+
+export const dynamic = 'force-dynamic';
+
+// Called by a paired desktop helper (no session): the token its local API
+// requires from the web app
+const schema = z.object({
+  deviceId: z.string().uuid(),
+  sessionToken: z.string().min(32).max(128),
+  signature: z.string().min(1),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const status = await shareSessionToken(schema.parse(await req.json()));
+    if (status === 'unpaired') {
+      return NextResponse.json({ error: 'Device is not paired' }, { status: 409 });
+    }
+    if (status === 'invalid') {
+      return NextResponse.json({ error: 'Invalid session signature' }, { status: 401 });
+    }
+    return NextResponse.json({ status });
+  } catch (err: any) {
+    console.error('helper/pair/session error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to store session token' }, { status: 400 });
+  }
+}
@@ -1,6 +1,8 @@
 import { NextRequest, NextResponse } from 'next/server';
 import { z } from 'zod';
 import { verifyAutomationToken } from '@/lib/ohfixit/jwt';
+import { verifyExecutionReceipt } from '@/lib/ohfixit/receipt';
+import { forwardedClientCertificate, verifyClientCertificate } from '@/lib/ohfixit/devices';
 import { db } from '@/lib/db/client';
 import { actionArtifact, actionLog, rollbackPoint } from '@/lib/db/schema';
 import { eq, desc } from 'drizzle-orm';
@@ -18,6 +20,16 @@ const rollbackSchema = z.object({
   data: z.any().optional(),
 });
 
+const receiptSchema = z.object({
+  approvalId: z.string(),
+  approvalTextHash: z.string().nullable(),
+  actionId: z.string(),
+  commandsHash: z.string(),
+  success: z.boolean(),
+  executedAt: z.string(),
+  signature: z.string(),
+});
+
 const payloadSchema = z.object({
   actionLogId: z.string().optional(),
   actionId: z.string().optional(),
@@ -25,6 +37,7 @@ const payloadSchema = z.object({
   output: z.string().optional(),
   artifacts: z.array(artifactSchema).default([]),
   rollbackPoint: rollbackSchema.optional(),
+  receipt: receiptSchema.nullable().optional(),
 });
 
 export async function POST(req: NextRequest) {
@@ -37,8 +50,25 @@ export async function POST(req: NextRequest) {
     const claims = await verifyAutomationToken(token);
     if (!claims) return NextResponse.json({ error: 'Invalid token' }, { status: 401 });
 
+    // A stolen token isn't enough: the report must come over the paired
+    // helper's client certificate when one is presented or required
+    const clientCert = forwardedClientCertificate(req.headers);
+    if (clientCert || process.env.OHFIXIT_REQUIRE_CLIENT_CERT === 'true') {
+      if (!(await verifyClientCertificate(clientCert, claims.deviceId))) {
+        return NextResponse.json({ error: 'Client certificate does not match the paired device' }, { status: 401 });
+      }
+    }
+
     const body = await req.json();
-    const { actionLogId, actionId, success, output, artifacts, rollbackPoint: rb } = payloadSchema.parse(body);
+    const { actionLogId, actionId, success, output, artifacts, rollbackPoint: rb, receipt } = payloadSchema.parse(body);
+
+    // A receipt only counts if it was signed with our secret for the approval this token carries
+    const receiptVerified = receipt
+      ? verifyExecutionReceipt(receipt, { approvalId: claims.approvalId, approvalTextHash: claims.approvalTextHash })
+      : false;
+    if (receipt && !receiptVerified) {
+      console.warn('helper/report: execution receipt failed verification', { actionId, approvalId: receipt.approvalId });
+    }
 
     // Determine outcome from success boolean
     const outcome = success === false ? 'failure' : 'success';
@@ -74,7 +104,7 @@ export async function POST(req: NextRequest) {
         outcome,
         executionHost: 'desktop-helper',
         summary: `Executed ${actionId || 'unknown action'}`,
-        payload: { actionId, output, success },
+        payload: { actionId, output, success, receipt, receiptVerified },
       }).returning();
 
       finalActionLogId = newLog[0].id;
@@ -85,7 +115,7 @@ export async function POST(req: NextRequest) {
         .set({
           outcome,
           executionHost: 'desktop-helper',
-          payload: { actionId, output, success },
+          payload: { actionId, output, success, receipt, receiptVerified },
         })
         .where(eq(actionLog.id, finalActionLogId));
     }
@@ -0,0 +1,29 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { z } from 'zod';
+import { recordSecurityEvent } from '@/lib/ohfixit/devices';
+
+export const dynamic = 'force-dynamic';
+
+// Called by a paired desktop helper (no session) when it locks out a local
+// source for presenting invalid approval tokens, signed with its device key
+const schema = z.object({
+  deviceId: z.string().uuid(),
+  payload: z.string().min(1).max(4096),
+  signature: z.string().min(1),
+});
+
+export async function POST(req: NextRequest) {
+  try {
+    const status = await recordSecurityEvent(schema.parse(await req.json()));
+    if (status === 'unpaired') {
+      return NextResponse.json({ error: 'Device is not paired' }, { status: 409 });
+    }
+    if (status === 'invalid') {
+      return NextResponse.json({ error: 'Invalid security event or signature' }, { status: 401 });
+    }
+    return NextResponse.json({ status });
+  } catch (err: any) {
+    console.error('helper/security-event error', err);
+    return NextResponse.json({ error: err?.message ?? 'Failed to record security event' }, { status: 400 });
+  }
+}
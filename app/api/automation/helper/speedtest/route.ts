@@ -0,0 +1,67 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { grantedScopes, verifyAutomationToken } from '@/lib/ohfixit/jwt';
+
+export const dynamic = 'force-dynamic';
+
+// The desktop helper's default speed test endpoint (POST /net/speedtest on the
+// helper): GET ?bytes=N streams N bytes, POST reads and discards the body.
+// Only callers holding a diagnostics:read token for a paired device get to
+// use the bandwidth.
+const MAX_BYTES = 25_000_000;
+const CHUNK = new Uint8Array(64 * 1024);
+
+async function authorized(req: NextRequest): Promise<boolean> {
+  const m = (req.headers.get('authorization') || '').match(/^Bearer\s+(.+)$/i);
+  if (!m) return false;
+  try {
+    const claims = await verifyAutomationToken(m[1]);
+    return Boolean(claims.deviceId) && grantedScopes(claims.scope).includes('diagnostics:read');
+  } catch {
+    return false;
+  }
+}
+
+export async function GET(req: NextRequest) {
+  if (!(await authorized(req))) {
+    return NextResponse.json({ error: 'A diagnostics token is required' }, { status: 401 });
+  }
+  const requested = Number.parseInt(req.nextUrl.searchParams.get('bytes') ?? '', 10);
+  const total = Math.min(Number.isFinite(requested) && requested > 0 ? requested : MAX_BYTES, MAX_BYTES);
+  let sent = 0;
+  const body = new ReadableStream<Uint8Array>({
+    pull(controller) {
+      if (sent >= total) {
+        controller.close();
+        return;
+      }
+      const size = Math.min(CHUNK.length, total - sent);
+      controller.enqueue(CHUNK.subarray(0, size));
+      sent += size;
+    },
+  });
+  return new Response(body, {
+    headers: {
+      'Content-Type': 'application/octet-stream',
+      'Content-Length': String(total),
+      'Cache-Control': 'no-store',
+    },
+  });
+}
+
+export async function POST(req: NextRequest) {
+  if (!(await authorized(req))) {
+    return NextResponse.json({ error: 'A diagnostics token is required' }, { status: 401 });
+  }
+  let bytes = 0;
+  const reader = req.body?.getReader();
+  while (reader) {
+    const { done, value } = await reader.read();
+    if (done) break;
+    bytes += value.byteLength;
+    if (bytes > MAX_BYTES) {
+      await reader.cancel();
+      return NextResponse.json({ error: 'Upload too large' }, { status: 413 });
+    }
+  }
+  return NextResponse.json({ bytes });
+}
@@ -2,7 +2,8 @@ import { NextRequest, NextResponse } from 'next/server';
 import { z } from 'zod';
 import { auth } from '@/app/(auth)/auth';
 import { resolveActorIds } from '@/lib/ohfixit/logger';
-import { signAutomationToken } from '@/lib/ohfixit/jwt';
+import { TOKEN_SCOPES, signAutomationToken, tokenScope } from '@/lib/ohfixit/jwt';
+import { attestationProblem, freezeState, resolveDevice } from '@/lib/ohfixit/devices';
 
 export const dynamic = 'force-dynamic';
 
@@ -10,7 +11,13 @@ const schema = z.object({
   chatId: z.string().nullable().optional(),
   actionId: z.string().optional(),
   approvalId: z.string().optional(),
-  scope: z.enum(['execute', 'report', 'both']).default('both').optional(),
+  deviceId: z.string().uuid().optional(),
+  // A list of TOKEN_SCOPES, e.g. ['diagnostics:read'] for a health scan, or a
+  // legacy value
+  scope: z
+    .union([z.array(z.enum(TOKEN_SCOPES)).min(1), z.enum(['execute', 'report', 'both'])])
+    .default('both')
+    .optional(),
 });
 
 export async function POST(req: NextRequest) {
@@ -21,9 +28,22 @@ export async function POST(req: NextRequest) {
     }
 
     const body = await req.json();
-    const { chatId, actionId, approvalId, scope } = schema.parse(body);
+    const { chatId, actionId, approvalId, deviceId: requestedDeviceId, scope } = schema.parse(body);
 
     const { userId, anonymousId } = await resolveActorIds();
+    const device = await resolveDevice(userId, requestedDeviceId);
+    if (!device) {
+      return NextResponse.json({ error: 'No paired desktop helper' }, { status: 409 });
+    }
+    const buildProblem = attestationProblem(device);
+    if (buildProblem) {
+      return NextResponse.json({ error: buildProblem }, { status: 403 });
+    }
+    // The helper refuses anyway; saying so here spares minting a dead token
+    const freeze = freezeState(device);
+    if (freeze.frozen) {
+      return NextResponse.json({ error: `Execution is frozen: ${freeze.reason}` }, { status: 423 });
+    }
     const token = await signAutomationToken(
       {
         chatId: chatId ?? null,
@@ -31,7 +51,9 @@ export async function POST(req: NextRequest) {
         anonymousId,
         actionId,
         approvalId,
-        scope: scope ?? 'both',
+        deviceId: device.id,
+        deviceFingerprint: device.fingerprint ?? undefined,
+        scope: Array.isArray(scope) ? tokenScope(scope) : scope ?? 'both',
       },
       60 * 10, // 10 minutes
     );
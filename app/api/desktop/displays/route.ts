@@ -1,4 +1,7 @@
 import { NextRequest, NextResponse } from 'next/server';
+import { auth } from '@/app/(auth)/auth';
+import { helperPinnedCertificate, helperScreenToken, helperSessionHeaders } from '@/lib/ohfixit/devices';
+import { helperBaseUrl, helperFetch, type PinnedCertificate } from '@/lib/ohfixit/helper-endpoint';
 
 /**
  * Desktop Displays API Endpoint
@@ -8,8 +11,15 @@ import { NextRequest, NextResponse } from 'next/server';
  */
 export async function GET(request: NextRequest) {
   try {
+    // The helper's local API requires the paired device's session token
+    const session = await auth();
+    const sessionHeaders = await helperSessionHeaders(session?.user?.id);
+    // ...and, over TLS, the certificate it shared
+    const pinned = await helperPinnedCertificate(session?.user?.id);
+    const baseUrl = await helperBaseUrl();
+
     // Check if desktop helper is available
-    const helperStatus = await checkDesktopHelperStatus();
+    const helperStatus = await checkDesktopHelperStatus(baseUrl, sessionHeaders, pinned);
     if (!helperStatus.connected) {
       return NextResponse.json(
         {
@@ -22,8 +32,17 @@ export async function GET(request: NextRequest) {
       );
     }
 
+    // The helper's /displays needs a token carrying the screen:capture scope
+    const screenToken = await helperScreenToken(session?.user?.id);
+    if ('error' in screenToken) {
+      return NextResponse.json(
+        { success: false, displays: [], error: screenToken.error },
+        { status: screenToken.status }
+      );
+    }
+
     // Get display information from desktop helper
-    const displaysResult = await getDisplaysFromDesktopHelper();
+    const displaysResult = await getDisplaysFromDesktopHelper(baseUrl, sessionHeaders, pinned, screenToken.token);
 
     if (!displaysResult.success) {
       return NextResponse.json(
@@ -78,18 +97,19 @@ type Display = {
 /**
  * Check desktop helper connection status
  */
-async function checkDesktopHelperStatus(): Promise<{
+async function checkDesktopHelperStatus(baseUrl: string, sessionHeaders: Record<string, string>, pinned: PinnedCertificate | null): Promise<{
   connected: boolean;
   version?: string;
 }> {
   try {
-    const response = await fetch('http://localhost:8765/status', {
+    const response = await helperFetch(`${baseUrl}/status`, {
       method: 'GET',
       headers: {
         'Content-Type': 'application/json',
+        ...sessionHeaders,
       },
       signal: AbortSignal.timeout(5000)
-    });
+    }, pinned);
 
     if (response.ok) {
       const status = await response.json();
@@ -108,7 +128,12 @@ async function checkDesktopHelperStatus(): Promise<{
 /**
  * Get display information from desktop helper
  */
-async function getDisplaysFromDesktopHelper(): Promise<{
+async function getDisplaysFromDesktopHelper(
+  baseUrl: string,
+  sessionHeaders: Record<string, string>,
+  pinned: PinnedCertificate | null,
+  token: string,
+): Promise<{
   success: boolean;
   displays?: Array<Display>;
   primaryDisplay?: string;
@@ -116,20 +141,22 @@ async function getDisplaysFromDesktopHelper(): Promise<{
   details?: string;
 }> {
   try {
-    const response = await fetch('http://localhost:8765/displays', {
+    const response = await helperFetch(`${baseUrl}/displays`, {
       method: 'GET',
       headers: {
         'Content-Type': 'application/json',
+        Authorization: `Bearer ${token}`,
+        ...sessionHeaders,
       },
       signal: AbortSignal.timeout(10000) // 10 second timeout
-    });
+    }, pinned);
 
     if (!response.ok) {
       const errorData = await response.json().catch(() => ({}));
       return {
         success: false,
-        error: `Desktop helper error: ${response.status}`,
-        details: errorData.message || response.statusText
+        error: errorData.error || `Desktop helper error: ${response.status}`,
+        details: errorData.details || errorData.message || response.statusText
       };
     }
 
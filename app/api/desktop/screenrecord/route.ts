@@ -0,0 +1,126 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { auth } from '@/app/(auth)/auth';
+import { helperPinnedCertificate, helperScreenToken, helperSessionHeaders } from '@/lib/ohfixit/devices';
+import { helperBaseUrl, helperFetch } from '@/lib/ohfixit/helper-endpoint';
+import { z } from 'zod';
+
+const screenRecordRequestSchema = z.object({
+  region: z.object({
+    x: z.number(),
+    y: z.number(),
+    width: z.number(),
+    height: z.number()
+  }).optional(),
+  display: z.number().int().min(0).default(0),
+  includeCursor: z.boolean().default(true),
+  durationSecs: z.number().int().min(1).max(30).default(10),
+  fps: z.number().int().min(1).max(15).default(5),
+  format: z.enum(['webm', 'mp4']).default('webm'),
+  // Shown to the user in the helper's consent dialog
+  reason: z.string().max(200).optional()
+});
+
+// The user has two minutes to answer the helper's consent dialog, then the
+// clip records and encodes; the token must outlive all of it because the
+// helper uploads the clip with it
+const SCREEN_RECORD_TOKEN_TTL_SECONDS = 300;
+
+/**
+ * Desktop Screen Recording API Endpoint
+ *
+ * Asks the desktop helper for a short clip (at most 30 seconds) of one display
+ * or a region of it. The user confirms on their computer; the helper also
+ * uploads the clip as a `screen_recording` artifact through its report.
+ */
+export async function POST(request: NextRequest) {
+  try {
+    const session = await auth();
+    const sessionHeaders = await helperSessionHeaders(session?.user?.id);
+    const pinned = await helperPinnedCertificate(session?.user?.id);
+    const baseUrl = await helperBaseUrl();
+
+    const body = await request.json();
+    const validatedInput = screenRecordRequestSchema.parse(body);
+
+    const screenToken = await helperScreenToken(session?.user?.id, SCREEN_RECORD_TOKEN_TTL_SECONDS);
+    if ('error' in screenToken) {
+      return NextResponse.json({ success: false, error: screenToken.error }, { status: screenToken.status });
+    }
+
+    let response: Response;
+    try {
+      response = await helperFetch(`${baseUrl}/screenrecord`, {
+        method: 'POST',
+        headers: {
+          'Content-Type': 'application/json',
+          Authorization: `Bearer ${screenToken.token}`,
+          ...sessionHeaders,
+        },
+        body: JSON.stringify(validatedInput),
+        signal: AbortSignal.timeout(SCREEN_RECORD_TOKEN_TTL_SECONDS * 1000)
+      }, pinned);
+    } catch (error) {
+      return NextResponse.json(
+        {
+          success: false,
+          error: 'Desktop helper not available',
+          details: error instanceof Error ? error.message : 'Please ensure the desktop helper application is running'
+        },
+        { status: 503 }
+      );
+    }
+
+    const result = await response.json().catch(() => ({}));
+    if (!response.ok || !result.success) {
+      return NextResponse.json(
+        {
+          success: false,
+          error: result.error || `Desktop helper error: ${response.status}`,
+          details: result.details,
+          // 'denied' when the OS refused screen capture; a 403 without it
+          // means the user declined the recording
+          permission: result.permission
+        },
+        { status: response.ok ? 500 : response.status }
+      );
+    }
+
+    return NextResponse.json({
+      success: true,
+      data: result.data,
+      format: result.format,
+      mimeType: result.mimeType,
+      size: result.size,
+      hash: result.hash,
+      dimensions: result.dimensions,
+      durationMs: result.durationMs,
+      frames: result.frames,
+      timestamp: result.timestamp,
+      uploaded: result.uploaded,
+      metadata: {
+        captureMethod: 'desktop_helper',
+        region: validatedInput.region,
+        display: validatedInput.display,
+        ...result.metadata
+      }
+    });
+  } catch (error) {
+    console.error('Desktop screen recording API error:', error);
+
+    if (error instanceof z.ZodError) {
+      return NextResponse.json(
+        { success: false, error: 'Invalid request parameters', details: error.issues },
+        { status: 400 }
+      );
+    }
+
+    return NextResponse.json(
+      {
+        success: false,
+        error: 'Internal server error',
+        details: 'An unexpected error occurred while recording the screen'
+      },
+      { status: 500 }
+    );
+  }
+}
@@ -1,4 +1,7 @@
 import { NextRequest, NextResponse } from 'next/server';
+import { auth } from '@/app/(auth)/auth';
+import { helperPinnedCertificate, helperScreenToken, helperSessionHeaders } from '@/lib/ohfixit/devices';
+import { helperBaseUrl, helperFetch, type PinnedCertificate } from '@/lib/ohfixit/helper-endpoint';
 import { z } from 'zod';
 
 // Request schema validation
@@ -12,7 +15,10 @@ const screenshotRequestSchema = z.object({
   display: z.number().optional(),
   includeCursor: z.boolean().default(false),
   format: z.enum(['png', 'jpeg']).default('png'),
-  quality: z.number().min(1).max(100).default(90)
+  quality: z.number().min(1).max(100).default(90),
+  // Where the helper can't black out sensitive windows (Wayland), let the
+  // user decide in a dialog on their machine instead of refusing outright
+  allowUnredacted: z.boolean().default(false)
 });
 
 /**
@@ -23,8 +29,15 @@ const screenshotRequestSchema = z.object({
  */
 export async function POST(request: NextRequest) {
   try {
+    // The helper's local API requires the paired device's session token
+    const session = await auth();
+    const sessionHeaders = await helperSessionHeaders(session?.user?.id);
+    // ...and, over TLS, the certificate it shared
+    const pinned = await helperPinnedCertificate(session?.user?.id);
+    const baseUrl = await helperBaseUrl();
+
     // Check if desktop helper is available
-    const helperStatus = await checkDesktopHelperStatus();
+    const helperStatus = await checkDesktopHelperStatus(baseUrl, sessionHeaders, pinned);
     if (!helperStatus.connected) {
       return NextResponse.json(
         { 
@@ -40,17 +53,33 @@ export async function POST(request: NextRequest) {
     const body = await request.json();
     const validatedInput = screenshotRequestSchema.parse(body);
 
+    // The helper's /screenshot needs a token carrying the screen:capture scope
+    const screenToken = await helperScreenToken(session?.user?.id);
+    if ('error' in screenToken) {
+      return NextResponse.json({ success: false, error: screenToken.error }, { status: screenToken.status });
+    }
+
     // Forward request to desktop helper
-    const screenshotResult = await captureScreenshotViaDesktopHelper(validatedInput);
+    const screenshotResult = await captureScreenshotViaDesktopHelper(
+      validatedInput,
+      baseUrl,
+      sessionHeaders,
+      pinned,
+      screenToken.token,
+    );
 
     if (!screenshotResult.success) {
       return NextResponse.json(
         {
           success: false,
           error: screenshotResult.error || 'Screenshot capture failed',
-          details: screenshotResult.details
+          details: screenshotResult.details,
+          // 'denied' when the OS refused (macOS Screen Recording permission)
+          permission: screenshotResult.permission,
+          // 'unavailable' when sensitive windows couldn't be blacked out
+          redaction: screenshotResult.redaction
         },
-        { status: 500 }
+        { status: screenshotResult.status ?? (screenshotResult.permission === 'denied' ? 403 : 500) }
       );
     }
 
@@ -65,7 +94,13 @@ export async function POST(request: NextRequest) {
         captureMethod: 'desktop_helper',
         region: validatedInput.region,
         display: validatedInput.display,
-        includeCursor: validatedInput.includeCursor
+        includeCursor: validatedInput.includeCursor,
+        // Sensitive windows (password managers, banking, terminals showing
+        // secrets) the helper blacked out, and whether it could look at all
+        redactions: screenshotResult.metadata?.redactions ?? [],
+        windowsChecked: screenshotResult.metadata?.windowsChecked ?? false,
+        // The user agreed on their machine to send it without redaction
+        unredactedAllowed: screenshotResult.metadata?.unredactedAllowed ?? false
       }
     });
 
@@ -98,7 +133,7 @@ export async function POST(request: NextRequest) {
 /**
  * Check desktop helper connection status
  */
-async function checkDesktopHelperStatus(): Promise<{
+async function checkDesktopHelperStatus(baseUrl: string, sessionHeaders: Record<string, string>, pinned: PinnedCertificate | null): Promise<{
   connected: boolean;
   version?: string;
   capabilities?: string[];
@@ -106,13 +141,14 @@ async function checkDesktopHelperStatus(): Promise<{
   try {
     // This would connect to the actual desktop helper service
     // For now, we'll simulate the check
-    const response = await fetch('http://localhost:8765/status', {
+    const response = await helperFetch(`${baseUrl}/status`, {
       method: 'GET',
       headers: {
         'Content-Type': 'application/json',
+        ...sessionHeaders,
       },
       signal: AbortSignal.timeout(5000) // 5 second timeout
-    });
+    }, pinned);
 
     if (response.ok) {
       const status = await response.json();
@@ -132,7 +168,13 @@ async function checkDesktopHelperStatus(): Promise<{
 /**
  * Capture screenshot via desktop helper
  */
-async function captureScreenshotViaDesktopHelper(options: z.infer<typeof screenshotRequestSchema>): Promise<{
+async function captureScreenshotViaDesktopHelper(
+  options: z.infer<typeof screenshotRequestSchema>,
+  baseUrl: string,
+  sessionHeaders: Record<string, string>,
+  pinned: PinnedCertificate | null,
+  token: string,
+): Promise<{
   success: boolean;
   data?: string;
   format?: string;
@@ -141,29 +183,43 @@ async function captureScreenshotViaDesktopHelper(options: z.infer<typeof screens
   timestamp?: string;
   error?: string;
   details?: string;
+  permission?: string;
+  redaction?: string;
+  status?: number;
+  metadata?: { redactions: unknown[]; windowsChecked: boolean; unredactedAllowed?: boolean };
 }> {
   try {
-    const response = await fetch('http://localhost:8765/screenshot', {
+    const response = await helperFetch(`${baseUrl}/screenshot`, {
       method: 'POST',
       headers: {
         'Content-Type': 'application/json',
+        Authorization: `Bearer ${token}`,
+        ...sessionHeaders,
       },
       body: JSON.stringify({
         region: options.region,
         display: options.display || 0,
         includeCursor: options.includeCursor,
         format: options.format,
-        quality: options.quality
+        quality: options.quality,
+        allowUnredacted: options.allowUnredacted
       }),
-      signal: AbortSignal.timeout(30000) // 30 second timeout for screenshot
-    });
+      // 30 seconds, or long enough for the user to answer the helper's
+      // two-minute dialog about an unredacted screenshot
+      signal: AbortSignal.timeout(options.allowUnredacted ? 150000 : 30000)
+    }, pinned);
 
     if (!response.ok) {
       const errorData = await response.json().catch(() => ({}));
       return {
         success: false,
-        error: `Desktop helper error: ${response.status}`,
-        details: errorData.message || response.statusText
+        error: errorData.error || `Desktop helper error: ${response.status}`,
+        details: errorData.details || errorData.message || response.statusText,
+        permission: errorData.permission,
+        redaction: errorData.redaction,
+        // The helper's refusal of an unredacted screenshot (409, or 403 when
+        // the user declined) passes through as is
+        status: errorData.redaction ? response.status : undefined
       };
     }
 
@@ -192,7 +248,8 @@ async function captureScreenshotViaDesktopHelper(options: z.infer<typeof screens
       format: result.format || options.format,
       size: result.size || 0,
       dimensions: result.dimensions || { width: 0, height: 0 },
-      timestamp: result.timestamp || new Date().toISOString()
+      timestamp: result.timestamp || new Date().toISOString(),
+      metadata: result.metadata
     };
 
   } catch (error) {
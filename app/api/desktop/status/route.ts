@@ -1,4 +1,7 @@
 import { NextRequest, NextResponse } from 'next/server';
+import { auth } from '@/app/(auth)/auth';
+import { helperPinnedCertificate, helperSessionHeaders } from '@/lib/ohfixit/devices';
+import { helperBaseUrl, helperFetch, type PinnedCertificate } from '@/lib/ohfixit/helper-endpoint';
 
 /**
  * Desktop Helper Status API Endpoint
@@ -7,14 +10,22 @@ import { NextRequest, NextResponse } from 'next/server';
  */
 export async function GET(request: NextRequest) {
   try {
-    const status = await checkDesktopHelperConnection();
+    const session = await auth();
+    const baseUrl = await helperBaseUrl();
+    const status = await checkDesktopHelperConnection(
+      baseUrl,
+      await helperSessionHeaders(session?.user?.id),
+      await helperPinnedCertificate(session?.user?.id),
+    );
 
     return NextResponse.json({
       connected: status.connected,
       version: status.version,
       capabilities: status.capabilities,
+      deviceId: status.deviceId,
+      paired: status.paired,
       lastCheck: new Date().toISOString(),
-      endpoint: 'http://localhost:8765'
+      endpoint: baseUrl,
     });
 
   } catch (error) {
@@ -35,10 +46,16 @@ export async function GET(request: NextRequest) {
 /**
  * Check desktop helper connection and capabilities
  */
-async function checkDesktopHelperConnection(): Promise<{
+async function checkDesktopHelperConnection(
+  baseUrl: string,
+  sessionHeaders: Record<string, string>,
+  pinned: PinnedCertificate | null,
+): Promise<{
   connected: boolean;
   version?: string;
   capabilities?: string[];
+  deviceId?: string;
+  paired?: boolean;
   error?: string;
 }> {
   try {
@@ -46,14 +63,15 @@ async function checkDesktopHelperConnection(): Promise<{
     const controller = new AbortController();
     const timeoutId = setTimeout(() => controller.abort(), 5000);
 
-    const response = await fetch('http://localhost:8765/status', {
+    const response = await helperFetch(`${baseUrl}/status`, {
       method: 'GET',
       headers: {
         'Content-Type': 'application/json',
-        'User-Agent': 'sparka-ohfixit/1.0'
+        'User-Agent': 'sparka-ohfixit/1.0',
+        ...sessionHeaders,
       },
       signal: controller.signal
-    });
+    }, pinned);
 
     clearTimeout(timeoutId);
 
@@ -74,7 +92,10 @@ async function checkDesktopHelperConnection(): Promise<{
         'system_info',
         'process_list',
         'file_operations'
-      ]
+      ],
+      // Helpers from before pairing report neither
+      deviceId: data.device_id,
+      paired: data.paired,
     };
 
   } catch (error) {
@@ -39,6 +39,23 @@ const ClientDiagnosticsInput = z.object({
     platform: z.string().optional(),
     languages: z.array(z.string()).optional(),
     timeZone: z.string().optional(),
+    locale: z
+      .object({
+        locale: z.string().optional(),
+        utcOffsetMinutes: z.number().optional(),
+        hourCycle: z.string().optional(),
+        calendar: z.string().optional(),
+        numberingSystem: z.string().optional(),
+      })
+      .optional(),
+    keyboard: z
+      .object({
+        layouts: z.array(z.string()),
+        source: z.enum(['layout-map', 'unavailable']),
+      })
+      .optional(),
+    // Date.now() on the client when the payload was sent, for clock skew
+    clientTime: z.number().optional(),
     screen: z
       .object({ width: z.number(), height: z.number(), dpr: z.number() })
       .optional(),
@@ -108,14 +125,18 @@ export async function POST(request: NextRequest) {
   const chatId = parsed.data.chatId;
   const sessionKey = getSessionKeyForIds({ userId, anonymousId: anonymous?.id, chatId });
 
-    const { userAgent, platform } = parsed.data.data;
-    const family = detectOS(userAgent || '', platform);
+    const { clientTime, ...data } = parsed.data.data;
+    const family = detectOS(data.userAgent || '', data.platform);
+
+    // A skewed clock breaks token validation, so record how far off it is
+    const clockSkewMs = clientTime !== undefined ? clientTime - Date.now() : undefined;
 
     const payload: ClientDiagnostics = {
       collectedAt: Date.now(),
       consent: true,
       data: {
-        ...parsed.data.data,
+        ...data,
+        locale: (data.locale || clockSkewMs !== undefined) ? { ...data.locale, clockSkewMs } : undefined,
         osGuess: { family, source: 'ua+platform' },
       },
     };
@@ -14,6 +14,8 @@ interface StatusResponse {
   connected: boolean;
   version?: string;
   capabilities?: string[];
+  deviceId?: string;
+  paired?: boolean;
   error?: string;
   lastCheck: string;
   endpoint?: string;
@@ -50,6 +52,28 @@ export function DesktopHelperStatus({ className }: DesktopHelperStatusProps) {
     }
   }, []);
 
+  // A connected but unpaired helper shows a code the user types in here
+  const needsPairing = !isLoading && status?.connected && status.paired === false;
+
+  const pairHelper = useCallback(async () => {
+    const code = window.prompt('Enter the pairing code shown in the OhFixIt Desktop Helper');
+    if (!code) return;
+    try {
+      const response = await fetch('/api/automation/helper/pair/confirm', {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ code }),
+      });
+      if (!response.ok) {
+        const data = await response.json().catch(() => ({}));
+        window.alert(data.error || 'Pairing failed');
+      }
+    } catch (error) {
+      console.error('Failed to pair desktop helper:', error);
+    }
+    await checkStatus();
+  }, [checkStatus]);
+
   // Check status on mount and set up periodic checking
   useEffect(() => {
     checkStatus();
@@ -115,6 +139,11 @@ export function DesktopHelperStatus({ className }: DesktopHelperStatusProps) {
             Endpoint: {status.endpoint}
           </div>
         )}
+        {status.paired === false && (
+          <div className="text-xs text-amber-400 mt-1">
+            Not paired: click to enter the code shown in the helper
+          </div>
+        )}
         {status.capabilities && status.capabilities.length > 0 && (
           <div className="text-xs text-muted-foreground mt-1">
             Capabilities: {status.capabilities.join(', ')}
@@ -144,7 +173,7 @@ export function DesktopHelperStatus({ className }: DesktopHelperStatusProps) {
           variant="ghost"
           size="sm"
           className={cn('h-8 px-2 gap-1.5', className)}
-          onClick={checkStatus}
+          onClick={needsPairing ? pairHelper : checkStatus}
           disabled={isLoading}
         >
           <Monitor className="h-4 w-4" />
@@ -154,7 +183,7 @@ export function DesktopHelperStatus({ className }: DesktopHelperStatusProps) {
             'bg-red-500': !isLoading && !status?.connected,
           })} />
           <span className="hidden sm:inline text-xs">
-            {isLoading ? 'Checking...' : status?.connected ? 'Connected' : 'Disconnected'}
+            {isLoading ? 'Checking...' : needsPairing ? 'Pair' : status?.connected ? 'Connected' : 'Disconnected'}
           </span>
         </Button>
       </TooltipTrigger>
@@ -4,6 +4,7 @@ import { useCallback, useEffect, useMemo, useState } from 'react';
 import { Dialog, DialogContent, DialogHeader, DialogTitle, DialogDescription } from '@/components/ui/dialog';
 import { Button } from '@/components/ui/button';
 import { toast } from 'sonner';
+import { collectKeyboardLayouts } from '@/lib/ohfixit/keyboard-layout';
 
 type Props = {
   open: boolean;
@@ -33,7 +34,17 @@ async function collectSnapshot() {
     const platform = navigator.platform;
     const languages = navigator.languages;
     const language = navigator.language;
-    const timeZone = Intl.DateTimeFormat().resolvedOptions().timeZone;
+    const resolved = Intl.DateTimeFormat().resolvedOptions();
+    const timeZone = resolved.timeZone;
+    const locale = {
+      locale: resolved.locale,
+      // getTimezoneOffset is positive west of UTC; flip it to the usual sign
+      utcOffsetMinutes: -new Date().getTimezoneOffset(),
+      hourCycle: (resolved as any).hourCycle,
+      calendar: resolved.calendar,
+      numberingSystem: resolved.numberingSystem,
+    };
+    const keyboard = await collectKeyboardLayouts();
     const screenInfo = typeof screen !== 'undefined' ? { width: screen.width, height: screen.height, dpr: window.devicePixelRatio } : undefined;
     const device = { memoryGB: (typeof nav?.deviceMemory === 'number' ? nav.deviceMemory : undefined), cores: (typeof nav?.hardwareConcurrency === 'number' ? nav.hardwareConcurrency : undefined) };
     const network = connection ? {
@@ -54,6 +65,8 @@ async function collectSnapshot() {
         languages,
         language,
         timeZone,
+        locale,
+        keyboard,
         screen: screenInfo,
         device,
         network: {
@@ -81,7 +94,8 @@ export function CollectClientDiagnostics({ open, onOpenChange, chatId }: Props)
   const handleAccept = useCallback(async () => {
     setSubmitting(true);
     try {
-  const payload = { ...(await collectSnapshot()), chatId };
+      const snapshot = await collectSnapshot();
+      const payload = { ...snapshot, data: { ...snapshot.data, clientTime: Date.now() }, chatId };
       const res = await fetch('/api/diagnostics/client', {
         method: 'POST',
         headers: { 'Content-Type': 'application/json' },
@@ -124,6 +138,8 @@ export function CollectClientDiagnostics({ open, onOpenChange, chatId }: Props)
               <li>User Agent: <span className="break-words">{redactUA}</span></li>
               <li>Language: {preview?.data?.language ?? 'Unknown'}</li>
               <li>Timezone: {preview?.data?.timeZone ?? 'Unknown'}</li>
+              <li>Locale: {preview?.data?.locale?.locale ?? 'Unknown'}</li>
+              <li>Keyboard: {preview?.data?.keyboard?.layouts?.length ? preview.data.keyboard.layouts.join(', ') : 'Unknown'}</li>
               <li>Screen: {preview?.data?.screen ? `${preview.data.screen.width}x${preview.data.screen.height} @${preview.data.screen.dpr}x` : 'Unknown'}</li>
               {preview?.data?.network && (
                 <li>Network: {preview.data.network.effectiveType} · {preview.data.network.downlink} Mbps · rtt {preview.data.network.rtt}ms</li>
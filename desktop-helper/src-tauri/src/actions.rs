@@ -0,0 +1,506 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use axum::extract::State;
+use axum::Json;
+use tauri::{AppHandle, Manager};
+
+use crate::{probes, startup, updates, ActionDefinition, AppState, Category, Risk};
+
+// GET /actions
+pub async fn actions_handler(State(app): State<AppHandle>) -> Json<serde_json::Value> {
+    startup::catalog_ready().await;
+    let state = app.state::<Mutex<AppState>>();
+    let manifest_version = state.lock().unwrap().manifest_version;
+    Json(serde_json::json!({
+        "os": std::env::consts::OS,
+        "manifest_version": manifest_version,
+        "actions": available(&app),
+    }))
+}
+
+#[tauri::command]
+pub async fn list_actions(app: AppHandle) -> Result<Vec<serde_json::Value>, String> {
+    startup::catalog_ready().await;
+    Ok(available(&app))
+}
+
+// Allowlisted actions that can run on this platform, sorted by id
+pub fn available(app: &AppHandle) -> Vec<serde_json::Value> {
+    let state = app.state::<Mutex<AppState>>();
+    let state = state.lock().unwrap();
+    let mut actions: Vec<_> = state
+        .actions
+        .values()
+        .filter(|a| a.os == std::env::consts::OS)
+        .collect();
+    actions.sort_by(|a, b| a.id.cmp(&b.id));
+    actions.into_iter().map(|a| a.describe()).collect()
+}
+
+// Built-in allowlist, used until a signed remote manifest replaces it
+pub fn builtin() -> HashMap<String, ActionDefinition> {
+    let mut actions = HashMap::new();
+    macos_actions(&mut actions);
+    windows_actions(&mut actions);
+    linux_actions(&mut actions);
+    actions
+}
+
+fn macos_actions(actions: &mut HashMap<String, ActionDefinition>) {
+    actions.insert(
+        "flush-dns-macos".to_string(),
+        ActionDefinition::new(
+            "flush-dns-macos",
+            "Flush DNS Cache (macOS)",
+            "macos",
+            vec![
+                "/usr/bin/dscacheutil -flushcache",
+                "/usr/bin/killall -HUP mDNSResponder"
+            ]
+        )
+        .in_category(Category::Network)
+        .with_risk(Risk::Medium)
+        .with_exit_hint("killall", 1, "mDNSResponder wasn't running, so there was no DNS cache to flush. Restart the Mac and try again.")
+        .elevated()
+        .with_postcondition(probes::Probe::ProcessRunning { name: "mDNSResponder".to_string() })
+    );
+
+    actions.insert(
+        "toggle-wifi-macos".to_string(),
+        ActionDefinition::new(
+            "toggle-wifi-macos",
+            "Toggle Wi‑Fi (macOS)",
+            "macos",
+            vec![
+                "/usr/sbin/networksetup -setairportpower en0 off",
+                "/bin/sleep 2",
+                "/usr/sbin/networksetup -setairportpower en0 on"
+            ]
+        )
+        .in_category(Category::Network)
+        .with_risk(Risk::Medium)
+        .with_exit_hint("networksetup", 6, "This Mac has no Wi‑Fi interface named en0. Turn Wi‑Fi off and on from the menu bar instead.")
+        .with_exit_hint("networksetup", 4, "Wi‑Fi settings are locked by an administrator or device management profile.")
+        .with_state_capture("wifi_power", "/usr/sbin/networksetup -getairportpower en0")
+        .with_rollback(vec![
+            "/usr/sbin/networksetup -setairportpower en0 {state.wifi_power}"
+        ])
+    );
+
+    actions.insert(
+        "clear-app-cache".to_string(),
+        ActionDefinition::new(
+            "clear-app-cache",
+            "Clear App Cache (macOS)",
+            "macos",
+            vec![
+                "/usr/bin/rsync -a --prune-empty-dirs --include=*/ --include=*.cache --exclude=* {home}/Library/Caches/ {backup_dir}/Caches/",
+                "/usr/bin/find {home}/Library/Caches -name *.cache -type f -delete"
+            ]
+        )
+        .in_category(Category::Storage)
+        .with_risk(Risk::Low)
+        .in_background()
+        .with_rollback(vec![
+            "/usr/bin/rsync -a {backup_dir}/Caches/ {home}/Library/Caches/"
+        ])
+        .with_space_estimate(vec!["{home}/Library/Caches/*.cache"])
+    );
+
+    // Additional safe macOS actions
+    actions.insert(
+        "restart-finder".to_string(),
+        ActionDefinition::new(
+            "restart-finder",
+            "Restart Finder (macOS)",
+            "macos",
+            vec![
+                "/usr/bin/killall Finder"
+            ]
+        )
+        .in_category(Category::Ui)
+        .with_risk(Risk::Low)
+        .with_exit_hint("killall", 1, "Finder wasn't running. Click the Finder icon in the Dock to start it.")
+        .interactive()
+        .with_postcondition(probes::Probe::ProcessRunning { name: "Finder".to_string() })
+    );
+
+    actions.insert(
+        "clear-recent-items".to_string(),
+        ActionDefinition::new(
+            "clear-recent-items",
+            "Clear Recent Items (macOS)",
+            "macos",
+            vec![
+                "/usr/bin/defaults delete com.apple.recentitems RecentApplications",
+                "/usr/bin/defaults delete com.apple.recentitems RecentDocuments",
+                "/usr/bin/defaults delete com.apple.recentitems RecentServers"
+            ]
+        )
+        .in_category(Category::Ui)
+        .with_risk(Risk::Medium)
+        // The list was already empty
+        .with_tolerated_exit("defaults", 1, "There were no recent items of this kind to clear.")
+    );
+
+    actions.insert(
+        "reset-launchpad".to_string(),
+        ActionDefinition::new(
+            "reset-launchpad",
+            "Reset Launchpad Layout (macOS)",
+            "macos",
+            vec![
+                "/usr/bin/defaults write com.apple.dock ResetLaunchPad -bool true",
+                "/usr/bin/killall Dock"
+            ]
+        )
+        .in_category(Category::Ui)
+        .with_risk(Risk::Medium)
+        .interactive()
+        .with_postcondition(probes::Probe::ProcessRunning { name: "Dock".to_string() })
+    );
+
+    actions.insert(
+        "clear-system-logs".to_string(),
+        ActionDefinition::new(
+            "clear-system-logs",
+            "Clear Old System Logs (macOS)",
+            "macos",
+            vec![
+                "/usr/bin/find /private/var/log/asl -name *.asl -type f -delete",
+                "/usr/bin/find /private/var/log/DiagnosticMessages -name *.asl -type f -delete"
+            ]
+        )
+        .in_category(Category::Storage)
+        .with_risk(Risk::Medium)
+        .in_background()
+        .elevated()
+        .with_volume_snapshot(vec![
+            "/private/var/log/asl",
+            "/private/var/log/DiagnosticMessages"
+        ])
+        .with_space_estimate(vec![
+            "/private/var/log/asl/*.asl",
+            "/private/var/log/DiagnosticMessages/*.asl"
+        ])
+    );
+
+    // Per-source update actions referenced by the pending-updates report
+    actions.insert(
+        "install-macos-updates".to_string(),
+        ActionDefinition::new(
+            "install-macos-updates",
+            "Install macOS Software Updates",
+            "macos",
+            vec![
+                "/usr/sbin/softwareupdate --install --all"
+            ]
+        )
+        .in_category(Category::Security)
+        .with_risk(Risk::High)
+        .with_exit_hint("softwareupdate", 1, "macOS couldn't download or install the updates. Check the internet connection and free disk space, then try again.")
+        .elevated()
+        .with_preflight(probes::Probe::DnsResolves { host: "swscan.apple.com".to_string() })
+        .irreversible()
+        .with_estimated_time("30 minutes")
+    );
+
+    let brew_upgrade = format!("{} upgrade", updates::homebrew_bin("brew"));
+    actions.insert(
+        "upgrade-homebrew-packages".to_string(),
+        ActionDefinition::new(
+            "upgrade-homebrew-packages",
+            "Upgrade Homebrew Packages (macOS)",
+            "macos",
+            vec![brew_upgrade.as_str()]
+        )
+        .in_category(Category::Security)
+        .with_risk(Risk::Medium)
+        .in_background()
+        .irreversible()
+        .with_estimated_time("5 minutes")
+        .with_requirements(vec!["Homebrew installed"])
+        .with_preflight(probes::Probe::FileExists { path: updates::homebrew_bin("brew") })
+        .with_homebrew_binaries()
+    );
+
+    let mas_upgrade = format!("{} upgrade", updates::homebrew_bin("mas"));
+    actions.insert(
+        "upgrade-app-store-apps".to_string(),
+        ActionDefinition::new(
+            "upgrade-app-store-apps",
+            "Upgrade App Store Apps (macOS)",
+            "macos",
+            vec![mas_upgrade.as_str()]
+        )
+        .in_category(Category::Security)
+        .with_risk(Risk::Medium)
+        .irreversible()
+        .with_estimated_time("10 minutes")
+        .with_requirements(vec!["mas installed", "Signed in to the App Store"])
+        .with_preflight(probes::Probe::FileExists { path: updates::homebrew_bin("mas") })
+        .with_homebrew_binaries()
+    );
+
+    // Recommended when approval tokens are rejected because of clock skew
+    actions.insert(
+        "sync-clock-macos".to_string(),
+        ActionDefinition::new(
+            "sync-clock-macos",
+            "Sync Clock with Time Server (macOS)",
+            "macos",
+            vec![
+                "/usr/bin/sntp -sS time.apple.com"
+            ]
+        )
+        .in_category(Category::Security)
+        .with_risk(Risk::Low)
+        .elevated()
+        .irreversible()
+    );
+}
+
+fn windows_actions(actions: &mut HashMap<String, ActionDefinition>) {
+    actions.insert(
+        "flush-dns-windows".to_string(),
+        ActionDefinition::new(
+            "flush-dns-windows",
+            "Flush DNS Cache (Windows)",
+            "windows",
+            vec![
+                "{system32}\\ipconfig.exe /flushdns"
+            ]
+        )
+        .in_category(Category::Network)
+        .with_risk(Risk::Low)
+        .with_estimated_time("5 seconds")
+    );
+
+    // `start` returns immediately; launching explorer.exe directly would block
+    // forever once it becomes the shell again
+    actions.insert(
+        "restart-explorer-windows".to_string(),
+        ActionDefinition::new(
+            "restart-explorer-windows",
+            "Restart Explorer (Windows)",
+            "windows",
+            vec![
+                "{system32}\\taskkill.exe /F /IM explorer.exe",
+                "{system32}\\cmd.exe /C start explorer.exe"
+            ]
+        )
+        .in_category(Category::Ui)
+        .with_risk(Risk::Low)
+        .interactive()
+        .with_postcondition(probes::Probe::ProcessRunning { name: "explorer.exe".to_string() })
+    );
+
+    actions.insert(
+        "reset-winsock-windows".to_string(),
+        ActionDefinition::new(
+            "reset-winsock-windows",
+            "Reset Winsock Catalog (Windows)",
+            "windows",
+            vec![
+                "{system32}\\netsh.exe winsock reset"
+            ]
+        )
+        .in_category(Category::Network)
+        .with_risk(Risk::High)
+        .with_exit_hint("netsh", 1, "Resetting Winsock needs administrator rights. Sign in with an administrator account and try again.")
+        .elevated()
+        .irreversible()
+        .with_requirements(vec!["Administrator privileges", "Restart required to take effect"])
+    );
+
+    // Locked files are skipped, so the delete step always exits cleanly
+    actions.insert(
+        "clear-temp-windows".to_string(),
+        ActionDefinition::new(
+            "clear-temp-windows",
+            "Clear Temporary Files (Windows)",
+            "windows",
+            vec![
+                "{system32}\\WindowsPowerShell\\v1.0\\powershell.exe -NoProfile -Command New-Item -ItemType Directory -Force -Path '{backup_dir}\\Temp' | Out-Null; Copy-Item -Path '{temp}\\*' -Destination '{backup_dir}\\Temp' -Recurse -Force -ErrorAction SilentlyContinue; exit 0",
+                "{system32}\\WindowsPowerShell\\v1.0\\powershell.exe -NoProfile -Command Get-ChildItem -Path '{temp}' -Force | Remove-Item -Recurse -Force -ErrorAction SilentlyContinue; exit 0"
+            ]
+        )
+        .in_category(Category::Storage)
+        .with_risk(Risk::Low)
+        .in_background()
+        .with_rollback(vec![
+            "{system32}\\WindowsPowerShell\\v1.0\\powershell.exe -NoProfile -Command Copy-Item -Path '{backup_dir}\\Temp\\*' -Destination '{temp}' -Recurse -Force"
+        ])
+        .with_space_estimate(vec!["{temp}"])
+        .with_estimated_time("1 minute")
+    );
+
+    actions.insert(
+        "restart-print-spooler-windows".to_string(),
+        ActionDefinition::new(
+            "restart-print-spooler-windows",
+            "Restart Print Spooler (Windows)",
+            "windows",
+            vec![
+                "{system32}\\WindowsPowerShell\\v1.0\\powershell.exe -NoProfile -Command Restart-Service -Name Spooler -Force"
+            ]
+        )
+        .in_category(Category::Ui)
+        .with_risk(Risk::Medium)
+        .with_exit_hint("powershell", 1, "The Print Spooler service couldn't be restarted. It may be disabled; check Services for \"Print Spooler\".")
+        .elevated()
+        .with_state_capture("spooler_status", "{system32}\\WindowsPowerShell\\v1.0\\powershell.exe -NoProfile -Command (Get-Service -Name Spooler).Status")
+        .with_rollback(vec![
+            "{system32}\\WindowsPowerShell\\v1.0\\powershell.exe -NoProfile -Command if ('{state.spooler_status}' -eq 'Stopped') { Stop-Service -Name Spooler -Force }"
+        ])
+        .with_postcondition(probes::Probe::ProcessRunning { name: "spoolsv.exe".to_string() })
+    );
+
+    actions.insert(
+        "sync-clock-windows".to_string(),
+        ActionDefinition::new(
+            "sync-clock-windows",
+            "Sync Clock with Time Server (Windows)",
+            "windows",
+            vec![
+                "{system32}\\WindowsPowerShell\\v1.0\\powershell.exe -NoProfile -Command Start-Service -Name w32time; w32tm /resync /force"
+            ]
+        )
+        .in_category(Category::Security)
+        .with_risk(Risk::Low)
+        .elevated()
+        .irreversible()
+    );
+}
+
+fn linux_actions(actions: &mut HashMap<String, ActionDefinition>) {
+    actions.insert(
+        "restart-networkmanager-linux".to_string(),
+        ActionDefinition::new(
+            "restart-networkmanager-linux",
+            "Restart NetworkManager (Linux)",
+            "linux",
+            vec![
+                "/usr/bin/systemctl restart NetworkManager"
+            ]
+        )
+        .in_category(Category::Network)
+        .with_risk(Risk::Medium)
+        .with_exit_hint("systemctl", 5, "NetworkManager isn't installed; this system manages the network another way.")
+        .elevated()
+        .with_preflight(probes::Probe::ProcessRunning { name: "NetworkManager".to_string() })
+        .with_postcondition(probes::Probe::ProcessRunning { name: "NetworkManager".to_string() })
+    );
+
+    actions.insert(
+        "flush-dns-linux".to_string(),
+        ActionDefinition::new(
+            "flush-dns-linux",
+            "Flush DNS Cache (Linux)",
+            "linux",
+            vec![
+                "/usr/bin/resolvectl flush-caches"
+            ]
+        )
+        .in_category(Category::Network)
+        .with_risk(Risk::Low)
+        .with_exit_hint("resolvectl", 1, "systemd-resolved isn't running, so there is no DNS cache to flush.")
+        .with_requirements(vec!["systemd-resolved"])
+        .with_preflight(probes::Probe::ProcessRunning { name: "systemd-resolve".to_string() })
+        .with_estimated_time("5 seconds")
+    );
+
+    actions.insert(
+        "vacuum-journal-linux".to_string(),
+        ActionDefinition::new(
+            "vacuum-journal-linux",
+            "Remove Journal Logs Older Than 7 Days (Linux)",
+            "linux",
+            vec![
+                "/usr/bin/journalctl --vacuum-time=7d"
+            ]
+        )
+        .in_category(Category::Storage)
+        .with_risk(Risk::Medium)
+        .in_background()
+        .with_exit_hint("journalctl", 1, "Only administrators can clean up the system journal.")
+        .elevated()
+        .irreversible()
+        .with_estimated_time("30 seconds")
+    );
+
+    actions.insert(
+        "clear-user-cache-linux".to_string(),
+        ActionDefinition::new(
+            "clear-user-cache-linux",
+            "Clear User Cache (Linux)",
+            "linux",
+            vec![
+                "/usr/bin/rsync -a {home}/.cache/ {backup_dir}/cache/",
+                "/usr/bin/find {home}/.cache -mindepth 1 -delete"
+            ]
+        )
+        .in_category(Category::Storage)
+        .with_risk(Risk::Low)
+        .in_background()
+        .with_rollback(vec![
+            "/usr/bin/rsync -a {backup_dir}/cache/ {home}/.cache/"
+        ])
+        .with_space_estimate(vec!["{home}/.cache"])
+        .with_estimated_time("1 minute")
+    );
+
+    // User services, no elevation needed
+    actions.insert(
+        "restart-pipewire-linux".to_string(),
+        ActionDefinition::new(
+            "restart-pipewire-linux",
+            "Restart PipeWire Audio (Linux)",
+            "linux",
+            vec![
+                "/usr/bin/systemctl --user restart pipewire pipewire-pulse wireplumber"
+            ]
+        )
+        .in_category(Category::Ui)
+        .with_risk(Risk::Low)
+        .with_exit_hint("systemctl", 5, "PipeWire isn't installed for this user; try restarting PulseAudio instead.")
+        .with_preflight(probes::Probe::ProcessRunning { name: "pipewire".to_string() })
+        .with_postcondition(probes::Probe::ProcessRunning { name: "pipewire".to_string() })
+    );
+
+    actions.insert(
+        "restart-pulseaudio-linux".to_string(),
+        ActionDefinition::new(
+            "restart-pulseaudio-linux",
+            "Restart PulseAudio (Linux)",
+            "linux",
+            vec![
+                "/usr/bin/systemctl --user restart pulseaudio"
+            ]
+        )
+        .in_category(Category::Ui)
+        .with_risk(Risk::Low)
+        .with_exit_hint("systemctl", 5, "PulseAudio isn't installed for this user; try restarting PipeWire instead.")
+        .with_preflight(probes::Probe::ProcessRunning { name: "pulseaudio".to_string() })
+        .with_postcondition(probes::Probe::ProcessRunning { name: "pulseaudio".to_string() })
+    );
+
+    // Turns on NTP sync (timesyncd or chrony), which corrects the clock right away
+    actions.insert(
+        "sync-clock-linux".to_string(),
+        ActionDefinition::new(
+            "sync-clock-linux",
+            "Sync Clock with Time Server (Linux)",
+            "linux",
+            vec![
+                "/usr/bin/timedatectl set-ntp true"
+            ]
+        )
+        .in_category(Category::Security)
+        .with_risk(Risk::Low)
+        .with_exit_hint("timedatectl", 1, "Automatic time sync isn't available. Install systemd-timesyncd or chrony, then try again.")
+        .elevated()
+        .irreversible()
+    );
+}
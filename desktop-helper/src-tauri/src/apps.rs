@@ -0,0 +1,216 @@
+use std::path::Path;
+
+use axum::extract::Query;
+use axum::Json;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::TtlCache;
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+
+// GET /health/apps[?name=][&refresh=true]: installed applications with
+// version, install date and who signed or published them, to check whether a
+// troublesome app is outdated. macOS asks system_profiler (every app it
+// knows, with signing authorities; slow, hence the cache); Windows reads the
+// Uninstall registry keys; Linux lists desktop entries, Flatpaks and snaps.
+static SCAN_CACHE: TtlCache = TtlCache::new(std::time::Duration::from_secs(10 * 60));
+
+#[derive(Debug, Deserialize, Default)]
+pub struct AppsQuery {
+    #[serde(default)]
+    refresh: bool,
+    name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct InstalledApp {
+    name: String,
+    version: Option<String>,
+    // Bundle identifier (macOS) or Flatpak/snap id
+    id: Option<String>,
+    path: Option<String>,
+    installed_at: Option<String>,
+    // The first signing authority on macOS, the publisher on Windows
+    signer: Option<String>,
+    // "mac_app_store", "apple", "identified_developer", "unknown" (macOS);
+    // "registry", "desktop_entry", "flatpak", "snap" elsewhere
+    source: String,
+}
+
+// GET /health/apps
+pub async fn apps_handler(Query(query): Query<AppsQuery>) -> Json<serde_json::Value> {
+    let mut report = SCAN_CACHE.get_or_compute("apps", query.refresh, apps_report).await;
+    if let Some(name) = query.name.as_deref().map(str::to_lowercase).filter(|name| !name.is_empty()) {
+        if let Some(apps) = report["apps"].as_array_mut() {
+            apps.retain(|app| app["name"].as_str().is_some_and(|app| app.to_lowercase().contains(&name)));
+            report["count"] = apps.len().into();
+        }
+    }
+    Json(report)
+}
+
+async fn apps_report() -> serde_json::Value {
+    let mut apps = tauri::async_runtime::spawn_blocking(|| match std::env::consts::OS {
+        "macos" => macos(),
+        "windows" => windows(),
+        _ => linux(),
+    })
+    .await
+    .unwrap_or_default();
+    apps.sort_by_key(|app| app.name.to_lowercase());
+    serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "count": apps.len(),
+        "apps": apps,
+    })
+}
+
+// A bundle's creation time is when it landed on this disk; system_profiler's
+// lastModified changes with every update
+fn created(path: &Path) -> Option<String> {
+    let created = std::fs::metadata(path).and_then(|metadata| metadata.created()).ok()?;
+    Some(DateTime::<Utc>::from(created).to_rfc3339())
+}
+
+fn macos() -> Vec<InstalledApp> {
+    let Some(output) = read_output("/usr/sbin/system_profiler", &["SPApplicationsDataType", "-json"]) else {
+        return Vec::new();
+    };
+    let Ok(report) = serde_json::from_str::<serde_json::Value>(&output) else { return Vec::new() };
+    let items = report["SPApplicationsDataType"].as_array().cloned().unwrap_or_default();
+    items
+        .iter()
+        .filter_map(|item| {
+            let path = item["path"].as_str()?;
+            // Apps inside other bundles and system internals aren't things
+            // the user installed
+            if path.starts_with("/System/") || path.starts_with("/Library/Apple/") || path.contains(".app/") {
+                return None;
+            }
+            Some(InstalledApp {
+                name: item["_name"].as_str().unwrap_or_default().to_string(),
+                version: item["version"].as_str().map(str::to_string),
+                id: bundle_id(Path::new(path)),
+                path: Some(path.to_string()),
+                installed_at: created(Path::new(path)).or_else(|| item["lastModified"].as_str().map(str::to_string)),
+                signer: item["signed_by"][0].as_str().map(str::to_string),
+                source: item["obtained_from"].as_str().unwrap_or("unknown").to_string(),
+            })
+        })
+        .collect()
+}
+
+fn bundle_id(bundle: &Path) -> Option<String> {
+    let info = bundle.join("Contents/Info.plist");
+    read_output("/usr/bin/plutil", &["-extract", "CFBundleIdentifier", "raw", "-o", "-", &info.to_string_lossy()])
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+}
+
+// Every Uninstall entry with a display name that isn't a system component or
+// an update
+fn windows() -> Vec<InstalledApp> {
+    let script = r#"
+$ErrorActionPreference = 'SilentlyContinue'
+$keys = 'HKLM:\Software\Microsoft\Windows\CurrentVersion\Uninstall\*',
+        'HKLM:\Software\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall\*',
+        'HKCU:\Software\Microsoft\Windows\CurrentVersion\Uninstall\*'
+$apps = Get-ItemProperty -Path $keys |
+  Where-Object { $_.DisplayName -and -not $_.SystemComponent -and -not $_.ParentKeyName } |
+  ForEach-Object { [ordered]@{ name = $_.DisplayName; version = $_.DisplayVersion; path = $_.InstallLocation; installed = $_.InstallDate; publisher = $_.Publisher } }
+ConvertTo-Json -InputObject @($apps) -Compress
+"#;
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    let Some(output) = read_output(&powershell.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", script]) else {
+        return Vec::new();
+    };
+    let Ok(serde_json::Value::Array(entries)) = serde_json::from_str(output.trim()) else { return Vec::new() };
+    let text = |value: &serde_json::Value| value.as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    let mut apps: Vec<InstalledApp> = entries
+        .iter()
+        .map(|entry| InstalledApp {
+            name: text(&entry["name"]).unwrap_or_default(),
+            version: text(&entry["version"]),
+            path: text(&entry["path"]),
+            // InstallDate is "yyyyMMdd"
+            installed_at: entry["installed"]
+                .as_str()
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+                .map(|date| date.to_string()),
+            signer: text(&entry["publisher"]),
+            source: "registry".to_string(),
+            ..Default::default()
+        })
+        .collect();
+    // 32- and 64-bit keys often list the same app
+    apps.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    apps.dedup_by(|a, b| a.name == b.name && a.version == b.version);
+    apps
+}
+
+fn linux() -> Vec<InstalledApp> {
+    let mut apps = Vec::new();
+    // "Firefox\torg.mozilla.firefox\t128.0"
+    if let Some(output) = read_output("flatpak", &["list", "--app", "--columns=name,application,version"]) {
+        for line in output.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [name, id, version] = fields[..] else { continue };
+            apps.push(InstalledApp {
+                name: name.to_string(),
+                version: Some(version.to_string()).filter(|version| !version.is_empty()),
+                id: Some(id.to_string()),
+                source: "flatpak".to_string(),
+                ..Default::default()
+            });
+        }
+    }
+    // "Name  Version  Rev  Tracking  Publisher  Notes"
+    if let Some(output) = read_output("snap", &["list"]) {
+        for line in output.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 5 || fields.get(5).is_some_and(|notes| notes.contains("base") || notes.contains("core")) {
+                continue;
+            }
+            apps.push(InstalledApp {
+                name: fields[0].to_string(),
+                version: Some(fields[1].to_string()),
+                id: Some(fields[0].to_string()),
+                signer: Some(fields[4].trim_end_matches('✓').trim_end_matches('*').to_string()),
+                source: "snap".to_string(),
+                ..Default::default()
+            });
+        }
+    }
+
+    // Desktop entries for everything else; packaged apps carry no version here
+    let directories = [
+        dirs::data_dir().unwrap_or_default().join("applications"),
+        "/usr/share/applications".into(),
+        "/usr/local/share/applications".into(),
+    ];
+    for directory in directories {
+        let Ok(entries) = std::fs::read_dir(&directory) else { continue };
+        for path in entries.flatten().map(|entry| entry.path()).filter(|path| path.extension().is_some_and(|ext| ext == "desktop")) {
+            let Ok(contents) = std::fs::read_to_string(&path) else { continue };
+            let value = |key: &str| {
+                contents.lines().find_map(|line| line.strip_prefix(key)?.strip_prefix('=').map(|value| value.trim().to_string()))
+            };
+            if value("NoDisplay").as_deref() == Some("true") || value("Type").as_deref() != Some("Application") {
+                continue;
+            }
+            let Some(name) = value("Name") else { continue };
+            if apps.iter().any(|app: &InstalledApp| app.name == name) {
+                continue;
+            }
+            apps.push(InstalledApp {
+                name,
+                path: Some(path.to_string_lossy().into_owned()),
+                installed_at: created(&path),
+                source: "desktop_entry".to_string(),
+                ..Default::default()
+            });
+        }
+    }
+    apps
+}
@@ -0,0 +1,156 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Serialize;
+
+use crate::cmd::diagnostic;
+use crate::pairing;
+
+// What this helper build is: the SHA-256 of the running binary and whether
+// its code signature holds. Signed with the device key and sent while paired
+// so the server can refuse execution tokens to tampered or outdated builds.
+const ATTEST_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const ATTEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Attestation {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    pub binary_sha256: String,
+    // "valid", "unsigned", "invalid" or "unsupported" (no check on this OS)
+    pub code_signature: String,
+    // Team identifier (macOS) or certificate subject (Windows)
+    pub signer: Option<String>,
+    pub computed_at: DateTime<Utc>,
+}
+
+static ATTESTATION: OnceLock<Result<Attestation, String>> = OnceLock::new();
+static LAST_SENT: Mutex<Option<Instant>> = Mutex::new(None);
+
+// Computed once, at startup: it's the binary that's running that counts, not
+// whatever an update later puts on disk
+pub fn current() -> Result<Attestation, String> {
+    ATTESTATION.get_or_init(compute).clone()
+}
+
+fn compute() -> Result<Attestation, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate the helper binary: {}", e))?;
+    let (code_signature, signer) = code_signature(&exe);
+    Ok(Attestation {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        binary_sha256: sha256_file(&exe)?,
+        code_signature,
+        signer,
+        computed_at: Utc::now(),
+    })
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        context.update(&buffer[..read]);
+    }
+    Ok(context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn code_signature(exe: &Path) -> (String, Option<String>) {
+    match std::env::consts::OS {
+        "macos" => {
+            // The whole .app when running from one, so resources count too
+            let target = app_bundle(exe).unwrap_or_else(|| exe.to_path_buf());
+            let target = target.to_string_lossy();
+            let verified = diagnostic("/usr/bin/codesign").args(["--verify", "--deep", "--strict", &target]).output();
+            let status = match verified {
+                Ok(output) if output.status.success() => "valid",
+                Ok(output) if String::from_utf8_lossy(&output.stderr).contains("not signed at all") => "unsigned",
+                Ok(_) => "invalid",
+                Err(_) => "unsupported",
+            };
+            // codesign -dv prints "TeamIdentifier=ABCDE12345" to stderr
+            let signer = diagnostic("/usr/bin/codesign").args(["-dv", &target]).output().ok().and_then(|output| {
+                String::from_utf8_lossy(&output.stderr)
+                    .lines()
+                    .find_map(|line| line.strip_prefix("TeamIdentifier="))
+                    .filter(|team| *team != "not set")
+                    .map(str::to_string)
+            });
+            (status.to_string(), signer)
+        }
+        "windows" => {
+            let script = format!(
+                "$s = Get-AuthenticodeSignature -LiteralPath '{}'; \"$($s.Status)|$($s.SignerCertificate.Subject)\"",
+                exe.to_string_lossy().replace('\'', "''")
+            );
+            let output = diagnostic("powershell").args(["-NoProfile", "-Command", &script]).output();
+            let Ok(output) = output else { return ("unsupported".to_string(), None) };
+            let line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            let (status, subject) = line.split_once('|').unwrap_or((line.as_str(), ""));
+            let status = match status {
+                "Valid" => "valid",
+                "NotSigned" => "unsigned",
+                "" => "unsupported",
+                _ => "invalid",
+            };
+            (status.to_string(), (!subject.is_empty()).then(|| subject.to_string()))
+        }
+        _ => ("unsupported".to_string(), None),
+    }
+}
+
+// .../OhFixIt.app/Contents/MacOS/ohfixit -> .../OhFixIt.app
+fn app_bundle(exe: &Path) -> Option<PathBuf> {
+    exe.ancestors().find(|dir| dir.extension().is_some_and(|ext| ext == "app")).map(Path::to_path_buf)
+}
+
+// Part of the pairing job: true when a paired helper should attest again
+pub fn due() -> bool {
+    LAST_SENT.lock().unwrap().map_or(true, |sent| sent.elapsed() >= ATTEST_INTERVAL)
+}
+
+// Send the signed attestation. The signature covers `payload` exactly as sent.
+pub async fn report(client: &Client) -> Result<String, String> {
+    let attestation = current()?;
+    let device_id = pairing::status().device_id;
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let payload = serde_json::json!({
+        "device_id": device_id,
+        "attestation": attestation,
+    })
+    .to_string();
+    let signature = pairing::sign(payload.as_bytes())?;
+
+    client
+        .post(format!("{}/api/automation/helper/pair/attestation", server_url))
+        .timeout(ATTEST_TIMEOUT)
+        .json(&serde_json::json!({
+            "deviceId": device_id,
+            "payload": payload,
+            "signature": signature,
+        }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to send build attestation: {}", e))?;
+    *LAST_SENT.lock().unwrap() = Some(Instant::now());
+    Ok(format!(
+        "Attested build {} ({}, signature {})",
+        attestation.version,
+        &attestation.binary_sha256[..12],
+        attestation.code_signature
+    ))
+}
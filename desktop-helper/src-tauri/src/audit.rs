@@ -0,0 +1,182 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{pairing, storage};
+
+const AUDIT_FILE: &str = "audit.jsonl";
+// prev_hash of the first entry
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Hash of the last line written, loaded from the file on first use
+static AUDIT_LOCK: Mutex<Option<String>> = Mutex::new(None);
+
+// Append one event to the local audit log (one JSON object per line). Each
+// entry carries the SHA-256 of the line before it, so an edited or deleted
+// entry breaks the chain (see verify).
+pub fn record(event: &str, details: serde_json::Value) {
+    let mut last_hash = AUDIT_LOCK.lock().unwrap();
+    let path = storage::data_dir().join(AUDIT_FILE);
+    let prev_hash = last_hash.get_or_insert_with(|| tail_hash(&path)).clone();
+    let entry = serde_json::json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "event": event,
+        "details": details,
+        "prev_hash": prev_hash,
+    });
+    let line = entry.to_string();
+
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    match result {
+        Ok(()) => *last_hash = Some(sha256_hex(&line)),
+        Err(e) => log::error!("Failed to write audit entry to {}: {}", path.display(), e),
+    }
+}
+
+fn tail_hash(path: &Path) -> String {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .rev()
+        .find(|line| !line.is_empty())
+        .map(sha256_hex)
+        .unwrap_or_else(|| GENESIS_HASH.to_string())
+}
+
+fn sha256_hex(line: &str) -> String {
+    ring::digest::digest(&ring::digest::SHA256, line.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Verification {
+    pub valid: bool,
+    pub entries: usize,
+    // Entries written before the log was chained; only allowed at the start
+    pub unchained: usize,
+    // Hash of the last entry: a copy of the log ending here can't be altered
+    // without changing it
+    pub head_hash: String,
+    // 1-based line where the chain breaks
+    pub broken_at: Option<usize>,
+    pub reason: Option<String>,
+}
+
+pub fn verify() -> Verification {
+    let _guard = AUDIT_LOCK.lock().unwrap();
+    let content = fs::read_to_string(storage::data_dir().join(AUDIT_FILE)).unwrap_or_default();
+    verify_lines(&content)
+}
+
+fn verify_lines(content: &str) -> Verification {
+    let mut verification = Verification {
+        valid: true,
+        entries: 0,
+        unchained: 0,
+        head_hash: GENESIS_HASH.to_string(),
+        broken_at: None,
+        reason: None,
+    };
+    let mut chained = false;
+    for (index, line) in content.lines().enumerate().filter(|(_, line)| !line.is_empty()) {
+        let problem = match serde_json::from_str::<serde_json::Value>(line) {
+            Err(e) => Some(format!("Not valid JSON: {}", e)),
+            Ok(entry) => match entry["prev_hash"].as_str() {
+                None if chained => Some("Entry has no prev_hash".to_string()),
+                None => {
+                    verification.unchained += 1;
+                    None
+                }
+                Some(hash) if hash != verification.head_hash => {
+                    Some(format!("prev_hash {} doesn't match the previous entry ({})", hash, verification.head_hash))
+                }
+                Some(_) => {
+                    chained = true;
+                    None
+                }
+            },
+        };
+        if let Some(reason) = problem {
+            verification.valid = false;
+            verification.broken_at = Some(index + 1);
+            verification.reason = Some(reason);
+            return verification;
+        }
+        verification.entries += 1;
+        verification.head_hash = sha256_hex(line);
+    }
+    verification
+}
+
+#[tauri::command]
+pub fn verify_audit_log() -> Verification {
+    verify()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+// GET /audit/export?from=&to= (RFC 3339): the entries in that window, signed
+// with the device key from pairing. The signature covers `payload` exactly as
+// sent, so anyone holding the device's public key can prove which commands
+// ran here (see lib/ohfixit/devices.ts verifyAuditExport).
+pub async fn export_handler(Query(query): Query<ExportQuery>) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let (content, verification) = tauri::async_runtime::spawn_blocking(|| {
+        let _guard = AUDIT_LOCK.lock().unwrap();
+        let content = fs::read_to_string(storage::data_dir().join(AUDIT_FILE)).unwrap_or_default();
+        let verification = verify_lines(&content);
+        (content, verification)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let in_window = |entry: &serde_json::Value| {
+        let at = entry["timestamp"].as_str().and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+        match at.map(|at| at.with_timezone(&Utc)) {
+            Some(at) => query.from.map_or(true, |from| at >= from) && query.to.map_or(true, |to| at <= to),
+            None => false,
+        }
+    };
+    let entries: Vec<serde_json::Value> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .filter(in_window)
+        .collect();
+
+    let device = pairing::status();
+    let payload = serde_json::json!({
+        "device_id": device.device_id,
+        "exported_at": Utc::now().to_rfc3339(),
+        "from": query.from,
+        "to": query.to,
+        "chain": verification,
+        "entries": entries,
+    })
+    .to_string();
+    let signature = pairing::sign(payload.as_bytes()).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    record("audit_exported", serde_json::json!({ "device_id": device.device_id, "entries": entries.len() }));
+
+    Ok(Json(serde_json::json!({
+        "payload": payload,
+        "signature": signature,
+        "algorithm": "Ed25519",
+        "public_key": pairing::public_key(),
+    })))
+}
@@ -0,0 +1,209 @@
+use chrono::Utc;
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
+use reqwest::Client;
+
+use crate::nonce_cache::ConsumedCache;
+use crate::{audit, clock, jwks, pairing, Claims};
+
+// Same allowance jsonwebtoken applies to exp by default
+const LEEWAY_SECS: i64 = 60;
+
+// Must match lib/ohfixit/jwt.ts. Deployments that share a signing key set
+// OHFIXIT_JWT_ISSUER on both sides to keep each other's tokens apart.
+const DEFAULT_ISSUER: &str = "ohfixit-helper";
+// Tokens minted for web sessions or other consumers carry a different audience
+const AUDIENCE: &str = "desktop-helper";
+// Asymmetric only: the helper holds no secret that could mint an approval
+const ALGORITHMS: &[Algorithm] = &[Algorithm::ES256, Algorithm::RS256];
+
+// Token ids already presented, kept until the token would have expired
+static USED_TOKENS: ConsumedCache = ConsumedCache::new("used_tokens.json");
+
+// Scopes the server grants, space-separated in Claims.scope (lib/ohfixit/jwt.ts).
+// Routes require one each (policy::Policy::scope), so a token minted for a
+// health scan can't run commands or capture the screen.
+pub const SCOPE_DIAGNOSTICS: &str = "diagnostics:read";
+pub const SCOPE_EXECUTE: &str = "automation:execute";
+pub const SCOPE_SCREEN: &str = "screen:capture";
+pub const SCOPES: &[&str] = &[SCOPE_DIAGNOSTICS, SCOPE_EXECUTE, SCOPE_SCREEN];
+
+// Why a token was refused. Only `invalid` ones (malformed, forged, for
+// another issuer, audience or device) count towards a lockout: a genuine
+// token can also be refused for being replayed or expired, for a skewed
+// clock, an unreachable JWKS or a helper that isn't paired yet.
+pub struct Rejection {
+    pub message: String,
+    pub invalid: bool,
+}
+
+impl Rejection {
+    fn invalid(message: String) -> Self {
+        Rejection { message, invalid: true }
+    }
+
+    fn other(message: String) -> Self {
+        Rejection { message, invalid: false }
+    }
+}
+
+// Decode and verify an approval token against the server's published keys.
+// Time-based rejections are checked against a remote clock first so a wrong
+// system time isn't reported as an expired approval.
+pub async fn validate_token(token: &str, client: &Client) -> Result<Claims, String> {
+    verify(token, client).await.map_err(|rejection| rejection.message)
+}
+
+async fn verify(token: &str, client: &Client) -> Result<Claims, Rejection> {
+    let header = decode_header(token).map_err(|e| Rejection::invalid(format!("Invalid token: {}", e)))?;
+    if !ALGORITHMS.contains(&header.alg) {
+        return Err(Rejection::invalid(format!("Invalid token: {:?} signatures are not accepted", header.alg)));
+    }
+    let kid = header.kid.ok_or_else(|| Rejection::invalid("Invalid token: missing key id".to_string()))?;
+    let key = jwks::key(client, &kid).await.map_err(|e| Rejection { message: e, invalid: jwks::loaded() })?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[issuer()]);
+    validation.set_audience(&[AUDIENCE]);
+    // Present-and-matching, not just matching-if-present
+    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+    // exp is checked below, where a skewed clock can be told apart
+    validation.validate_exp = false;
+    let token_data = decode::<Claims>(token, &key, &validation)
+        .map_err(|e| Rejection::invalid(format!("Invalid token: {}", e)))?;
+
+    let claims = token_data.claims;
+    // A token for another device is invalid; any token before pairing isn't
+    pairing::check_binding(&claims).map_err(|e| Rejection { message: e, invalid: pairing::status().paired })?;
+    let now = Utc::now().timestamp();
+    match time_error(&claims, now) {
+        None => Ok(claims),
+        Some(error) => match clock::skew() {
+            Some(skew) if skew.num_seconds().abs() > clock::MAX_SKEW_SECS => {
+                audit::record("clock_skew_detected", serde_json::json!({
+                    "skew_secs": skew.num_seconds(),
+                    "action_id": claims.action_id,
+                    "fix_action_id": clock::sync_action_id(),
+                }));
+                // The fix itself has to be approvable while the clock is wrong,
+                // so its token is checked against the reference time instead
+                if claims.action_id == clock::sync_action_id() && time_error(&claims, now - skew.num_seconds()).is_none() {
+                    return Ok(claims);
+                }
+                Err(Rejection::other(clock::skew_error(skew)))
+            }
+            _ => Err(Rejection::other(error)),
+        },
+    }
+}
+
+// validate_token for a token arriving from outside (an HTTP request, a Tauri
+// command): each token is accepted once, so a captured one can't be replayed
+// until it expires
+pub async fn validate_once(token: &str, client: &Client) -> Result<Claims, Rejection> {
+    let claims = verify(token, client).await?;
+    let expires_at = chrono::DateTime::from_timestamp(claims.exp as i64 + LEEWAY_SECS, 0).unwrap_or_else(Utc::now);
+    if let Err(first_seen) = USED_TOKENS.consume(&claims.jti, expires_at) {
+        audit::record("token_replay_rejected", serde_json::json!({
+            "jti": claims.jti,
+            "action_id": claims.action_id,
+            "first_seen": first_seen.to_rfc3339(),
+        }));
+        return Err(Rejection::other(format!("Token '{}' was already used at {}", claims.jti, first_seen.to_rfc3339())));
+    }
+    Ok(claims)
+}
+
+fn issuer() -> String {
+    std::env::var("OHFIXIT_JWT_ISSUER").unwrap_or_else(|_| DEFAULT_ISSUER.to_string())
+}
+
+fn time_error(claims: &Claims, now: i64) -> Option<String> {
+    if (claims.exp as i64) < now - LEEWAY_SECS {
+        return Some("Token expired".to_string());
+    }
+    // Issued in the future means our clock is behind the server's
+    if claims.iat as i64 > now + LEEWAY_SECS {
+        return Some("Token issued in the future".to_string());
+    }
+    None
+}
+
+// A token authorizes exactly the action it was minted for, and only if its
+// scope permits execution (a diagnostics token can't run anything)
+pub fn authorize_action(claims: &Claims, action_id: &str) -> Result<(), String> {
+    if claims.action_id.is_empty() || claims.approval_id.is_empty() {
+        return Err(format!("Token carries no approved action, so it can't run '{}'", action_id));
+    }
+    if claims.action_id != action_id {
+        return Err(format!(
+            "Token was issued for action '{}', not '{}'",
+            claims.action_id, action_id
+        ));
+    }
+    if !permits_execution(claims) {
+        return Err(format!("Token scope '{}' does not permit execution", claims.scope));
+    }
+    Ok(())
+}
+
+pub fn permits_execution(claims: &Claims) -> bool {
+    has_scope(claims, SCOPE_EXECUTE)
+}
+
+// Servers from before granular scopes mint "execute", "both" or "report";
+// those read as execution plus diagnostics, or diagnostics alone. None of
+// them grants screen capture.
+pub fn has_scope(claims: &Claims, scope: &str) -> bool {
+    claims.scope.split_whitespace().any(|granted| match granted {
+        "execute" | "both" => scope == SCOPE_EXECUTE || scope == SCOPE_DIAGNOSTICS,
+        "report" => scope == SCOPE_DIAGNOSTICS,
+        granted => granted == scope,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use jsonwebtoken::{encode, DecodingKey, EncodingKey, Header};
+
+    use super::*;
+
+    // What the server mints for a health scan or a screenshot: a scope, no
+    // action and no approval. Decoded the way verify() does, with a test key.
+    pub fn scope_only(scope: &str) -> Claims {
+        let now = Utc::now().timestamp();
+        let payload = serde_json::json!({
+            "chatId": null,
+            "userId": "user-1",
+            "anonymousId": null,
+            "deviceId": "device-1",
+            "scope": scope,
+            "iss": DEFAULT_ISSUER,
+            "aud": AUDIENCE,
+            "jti": "jti-1",
+            "iat": now,
+            "exp": now + 600,
+        });
+        let token = encode(&Header::default(), &payload, &EncodingKey::from_secret(b"test")).unwrap();
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[DEFAULT_ISSUER]);
+        validation.set_audience(&[AUDIENCE]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+        decode::<Claims>(&token, &DecodingKey::from_secret(b"test"), &validation).unwrap().claims
+    }
+
+    #[test]
+    fn scope_only_token_decodes_with_its_scope() {
+        let claims = scope_only(SCOPE_DIAGNOSTICS);
+        assert!(claims.action_id.is_empty() && claims.approval_id.is_empty());
+        assert!(has_scope(&claims, SCOPE_DIAGNOSTICS));
+        assert!(!has_scope(&claims, SCOPE_EXECUTE));
+        assert!(!has_scope(&claims, SCOPE_SCREEN));
+    }
+
+    #[test]
+    fn scope_only_token_authorizes_no_action() {
+        let claims = scope_only(&format!("{} {}", SCOPE_DIAGNOSTICS, SCOPE_EXECUTE));
+        assert!(authorize_action(&claims, "flush-dns").is_err());
+        assert!(authorize_action(&claims, "").is_err());
+    }
+}
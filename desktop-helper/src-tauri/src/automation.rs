@@ -0,0 +1,76 @@
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::Json;
+use serde::Deserialize;
+use tauri::AppHandle;
+
+use crate::watchdog::{self, AbortedExecution};
+use crate::{fingerprint, ActionResult};
+
+#[derive(Debug, Deserialize)]
+pub struct ExecuteRequest {
+    #[serde(alias = "actionId")]
+    action_id: String,
+    // Dry run: record what would execute and report fixture output instead
+    #[serde(default)]
+    simulate: bool,
+}
+
+// POST /automation/execute (Authorization: Bearer <approval token>)
+pub async fn automation_execute_handler(
+    State(app): State<AppHandle>,
+    headers: HeaderMap,
+    Json(request): Json<ExecuteRequest>,
+) -> Json<ActionResult> {
+    let result = match bearer_token(&headers) {
+        Some(token) => crate::run_action(&app, &request.action_id, token, request.simulate).await,
+        None => Err("Missing bearer token".to_string()),
+    };
+
+    Json(result.unwrap_or_else(|e| ActionResult {
+        success: false,
+        message: e.clone(),
+        error: Some(e),
+        artifacts: None,
+        rollback_id: None,
+        environment: Some(fingerprint::current()),
+        elevation: None,
+        simulated: false,
+        risk: None,
+        category: None,
+        estimated_bytes_freed: None,
+    }))
+}
+
+// POST /automation/abort-all. Needs no token: stopping is always allowed.
+pub async fn abort_all_handler(State(app): State<AppHandle>) -> Json<serde_json::Value> {
+    let aborted = abort_all(&app).await;
+    Json(serde_json::json!({ "aborted": aborted }))
+}
+
+#[tauri::command]
+pub async fn abort_all_automation(app: AppHandle) -> Result<Vec<AbortedExecution>, String> {
+    Ok(abort_all(&app).await)
+}
+
+// Kill switch shared by the command, the endpoint and the tray menu
+pub async fn abort_all(app: &AppHandle) -> Vec<AbortedExecution> {
+    let aborted = tauri::async_runtime::spawn_blocking(watchdog::abort_all)
+        .await
+        .unwrap_or_default();
+    log::info!("Abort requested, stopped {} execution(s)", aborted.len());
+    let message = match aborted.len() {
+        0 => "🛑 Nothing was running".to_string(),
+        n => format!("🛑 Stopped {} running action(s)", n),
+    };
+    crate::emit_status(app, &message, "aborted");
+    aborted
+}
+
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
@@ -0,0 +1,162 @@
+use axum::extract::State;
+use axum::Json;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{fingerprint, outbound, redact, ActionResult, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct BatchStep {
+    #[serde(alias = "actionId")]
+    action_id: String,
+    // Approval tokens are minted per action, so each step brings its own
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    steps: Vec<BatchStep>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepResult {
+    action_id: String,
+    // "succeeded", "failed", "rolled_back", "rollback_failed", "not_reversible" or "skipped"
+    status: String,
+    result: Option<ActionResult>,
+    error: Option<String>,
+    rollback: Option<ActionResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchResult {
+    success: bool,
+    message: String,
+    steps: Vec<StepResult>,
+}
+
+// POST /automation/execute-batch: run steps in order, stop at the first failure
+// and undo the completed steps in reverse order
+pub async fn execute_batch_handler(
+    State(app): State<AppHandle>,
+    Json(request): Json<BatchRequest>,
+) -> Json<BatchResult> {
+    let mut steps: Vec<StepResult> = Vec::new();
+    let mut failed = false;
+
+    for step in &request.steps {
+        if failed {
+            steps.push(StepResult {
+                action_id: step.action_id.clone(),
+                status: "skipped".to_string(),
+                result: None,
+                error: None,
+                rollback: None,
+            });
+            continue;
+        }
+
+        let (status, result, error) = match crate::run_action(&app, &step.action_id, &step.token, false).await {
+            Ok(result) if result.success => ("succeeded", Some(result), None),
+            Ok(result) => ("failed", Some(result), None),
+            Err(e) => ("failed", None, Some(e)),
+        };
+        failed = status == "failed";
+        steps.push(StepResult {
+            action_id: step.action_id.clone(),
+            status: status.to_string(),
+            result,
+            error,
+            rollback: None,
+        });
+    }
+
+    if failed {
+        roll_back_completed(&app, &request.steps, &mut steps).await;
+    }
+
+    let message = if failed {
+        let failed_step = steps.iter().find(|s| s.status == "failed").map(|s| s.action_id.as_str()).unwrap_or("");
+        let rolled_back = steps.iter().filter(|s| s.status == "rolled_back").count();
+        let stuck = steps.iter().filter(|s| matches!(s.status.as_str(), "rollback_failed" | "not_reversible")).count();
+        format!(
+            "Batch stopped at '{}': {} completed step(s) rolled back, {} could not be undone",
+            failed_step, rolled_back, stuck
+        )
+    } else {
+        format!("All {} steps completed", steps.len())
+    };
+    log::info!("{}", message);
+
+    let result = BatchResult { success: !failed, message, steps };
+    if let Some(first) = request.steps.first() {
+        if let Err(e) = report_batch(&app, &first.token, &result).await {
+            log::error!("Failed to report batch result: {}", e);
+        }
+    }
+    Json(result)
+}
+
+async fn roll_back_completed(app: &AppHandle, requested: &[BatchStep], steps: &mut [StepResult]) {
+    for (step, request) in steps.iter_mut().zip(requested).rev() {
+        if step.status != "succeeded" {
+            continue;
+        }
+        let Some(rollback_id) = step.result.as_ref().and_then(|r| r.rollback_id.clone()) else {
+            step.status = "not_reversible".to_string();
+            continue;
+        };
+
+        match crate::run_rollback(app, &step.action_id, &rollback_id, &request.token).await {
+            Ok(rollback) => {
+                step.status = if rollback.success { "rolled_back" } else { "rollback_failed" }.to_string();
+                step.rollback = Some(rollback);
+            }
+            Err(e) => {
+                step.status = "rollback_failed".to_string();
+                step.error = Some(e);
+            }
+        }
+    }
+}
+
+async fn report_batch(app: &AppHandle, token: &str, result: &BatchResult) -> Result<(), String> {
+    let client = app.state::<std::sync::Mutex<AppState>>().lock().unwrap().client.clone();
+    let report_url = outbound::report_url(&client).await?;
+
+    let steps: Vec<serde_json::Value> = result
+        .steps
+        .iter()
+        .map(|step| {
+            serde_json::json!({
+                "actionId": step.action_id,
+                "status": step.status,
+                "rollbackId": step.result.as_ref().and_then(|r| r.rollback_id.clone()),
+                "error": step.error.as_deref().map(|e| redact::redact(e).0),
+            })
+        })
+        .collect();
+
+    let payload = serde_json::json!({
+        "actionId": "batch",
+        "success": result.success,
+        "output": result.message,
+        "steps": steps,
+        "environment": fingerprint::current(),
+        "timestamp": Utc::now().to_rfc3339(),
+    });
+
+    let response = client
+        .post(&report_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to report batch result: {}", e))?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Server returned status: {}", response.status()))
+    }
+}
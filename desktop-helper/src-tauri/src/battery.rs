@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+use crate::health::CheckResult;
+use crate::scheduler;
+
+// The battery check behind GET /health/battery, for "my laptop dies fast" and
+// "it's slow unplugged". macOS reads the smart battery's registry entry
+// (ioreg) and system_profiler's condition; Windows reads the battery WMI
+// classes; Linux reads /sys/class/power_supply.
+// Below this share of the design capacity a battery is worn
+const WORN_PERCENT: f64 = 80.0;
+const FAILING_PERCENT: f64 = 50.0;
+
+#[derive(Debug, Serialize, Default)]
+pub struct Battery {
+    cycle_count: Option<u32>,
+    // mAh on macOS, mWh on Windows and Linux (µ-units divided down)
+    design_capacity: Option<u64>,
+    full_charge_capacity: Option<u64>,
+    capacity_unit: &'static str,
+    // Full charge capacity as a share of the design capacity
+    health_percent: Option<f64>,
+    // The OS's own verdict: "Normal", "Service Recommended", …
+    condition: Option<String>,
+    charge_percent: Option<u8>,
+    on_battery: bool,
+    charging: Option<bool>,
+    charger_watts: Option<u32>,
+    // Low power mode, a power saver plan or profile, or a CPU speed limit
+    // while unplugged
+    throttled_on_battery: bool,
+    throttle_reasons: Vec<String>,
+}
+
+pub fn check() -> CheckResult {
+    let found = match std::env::consts::OS {
+        "macos" => macos(),
+        "windows" => windows(),
+        _ => linux(),
+    };
+    let Some(mut battery) = found else {
+        return CheckResult::new("unsupported", "No battery found");
+    };
+    let power = scheduler::power_state();
+    battery.on_battery = power.on_battery;
+    battery.charge_percent = battery.charge_percent.or(power.battery_percent);
+    if power.on_battery && power.thermal_pressure {
+        battery.throttle_reasons.push("CPU speed limited".to_string());
+    }
+    battery.throttled_on_battery = battery.on_battery && !battery.throttle_reasons.is_empty();
+    if let (Some(design), Some(full)) = (battery.design_capacity, battery.full_charge_capacity) {
+        if design > 0 {
+            battery.health_percent = Some((full as f64 * 1000.0 / design as f64).round() / 10.0);
+        }
+    }
+
+    let worn_condition = battery.condition.as_deref().is_some_and(|condition| {
+        let condition = condition.to_lowercase();
+        condition != "normal" && condition != "good"
+    });
+    let status = match battery.health_percent {
+        Some(health) if health < FAILING_PERCENT => "critical",
+        Some(health) if health < WORN_PERCENT => "warning",
+        _ if worn_condition => "warning",
+        _ => "ok",
+    };
+    let mut detail = match (battery.health_percent, battery.cycle_count) {
+        (Some(health), Some(cycles)) => format!("Battery holds {}% of its original charge after {} cycles", health, cycles),
+        (Some(health), None) => format!("Battery holds {}% of its original charge", health),
+        (None, _) => "Battery capacity couldn't be read".to_string(),
+    };
+    if let Some(condition) = battery.condition.as_deref().filter(|_| worn_condition) {
+        detail.push_str(&format!("; condition: {}", condition));
+    }
+    if battery.throttled_on_battery {
+        detail.push_str(&format!("; slowed on battery ({})", battery.throttle_reasons.join(", ")));
+    }
+    CheckResult::new(status, detail).with_data(serde_json::to_value(&battery).unwrap_or_default())
+}
+
+// `"Key" = value` lines of `ioreg -rn AppleSmartBattery`, with nested
+// dictionaries left as their raw text
+fn ioreg_values(output: &str) -> BTreeMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().trim_start_matches('|').trim().split_once(" = "))
+        .filter_map(|(key, value)| Some((key.strip_prefix('"')?.strip_suffix('"')?.to_string(), value.trim().to_string())))
+        .collect()
+}
+
+fn macos() -> Option<Battery> {
+    let output = read_output("/usr/sbin/ioreg", &["-rn", "AppleSmartBattery"])?;
+    let values = ioreg_values(&output);
+    if values.get("BatteryInstalled").map(String::as_str) != Some("Yes") {
+        return None;
+    }
+    let number = |key: &str| values.get(key).and_then(|value| value.parse::<u64>().ok());
+    let mut battery = Battery {
+        cycle_count: number("CycleCount").map(|cycles| cycles as u32),
+        design_capacity: number("DesignCapacity"),
+        // MaxCapacity is a percentage on Apple silicon; the raw value is mAh
+        full_charge_capacity: number("AppleRawMaxCapacity").or_else(|| number("MaxCapacity").filter(|capacity| *capacity > 100)),
+        capacity_unit: "mAh",
+        charging: values.get("IsCharging").map(|charging| charging == "Yes"),
+        ..Default::default()
+    };
+    // "AdapterDetails" = {"Watts"=96,"FamilyCode"=…}
+    battery.charger_watts = values.get("AdapterDetails").and_then(|details| {
+        let watts = details.split_once("\"Watts\"=")?.1;
+        watts.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+    });
+
+    // "Condition: Normal" under the battery's health information
+    let profile = read_output("/usr/sbin/system_profiler", &["SPPowerDataType"]).unwrap_or_default();
+    battery.condition = profile
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Condition:").map(|condition| condition.trim().to_string()));
+    // " lowpowermode         1" among the settings in use
+    let settings = read_output("/usr/bin/pmset", &["-g"]).unwrap_or_default();
+    let low_power = settings.lines().any(|line| {
+        let mut words = line.split_whitespace();
+        words.next() == Some("lowpowermode") && words.next() == Some("1")
+    });
+    if low_power {
+        battery.throttle_reasons.push("Low Power Mode".to_string());
+    }
+    Some(battery)
+}
+
+fn windows() -> Option<Battery> {
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    // Capacities are in mWh; the power plan's name says whether it saves power
+    let script = "$s = Get-CimInstance -Namespace root\\wmi -ClassName BatteryStaticData -ErrorAction SilentlyContinue | Select-Object -First 1; \
+                  $f = Get-CimInstance -Namespace root\\wmi -ClassName BatteryFullChargedCapacity -ErrorAction SilentlyContinue | Select-Object -First 1; \
+                  $c = Get-CimInstance -Namespace root\\wmi -ClassName BatteryCycleCount -ErrorAction SilentlyContinue | Select-Object -First 1; \
+                  $b = Get-CimInstance -Namespace root\\wmi -ClassName BatteryStatus -ErrorAction SilentlyContinue | Select-Object -First 1; \
+                  $p = Get-CimInstance -Namespace root\\cimv2\\power -ClassName Win32_PowerPlan -Filter 'IsActive=True' -ErrorAction SilentlyContinue; \
+                  \"present=$($null -ne $s)\"; \"design=$($s.DesignedCapacity)\"; \"full=$($f.FullChargedCapacity)\"; \
+                  \"cycles=$($c.CycleCount)\"; \"charging=$($b.Charging)\"; \"plan=$($p.ElementName)\"";
+    let output = read_output(&powershell.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", script])?;
+    let values: BTreeMap<&str, &str> = output.lines().filter_map(|line| line.trim().split_once('=')).collect();
+    if values.get("present") != Some(&"True") {
+        return None;
+    }
+    let number = |key: &str| values.get(key).and_then(|value| value.parse::<u64>().ok()).filter(|value| *value > 0);
+    let mut battery = Battery {
+        // Many batteries don't report a cycle count and WMI gives 0
+        cycle_count: number("cycles").map(|cycles| cycles as u32),
+        design_capacity: number("design"),
+        full_charge_capacity: number("full"),
+        capacity_unit: "mWh",
+        charging: values.get("charging").map(|charging| *charging == "True"),
+        ..Default::default()
+    };
+    if let Some(plan) = values.get("plan").filter(|plan| plan.to_lowercase().contains("saver")) {
+        battery.throttle_reasons.push(format!("{} power plan", plan));
+    }
+    Some(battery)
+}
+
+fn linux() -> Option<Battery> {
+    let supplies: Vec<_> = std::fs::read_dir("/sys/class/power_supply").ok()?.flatten().map(|entry| entry.path()).collect();
+    let read = |path: &std::path::Path, file: &str| std::fs::read_to_string(path.join(file)).map(|s| s.trim().to_string()).ok();
+    let path = supplies.iter().find(|path| read(path, "type").as_deref() == Some("Battery") && read(path, "scope").as_deref() != Some("Device"))?;
+    let number = |file: &str| read(path, file).and_then(|value| value.parse::<u64>().ok()).filter(|value| *value > 0);
+
+    // energy_* is µWh; batteries that only report charge_* (µAh) are
+    // converted with the design voltage (µV)
+    let voltage = number("voltage_min_design");
+    let (design, full) = match (number("energy_full_design"), number("energy_full")) {
+        (Some(design), full) => (Some(design / 1000), full.map(|full| full / 1000)),
+        _ => {
+            let to_mwh = |charge: u64| voltage.map(|voltage| charge * voltage / 1_000_000_000);
+            (number("charge_full_design").and_then(to_mwh), number("charge_full").and_then(to_mwh))
+        }
+    };
+    let mut battery = Battery {
+        cycle_count: number("cycle_count").map(|cycles| cycles as u32),
+        design_capacity: design,
+        full_charge_capacity: full,
+        capacity_unit: "mWh",
+        condition: read(path, "health"),
+        charge_percent: read(path, "capacity").and_then(|capacity| capacity.parse().ok()),
+        charging: read(path, "status").map(|status| status == "Charging"),
+        ..Default::default()
+    };
+    // A USB-PD charger advertises its maximum voltage and current
+    battery.charger_watts = supplies
+        .iter()
+        .filter(|supply| read(supply, "online").as_deref() == Some("1") && read(supply, "type").as_deref() != Some("Battery"))
+        .find_map(|supply| {
+            let volts = read(supply, "voltage_max")?.parse::<u64>().ok()?;
+            let amps = read(supply, "current_max")?.parse::<u64>().ok()?;
+            Some((volts * amps / 1_000_000_000_000) as u32).filter(|watts| *watts > 0)
+        });
+    if read(std::path::Path::new("/sys/firmware/acpi"), "platform_profile").as_deref() == Some("low-power") {
+        battery.throttle_reasons.push("low-power platform profile".to_string());
+    }
+    Some(battery)
+}
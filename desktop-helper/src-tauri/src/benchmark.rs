@@ -0,0 +1,279 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::read_trimmed;
+use crate::exec_context::system32;
+use crate::storage;
+
+const RESULTS_FILE: &str = "benchmark_results.json";
+
+// Sized to finish in well under a minute on slow hardware
+const DISK_FILE_BYTES: usize = 256 * 1024 * 1024;
+const DISK_CHUNK_BYTES: usize = 4 * 1024 * 1024;
+const RANDOM_READ_BYTES: usize = 4096;
+const RANDOM_READ_TIME: Duration = Duration::from_secs(3);
+const MEMORY_BUFFER_BYTES: usize = 128 * 1024 * 1024;
+const MEMORY_PASSES: usize = 8;
+const CPU_TIME: Duration = Duration::from_secs(3);
+const CPU_BLOCK_BYTES: usize = 1024 * 1024;
+
+// One benchmark runs at a time; they'd skew each other
+static RUN_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Measurement {
+    pub metric: String,
+    pub value: f64,
+    pub unit: String,
+    // Lowest value healthy hardware of this class normally reaches
+    pub expected_min: Option<f64>,
+    // "ok", "below_expected" or "unknown" when there's no reference for the hardware
+    pub verdict: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BenchmarkReport {
+    pub ran_at: DateTime<Utc>,
+    pub hardware_model: Option<String>,
+    pub hardware_class: String,
+    pub cores: usize,
+    pub measurements: Vec<Measurement>,
+    pub duration_ms: u64,
+}
+
+// GET /benchmark: the last report, if any
+pub async fn last_benchmark_handler() -> Json<Option<BenchmarkReport>> {
+    Json(storage::load_json::<Option<BenchmarkReport>>(RESULTS_FILE))
+}
+
+// POST /benchmark
+pub async fn run_benchmark_handler() -> Json<serde_json::Value> {
+    Json(match run_in_background().await {
+        Ok(report) => serde_json::json!(report),
+        Err(e) => serde_json::json!({ "error": e }),
+    })
+}
+
+#[tauri::command]
+pub async fn run_benchmark() -> Result<BenchmarkReport, String> {
+    run_in_background().await
+}
+
+async fn run_in_background() -> Result<BenchmarkReport, String> {
+    tauri::async_runtime::spawn_blocking(run)
+        .await
+        .map_err(|e| format!("Benchmark task failed: {}", e))?
+}
+
+// Run the suite and keep the report. Takes ~15 seconds and is disk and CPU heavy.
+pub fn run() -> Result<BenchmarkReport, String> {
+    let _guard = RUN_LOCK.try_lock().map_err(|_| "A benchmark is already running".to_string())?;
+    let started = Instant::now();
+    let model = hardware_model();
+    let class = hardware_class(model.as_deref());
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    log::info!("Running benchmark on {} ({})", model.as_deref().unwrap_or("unknown model"), class);
+
+    let (write, read, iops) = disk()?;
+    let single = cpu_hash_rate(1);
+    let raw = [
+        ("disk_sequential_write", write, "MB/s"),
+        ("disk_sequential_read", read, "MB/s"),
+        ("disk_random_read", iops, "IOPS"),
+        ("memory_copy", memory_bandwidth(), "MB/s"),
+        ("cpu_single_core", single, "MB/s SHA-256"),
+        ("cpu_multi_core", cpu_hash_rate(cores), "MB/s SHA-256"),
+    ];
+
+    let measurements = raw
+        .iter()
+        .map(|(metric, value, unit)| {
+            let expected_min = expected_min(class, metric, cores);
+            let verdict = match expected_min {
+                Some(min) if *value < min => "below_expected",
+                Some(_) => "ok",
+                None => "unknown",
+            };
+            Measurement {
+                metric: metric.to_string(),
+                value: (value * 10.0).round() / 10.0,
+                unit: unit.to_string(),
+                expected_min,
+                verdict: verdict.to_string(),
+            }
+        })
+        .collect();
+
+    let report = BenchmarkReport {
+        ran_at: Utc::now(),
+        hardware_model: model,
+        hardware_class: class.to_string(),
+        cores,
+        measurements,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    storage::save_json(RESULTS_FILE, &Some(&report))?;
+    Ok(report)
+}
+
+// Sequential write and read of a scratch file, then random 4 KiB reads from it.
+// The read figures include whatever the OS page cache contributes.
+fn disk() -> Result<(f64, f64, f64), String> {
+    let path = std::env::temp_dir().join(format!("ohfixit-benchmark-{}.tmp", uuid::Uuid::new_v4()));
+    let result = disk_at(&path);
+    let _ = std::fs::remove_file(&path);
+    result.map_err(|e| format!("Disk benchmark failed: {}", e))
+}
+
+fn disk_at(path: &std::path::Path) -> std::io::Result<(f64, f64, f64)> {
+    let chunk = pseudo_random(DISK_CHUNK_BYTES, 0x9E37_79B9_7F4A_7C15);
+
+    let started = Instant::now();
+    let mut file = std::fs::File::create(path)?;
+    for _ in 0..DISK_FILE_BYTES / DISK_CHUNK_BYTES {
+        file.write_all(&chunk)?;
+    }
+    file.sync_all()?;
+    let write = mb_per_sec(DISK_FILE_BYTES, started.elapsed());
+
+    let mut file = std::fs::File::open(path)?;
+    let mut buffer = vec![0u8; DISK_CHUNK_BYTES];
+    let started = Instant::now();
+    while file.read(&mut buffer)? > 0 {}
+    let read = mb_per_sec(DISK_FILE_BYTES, started.elapsed());
+
+    let blocks = (DISK_FILE_BYTES / RANDOM_READ_BYTES) as u64;
+    let mut block = [0u8; RANDOM_READ_BYTES];
+    let mut state = 0x2545_F491_4F6C_DD1D_u64;
+    let mut reads = 0u64;
+    let started = Instant::now();
+    while started.elapsed() < RANDOM_READ_TIME {
+        state = xorshift(state);
+        file.seek(SeekFrom::Start((state % blocks) * RANDOM_READ_BYTES as u64))?;
+        file.read_exact(&mut block)?;
+        reads += 1;
+    }
+    let iops = reads as f64 / started.elapsed().as_secs_f64();
+    Ok((write, read, iops))
+}
+
+fn memory_bandwidth() -> f64 {
+    let source = pseudo_random(MEMORY_BUFFER_BYTES, 0xD1B5_4A32_D192_ED03);
+    let mut target = vec![0u8; MEMORY_BUFFER_BYTES];
+    let started = Instant::now();
+    for _ in 0..MEMORY_PASSES {
+        target.copy_from_slice(&source);
+        std::hint::black_box(&target);
+    }
+    mb_per_sec(MEMORY_BUFFER_BYTES * MEMORY_PASSES, started.elapsed())
+}
+
+// SHA-256 throughput across `threads` threads hashing for a fixed time
+fn cpu_hash_rate(threads: usize) -> f64 {
+    let block = std::sync::Arc::new(pseudo_random(CPU_BLOCK_BYTES, 0xA076_1D64_78BD_642F));
+    let started = Instant::now();
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let block = block.clone();
+            std::thread::spawn(move || {
+                let mut hashed = 0usize;
+                while started.elapsed() < CPU_TIME {
+                    std::hint::black_box(ring::digest::digest(&ring::digest::SHA256, &block));
+                    hashed += block.len();
+                }
+                hashed
+            })
+        })
+        .collect();
+    let total: usize = workers.into_iter().map(|w| w.join().unwrap_or(0)).sum();
+    mb_per_sec(total, started.elapsed())
+}
+
+fn mb_per_sec(bytes: usize, elapsed: Duration) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64().max(f64::EPSILON)
+}
+
+// Incompressible data so storage controllers can't shortcut the disk test
+fn pseudo_random(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    let mut data = Vec::with_capacity(len + 8);
+    while data.len() < len {
+        state = xorshift(state);
+        data.extend_from_slice(&state.to_le_bytes());
+    }
+    data.truncate(len);
+    data
+}
+
+fn xorshift(mut x: u64) -> u64 {
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    x
+}
+
+// "MacBookPro18,3", "Mac14,2", "XPS 13 9310"
+fn hardware_model() -> Option<String> {
+    match std::env::consts::OS {
+        "macos" => read_trimmed("/usr/sbin/sysctl", &["-n", "hw.model"]),
+        "windows" => read_trimmed(
+            &system32().join(r"WindowsPowerShell\v1.0\powershell.exe").to_string_lossy(),
+            &["-NoProfile", "-Command", "(Get-CimInstance Win32_ComputerSystem).Model"],
+        ),
+        _ => std::fs::read_to_string("/sys/devices/virtual/dmi/id/product_name")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty()),
+    }
+}
+
+// Broad hardware generations the reference ranges are kept for. Virtual
+// machines get no reference: their numbers say more about the host.
+fn hardware_class(model: Option<&str>) -> &'static str {
+    let virtual_machine = model.is_some_and(|m| {
+        let m = m.to_lowercase();
+        ["virtual", "vmware", "kvm", "qemu", "parallels"].iter().any(|v| m.contains(v))
+    });
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        _ if virtual_machine => "virtual_machine",
+        ("macos", "aarch64") => "apple_silicon_mac",
+        ("macos", _) => "intel_mac",
+        (_, "x86_64") => "x86_64_pc",
+        (_, "aarch64") => "arm64_pc",
+        _ => "unknown",
+    }
+}
+
+// Conservative floors: a healthy machine of the class should clear them, so a
+// result below one points at a problem (thermal throttling, failing disk, a
+// full SSD) rather than ordinary variation
+fn expected_min(class: &str, metric: &str, cores: usize) -> Option<f64> {
+    let per_core = |single: f64| single * (cores as f64 * 0.6).max(1.0);
+    let value = match (class, metric) {
+        ("apple_silicon_mac", "disk_sequential_write") => 1000.0,
+        ("apple_silicon_mac", "disk_sequential_read") => 1500.0,
+        ("apple_silicon_mac", "disk_random_read") => 20_000.0,
+        ("apple_silicon_mac", "memory_copy") => 20_000.0,
+        ("apple_silicon_mac", "cpu_single_core") => 1200.0,
+        ("apple_silicon_mac", "cpu_multi_core") => per_core(1200.0),
+        ("intel_mac", "disk_sequential_write") => 400.0,
+        ("intel_mac", "disk_sequential_read") => 800.0,
+        ("intel_mac", "disk_random_read") => 8000.0,
+        ("intel_mac", "memory_copy") => 6000.0,
+        ("intel_mac", "cpu_single_core") => 250.0,
+        ("intel_mac", "cpu_multi_core") => per_core(250.0),
+        ("x86_64_pc" | "arm64_pc", "disk_sequential_write") => 150.0,
+        ("x86_64_pc" | "arm64_pc", "disk_sequential_read") => 300.0,
+        ("x86_64_pc" | "arm64_pc", "disk_random_read") => 3000.0,
+        ("x86_64_pc" | "arm64_pc", "memory_copy") => 4000.0,
+        ("x86_64_pc" | "arm64_pc", "cpu_single_core") => 200.0,
+        ("x86_64_pc" | "arm64_pc", "cpu_multi_core") => per_core(200.0),
+        _ => return None,
+    };
+    Some(value)
+}
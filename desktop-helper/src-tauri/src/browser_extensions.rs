@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tauri::AppHandle;
+use tokio::sync::Mutex;
+
+use crate::{audit, consent, storage};
+
+// GET /health/browser-extensions: the extensions installed in every Chrome,
+// Edge and Firefox profile, with their permissions, for "my browser is acting
+// weird" (new search engine, pop-ups, redirects). Chromium profiles are read
+// from each extension's manifest.json, Firefox's from extensions.json.
+// Extensions reveal a lot about someone's browsing, so the user agrees in a
+// dialog on this machine first; the answer holds for GRANT_TTL.
+const GRANT_TTL: Duration = Duration::from_secs(30 * 60);
+const BLOCKLIST_FILE: &str = "extension_blocklist.json";
+
+// Extensions pulled from the stores as malware or sold to adware publishers.
+// data_dir/extension_blocklist.json adds more: {"<extension id>": "<reason>"}
+const KNOWN_BAD: &[(&str, &str)] = &[
+    ("klbibkeccnjlkjkiokjodocebajanakg", "The Great Suspender: removed from the Chrome Web Store as malware"),
+    ("gabbbocakeomblphkmmnoamkioajlkfo", "Nano Adblocker: sold and updated to hijack accounts"),
+    ("ggolfgbegefeeoocgjbmkembbncoadlb", "Nano Defender: sold and updated to hijack accounts"),
+];
+
+// Permissions that let an extension read and change every site
+const ALL_SITES: &[&str] = &["<all_urls>", "*://*/*", "http://*/*", "https://*/*"];
+
+// Update URLs of the Chrome Web Store and Edge Add-ons
+const STORE_UPDATE_URLS: &[&str] = &[
+    "https://clients2.google.com/service/update2/crx",
+    "https://edge.microsoft.com/extensionwebstorebase/v1/crx",
+];
+
+// When the user last allowed the list; held while the dialog is up, so
+// concurrent requests share one prompt
+static GRANTED: Mutex<Option<Instant>> = Mutex::const_new(None);
+
+#[derive(Debug, Serialize, Default)]
+pub struct Extension {
+    id: String,
+    name: String,
+    version: Option<String>,
+    browser: String,
+    // The profile directory ("Default", "Profile 1", "abcd1234.default-release")
+    profile: String,
+    enabled: Option<bool>,
+    permissions: Vec<String>,
+    installed_at: Option<String>,
+    // "known_bad", "search_override", "all_sites", "not_from_store"
+    flags: Vec<String>,
+    known_bad_reason: Option<String>,
+}
+
+// GET /health/browser-extensions
+pub async fn extensions_handler(State(app): State<AppHandle>) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    allowed(&app).await?;
+    let extensions = tauri::async_runtime::spawn_blocking(list)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Extension scan failed: {}", e)))?;
+    let flagged = extensions.iter().filter(|extension| !extension.flags.is_empty()).count();
+    let known_bad = extensions.iter().filter(|extension| extension.known_bad_reason.is_some()).count();
+    Ok(Json(serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "count": extensions.len(),
+        "flagged": flagged,
+        "known_bad": known_bad,
+        "extensions": extensions,
+    })))
+}
+
+// Ok once the user has allowed reading their browser extensions (see
+// GRANT_TTL)
+async fn allowed(app: &AppHandle) -> Result<(), (StatusCode, String)> {
+    let mut granted = GRANTED.lock().await;
+    if granted.is_some_and(|at| at.elapsed() < GRANT_TTL) {
+        return Ok(());
+    }
+    let message = format!(
+        "OhFixIt wants to list the extensions installed in Chrome, Edge and Firefox, to look for ones known to cause trouble.\n\nThis stays allowed for {} minutes.",
+        GRANT_TTL.as_secs() / 60
+    );
+    let answer = consent::ask(app, "Allow OhFixIt to list browser extensions?".to_string(), message, "Allow", "Don't allow").await;
+    audit::record(
+        if answer == Some(true) { "browser_extensions_granted" } else { "browser_extensions_declined" },
+        serde_json::json!({ "timed_out": answer.is_none() }),
+    );
+    if answer != Some(true) {
+        return Err((StatusCode::FORBIDDEN, "Listing browser extensions was not allowed on this computer".to_string()));
+    }
+    *granted = Some(Instant::now());
+    Ok(())
+}
+
+fn list() -> Vec<Extension> {
+    let mut blocklist: HashMap<String, String> = storage::load_json(BLOCKLIST_FILE);
+    for (id, reason) in KNOWN_BAD {
+        blocklist.entry(id.to_string()).or_insert_with(|| reason.to_string());
+    }
+    let home = dirs::home_dir().unwrap_or_default();
+    let (chrome, edge, firefox) = match std::env::consts::OS {
+        "macos" => {
+            let support = home.join("Library/Application Support");
+            (support.join("Google/Chrome"), support.join("Microsoft Edge"), vec![support.join("Firefox/Profiles")])
+        }
+        "windows" => {
+            let local = dirs::data_local_dir().unwrap_or_default();
+            let roaming = dirs::data_dir().unwrap_or_default();
+            (
+                local.join(r"Google\Chrome\User Data"),
+                local.join(r"Microsoft\Edge\User Data"),
+                vec![roaming.join(r"Mozilla\Firefox\Profiles")],
+            )
+        }
+        _ => {
+            let config = dirs::config_dir().unwrap_or_default();
+            (
+                config.join("google-chrome"),
+                config.join("microsoft-edge"),
+                vec![home.join(".mozilla/firefox"), home.join("snap/firefox/common/.mozilla/firefox")],
+            )
+        }
+    };
+    let mut extensions = chromium("Google Chrome", &chrome);
+    extensions.extend(chromium("Microsoft Edge", &edge));
+    for root in firefox {
+        extensions.extend(gecko(&root));
+    }
+    for extension in &mut extensions {
+        if extension.permissions.iter().any(|permission| ALL_SITES.contains(&permission.as_str())) {
+            extension.flags.push("all_sites".to_string());
+        }
+        if let Some(reason) = blocklist.get(&extension.id) {
+            extension.flags.insert(0, "known_bad".to_string());
+            extension.known_bad_reason = Some(reason.clone());
+        }
+    }
+    extensions
+}
+
+fn read_json(path: &Path) -> serde_json::Value {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn strings(value: &serde_json::Value) -> impl Iterator<Item = String> + '_ {
+    value.as_array().into_iter().flatten().filter_map(|value| value.as_str().map(str::to_string))
+}
+
+fn subdirectories(path: &Path) -> Vec<PathBuf> {
+    let mut directories: Vec<PathBuf> = std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()).collect())
+        .unwrap_or_default();
+    directories.sort();
+    directories
+}
+
+// Profiles are the directories with a Preferences file ("Default",
+// "Profile 1"); each extension lives in Extensions/<id>/<version>/
+fn chromium(browser: &str, root: &Path) -> Vec<Extension> {
+    let mut extensions = Vec::new();
+    for profile in subdirectories(root).into_iter().filter(|profile| profile.join("Preferences").exists()) {
+        // Chrome keeps extension state in Secure Preferences where it can
+        let secure = read_json(&profile.join("Secure Preferences"));
+        let preferences = read_json(&profile.join("Preferences"));
+        let settings = |id: &str| {
+            let secure = &secure["extensions"]["settings"][id];
+            if secure.is_null() { preferences["extensions"]["settings"][id].clone() } else { secure.clone() }
+        };
+        for directory in subdirectories(&profile.join("Extensions")) {
+            let id = directory.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let Some(version) = subdirectories(&directory).pop() else { continue };
+            let manifest = read_json(&version.join("manifest.json"));
+            if manifest.is_null() {
+                continue;
+            }
+            let settings = settings(&id);
+            // Locations 5 and 10 are components bundled with the browser
+            if matches!(settings["location"].as_i64(), Some(5) | Some(10)) {
+                continue;
+            }
+            let disabled = settings["state"].as_i64() == Some(0)
+                || settings["disable_reasons"].as_array().is_some_and(|reasons| !reasons.is_empty())
+                || settings["disable_reasons"].as_i64().is_some_and(|reasons| reasons != 0);
+            // Chrome stores install_time as microseconds since 1601
+            let installed_at = settings["install_time"]
+                .as_str()
+                .and_then(|time| time.parse::<i64>().ok())
+                .and_then(|micros| DateTime::<Utc>::from_timestamp_micros(micros - 11_644_473_600_000_000))
+                .map(|at| at.to_rfc3339());
+
+            let mut flags = Vec::new();
+            let overrides = &manifest["chrome_settings_overrides"];
+            if ["search_provider", "homepage", "startup_pages"].iter().any(|key| !overrides[key].is_null()) {
+                flags.push("search_override".to_string());
+            }
+            let update_url = manifest["update_url"].as_str().unwrap_or_default();
+            if !STORE_UPDATE_URLS.contains(&update_url) {
+                flags.push("not_from_store".to_string());
+            }
+            let permissions: Vec<String> = strings(&manifest["permissions"]).chain(strings(&manifest["host_permissions"])).collect();
+            extensions.push(Extension {
+                name: chromium_name(&version, &manifest).unwrap_or_else(|| id.clone()),
+                id,
+                version: manifest["version"].as_str().map(str::to_string),
+                browser: browser.to_string(),
+                profile: profile.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                enabled: (!settings.is_null()).then_some(!disabled),
+                permissions,
+                installed_at,
+                flags,
+                ..Default::default()
+            });
+        }
+    }
+    extensions
+}
+
+// A name of "__MSG_appName__" is looked up in the default locale's
+// messages.json, whose keys are case-insensitive
+fn chromium_name(version: &Path, manifest: &serde_json::Value) -> Option<String> {
+    let name = manifest["name"].as_str()?;
+    let Some(key) = name.strip_prefix("__MSG_").and_then(|key| key.strip_suffix("__")) else {
+        return Some(name.to_string());
+    };
+    let locale = manifest["default_locale"].as_str().unwrap_or("en");
+    let messages = read_json(&version.join("_locales").join(locale).join("messages.json"));
+    messages
+        .as_object()?
+        .iter()
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(key))
+        .and_then(|(_, message)| message["message"].as_str().map(str::to_string))
+}
+
+// Each profile's extensions.json lists its add-ons; the ones bundled with
+// Firefox live outside the profile and are skipped
+fn gecko(root: &Path) -> Vec<Extension> {
+    let mut extensions = Vec::new();
+    for profile in subdirectories(root) {
+        let addons = read_json(&profile.join("extensions.json"));
+        for addon in addons["addons"].as_array().into_iter().flatten() {
+            if addon["type"] != "extension" || addon["location"] != "app-profile" {
+                continue;
+            }
+            let Some(id) = addon["id"].as_str() else { continue };
+            let permissions = &addon["userPermissions"];
+            let mut flags = Vec::new();
+            // Add-ons signed by Mozilla have a positive signedState
+            if addon["signedState"].as_i64().is_some_and(|state| state <= 0) {
+                flags.push("not_from_store".to_string());
+            }
+            extensions.push(Extension {
+                id: id.to_string(),
+                name: addon["defaultLocale"]["name"].as_str().unwrap_or(id).to_string(),
+                version: addon["version"].as_str().map(str::to_string),
+                browser: "Firefox".to_string(),
+                profile: profile.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                enabled: addon["active"].as_bool(),
+                permissions: strings(&permissions["permissions"]).chain(strings(&permissions["origins"])).collect(),
+                installed_at: addon["installDate"]
+                    .as_i64()
+                    .and_then(DateTime::<Utc>::from_timestamp_millis)
+                    .map(|at| at.to_rfc3339()),
+                flags,
+                ..Default::default()
+            });
+        }
+    }
+    extensions
+}
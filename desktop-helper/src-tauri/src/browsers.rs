@@ -0,0 +1,230 @@
+use axum::Json;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+
+// GET /health/browsers: installed browsers, their versions and which one is
+// the default, so browser fixes fit the browser in use ("your Chrome is 14
+// versions behind"). macOS reads each app's Info.plist and Launch Services'
+// https handler; Windows the registered StartMenuInternet clients and the
+// https UserChoice; Linux runs each browser's --version and asks
+// xdg-settings.
+pub struct Known {
+    pub name: &'static str,
+    // "chromium", "gecko" or "webkit"
+    pub engine: &'static str,
+    pub mac_bundle_id: &'static str,
+    // A fragment of the Windows ProgId / client key ("ChromeHTML",
+    // "Google Chrome")
+    pub windows_ids: &'static [&'static str],
+    pub linux_commands: &'static [&'static str],
+}
+
+pub const KNOWN: &[Known] = &[
+    Known {
+        name: "Google Chrome",
+        engine: "chromium",
+        mac_bundle_id: "com.google.Chrome",
+        windows_ids: &["ChromeHTML", "Google Chrome"],
+        linux_commands: &["google-chrome", "google-chrome-stable"],
+    },
+    Known {
+        name: "Microsoft Edge",
+        engine: "chromium",
+        mac_bundle_id: "com.microsoft.edgemac",
+        windows_ids: &["MSEdgeHTM", "Microsoft Edge"],
+        linux_commands: &["microsoft-edge", "microsoft-edge-stable"],
+    },
+    Known {
+        name: "Firefox",
+        engine: "gecko",
+        mac_bundle_id: "org.mozilla.firefox",
+        windows_ids: &["FirefoxURL", "Firefox"],
+        linux_commands: &["firefox"],
+    },
+    Known {
+        name: "Safari",
+        engine: "webkit",
+        mac_bundle_id: "com.apple.Safari",
+        windows_ids: &[],
+        linux_commands: &[],
+    },
+    Known {
+        name: "Brave",
+        engine: "chromium",
+        mac_bundle_id: "com.brave.Browser",
+        windows_ids: &["BraveHTML", "Brave"],
+        linux_commands: &["brave-browser", "brave"],
+    },
+    Known {
+        name: "Arc",
+        engine: "chromium",
+        mac_bundle_id: "company.thebrowser.Browser",
+        windows_ids: &["Arc"],
+        linux_commands: &[],
+    },
+    Known {
+        name: "Opera",
+        engine: "chromium",
+        mac_bundle_id: "com.operasoftware.Opera",
+        windows_ids: &["OperaStable", "Opera"],
+        linux_commands: &["opera"],
+    },
+    Known {
+        name: "Vivaldi",
+        engine: "chromium",
+        mac_bundle_id: "com.vivaldi.Vivaldi",
+        windows_ids: &["VivaldiHTM", "Vivaldi"],
+        linux_commands: &["vivaldi", "vivaldi-stable"],
+    },
+    Known {
+        name: "Chromium",
+        engine: "chromium",
+        mac_bundle_id: "org.chromium.Chromium",
+        windows_ids: &["ChromiumHTM", "Chromium"],
+        linux_commands: &["chromium", "chromium-browser"],
+    },
+];
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Browser {
+    name: String,
+    engine: String,
+    version: Option<String>,
+    major_version: Option<u32>,
+    path: Option<String>,
+    default: bool,
+}
+
+// GET /health/browsers
+pub async fn browsers_handler() -> Json<serde_json::Value> {
+    let browsers = tauri::async_runtime::spawn_blocking(|| match std::env::consts::OS {
+        "macos" => macos(),
+        "windows" => windows(),
+        _ => linux(),
+    })
+    .await
+    .unwrap_or_default();
+    let default = browsers.iter().find(|browser| browser.default).map(|browser| browser.name.clone());
+    Json(serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "default": default,
+        "browsers": browsers,
+    }))
+}
+
+fn browser(known: &Known, version: Option<String>, path: Option<String>, default: bool) -> Browser {
+    let major_version = version.as_deref().and_then(|version| version.split('.').next()?.parse().ok());
+    Browser {
+        name: known.name.to_string(),
+        engine: known.engine.to_string(),
+        version,
+        major_version,
+        path,
+        default,
+    }
+}
+
+// mdfind finds each browser wherever it was installed; /Applications covers
+// machines with Spotlight off
+fn macos() -> Vec<Browser> {
+    let default = macos_default();
+    KNOWN
+        .iter()
+        .filter_map(|known| {
+            let query = format!("kMDItemCFBundleIdentifier == '{}'", known.mac_bundle_id);
+            let path = read_output("/usr/bin/mdfind", &[&query])
+                .and_then(|output| output.lines().find(|path| !path.contains(".app/")).map(str::to_string))
+                .or_else(|| {
+                    let path = format!("/Applications/{}.app", known.name);
+                    std::path::Path::new(&path).exists().then_some(path)
+                })?;
+            let info = format!("{}/Contents/Info.plist", path);
+            let version = read_output("/usr/bin/plutil", &["-extract", "CFBundleShortVersionString", "raw", "-o", "-", &info])
+                .map(|version| version.trim().to_string());
+            let is_default = default.as_deref().is_some_and(|id| id.eq_ignore_ascii_case(known.mac_bundle_id));
+            Some(browser(known, version, Some(path), is_default))
+        })
+        .collect()
+}
+
+// The https handler in Launch Services' preferences; Safari when none is set
+fn macos_default() -> Option<String> {
+    let home = dirs::home_dir()?;
+    let plist = home.join("Library/Preferences/com.apple.LaunchServices/com.apple.launchservices.secure.plist");
+    let json = read_output("/usr/bin/plutil", &["-convert", "json", "-o", "-", &plist.to_string_lossy()]);
+    let handlers: serde_json::Value = json.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default();
+    let handler = handlers["LSHandlers"]
+        .as_array()
+        .and_then(|handlers| handlers.iter().find(|handler| handler["LSHandlerURLScheme"] == "https"))
+        .and_then(|handler| handler["LSHandlerRoleAll"].as_str())
+        .map(str::to_string);
+    handler.or_else(|| Some("com.apple.Safari".to_string()))
+}
+
+// Each registered client's executable and ProductVersion, and the https
+// UserChoice ProgId
+fn windows() -> Vec<Browser> {
+    let script = r#"
+$ErrorActionPreference = 'SilentlyContinue'
+$default = (Get-ItemProperty 'HKCU:\Software\Microsoft\Windows\Shell\Associations\UrlAssociations\https\UserChoice').ProgId
+"default`t$default"
+foreach ($root in 'HKLM:\SOFTWARE\Clients\StartMenuInternet', 'HKCU:\SOFTWARE\Clients\StartMenuInternet') {
+  foreach ($client in Get-ChildItem $root) {
+    $command = (Get-ItemProperty "$($client.PSPath)\shell\open\command").'(default)'
+    $exe = if ($command -match '^"([^"]+)"') { $Matches[1] } else { ($command -split ' ')[0] }
+    $version = (Get-Item -LiteralPath $exe).VersionInfo.ProductVersion
+    "client`t$($client.PSChildName)`t$exe`t$version"
+  }
+}
+"#;
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    let output = read_output(&powershell.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", script]).unwrap_or_default();
+    let mut default = String::new();
+    let mut browsers: Vec<Browser> = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.trim_end().split('\t').collect();
+        match fields[..] {
+            ["default", prog_id] => default = prog_id.to_string(),
+            ["client", key, exe, version] => {
+                let Some(known) = KNOWN.iter().find(|known| known.windows_ids.iter().any(|id| key.contains(id))) else { continue };
+                if browsers.iter().any(|browser| browser.name == known.name) {
+                    continue;
+                }
+                let version = Some(version.to_string()).filter(|version| !version.is_empty());
+                browsers.push(browser(known, version, Some(exe.to_string()), false));
+            }
+            _ => {}
+        }
+    }
+    // "ChromeHTML", "MSEdgeHTM", "FirefoxURL-308046B0AF4A39CB"
+    if let Some(known) = KNOWN.iter().find(|known| known.windows_ids.iter().any(|id| default.starts_with(id))) {
+        if let Some(browser) = browsers.iter_mut().find(|browser| browser.name == known.name) {
+            browser.default = true;
+        }
+    }
+    browsers
+}
+
+// "Mozilla Firefox 128.0.3", "Google Chrome 126.0.6478.126"
+fn linux() -> Vec<Browser> {
+    let default = read_output("xdg-settings", &["get", "default-web-browser"]).unwrap_or_default();
+    let default = default.trim().trim_end_matches(".desktop").to_lowercase();
+    KNOWN
+        .iter()
+        .filter_map(|known| {
+            let (command, output) = known
+                .linux_commands
+                .iter()
+                .find_map(|command| Some((command, read_output(command, &["--version"])?)))?;
+            let version = output
+                .split_whitespace()
+                .find(|word| word.chars().next().is_some_and(|c| c.is_ascii_digit()) && word.contains('.'))
+                .map(|version| version.trim_end_matches(',').to_string());
+            let is_default = !default.is_empty() && known.linux_commands.iter().any(|command| default.contains(command));
+            Some(browser(known, version, Some(command.to_string()), is_default))
+        })
+        .collect()
+}
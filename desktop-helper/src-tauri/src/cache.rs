@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+// `?refresh=true` bypasses the cache for one request
+#[derive(Debug, Deserialize, Default)]
+pub struct CacheQuery {
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+// Short-lived results of expensive read-only diagnostics, so repeated tool
+// calls within one chat don't rerun them
+pub struct TtlCache {
+    ttl: Duration,
+    entries: Mutex<BTreeMap<String, (Instant, serde_json::Value)>>,
+}
+
+impl TtlCache {
+    pub const fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn insert(&self, key: &str, value: serde_json::Value) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (stored_at, _)| stored_at.elapsed() < self.ttl);
+        entries.insert(key.to_string(), (Instant::now(), value));
+    }
+
+    pub async fn get_or_compute<F, Fut>(&self, key: &str, refresh: bool, compute: F) -> serde_json::Value
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = serde_json::Value>,
+    {
+        if !refresh {
+            if let Some(value) = self.get(key) {
+                return value;
+            }
+        }
+        let value = compute().await;
+        self.insert(key, value.clone());
+        value
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::cmd::read_output;
+
+// Beyond this, token expiry and issue times can't be trusted locally
+pub const MAX_SKEW_SECS: i64 = 5 * 60;
+// Used when the OhFixIt server can't be reached
+const FALLBACK_TIME_URL: &str = "https://www.apple.com";
+
+// How far the local clock is ahead (positive) or behind (negative) a trusted
+// HTTPS Date header, or None when no reference could be reached
+pub fn skew() -> Option<Duration> {
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    [server_url.as_str(), FALLBACK_TIME_URL]
+        .iter()
+        .find_map(|url| remote_time(url))
+        .map(|remote| Utc::now() - remote)
+}
+
+// A distinct error for tokens rejected because the local clock is wrong, not
+// because the approval is actually stale
+pub fn skew_error(skew: Duration) -> String {
+    log::error!("Local clock is off by {}s", skew.num_seconds());
+    format!(
+        "Clock skew: this computer's clock is {} {} the correct time. Run '{}' to fix the time, then approve again.",
+        describe(skew),
+        if skew > Duration::zero() { "ahead of" } else { "behind" },
+        sync_action_id()
+    )
+}
+
+// The allowlisted action that resyncs the clock on this platform
+pub fn sync_action_id() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "sync-clock-macos",
+        "windows" => "sync-clock-windows",
+        _ => "sync-clock-linux",
+    }
+}
+
+// curl ships with macOS, Windows 10+ and every desktop Linux
+fn remote_time(url: &str) -> Option<DateTime<Utc>> {
+    let curl = if cfg!(windows) { "curl.exe" } else { "curl" };
+    let headers = read_output(curl, &["-sI", "--max-time", "5", url])?;
+    headers.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if !name.trim().eq_ignore_ascii_case("date") {
+            return None;
+        }
+        DateTime::parse_from_rfc2822(value.trim()).ok().map(|t| t.with_timezone(&Utc))
+    })
+}
+
+fn describe(skew: Duration) -> String {
+    let minutes = skew.num_minutes().abs();
+    match (minutes / (60 * 24), minutes / 60 % 24, minutes % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
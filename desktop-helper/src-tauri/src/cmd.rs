@@ -0,0 +1,98 @@
+use std::path::Path;
+use std::process::Command;
+
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+
+// A command whose output we parse. The C locale keeps messages, labels and
+// number formats in English on non-English systems; Windows tools ignore it,
+// which is why the Windows probes match image names or PowerShell values instead.
+pub fn diagnostic(program: &str) -> Command {
+    let mut command = Command::new(program);
+    command.env("LC_ALL", "C").env("LANG", "C");
+    command
+}
+
+// Run a read-only diagnostic command and return its stdout when it exits successfully
+pub fn read_output(program: &str, args: &[&str]) -> Option<String> {
+    match diagnostic(program).args(args).output() {
+        Ok(result) if result.status.success() => {
+            Some(String::from_utf8_lossy(&result.stdout).into_owned())
+        }
+        Ok(result) => {
+            log::debug!("{} exited with {}", program, result.status);
+            None
+        }
+        Err(e) => {
+            log::debug!("Failed to run {}: {}", program, e);
+            None
+        }
+    }
+}
+
+// Same as read_output, but trimmed and with empty output treated as missing
+pub fn read_trimmed(program: &str, args: &[&str]) -> Option<String> {
+    read_output(program, args)
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+// A property list (binary or XML) as JSON, dates as RFC 3339 strings and data
+// as base64. `plutil -convert json` refuses any plist holding a date or data.
+pub fn read_plist(path: &Path) -> Option<serde_json::Value> {
+    match plist::Value::from_file(path) {
+        Ok(value) => Some(plist_to_json(value)),
+        Err(e) => {
+            log::debug!("Failed to read {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn plist_to_json(value: plist::Value) -> serde_json::Value {
+    match value {
+        plist::Value::Dictionary(dict) => dict.into_iter().map(|(k, v)| (k, plist_to_json(v))).collect(),
+        plist::Value::Array(items) => items.into_iter().map(plist_to_json).collect(),
+        plist::Value::Boolean(flag) => flag.into(),
+        plist::Value::Integer(n) => n.as_signed().map(Into::into).or_else(|| n.as_unsigned().map(Into::into)).unwrap_or_default(),
+        plist::Value::Real(n) => n.into(),
+        plist::Value::String(s) => s.into(),
+        plist::Value::Date(date) => DateTime::<Utc>::from(std::time::SystemTime::from(date)).to_rfc3339().into(),
+        plist::Value::Data(bytes) => general_purpose::STANDARD.encode(bytes).into(),
+        plist::Value::Uid(uid) => uid.get().into(),
+        _ => serde_json::Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plist_with_dates_and_data() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<array>
+  <dict>
+    <key>date</key><date>2026-10-14T09:12:01Z</date>
+    <key>displayName</key><string>Safari</string>
+    <key>TrialExpired</key><true/>
+    <key>LicenseBlob</key><data>AQID</data>
+    <key>Launches</key><integer>42</integer>
+  </dict>
+</array>
+</plist>"#;
+        let path = std::env::temp_dir().join(format!("ohfixit-plist-{}.plist", uuid::Uuid::new_v4()));
+        std::fs::write(&path, xml).unwrap();
+        let value = read_plist(&path);
+        let _ = std::fs::remove_file(&path);
+
+        let entry = &value.unwrap()[0];
+        assert_eq!(entry["date"], "2026-10-14T09:12:01+00:00");
+        assert_eq!(entry["displayName"], "Safari");
+        assert_eq!(entry["TrialExpired"], true);
+        assert_eq!(entry["LicenseBlob"], "AQID");
+        assert_eq!(entry["Launches"], 42);
+    }
+}
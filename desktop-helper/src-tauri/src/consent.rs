@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{audit, ActionDefinition, Risk};
+
+// Irreversible and high-risk actions also need a click on this machine, even
+// with a valid approval token: a compromised web session can mint approvals
+// but can't press the button.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub fn required(action: &ActionDefinition) -> bool {
+    !action.reversible || action.risk == Risk::High
+}
+
+// Err when the user declined or didn't answer in time; nothing has run yet
+pub async fn confirm_locally(app: &AppHandle, action: &ActionDefinition, approval_id: &str) -> Result<(), String> {
+    let title = format!("Allow OhFixIt to run \"{}\"?", action.title);
+    let answer = ask(app, title, describe(action), "Run", "Don't run").await;
+    let confirmed = answer == Some(true);
+    audit::record(
+        if confirmed { "local_confirmation_granted" } else { "local_confirmation_declined" },
+        serde_json::json!({
+            "action_id": action.id,
+            "approval_id": approval_id,
+            "timed_out": answer.is_none(),
+        }),
+    );
+    if confirmed {
+        Ok(())
+    } else if answer.is_none() {
+        Err(format!("'{}' was not confirmed on this computer within {}s", action.id, CONFIRM_TIMEOUT.as_secs()))
+    } else {
+        Err(format!("'{}' was declined on this computer", action.id))
+    }
+}
+
+// Native warning dialog; None when nobody answered within CONFIRM_TIMEOUT
+pub async fn ask(app: &AppHandle, title: String, message: String, ok: &str, cancel: &str) -> Option<bool> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(ok.to_string(), cancel.to_string()))
+        .show(move |confirmed| {
+            let _ = tx.send(confirmed);
+        });
+    tokio::time::timeout(CONFIRM_TIMEOUT, rx).await.ok().map(|answer| answer.unwrap_or(false))
+}
+
+// What will run, exactly, and whether it can be undone
+fn describe(action: &ActionDefinition) -> String {
+    let commands = match &action.plugin {
+        Some(hash) => format!("Organization plugin (module {})", &hash[..hash.len().min(12)]),
+        None => action.commands.join("\n"),
+    };
+    let undo = if action.has_rollback() {
+        "A rollback point is saved first, so this can be undone."
+    } else {
+        "This can't be undone."
+    };
+    let elevated = if action.elevated { "\nIt runs with administrator rights." } else { "" };
+    format!("These commands will run:\n\n{}\n\n{}{}", commands, undo, elevated)
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use sysinfo::{CpuRefreshKind, System};
+
+use crate::health::CheckResult;
+
+// The check behind GET /health/cpu: what the processor is and how busy it is
+// (overall, per core and as 1/5/15-minute load averages), for "is this
+// machine underpowered or just overloaded?". Windows has no load average.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+// Busier than this over the sample is a warning
+const BUSY_PERCENT: f32 = 90.0;
+// More runnable work than this per logical core over five minutes is a warning
+const OVERLOADED_PER_CORE: f64 = 1.5;
+
+pub fn check() -> CheckResult {
+    let mut system = System::new();
+    let refresh = CpuRefreshKind::nothing().with_cpu_usage().with_frequency();
+    system.refresh_cpu_specifics(refresh);
+    std::thread::sleep(SAMPLE_INTERVAL.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL));
+    system.refresh_cpu_specifics(refresh);
+
+    let cpus = system.cpus();
+    if cpus.is_empty() {
+        return CheckResult::new("unknown", "Couldn't read the processor");
+    }
+    let brand = cpus[0].brand().trim().to_string();
+    let logical = cpus.len();
+    let physical = System::physical_core_count();
+    let frequency_mhz = cpus.iter().map(|cpu| cpu.frequency()).max().filter(|mhz| *mhz > 0);
+    let usage = system.global_cpu_usage();
+    let per_core: Vec<f64> = cpus.iter().map(|cpu| (cpu.cpu_usage() as f64 * 10.0).round() / 10.0).collect();
+    let load = (std::env::consts::OS != "windows").then(System::load_average);
+    let round = |value: f64| (value * 100.0).round() / 100.0;
+
+    let mut detail = format!(
+        "{}, {} cores ({} threads), {:.0}% busy",
+        if brand.is_empty() { "Unknown CPU" } else { &brand },
+        physical.map_or("?".to_string(), |cores| cores.to_string()),
+        logical,
+        usage
+    );
+    if let Some(load) = &load {
+        detail.push_str(&format!(", load {:.2} / {:.2} / {:.2}", load.one, load.five, load.fifteen));
+    }
+    let overloaded = load.as_ref().is_some_and(|load| load.five / logical as f64 >= OVERLOADED_PER_CORE);
+    let status = if usage >= BUSY_PERCENT || overloaded { "warning" } else { "ok" };
+    CheckResult::new(status, detail).with_data(serde_json::json!({
+        "brand": brand,
+        "physical_cores": physical,
+        "logical_cores": logical,
+        "frequency_mhz": frequency_mhz,
+        "usage_percent": (usage as f64 * 10.0).round() / 10.0,
+        "per_core_percent": per_core,
+        "load_average": load.as_ref().map(|load| serde_json::json!({
+            "one": round(load.one),
+            "five": round(load.five),
+            "fifteen": round(load.fifteen),
+        })),
+        "load_per_core": load.as_ref().map(|load| round(load.one / logical as f64)),
+        "interval_ms": SAMPLE_INTERVAL.as_millis() as u64,
+    }))
+}
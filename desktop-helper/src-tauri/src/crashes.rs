@@ -0,0 +1,336 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::sync::Mutex;
+
+use crate::{audit, consent, secrets};
+
+// GET /health/crashes?app=&limit=&include_reports=true: recent crash and hang
+// reports with their headers parsed (app, version, exception, when), so "it
+// keeps crashing" comes with evidence. macOS reads DiagnosticReports, Windows
+// Error Reporting's archive and queue, Linux apport's /var/crash. With
+// include_reports each report also comes back as a `crash_report` artifact,
+// screened for secrets like any uploaded output. Reports show what the user
+// runs and may quote their documents, so the user agrees in a dialog on this
+// machine first; the answer holds for GRANT_TTL.
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 100;
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const MAX_REPORT_BYTES: usize = 256 * 1024;
+const GRANT_TTL: Duration = Duration::from_secs(30 * 60);
+const ARTIFACT_TYPE: &str = "crash_report";
+
+// When the user last allowed reading crash reports; held while the dialog is
+// up, so concurrent requests share one prompt
+static GRANTED: Mutex<Option<Instant>> = Mutex::const_new(None);
+
+#[derive(Debug, Deserialize)]
+pub struct CrashQuery {
+    app: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    include_reports: bool,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct CrashReport {
+    app: String,
+    app_version: Option<String>,
+    // "crash", "hang" or "diagnostic"
+    kind: String,
+    // "EXC_BAD_ACCESS (SIGSEGV)", "c0000005", "SIGSEGV"
+    exception_type: Option<String>,
+    timestamp: Option<String>,
+    path: String,
+}
+
+// GET /health/crashes
+pub async fn crashes_handler(
+    State(app): State<AppHandle>,
+    Query(query): Query<CrashQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    allowed(&app).await?;
+    let name = query.app.as_deref().map(str::to_lowercase).filter(|name| !name.is_empty());
+    let include_reports = query.include_reports;
+    let (reports, artifacts) = tauri::async_runtime::spawn_blocking(move || {
+        let reports = collect(name.as_deref(), limit);
+        let artifacts = if include_reports { artifacts(&reports) } else { Vec::new() };
+        (reports, artifacts)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Crash report scan failed: {}", e)))?;
+    let mut body = serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "window_days": MAX_AGE.as_secs() / 86_400,
+        "count": reports.len(),
+        "reports": reports,
+    });
+    if include_reports {
+        body["artifacts"] = serde_json::json!(artifacts);
+    }
+    Ok(Json(body))
+}
+
+// Ok once the user has allowed reading crash reports (see GRANT_TTL)
+async fn allowed(app: &AppHandle) -> Result<(), (StatusCode, String)> {
+    let mut granted = GRANTED.lock().await;
+    if granted.is_some_and(|at| at.elapsed() < GRANT_TTL) {
+        return Ok(());
+    }
+    let message = format!(
+        "OhFixIt wants to read the crash reports on this computer to see which apps have been crashing and why.\n\nThis stays allowed for {} minutes.",
+        GRANT_TTL.as_secs() / 60
+    );
+    let answer = consent::ask(app, "Allow OhFixIt to read crash reports?".to_string(), message, "Allow", "Don't allow").await;
+    audit::record(
+        if answer == Some(true) { "crash_reports_granted" } else { "crash_reports_declined" },
+        serde_json::json!({ "timed_out": answer.is_none() }),
+    );
+    if answer != Some(true) {
+        return Err((StatusCode::FORBIDDEN, "Reading crash reports was not allowed on this computer".to_string()));
+    }
+    *granted = Some(Instant::now());
+    Ok(())
+}
+
+// The newest reports first, parsed until `limit` of them match `name`
+fn collect(name: Option<&str>, limit: usize) -> Vec<CrashReport> {
+    let mut files = candidates();
+    files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    files
+        .into_iter()
+        .filter_map(|(path, modified)| {
+            let mut report = parse(&path)?;
+            report.timestamp = report.timestamp.or_else(|| Some(DateTime::<Utc>::from(modified).to_rfc3339()));
+            Some(report)
+        })
+        .filter(|report| name.map_or(true, |name| report.app.to_lowercase().contains(name)))
+        .take(limit)
+        .collect()
+}
+
+// Report files changed within MAX_AGE, with when they changed
+fn candidates() -> Vec<(PathBuf, SystemTime)> {
+    let home = dirs::home_dir().unwrap_or_default();
+    let directories: Vec<PathBuf> = match std::env::consts::OS {
+        "macos" => vec![home.join("Library/Logs/DiagnosticReports"), "/Library/Logs/DiagnosticReports".into()],
+        "windows" => {
+            let local = dirs::data_local_dir().unwrap_or_default().join(r"Microsoft\Windows\WER");
+            let program_data = PathBuf::from(std::env::var("ProgramData").unwrap_or_else(|_| r"C:\ProgramData".to_string()))
+                .join(r"Microsoft\Windows\WER");
+            [local, program_data]
+                .iter()
+                .flat_map(|wer| [wer.join("ReportArchive"), wer.join("ReportQueue")])
+                .flat_map(|directory| std::fs::read_dir(directory).into_iter().flatten().flatten().map(|entry| entry.path()))
+                .collect()
+        }
+        _ => vec!["/var/crash".into()],
+    };
+    let now = SystemTime::now();
+    directories
+        .iter()
+        .flat_map(|directory| std::fs::read_dir(directory).into_iter().flatten().flatten())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            match std::env::consts::OS {
+                "macos" => [".ips", ".crash", ".hang", ".spin", ".diag"].iter().any(|ext| name.ends_with(ext)) && !name.starts_with("panic"),
+                "windows" => name == "Report.wer",
+                _ => name.ends_with(".crash"),
+            }
+        })
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+            now.duration_since(modified).is_ok_and(|age| age < MAX_AGE).then_some((path, modified))
+        })
+        .collect()
+}
+
+// Report.wer is UTF-16; everything else is UTF-8
+fn read_text(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    match bytes.strip_prefix(&[0xFF, 0xFE]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+            Some(String::from_utf16_lossy(&units))
+        }
+        None => Some(String::from_utf8_lossy(&bytes).into_owned()),
+    }
+}
+
+fn parse(path: &Path) -> Option<CrashReport> {
+    let text = read_text(path)?;
+    let extension = path.extension().unwrap_or_default().to_string_lossy().into_owned();
+    let mut report = match extension.as_str() {
+        "ips" => parse_ips(&text)?,
+        "wer" => parse_wer(&text)?,
+        _ if std::env::consts::OS == "linux" => parse_apport(&text)?,
+        _ => parse_crash(&text, &extension)?,
+    };
+    report.path = path.to_string_lossy().into_owned();
+    Some(report)
+}
+
+// An .ips file is a one-line JSON header followed by a JSON body; the body's
+// "exception" is only there for crashes
+fn parse_ips(text: &str) -> Option<CrashReport> {
+    let (header, body) = text.split_once('\n').unwrap_or((text, ""));
+    let header: serde_json::Value = serde_json::from_str(header).ok()?;
+    let body: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    let exception = &body["exception"];
+    let exception_type = exception["type"].as_str().map(|kind| match exception["signal"].as_str() {
+        Some(signal) => format!("{} ({})", kind, signal),
+        None => kind.to_string(),
+    });
+    let kind = match header["bug_type"].as_str() {
+        _ if exception_type.is_some() => "crash",
+        Some("298") | Some("288") => "hang",
+        _ => "diagnostic",
+    };
+    Some(CrashReport {
+        app: header["app_name"].as_str().or_else(|| header["name"].as_str()).or_else(|| body["procName"].as_str())?.to_string(),
+        app_version: header["app_version"].as_str().filter(|version| !version.is_empty()).map(str::to_string),
+        kind: kind.to_string(),
+        exception_type,
+        // "2024-05-01 10:22:33.00 -0700"
+        timestamp: header["timestamp"]
+            .as_str()
+            .and_then(|at| DateTime::parse_from_str(at, "%Y-%m-%d %H:%M:%S%.f %z").ok())
+            .map(|at| at.with_timezone(&Utc).to_rfc3339()),
+        ..Default::default()
+    })
+}
+
+// "Key:   value" lines, as in older .crash, .hang and .spin reports and apport
+fn field<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    text.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+// "Process:  Safari [1234]", "Version:  17.4 (19618.1.15)", "Exception Type:
+// EXC_BAD_ACCESS (SIGSEGV)", "Date/Time:  2024-05-01 10:22:33.123 -0700"
+fn parse_crash(text: &str, extension: &str) -> Option<CrashReport> {
+    let process = field(text, "Process").or_else(|| field(text, "Command"))?;
+    let exception_type = field(text, "Exception Type").map(str::to_string);
+    let kind = match extension {
+        "crash" => "crash",
+        "hang" | "spin" => "hang",
+        _ => "diagnostic",
+    };
+    Some(CrashReport {
+        app: process.split(" [").next().unwrap_or(process).to_string(),
+        app_version: field(text, "Version").map(|version| version.split(" (").next().unwrap_or(version).to_string()),
+        kind: kind.to_string(),
+        exception_type,
+        timestamp: field(text, "Date/Time")
+            .and_then(|at| DateTime::parse_from_str(at, "%Y-%m-%d %H:%M:%S%.f %z").ok())
+            .map(|at| at.with_timezone(&Utc).to_rfc3339()),
+        ..Default::default()
+    })
+}
+
+// Report.wer is "Key=Value" lines; the signature is numbered pairs
+// ("Sig[6].Name=Exception Code", "Sig[6].Value=c0000005") and EventTime a
+// FILETIME
+fn parse_wer(text: &str) -> Option<CrashReport> {
+    let value = |key: &str| {
+        text.lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let signature = |name: &str| {
+        let key = text.lines().find_map(|line| {
+            let (key, value) = line.trim().split_once('=')?;
+            (value.trim() == name).then(|| key.strip_suffix(".Name"))?
+        })?;
+        value(&format!("{}.Value", key))
+    };
+    let event_type = value("EventType").unwrap_or_default();
+    let kind = match event_type {
+        "APPCRASH" | "BEX" | "BEX64" | "MoAppCrash" => "crash",
+        "AppHangB1" | "AppHangXProcB1" | "MoAppHang" => "hang",
+        _ => "diagnostic",
+    };
+    Some(CrashReport {
+        app: value("AppName").or_else(|| signature("Application Name"))?.to_string(),
+        app_version: signature("Application Version").map(str::to_string),
+        kind: kind.to_string(),
+        exception_type: signature("Exception Code").map(str::to_string).or_else(|| Some(event_type.to_string()).filter(|e| !e.is_empty())),
+        timestamp: value("EventTime")
+            .and_then(|time| time.parse::<i64>().ok())
+            .and_then(|ticks| DateTime::<Utc>::from_timestamp_micros(ticks / 10 - 11_644_473_600_000_000))
+            .map(|at| at.to_rfc3339()),
+        ..Default::default()
+    })
+}
+
+// "ExecutablePath: /usr/bin/gnome-shell", "Signal: 11", "Package: gnome-shell
+// 46.0-0ubuntu1"
+fn parse_apport(text: &str) -> Option<CrashReport> {
+    let executable = field(text, "ExecutablePath")?;
+    let kind = match field(text, "ProblemType") {
+        Some("Crash") => "crash",
+        Some("Hang") => "hang",
+        _ => "diagnostic",
+    };
+    Some(CrashReport {
+        app: executable.rsplit('/').next().unwrap_or(executable).to_string(),
+        app_version: field(text, "Package").and_then(|package| package.split_whitespace().nth(1)).map(str::to_string),
+        kind: kind.to_string(),
+        exception_type: field(text, "Signal").map(|signal| format!("signal {}", signal)),
+        ..Default::default()
+    })
+}
+
+// Each report's text, screened and capped at MAX_REPORT_BYTES; apport's
+// base64 core dump is cut off where it starts
+fn artifacts(reports: &[CrashReport]) -> Vec<serde_json::Value> {
+    let mut screen = secrets::Screen::new(ARTIFACT_TYPE);
+    let artifacts = reports
+        .iter()
+        .filter_map(|report| {
+            let mut text = read_text(Path::new(&report.path))?;
+            if let Some(core) = text.find("\nCoreDump:") {
+                text.truncate(core);
+            }
+            let truncated = text.len() > MAX_REPORT_BYTES;
+            if truncated {
+                let mut end = MAX_REPORT_BYTES;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                text.truncate(end);
+            }
+            let (data, redactions) = match screen.text(ARTIFACT_TYPE, &text) {
+                Some(screened) => screened,
+                None => (secrets::blocked_notice(ARTIFACT_TYPE), 0),
+            };
+            let digest = ring::digest::digest(&ring::digest::SHA256, data.as_bytes());
+            let hash: String = digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+            Some(serde_json::json!({
+                "artifact_type": ARTIFACT_TYPE,
+                "uri": null,
+                "hash": hash,
+                "data": data,
+                "metadata": {
+                    "path": report.path,
+                    "app": report.app,
+                    "redactions": redactions,
+                    "truncated": truncated,
+                },
+            }))
+        })
+        .collect();
+    screen.finish();
+    artifacts
+}
@@ -0,0 +1,177 @@
+use ring::rand::{SecureRandom, SystemRandom};
+
+// Secrets live in the OS credential store (macOS Keychain, Windows Credential
+// Manager, Secret Service on Linux) rather than in env vars or files, which
+// every process the user runs can read.
+const SERVICE: &str = "com.ohfixit.desktophelper";
+
+#[cfg(target_os = "macos")]
+use keychain as store;
+#[cfg(not(any(target_os = "macos", windows)))]
+use secret_service as store;
+#[cfg(windows)]
+use wincred as store;
+
+// The stored secret, None when there is none
+pub fn get(name: &str) -> Result<Option<String>, String> {
+    store::get(name).map_err(|e| format!("Failed to read {} from the credential store: {}", name, e))
+}
+
+pub fn set(name: &str, value: &str) -> Result<(), String> {
+    store::set(name, value).map_err(|e| format!("Failed to save {} to the credential store: {}", name, e))
+}
+
+// A secret deployments used to pass in `env_var`. A value found there is moved
+// into the store once; after that only dev builds read the env var, as a
+// fallback when the store is unavailable.
+pub fn secret(name: &str, env_var: &str) -> Option<String> {
+    match get(name) {
+        Ok(Some(value)) => return Some(value),
+        Ok(None) => {
+            if let Ok(value) = std::env::var(env_var) {
+                match set(name, &value) {
+                    Ok(()) => {
+                        log::info!("Moved {} into the credential store; it can be removed from the environment", env_var);
+                        return Some(value);
+                    }
+                    Err(e) => log::error!("{}", e),
+                }
+            }
+        }
+        Err(e) => log::error!("{}", e),
+    }
+    if cfg!(debug_assertions) {
+        std::env::var(env_var).ok()
+    } else {
+        None
+    }
+}
+
+// secret(), or on first run a random 256-bit one saved to the store. None
+// when the store can't keep it: callers refuse to work rather than fall back
+// to a value anyone could know.
+pub fn generated_secret(name: &str, env_var: &str) -> Option<String> {
+    if let Some(value) = secret(name, env_var) {
+        return Some(value);
+    }
+    let mut bytes = [0u8; 32];
+    SystemRandom::new().fill(&mut bytes).ok()?;
+    let value: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    match set(name, &value) {
+        Ok(()) => {
+            log::info!("Generated {} and saved it to the credential store", name);
+            Some(value)
+        }
+        Err(e) => {
+            log::error!("{}", e);
+            None
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod keychain {
+    use security_framework::passwords::{get_generic_password, set_generic_password};
+
+    const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+    pub fn get(name: &str) -> Result<Option<String>, String> {
+        match get_generic_password(super::SERVICE, name) {
+            Ok(bytes) => String::from_utf8(bytes).map(Some).map_err(|e| e.to_string()),
+            Err(e) if e.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<(), String> {
+        set_generic_password(super::SERVICE, name, value.as_bytes()).map_err(|e| e.to_string())
+    }
+}
+
+// libsecret's CLI. The secret goes over stdin, never on a command line.
+#[cfg(not(any(target_os = "macos", windows)))]
+mod secret_service {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    const SECRET_TOOL: &str = "/usr/bin/secret-tool";
+
+    pub fn get(name: &str) -> Result<Option<String>, String> {
+        let output = Command::new(SECRET_TOOL)
+            .args(["lookup", "service", super::SERVICE, "account", name])
+            .output()
+            .map_err(|e| format!("{}: {}", SECRET_TOOL, e))?;
+        // Exits 1 with no output when there's no such item
+        if !output.status.success() && !output.stderr.is_empty() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        let value = String::from_utf8_lossy(&output.stdout).to_string();
+        Ok((!value.is_empty()).then_some(value))
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<(), String> {
+        let label = format!("OhFixIt Desktop Helper ({})", name);
+        let mut child = Command::new(SECRET_TOOL)
+            .args(["store", "--label", &label, "service", super::SERVICE, "account", name])
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("{}: {}", SECRET_TOOL, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(value.as_bytes()).map_err(|e| e.to_string())?;
+        }
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+}
+
+#[cfg(windows)]
+mod wincred {
+    use windows_sys::Win32::Foundation::ERROR_NOT_FOUND;
+    use windows_sys::Win32::Security::Credentials::{
+        CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    fn target(name: &str) -> Vec<u16> {
+        format!("{}/{}", super::SERVICE, name).encode_utf16().chain(Some(0)).collect()
+    }
+
+    pub fn get(name: &str) -> Result<Option<String>, String> {
+        let target = target(name);
+        unsafe {
+            let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+            if CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) == 0 {
+                let error = std::io::Error::last_os_error();
+                return match error.raw_os_error() {
+                    Some(code) if code == ERROR_NOT_FOUND as i32 => Ok(None),
+                    _ => Err(error.to_string()),
+                };
+            }
+            let blob = std::slice::from_raw_parts((*credential).CredentialBlob, (*credential).CredentialBlobSize as usize);
+            let value = String::from_utf8(blob.to_vec()).map_err(|e| e.to_string());
+            CredFree(credential as *const std::ffi::c_void);
+            value.map(Some)
+        }
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<(), String> {
+        let mut target = target(name);
+        let mut blob = value.as_bytes().to_vec();
+        unsafe {
+            let mut credential: CREDENTIALW = std::mem::zeroed();
+            credential.Type = CRED_TYPE_GENERIC;
+            credential.TargetName = target.as_mut_ptr();
+            credential.CredentialBlobSize = blob.len() as u32;
+            credential.CredentialBlob = blob.as_mut_ptr();
+            credential.Persist = CRED_PERSIST_LOCAL_MACHINE;
+            if CredWriteW(&credential, 0) == 0 {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+        }
+        Ok(())
+    }
+}
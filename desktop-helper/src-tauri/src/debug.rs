@@ -0,0 +1,97 @@
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use log::LevelFilter;
+use tauri::{AppHandle, Manager};
+
+use crate::{actions, fingerprint, history, redact, server, startup, storage, AppState};
+
+const BUNDLE_LOG_LINES: usize = 500;
+const BUNDLE_EXECUTIONS: usize = 20;
+
+// Takes effect immediately for every module; resets to the default on restart
+#[tauri::command]
+pub async fn set_log_level(level: String) -> Result<String, String> {
+    let filter = LevelFilter::from_str(&level)
+        .map_err(|_| format!("Unknown log level '{}' (use off, error, warn, info, debug or trace)", level))?;
+    log::set_max_level(filter);
+    log::warn!("Log level set to {}", filter);
+    Ok(filter.to_string().to_lowercase())
+}
+
+// Collect what support needs to diagnose a helper failure into one JSON file
+// in the data dir and return its path. Logs and config pass through redaction.
+#[tauri::command]
+pub async fn create_debug_bundle(app: AppHandle) -> Result<String, String> {
+    startup::catalog_ready().await;
+    let manifest_version = {
+        let state = app.state::<Mutex<AppState>>();
+        let state = state.lock().unwrap();
+        state.manifest_version
+    };
+
+    let logs = app
+        .path()
+        .app_log_dir()
+        .map(|dir| recent_log_lines(&dir, BUNDLE_LOG_LINES))
+        .unwrap_or_default();
+    let (logs, redactions) = redact::redact(&logs.join("\n"));
+
+    let bundle = serde_json::json!({
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "version": env!("CARGO_PKG_VERSION"),
+        "log_level": log::max_level().to_string().to_lowercase(),
+        "environment": fingerprint::current(),
+        "config": config(),
+        "manifest_version": manifest_version,
+        "capabilities": server::CAPABILITIES,
+        "startup": startup::spans(),
+        "actions": actions::available(&app),
+        "recent_executions": history::recent(BUNDLE_EXECUTIONS),
+        "logs": logs.lines().collect::<Vec<_>>(),
+        "log_redactions": redactions,
+    });
+
+    let name = format!("debug-bundle-{}.json", chrono::Utc::now().format("%Y%m%dT%H%M%SZ"));
+    storage::save_json(&name, &bundle)?;
+    let path = storage::data_dir().join(&name);
+    log::info!("Wrote debug bundle to {}", path.display());
+    Ok(path.to_string_lossy().into_owned())
+}
+
+// OHFIXIT_* settings in effect, with anything secret-looking masked
+fn config() -> serde_json::Value {
+    let settings: serde_json::Map<String, serde_json::Value> = std::env::vars()
+        .filter(|(name, _)| name.starts_with("OHFIXIT_"))
+        .map(|(name, value)| {
+            let secret = ["SECRET", "TOKEN", "KEY", "PASSWORD"].iter().any(|s| name.contains(s));
+            let value = if secret { "[REDACTED]".to_string() } else { redact::redact(&value).0 };
+            (name, value.into())
+        })
+        .collect();
+    settings.into()
+}
+
+// The newest lines across the plugin's log files (the current one plus rotated ones)
+fn recent_log_lines(dir: &std::path::Path, limit: usize) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return vec![] };
+    let mut files: Vec<_> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "log"))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    files.sort();
+
+    let mut lines: Vec<String> = Vec::new();
+    for (_, path) in files.iter().rev() {
+        let Ok(content) = std::fs::read_to_string(path) else { continue };
+        let mut file_lines: Vec<String> = content.lines().map(str::to_string).collect();
+        file_lines.append(&mut lines);
+        lines = file_lines;
+        if lines.len() >= limit {
+            break;
+        }
+    }
+    let skip = lines.len().saturating_sub(limit);
+    lines.split_off(skip)
+}
@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::Deserialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{audit, consent, pairing, AppState};
+
+// ohfixit:// links reach the helper as launch arguments, directly or forwarded
+// by a second launch (instance.rs). Any page can open such a link, so before
+// it does anything the server has to confirm it issued the nonce for this
+// device, the user has to agree here, and only then is it redeemed (once).
+pub const SCHEME: &str = "ohfixit://";
+const MAX_URL_LEN: usize = 512;
+const REDEEM_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Link {
+    // ohfixit://open: only brings the window forward
+    Open,
+    // ohfixit://pair?nonce=...: pair with the account that issued the link
+    Pair { nonce: String },
+    // ohfixit://action/<id>?nonce=...: run an action the user approved
+    Action { action_id: String, nonce: String },
+}
+
+impl Link {
+    fn kind(&self) -> &'static str {
+        match self {
+            Link::Open => "open",
+            Link::Pair { .. } => "pair",
+            Link::Action { .. } => "action",
+        }
+    }
+}
+
+// Anything not exactly in one of the shapes above is refused: no escapes,
+// fragments, hosts or extra parameters, so what's checked is what runs
+pub fn parse(url: &str) -> Result<Link, String> {
+    if url.len() > MAX_URL_LEN {
+        return Err(format!("Deep link is longer than {} characters", MAX_URL_LEN));
+    }
+    let rest = url.strip_prefix(SCHEME).ok_or_else(|| "Not an ohfixit:// link".to_string())?;
+    if let Some(c) = rest.chars().find(|c| !(c.is_ascii_alphanumeric() || "/?=&-_".contains(*c))) {
+        return Err(format!("Unexpected character {:?} in deep link", c));
+    }
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let mut params = BTreeMap::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').ok_or_else(|| format!("Malformed query parameter '{}'", pair))?;
+        if params.insert(key, value).is_some() {
+            return Err(format!("Duplicate query parameter '{}'", key));
+        }
+    }
+    let nonce = || match params.get("nonce") {
+        Some(nonce) if (16..=64).contains(&nonce.len()) && !nonce.contains(['/', '?', '=']) => Ok(nonce.to_string()),
+        Some(_) => Err("Malformed nonce".to_string()),
+        None => Err("Deep link has no nonce".to_string()),
+    };
+
+    let link = match path.split('/').collect::<Vec<_>>().as_slice() {
+        ["open"] => Link::Open,
+        ["pair"] => Link::Pair { nonce: nonce()? },
+        ["action", id] if valid_action_id(id) => Link::Action { action_id: id.to_string(), nonce: nonce()? },
+        _ => return Err(format!("Unknown deep link '{}'", path)),
+    };
+    let allowed: &[&str] = if link == Link::Open { &[] } else { &["nonce"] };
+    if let Some(key) = params.keys().find(|key| !allowed.contains(key)) {
+        return Err(format!("Unexpected query parameter '{}'", key));
+    }
+    Ok(link)
+}
+
+fn valid_action_id(id: &str) -> bool {
+    (1..=64).contains(&id.len()) && id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+pub fn spawn_handle(app: AppHandle, url: String) {
+    tauri::async_runtime::spawn(async move {
+        let outcome = handle(&app, &url).await;
+        if let Err(e) = &outcome {
+            log::error!("Deep link refused: {}", e);
+            audit::record("deep_link_refused", serde_json::json!({ "reason": e }));
+        }
+        let _ = app.emit("deep-link-handled", serde_json::json!({
+            "url": url,
+            "ok": outcome.is_ok(),
+            "message": outcome.unwrap_or_else(|e| e),
+        }));
+    });
+}
+
+async fn handle(app: &AppHandle, url: &str) -> Result<String, String> {
+    let link = parse(url)?;
+    audit::record("deep_link_received", serde_json::json!({ "kind": link.kind() }));
+    // Activation already brought the window forward
+    if link == Link::Open {
+        return Ok("Opened".to_string());
+    }
+
+    let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+    redeem(&client, &link, false).await?;
+    let (title, message) = match &link {
+        Link::Pair { .. } => (
+            "Pair OhFixIt with your account?".to_string(),
+            "A link asked to pair this computer with the OhFixIt account that created it. Only continue if you just clicked a pairing link yourself.".to_string(),
+        ),
+        Link::Action { action_id, .. } => (
+            format!("Start \"{}\" from a link?", action_id),
+            format!("A link asked OhFixIt to run the approved action '{}' on this computer.", action_id),
+        ),
+        Link::Open => unreachable!(),
+    };
+    if consent::ask(app, title, message, "Continue", "Cancel").await != Some(true) {
+        return Err(format!("{} link was not confirmed on this computer", link.kind()));
+    }
+
+    let redeemed = redeem(&client, &link, true).await?;
+    audit::record("deep_link_redeemed", serde_json::json!({ "kind": link.kind() }));
+    match link {
+        Link::Pair { .. } => pairing::register(&client).await,
+        Link::Action { action_id, .. } => {
+            let token = redeemed.helper_token.ok_or_else(|| "Server returned no approval token".to_string())?;
+            let result = crate::run_action(app, &action_id, &token, false).await?;
+            Ok(result.message)
+        }
+        Link::Open => unreachable!(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Redemption {
+    #[serde(rename = "helperToken")]
+    helper_token: Option<String>,
+}
+
+// Check (consume = false) or redeem the link's nonce with the server, signed
+// with the device key so a nonce only works for the device it reached
+async fn redeem(client: &Client, link: &Link, consume: bool) -> Result<Redemption, String> {
+    let (nonce, action_id) = match link {
+        Link::Pair { nonce } => (nonce, None),
+        Link::Action { action_id, nonce } => (nonce, Some(action_id)),
+        Link::Open => return Err("Open links carry no nonce".to_string()),
+    };
+    let device_id = pairing::status().device_id;
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let signature = pairing::sign(format!("{}.{}", device_id, nonce).as_bytes())?;
+
+    let response = client
+        .post(format!("{}/api/automation/helper/deep-link/redeem", server_url))
+        .timeout(REDEEM_TIMEOUT)
+        .json(&serde_json::json!({
+            "deviceId": device_id,
+            "nonce": nonce,
+            "kind": link.kind(),
+            "actionId": action_id,
+            "signature": signature,
+            "consume": consume,
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to verify deep link: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Server did not accept the deep link ({})", response.status()));
+    }
+    response.json().await.map_err(|e| format!("Malformed deep link response: {}", e))
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+use sysinfo::Disks;
+
+use crate::health::CheckResult;
+use crate::space;
+
+// The check behind GET /health/disks: free space on every mounted volume as
+// well as in total, because a nearly full boot volume is what breaks updates
+// and apps even when an external drive has room to spare. The status follows
+// the boot volume; other fixed disks only raise a warning.
+const WARNING_USED_PERCENT: f64 = 85.0;
+const CRITICAL_USED_PERCENT: f64 = 95.0;
+
+// Pseudo and read-only image filesystems that would only add noise
+const IGNORED_FILESYSTEMS: &[&str] = &["tmpfs", "devtmpfs", "overlay", "squashfs", "efivarfs", "autofs", "devfs", "nullfs"];
+
+#[derive(Debug, Serialize)]
+pub struct Volume {
+    mount_point: String,
+    fs_type: String,
+    total_bytes: u64,
+    available_bytes: u64,
+    used_percent: f64,
+    removable: bool,
+    boot: bool,
+}
+
+pub fn check() -> CheckResult {
+    let disks = Disks::new_with_refreshed_list();
+    let boot_mount = boot_mount();
+    let mut volumes: Vec<Volume> = disks
+        .list()
+        .iter()
+        .filter(|disk| disk.total_space() > 0)
+        .filter(|disk| !IGNORED_FILESYSTEMS.contains(&disk.file_system().to_string_lossy().as_ref()))
+        // APFS lists the sealed system volume's siblings (Data, Preboot, VM…),
+        // which share its container and free space
+        .filter(|disk| !disk.mount_point().starts_with("/System/Volumes"))
+        .map(|disk| {
+            let mount_point = disk.mount_point().to_string_lossy().into_owned();
+            let (total, available) = (disk.total_space(), disk.available_space());
+            Volume {
+                boot: mount_point.eq_ignore_ascii_case(&boot_mount),
+                mount_point,
+                fs_type: disk.file_system().to_string_lossy().into_owned(),
+                total_bytes: total,
+                available_bytes: available,
+                used_percent: ((total.saturating_sub(available)) as f64 * 1000.0 / total as f64).round() / 10.0,
+                removable: disk.is_removable(),
+            }
+        })
+        .collect();
+    volumes.sort_by(|a, b| b.boot.cmp(&a.boot).then_with(|| a.mount_point.cmp(&b.mount_point)));
+
+    let total_bytes: u64 = volumes.iter().map(|volume| volume.total_bytes).sum();
+    let available_bytes: u64 = volumes.iter().map(|volume| volume.available_bytes).sum();
+    let data = serde_json::json!({
+        "total_bytes": total_bytes,
+        "available_bytes": available_bytes,
+        "disks": volumes,
+    });
+    let Some(boot) = volumes.iter().find(|volume| volume.boot) else {
+        return CheckResult::new("unknown", "Couldn't find the boot volume").with_data(data);
+    };
+    let full_fixed = volumes
+        .iter()
+        .filter(|volume| !volume.boot && !volume.removable && volume.used_percent >= CRITICAL_USED_PERCENT)
+        .map(|volume| volume.mount_point.as_str())
+        .collect::<Vec<_>>();
+    let mut detail = format!("Boot volume {}% full, {} free", boot.used_percent, space::describe(boot.available_bytes));
+    if !full_fixed.is_empty() {
+        detail.push_str(&format!("; nearly full: {}", full_fixed.join(", ")));
+    }
+    let status = if boot.used_percent >= CRITICAL_USED_PERCENT {
+        "critical"
+    } else if boot.used_percent >= WARNING_USED_PERCENT || !full_fixed.is_empty() {
+        "warning"
+    } else {
+        "ok"
+    };
+    CheckResult::new(status, detail).with_data(data)
+}
+
+// "/" everywhere but Windows, where it's the system drive ("C:\")
+fn boot_mount() -> String {
+    match std::env::consts::OS {
+        "windows" => format!("{}\\", std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string())),
+        _ => "/".to_string(),
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::BTreeSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+// GET /net/dns?name= for "the internet works but this site won't load". The
+// name is resolved by the system resolver (what every app gets, hosts file
+// included) and, in parallel, straight from public resolvers over UDP, so a
+// broken or filtering local DNS shows up as the odd one out.
+const PUBLIC_RESOLVERS: &[&str] = &["1.1.1.1", "8.8.8.8"];
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const RCODES: &[&str] = &["NOERROR", "FORMERR", "SERVFAIL", "NXDOMAIN", "NOTIMP", "REFUSED"];
+
+#[derive(Debug, Deserialize)]
+pub struct DnsQuery {
+    name: String,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct Resolution {
+    // "system" or the public resolver's address
+    resolver: String,
+    duration_ms: u64,
+    addresses: Vec<IpAddr>,
+    // Response code from a public resolver ("NOERROR", "NXDOMAIN", …)
+    rcode: Option<String>,
+    error: Option<String>,
+}
+
+// GET /net/dns?name=example.com
+pub async fn dns_handler(Query(query): Query<DnsQuery>) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let name = query.name.trim().trim_end_matches('.').to_lowercase();
+    if !valid_hostname(&name) {
+        return Err((StatusCode::BAD_REQUEST, format!("'{}' is not a hostname", query.name)));
+    }
+    let system = {
+        let name = name.clone();
+        tauri::async_runtime::spawn_blocking(move || resolve_system(&name))
+    };
+    let public: Vec<_> = PUBLIC_RESOLVERS
+        .iter()
+        .map(|resolver| {
+            let name = name.clone();
+            tauri::async_runtime::spawn_blocking(move || resolve_with(resolver, &name))
+        })
+        .collect();
+    let mut results = vec![system.await.unwrap_or_default()];
+    for resolution in public {
+        results.push(resolution.await.unwrap_or_default());
+    }
+    let (consistent, diagnosis) = compare(&results);
+    Ok(Json(serde_json::json!({
+        "name": name,
+        "generated_at": Utc::now().to_rfc3339(),
+        "results": results,
+        "consistent": consistent,
+        "diagnosis": diagnosis,
+    })))
+}
+
+// Up to 253 characters of dot-separated labels of letters, digits and hyphens
+pub fn valid_hostname(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+fn resolve_system(name: &str) -> Resolution {
+    let started = Instant::now();
+    let resolved = (name, 0).to_socket_addrs();
+    let mut resolution = Resolution {
+        resolver: "system".to_string(),
+        duration_ms: started.elapsed().as_millis() as u64,
+        ..Default::default()
+    };
+    match resolved {
+        Ok(addresses) => {
+            let unique: BTreeSet<IpAddr> = addresses.map(|address| address.ip()).collect();
+            resolution.addresses = unique.into_iter().collect();
+        }
+        Err(e) => resolution.error = Some(e.to_string()),
+    }
+    resolution
+}
+
+// A and AAAA straight from `resolver`, both queries in flight at once
+fn resolve_with(resolver: &str, name: &str) -> Resolution {
+    let mut resolution = Resolution { resolver: resolver.to_string(), ..Default::default() };
+    let started = Instant::now();
+    match exchange(resolver, name) {
+        Ok((rcode, addresses)) => {
+            resolution.rcode = Some(RCODES.get(rcode as usize).map(|s| s.to_string()).unwrap_or_else(|| format!("RCODE{}", rcode)));
+            resolution.addresses = addresses.into_iter().collect();
+        }
+        Err(e) => resolution.error = Some(e),
+    }
+    resolution.duration_ms = started.elapsed().as_millis() as u64;
+    resolution
+}
+
+fn exchange(resolver: &str, name: &str) -> Result<(u8, BTreeSet<IpAddr>), String> {
+    let server = SocketAddr::new(resolver.parse().map_err(|e| format!("Bad resolver {}: {}", resolver, e))?, 53);
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| format!("Failed to open a UDP socket: {}", e))?;
+    socket.connect(server).map_err(|e| format!("Failed to reach {}: {}", resolver, e))?;
+    let id = (uuid::Uuid::new_v4().as_u128() & 0xfffe) as u16;
+    let pending = [(id, TYPE_A), (id + 1, TYPE_AAAA)];
+    for (id, record_type) in pending {
+        socket.send(&query(id, name, record_type)).map_err(|e| format!("Failed to query {}: {}", resolver, e))?;
+    }
+
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut answered = Vec::new();
+    let mut rcode = 0;
+    let mut addresses = BTreeSet::new();
+    let mut buffer = [0u8; 1500];
+    while answered.len() < pending.len() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(format!("{} didn't answer within {}s", resolver, QUERY_TIMEOUT.as_secs()));
+        }
+        socket.set_read_timeout(Some(remaining)).map_err(|e| e.to_string())?;
+        let size = match socket.recv(&mut buffer) {
+            Ok(size) => size,
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => continue,
+            Err(e) => return Err(format!("No answer from {}: {}", resolver, e)),
+        };
+        // Anything that isn't a reply to our queries is ignored
+        let Some(reply) = parse_reply(&buffer[..size]) else { continue };
+        if !pending.iter().any(|(id, _)| *id == reply.id) || answered.contains(&reply.id) {
+            continue;
+        }
+        answered.push(reply.id);
+        rcode = rcode.max(reply.rcode);
+        addresses.extend(reply.addresses);
+    }
+    Ok((rcode, addresses))
+}
+
+// A recursive query for `name` with one question
+fn query(id: u16, name: &str, record_type: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(18 + name.len());
+    packet.extend_from_slice(&id.to_be_bytes());
+    // Recursion desired; one question
+    packet.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.split('.') {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&record_type.to_be_bytes());
+    // Class IN
+    packet.extend_from_slice(&[0, 1]);
+    packet
+}
+
+struct Reply {
+    id: u16,
+    rcode: u8,
+    addresses: Vec<IpAddr>,
+}
+
+// The A and AAAA records of a reply; CNAMEs along the way are skipped
+fn parse_reply(packet: &[u8]) -> Option<Reply> {
+    let u16_at = |at: usize| packet.get(at..at + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]));
+    let id = u16_at(0)?;
+    let flags = u16_at(2)?;
+    // Not a response
+    if flags & 0x8000 == 0 {
+        return None;
+    }
+    let (questions, answers) = (u16_at(4)?, u16_at(6)?);
+    let mut at = 12;
+    for _ in 0..questions {
+        at = skip_name(packet, at)? + 4;
+    }
+    let mut addresses = Vec::new();
+    for _ in 0..answers {
+        at = skip_name(packet, at)?;
+        let (record_type, length) = (u16_at(at)?, u16_at(at + 8)? as usize);
+        let data = packet.get(at + 10..at + 10 + length)?;
+        match (record_type, length) {
+            (TYPE_A, 4) => addresses.push(IpAddr::V4(Ipv4Addr::new(data[0], data[1], data[2], data[3]))),
+            (TYPE_AAAA, 16) => {
+                let octets: [u8; 16] = data.try_into().ok()?;
+                addresses.push(IpAddr::V6(Ipv6Addr::from(octets)));
+            }
+            _ => {}
+        }
+        at += 10 + length;
+    }
+    Some(Reply { id, rcode: (flags & 0x000f) as u8, addresses })
+}
+
+// Offset just past the (possibly compressed) name at `at`
+fn skip_name(packet: &[u8], mut at: usize) -> Option<usize> {
+    loop {
+        let length = *packet.get(at)?;
+        match length {
+            0 => return Some(at + 1),
+            // A pointer ends the name
+            length if length & 0xc0 == 0xc0 => return Some(at + 2),
+            length => at += 1 + length as usize,
+        }
+    }
+}
+
+// Whether every resolver that answered agrees, and what the disagreement
+// most likely means. CDNs hand out different addresses per resolver, so
+// differing answers alone aren't a fault.
+fn compare(results: &[Resolution]) -> (bool, String) {
+    let Some((system, public)) = results.split_first() else {
+        return (true, String::new());
+    };
+    let public_answered = public.iter().any(|r| !r.addresses.is_empty());
+    let sets: Vec<BTreeSet<&IpAddr>> =
+        results.iter().filter(|r| !r.addresses.is_empty()).map(|r| r.addresses.iter().collect()).collect();
+    // Everyone answered the same, or nobody answered at all
+    let consistent = (sets.is_empty() || sets.len() == results.len()) && sets.windows(2).all(|pair| pair[0] == pair[1]);
+
+    let diagnosis = if system.addresses.is_empty() && public_answered {
+        "Only the system resolver fails: the DNS servers this network or VPN hands out can't resolve it, or are filtering it"
+    } else if !system.addresses.is_empty() && !public_answered {
+        if public.iter().any(|r| r.rcode.as_deref() == Some("NXDOMAIN")) {
+            "Only the system resolver knows this name: it's internal, or comes from the hosts file"
+        } else {
+            "Public resolvers can't be reached directly; this network may only allow its own DNS"
+        }
+    } else if system.addresses.is_empty() {
+        "No resolver could resolve this name: check the spelling, or whether the domain exists"
+    } else if system.addresses.iter().all(|address| address.is_unspecified() || address.is_loopback()) {
+        "The system resolver answers with a blackhole address: the name is blocked by the hosts file or a DNS filter"
+    } else if consistent {
+        "All resolvers agree"
+    } else {
+        "Resolvers return different addresses; normal for sites behind a CDN, otherwise the local DNS may be stale or redirecting"
+    };
+    (consistent, diagnosis.to_string())
+}
@@ -0,0 +1,196 @@
+use std::process::Command;
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+
+use crate::exec_context::system32;
+
+// Appended by the elevated script so we get per-command output and the
+// overall result even though the elevation wrapper only reports its own status
+const STATUS_MARKER: &str = "__OHFIXIT_STATUS__=";
+// Printed after each command with its exit code
+const EXIT_MARKER: &str = "__OHFIXIT_EXIT__=";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ElevationStatus {
+    Granted,
+    // The user dismissed or refused the prompt
+    Denied,
+    // No way to ask (no elevation tool, no GUI session, ...)
+    Unavailable,
+}
+
+impl ElevationStatus {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ElevationStatus::Granted => "administrator access granted",
+            ElevationStatus::Denied => "administrator access was declined, nothing was changed",
+            ElevationStatus::Unavailable => "administrator access could not be requested, nothing was changed",
+        }
+    }
+}
+
+// Overall success, combined output and each command's exit code (in order)
+pub struct ElevatedOutput {
+    pub success: bool,
+    pub output: String,
+    pub exit_codes: Vec<i32>,
+}
+
+// Run all commands under a single OS elevation prompt (osascript on macOS,
+// UAC on Windows, pkexec on Linux). Nothing has run when this returns Err.
+pub fn execute(commands: &[Vec<String>]) -> Result<ElevatedOutput, ElevationStatus> {
+    let output = match std::env::consts::OS {
+        "macos" => run_macos(commands),
+        "windows" => run_windows(commands),
+        _ => run_linux(commands),
+    }?;
+
+    log::info!("Elevation granted, ran {} command(s)", commands.len());
+    Ok(parse_output(&output))
+}
+
+fn run_macos(commands: &[Vec<String>]) -> Result<String, ElevationStatus> {
+    let script = posix_script(commands);
+    let applescript = format!(
+        "do shell script \"{}\" with administrator privileges without altering line endings",
+        script.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+
+    let result = Command::new("/usr/bin/osascript")
+        .args(["-e", &applescript])
+        .output()
+        .map_err(|e| unavailable(&format!("osascript failed to start: {}", e)))?;
+    if result.status.success() {
+        return Ok(String::from_utf8_lossy(&result.stdout).into_owned());
+    }
+
+    // "execution error: User canceled. (-128)"
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    if stderr.contains("(-128)") {
+        log::info!("Administrator prompt was cancelled");
+        Err(ElevationStatus::Denied)
+    } else {
+        Err(unavailable(&stderr))
+    }
+}
+
+fn run_linux(commands: &[Vec<String>]) -> Result<String, ElevationStatus> {
+    let script = posix_script(commands);
+    let result = Command::new("pkexec")
+        .args(["/bin/sh", "-c", &script])
+        .output()
+        .map_err(|e| unavailable(&format!("pkexec failed to start: {}", e)))?;
+
+    match result.status.code() {
+        Some(0) => Ok(String::from_utf8_lossy(&result.stdout).into_owned()),
+        // 126: dialog dismissed, 127: not authorized
+        Some(126) | Some(127) => {
+            log::info!("pkexec authorization was refused");
+            Err(ElevationStatus::Denied)
+        }
+        _ => Err(unavailable(&String::from_utf8_lossy(&result.stderr))),
+    }
+}
+
+fn run_windows(commands: &[Vec<String>]) -> Result<String, ElevationStatus> {
+    // The elevated process can't hand its output back directly, so it writes to a file
+    let output_file = std::env::temp_dir().join(format!("ohfixit-elevated-{}.log", uuid::Uuid::new_v4()));
+    let output_path = ps_quote(&output_file.to_string_lossy());
+
+    let mut inner = String::from("$status = 0\n& {\n");
+    for argv in commands {
+        let Some((program, args)) = argv.split_first() else { continue };
+        inner.push_str(&format!("Write-Output {}\n", ps_quote(&format!("Command: {}", argv.join(" ")))));
+        let quoted: Vec<String> = args.iter().map(|a| ps_quote(a)).collect();
+        inner.push_str("$global:LASTEXITCODE = $null\n");
+        inner.push_str(&format!("& {} {} 2>&1\n", ps_quote(program), quoted.join(" ")));
+        inner.push_str("$ok = $?\n");
+        inner.push_str(&format!(
+            "Write-Output \"{}$(if ($LASTEXITCODE -ne $null) {{ $LASTEXITCODE }} elseif ($ok) {{ 0 }} else {{ 1 }})\"\n",
+            EXIT_MARKER
+        ));
+        inner.push_str("if (-not $ok) { $script:status = 1 }\n");
+    }
+    inner.push_str(&format!("}} *> {}\n", output_path));
+    inner.push_str(&format!("Add-Content -Path {} -Value \"{}$status\"\n", output_path, STATUS_MARKER));
+
+    let utf16: Vec<u8> = inner.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+    let encoded = general_purpose::STANDARD.encode(utf16);
+    // Never looked up on the PATH, least of all for the elevated process
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    let outer = format!(
+        "$p = Start-Process -FilePath {} -Verb RunAs -Wait -PassThru -WindowStyle Hidden \
+         -ArgumentList '-NoProfile','-EncodedCommand','{}'; exit $p.ExitCode",
+        ps_quote(&powershell.to_string_lossy()),
+        encoded
+    );
+
+    let result = Command::new(&powershell)
+        .args(["-NoProfile", "-Command", &outer])
+        .output()
+        .map_err(|e| unavailable(&format!("powershell failed to start: {}", e)))?;
+
+    // No output file means the elevated process never started
+    let output = std::fs::read_to_string(&output_file).unwrap_or_default();
+    let _ = std::fs::remove_file(&output_file);
+    if !output.is_empty() {
+        return Ok(output);
+    }
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    if stderr.contains("canceled by the user") {
+        log::info!("UAC prompt was declined");
+        Err(ElevationStatus::Denied)
+    } else {
+        Err(unavailable(&stderr))
+    }
+}
+
+// Each command keeps running after a failure, matching the unelevated executor
+fn posix_script(commands: &[Vec<String>]) -> String {
+    let mut script = String::from("status=0; ");
+    for argv in commands {
+        let quoted: Vec<String> = argv.iter().map(|a| sh_quote(a)).collect();
+        script.push_str(&format!(
+            "echo {}; {} 2>&1; rc=$?; echo \"{}$rc\"; [ $rc -eq 0 ] || status=1; ",
+            sh_quote(&format!("Command: {}", argv.join(" "))),
+            quoted.join(" "),
+            EXIT_MARKER
+        ));
+    }
+    script.push_str(&format!("echo \"{}$status\"", STATUS_MARKER));
+    script
+}
+
+fn parse_output(raw: &str) -> ElevatedOutput {
+    let mut success = false;
+    let mut output = String::new();
+    let mut exit_codes = Vec::new();
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if let Some(status) = trimmed.strip_prefix(STATUS_MARKER) {
+            success = status.trim() == "0";
+        } else if let Some(code) = trimmed.strip_prefix(EXIT_MARKER) {
+            exit_codes.push(code.trim().parse().unwrap_or(-1));
+        } else {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+    ElevatedOutput { success, output, exit_codes }
+}
+
+fn sh_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn ps_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "''"))
+}
+
+fn unavailable(detail: &str) -> ElevationStatus {
+    log::error!("Elevation unavailable: {}", detail.trim());
+    ElevationStatus::Unavailable
+}
@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::read_trimmed;
+
+// Only these environment variables are reported; everything else may hold secrets
+const ENV_ALLOWLIST: &[&str] = &[
+    "PATH",
+    "PATHEXT",
+    "SHELL",
+    "LANG",
+    "LC_ALL",
+    "TERM",
+    "TMPDIR",
+    "HOMEBREW_PREFIX",
+    "XDG_SESSION_TYPE",
+    "XDG_CURRENT_DESKTOP",
+    "SystemRoot",
+    "PROCESSOR_ARCHITECTURE",
+];
+
+// Where action binaries may live. Commands name them by absolute path, and the
+// path must still land in one of these once symlinks are resolved.
+const TRUSTED_DIRS_UNIX: &[&str] = &["/bin", "/sbin", "/usr/bin", "/usr/sbin", "/usr/libexec", "/usr/lib", "/lib", "/System"];
+// Homebrew's prefixes (see updates::homebrew_bin). User-writable, so trusted
+// only for the non-elevated brew and mas update actions (verify_binary_for).
+const TRUSTED_DIRS_HOMEBREW: &[&str] = &["/opt/homebrew", "/usr/local/Homebrew", "/usr/local/Cellar"];
+
+// How one command of an action actually ran
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommandRun {
+    pub program: String,
+    // Where PATH lookup found the binary, None when it wasn't found
+    pub resolved_path: Option<String>,
+    // None when the process couldn't be started or the code wasn't reported
+    pub exit_code: Option<i32>,
+    // Elevated commands run as one batch, so they have no individual duration
+    pub duration_ms: Option<u64>,
+    pub elevated: bool,
+}
+
+impl CommandRun {
+    pub fn new(program: &str, elevated: bool) -> Self {
+        Self {
+            program: program.to_string(),
+            resolved_path: resolve(program).map(|p| p.to_string_lossy().into_owned()),
+            exit_code: None,
+            duration_ms: None,
+            elevated,
+        }
+    }
+}
+
+// Who ran the commands, from where, with which environment and binaries
+pub fn describe(commands: &[CommandRun]) -> serde_json::Value {
+    let env: serde_json::Map<String, serde_json::Value> = ENV_ALLOWLIST
+        .iter()
+        .filter_map(|name| std::env::var(name).ok().map(|v| (name.to_string(), v.into())))
+        .collect();
+
+    serde_json::json!({
+        "effective_user": read_trimmed("whoami", &[]),
+        "working_directory": std::env::current_dir().ok(),
+        "environment": env,
+        "commands": commands,
+    })
+}
+
+// Same lookup the OS does when spawning `program` without a path
+pub fn resolve(program: &str) -> Option<PathBuf> {
+    let candidate = Path::new(program);
+    if candidate.components().count() > 1 {
+        return candidate.canonicalize().ok();
+    }
+
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+            .split(';')
+            .map(|e| e.to_string())
+            .chain(std::iter::once(String::new()))
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", program, ext)))
+            .find(|p| p.is_file())
+    })
+}
+
+// %SystemRoot%\System32, where every Windows action binary lives
+pub fn system32() -> PathBuf {
+    let root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".to_string());
+    Path::new(&root).join("System32")
+}
+
+// Refuse bare names (PATH could be pointed at a look-alike) and anything that
+// resolves outside the system binary directories. Returns the resolved binary.
+pub fn verify_binary(program: &str) -> Result<PathBuf, String> {
+    verify_binary_for(program, false)
+}
+
+// verify_binary, also accepting Homebrew's prefixes on macOS when `homebrew`
+pub fn verify_binary_for(program: &str, homebrew: bool) -> Result<PathBuf, String> {
+    let trusted: Vec<PathBuf> = match std::env::consts::OS {
+        "windows" => vec![system32(), system32().with_file_name("SysWOW64")],
+        "macos" if homebrew => TRUSTED_DIRS_UNIX.iter().chain(TRUSTED_DIRS_HOMEBREW).map(PathBuf::from).collect(),
+        _ => TRUSTED_DIRS_UNIX.iter().map(PathBuf::from).collect(),
+    };
+    verify_binary_in(program, &trusted)
+}
+
+// verify_binary against an allowlist of directories other than the system's
+pub fn verify_binary_in(program: &str, trusted: &[PathBuf]) -> Result<PathBuf, String> {
+    if !Path::new(program).is_absolute() {
+        return Err(format!("'{}' is not an absolute path", program));
+    }
+    let resolved = Path::new(program)
+        .canonicalize()
+        .map_err(|e| format!("'{}' can't be resolved: {}", program, e))?;
+    if trusted
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .any(|dir| resolved.starts_with(dir))
+    {
+        Ok(resolved)
+    } else {
+        Err(format!("'{}' resolves to {}, outside the trusted directories", program, resolved.display()))
+    }
+}
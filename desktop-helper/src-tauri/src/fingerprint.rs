@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::read_trimmed;
+use crate::updates::homebrew_bin;
+
+// Where an action ran, so the server can correlate failures with configurations
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnvFingerprint {
+    // Stable hash of everything below, for grouping results
+    pub id: String,
+    pub os: String,
+    pub os_version: Option<String>,
+    pub os_build: Option<String>,
+    pub arch: String,
+    pub shell: Option<String>,
+    pub tool_versions: BTreeMap<String, String>,
+}
+
+static FINGERPRINT: OnceLock<EnvFingerprint> = OnceLock::new();
+
+// Computed once per run, none of this changes while the helper is up
+pub fn current() -> EnvFingerprint {
+    FINGERPRINT.get_or_init(collect).clone()
+}
+
+fn collect() -> EnvFingerprint {
+    let os = std::env::consts::OS.to_string();
+    let (os_version, os_build) = match os.as_str() {
+        "macos" => (
+            read_trimmed("/usr/bin/sw_vers", &["-productVersion"]),
+            read_trimmed("/usr/bin/sw_vers", &["-buildVersion"]),
+        ),
+        "windows" => {
+            // "Microsoft Windows [Version 10.0.22631.4317]"
+            let ver = read_trimmed("cmd", &["/C", "ver"])
+                .and_then(|v| v.split("Version ").nth(1).map(|s| s.trim_end_matches(']').to_string()));
+            (ver.clone(), ver.and_then(|v| v.split('.').nth(2).map(str::to_string)))
+        }
+        _ => (
+            std::fs::read_to_string("/etc/os-release").ok().and_then(|release| {
+                release
+                    .lines()
+                    .find_map(|l| l.strip_prefix("PRETTY_NAME="))
+                    .map(|v| v.trim_matches('"').to_string())
+            }),
+            read_trimmed("/usr/bin/uname", &["-r"]),
+        ),
+    };
+
+    let shell = std::env::var(if os == "windows" { "ComSpec" } else { "SHELL" }).ok();
+
+    let mut tool_versions = BTreeMap::new();
+    for (name, program, args) in tools(&os) {
+        if let Some(line) = read_trimmed(&program, args).and_then(|out| out.lines().next().map(str::to_string)) {
+            tool_versions.insert(name.to_string(), line);
+        }
+    }
+
+    let arch = std::env::consts::ARCH.to_string();
+    let id = fingerprint_id(&[
+        &os,
+        os_version.as_deref().unwrap_or(""),
+        os_build.as_deref().unwrap_or(""),
+        &arch,
+        shell.as_deref().unwrap_or(""),
+        &serde_json::to_string(&tool_versions).unwrap_or_default(),
+    ]);
+
+    EnvFingerprint {
+        id,
+        os,
+        os_version,
+        os_build,
+        arch,
+        shell,
+        tool_versions,
+    }
+}
+
+// Tools whose behaviour the allowlisted commands depend on
+fn tools(os: &str) -> Vec<(&'static str, String, &'static [&'static str])> {
+    match os {
+        "macos" => vec![
+            ("rsync", "/usr/bin/rsync".to_string(), &["--version"]),
+            ("brew", homebrew_bin("brew"), &["--version"]),
+            ("mas", homebrew_bin("mas"), &["version"]),
+        ],
+        "windows" => vec![(
+            "powershell",
+            "powershell".to_string(),
+            &["-NoProfile", "-Command", "$PSVersionTable.PSVersion.ToString()"],
+        )],
+        _ => vec![
+            ("rsync", "/usr/bin/rsync".to_string(), &["--version"]),
+            ("systemctl", "/usr/bin/systemctl".to_string(), &["--version"]),
+        ],
+    }
+}
+
+fn fingerprint_id(parts: &[&str]) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, parts.join("\n").as_bytes());
+    digest.as_ref()[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,189 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{audit, automation, pairing, scheduler, storage, tray, AppState};
+
+// Incident switch: while frozen, nothing is executed or rolled back, by any
+// route, command, link or batch; health checks and other reads keep working.
+// The paired server can freeze the helper (polled below) and lift its own
+// freeze; a freeze set on this computer is only lifted here, so a server
+// suspected of being compromised can't undo it. Survives restarts.
+const FREEZE_FILE: &str = "freeze.json";
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const POLL_TIMEOUT: Duration = Duration::from_secs(10);
+pub const SOURCE_SERVER: &str = "server";
+pub const SOURCE_LOCAL: &str = "local";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Freeze {
+    pub reason: String,
+    // SOURCE_SERVER or SOURCE_LOCAL
+    pub source: String,
+    pub since: DateTime<Utc>,
+}
+
+// The freeze saved by a previous run, for AppState::new
+pub fn load() -> Option<Freeze> {
+    storage::load_json(FREEZE_FILE)
+}
+
+pub fn current(app: &AppHandle) -> Option<Freeze> {
+    app.state::<Mutex<AppState>>().lock().unwrap().frozen.clone()
+}
+
+// Err, with the reason, while execution is frozen
+pub fn check(app: &AppHandle) -> Result<(), String> {
+    match current(app) {
+        Some(freeze) => Err(format!(
+            "Execution is frozen by the {} since {}: {}",
+            if freeze.source == SOURCE_SERVER { "OhFixIt server" } else { "user of this computer" },
+            freeze.since.format("%Y-%m-%d %H:%M UTC"),
+            freeze.reason
+        )),
+        None => Ok(()),
+    }
+}
+
+// Freeze execution and stop whatever is running. Freezing again only
+// updates the reason.
+pub async fn freeze(app: &AppHandle, source: &str, reason: &str) -> Freeze {
+    let freeze = {
+        let state = app.state::<Mutex<AppState>>();
+        let mut state = state.lock().unwrap();
+        // A local freeze stays one, so the server can't lift it later
+        let source = match &state.frozen {
+            Some(frozen) if frozen.source == SOURCE_LOCAL => SOURCE_LOCAL,
+            _ => source,
+        };
+        let freeze = Freeze {
+            reason: reason.to_string(),
+            source: source.to_string(),
+            since: state.frozen.as_ref().map_or_else(Utc::now, |frozen| frozen.since),
+        };
+        state.frozen = Some(freeze.clone());
+        freeze
+    };
+    if let Err(e) = storage::save_json(FREEZE_FILE, &Some(&freeze)) {
+        log::error!("Failed to persist execution freeze: {}", e);
+    }
+    log::error!("Execution frozen by {}: {}", source, reason);
+    audit::record("execution_frozen", serde_json::json!({ "source": source, "reason": reason }));
+    tray::alert(app, "Fixes are frozen", &format!("OhFixIt won't run or roll back anything until this is lifted.\n\n{}", reason));
+    let _ = app.emit("freeze-changed", Some(&freeze));
+    automation::abort_all(app).await;
+    freeze
+}
+
+// Lift the freeze. The server may only lift a freeze it set.
+pub fn unfreeze(app: &AppHandle, source: &str) -> Result<(), String> {
+    {
+        let state = app.state::<Mutex<AppState>>();
+        let mut state = state.lock().unwrap();
+        match &state.frozen {
+            None => return Ok(()),
+            Some(freeze) if source == SOURCE_SERVER && freeze.source != SOURCE_SERVER => {
+                return Err("A freeze set on this computer can only be lifted here".to_string());
+            }
+            Some(_) => state.frozen = None,
+        }
+    }
+    storage::save_json(FREEZE_FILE, &None::<Freeze>)?;
+    log::info!("Execution freeze lifted by {}", source);
+    audit::record("execution_unfrozen", serde_json::json!({ "source": source }));
+    tray::clear_alert(app);
+    let _ = app.emit("freeze-changed", None::<Freeze>);
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FreezeRequest {
+    reason: Option<String>,
+}
+
+// POST /automation/freeze: like the kill switch, freezing needs no approval.
+// Lifting it isn't possible over HTTP.
+pub async fn freeze_handler(State(app): State<AppHandle>, Json(request): Json<FreezeRequest>) -> Json<Freeze> {
+    let reason = request.reason.unwrap_or_else(|| "Frozen through the local API".to_string());
+    Json(freeze(&app, SOURCE_LOCAL, &reason).await)
+}
+
+#[tauri::command]
+pub async fn freeze_execution(app: AppHandle, reason: Option<String>) -> Result<Freeze, String> {
+    let reason = reason.unwrap_or_else(|| "Frozen from the helper window".to_string());
+    Ok(freeze(&app, SOURCE_LOCAL, &reason).await)
+}
+
+#[tauri::command]
+pub fn unfreeze_execution(app: AppHandle) -> Result<(), String> {
+    unfreeze(&app, SOURCE_LOCAL)
+}
+
+#[tauri::command]
+pub fn execution_freeze(app: AppHandle) -> Option<Freeze> {
+    current(&app)
+}
+
+#[derive(Debug, Deserialize)]
+struct ServerFreeze {
+    frozen: bool,
+    reason: Option<String>,
+}
+
+// Asks the paired server whether it wants execution frozen, every minute
+pub fn spawn_poll(app: AppHandle) {
+    scheduler::spawn_job("freeze_poll", POLL_INTERVAL, false, move || {
+        let app = app.clone();
+        async move {
+            if !pairing::status().paired {
+                return Ok(String::new());
+            }
+            let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+            let server = poll(&client).await?;
+            match (server.frozen, current(&app)) {
+                (true, Some(_)) => Ok(String::new()),
+                (true, None) => {
+                    let reason = server.reason.unwrap_or_else(|| "Frozen by the OhFixIt server".to_string());
+                    freeze(&app, SOURCE_SERVER, &reason).await;
+                    Ok(format!("Execution frozen by the server: {}", reason))
+                }
+                (false, Some(freeze)) if freeze.source == SOURCE_SERVER => {
+                    unfreeze(&app, SOURCE_SERVER)?;
+                    Ok("Server lifted the execution freeze".to_string())
+                }
+                (false, _) => Ok(String::new()),
+            }
+        }
+    });
+}
+
+// Signed with the device key so the server answers only for its own device
+async fn poll(client: &Client) -> Result<ServerFreeze, String> {
+    let device_id = pairing::status().device_id;
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let timestamp = Utc::now().timestamp();
+    let signature = pairing::sign(format!("{}.{}", device_id, timestamp).as_bytes())?;
+
+    client
+        .post(format!("{}/api/automation/helper/freeze/status", server_url))
+        .timeout(POLL_TIMEOUT)
+        .json(&serde_json::json!({
+            "deviceId": device_id,
+            "timestamp": timestamp,
+            "signature": signature,
+        }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to check for an execution freeze: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Malformed freeze status: {}", e))
+}
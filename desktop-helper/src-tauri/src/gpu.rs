@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+use crate::health::CheckResult;
+use crate::space;
+
+// The check behind GET /health/gpu: the graphics adapters with their memory,
+// driver and API support, and whether graphics are hardware accelerated at
+// all, for choppy video, external display and gaming problems. macOS asks
+// system_profiler (Metal support); Windows reads the video controllers, with
+// the real memory size from the display class registry key (AdapterRAM stops
+// at 4 GB); Linux reads the DRM devices and asks glxinfo which renderer is in
+// use. A software renderer (llvmpipe, Microsoft Basic Display Adapter) is a
+// warning.
+#[derive(Debug, Serialize, Default)]
+pub struct Gpu {
+    name: String,
+    vendor: Option<String>,
+    vram_bytes: Option<u64>,
+    // Integrated graphics that use system memory (Apple silicon, most Intel)
+    shared_memory: bool,
+    driver: Option<String>,
+    driver_version: Option<String>,
+    driver_date: Option<String>,
+    // "Metal 3" on macOS
+    api_support: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
+struct Graphics {
+    gpus: Vec<Gpu>,
+    hardware_acceleration: Option<bool>,
+    // The OpenGL renderer on Linux ("llvmpipe (LLVM 17.0.6, 256 bits)")
+    renderer: Option<String>,
+    // Windows' hardware-accelerated GPU scheduling
+    gpu_scheduling: Option<bool>,
+}
+
+pub fn check() -> CheckResult {
+    let graphics = match std::env::consts::OS {
+        "macos" => macos(),
+        "windows" => windows(),
+        _ => linux(),
+    };
+    if graphics.gpus.is_empty() {
+        return CheckResult::new("unknown", "Couldn't find a graphics adapter");
+    }
+    let names = graphics
+        .gpus
+        .iter()
+        .map(|gpu| match gpu.vram_bytes.filter(|_| !gpu.shared_memory) {
+            Some(bytes) => format!("{} ({})", gpu.name, space::describe(bytes).trim_start_matches('~')),
+            None => gpu.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let (status, detail) = match graphics.hardware_acceleration {
+        Some(false) => ("warning", format!("{}: graphics are drawn in software, so video, animations and games will be slow", names)),
+        _ => ("ok", names),
+    };
+    CheckResult::new(status, detail).with_data(serde_json::json!(graphics))
+}
+
+// "1536 MB", "4 GB"
+fn size(text: &str) -> Option<u64> {
+    let (number, unit) = text.trim().split_once(' ')?;
+    let number: u64 = number.parse().ok()?;
+    match unit {
+        "MB" => Some(number * 1024 * 1024),
+        "GB" => Some(number * 1024 * 1024 * 1024),
+        _ => None,
+    }
+}
+
+fn macos() -> Graphics {
+    let output = read_output("/usr/sbin/system_profiler", &["SPDisplaysDataType", "-json"]).unwrap_or_default();
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap_or_default();
+    let gpus: Vec<Gpu> = report["SPDisplaysDataType"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|item| {
+            let dedicated = item["spdisplays_vram"].as_str().and_then(size);
+            let shared = item["spdisplays_vram_shared"].as_str().and_then(size);
+            Gpu {
+                name: item["sppci_model"].as_str().or_else(|| item["_name"].as_str()).unwrap_or_default().to_string(),
+                // "sppci_vendor_Apple", "Intel"
+                vendor: item["spdisplays_vendor"].as_str().map(|vendor| vendor.trim_start_matches("sppci_vendor_").to_string()),
+                vram_bytes: dedicated.or(shared),
+                shared_memory: dedicated.is_none(),
+                // "spdisplays_metal3" / "spdisplays_supported"
+                api_support: item["spdisplays_mtlgpufamilysupport"]
+                    .as_str()
+                    .and_then(|family| family.strip_prefix("spdisplays_metal"))
+                    .map(|version| format!("Metal {}", version)),
+                ..Default::default()
+            }
+        })
+        .collect();
+    // Quartz always composites on the GPU
+    let hardware_acceleration = (!gpus.is_empty()).then_some(true);
+    Graphics { gpus, hardware_acceleration, ..Default::default() }
+}
+
+fn windows() -> Graphics {
+    let script = r#"
+$ErrorActionPreference = 'SilentlyContinue'
+$memory = @{}
+Get-ChildItem 'HKLM:\SYSTEM\CurrentControlSet\Control\Class\{4d36e968-e325-11ce-bfc1-08002be10318}' | ForEach-Object {
+  $p = Get-ItemProperty $_.PSPath
+  $size = $p.'HardwareInformation.qwMemorySize'
+  if (-not $size) { $size = $p.'HardwareInformation.MemorySize' }
+  if ($size -is [byte[]]) { $size = [BitConverter]::ToUInt32($size, 0) }
+  if ($p.DriverDesc -and $size) { $memory[$p.DriverDesc] = [uint64]$size }
+}
+$gpus = Get-CimInstance Win32_VideoController | ForEach-Object {
+  [ordered]@{
+    name = $_.Name; vendor = $_.AdapterCompatibility
+    vram = if ($memory[$_.Name]) { $memory[$_.Name] } else { $_.AdapterRAM }
+    driver = $_.InfFilename; driver_version = $_.DriverVersion
+    driver_date = if ($_.DriverDate) { $_.DriverDate.ToString('yyyy-MM-dd') } else { $null }
+  }
+}
+$scheduling = (Get-ItemProperty 'HKLM:\SYSTEM\CurrentControlSet\Control\GraphicsDrivers').HwSchMode
+ConvertTo-Json -InputObject @{ gpus = @($gpus); scheduling = $scheduling } -Compress -Depth 3
+"#;
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    let output = read_output(&powershell.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", script]).unwrap_or_default();
+    let report: serde_json::Value = serde_json::from_str(output.trim()).unwrap_or_default();
+    let text = |value: &serde_json::Value| value.as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    let gpus: Vec<Gpu> = report["gpus"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|gpu| {
+            let name = text(&gpu["name"]).unwrap_or_default();
+            Gpu {
+                // Intel's integrated graphics borrow system memory
+                shared_memory: name.contains("Intel") && !name.contains("Arc"),
+                name,
+                vendor: text(&gpu["vendor"]),
+                vram_bytes: gpu["vram"].as_u64().filter(|bytes| *bytes > 0),
+                driver: text(&gpu["driver"]),
+                driver_version: text(&gpu["driver_version"]),
+                driver_date: text(&gpu["driver_date"]),
+                api_support: None,
+            }
+        })
+        .collect();
+    // Windows falls back to the basic adapter when no vendor driver is installed
+    let hardware_acceleration = (!gpus.is_empty()).then(|| gpus.iter().any(|gpu| !gpu.name.starts_with("Microsoft Basic")));
+    // HwSchMode: 2 on, 1 off
+    let gpu_scheduling = report["scheduling"].as_u64().map(|mode| mode == 2);
+    Graphics { gpus, hardware_acceleration, gpu_scheduling, ..Default::default() }
+}
+
+fn read_trimmed_file(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|text| text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+// /sys/class/drm/card<N> (not the card<N>-<connector> entries) for each
+// adapter; lspci names it, nvidia-smi or amdgpu's sysfs give its memory
+fn linux() -> Graphics {
+    let mut cards: Vec<PathBuf> = std::fs::read_dir("/sys/class/drm")
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    cards.retain(|card| {
+        let name = card.file_name().unwrap_or_default().to_string_lossy();
+        name.starts_with("card") && !name.contains('-')
+    });
+    cards.sort();
+
+    // "GeForce RTX 3060, 12288, 550.54.14"
+    let nvidia = read_output("nvidia-smi", &["--query-gpu=name,memory.total,driver_version", "--format=csv,noheader,nounits"]).unwrap_or_default();
+    let mut nvidia = nvidia.lines();
+
+    let gpus: Vec<Gpu> = cards
+        .iter()
+        .filter_map(|card| {
+            let device = card.join("device");
+            let slot = std::fs::canonicalize(&device).ok()?.file_name()?.to_string_lossy().into_owned();
+            let driver = std::fs::read_link(device.join("driver"))
+                .ok()
+                .and_then(|driver| driver.file_name().map(|name| name.to_string_lossy().into_owned()));
+            // "01:00.0 "VGA compatible controller" "NVIDIA Corporation" "GA106 [GeForce RTX 3060]" …"
+            let lspci = read_output("lspci", &["-mm", "-s", &slot]).unwrap_or_default();
+            let fields: Vec<&str> = lspci.split('"').collect();
+            let vendor = fields.get(3).map(|vendor| vendor.to_string()).filter(|vendor| !vendor.is_empty());
+            let mut gpu = Gpu {
+                name: fields.get(5).map(|name| name.to_string()).unwrap_or_else(|| slot.clone()),
+                vendor,
+                vram_bytes: read_trimmed_file(&device.join("mem_info_vram_total")).and_then(|bytes| bytes.parse().ok()),
+                shared_memory: driver.as_deref() == Some("i915") || driver.as_deref() == Some("xe"),
+                driver_version: driver.as_deref().and_then(|driver| read_trimmed_file(&Path::new("/sys/module").join(driver).join("version"))),
+                driver,
+                ..Default::default()
+            };
+            if gpu.driver.as_deref() == Some("nvidia") {
+                if let Some(line) = nvidia.next() {
+                    let fields: Vec<&str> = line.split(", ").collect();
+                    if let [name, memory, version] = fields[..] {
+                        gpu.name = name.to_string();
+                        gpu.vram_bytes = memory.trim().parse::<u64>().ok().map(|mib| mib * 1024 * 1024);
+                        gpu.driver_version = Some(version.trim().to_string());
+                    }
+                }
+            }
+            Some(gpu)
+        })
+        .collect();
+
+    // "direct rendering: Yes" and "OpenGL renderer string: llvmpipe (LLVM
+    // 17.0.6, 256 bits)"; glxinfo needs a display and isn't always installed
+    let glxinfo = read_output("glxinfo", &["-B"]).unwrap_or_default();
+    let renderer = glxinfo
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("OpenGL renderer string:"))
+        .map(|renderer| renderer.trim().to_string());
+    let software = ["llvmpipe", "softpipe", "software rasterizer"];
+    let hardware_acceleration = renderer.as_deref().map(|renderer| {
+        let renderer = renderer.to_lowercase();
+        !software.iter().any(|name| renderer.contains(name)) && glxinfo.contains("direct rendering: Yes")
+    });
+    Graphics { gpus, hardware_acceleration, renderer, ..Default::default() }
+}
@@ -0,0 +1,138 @@
+use std::sync::Mutex;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::probes::Probe;
+use crate::storage;
+
+const GUIDED_FILE: &str = "guided_steps.json";
+const MAX_GUIDED_STEPS: usize = 100;
+
+static GUIDED_LOCK: Mutex<()> = Mutex::new(());
+
+// A command outside the allowlist that the user runs themselves. The helper
+// only displays it and verifies the outcome, it never executes it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GuidedStep {
+    pub id: String,
+    pub command: String,
+    pub explanation: String,
+    pub check: Probe,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub attempts: u32,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GuidedRequest {
+    command: String,
+    #[serde(default)]
+    explanation: String,
+    check: Probe,
+}
+
+// POST /guided
+pub async fn create_guided_handler(
+    State(app): State<AppHandle>,
+    Json(request): Json<GuidedRequest>,
+) -> Result<Json<GuidedStep>, (StatusCode, Json<serde_json::Value>)> {
+    let step = GuidedStep {
+        id: uuid::Uuid::new_v4().to_string(),
+        command: request.command,
+        explanation: request.explanation,
+        check: request.check,
+        status: "pending".to_string(),
+        created_at: Utc::now(),
+        verified_at: None,
+        attempts: 0,
+        detail: None,
+    };
+    save(&step).map_err(internal_error)?;
+
+    log::info!("Guided step {} shown to user: {}", step.id, step.command);
+    let _ = app.emit("guided-command", &step);
+    Ok(Json(step))
+}
+
+// GET /guided/{id}
+pub async fn get_guided_handler(
+    Path(id): Path<String>,
+) -> Result<Json<GuidedStep>, (StatusCode, Json<serde_json::Value>)> {
+    get(&id).map(Json).ok_or_else(|| not_found(&id))
+}
+
+// POST /guided/{id}/verify
+pub async fn verify_guided_handler(
+    Path(id): Path<String>,
+) -> Result<Json<GuidedStep>, (StatusCode, Json<serde_json::Value>)> {
+    tauri::async_runtime::spawn_blocking(move || verify(&id))
+        .await
+        .map_err(|e| internal_error(e.to_string()))?
+        .map(Json)
+        .map_err(|e| (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": e }))))
+}
+
+#[tauri::command]
+pub async fn list_guided_steps() -> Result<Vec<GuidedStep>, String> {
+    Ok(storage::load_json(GUIDED_FILE))
+}
+
+// Called when the user says they've run the command
+#[tauri::command]
+pub async fn verify_guided_step(step_id: String) -> Result<GuidedStep, String> {
+    tauri::async_runtime::spawn_blocking(move || verify(&step_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+pub fn get(id: &str) -> Option<GuidedStep> {
+    let steps: Vec<GuidedStep> = storage::load_json(GUIDED_FILE);
+    steps.into_iter().find(|s| s.id == id)
+}
+
+pub fn verify(id: &str) -> Result<GuidedStep, String> {
+    let mut step = get(id).ok_or_else(|| format!("Unknown guided step '{}'", id))?;
+
+    let result = step.check.run();
+    step.attempts += 1;
+    step.detail = Some(result.detail);
+    if result.passed {
+        step.status = "verified".to_string();
+        step.verified_at = Some(Utc::now());
+    } else {
+        step.status = "failed".to_string();
+    }
+
+    save(&step)?;
+    Ok(step)
+}
+
+fn save(step: &GuidedStep) -> Result<(), String> {
+    let _guard = GUIDED_LOCK.lock().unwrap();
+    let mut steps: Vec<GuidedStep> = storage::load_json(GUIDED_FILE);
+    steps.retain(|s| s.id != step.id);
+    steps.push(step.clone());
+    if steps.len() > MAX_GUIDED_STEPS {
+        let excess = steps.len() - MAX_GUIDED_STEPS;
+        steps.drain(..excess);
+    }
+    storage::save_json(GUIDED_FILE, &steps)
+}
+
+fn not_found(id: &str) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({ "error": format!("Unknown guided step '{}'", id) })),
+    )
+}
+
+fn internal_error(e: String) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e })))
+}
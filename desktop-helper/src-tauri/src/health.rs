@@ -0,0 +1,263 @@
+use std::future::Future;
+use std::time::Duration;
+
+use axum::extract::Query;
+use axum::Json;
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+
+use crate::cache::{CacheQuery, TtlCache};
+use crate::cmd::read_trimmed;
+use crate::{audit, battery, cpu, disks, gpu, panics, smart, startup_items, thermals, updates};
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+// softwareupdate -l is slow even when nothing is pending
+const UPDATES_TIMEOUT: Duration = Duration::from_secs(60);
+// A signature check per item, and possibly a permission prompt for System
+// Events; left out of /health/all
+const STARTUP_ITEMS_TIMEOUT: Duration = Duration::from_secs(60);
+// A Time Machine backup older than this is reported
+const STALE_BACKUP_DAYS: i64 = 7;
+
+const SOCKETFILTERFW: &str = "/usr/libexec/ApplicationFirewall/socketfilterfw";
+const XPROTECT_PLIST: &str = "/Library/Apple/System/Library/CoreServices/XProtect.bundle/Contents/Info";
+
+static REPORT_CACHE: TtlCache = TtlCache::new(Duration::from_secs(60));
+
+// Outcome of one security/health check. status is "ok", "warning",
+// "critical", "unknown" (couldn't tell), "unsupported" (not on this OS) or
+// "timeout".
+#[derive(Debug, Serialize, Clone)]
+pub struct CheckResult {
+    pub check: String,
+    pub status: String,
+    pub detail: String,
+    pub data: Option<serde_json::Value>,
+    pub duration_ms: u64,
+}
+
+impl CheckResult {
+    pub fn new(status: &str, detail: impl Into<String>) -> Self {
+        Self {
+            check: String::new(),
+            status: status.to_string(),
+            detail: detail.into(),
+            data: None,
+            duration_ms: 0,
+        }
+    }
+
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    fn unsupported() -> Self {
+        Self::new("unsupported", format!("Not available on {}", std::env::consts::OS))
+    }
+}
+
+// GET /health/firewall, /health/antivirus, /health/filevault, /health/time-machine, /health/sip,
+// /health/battery, /health/disks, /health/disks/smart, /health/thermals,
+// /health/cpu, /health/gpu, /health/startup-items, /health/panics
+pub async fn firewall_handler() -> Json<CheckResult> {
+    Json(run_blocking("firewall", CHECK_TIMEOUT, firewall).await)
+}
+
+pub async fn antivirus_handler() -> Json<CheckResult> {
+    Json(run_blocking("antivirus", CHECK_TIMEOUT, antivirus).await)
+}
+
+pub async fn filevault_handler() -> Json<CheckResult> {
+    Json(run_blocking("filevault", CHECK_TIMEOUT, filevault).await)
+}
+
+pub async fn time_machine_handler() -> Json<CheckResult> {
+    Json(run_blocking("time_machine", CHECK_TIMEOUT, time_machine).await)
+}
+
+pub async fn sip_handler() -> Json<CheckResult> {
+    Json(run_blocking("sip", CHECK_TIMEOUT, sip).await)
+}
+
+pub async fn battery_handler() -> Json<CheckResult> {
+    Json(run_blocking("battery", CHECK_TIMEOUT, battery::check).await)
+}
+
+pub async fn disks_handler() -> Json<CheckResult> {
+    Json(run_blocking("disk_space", CHECK_TIMEOUT, disks::check).await)
+}
+
+pub async fn smart_handler() -> Json<CheckResult> {
+    Json(run_blocking("disk_smart", CHECK_TIMEOUT, smart::check).await)
+}
+
+pub async fn thermals_handler() -> Json<CheckResult> {
+    Json(run_blocking("thermals", CHECK_TIMEOUT, thermals::check).await)
+}
+
+pub async fn cpu_handler() -> Json<CheckResult> {
+    Json(run_blocking("cpu", CHECK_TIMEOUT, cpu::check).await)
+}
+
+pub async fn gpu_handler() -> Json<CheckResult> {
+    Json(run_blocking("gpu", CHECK_TIMEOUT, gpu::check).await)
+}
+
+pub async fn startup_items_handler() -> Json<CheckResult> {
+    Json(run_blocking("startup_items", STARTUP_ITEMS_TIMEOUT, startup_items::check).await)
+}
+
+pub async fn panics_handler() -> Json<CheckResult> {
+    Json(run_blocking("panics", CHECK_TIMEOUT, panics::check).await)
+}
+
+// GET /health/all[?refresh=true]: every check at once, each under its own
+// timeout, so one slow tool can't hold up the rest
+pub async fn all_handler(Query(query): Query<CacheQuery>) -> Json<serde_json::Value> {
+    Json(REPORT_CACHE.get_or_compute("all", query.refresh, report).await)
+}
+
+async fn report() -> serde_json::Value {
+    let (updates, firewall, antivirus, filevault, time_machine, sip, battery, disk_space, disk_smart, thermals, cpu, gpu, panics) = tokio::join!(
+        timed("updates", UPDATES_TIMEOUT, pending_updates()),
+        run_blocking("firewall", CHECK_TIMEOUT, firewall),
+        run_blocking("antivirus", CHECK_TIMEOUT, antivirus),
+        run_blocking("filevault", CHECK_TIMEOUT, filevault),
+        run_blocking("time_machine", CHECK_TIMEOUT, time_machine),
+        run_blocking("sip", CHECK_TIMEOUT, sip),
+        run_blocking("battery", CHECK_TIMEOUT, battery::check),
+        run_blocking("disk_space", CHECK_TIMEOUT, disks::check),
+        run_blocking("disk_smart", CHECK_TIMEOUT, smart::check),
+        run_blocking("thermals", CHECK_TIMEOUT, thermals::check),
+        run_blocking("cpu", CHECK_TIMEOUT, cpu::check),
+        run_blocking("gpu", CHECK_TIMEOUT, gpu::check),
+        run_blocking("panics", CHECK_TIMEOUT, panics::check),
+    );
+    let checks = [updates, firewall, antivirus, filevault, time_machine, sip, battery, disk_space, disk_smart, thermals, cpu, gpu, panics];
+    let overall = ["critical", "warning", "timeout", "unknown"]
+        .into_iter()
+        .find(|status| checks.iter().any(|c| c.status == *status))
+        .unwrap_or("ok");
+    serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "os": std::env::consts::OS,
+        "overall": overall,
+        "checks": checks,
+    })
+}
+
+async fn run_blocking(name: &str, limit: Duration, check: fn() -> CheckResult) -> CheckResult {
+    timed(name, limit, async move {
+        tauri::async_runtime::spawn_blocking(check)
+            .await
+            .unwrap_or_else(|e| CheckResult::new("unknown", format!("Check failed: {}", e)))
+    })
+    .await
+}
+
+// A timed-out blocking check keeps running in the background; only its result is dropped
+async fn timed(name: &str, limit: Duration, check: impl Future<Output = CheckResult>) -> CheckResult {
+    let started = std::time::Instant::now();
+    let mut result = tokio::time::timeout(limit, check)
+        .await
+        .unwrap_or_else(|_| CheckResult::new("timeout", format!("No answer within {}s", limit.as_secs())));
+    result.check = name.to_string();
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    audit::record("health_probe", serde_json::json!({
+        "check": result.check,
+        "status": result.status,
+        "duration_ms": result.duration_ms,
+    }));
+    result
+}
+
+async fn pending_updates() -> CheckResult {
+    let report = updates::cached_report(false).await;
+    let total = report["total"].as_u64().unwrap_or(0);
+    let status = if total == 0 { "ok" } else { "warning" };
+    CheckResult::new(status, format!("{} pending update(s)", total)).with_data(report)
+}
+
+// "Firewall is enabled. (State = 1)" / "Firewall is disabled. (State = 0)"
+fn firewall() -> CheckResult {
+    if std::env::consts::OS != "macos" {
+        return CheckResult::unsupported();
+    }
+    match read_trimmed(SOCKETFILTERFW, &["--getglobalstate"]) {
+        Some(out) if out.contains("enabled") => CheckResult::new("ok", "Firewall is on"),
+        Some(out) if out.contains("disabled") => CheckResult::new("warning", "Firewall is off"),
+        Some(out) => CheckResult::new("unknown", format!("Unexpected firewall state '{}'", out)),
+        None => CheckResult::new("unknown", "Couldn't read the firewall state"),
+    }
+}
+
+// macOS has no third-party AV API; XProtect's definitions version shows the
+// built-in protection is present and being updated
+fn antivirus() -> CheckResult {
+    if std::env::consts::OS != "macos" {
+        return CheckResult::unsupported();
+    }
+    match read_trimmed("/usr/bin/defaults", &["read", XPROTECT_PLIST, "CFBundleShortVersionString"]) {
+        Some(version) => CheckResult::new("ok", format!("XProtect definitions {}", version))
+            .with_data(serde_json::json!({ "xprotect_version": version })),
+        None => CheckResult::new("warning", "XProtect definitions not found"),
+    }
+}
+
+// "FileVault is On." / "FileVault is Off." / "Encryption in progress: ..."
+fn filevault() -> CheckResult {
+    if std::env::consts::OS != "macos" {
+        return CheckResult::unsupported();
+    }
+    match read_trimmed("/usr/bin/fdesetup", &["status"]) {
+        Some(out) if out.contains("FileVault is On") => CheckResult::new("ok", "Disk encryption is on"),
+        Some(out) if out.contains("FileVault is Off") => CheckResult::new("critical", "Disk encryption is off"),
+        Some(out) if out.contains("in progress") => CheckResult::new("ok", out.lines().next().unwrap_or_default()),
+        Some(out) => CheckResult::new("unknown", format!("Unexpected FileVault state '{}'", out)),
+        None => CheckResult::new("unknown", "Couldn't read the FileVault state"),
+    }
+}
+
+// latestbackup prints ".../Backups.backupdb/Mac/2024-06-10-123456" (or a
+// ".../2024-06-10-123456.backup" snapshot on APFS destinations)
+fn time_machine() -> CheckResult {
+    if std::env::consts::OS != "macos" {
+        return CheckResult::unsupported();
+    }
+    let Some(destinations) = read_trimmed("/usr/bin/tmutil", &["destinationinfo"]) else {
+        return CheckResult::new("unknown", "Couldn't read Time Machine settings");
+    };
+    if destinations.contains("No destinations configured") {
+        return CheckResult::new("warning", "Time Machine isn't set up");
+    }
+    let latest = read_trimmed("/usr/bin/tmutil", &["latestbackup"]).and_then(|path| {
+        let name = path.rsplit('/').next()?.trim_end_matches(".backup").to_string();
+        NaiveDateTime::parse_from_str(&name, "%Y-%m-%d-%H%M%S").ok()
+    });
+    match latest {
+        // tmutil needs Full Disk Access to read backups on some versions
+        None => CheckResult::new("unknown", "Time Machine is set up, but the last backup couldn't be read"),
+        Some(at) => {
+            let days = (Utc::now().naive_local() - at).num_days();
+            let status = if days > STALE_BACKUP_DAYS { "warning" } else { "ok" };
+            CheckResult::new(status, format!("Last backup {} day(s) ago", days))
+                .with_data(serde_json::json!({ "latest_backup": at.format("%Y-%m-%dT%H:%M:%S").to_string() }))
+        }
+    }
+}
+
+// "System Integrity Protection status: enabled."
+fn sip() -> CheckResult {
+    if std::env::consts::OS != "macos" {
+        return CheckResult::unsupported();
+    }
+    match read_trimmed("/usr/bin/csrutil", &["status"]) {
+        Some(out) if out.contains("status: enabled") => CheckResult::new("ok", "System Integrity Protection is on"),
+        Some(out) if out.contains("status: disabled") => {
+            CheckResult::new("critical", "System Integrity Protection is off")
+        }
+        Some(out) => CheckResult::new("warning", out.lines().next().unwrap_or_default()),
+        None => CheckResult::new("unknown", "Couldn't read the System Integrity Protection state"),
+    }
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use crate::exec_context::CommandRun;
+
+// What one exit code of one of an action's binaries means, in plain language
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExitHint {
+    pub program: String,
+    pub code: i32,
+    pub hint: String,
+    // The code means there was nothing to do, so the command counts as
+    // succeeded ("defaults delete" of a key that isn't set)
+    #[serde(default)]
+    pub tolerated: bool,
+}
+
+// Codes that mean the same thing whichever binary returned them
+const GENERIC_HINTS: &[(i32, &str)] = &[
+    (126, "A required program couldn't be started because of missing permissions."),
+    (127, "A required program isn't installed on this computer."),
+];
+
+// Whether `program` exiting with `code` still counts as success
+pub fn tolerated(hints: &[ExitHint], program: &str, code: i32) -> bool {
+    let program = file_name(program);
+    hints.iter().any(|h| h.tolerated && h.code == code && file_name(&h.program) == program)
+}
+
+// The hint for the first command that failed, when its exit code is known
+pub fn explain(hints: &[ExitHint], commands: &[CommandRun]) -> Option<String> {
+    let failed = commands
+        .iter()
+        .find(|c| c.exit_code.is_some_and(|code| code != 0 && !tolerated(hints, &c.program, code)))?;
+    let code = failed.exit_code?;
+    let program = file_name(&failed.program);
+    hints
+        .iter()
+        .find(|h| h.code == code && file_name(&h.program) == program)
+        .map(|h| h.hint.clone())
+        .or_else(|| GENERIC_HINTS.iter().find(|(c, _)| *c == code).map(|(_, hint)| hint.to_string()))
+}
+
+// Hints may name the binary with or without its path or .exe
+fn file_name(program: &str) -> String {
+    let name = program.rsplit(['/', '\\']).next().unwrap_or(program).to_lowercase();
+    name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exited(program: &str, code: i32) -> CommandRun {
+        let mut run = CommandRun::new(program, false);
+        run.exit_code = Some(code);
+        run
+    }
+
+    #[test]
+    fn tolerated_code_is_not_a_failure() {
+        let hints = vec![
+            ExitHint { program: "defaults".to_string(), code: 1, hint: "Nothing to clear.".to_string(), tolerated: true },
+            ExitHint { program: "killall".to_string(), code: 1, hint: "Finder wasn't running.".to_string(), tolerated: false },
+        ];
+        assert!(tolerated(&hints, "/usr/bin/defaults", 1));
+        assert!(!tolerated(&hints, "/usr/bin/defaults", 2));
+        assert!(!tolerated(&hints, "/usr/bin/killall", 1));
+
+        let commands = [exited("/usr/bin/defaults", 1), exited("/usr/bin/killall", 1)];
+        assert_eq!(explain(&hints, &commands).as_deref(), Some("Finder wasn't running."));
+    }
+}
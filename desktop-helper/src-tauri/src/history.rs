@@ -0,0 +1,76 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{receipt, storage};
+
+const HISTORY_FILE: &str = "execution_history.json";
+const MAX_ENTRIES: usize = 500;
+
+// One execution or rollback run by the helper
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExecutionRecord {
+    pub id: String,
+    pub action_id: String,
+    pub kind: String,
+    pub success: bool,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub rollback_id: Option<String>,
+    // The helper stopped (crash, quit) before the run finished
+    #[serde(default)]
+    pub interrupted: bool,
+    // The user stopped it with abort-all
+    #[serde(default)]
+    pub aborted: bool,
+    // Which approval authorized the run, for executions
+    #[serde(default)]
+    pub receipt: Option<receipt::Receipt>,
+}
+
+impl ExecutionRecord {
+    pub fn new(action_id: &str, kind: &str, started_at: DateTime<Utc>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            action_id: action_id.to_string(),
+            kind: kind.to_string(),
+            success: false,
+            started_at,
+            finished_at: Utc::now(),
+            rollback_id: None,
+            interrupted: false,
+            aborted: false,
+            receipt: None,
+        }
+    }
+}
+
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn record(entry: ExecutionRecord) {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let mut entries: Vec<ExecutionRecord> = storage::load_json(HISTORY_FILE);
+    entries.push(entry);
+    if entries.len() > MAX_ENTRIES {
+        let excess = entries.len() - MAX_ENTRIES;
+        entries.drain(..excess);
+    }
+    if let Err(e) = storage::save_json(HISTORY_FILE, &entries) {
+        log::error!("Failed to persist execution history: {}", e);
+    }
+}
+
+pub fn since(since: DateTime<Utc>) -> Vec<ExecutionRecord> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let entries: Vec<ExecutionRecord> = storage::load_json(HISTORY_FILE);
+    entries.into_iter().filter(|e| e.started_at >= since).collect()
+}
+
+// The latest `limit` records, newest last
+pub fn recent(limit: usize) -> Vec<ExecutionRecord> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let mut entries: Vec<ExecutionRecord> = storage::load_json(HISTORY_FILE);
+    let skip = entries.len().saturating_sub(limit);
+    entries.split_off(skip)
+}
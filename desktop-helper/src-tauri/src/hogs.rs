@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+use crate::processes::{self, ProcessInfo};
+
+// GET /health/hogs?limit=: the top processes by CPU, memory, disk I/O and
+// energy over a one-second sample, named the way the user knows them ("Google
+// Chrome", not "Google Chrome Helper (Renderer)"), as a ready answer to "why
+// is my computer slow?". Energy impact is macOS's own figure from top;
+// elsewhere it's estimated from CPU use. Uses the same local consent as
+// /processes.
+const DEFAULT_LIMIT: usize = 5;
+const MAX_LIMIT: usize = 20;
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Deserialize)]
+pub struct HogsQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Hog {
+    pid: u32,
+    name: String,
+    // The app the process belongs to, when it could be resolved
+    app: Option<String>,
+    bundle_id: Option<String>,
+    cpu_percent: f64,
+    rss_bytes: u64,
+    disk_bytes_per_sec: u64,
+    energy_impact: f64,
+}
+
+// GET /health/hogs
+pub async fn hogs_handler(
+    State(app): State<AppHandle>,
+    Query(query): Query<HogsQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    processes::allowed(&app).await?;
+    let report = tauri::async_runtime::spawn_blocking(move || report(limit))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Resource sample failed: {}", e)))?;
+    Ok(Json(report))
+}
+
+fn report(limit: usize) -> serde_json::Value {
+    // top samples for as long as sysinfo does, so both cover the same second
+    let energy = (std::env::consts::OS == "macos").then(|| std::thread::spawn(macos_energy));
+    let sample = processes::sample(SAMPLE_INTERVAL);
+    let energy = energy.and_then(|handle| handle.join().ok()).unwrap_or_default();
+    let energy_source = if energy.is_empty() { "estimated_from_cpu" } else { "top" };
+
+    let mut hogs: Vec<Hog> = sample
+        .iter()
+        .map(|process| hog(process, energy.get(&process.pid).copied()))
+        .collect();
+    let top = |hogs: &mut Vec<Hog>, key: fn(&Hog) -> f64| {
+        hogs.sort_by(|a, b| key(b).total_cmp(&key(a)));
+        hogs.iter().take(limit).filter(|hog| key(hog) > 0.0).cloned().collect::<Vec<_>>()
+    };
+    let mut by_cpu = top(&mut hogs, |hog| hog.cpu_percent);
+    let mut by_memory = top(&mut hogs, |hog| hog.rss_bytes as f64);
+    let mut by_disk = top(&mut hogs, |hog| hog.disk_bytes_per_sec as f64);
+    let mut by_energy = top(&mut hogs, |hog| hog.energy_impact);
+
+    // Only the processes that made a list get their app looked up
+    let mut paths: Vec<PathBuf> = [&by_cpu, &by_memory, &by_disk, &by_energy]
+        .into_iter()
+        .flatten()
+        .filter_map(|hog| sample.iter().find(|process| process.pid == hog.pid)?.exe.clone())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    let apps = app_names(&paths);
+    for hog in by_cpu.iter_mut().chain(&mut by_memory).chain(&mut by_disk).chain(&mut by_energy) {
+        let exe = sample.iter().find(|process| process.pid == hog.pid).and_then(|process| process.exe.as_ref());
+        if let Some((app, bundle_id)) = exe.and_then(|exe| apps.get(exe)) {
+            hog.app = app.clone();
+            hog.bundle_id = bundle_id.clone();
+        }
+    }
+
+    serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "interval_ms": SAMPLE_INTERVAL.as_millis() as u64,
+        "energy_source": energy_source,
+        "cpu": by_cpu,
+        "memory": by_memory,
+        "disk": by_disk,
+        "energy": by_energy,
+    })
+}
+
+fn hog(process: &ProcessInfo, energy: Option<f64>) -> Hog {
+    Hog {
+        pid: process.pid,
+        name: process.name.clone(),
+        app: None,
+        bundle_id: None,
+        cpu_percent: process.cpu_percent,
+        rss_bytes: process.rss_bytes,
+        disk_bytes_per_sec: (process.disk_bytes as f64 / SAMPLE_INTERVAL.as_secs_f64()) as u64,
+        energy_impact: energy.unwrap_or(process.cpu_percent),
+    }
+}
+
+// Energy impact per pid from the second of two top samples (the first has
+// nothing to compare against)
+fn macos_energy() -> BTreeMap<u32, f64> {
+    let args = ["-l", "2", "-s", "1", "-o", "power", "-n", "50", "-stats", "pid,power"];
+    let Some(output) = read_output("/usr/bin/top", &args) else { return BTreeMap::new() };
+    let Some((_, last)) = output.rsplit_once("\nPID") else { return BTreeMap::new() };
+    last.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            // top marks some pids with a trailing '*'
+            let pid = fields.next()?.trim_end_matches('*').parse().ok()?;
+            Some((pid, fields.next()?.parse().ok()?))
+        })
+        .collect()
+}
+
+// The app name (and bundle id, on macOS) for each executable
+fn app_names(paths: &[PathBuf]) -> BTreeMap<PathBuf, (Option<String>, Option<String>)> {
+    match std::env::consts::OS {
+        "macos" => paths.iter().filter_map(|path| Some((path.clone(), macos_app(path)?))).collect(),
+        "windows" => windows_descriptions(paths),
+        _ => BTreeMap::new(),
+    }
+}
+
+// The outermost .app around the executable, so helpers nested in
+// Contents/Frameworks count as their app
+fn macos_app(path: &Path) -> Option<(Option<String>, Option<String>)> {
+    let bundle = path.ancestors().filter(|ancestor| ancestor.extension().is_some_and(|ext| ext == "app")).last()?;
+    let info = bundle.join("Contents/Info");
+    let info = info.to_string_lossy();
+    let read = |key: &str| read_output("/usr/bin/defaults", &["read", &info, key]).map(|value| value.trim().to_string());
+    let name = read("CFBundleDisplayName")
+        .or_else(|| read("CFBundleName"))
+        .or_else(|| bundle.file_stem().map(|stem| stem.to_string_lossy().into_owned()));
+    Some((name, read("CFBundleIdentifier")))
+}
+
+// Each executable's FileDescription ("Google Chrome", "Microsoft Teams"), in
+// one PowerShell call
+fn windows_descriptions(paths: &[PathBuf]) -> BTreeMap<PathBuf, (Option<String>, Option<String>)> {
+    if paths.is_empty() {
+        return BTreeMap::new();
+    }
+    let list = paths
+        .iter()
+        .map(|path| format!("'{}'", path.to_string_lossy().replace('\'', "''")))
+        .collect::<Vec<_>>()
+        .join(",");
+    let script = format!(
+        "foreach ($p in @({})) {{ $d = (Get-Item -LiteralPath $p -ErrorAction SilentlyContinue).VersionInfo.FileDescription; \"$p`t$d\" }}",
+        list
+    );
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    let output = read_output(&powershell.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", &script]).unwrap_or_default();
+    output
+        .lines()
+        .filter_map(|line| {
+            let (path, description) = line.trim_end().split_once('\t')?;
+            let description = description.trim();
+            (!description.is_empty()).then(|| (PathBuf::from(path), (Some(description.to_string()), None)))
+        })
+        .collect()
+}
@@ -0,0 +1,317 @@
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::net::TcpListener;
+
+use crate::server::{status_port, FALLBACK_PORTS};
+use crate::{deep_link, local_tls, pairing, storage, updates, watchdog};
+
+// Written by the instance serving the API. Only the same OS user can read it,
+// which is what lets a newer helper ask an older one to step aside.
+const INSTANCE_FILE: &str = "helper_instance.json";
+// The active port without the token, for the web client to discover. Read by
+// lib/ohfixit/helper-endpoint.ts, so the name and location are fixed.
+const WELL_KNOWN_FILE: &str = "status_server.json";
+pub const TOKEN_HEADER: &str = "x-ohfixit-instance";
+// Identifies a helper in /status, as opposed to whatever else took the port
+pub const APP_NAME: &str = "ohfixit-desktop-helper";
+
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+// How long the old instance gets to release the port
+const TAKEOVER_WAIT: Duration = Duration::from_secs(5);
+const TAKEOVER_POLL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct Published {
+    pid: u32,
+    port: u16,
+    version: String,
+    token: String,
+    // Served over HTTPS with the certificate from local_tls
+    #[serde(default)]
+    tls: bool,
+}
+
+// Outcome of claiming the status port
+pub enum Bound {
+    Listening(TcpListener, u16),
+    // A helper at least as new as this one serves the API; this launch was
+    // handed to it and should exit
+    AlreadyRunning,
+    Unavailable,
+}
+
+pub fn token() -> &'static str {
+    static TOKEN: OnceLock<String> = OnceLock::new();
+    TOKEN.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+// Bind the status port. When it's taken by an older helper, ask that one to
+// shut down and take over; a helper at least as new as this one gets this
+// launch forwarded to it instead. Another program on the port (or a refused
+// takeover) moves the API to a fallback port.
+pub async fn bind() -> Bound {
+    let preferred = status_port();
+    match try_bind(preferred).await {
+        Ok(listener) => return Bound::Listening(listener, preferred),
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {}
+        Err(e) => {
+            log::error!("Failed to bind status server on port {}: {}", preferred, e);
+            return Bound::Unavailable;
+        }
+    }
+
+    match occupant_version(preferred).await {
+        Some(version) if is_older(&version) => {
+            log::info!("Port {} is held by helper v{}, taking over", preferred, version);
+            match take_over().await {
+                Some(listener) => return Bound::Listening(listener, preferred),
+                None => log::error!("Helper v{} didn't release port {}", version, preferred),
+            }
+        }
+        // Started at the same moment as this one, so the early check in
+        // forward_to_running missed it
+        Some(version) if forward(preferred).await => {
+            log::info!("Helper v{} is already serving on port {}", version, preferred);
+            return Bound::AlreadyRunning;
+        }
+        Some(version) => log::error!("Helper v{} on port {} didn't accept this launch", version, preferred),
+        None => log::error!("Port {} is in use by another program", preferred),
+    }
+
+    for port in FALLBACK_PORTS {
+        match try_bind(*port).await {
+            Ok(listener) => {
+                log::info!("Using fallback port {}", port);
+                return Bound::Listening(listener, *port);
+            }
+            Err(e) => log::error!("Fallback port {} unavailable: {}", port, e),
+        }
+    }
+    // Let the OS pick; clients find it through publish()
+    match try_bind(0).await.and_then(|listener| Ok((listener.local_addr()?.port(), listener))) {
+        Ok((port, listener)) => {
+            log::info!("Using ephemeral port {}", port);
+            Bound::Listening(listener, port)
+        }
+        Err(e) => {
+            log::error!("No port available for the status server: {}", e);
+            Bound::Unavailable
+        }
+    }
+}
+
+// Called before the app starts: hand this launch's arguments to a running
+// helper of the same user that is at least as new, so it keeps the single
+// status server, scheduler and execution queue. Returns true when this
+// process should exit.
+pub fn forward_to_running() -> bool {
+    let published: Published = storage::load_json(INSTANCE_FILE);
+    if published.token.is_empty() {
+        return false;
+    }
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            log::error!("Failed to check for a running helper: {}", e);
+            return false;
+        }
+    };
+    runtime.block_on(async {
+        match occupant_version(published.port).await {
+            Some(version) if !is_older(&version) => forward(published.port).await,
+            _ => false,
+        }
+    })
+}
+
+fn is_older(version: &str) -> bool {
+    updates::compare_versions(env!("CARGO_PKG_VERSION"), version) == Ordering::Greater
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivateRequest {
+    // This launch's command line without the program name (deep links arrive here)
+    args: Vec<String>,
+    cwd: Option<String>,
+}
+
+async fn forward(port: u16) -> bool {
+    let published: Published = storage::load_json(INSTANCE_FILE);
+    if published.token.is_empty() || published.port != port {
+        return false;
+    }
+    let request = ActivateRequest {
+        args: std::env::args().skip(1).collect(),
+        cwd: std::env::current_dir().ok().map(|dir| dir.display().to_string()),
+    };
+    let (client, base_url) = local(port);
+    client
+        .post(format!("{}/instance/activate", base_url))
+        .header(TOKEN_HEADER, &published.token)
+        .timeout(PROBE_TIMEOUT)
+        .json(&request)
+        .send()
+        .await
+        .is_ok_and(|r| r.status().is_success())
+}
+
+// (port, TLS) this instance serves
+static ACTIVE: OnceLock<(u16, bool)> = OnceLock::new();
+
+// Record this instance as the one serving `port`
+pub fn publish(port: u16, tls: bool) {
+    let _ = ACTIVE.set((port, tls));
+    if let Err(e) = storage::save_json(WELL_KNOWN_FILE, &status_server()) {
+        log::error!("Failed to advertise status server port: {}", e);
+    }
+    let published = Published {
+        pid: std::process::id(),
+        port,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        token: token().to_string(),
+        tls,
+    };
+    if let Err(e) = storage::save_json(INSTANCE_FILE, &published) {
+        log::error!("Failed to publish helper instance: {}", e);
+    }
+}
+
+// Where the status server is listening, if it is
+pub fn status_server() -> serde_json::Value {
+    match ACTIVE.get() {
+        Some((port, tls)) => serde_json::json!({
+            "port": port,
+            "url": format!("{}://127.0.0.1:{}", if *tls { "https" } else { "http" }, port),
+            "tls": tls,
+            // What the web app compares against the fingerprint it was sent
+            "cert_sha256": tls.then(local_tls::certificate).flatten().map(|(_, sha256)| sha256),
+            "pid": std::process::id(),
+            "version": env!("CARGO_PKG_VERSION"),
+        }),
+        None => serde_json::Value::Null,
+    }
+}
+
+// For the helper's own UI, which may have missed the "status-server" event
+#[tauri::command]
+pub fn status_server_info() -> serde_json::Value {
+    status_server()
+}
+
+// Client and base URL for the helper instance on `port`: HTTPS trusting this
+// user's local certificate when the instance file says it serves TLS
+fn local(port: u16) -> (reqwest::Client, String) {
+    let published: Published = storage::load_json(INSTANCE_FILE);
+    if published.tls && published.port == port {
+        let client = local_tls::certificate()
+            .and_then(|(pem, _)| reqwest::Certificate::from_pem(pem.as_bytes()).ok())
+            .and_then(|cert| reqwest::Client::builder().add_root_certificate(cert).build().ok());
+        if let Some(client) = client {
+            return (client, format!("https://127.0.0.1:{}", port));
+        }
+    }
+    (reqwest::Client::new(), format!("http://127.0.0.1:{}", port))
+}
+
+async fn try_bind(port: u16) -> std::io::Result<TcpListener> {
+    TcpListener::bind(("127.0.0.1", port)).await
+}
+
+// The version a helper on `port` reports, or None if it isn't one
+async fn occupant_version(port: u16) -> Option<String> {
+    let (client, base_url) = local(port);
+    let status: serde_json::Value = client
+        .get(format!("{}/status", base_url))
+        .header(pairing::SESSION_HEADER, pairing::session_token())
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    // Helpers from before the app field existed still list capabilities
+    let is_helper = status["app"] == APP_NAME || status["capabilities"].is_array();
+    is_helper.then(|| status["version"].as_str().unwrap_or("0").to_string())
+}
+
+async fn take_over() -> Option<TcpListener> {
+    let published: Published = storage::load_json(INSTANCE_FILE);
+    if published.token.is_empty() || published.port != status_port() {
+        return None;
+    }
+    let (client, base_url) = local(status_port());
+    let response = client
+        .post(format!("{}/shutdown", base_url))
+        .header(TOKEN_HEADER, &published.token)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        log::error!("Helper refused to shut down: {}", response.text().await.unwrap_or_default());
+        return None;
+    }
+
+    let deadline = std::time::Instant::now() + TAKEOVER_WAIT;
+    while std::time::Instant::now() < deadline {
+        if let Ok(listener) = try_bind(status_port()).await {
+            return Some(listener);
+        }
+        tokio::time::sleep(TAKEOVER_POLL).await;
+    }
+    None
+}
+
+// POST /shutdown (x-ohfixit-instance: <token from the instance file>): a newer
+// helper taking over the port. Refused while anything is running.
+pub async fn shutdown_handler(State(app): State<AppHandle>) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    if watchdog::has_running() {
+        return Err((StatusCode::CONFLICT, "Executions are in progress".to_string()));
+    }
+    log::info!("Shutting down for a newer helper instance");
+    tauri::async_runtime::spawn(async move {
+        // Let the response go out first
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        app.exit(0);
+    });
+    Ok(Json(serde_json::json!({ "status": "shutting_down" })))
+}
+
+// POST /instance/activate (x-ohfixit-instance): a second launch handing over
+// its arguments. The window is brought forward, the UI gets the arguments as
+// a "second-instance" event, and ohfixit:// URLs go to deep_link.
+pub async fn activate_handler(State(app): State<AppHandle>, Json(request): Json<ActivateRequest>) -> Json<serde_json::Value> {
+    log::info!("Second launch forwarded with {} argument(s)", request.args.len());
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    for url in request.args.iter().filter(|arg| arg.starts_with(deep_link::SCHEME)) {
+        let _ = app.emit("deep-link", url);
+        deep_link::spawn_handle(app.clone(), url.clone());
+    }
+    let _ = app.emit("second-instance", &request);
+    Json(serde_json::json!({ "status": "activated" }))
+}
+
+pub fn valid_token(headers: &HeaderMap) -> bool {
+    headers
+        .get(TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        // Comparing digests keeps the comparison time independent of the token
+        .is_some_and(|v| digest(v) == digest(token()))
+}
+
+pub fn digest(value: &str) -> Vec<u8> {
+    ring::digest::digest(&ring::digest::SHA256, value.as_bytes()).as_ref().to_vec()
+}
@@ -0,0 +1,128 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::DecodingKey;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::{outbound, pairing, scheduler, storage};
+
+// Kept on disk so approvals still verify while the server is briefly unreachable
+const JWKS_CACHE_FILE: &str = "jwks_cache.json";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// A token with an unknown kid (a freshly rotated key) triggers a refetch, at most this often
+const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+const JWKS_PATH: &str = "/.well-known/jwks.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct CachedJwks {
+    url: String,
+    keys: Option<JwkSet>,
+}
+
+static KEYS: Mutex<Option<JwkSet>> = Mutex::new(None);
+static LAST_FETCH: Mutex<Option<Instant>> = Mutex::new(None);
+// Serializes fetches so a burst of unknown-kid tokens makes one request
+static FETCH_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+// OHFIXIT_JWKS_URL, or the well-known path on the OhFixIt server
+fn jwks_url() -> String {
+    std::env::var("OHFIXIT_JWKS_URL").unwrap_or_else(|_| {
+        let server_url = std::env::var("OHFIXIT_SERVER_URL")
+            .unwrap_or_else(|_| "http://localhost:3000".to_string());
+        format!("{}{}", server_url.trim_end_matches('/'), JWKS_PATH)
+    })
+}
+
+pub fn spawn_refresh(client: Client) {
+    scheduler::spawn_job("jwks_refresh", REFRESH_INTERVAL, false, move || {
+        let client = client.clone();
+        async move { fetch(&client).await.map(|count| format!("{} signing key(s)", count)) }
+    });
+}
+
+// The server's public key for `kid`. Only asymmetric keys are returned: a
+// shared secret in the JWKS would let anyone with the helper forge tokens.
+pub async fn key(client: &Client, kid: &str) -> Result<DecodingKey, String> {
+    if let Some(key) = cached_key(kid)? {
+        return Ok(key);
+    }
+    let _guard = FETCH_LOCK.lock().await;
+    if let Some(key) = cached_key(kid)? {
+        return Ok(key);
+    }
+    let recently = LAST_FETCH.lock().unwrap().is_some_and(|at| at.elapsed() < MIN_REFETCH_INTERVAL);
+    if !recently {
+        if let Err(e) = fetch(client).await {
+            log::error!("Failed to refresh signing keys: {}", e);
+        }
+    }
+    cached_key(kid)?.ok_or_else(|| format!("Unknown signing key '{}'", kid))
+}
+
+// Whether there's a key set to check kids against, fetched or cached. An
+// unknown kid only says something about the token when there is.
+pub fn loaded() -> bool {
+    KEYS.lock().unwrap().is_some()
+}
+
+fn cached_key(kid: &str) -> Result<Option<DecodingKey>, String> {
+    let mut keys = KEYS.lock().unwrap();
+    if keys.is_none() {
+        let cached: CachedJwks = storage::load_json(JWKS_CACHE_FILE);
+        // Keys cached for a different server don't count
+        if cached.url == jwks_url() {
+            *keys = cached.keys;
+        }
+    }
+    let Some(jwk) = keys.as_ref().and_then(|set| set.find(kid)) else { return Ok(None) };
+    match jwk.algorithm {
+        AlgorithmParameters::RSA(_) | AlgorithmParameters::EllipticCurve(_) => {
+            DecodingKey::from_jwk(jwk).map(Some).map_err(|e| format!("Invalid signing key '{}': {}", kid, e))
+        }
+        _ => Err(format!("Signing key '{}' is not an RSA or EC public key", kid)),
+    }
+}
+
+// Where to fetch the keys from. Whoever serves them decides which approvals
+// are genuine, so it has to be HTTPS (plain HTTP only to this machine, for
+// development), and the server's own JWKS is checked against the pinned
+// server and certificate like reports are.
+async fn source(client: &Client) -> Result<String, String> {
+    let url = jwks_url();
+    let parsed = Url::parse(&url).map_err(|e| format!("Invalid JWKS URL '{}': {}", url, e))?;
+    if !outbound::https_or_loopback(&parsed) {
+        return Err(format!("Refusing to fetch signing keys from {}: not HTTPS", url));
+    }
+    if std::env::var("OHFIXIT_JWKS_URL").is_err() && pairing::pinned_server().is_some() {
+        return outbound::pinned_url(client, JWKS_PATH).await;
+    }
+    Ok(url)
+}
+
+// `client` is pairing::client(), which exposes the server's certificate for
+// the pin check
+async fn fetch(client: &Client) -> Result<usize, String> {
+    *LAST_FETCH.lock().unwrap() = Some(Instant::now());
+    let url = source(client).await?;
+    let keys: JwkSet = client
+        .get(&url)
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid JWKS from {}: {}", url, e))?;
+
+    let count = keys.keys.len();
+    if let Err(e) = storage::save_json(JWKS_CACHE_FILE, &CachedJwks { url: jwks_url(), keys: Some(keys.clone()) }) {
+        log::error!("Failed to cache signing keys: {}", e);
+    }
+    // Keys dropped from the server's set (rotated out, revoked) stop verifying here too
+    *KEYS.lock().unwrap() = Some(keys);
+    Ok(count)
+}
@@ -0,0 +1,153 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use axum::extract::Query;
+use axum::Json;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::Serialize;
+
+use crate::cache::{CacheQuery, TtlCache};
+use crate::cmd::read_plist;
+
+// Preference keys that usually hold trial/licensing state
+const LICENSE_KEY_HINTS: &[&str] = &["trial", "expir", "licen", "subscri", "registration", "renew"];
+const EXPIRING_SOON_DAYS: i64 = 7;
+
+static SCAN_CACHE: TtlCache = TtlCache::new(std::time::Duration::from_secs(10 * 60));
+
+// A licensing-related value an app stores where we can read it
+#[derive(Debug, Serialize, Clone)]
+pub struct LicenseFinding {
+    pub app: String,
+    pub bundle_id: String,
+    pub source: String,
+    pub key: String,
+    pub value: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub status: String,
+}
+
+// GET /health/licenses[?refresh=true]
+pub async fn licenses_handler(Query(query): Query<CacheQuery>) -> Json<serde_json::Value> {
+    Json(SCAN_CACHE.get_or_compute("licenses", query.refresh, licenses_report).await)
+}
+
+async fn licenses_report() -> serde_json::Value {
+    let findings = tauri::async_runtime::spawn_blocking(scan_licenses)
+        .await
+        .unwrap_or_default();
+
+    let expired: Vec<&LicenseFinding> = findings.iter().filter(|f| f.status == "expired").collect();
+    serde_json::json!({
+        "supported": std::env::consts::OS == "macos",
+        "generated_at": Utc::now().to_rfc3339(),
+        "expired_count": expired.len(),
+        "expiring_soon_count": findings.iter().filter(|f| f.status == "expiring_soon").count(),
+        "findings": findings,
+    })
+}
+
+pub fn scan_licenses() -> Vec<LicenseFinding> {
+    if std::env::consts::OS != "macos" {
+        return vec![];
+    }
+    let Ok(entries) = fs::read_dir("/Applications") else { return vec![] };
+    let home = dirs::home_dir().unwrap_or_default();
+
+    let mut findings = Vec::new();
+    for app in entries.flatten().map(|e| e.path()) {
+        if !app.extension().is_some_and(|ext| ext == "app") {
+            continue;
+        }
+        let Some(info) = read_plist(&app.join("Contents/Info.plist")) else { continue };
+        let Some(bundle_id) = info["CFBundleIdentifier"].as_str() else { continue };
+        let name = app.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+
+        for (source, prefs) in preference_files(&home, bundle_id) {
+            let Some(prefs) = read_plist(&prefs) else { continue };
+            let Some(map) = prefs.as_object() else { continue };
+            for (key, value) in map {
+                let lower = key.to_lowercase();
+                if !LICENSE_KEY_HINTS.iter().any(|hint| lower.contains(hint)) {
+                    continue;
+                }
+                findings.push(classify(&name, bundle_id, &source, key, value));
+            }
+        }
+    }
+
+    findings
+}
+
+// Regular and sandboxed (container) preference locations for a bundle
+fn preference_files(home: &Path, bundle_id: &str) -> Vec<(String, PathBuf)> {
+    let file = format!("{}.plist", bundle_id);
+    vec![
+        ("preferences".to_string(), home.join("Library/Preferences").join(&file)),
+        (
+            "container_preferences".to_string(),
+            home.join("Library/Containers")
+                .join(bundle_id)
+                .join("Data/Library/Preferences")
+                .join(&file),
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, path)| path.exists())
+    .collect()
+}
+
+fn classify(app: &str, bundle_id: &str, source: &str, key: &str, value: &serde_json::Value) -> LicenseFinding {
+    let lower = key.to_lowercase();
+    let expires_at = parse_date(value);
+    let now = Utc::now();
+
+    let status = match (expires_at, value) {
+        (Some(at), _) if at <= now => "expired",
+        (Some(at), _) if at <= now + Duration::days(EXPIRING_SOON_DAYS) => "expiring_soon",
+        (Some(_), _) => "active",
+        // Flags such as "TrialExpired = 1" or "LicenseValid = 0"
+        (None, serde_json::Value::Bool(flag)) => match (lower.contains("expired"), lower.contains("valid"), flag) {
+            (true, _, true) | (_, true, false) => "expired",
+            (true, _, false) | (_, true, true) => "active",
+            _ => "unknown",
+        },
+        _ => "unknown",
+    };
+
+    let mut rendered = match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    // Never echo anything that looks like a license key back to the server
+    if lower.contains("key") || lower.contains("serial") || lower.contains("code") {
+        rendered = "[redacted]".to_string();
+    }
+
+    LicenseFinding {
+        app: app.to_string(),
+        bundle_id: bundle_id.to_string(),
+        source: source.to_string(),
+        key: key.to_string(),
+        value: rendered,
+        expires_at,
+        status: status.to_string(),
+    }
+}
+
+// Dates come out of read_plist as RFC 3339 strings; apps also store epoch seconds
+fn parse_date(value: &serde_json::Value) -> Option<DateTime<Utc>> {
+    match value {
+        serde_json::Value::String(s) => DateTime::parse_from_rfc3339(s).ok().map(|d| d.with_timezone(&Utc)),
+        serde_json::Value::Number(n) => {
+            let secs = n.as_f64()? as i64;
+            // Plausible epoch seconds (2001..2100); milliseconds are scaled down
+            let secs = if secs > 10_000_000_000 { secs / 1000 } else { secs };
+            (978_307_200..4_102_444_800)
+                .contains(&secs)
+                .then(|| Utc.timestamp_opt(secs, 0).single())
+                .flatten()
+        }
+        _ => None,
+    }
+}
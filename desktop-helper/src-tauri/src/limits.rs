@@ -0,0 +1,92 @@
+use std::path::Path;
+use std::process::{Child, Command};
+
+// Background actions (cache purges, scans over ~/Library) run at low CPU and
+// I/O priority so the machine stays usable during a support session.
+// macOS and Linux wrap the command in the OS's own priority tools; Windows
+// sets the priority class at spawn and puts the child in a CPU-capped job.
+
+const TASKPOLICY: &str = "/usr/sbin/taskpolicy";
+const IONICE: &str = "/usr/bin/ionice";
+const NICE: &str = "/usr/bin/nice";
+
+// Share of total CPU a background job may use on Windows, in 1/100 percent
+#[cfg(windows)]
+const WINDOWS_CPU_RATE: u32 = 5000;
+
+// The argv to spawn for a background command. Elevated commands go through
+// this too, so the limits hold under the elevation prompt's shell.
+pub fn background_argv(argv: Vec<String>) -> Vec<String> {
+    let prefix: Vec<&str> = match std::env::consts::OS {
+        // Darwin background policy: lowest CPU priority and throttled disk I/O
+        "macos" => vec![TASKPOLICY, "-b"],
+        // ionice comes with util-linux, which minimal systems may lack
+        "linux" if Path::new(IONICE).exists() => vec![IONICE, "-c", "2", "-n", "7", NICE, "-n", "10"],
+        "linux" => vec![NICE, "-n", "10"],
+        _ => vec![],
+    };
+    prefix.into_iter().map(str::to_string).chain(argv).collect()
+}
+
+pub fn command(argv: &[String], background: bool) -> Command {
+    let argv = if background { background_argv(argv.to_vec()) } else { argv.to_vec() };
+    let mut command = Command::new(&argv[0]);
+    command.args(&argv[1..]);
+    #[cfg(windows)]
+    if background {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(windows_sys::Win32::System::Threading::BELOW_NORMAL_PRIORITY_CLASS);
+    }
+    command
+}
+
+// Called right after spawning a background command
+pub fn contain(child: &Child) {
+    #[cfg(windows)]
+    if let Err(e) = job::limit(child) {
+        log::error!("Failed to limit process {}: {}", child.id(), e);
+    }
+    #[cfg(not(windows))]
+    let _ = child;
+}
+
+#[cfg(windows)]
+mod job {
+    use std::os::windows::io::AsRawHandle;
+    use std::process::Child;
+
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectCpuRateControlInformation, SetInformationJobObject,
+        JOBOBJECT_CPU_RATE_CONTROL_INFORMATION, JOB_OBJECT_CPU_RATE_CONTROL_ENABLE,
+        JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+    };
+
+    // Processes the child starts join the job too. The job lives on until its
+    // last process exits, so the handle can be closed right away.
+    pub fn limit(child: &Child) -> Result<(), String> {
+        unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if job.is_null() {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+            let mut rate: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION = std::mem::zeroed();
+            rate.ControlFlags = JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+            rate.Anonymous.CpuRate = super::WINDOWS_CPU_RATE;
+            let limited = SetInformationJobObject(
+                job,
+                JobObjectCpuRateControlInformation,
+                &rate as *const _ as *const std::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_CPU_RATE_CONTROL_INFORMATION>() as u32,
+            ) != 0
+                && AssignProcessToJobObject(job, child.as_raw_handle() as _) != 0;
+            let error = std::io::Error::last_os_error();
+            CloseHandle(job);
+            if limited {
+                Ok(())
+            } else {
+                Err(error.to_string())
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Datelike, Utc};
+use rcgen::{CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, SanType, PKCS_ECDSA_P256_SHA256};
+use serde::{Deserialize, Serialize};
+
+use crate::{audit, credentials, storage};
+
+// Any local process can listen on or connect to 127.0.0.1, so the status
+// server speaks TLS with a self-signed certificate generated here. The web app
+// learns its fingerprint from the paired device (pairing.rs shares it, signed)
+// and refuses a helper presenting any other. OHFIXIT_LOCAL_TLS=off serves
+// plain HTTP, for browser pages that call the helper directly.
+const TLS_FILE: &str = "local_tls.json";
+const KEY_SECRET: &str = "local_tls_key";
+const CERT_VALIDITY_DAYS: i64 = 365;
+const CERT_RENEW_DAYS: i64 = 30;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct LocalCertificate {
+    certificate: String,
+    sha256: String,
+    expires_at: Option<DateTime<Utc>>,
+    // PKCS#8 PEM; only here when the credential store is unavailable
+    #[serde(default)]
+    key: Option<String>,
+}
+
+pub fn enabled() -> bool {
+    !matches!(
+        std::env::var("OHFIXIT_LOCAL_TLS").unwrap_or_default().to_lowercase().as_str(),
+        "off" | "false" | "0"
+    )
+}
+
+// The certificate PEM and its SHA-256 (hex), once one was generated
+pub fn certificate() -> Option<(String, String)> {
+    let stored: LocalCertificate = storage::load_json(TLS_FILE);
+    (!stored.certificate.is_empty()).then_some((stored.certificate, stored.sha256))
+}
+
+// The server identity, generating a certificate first when there's none or
+// it expires within CERT_RENEW_DAYS
+pub fn identity() -> Result<native_tls::Identity, String> {
+    let mut stored: LocalCertificate = storage::load_json(TLS_FILE);
+    let key = match &stored.key {
+        Some(key) => Some(key.clone()),
+        None if stored.certificate.is_empty() => None,
+        None => credentials::get(KEY_SECRET)?,
+    };
+    let renew_after = Utc::now() + chrono::Duration::days(CERT_RENEW_DAYS);
+    let key = match key {
+        Some(key) if stored.expires_at.is_some_and(|expires| expires > renew_after) => key,
+        _ => {
+            let (certificate, sha256, key, expires_at) = self_signed()?;
+            stored = LocalCertificate {
+                certificate,
+                sha256,
+                expires_at: Some(expires_at),
+                key: None,
+            };
+            // Without a credential store the key stays in local_tls.json, as
+            // the device key does in device.json
+            if let Err(e) = credentials::set(KEY_SECRET, &key) {
+                log::error!("{}", e);
+                stored.key = Some(key.clone());
+            }
+            storage::save_json(TLS_FILE, &stored)?;
+            log::info!("Generated local TLS certificate {}", &stored.sha256[..12]);
+            audit::record("local_certificate_generated", serde_json::json!({
+                "sha256": stored.sha256,
+                "expires_at": expires_at,
+            }));
+            key
+        }
+    };
+    native_tls::Identity::from_pkcs8(stored.certificate.as_bytes(), key.as_bytes())
+        .map_err(|e| format!("Failed to load the local TLS certificate: {}", e))
+}
+
+// (certificate PEM, its SHA-256, PKCS#8 key PEM, expiry) for localhost,
+// 127.0.0.1 and ::1. The SHA-256 is of the DER encoding, lowercase hex, as the
+// web app computes it.
+fn self_signed() -> Result<(String, String, String, DateTime<Utc>), String> {
+    let key_pair = rcgen::KeyPair::generate_for(&PKCS_ECDSA_P256_SHA256)
+        .map_err(|e| format!("Failed to generate local TLS key: {}", e))?;
+    let mut params = CertificateParams::new(vec!["localhost".to_string()])
+        .map_err(|e| format!("Failed to create local TLS certificate: {}", e))?;
+    params.subject_alt_names.push(SanType::IpAddress([127, 0, 0, 1].into()));
+    params.subject_alt_names.push(SanType::IpAddress(std::net::Ipv6Addr::LOCALHOST.into()));
+    let mut name = DistinguishedName::new();
+    name.push(DnType::CommonName, "localhost");
+    name.push(DnType::OrganizationName, "OhFixIt Desktop Helper");
+    params.distinguished_name = name;
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::days(CERT_VALIDITY_DAYS);
+    params.not_before = rcgen::date_time_ymd(now.year(), now.month() as u8, now.day() as u8);
+    params.not_after = rcgen::date_time_ymd(expires_at.year(), expires_at.month() as u8, expires_at.day() as u8);
+    let certificate = params
+        .self_signed(&key_pair)
+        .map_err(|e| format!("Failed to create local TLS certificate: {}", e))?;
+    let digest = ring::digest::digest(&ring::digest::SHA256, certificate.der());
+    let sha256 = digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+    Ok((certificate.pem(), sha256, key_pair.serialize_pem(), expires_at))
+}
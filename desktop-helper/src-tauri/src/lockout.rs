@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::http::{header, HeaderMap};
+use chrono::Utc;
+use reqwest::Client;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{audit, auth, pairing, tray, AppState, Claims};
+
+// Approval tokens that fail validation are counted per source. After
+// OHFIXIT_LOCKOUT_THRESHOLD failures within OHFIXIT_LOCKOUT_WINDOW_SECS the
+// source can't start executions for OHFIXIT_LOCKOUT_SECS, and the user, the
+// audit log and the paired server hear about it.
+const DEFAULT_THRESHOLD: u64 = 5;
+const DEFAULT_WINDOW_SECS: u64 = 5 * 60;
+const DEFAULT_LOCKOUT_SECS: u64 = 15 * 60;
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+// Tokens handed to the helper's own window (Tauri commands)
+pub const WINDOW_SOURCE: &str = "helper window";
+
+struct Failures {
+    started: Instant,
+    count: u64,
+    locked_until: Option<Instant>,
+}
+
+static SOURCES: Mutex<BTreeMap<String, Failures>> = Mutex::new(BTreeMap::new());
+
+fn setting(var: &str, default: u64) -> u64 {
+    std::env::var(var).ok().and_then(|v| v.parse().ok()).filter(|v| *v > 0).unwrap_or(default)
+}
+
+fn window() -> Duration {
+    Duration::from_secs(setting("OHFIXIT_LOCKOUT_WINDOW_SECS", DEFAULT_WINDOW_SECS))
+}
+
+fn lockout() -> Duration {
+    Duration::from_secs(setting("OHFIXIT_LOCKOUT_SECS", DEFAULT_LOCKOUT_SECS))
+}
+
+// Who sent a request to the local API. Every local process connects from
+// 127.0.0.1, so the browser origin (already checked by policy::check_origin)
+// is all that tells callers apart.
+pub fn source(headers: &HeaderMap) -> String {
+    match headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) {
+        Some(origin) => format!("local API ({})", origin),
+        None => "local API".to_string(),
+    }
+}
+
+// Err with the seconds left while `source` is locked out
+pub fn check(source: &str) -> Result<(), u64> {
+    let sources = SOURCES.lock().unwrap();
+    match sources.get(source).and_then(|failures| failures.locked_until) {
+        Some(until) if until > Instant::now() => Err(until.duration_since(Instant::now()).as_secs().max(1)),
+        _ => Ok(()),
+    }
+}
+
+pub fn locked_message(retry_after: u64) -> String {
+    format!("Too many invalid approval tokens; executions are refused for {}s", retry_after)
+}
+
+// Count a failed validation. Reaching the threshold locks the source out and
+// raises the alarm, once per lockout.
+pub fn record_failure(app: &AppHandle, source: &str, error: &str) {
+    let threshold = setting("OHFIXIT_LOCKOUT_THRESHOLD", DEFAULT_THRESHOLD);
+    let failures = {
+        let mut sources = SOURCES.lock().unwrap();
+        let failures = sources.entry(source.to_string()).or_insert_with(|| Failures {
+            started: Instant::now(),
+            count: 0,
+            locked_until: None,
+        });
+        if failures.locked_until.is_some_and(|until| until <= Instant::now()) || failures.started.elapsed() >= window() {
+            *failures = Failures { started: Instant::now(), count: 0, locked_until: None };
+        }
+        failures.count += 1;
+        if failures.count < threshold || failures.locked_until.is_some() {
+            log::info!("Invalid approval token from {} ({} of {}): {}", source, failures.count, threshold, error);
+            return;
+        }
+        failures.locked_until = Some(Instant::now() + lockout());
+        failures.count
+    };
+    alarm(app, source, failures, error);
+}
+
+fn alarm(app: &AppHandle, source: &str, failures: u64, error: &str) {
+    let lockout = lockout();
+    let locked_until = Utc::now() + chrono::Duration::seconds(lockout.as_secs() as i64);
+    let details = serde_json::json!({
+        "source": source,
+        "failures": failures,
+        "window_secs": window().as_secs(),
+        "locked_until": locked_until.to_rfc3339(),
+        "last_error": error,
+    });
+    log::error!("Locked out {} for {}s after {} invalid approval tokens", source, lockout.as_secs(), failures);
+    audit::record("token_lockout", details.clone());
+    tray::alert(
+        app,
+        "Repeated invalid approvals",
+        &format!(
+            "Something on this computer ({}) sent {} invalid approval tokens. OhFixIt won't run fixes it asks for during the next {} minutes.",
+            source,
+            failures,
+            lockout.as_secs() / 60
+        ),
+    );
+    let _ = app.emit("security-alert", &details);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if pairing::status().paired {
+            let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+            if let Err(e) = notify_server(&client, &details).await {
+                log::error!("{}", e);
+            }
+        }
+        tokio::time::sleep(lockout).await;
+        if summary().as_array().is_some_and(|locked| locked.is_empty()) {
+            tray::clear_alert(&app);
+        }
+    });
+}
+
+// Signed with the device key like the other reports from a paired helper
+async fn notify_server(client: &Client, details: &serde_json::Value) -> Result<(), String> {
+    let device_id = pairing::status().device_id;
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let payload = serde_json::json!({
+        "device_id": device_id,
+        "event": "token_lockout",
+        "details": details,
+    })
+    .to_string();
+    let signature = pairing::sign(payload.as_bytes())?;
+
+    client
+        .post(format!("{}/api/automation/helper/security-event", server_url))
+        .timeout(NOTIFY_TIMEOUT)
+        .json(&serde_json::json!({
+            "deviceId": device_id,
+            "payload": payload,
+            "signature": signature,
+        }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to report token lockout: {}", e))?;
+    Ok(())
+}
+
+// auth::validate_once for a token that would start an execution: refused
+// outright while `source` is locked out, and counted when it's invalid (a
+// bad signature or claims, not a replay, an expiry or a JWKS outage)
+pub async fn validate_execution(app: &AppHandle, client: &Client, token: &str, source: &str) -> Result<Claims, String> {
+    check(source).map_err(locked_message)?;
+    auth::validate_once(token, client).await.map_err(|rejection| {
+        if rejection.invalid {
+            record_failure(app, source, &rejection.message);
+        }
+        rejection.message
+    })
+}
+
+// Sources locked out right now, for /status
+pub fn summary() -> serde_json::Value {
+    let sources = SOURCES.lock().unwrap();
+    let now = Instant::now();
+    sources
+        .iter()
+        .filter_map(|(source, failures)| {
+            let until = failures.locked_until.filter(|until| *until > now)?;
+            Some(serde_json::json!({ "source": source, "retry_after": until.duration_since(now).as_secs().max(1) }))
+        })
+        .collect()
+}
@@ -0,0 +1,339 @@
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+use crate::{redact, transcript};
+
+// POST /health/logs/query: recent system log entries, filtered on this
+// machine, so the assistant can read "the last 5 minutes of errors from
+// bluetoothd" itself instead of asking for a pasted log. macOS runs
+// `log show`, Windows `wevtutil qe` over the System and Application logs,
+// Linux journalctl. Like /diagnostics/query, the caller never supplies a
+// predicate: filters are validated values the helper builds one from.
+const DEFAULT_MINUTES: u64 = 5;
+const MAX_MINUTES: u64 = 6 * 60;
+const DEFAULT_LIMIT: usize = 200;
+const MAX_LIMIT: usize = 1000;
+const MAX_MESSAGE_CHARS: usize = 2000;
+// log show over a few busy hours takes a while
+const QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+const LEVELS: &[&str] = &["critical", "error", "warning", "info", "debug"];
+const WINDOWS_LOGS: &[&str] = &["System", "Application"];
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LogQuery {
+    // The macOS subsystem ("com.apple.bluetooth"), Windows event provider or
+    // systemd unit
+    subsystem: Option<String>,
+    // The process (macOS) or syslog identifier (Linux); Windows events have
+    // none to filter on
+    process: Option<String>,
+    // Case-insensitive text the message must contain
+    contains: Option<String>,
+    // The lowest level returned: "critical", "error" (default), "warning",
+    // "info" or "debug"
+    level: Option<String>,
+    #[serde(alias = "lastMinutes")]
+    last_minutes: Option<u64>,
+    limit: Option<usize>,
+    // Windows only: "System" or "Application" (default: both)
+    log: Option<String>,
+    // Attributes the entries to a chat transcript
+    #[serde(default, alias = "chatId")]
+    chat_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct LogEntry {
+    timestamp: Option<String>,
+    // "critical", "error", "warning", "info", "debug" or macOS's "default"
+    level: String,
+    // Subsystem, event provider or systemd unit
+    source: Option<String>,
+    process: Option<String>,
+    pid: Option<u32>,
+    event_id: Option<u32>,
+    message: String,
+}
+
+// POST /health/logs/query
+pub async fn logs_query_handler(Json(query): Json<LogQuery>) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    validate(&query).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let minutes = query.last_minutes.unwrap_or(DEFAULT_MINUTES).clamp(1, MAX_MINUTES);
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let level = query.level.clone().unwrap_or_else(|| "error".to_string());
+
+    let request = query.clone();
+    let run = tauri::async_runtime::spawn_blocking(move || match std::env::consts::OS {
+        "macos" => macos(&request, minutes),
+        "windows" => windows(&request, minutes, limit),
+        _ => linux(&request, minutes, limit),
+    });
+    let (mut entries, source) = tokio::time::timeout(QUERY_TIMEOUT, run)
+        .await
+        .map_err(|_| (StatusCode::GATEWAY_TIMEOUT, format!("The log query took over {}s; narrow the window or add filters", QUERY_TIMEOUT.as_secs())))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Log query failed: {}", e)))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    if let Some(text) = query.contains.as_deref().map(str::to_lowercase) {
+        entries.retain(|entry| entry.message.to_lowercase().contains(&text));
+    }
+    entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    let truncated = entries.len() > limit;
+    entries.truncate(limit);
+    for entry in &mut entries {
+        let (message, _) = redact::redact(&entry.message);
+        entry.message = message.chars().take(MAX_MESSAGE_CHARS).collect();
+    }
+    transcript::record_diagnostics(query.chat_id.as_deref(), "logs", &entries);
+    Ok(Json(serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "source": source,
+        "level": level,
+        "window_minutes": minutes,
+        "count": entries.len(),
+        "truncated": truncated,
+        "entries": entries,
+    })))
+}
+
+// Names end up inside a predicate or XPath query, so quotes, backslashes and
+// control characters are refused rather than escaped
+fn validate(query: &LogQuery) -> Result<(), String> {
+    let name = |field: &str, value: &Option<String>| match value {
+        Some(value) if value.is_empty() || value.len() > 128 || value.chars().any(|c| c.is_control() || matches!(c, '"' | '\'' | '\\')) => {
+            Err(format!("Invalid {} '{}'", field, value))
+        }
+        _ => Ok(()),
+    };
+    name("subsystem", &query.subsystem)?;
+    name("process", &query.process)?;
+    name("contains", &query.contains)?;
+    if let Some(level) = query.level.as_deref().filter(|level| !LEVELS.contains(level)) {
+        return Err(format!("Unknown level '{}': use {}", level, LEVELS.join(", ")));
+    }
+    if let Some(log) = query.log.as_deref().filter(|log| !WINDOWS_LOGS.iter().any(|known| known.eq_ignore_ascii_case(log))) {
+        return Err(format!("Unknown log '{}': use System or Application", log));
+    }
+    Ok(())
+}
+
+// log show --style ndjson, one entry per line:
+// {"timestamp":"2024-05-01 10:22:33.123456-0700","messageType":"Error",
+// "subsystem":"com.apple.bluetooth","processImagePath":"/usr/sbin/bluetoothd",
+// "processID":312,"eventMessage":"…"}
+fn macos(query: &LogQuery, minutes: u64) -> Result<(Vec<LogEntry>, &'static str), String> {
+    let mut predicate = Vec::new();
+    match query.level.as_deref().unwrap_or("error") {
+        "critical" => predicate.push("messageType == fault".to_string()),
+        // The unified log has no warning level
+        "error" | "warning" => predicate.push("(messageType == error OR messageType == fault)".to_string()),
+        _ => {}
+    }
+    if let Some(subsystem) = &query.subsystem {
+        predicate.push(format!("subsystem == \"{}\"", subsystem));
+    }
+    if let Some(process) = &query.process {
+        predicate.push(format!("process == \"{}\"", process));
+    }
+    if let Some(text) = &query.contains {
+        predicate.push(format!("eventMessage CONTAINS[c] \"{}\"", text));
+    }
+    let last = format!("{}m", minutes);
+    let mut args = vec!["show", "--style", "ndjson", "--last", &last];
+    match query.level.as_deref() {
+        Some("info") => args.push("--info"),
+        Some("debug") => args.extend(["--info", "--debug"]),
+        _ => {}
+    }
+    let predicate = predicate.join(" AND ");
+    if !predicate.is_empty() {
+        args.extend(["--predicate", &predicate]);
+    }
+    let output = read_output("/usr/bin/log", &args).ok_or_else(|| "log show failed".to_string())?;
+    let entries = output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|entry| entry["eventMessage"].is_string())
+        .map(|entry| LogEntry {
+            timestamp: entry["timestamp"]
+                .as_str()
+                .and_then(|at| DateTime::parse_from_str(at, "%Y-%m-%d %H:%M:%S%.f%z").ok())
+                .map(|at| at.with_timezone(&Utc).to_rfc3339()),
+            level: match entry["messageType"].as_str().unwrap_or_default() {
+                "Fault" => "critical".to_string(),
+                kind => kind.to_lowercase(),
+            },
+            source: entry["subsystem"].as_str().filter(|s| !s.is_empty()).map(str::to_string),
+            process: entry["processImagePath"].as_str().and_then(|path| path.rsplit('/').next()).map(str::to_string),
+            pid: entry["processID"].as_u64().map(|pid| pid as u32),
+            event_id: None,
+            message: entry["eventMessage"].as_str().unwrap_or_default().to_string(),
+        })
+        .collect();
+    Ok((entries, "log show"))
+}
+
+// wevtutil qe with an XPath filter and rendered XML, newest first; each
+// <Event> carries its provider, id, level, time and rendered message
+fn windows(query: &LogQuery, minutes: u64, limit: usize) -> Result<(Vec<LogEntry>, &'static str), String> {
+    let levels = match query.level.as_deref().unwrap_or("error") {
+        "critical" => Some("Level=1"),
+        "error" => Some("(Level=1 or Level=2)"),
+        "warning" => Some("(Level=1 or Level=2 or Level=3)"),
+        "info" => Some("(Level=0 or Level=1 or Level=2 or Level=3 or Level=4)"),
+        _ => None,
+    };
+    let mut conditions = vec![format!("TimeCreated[timediff(@SystemTime) <= {}]", minutes * 60 * 1000)];
+    conditions.extend(levels.map(str::to_string));
+    if let Some(provider) = &query.subsystem {
+        conditions.push(format!("Provider[@Name='{}']", provider));
+    }
+    let xpath = format!("*[System[{}]]", conditions.join(" and "));
+    // A text filter is applied afterwards, so fetch more to filter from
+    let count = if query.contains.is_some() { MAX_LIMIT } else { limit };
+    let logs: Vec<&str> = match query.log.as_deref() {
+        Some(log) => WINDOWS_LOGS.iter().copied().filter(|known| known.eq_ignore_ascii_case(log)).collect(),
+        None => WINDOWS_LOGS.to_vec(),
+    };
+
+    let wevtutil = system32().join("wevtutil.exe");
+    let mut entries = Vec::new();
+    let mut ran = false;
+    for log in logs {
+        let query_arg = format!("/q:{}", xpath);
+        let count_arg = format!("/c:{}", count);
+        let args = ["qe", log, &query_arg, &count_arg, "/rd:true", "/f:RenderedXml"];
+        let Some(output) = read_output(&wevtutil.to_string_lossy(), &args) else { continue };
+        ran = true;
+        entries.extend(parse_events(&output));
+    }
+    if !ran {
+        return Err("wevtutil failed".to_string());
+    }
+    Ok((entries, "wevtutil"))
+}
+
+fn parse_events(output: &str) -> Vec<LogEntry> {
+    let regex = |pattern| Regex::new(pattern).unwrap();
+    let time = regex(r"<TimeCreated SystemTime='([^']+)'");
+    let level = regex(r"<Level>(\d+)</Level>");
+    let provider = regex(r"<Provider Name='([^']+)'");
+    let pid = regex(r"<Execution ProcessID='(\d+)'");
+    let event_id = regex(r"<EventID[^>]*>(\d+)</EventID>");
+    let message = regex(r"(?s)<Message>(.*?)</Message>");
+    let capture = |regex: &Regex, event: &str| regex.captures(event).and_then(|caps| caps.get(1)).map(|value| unescape(value.as_str()));
+    output
+        .split("</Event>")
+        .filter(|event| event.contains("<Event"))
+        .map(|event| LogEntry {
+            timestamp: capture(&time, event)
+                .and_then(|at| DateTime::parse_from_rfc3339(&at).ok())
+                .map(|at| at.with_timezone(&Utc).to_rfc3339()),
+            level: match capture(&level, event).as_deref() {
+                Some("1") => "critical",
+                Some("2") => "error",
+                Some("3") => "warning",
+                Some("5") => "debug",
+                _ => "info",
+            }
+            .to_string(),
+            source: capture(&provider, event),
+            process: None,
+            pid: capture(&pid, event).and_then(|pid| pid.parse().ok()),
+            event_id: capture(&event_id, event).and_then(|id| id.parse().ok()),
+            message: capture(&message, event).unwrap_or_default().trim().to_string(),
+        })
+        .collect()
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+// journalctl -o json, newest first (-r); MESSAGE is an array of bytes when it
+// isn't valid UTF-8
+fn linux(query: &LogQuery, minutes: u64, limit: usize) -> Result<(Vec<LogEntry>, &'static str), String> {
+    let priority = match query.level.as_deref().unwrap_or("error") {
+        "critical" => "crit",
+        "error" => "err",
+        level => level,
+    };
+    let since = format!("-{}min", minutes);
+    let count = if query.contains.is_some() { MAX_LIMIT } else { limit }.to_string();
+    let mut args = vec!["--no-pager", "-o", "json", "-r", "--since", &since, "-p", priority, "-n", &count];
+    if let Some(unit) = &query.subsystem {
+        args.extend(["-u", unit]);
+    }
+    if let Some(identifier) = &query.process {
+        args.extend(["-t", identifier]);
+    }
+    let output = read_output("journalctl", &args).ok_or_else(|| "journalctl failed".to_string())?;
+    let entries = output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .map(|entry| {
+            let message = match &entry["MESSAGE"] {
+                serde_json::Value::Array(bytes) => {
+                    String::from_utf8_lossy(&bytes.iter().filter_map(|b| b.as_u64().map(|b| b as u8)).collect::<Vec<_>>()).into_owned()
+                }
+                message => message.as_str().unwrap_or_default().to_string(),
+            };
+            LogEntry {
+                timestamp: entry["__REALTIME_TIMESTAMP"]
+                    .as_str()
+                    .and_then(|micros| micros.parse().ok())
+                    .and_then(DateTime::<Utc>::from_timestamp_micros)
+                    .map(|at| at.to_rfc3339()),
+                level: match entry["PRIORITY"].as_str().unwrap_or("6") {
+                    "0" | "1" | "2" => "critical",
+                    "3" => "error",
+                    "4" => "warning",
+                    "7" => "debug",
+                    _ => "info",
+                }
+                .to_string(),
+                source: entry["_SYSTEMD_UNIT"].as_str().map(str::to_string),
+                process: entry["SYSLOG_IDENTIFIER"].as_str().or_else(|| entry["_COMM"].as_str()).map(str::to_string),
+                pid: entry["_PID"].as_str().and_then(|pid| pid.parse().ok()),
+                event_id: None,
+                message,
+            }
+        })
+        .collect();
+    Ok((entries, "journalctl"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_keep_the_numeric_level_on_a_german_system() {
+        let output = "<Event xmlns='http://schemas.microsoft.com/win/2004/08/events/event'><System>\
+            <Provider Name='Microsoft-Windows-Kernel-Power'/><EventID>41</EventID><Level>1</Level>\
+            <TimeCreated SystemTime='2026-10-14T09:12:01.5000000Z'/><Execution ProcessID='4' ThreadID='8'/></System>\
+            <RenderingInfo Culture='de-DE'><Message>Das System wurde neu gestartet, ohne dass es zuvor ordnungsgemäß heruntergefahren wurde.</Message>\
+            <Level>Kritisch</Level></RenderingInfo></Event>\
+            <Event xmlns='http://schemas.microsoft.com/win/2004/08/events/event'><System>\
+            <Provider Name='Service Control Manager'/><EventID Qualifiers='49152'>7000</EventID><Level>2</Level>\
+            <TimeCreated SystemTime='2026-10-14T09:13:00.0000000Z'/></System>\
+            <RenderingInfo Culture='de-DE'><Message>Der Dienst &quot;Druckwarteschlange&quot; wurde nicht gestartet.</Message>\
+            <Level>Fehler</Level></RenderingInfo></Event>";
+        let events = parse_events(output);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].level, "critical");
+        assert_eq!(events[0].event_id, Some(41));
+        assert_eq!(events[0].pid, Some(4));
+        assert_eq!(events[1].level, "error");
+        assert_eq!(events[1].event_id, Some(7000));
+        assert_eq!(events[1].message, "Der Dienst \"Druckwarteschlange\" wurde nicht gestartet.");
+        assert_eq!(events[1].timestamp.as_deref(), Some("2026-10-14T09:13:00+00:00"));
+    }
+}
@@ -3,25 +3,128 @@
     windows_subsystem = "windows"
 )]
 
+mod actions;
+mod apps;
+mod attestation;
+mod audit;
+mod auth;
+mod automation;
+mod batch;
+mod battery;
+mod benchmark;
+mod browser_extensions;
+mod browsers;
+mod cache;
+mod clock;
+mod cmd;
+mod consent;
+mod cpu;
+mod crashes;
+mod credentials;
+mod debug;
+mod deep_link;
+mod disks;
+mod dns;
+mod elevation;
+mod exec_context;
+mod fingerprint;
+mod freeze;
+mod gpu;
+mod guided;
+mod health;
+mod hints;
+mod history;
+mod hogs;
+mod instance;
+mod jwks;
+mod licenses;
+mod limits;
+mod local_tls;
+mod lockout;
+mod logs;
+mod manifest;
+mod net;
+mod nonce_cache;
+mod outbound;
+mod pairing;
+mod panics;
+mod pipeline;
+mod plugins;
+mod policy;
+mod power;
+mod privileges;
+mod probes;
+mod processes;
+mod proxy;
+mod queries;
+mod rate_limit;
+mod receipt;
+mod redact;
+mod rollback;
+mod scheduler;
+mod screenrecord;
+mod screenshot;
+mod secrets;
+mod sensitive_windows;
+mod server;
+mod simulation;
+mod smart;
+mod snapshots;
+mod sockets;
+mod space;
+mod speedtest;
+mod startup;
+mod startup_items;
+mod storage;
+mod thermals;
+mod timeline;
+mod transcript;
+mod transport;
+mod tray;
+mod updates;
+mod vpn;
+mod watchdog;
+mod wifi;
+
 use std::collections::HashMap;
-use std::process::Command;
+use std::process::Stdio;
 use std::sync::Mutex;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use serde::{Deserialize, Serialize};
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
 use chrono::Utc;
 use reqwest::Client;
 use base64::{Engine as _, engine::general_purpose};
 
-// JWT Claims structure for OhFixIt tokens
+// JWT Claims structure for OhFixIt tokens (the server mints camelCase claims)
 #[derive(Debug, Serialize, Deserialize)]
 struct Claims {
+    #[serde(alias = "chatId")]
     chat_id: Option<String>,
+    #[serde(alias = "userId")]
     user_id: Option<String>,
+    #[serde(alias = "anonymousId")]
     anonymous_id: Option<String>,
+    // Empty on scope-only tokens (a health scan, a screenshot), which
+    // authorize no action (see auth::authorize_action)
+    #[serde(alias = "actionId", default)]
     action_id: String,
+    #[serde(alias = "approvalId", default)]
     approval_id: String,
+    // SHA-256 of the approval text the user saw, added by newer servers
+    #[serde(alias = "approvalTextHash")]
+    approval_text_hash: Option<String>,
+    // The paired helper the token was minted for (see pairing::check_binding)
+    #[serde(alias = "deviceId")]
+    device_id: Option<String>,
+    // pairing::fingerprint of that device, as registered with the server
+    #[serde(alias = "deviceFingerprint")]
+    device_fingerprint: Option<String>,
     scope: String,
+    // Required: who minted the token and who it is for (see auth::validate_token)
+    iss: String,
+    aud: String,
+    // Unique per token, for replay protection (see auth::validate_once)
+    jti: String,
     exp: usize,
     iat: usize,
 }
@@ -34,6 +137,15 @@ struct ActionResult {
     error: Option<String>,
     artifacts: Option<Vec<ActionArtifact>>,
     rollback_id: Option<String>,
+    environment: Option<fingerprint::EnvFingerprint>,
+    // Only set for actions that needed administrator rights
+    elevation: Option<elevation::ElevationStatus>,
+    // Nothing was executed, the output came from simulation fixtures
+    simulated: bool,
+    risk: Option<Risk>,
+    category: Option<Category>,
+    // Measured before running, for cleanup actions
+    estimated_bytes_freed: Option<u64>,
 }
 
 // Action artifact structure
@@ -43,6 +155,14 @@ struct ActionArtifact {
     uri: Option<String>,
     hash: Option<String>,
     data: Option<String>,
+    metadata: Option<serde_json::Value>,
+}
+
+// What running an action's commands produced
+struct Execution {
+    success: bool,
+    output: String,
+    commands: Vec<exec_context::CommandRun>,
 }
 
 // Rollback point structure
@@ -52,6 +172,24 @@ struct RollbackPoint {
     data: serde_json::Value,
 }
 
+// How much could go wrong; high-risk actions get a stronger consent prompt
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Risk {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Category {
+    Network,
+    Storage,
+    Ui,
+    Security,
+}
+
 // Allowlisted action definitions
 #[derive(Debug, Clone)]
 struct ActionDefinition {
@@ -64,6 +202,36 @@ struct ActionDefinition {
     estimated_time: String,
     requirements: Vec<String>,
     creates_backup: bool,
+    // (key, command) pairs run before execution; the last word of each output
+    // is stored in the rollback point and available as {state.<key>}
+    state_captures: Vec<(String, String)>,
+    // Paths restored from a volume snapshot taken before execution
+    snapshot_paths: Vec<String>,
+    // Must pass before anything runs
+    preflight: Vec<probes::Probe>,
+    // Must pass afterwards for the action to count as successful
+    postconditions: Vec<probes::Probe>,
+    // JSON Schema for the parameters the action accepts
+    parameters: serde_json::Value,
+    // Commands run through the OS elevation prompt instead of as the user
+    elevated: bool,
+    // Needs someone at the screen (a prompt, a UI restart), so held while locked
+    interactive: bool,
+    // Long, heavy commands run at low CPU and I/O priority (see limits)
+    background: bool,
+    risk: Risk,
+    category: Category,
+    // What a cleanup action deletes, measured beforehand (see space::estimate)
+    space_estimate_targets: Vec<String>,
+    // Plain-language explanations for known failure exit codes
+    exit_hints: Vec<hints::ExitHint>,
+    // SHA-256 of the WASM module for organization plugin actions, which run
+    // through plugins::execute instead of commands
+    plugin: Option<String>,
+    // Binaries may also come from Homebrew's prefixes, which the user can
+    // write to. Only the built-in brew and mas actions set it, and it's
+    // ignored for elevated commands.
+    homebrew: bool,
 }
 
 impl ActionDefinition {
@@ -76,337 +244,746 @@ impl ActionDefinition {
             rollback_commands: vec![],
             reversible: true,
             estimated_time: "10 seconds".to_string(),
-            requirements: vec!["Administrator privileges".to_string()],
+            requirements: vec![],
             creates_backup: false,
+            state_captures: vec![],
+            snapshot_paths: vec![],
+            preflight: vec![],
+            postconditions: vec![],
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {},
+                "additionalProperties": false,
+            }),
+            elevated: false,
+            interactive: false,
+            background: false,
+            risk: Risk::Low,
+            category: Category::Ui,
+            space_estimate_targets: vec![],
+            exit_hints: vec![],
+            plugin: None,
+            homebrew: false,
         }
     }
 
+    fn with_risk(mut self, risk: Risk) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    fn in_category(mut self, category: Category) -> Self {
+        self.category = category;
+        self
+    }
+
+    fn with_space_estimate(mut self, targets: Vec<&str>) -> Self {
+        self.space_estimate_targets = targets.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    fn with_exit_hint(mut self, program: &str, code: i32, hint: &str) -> Self {
+        self.exit_hints.push(hints::ExitHint {
+            program: program.to_string(),
+            code,
+            hint: hint.to_string(),
+            tolerated: false,
+        });
+        self
+    }
+
+    // An exit code that means there was nothing to do: the command counts as
+    // succeeded
+    fn with_tolerated_exit(mut self, program: &str, code: i32, hint: &str) -> Self {
+        self.exit_hints.push(hints::ExitHint {
+            program: program.to_string(),
+            code,
+            hint: hint.to_string(),
+            tolerated: true,
+        });
+        self
+    }
+
     fn with_rollback(mut self, rollback_commands: Vec<&str>) -> Self {
         self.rollback_commands = rollback_commands.iter().map(|s| s.to_string()).collect();
         self.creates_backup = true;
         self
     }
+
+    fn elevated(mut self) -> Self {
+        self.elevated = true;
+        self.interactive = true;
+        self.requirements.push("Administrator privileges".to_string());
+        self
+    }
+
+    fn interactive(mut self) -> Self {
+        self.interactive = true;
+        self
+    }
+
+    fn in_background(mut self) -> Self {
+        self.background = true;
+        self
+    }
+
+    fn with_state_capture(mut self, key: &str, command: &str) -> Self {
+        self.state_captures.push((key.to_string(), command.to_string()));
+        self
+    }
+
+    fn with_volume_snapshot(mut self, paths: Vec<&str>) -> Self {
+        self.snapshot_paths = paths.iter().map(|s| s.to_string()).collect();
+        self.creates_backup = true;
+        self
+    }
+
+    fn with_preflight(mut self, probe: probes::Probe) -> Self {
+        self.preflight.push(probe);
+        self
+    }
+
+    fn with_postcondition(mut self, probe: probes::Probe) -> Self {
+        self.postconditions.push(probe);
+        self
+    }
+
+    fn irreversible(mut self) -> Self {
+        self.reversible = false;
+        self
+    }
+
+    fn with_homebrew_binaries(mut self) -> Self {
+        self.homebrew = true;
+        self
+    }
+
+    fn with_estimated_time(mut self, estimated_time: &str) -> Self {
+        self.estimated_time = estimated_time.to_string();
+        self
+    }
+
+    fn with_requirements(mut self, requirements: Vec<&str>) -> Self {
+        self.requirements = requirements.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    fn has_rollback(&self) -> bool {
+        self.reversible && (!self.rollback_commands.is_empty() || !self.snapshot_paths.is_empty())
+    }
+
+    // What the web app needs to present an action, without the raw commands
+    fn describe(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "title": self.title,
+            "os": self.os,
+            "reversible": self.has_rollback(),
+            "creates_backup": self.creates_backup,
+            "estimated_time": self.estimated_time,
+            "requirements": self.requirements,
+            "elevated": self.elevated,
+            "interactive": self.interactive,
+            "background": self.background,
+            "risk": self.risk,
+            "category": self.category,
+            "frees_space": !self.space_estimate_targets.is_empty(),
+            "plugin": self.plugin.is_some(),
+            "parameters": self.parameters,
+        })
+    }
 }
 
+// How long a held action waits for the screen to be unlocked
+const UNLOCK_WAIT: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+const UNLOCK_POLL: std::time::Duration = std::time::Duration::from_secs(5);
+
+// Approval ids that have already authorized an execution
+static USED_APPROVALS: nonce_cache::ConsumedCache = nonce_cache::ConsumedCache::new("used_approvals.json");
+
 // Global state for tracking executions
 struct AppState {
     actions: HashMap<String, ActionDefinition>,
     client: Client,
-    jwt_secret: String,
+    // HMAC key for execution receipts and transcripts. Approval tokens are
+    // verified against the server's public keys instead (see jwks). None when
+    // the credential store can't hold one; nothing is signed or run then.
+    jwt_secret: Option<String>,
+    // Version of the signed remote manifest in use, None for the built-in allowlist
+    manifest_version: Option<u64>,
+    // Set while execution is frozen (see freeze.rs)
+    frozen: Option<freeze::Freeze>,
 }
 
 impl AppState {
     fn new() -> Self {
-        let mut actions = HashMap::new();
-
-        // Initialize allowlisted actions for macOS with rollback support
-        actions.insert(
-            "flush-dns-macos".to_string(),
-            ActionDefinition::new(
-                "flush-dns-macos",
-                "Flush DNS Cache (macOS)",
-                "macos",
-                vec![
-                    "sudo dscacheutil -flushcache",
-                    "sudo killall -HUP mDNSResponder"
-                ]
-            )
-        );
-
-        actions.insert(
-            "toggle-wifi-macos".to_string(),
-            ActionDefinition::new(
-                "toggle-wifi-macos",
-                "Toggle Wi‑Fi (macOS)",
-                "macos",
-                vec![
-                    "networksetup -getairportpower en0 > /tmp/wifi_state_backup.txt",
-                    "networksetup -setairportpower en0 off",
-                    "sleep 2",
-                    "networksetup -setairportpower en0 on"
-                ]
-            ).with_rollback(vec![
-                "if grep -q 'On' /tmp/wifi_state_backup.txt; then networksetup -setairportpower en0 on; else networksetup -setairportpower en0 off; fi",
-                "rm -f /tmp/wifi_state_backup.txt"
-            ])
-        );
-
-        actions.insert(
-            "clear-app-cache".to_string(),
-            ActionDefinition::new(
-                "clear-app-cache",
-                "Clear App Cache (macOS)",
-                "macos",
-                vec![
-                    "mkdir -p /tmp/cache_backup_$(date +%s)",
-                    "find ~/Library/Caches -name \"*.cache\" -type f -exec cp {} /tmp/cache_backup_$(date +%s)/ \\; 2>/dev/null || true",
-                    "find ~/Library/Caches -name \"*.cache\" -type f -delete 2>/dev/null || true"
-                ]
-            ).with_rollback(vec![
-                "latest_backup=$(ls -t /tmp/cache_backup_* | head -1)",
-                "if [ -d \"$latest_backup\" ]; then cp \"$latest_backup\"/* ~/Library/Caches/ 2>/dev/null || true; fi"
-            ])
-        );
-
-        // Additional safe macOS actions
-        actions.insert(
-            "restart-finder".to_string(),
-            ActionDefinition::new(
-                "restart-finder",
-                "Restart Finder (macOS)",
-                "macos",
-                vec![
-                    "killall Finder"
-                ]
-            )
-        );
-
-        actions.insert(
-            "clear-recent-items".to_string(),
-            ActionDefinition::new(
-                "clear-recent-items",
-                "Clear Recent Items (macOS)",
-                "macos",
-                vec![
-                    "defaults delete com.apple.recentitems RecentApplications 2>/dev/null || true",
-                    "defaults delete com.apple.recentitems RecentDocuments 2>/dev/null || true",
-                    "defaults delete com.apple.recentitems RecentServers 2>/dev/null || true"
-                ]
-            )
-        );
-
-        actions.insert(
-            "reset-launchpad".to_string(),
-            ActionDefinition::new(
-                "reset-launchpad",
-                "Reset Launchpad Layout (macOS)",
-                "macos",
-                vec![
-                    "defaults write com.apple.dock ResetLaunchPad -bool true",
-                    "killall Dock"
-                ]
-            )
-        );
-
-        actions.insert(
-            "clear-system-logs".to_string(),
-            ActionDefinition::new(
-                "clear-system-logs",
-                "Clear Old System Logs (macOS)",
-                "macos",
-                vec![
-                    "sudo rm -rf /private/var/log/asl/*.asl 2>/dev/null || true",
-                    "sudo rm -rf /private/var/log/DiagnosticMessages/*.asl 2>/dev/null || true"
-                ]
-            )
-        );
-
         Self {
-            actions,
-            client: Client::new(),
-            jwt_secret: std::env::var("OHFIXIT_JWT_SECRET")
-                .unwrap_or_else(|_| "default-secret-change-in-production".to_string()),
+            actions: actions::builtin(),
+            client: pairing::client(),
+            jwt_secret: credentials::generated_secret("jwt_secret", "OHFIXIT_JWT_SECRET"),
+            manifest_version: None,
+            frozen: freeze::load(),
         }
     }
 }
 
 #[tauri::command]
-async fn get_health_status() -> Result<serde_json::Value, String> {
+async fn get_health_status(app: AppHandle) -> Result<serde_json::Value, String> {
+    startup::catalog_ready().await;
     Ok(serde_json::json!({
         "status": "healthy",
         "version": "0.1.0",
         "timestamp": chrono::Utc::now().to_rfc3339(),
-        "actions_available": 7
+        "actions_available": actions::available(&app).len()
     }))
 }
 
 #[tauri::command]
 async fn execute_rollback(
     app: AppHandle,
-    state: tauri::State<'_, Mutex<AppState>>,
     action_id: String,
     rollback_id: String,
     token: String,
 ) -> Result<ActionResult, String> {
+    let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+    lockout::validate_execution(&app, &client, &token, lockout::WINDOW_SOURCE).await?;
+    run_rollback(&app, &action_id, &rollback_id, &token).await
+}
+
+// Shared by the Tauri command and batch execution
+async fn run_rollback(app: &AppHandle, action_id: &str, rollback_id: &str, token: &str) -> Result<ActionResult, String> {
+    startup::catalog_ready().await;
+    let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+    let run = pipeline::Run::requested(app, &client, token, action_id, "rollback");
+    rollback_run(app, &run, &client, rollback_id, token).await.map_err(|e| run.rejected(e))
+}
+
+async fn rollback_run(
+    app: &AppHandle,
+    run: &pipeline::Run,
+    client: &Client,
+    rollback_id: &str,
+    token: &str,
+) -> Result<ActionResult, String> {
+    freeze::check(app)?;
+    let (action_id, rollback_id) = (run.action_id().to_string(), rollback_id.to_string());
+
     // Extract data from state before async operations
-    let (jwt_secret, action, client) = {
+    let action = {
+        let state = app.state::<Mutex<AppState>>();
         let state = state.lock().unwrap();
-        let action = state.actions.get(&action_id)
+        state.actions.get(&action_id)
             .ok_or_else(|| format!("Action '{}' not allowlisted", action_id))?
-            .clone();
-        (state.jwt_secret.clone(), action, state.client.clone())
+            .clone()
     };
 
     // Validate JWT token
-    let validation = Validation::new(Algorithm::HS256);
-    let token_data = decode::<Claims>(
-        &token,
-        &DecodingKey::from_secret(jwt_secret.as_bytes()),
-        &validation
-    ).map_err(|e| format!("Invalid token: {}", e))?;
+    let claims = auth::validate_token(token, client).await?;
+    auth::authorize_action(&claims, &action_id)?;
 
-    let claims = token_data.claims;
+    if !action.has_rollback() {
+        return Err(format!("Action '{}' is not reversible", action_id));
+    }
 
-    // Check if token is expired
-    let now = Utc::now().timestamp() as usize;
-    if claims.exp < now {
-        return Err("Token expired".to_string());
+    // Restore from the exact point recorded when the action ran
+    let record = rollback::get(&rollback_id)
+        .ok_or_else(|| format!("Unknown rollback point '{}'", rollback_id))?;
+    if record.action_id != action_id {
+        return Err(format!(
+            "Rollback point '{}' belongs to action '{}', not '{}'",
+            rollback_id, record.action_id, action_id
+        ));
     }
+    if record.expires_at <= Utc::now() {
+        return Err(format!("Rollback window for '{}' closed at {}", rollback_id, record.expires_at.to_rfc3339()));
+    }
+    run.validated(&claims);
 
-    if !action.reversible || action.rollback_commands.is_empty() {
-        return Err(format!("Action '{}' is not reversible", action_id));
+    // Restoring from a volume snapshot always needs administrator rights
+    let elevated = action.elevated || record.snapshot.is_some();
+    if elevated || action.interactive {
+        wait_for_unlock(app, &action).await?;
     }
 
     // Log rollback start
     log::info!("Starting rollback of action: {} (rollback_id: {})", action_id, rollback_id);
-    emit_status(&app, &format!("🔄 Rolling back {}...", action.title), "rolling_back");
+    run.running(&format!("🔄 Rolling back {}...", action.title));
 
     // Execute the rollback commands
-    let result = execute_commands(&action.rollback_commands).await;
+    let awake = power::KeepAwake::acquire(&format!("Rolling back {}", action.title));
+    let running = watchdog::begin(run.id(), &action_id, "rollback", &claims.approval_id, Some(&rollback_id));
+    let result = execute_commands(&record.restore_commands(&action), &record.context(), &action, elevated, &running, run).await;
+    let aborted = running.aborted();
+    drop(running);
+    drop(awake);
 
-    match result {
-        Ok((success, output)) => {
+    let action_result = match result {
+        Ok(execution) => {
+            let success = execution.success;
             let message = if success {
                 format!("✅ {} rollback completed successfully", action.title)
             } else {
                 format!("❌ {} rollback failed", action.title)
             };
 
-            emit_status(&app, &message, if success { "success" } else { "error" });
-
-            // Report rollback result back to server
-            if let Err(e) = report_rollback_result(&client, &token, &action_id, &rollback_id, success, &output).await {
-                log::error!("Failed to report rollback result: {}", e);
+            // A restored point can't be applied twice; keep it around if the restore failed
+            if success {
+                if let Err(e) = tokio::task::block_in_place(|| rollback::discard(&rollback_id)) {
+                    log::error!("Failed to discard rollback point {}: {}", rollback_id, e);
+                }
             }
 
-            Ok(ActionResult {
+            let output = execution.output.clone();
+            let mut outcome = pipeline::Outcome::new(execution, message);
+            outcome.aborted = aborted;
+            outcome.rollback_id = Some(rollback_id.clone());
+            run.finish(outcome).await;
+
+            ActionResult {
                 success,
                 message: output.clone(),
                 error: if success { None } else { Some(output) },
                 artifacts: Some(vec![]),
                 rollback_id: None,
-            })
+                environment: Some(fingerprint::current()),
+                elevation: elevated.then_some(elevation::ElevationStatus::Granted),
+                simulated: false,
+                risk: Some(action.risk),
+                category: Some(action.category),
+                estimated_bytes_freed: None,
+            }
         }
-        Err(e) => {
-            let error_msg = format!("❌ {} rollback execution error: {}", action.title, e);
-            emit_status(&app, &error_msg, "error");
+        Err(status) => {
+            // The rollback point is kept so the user can try again
+            let error_msg = format!("❌ {} rollback: {}", action.title, status.describe());
+            let execution = Execution { success: false, output: error_msg.clone(), commands: vec![] };
+            let mut outcome = pipeline::Outcome::new(execution, error_msg.clone());
+            outcome.rollback_id = Some(rollback_id.clone());
+            run.finish(outcome).await;
 
-            Ok(ActionResult {
+            ActionResult {
                 success: false,
                 message: error_msg.clone(),
                 error: Some(error_msg),
                 artifacts: None,
-                rollback_id: None,
-            })
+                rollback_id: Some(rollback_id),
+                environment: Some(fingerprint::current()),
+                elevation: Some(status),
+                simulated: false,
+                risk: Some(action.risk),
+                category: Some(action.category),
+                estimated_bytes_freed: None,
+            }
         }
-    }
+    };
+
+    transcript::record_action(claims.chat_id.as_deref(), "rollback", &action_id, &claims.approval_id, &action_result);
+    Ok(action_result)
 }
 
 #[tauri::command]
 async fn execute_action(
     app: AppHandle,
-    state: tauri::State<'_, Mutex<AppState>>,
     action_id: String,
-    _parameters: String,
+    parameters: String,
     token: String,
 ) -> Result<ActionResult, String> {
+    // {"simulate": true} asks for a dry run of this one execution
+    let simulate = serde_json::from_str::<serde_json::Value>(&parameters)
+        .ok()
+        .and_then(|p| p.get("simulate").and_then(|v| v.as_bool()))
+        .unwrap_or(false);
+    let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+    lockout::validate_execution(&app, &client, &token, lockout::WINDOW_SOURCE).await?;
+    run_action(&app, &action_id, &token, simulate).await
+}
+
+// Shared by the Tauri command and POST /automation/execute. A simulated run goes
+// through authorization and approval like a real one but executes nothing.
+async fn run_action(app: &AppHandle, action_id: &str, token: &str, simulate: bool) -> Result<ActionResult, String> {
+    startup::catalog_ready().await;
+    let simulate = simulate || simulation::enabled();
+    let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+    let run = pipeline::Run::requested(app, &client, token, action_id, if simulate { "simulate" } else { "execute" });
+    // Anything refused before the run starts ends the pipeline as rejected
+    execute_run(app, &run, &client, token, simulate).await.map_err(|e| run.rejected(e))
+}
+
+async fn execute_run(
+    app: &AppHandle,
+    run: &pipeline::Run,
+    client: &Client,
+    token: &str,
+    simulate: bool,
+) -> Result<ActionResult, String> {
+    freeze::check(app)?;
+    let action_id = run.action_id().to_string();
+
     // Extract data from state before async operations
-    let (jwt_secret, action, client) = {
+    let (jwt_secret, action) = {
+        let state = app.state::<Mutex<AppState>>();
         let state = state.lock().unwrap();
         let action = state.actions.get(&action_id)
             .ok_or_else(|| format!("Action '{}' not allowlisted", action_id))?
             .clone();
-        (state.jwt_secret.clone(), action, state.client.clone())
+        (state.jwt_secret.clone(), action)
     };
+    let jwt_secret = jwt_secret.ok_or_else(|| "No receipt signing key: the credential store is unavailable".to_string())?;
 
-    // Validate JWT token
-    let validation = Validation::new(Algorithm::HS256);
-    let token_data = decode::<Claims>(
-        &token,
-        &DecodingKey::from_secret(jwt_secret.as_bytes()),
-        &validation
-    ).map_err(|e| format!("Invalid token: {}", e))?;
+    // Validate JWT token and make sure it was minted for this action
+    let claims = auth::validate_token(token, client).await?;
+    if let Err(e) = auth::authorize_action(&claims, &action_id) {
+        audit::record("token_action_mismatch", serde_json::json!({
+            "action_id": action_id,
+            "token_action_id": claims.action_id,
+            "scope": claims.scope,
+        }));
+        return Err(e);
+    }
+
+    // Check OS compatibility
+    if action.os != std::env::consts::OS {
+        return Err(format!(
+            "Action '{}' is for {}, not {}",
+            action_id, action.os, std::env::consts::OS
+        ));
+    }
+    run.validated(&claims);
 
-    let claims = token_data.claims;
+    // Don't start something the user can't see through or the battery can't finish
+    if action.interactive && !simulate {
+        wait_for_unlock(app, &action).await?;
+    }
+    if !simulate && power::sleep_imminent() {
+        return Err(format!("Battery is critically low, plug in before running '{}'", action_id));
+    }
 
-    // Check if token is expired
-    let now = Utc::now().timestamp() as usize;
-    if claims.exp < now {
-        return Err("Token expired".to_string());
+    // Each approval authorizes exactly one execution
+    if claims.approval_id.is_empty() {
+        return Err(format!("Token carries no approval for '{}'", action_id));
     }
+    let approval_expires_at = chrono::DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or_else(Utc::now);
+    if let Err(consumed_at) = USED_APPROVALS.consume(&claims.approval_id, approval_expires_at) {
+        audit::record("approval_reuse_rejected", serde_json::json!({
+            "action_id": action_id,
+            "approval_id": claims.approval_id,
+            "consumed_at": consumed_at.to_rfc3339(),
+        }));
+        return Err(format!(
+            "Approval '{}' already consumed at {}",
+            claims.approval_id,
+            consumed_at.to_rfc3339()
+        ));
+    }
+    run.consented(&claims.approval_id);
 
-    // Check OS compatibility
-    #[cfg(target_os = "macos")]
-    if action.os != "macos" {
-        return Err(format!("Action '{}' not compatible with macOS", action_id));
+    // Cleanup actions say up front how much they'll free
+    let space_estimate = if action.space_estimate_targets.is_empty() {
+        None
+    } else {
+        let targets = action.space_estimate_targets.clone();
+        let bytes = tauri::async_runtime::spawn_blocking(move || space::estimate(&targets))
+            .await
+            .unwrap_or(0);
+        emit_status(app, &format!("🧹 {} will be freed", space::describe(bytes)), "executing");
+        Some(bytes)
+    };
+
+    if simulate {
+        let action_result = simulate_action(run, &action, space_estimate).await;
+        transcript::record_action(claims.chat_id.as_deref(), "simulate", &action_id, &claims.approval_id, &action_result);
+        return Ok(action_result);
+    }
+
+    // Refuse to start if the machine isn't in the state the action expects
+    if let Some(failures) = probes::describe_failures(&probes::run_all(&action.preflight)) {
+        return Err(format!("Preflight checks failed for '{}': {}", action_id, failures));
+    }
+
+    // Beyond the server-side approval, someone at this computer has to agree
+    if consent::required(&action) {
+        emit_status(app, &format!("🛑 Confirm {} in the dialog to continue", action.title), "waiting");
+        consent::confirm_locally(app, &action, &claims.approval_id).await?;
     }
 
     // Log execution start
     log::info!("Starting execution of action: {}", action_id);
-    emit_status(&app, &format!("⚡ Executing {}...", action.title), "executing");
+    run.running(&format!("⚡ Executing {}...", action.title));
+
+    // Sleeping halfway through a backup or fix is worse than not starting
+    let awake = power::KeepAwake::acquire(&format!("Running {}", action.title));
+
+    // Capture prior state and a backup location before changing anything
+    let rollback_record = if action.has_rollback() {
+        // A volume snapshot may ask for administrator rights first
+        Some(tokio::task::block_in_place(|| rollback::prepare(&action))?)
+    } else {
+        None
+    };
+    let context = rollback_record
+        .as_ref()
+        .map(|r| r.context())
+        .unwrap_or_default();
 
     // Execute the action
-    let result = execute_commands(&action.commands).await;
+    let running = watchdog::begin(run.id(), &action_id, "execute", &claims.approval_id, None);
+    let result = match action.plugin {
+        Some(_) => Ok(tokio::task::block_in_place(|| plugins::execute(&action_id, &running))),
+        None => execute_commands(&action.commands, &context, &action, action.elevated, &running, run).await,
+    };
 
-    match result {
-        Ok((success, output)) => {
-            let message = if success {
-                format!("✅ {} completed successfully", action.title)
-            } else {
-                format!("❌ {} failed", action.title)
+    // Commands exiting cleanly isn't enough, the expected outcome must hold too
+    let result = result.map(|mut execution| {
+        if !execution.success || action.postconditions.is_empty() {
+            return execution;
+        }
+        let verification = probes::run_all(&action.postconditions);
+        for check in &verification {
+            execution.output.push_str(&format!("Verify: {} [{}]\n", check.detail, if check.passed { "ok" } else { "failed" }));
+        }
+        execution.success = probes::describe_failures(&verification).is_none();
+        execution
+    });
+    let result = result.map(|mut execution| {
+        if let Some(bytes) = space_estimate {
+            execution.output.push_str(&format!("Estimated space freed: {}\n", space::describe(bytes)));
+        }
+        execution
+    });
+    let aborted = running.aborted();
+    drop(running);
+    drop(awake);
+
+    let action_result = match result {
+        Ok(execution) => {
+            let success = execution.success;
+            // Non-technical users get the known cause instead of raw stderr
+            let hint = if success || aborted { None } else { hints::explain(&action.exit_hints, &execution.commands) };
+            let message = match (success, &hint) {
+                (true, _) => format!("✅ {} completed successfully", action.title),
+                (false, None) if aborted => format!("🛑 {} was stopped", action.title),
+                (false, Some(hint)) => format!("❌ {} failed: {}", action.title, hint),
+                (false, None) => format!("❌ {} failed", action.title),
             };
 
-            emit_status(&app, &message, if success { "success" } else { "error" });
+            // Persist the rollback point even on failure, a partial change may still need undoing
+            let rollback_record = match rollback_record {
+                Some(record) => match rollback::register(&record) {
+                    Ok(()) => Some(record),
+                    Err(e) => {
+                        log::error!("Failed to persist rollback point: {}", e);
+                        None
+                    }
+                },
+                None => None,
+            };
 
-            // Report result back to server
-            if let Err(e) = report_result(&client, &token, &action_id, success, &output).await {
-                log::error!("Failed to report result: {}", e);
-            }
+            let artifacts = create_artifacts(&mut secrets::Screen::local(), &execution);
+            let output = execution.output.clone();
+            let mut outcome = pipeline::Outcome::new(execution, message.clone());
+            outcome.aborted = aborted;
+            outcome.rollback_id = rollback_record.as_ref().map(|r| r.rollback_id.clone());
+            outcome.rollback_point = rollback_record.as_ref().map(|r| r.to_point());
+            outcome.receipt = Some(receipt::issue(&claims, &action, success, &jwt_secret));
+            run.finish(outcome).await;
 
-            let artifacts = create_artifacts(&action_id, &output);
-            Ok(ActionResult {
+            ActionResult {
                 success,
-                message: output.clone(),
-                error: if success { None } else { Some(output.clone()) },
+                message: if hint.is_some() || aborted { message } else { output.clone() },
+                error: if success { None } else { Some(output) },
                 artifacts: Some(artifacts),
-                rollback_id: if action.reversible { Some(uuid::Uuid::new_v4().to_string()) } else { None },
-            })
+                rollback_id: rollback_record.map(|r| r.rollback_id),
+                environment: Some(fingerprint::current()),
+                elevation: action.elevated.then_some(elevation::ElevationStatus::Granted),
+                simulated: false,
+                risk: Some(action.risk),
+                category: Some(action.category),
+                estimated_bytes_freed: space_estimate,
+            }
         }
-        Err(e) => {
-            let error_msg = format!("❌ {} execution error: {}", action.title, e);
-            emit_status(&app, &error_msg, "error");
+        Err(status) => {
+            // Nothing ran, so the prepared backup isn't needed
+            if let Some(record) = &rollback_record {
+                if let Err(e) = tokio::task::block_in_place(|| rollback::release(record)) {
+                    log::error!("Failed to release unused rollback point: {}", e);
+                }
+            }
+
+            let error_msg = format!("❌ {}: {}", action.title, status.describe());
+            let execution = Execution { success: false, output: error_msg.clone(), commands: vec![] };
+            let mut outcome = pipeline::Outcome::new(execution, error_msg.clone());
+            outcome.receipt = Some(receipt::issue(&claims, &action, false, &jwt_secret));
+            run.finish(outcome).await;
 
-            Ok(ActionResult {
+            ActionResult {
                 success: false,
                 message: error_msg.clone(),
                 error: Some(error_msg),
                 artifacts: None,
                 rollback_id: None,
-            })
+                environment: Some(fingerprint::current()),
+                elevation: Some(status),
+                simulated: false,
+                risk: Some(action.risk),
+                category: Some(action.category),
+                estimated_bytes_freed: None,
+            }
         }
+    };
+
+    transcript::record_action(claims.chat_id.as_deref(), "execute", &action_id, &claims.approval_id, &action_result);
+    Ok(action_result)
+}
+
+// Stand-in for the execution half of run_action: no probes, backups, elevation
+// or commands, but the same pipeline events, and so the same history and report
+async fn simulate_action(run: &pipeline::Run, action: &ActionDefinition, space_estimate: Option<u64>) -> ActionResult {
+    log::info!("Simulating execution of action: {}", action.id);
+    run.running(&format!("🧪 Simulating {}...", action.title));
+
+    let (success, mut output) = simulation::run(action);
+    if let Some(bytes) = space_estimate {
+        output.push_str(&format!("{} will be freed\n", space::describe(bytes)));
     }
+    let execution = Execution { success, output, commands: vec![] };
+    let message = if success {
+        format!("✅ {} completed successfully (simulated)", action.title)
+    } else {
+        format!("❌ {} failed (simulated)", action.title)
+    };
+
+    let artifacts = create_artifacts(&mut secrets::Screen::local(), &execution);
+    let output = execution.output.clone();
+    run.finish(pipeline::Outcome::new(execution, message)).await;
+
+    ActionResult {
+        success,
+        message: output.clone(),
+        error: if success { None } else { Some(output) },
+        artifacts: Some(artifacts),
+        rollback_id: None,
+        environment: Some(fingerprint::current()),
+        elevation: None,
+        simulated: true,
+        risk: Some(action.risk),
+        category: Some(action.category),
+        estimated_bytes_freed: space_estimate,
+    }
+}
+
+// Hold an action while the screen is locked and resume once the user is back
+async fn wait_for_unlock(app: &AppHandle, action: &ActionDefinition) -> Result<(), String> {
+    if !power::screen_locked() {
+        return Ok(());
+    }
+
+    log::info!("Holding {} until the screen is unlocked", action.id);
+    emit_status(app, &format!("🔒 {} will run once you unlock your screen", action.title), "waiting");
+    let deadline = tokio::time::Instant::now() + UNLOCK_WAIT;
+    while power::screen_locked() {
+        if tokio::time::Instant::now() >= deadline {
+            emit_status(app, &format!("⏸ {} was not run, the screen stayed locked", action.title), "error");
+            return Err(format!("Deferred '{}': screen stayed locked", action.id));
+        }
+        tokio::time::sleep(UNLOCK_POLL).await;
+    }
+
+    emit_status(app, &format!("🔓 Resuming {}...", action.title), "executing");
+    Ok(())
 }
 
-async fn execute_commands(commands: &[String]) -> Result<(bool, String), String> {
+// Err means the commands needed elevation that wasn't granted, and nothing ran
+async fn execute_commands(
+    commands: &[String],
+    context: &rollback::CommandContext,
+    action: &ActionDefinition,
+    elevated: bool,
+    running: &watchdog::Running,
+    run: &pipeline::Run,
+) -> Result<Execution, elevation::ElevationStatus> {
+    let background = action.background;
+    // Nothing runs unless every binary is named by a trusted absolute path
+    let homebrew = action.homebrew && !elevated;
+    let refused: Vec<String> = commands
+        .iter()
+        .filter_map(|c| command_argv(c, context).first().and_then(|p| exec_context::verify_binary_for(p, homebrew).err()))
+        .collect();
+    if !refused.is_empty() {
+        audit::record("binary_refused", serde_json::json!({ "reasons": refused }));
+        return Ok(Execution {
+            success: false,
+            output: format!("Refused to run: {}\n", refused.join("; ")),
+            commands: vec![],
+        });
+    }
+
+    if elevated {
+        log::info!("Executing {} command(s) with elevation", commands.len());
+        let argvs: Vec<Vec<String>> = commands
+            .iter()
+            .map(|c| command_argv(c, context))
+            .filter(|argv| !argv.is_empty())
+            .collect();
+        let mut runs: Vec<exec_context::CommandRun> = argvs
+            .iter()
+            .map(|argv| exec_context::CommandRun::new(&argv[0], true))
+            .collect();
+        let argvs: Vec<Vec<String>> = match background {
+            true => argvs.into_iter().map(limits::background_argv).collect(),
+            false => argvs,
+        };
+        // The prompt can sit on screen for a while, keep it off the async workers
+        let elevated = tauri::async_runtime::spawn_blocking(move || elevation::execute(&argvs))
+            .await
+            .unwrap_or(Err(elevation::ElevationStatus::Unavailable))?;
+        let ran_all = elevated.exit_codes.len() == runs.len();
+        for ((command_run, code), command) in runs.iter_mut().zip(elevated.exit_codes).zip(commands) {
+            command_run.exit_code = Some(code);
+            run.step_completed(command, command_run);
+        }
+        let tolerated = ran_all
+            && runs.iter().all(|r| r.exit_code.is_some_and(|code| code == 0 || hints::tolerated(&action.exit_hints, &r.program, code)));
+        return Ok(Execution { success: elevated.success || tolerated, output: elevated.output, commands: runs });
+    }
+
     let mut output = String::new();
     let mut all_success = true;
+    let mut runs = Vec::new();
 
     for command in commands {
+        if running.aborted() {
+            output.push_str(&format!("Aborted by user before: {}\n", command));
+            all_success = false;
+            break;
+        }
         log::info!("Executing command: {}", command);
 
-        // Parse command into program and args
-        let parts: Vec<&str> = command.split_whitespace().collect();
+        let parts = command_argv(command, context);
         if parts.is_empty() {
             continue;
         }
 
-        let program = parts[0];
-        let args = &parts[1..];
+        let program = &parts[0];
+        let mut command_run = exec_context::CommandRun::new(program, false);
+        let started = std::time::Instant::now();
+
+        let result = limits::command(&parts, background)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .and_then(|child| {
+                if background {
+                    limits::contain(&child);
+                }
+                running.track(child.id(), program);
+                child.wait_with_output()
+            });
+        command_run.duration_ms = Some(started.elapsed().as_millis() as u64);
+        command_run.exit_code = result.as_ref().ok().and_then(|r| r.status.code());
+        run.step_completed(command, &command_run);
+        runs.push(command_run);
 
-        match Command::new(program)
-            .args(args)
-            .output()
-        {
+        match result {
             Ok(result) => {
                 let stdout = String::from_utf8_lossy(&result.stdout);
                 let stderr = String::from_utf8_lossy(&result.stderr);
@@ -419,7 +996,10 @@ async fn execute_commands(commands: &[String]) -> Result<(bool, String), String>
                     output.push_str(&format!("Error: {}\n", stderr));
                 }
 
-                if !result.status.success() {
+                let tolerated = result.status.code().is_some_and(|code| hints::tolerated(&action.exit_hints, program, code));
+                if tolerated {
+                    log::info!("Command exited with {}, which means there was nothing to do", result.status);
+                } else if !result.status.success() {
                     all_success = false;
                     log::error!("Command failed with exit code: {}", result.status);
                 }
@@ -433,42 +1013,42 @@ async fn execute_commands(commands: &[String]) -> Result<(bool, String), String>
         }
     }
 
-    Ok((all_success, output))
+    Ok(Execution { success: all_success, output, commands: runs })
+}
+
+// Parse command into program and args, expanding placeholders per token
+// so substituted paths containing spaces stay a single argument
+fn command_argv(command: &str, context: &rollback::CommandContext) -> Vec<String> {
+    command.split_whitespace().map(|p| context.expand(p)).collect()
 }
 
 async fn report_result(
     client: &Client,
     token: &str,
     action_id: &str,
-    success: bool,
-    output: &str,
+    execution: &Execution,
+    rollback_point: Option<RollbackPoint>,
+    receipt: Option<&receipt::Receipt>,
+    simulated: bool,
 ) -> Result<(), String> {
-    // Extract server URL from environment or use default
-    let server_url = std::env::var("OHFIXIT_SERVER_URL")
-        .unwrap_or_else(|_| "http://localhost:3000".to_string());
-
-    let report_url = format!("{}/api/automation/helper/report", server_url);
-
-    let artifacts = create_artifacts(action_id, output);
-    let rollback_point = if success {
-        Some(RollbackPoint {
-            method: "command_sequence".to_string(),
-            data: serde_json::json!({
-                "action_id": action_id,
-                "timestamp": Utc::now().to_rfc3339(),
-                "output_hash": general_purpose::STANDARD.encode(output.as_bytes())
-            })
-        })
-    } else {
-        None
-    };
+    // Only ever the server this helper was paired with
+    let report_url = outbound::report_url(client).await?;
+
+    // Outputs can contain Wi-Fi keys, tokens or usernames
+    let mut screen = secrets::Screen::new(action_id);
+    let artifacts = create_artifacts(&mut screen, execution);
+    let output = report_output(&mut screen, action_id, &execution.output);
+    screen.finish();
 
     let payload = serde_json::json!({
         "actionId": action_id,
-        "success": success,
+        "success": execution.success,
         "output": output,
         "artifacts": artifacts,
         "rollbackPoint": rollback_point,
+        "receipt": receipt,
+        "environment": fingerprint::current(),
+        "simulated": simulated,
         "timestamp": Utc::now().to_rfc3339(),
     });
 
@@ -497,20 +1077,22 @@ async fn report_rollback_result(
     token: &str,
     action_id: &str,
     rollback_id: &str,
-    success: bool,
-    output: &str,
+    execution: &Execution,
 ) -> Result<(), String> {
-    let server_url = std::env::var("OHFIXIT_SERVER_URL")
-        .unwrap_or_else(|_| "http://localhost:3000".to_string());
-
-    let report_url = format!("{}/api/automation/helper/report", server_url);
+    let report_url = outbound::report_url(client).await?;
 
+    let reported_id = format!("{}_rollback", action_id);
+    let mut screen = secrets::Screen::new(&reported_id);
+    let artifacts = create_artifacts(&mut screen, execution);
+    let output = report_output(&mut screen, &reported_id, &execution.output);
+    screen.finish();
     let payload = serde_json::json!({
-        "actionId": format!("{}_rollback", action_id),
+        "actionId": reported_id,
         "rollbackId": rollback_id,
-        "success": success,
+        "success": execution.success,
         "output": output,
-        "artifacts": create_artifacts(&format!("{}_rollback", action_id), output),
+        "artifacts": artifacts,
+        "environment": fingerprint::current(),
         "timestamp": Utc::now().to_rfc3339(),
     });
 
@@ -534,15 +1116,48 @@ async fn report_rollback_result(
     }
 }
 
-fn create_artifacts(_action_id: &str, output: &str) -> Vec<ActionArtifact> {
-    vec![
-        ActionArtifact {
+// The raw output, redacted once, for a report's `output` field
+fn report_output(screen: &mut secrets::Screen, action_id: &str, output: &str) -> String {
+    match screen.text("output", output) {
+        Some((output, redactions)) => {
+            if redactions > 0 {
+                log::info!("Redacted {} value(s) from {} output before reporting", redactions, action_id);
+            }
+            output
+        }
+        None => secrets::blocked_notice("output"),
+    }
+}
+
+// Artifacts carry screened data only, with the number of redactions; one the
+// secrets policy blocks is left out
+fn create_artifacts(screen: &mut secrets::Screen, execution: &Execution) -> Vec<ActionArtifact> {
+    let mut artifacts = Vec::new();
+    if let Some((output, redactions)) = screen.text("execution_log", &execution.output) {
+        artifacts.push(ActionArtifact {
             artifact_type: "execution_log".to_string(),
             uri: None,
             hash: Some(general_purpose::STANDARD.encode(output.as_bytes())),
-            data: Some(output.to_string()),
-        }
-    ]
+            data: Some(output),
+            metadata: Some(serde_json::json!({ "redactions": redactions })),
+        });
+    }
+
+    // Nothing ran (simulated, or elevation refused), so there's no context to show
+    if !execution.commands.is_empty() {
+        let context = exec_context::describe(&execution.commands).to_string();
+        let Some((context, redactions)) = screen.text("execution_context", &context) else {
+            return artifacts;
+        };
+        artifacts.push(ActionArtifact {
+            artifact_type: "execution_context".to_string(),
+            uri: None,
+            hash: Some(general_purpose::STANDARD.encode(context.as_bytes())),
+            data: Some(context),
+            metadata: Some(serde_json::json!({ "redactions": redactions })),
+        });
+    }
+    artifacts
 }
 
 fn emit_status(app: &AppHandle, message: &str, status_type: &str) {
@@ -553,11 +1168,82 @@ fn emit_status(app: &AppHandle, message: &str, status_type: &str) {
 }
 
 fn main() {
+    startup::begin();
+    // One helper per user: a second launch hands over to the running one
+    if instance::forward_to_running() {
+        return;
+    }
     tauri::Builder::default()
         .manage(Mutex::new(AppState::new()))
-        .invoke_handler(tauri::generate_handler![execute_action, execute_rollback, get_health_status])
+        .invoke_handler(tauri::generate_handler![
+            actions::list_actions,
+            audit::verify_audit_log,
+            automation::abort_all_automation,
+            benchmark::run_benchmark,
+            debug::create_debug_bundle,
+            debug::set_log_level,
+            execute_action,
+            execute_rollback,
+            freeze::execution_freeze,
+            freeze::freeze_execution,
+            freeze::unfreeze_execution,
+            get_health_status,
+            guided::list_guided_steps,
+            guided::verify_guided_step,
+            instance::status_server_info,
+            manifest::refresh_action_manifest,
+            pairing::pairing_status,
+            rollback::discard_rollback_point,
+            rollback::list_rollback_points,
+            screenrecord::stop_screen_recording,
+            snapshots::take_config_snapshot,
+            snapshots::list_config_snapshots,
+            snapshots::get_config_drift
+        ])
+        .setup(|app| {
+            startup::mark("tauri_init");
+            // The web app probes the port right after launch, so it comes up first
+            server::spawn_status_server(app.handle().clone());
+
+            // Verifying the cached manifest and compiling plugins can take a
+            // while; action lookups wait on startup::catalog_ready meanwhile
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                startup::span("manifest_load", || manifest::load_cached(&handle));
+                startup::span("plugins_load", || plugins::load(&handle));
+                startup::catalog_loaded();
+                startup::mark("catalog_ready");
+            });
+            manifest::spawn_manifest_refresh(app.handle().clone());
+            jwks::spawn_refresh(app.state::<Mutex<AppState>>().lock().unwrap().client.clone());
+            pairing::spawn_registration(app.handle().clone());
+            freeze::spawn_poll(app.handle().clone());
+            // Launched by opening an ohfixit:// link
+            for url in std::env::args().filter(|arg| arg.starts_with(deep_link::SCHEME)) {
+                deep_link::spawn_handle(app.handle().clone(), url);
+            }
+
+            startup::span("tray", || {
+                if let Err(e) = tray::setup(app) {
+                    log::error!("Failed to create tray icon: {}", e);
+                }
+            });
+            snapshots::spawn_periodic_snapshots();
+            rollback::spawn_cleanup_task();
+            let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                startup::span("watchdog_recover", || watchdog::recover(client));
+                startup::span("events_compact", pipeline::compact);
+                if let Err(e) = startup::span("attestation", attestation::current) {
+                    log::error!("{}", e);
+                }
+            });
+            startup::mark("setup_done");
+            Ok(())
+        })
         .plugin(tauri_plugin_log::Builder::default().build())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
\ No newline at end of file
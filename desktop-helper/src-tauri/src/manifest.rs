@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use base64::{engine::general_purpose, Engine as _};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::rollback::CommandContext;
+use crate::{hints, plugins, probes, scheduler, storage, ActionDefinition, AppState, Category, Risk};
+
+const MANIFEST_FILE: &str = "action_manifest.json";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Base64 raw Ed25519 public key the server signs manifests with, pinned at build time
+const MANIFEST_PUBLIC_KEY: Option<&str> = option_env!("OHFIXIT_MANIFEST_PUBLIC_KEY");
+
+// Exactly what the server sent: the manifest JSON text and a signature over its bytes
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SignedManifest {
+    pub payload: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActionManifest {
+    pub version: u64,
+    pub actions: Vec<ManifestAction>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ManifestAction {
+    id: String,
+    title: String,
+    os: String,
+    commands: Vec<String>,
+    #[serde(default)]
+    rollback_commands: Vec<String>,
+    #[serde(default = "default_reversible")]
+    reversible: bool,
+    #[serde(default)]
+    estimated_time: Option<String>,
+    #[serde(default)]
+    requirements: Option<Vec<String>>,
+    #[serde(default)]
+    state_captures: Vec<StateCapture>,
+    #[serde(default)]
+    snapshot_paths: Vec<String>,
+    #[serde(default)]
+    preflight: Vec<probes::Probe>,
+    #[serde(default)]
+    postconditions: Vec<probes::Probe>,
+    #[serde(default)]
+    parameters: Option<serde_json::Value>,
+    #[serde(default)]
+    elevated: bool,
+    #[serde(default)]
+    interactive: bool,
+    #[serde(default)]
+    background: bool,
+    // Unrated remote actions aren't assumed to be harmless
+    #[serde(default = "default_risk")]
+    risk: Risk,
+    // Manifests signed before actions had a category still load
+    #[serde(default = "default_category")]
+    category: Category,
+    #[serde(default)]
+    space_estimate_targets: Vec<String>,
+    #[serde(default)]
+    exit_hints: Vec<hints::ExitHint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StateCapture {
+    key: String,
+    command: String,
+}
+
+fn default_reversible() -> bool {
+    true
+}
+
+fn default_risk() -> Risk {
+    Risk::Medium
+}
+
+// Same as a built-in action that doesn't pick one
+fn default_category() -> Category {
+    Category::Ui
+}
+
+static REFRESH_LOCK: Mutex<()> = Mutex::new(());
+
+// Remote manifests are opt-in (OHFIXIT_REMOTE_MANIFEST=1) and need a pinned key
+pub fn enabled() -> bool {
+    MANIFEST_PUBLIC_KEY.is_some()
+        && std::env::var("OHFIXIT_REMOTE_MANIFEST")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+}
+
+// Re-apply the last accepted manifest so a restart doesn't fall back to the
+// built-in allowlist until the next fetch
+pub fn load_cached(app: &AppHandle) {
+    if !enabled() {
+        return;
+    }
+    let signed: SignedManifest = storage::load_json(MANIFEST_FILE);
+    if signed.payload.is_empty() {
+        return;
+    }
+    match verify(&signed).and_then(|manifest| apply(app, manifest)) {
+        Ok(version) => log::info!("Loaded cached action manifest v{}", version),
+        Err(e) => log::error!("Ignoring cached action manifest: {}", e),
+    }
+}
+
+pub fn spawn_manifest_refresh(app: AppHandle) {
+    if !enabled() {
+        return;
+    }
+    scheduler::spawn_job("manifest_refresh", REFRESH_INTERVAL, false, move || {
+        let app = app.clone();
+        async move { refresh(&app).await.map(|_| String::new()) }
+    });
+}
+
+#[tauri::command]
+pub async fn refresh_action_manifest(app: AppHandle) -> Result<u64, String> {
+    if !enabled() {
+        return Err("Remote action manifests are disabled".to_string());
+    }
+    refresh(&app).await
+}
+
+// Fetch, verify and swap in the server's manifest. Returns the active version.
+pub async fn refresh(app: &AppHandle) -> Result<u64, String> {
+    let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+
+    let signed: SignedManifest = client
+        .get(format!("{}/api/automation/helper/manifest", server_url))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch manifest: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Malformed manifest response: {}", e))?;
+
+    let manifest = verify(&signed)?;
+
+    let _guard = REFRESH_LOCK.lock().unwrap();
+    let current = app.state::<Mutex<AppState>>().lock().unwrap().manifest_version;
+    if let Some(current) = current {
+        // Never roll back to an older (possibly revoked) allowlist
+        if manifest.version < current {
+            return Err(format!("Refusing manifest v{} older than active v{}", manifest.version, current));
+        }
+        if manifest.version == current {
+            return Ok(current);
+        }
+    }
+
+    let version = apply(app, manifest)?;
+    storage::save_json(MANIFEST_FILE, &signed)?;
+    log::info!("Activated action manifest v{}", version);
+    Ok(version)
+}
+
+pub fn verify(signed: &SignedManifest) -> Result<ActionManifest, String> {
+    let key = MANIFEST_PUBLIC_KEY.ok_or("No manifest public key pinned in this build")?;
+    verify_signature(signed, key)?;
+    serde_json::from_str(&signed.payload).map_err(|e| format!("Invalid manifest: {}", e))
+}
+
+// Ed25519 check of a signed payload against a base64 public key (also used for plugin bundles)
+pub fn verify_signature(signed: &SignedManifest, public_key: &str) -> Result<(), String> {
+    let key = general_purpose::STANDARD
+        .decode(public_key)
+        .map_err(|e| format!("Invalid pinned public key: {}", e))?;
+    let signature = general_purpose::STANDARD
+        .decode(&signed.signature)
+        .map_err(|e| format!("Invalid signature encoding: {}", e))?;
+
+    UnparsedPublicKey::new(&ED25519, &key)
+        .verify(signed.payload.as_bytes(), &signature)
+        .map_err(|_| "Signature verification failed".to_string())
+}
+
+// Build the complete allowlist first, then replace the old one in a single step
+fn apply(app: &AppHandle, manifest: ActionManifest) -> Result<u64, String> {
+    let mut seen = HashSet::new();
+    let mut actions = HashMap::new();
+    for entry in manifest.actions {
+        if !seen.insert(entry.id.clone()) {
+            return Err(format!("Duplicate action '{}' in manifest", entry.id));
+        }
+        if entry.commands.iter().all(|c| c.trim().is_empty()) {
+            return Err(format!("Action '{}' has no commands", entry.id));
+        }
+        let context = CommandContext::default();
+        let commands = entry.commands.iter().chain(&entry.rollback_commands).chain(entry.state_captures.iter().map(|c| &c.command));
+        for command in commands {
+            let program = command.split_whitespace().next().map(|p| context.expand(p)).unwrap_or_default();
+            if !program.is_empty() && !Path::new(&program).is_absolute() {
+                return Err(format!("Action '{}' runs '{}' without an absolute path", entry.id, program));
+            }
+        }
+        actions.insert(entry.id.clone(), into_definition(entry));
+    }
+    // Organization plugins live alongside whichever allowlist is active
+    for action in plugins::definitions() {
+        actions.entry(action.id.clone()).or_insert(action);
+    }
+
+    let state = app.state::<Mutex<AppState>>();
+    let mut state = state.lock().unwrap();
+    state.actions = actions;
+    state.manifest_version = Some(manifest.version);
+    Ok(manifest.version)
+}
+
+fn into_definition(entry: ManifestAction) -> ActionDefinition {
+    let commands = entry.commands.iter().map(String::as_str).collect();
+    let mut action = ActionDefinition::new(&entry.id, &entry.title, &entry.os, commands);
+    action.creates_backup = !entry.rollback_commands.is_empty() || !entry.snapshot_paths.is_empty();
+    action.rollback_commands = entry.rollback_commands;
+    action.reversible = entry.reversible;
+    if let Some(estimated_time) = entry.estimated_time {
+        action.estimated_time = estimated_time;
+    }
+    if let Some(requirements) = entry.requirements {
+        action.requirements = requirements;
+    }
+    action.state_captures = entry.state_captures.into_iter().map(|c| (c.key, c.command)).collect();
+    action.snapshot_paths = entry.snapshot_paths;
+    action.preflight = entry.preflight;
+    action.postconditions = entry.postconditions;
+    action.elevated = entry.elevated;
+    action.interactive = entry.interactive || entry.elevated;
+    action.background = entry.background;
+    action.risk = entry.risk;
+    action.category = entry.category;
+    action.space_estimate_targets = entry.space_estimate_targets;
+    action.exit_hints = entry.exit_hints;
+    if let Some(parameters) = entry.parameters {
+        action.parameters = parameters;
+    }
+    action
+}
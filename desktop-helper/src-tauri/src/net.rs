@@ -0,0 +1,393 @@
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::cmd::{diagnostic, read_output, read_trimmed};
+use crate::dns;
+use crate::exec_context::system32;
+
+// GET /net/ping for the diagnostics toolkit. Only a fixed set of targets can
+// be measured: the default gateway, Cloudflare's 1.1.1.1 and the OhFixIt
+// server. ICMP goes through the system ping (no raw sockets needed); the
+// server, which usually drops ICMP, gets TCP connects to its port, and so
+// does 1.1.1.1 when ICMP gets nothing back.
+//
+// GET /net/traceroute runs the system traceroute (tracert on Windows) to one
+// of those targets or any hostname, and parses its hops, so a broken path
+// shows where it breaks.
+const PING_COUNT: u32 = 4;
+const PING_TIMEOUT_SECS: u32 = 2;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const PUBLIC_RESOLVER: &str = "1.1.1.1";
+pub const TARGETS: &[&str] = &["gateway", PUBLIC_RESOLVER, "server"];
+const MAX_HOPS: u32 = 30;
+const HOP_WAIT_SECS: u32 = 2;
+// 30 silent hops would take far longer; what came back by then is returned
+const TRACE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+pub struct PingQuery {
+    // One of TARGETS; all of them when absent
+    host: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct PingResult {
+    target: String,
+    // Address or name actually measured
+    host: Option<String>,
+    // "icmp" or "tcp"
+    method: &'static str,
+    port: Option<u16>,
+    sent: u32,
+    received: u32,
+    loss_percent: f64,
+    min_ms: Option<f64>,
+    avg_ms: Option<f64>,
+    max_ms: Option<f64>,
+    // Mean difference between consecutive round trips
+    jitter_ms: Option<f64>,
+    error: Option<String>,
+}
+
+// GET /net/ping[?host=gateway|1.1.1.1|server]
+pub async fn ping_handler(Query(query): Query<PingQuery>) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let targets: Vec<&'static str> = match query.host.as_deref() {
+        None | Some("") => TARGETS.to_vec(),
+        Some(host) => match TARGETS.iter().find(|target| **target == host) {
+            Some(target) => vec![*target],
+            None => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown ping target '{}'; expected one of {}", host, TARGETS.join(", ")),
+                ))
+            }
+        },
+    };
+    let runs = targets.into_iter().map(|target| tauri::async_runtime::spawn_blocking(move || ping_target(target)));
+    let mut results = Vec::new();
+    for run in runs.collect::<Vec<_>>() {
+        if let Ok(result) = run.await {
+            results.push(result);
+        }
+    }
+    Ok(Json(serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "results": results,
+    })))
+}
+
+fn ping_target(target: &'static str) -> PingResult {
+    match target {
+        "gateway" => match default_gateway() {
+            Some(gateway) => icmp(target, &gateway),
+            None => PingResult { target: target.to_string(), error: Some("No default gateway".to_string()), ..Default::default() },
+        },
+        "server" => match server_address() {
+            Ok((host, port)) => tcp(target, &host, port),
+            Err(e) => PingResult { target: target.to_string(), method: "tcp", error: Some(e), ..Default::default() },
+        },
+        _ => {
+            let result = icmp(target, target);
+            if result.received > 0 {
+                return result;
+            }
+            log::info!("No ICMP replies from {} ({:?}); trying TCP", target, result.error);
+            tcp(target, target, 443)
+        }
+    }
+}
+
+// Host and port of OHFIXIT_SERVER_URL
+pub fn server_address() -> Result<(String, u16), String> {
+    let server_url = std::env::var("OHFIXIT_SERVER_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let url = reqwest::Url::parse(&server_url).map_err(|e| format!("Invalid OHFIXIT_SERVER_URL '{}': {}", server_url, e))?;
+    let host = url.host_str().ok_or_else(|| format!("OHFIXIT_SERVER_URL '{}' has no host", server_url))?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    Ok((host.trim_matches(['[', ']']).to_string(), port))
+}
+
+// The IPv4 default gateway
+pub fn default_gateway() -> Option<String> {
+    match std::env::consts::OS {
+        "macos" => read_output("/sbin/route", &["-n", "get", "default"])?
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("gateway:").map(|gateway| gateway.trim().to_string())),
+        "windows" => {
+            let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+            read_trimmed(
+                &powershell.to_string_lossy(),
+                &[
+                    "-NoProfile",
+                    "-NonInteractive",
+                    "-Command",
+                    "(Get-NetRoute -DestinationPrefix '0.0.0.0/0' | Sort-Object RouteMetric | Select-Object -First 1).NextHop",
+                ],
+            )
+        }
+        // Destination and gateway are little-endian hex in /proc/net/route
+        _ => std::fs::read_to_string("/proc/net/route").ok()?.lines().skip(1).find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(1) != Some(&"00000000") {
+                return None;
+            }
+            let gateway = u32::from_str_radix(fields.get(2)?, 16).ok()?;
+            Some(std::net::Ipv4Addr::from(gateway.to_le_bytes()).to_string())
+        }),
+    }
+}
+
+fn icmp(target: &str, host: &str) -> PingResult {
+    let count = PING_COUNT.to_string();
+    let (program, args): (String, Vec<String>) = match std::env::consts::OS {
+        "windows" => (
+            system32().join("ping.exe").to_string_lossy().into_owned(),
+            vec!["-n".into(), count, "-w".into(), (PING_TIMEOUT_SECS * 1000).to_string(), host.into()],
+        ),
+        // -W is the wait for each reply: milliseconds on macOS, seconds on Linux
+        "macos" => ("/sbin/ping".into(), vec!["-c".into(), count, "-W".into(), (PING_TIMEOUT_SECS * 1000).to_string(), host.into()]),
+        _ => ("ping".into(), vec!["-n".into(), "-c".into(), count, "-W".into(), PING_TIMEOUT_SECS.to_string(), host.into()]),
+    };
+    let mut result = PingResult { target: target.to_string(), host: Some(host.to_string()), method: "icmp", ..Default::default() };
+    // ping exits non-zero when nothing answered, which is still a result
+    match diagnostic(&program).args(&args).output() {
+        Ok(output) => {
+            let times = reply_times(&String::from_utf8_lossy(&output.stdout));
+            summarize(&mut result, PING_COUNT, &times);
+            if times.is_empty() && !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                result.error = Some(if stderr.is_empty() { "No replies".to_string() } else { stderr });
+            }
+        }
+        Err(e) => result.error = Some(format!("Failed to run ping: {}", e)),
+    }
+    result
+}
+
+// Round trips in ms from ping's reply lines: "time=12.3 ms" on macOS and
+// Linux, "time=12ms" or "time<1ms" on Windows (whose reply lines, in any
+// language, carry "TTL=")
+fn reply_times(output: &str) -> Vec<f64> {
+    let time = Regex::new(r"[=<]\s*([0-9]+(?:[.,][0-9]+)?)\s*ms").unwrap();
+    output
+        .lines()
+        .filter(|line| line.contains("time") || line.to_uppercase().contains("TTL="))
+        .filter_map(|line| time.captures(line))
+        .filter_map(|caps| caps[1].replace(',', ".").parse().ok())
+        .collect()
+}
+
+fn tcp(target: &str, host: &str, port: u16) -> PingResult {
+    let mut result = PingResult { target: target.to_string(), host: Some(host.to_string()), method: "tcp", port: Some(port), ..Default::default() };
+    let address = match (host, port).to_socket_addrs().map(|mut addresses| addresses.next()) {
+        Ok(Some(address)) => address,
+        Ok(None) | Err(_) => {
+            result.sent = PING_COUNT;
+            result.loss_percent = 100.0;
+            result.error = Some(format!("Failed to resolve {}", host));
+            return result;
+        }
+    };
+    let mut times = Vec::new();
+    let mut error = None;
+    for _ in 0..PING_COUNT {
+        let started = Instant::now();
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(_) => times.push(started.elapsed().as_secs_f64() * 1000.0),
+            Err(e) => error = Some(format!("Connecting to {}: {}", address, e)),
+        }
+    }
+    summarize(&mut result, PING_COUNT, &times);
+    if times.is_empty() {
+        result.error = error;
+    }
+    result
+}
+
+fn summarize(result: &mut PingResult, sent: u32, times: &[f64]) {
+    let round = |ms: f64| (ms * 100.0).round() / 100.0;
+    result.sent = sent;
+    result.received = (times.len() as u32).min(sent);
+    result.loss_percent = round(100.0 * (sent - result.received) as f64 / sent as f64);
+    if times.is_empty() {
+        return;
+    }
+    result.min_ms = times.iter().copied().reduce(f64::min).map(round);
+    result.max_ms = times.iter().copied().reduce(f64::max).map(round);
+    result.avg_ms = Some(round(times.iter().sum::<f64>() / times.len() as f64));
+    result.jitter_ms = (times.len() > 1).then(|| {
+        round(times.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum::<f64>() / (times.len() - 1) as f64)
+    });
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TracerouteQuery {
+    // One of TARGETS or a hostname; 1.1.1.1 when absent
+    host: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Hop {
+    hop: u32,
+    // Routers that answered this hop (more than one when the path splits)
+    addresses: Vec<String>,
+    rtts_ms: Vec<f64>,
+    // Probes that got no answer ("*")
+    lost: u32,
+    // Unreachable flags, e.g. "!H" (host) or "!N" (network)
+    flags: Vec<String>,
+}
+
+// GET /net/traceroute[?host=]
+pub async fn traceroute_handler(Query(query): Query<TracerouteQuery>) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let target = query.host.filter(|host| !host.is_empty()).unwrap_or_else(|| PUBLIC_RESOLVER.to_string());
+    let host = match target.as_str() {
+        "gateway" => tauri::async_runtime::spawn_blocking(default_gateway)
+            .await
+            .ok()
+            .flatten()
+            .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, "No default gateway".to_string()))?,
+        "server" => server_address().map(|(host, _)| host).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+        host if dns::valid_hostname(host) || host.parse::<IpAddr>().is_ok() => host.to_string(),
+        host => return Err((StatusCode::BAD_REQUEST, format!("'{}' is not a hostname or address", host))),
+    };
+
+    let started = Instant::now();
+    let (lines, completed, error) = trace(&host).await;
+    let hops: Vec<Hop> = lines.iter().filter_map(|line| parse_hop(line)).collect();
+    let destination = (host.as_str(), 0).to_socket_addrs().ok().and_then(|mut addresses| addresses.next()).map(|a| a.ip().to_string());
+    let reached = hops.last().is_some_and(|hop| destination.as_ref().is_some_and(|d| hop.addresses.contains(d)));
+    // Where the path goes dark: the last hop that answered before only
+    // silent hops follow
+    let last_responding_hop = hops.iter().rev().find(|hop| !hop.addresses.is_empty()).map(|hop| hop.hop);
+    Ok(Json(serde_json::json!({
+        "target": target,
+        "host": host,
+        "destination": destination,
+        "generated_at": Utc::now().to_rfc3339(),
+        "duration_ms": started.elapsed().as_millis() as u64,
+        "completed": completed,
+        "reached": reached,
+        "last_responding_hop": last_responding_hop,
+        "hops": hops,
+        "error": error,
+    })))
+}
+
+// Output lines, whether traceroute finished within TRACE_TIMEOUT, and why
+// it couldn't run
+async fn trace(host: &str) -> (Vec<String>, bool, Option<String>) {
+    let (program, args): (String, Vec<String>) = match std::env::consts::OS {
+        "windows" => (
+            system32().join("tracert.exe").to_string_lossy().into_owned(),
+            vec!["-d".into(), "-h".into(), MAX_HOPS.to_string(), "-w".into(), (HOP_WAIT_SECS * 1000).to_string(), host.into()],
+        ),
+        os => (
+            if os == "macos" { "/usr/sbin/traceroute" } else { "traceroute" }.to_string(),
+            vec!["-n".into(), "-q".into(), "3".into(), "-w".into(), HOP_WAIT_SECS.to_string(), "-m".into(), MAX_HOPS.to_string(), host.into()],
+        ),
+    };
+    let spawned = tokio::process::Command::from(diagnostic(&program))
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return (Vec::new(), false, Some(format!("{} is not installed", program)))
+        }
+        Err(e) => return (Vec::new(), false, Some(format!("Failed to run {}: {}", program, e))),
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return (Vec::new(), false, Some(format!("{} has no output", program)));
+    };
+    let mut lines = BufReader::new(stdout).lines();
+    let mut output = Vec::new();
+    let completed = tokio::time::timeout(TRACE_TIMEOUT, async {
+        while let Ok(Some(line)) = lines.next_line().await {
+            output.push(line);
+        }
+    })
+    .await
+    .is_ok();
+    if !completed {
+        let _ = child.kill().await;
+    }
+    (output, completed, None)
+}
+
+// One hop line. traceroute: " 3  10.0.0.1  5.1 ms * 10.0.0.2  6.2 ms !H";
+// tracert: "  2    <1 ms     *       12 ms  10.0.0.1" or
+// "  5     *        *        *     Request timed out."
+fn parse_hop(line: &str) -> Option<Hop> {
+    let mut tokens = line.split_whitespace().peekable();
+    let hop = tokens.next()?.parse().ok()?;
+    let mut parsed = Hop { hop, addresses: Vec::new(), rtts_ms: Vec::new(), lost: 0, flags: Vec::new() };
+    while let Some(token) = tokens.next() {
+        if token == "*" {
+            parsed.lost += 1;
+        } else if token.starts_with('!') {
+            parsed.flags.push(token.to_string());
+        } else if let Some(ms) = token.strip_suffix("ms").filter(|ms| !ms.is_empty()) {
+            // tracert can write "12ms" with no space
+            if let Ok(ms) = ms.trim_start_matches('<').parse::<f64>() {
+                parsed.rtts_ms.push(ms);
+            }
+        } else if let Ok(ms) = token.trim_start_matches('<').parse::<f64>() {
+            if tokens.peek() == Some(&"ms") {
+                tokens.next();
+                parsed.rtts_ms.push(ms);
+            }
+        } else if let Ok(address) = token.trim_matches(['(', ')', '[', ']']).parse::<IpAddr>() {
+            let address = address.to_string();
+            if !parsed.addresses.contains(&address) {
+                parsed.addresses.push(address);
+            }
+        }
+    }
+    Some(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reply_times_from_localized_windows_ping() {
+        let german = "Ping wird ausgeführt für 1.1.1.1 mit 32 Bytes Daten:\r\n\
+                      Antwort von 1.1.1.1: Bytes=32 Zeit=12ms TTL=57\r\n\
+                      Antwort von 1.1.1.1: Bytes=32 Zeit<1ms TTL=57\r\n\
+                      Zeitüberschreitung der Anforderung.\r\n";
+        assert_eq!(reply_times(german), vec![12.0, 1.0]);
+
+        let french = "Réponse de 1.1.1.1 : octets=32 temps=14 ms TTL=57\r\n";
+        assert_eq!(reply_times(french), vec![14.0]);
+    }
+
+    #[test]
+    fn reply_times_with_a_decimal_comma() {
+        let output = "64 bytes from 1.1.1.1: icmp_seq=1 ttl=57 time=12,5 ms\n";
+        assert_eq!(reply_times(output), vec![12.5]);
+    }
+
+    #[test]
+    fn hop_from_localized_tracert() {
+        let hop = parse_hop("  5     *        *        *     Zeitüberschreitung der Anforderung.").unwrap();
+        assert_eq!((hop.hop, hop.lost), (5, 3));
+        assert!(hop.addresses.is_empty());
+
+        let hop = parse_hop("  2    <1 ms     3 ms    12ms  fritz.box [192.168.178.1]").unwrap();
+        assert_eq!(hop.rtts_ms, vec![1.0, 3.0, 12.0]);
+        assert_eq!(hop.addresses, vec!["192.168.178.1".to_string()]);
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ConsumedEntry {
+    consumed_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+// Persisted set of single-use identifiers (approval ids, token ids). Entries are
+// kept until the credential they belong to would have expired anyway.
+pub struct ConsumedCache {
+    file: &'static str,
+}
+
+static CACHE_LOCK: Mutex<()> = Mutex::new(());
+
+impl ConsumedCache {
+    pub const fn new(file: &'static str) -> Self {
+        Self { file }
+    }
+
+    // Mark `key` as used. Returns when it was first consumed if it already was.
+    pub fn consume(&self, key: &str, expires_at: DateTime<Utc>) -> Result<(), DateTime<Utc>> {
+        let _guard = CACHE_LOCK.lock().unwrap();
+        let now = Utc::now();
+        let mut entries: HashMap<String, ConsumedEntry> = storage::load_json(self.file);
+        entries.retain(|_, entry| entry.expires_at > now);
+
+        if let Some(entry) = entries.get(key) {
+            return Err(entry.consumed_at);
+        }
+
+        entries.insert(
+            key.to_string(),
+            ConsumedEntry {
+                consumed_at: now,
+                expires_at,
+            },
+        );
+        if let Err(e) = storage::save_json(self.file, &entries) {
+            log::error!("Failed to persist {}: {}", self.file, e);
+        }
+        Ok(())
+    }
+}
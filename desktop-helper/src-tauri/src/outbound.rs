@@ -0,0 +1,104 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use reqwest::tls::TlsInfo;
+use reqwest::{Client, Url};
+
+use crate::{audit, pairing};
+
+// Execution output goes only to the server the helper was paired with. Its
+// origin is pinned when pairing completes, so OHFIXIT_SERVER_URL changed
+// afterwards (a tampered launcher, a stray environment) can't redirect
+// reports elsewhere. With OHFIXIT_PIN_SERVER_CERT=true the server's TLS
+// certificate is pinned too.
+const REPORT_PATH: &str = "/api/automation/helper/report";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn configured_origin() -> Result<String, String> {
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    Url::parse(&server_url)
+        .map(|url| url.origin().ascii_serialization())
+        .map_err(|e| format!("Invalid OHFIXIT_SERVER_URL '{}': {}", server_url, e))
+}
+
+// SHA-256 (hex) of the certificate `origin` presents; None over plain HTTP
+async fn certificate_sha256(client: &Client, origin: &str) -> Result<Option<String>, String> {
+    if !origin.starts_with("https://") {
+        return Ok(None);
+    }
+    let response = client
+        .head(origin)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", origin, e))?;
+    let der = response
+        .extensions()
+        .get::<TlsInfo>()
+        .and_then(|info| info.peer_certificate())
+        .ok_or_else(|| format!("{} presented no certificate", origin))?;
+    let digest = ring::digest::digest(&ring::digest::SHA256, der);
+    Ok(Some(digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect()))
+}
+
+// Pin the configured server as the only report destination
+pub async fn pin(client: &Client) -> Result<(), String> {
+    let origin = configured_origin()?;
+    let cert = if std::env::var("OHFIXIT_PIN_SERVER_CERT").is_ok_and(|v| v == "true") {
+        certificate_sha256(client, &origin).await?
+    } else {
+        None
+    };
+    log::info!("Pinned report destination {}{}", origin, if cert.is_some() { " and its certificate" } else { "" });
+    audit::record("report_destination_pinned", serde_json::json!({ "origin": origin, "cert_sha256": cert }));
+    pairing::pin_server(origin, cert)
+}
+
+// The report endpoint of the pinned server. Err, with an audit entry, when
+// the configured server or its certificate isn't the pinned one.
+pub async fn report_url(client: &Client) -> Result<String, String> {
+    pinned_url(client, REPORT_PATH).await
+}
+
+// `path` on the pinned server, checked the same way as the report endpoint
+pub async fn pinned_url(client: &Client, path: &str) -> Result<String, String> {
+    let configured = configured_origin()?;
+    let (origin, cert) = match pairing::pinned_server() {
+        Some(pinned) => pinned,
+        // Paired before destinations were pinned
+        None => {
+            pin(client).await?;
+            pairing::pinned_server().ok_or_else(|| "Failed to pin the report destination".to_string())?
+        }
+    };
+    if configured != origin {
+        return Err(refuse(
+            format!("Refusing to report to {}: this helper reports only to {}", configured, origin),
+            serde_json::json!({ "configured": configured, "pinned": origin }),
+        ));
+    }
+    if let Some(expected) = cert {
+        let presented = certificate_sha256(client, &origin).await?;
+        if presented.as_deref() != Some(expected.as_str()) {
+            return Err(refuse(
+                format!("Refusing to report to {}: its certificate doesn't match the pinned one", origin),
+                serde_json::json!({ "origin": origin, "expected": expected, "presented": presented }),
+            ));
+        }
+    }
+    Ok(format!("{}{}", origin, path))
+}
+
+// Whether a credential may be sent to `url`: HTTPS, or plain HTTP to this
+// machine for development
+pub fn https_or_loopback(url: &Url) -> bool {
+    let host = url.host_str().unwrap_or_default().trim_matches(['[', ']']);
+    url.scheme() == "https" || host == "localhost" || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+fn refuse(reason: String, details: serde_json::Value) -> String {
+    log::error!("{}", reason);
+    audit::record("report_destination_refused", details);
+    reason
+}
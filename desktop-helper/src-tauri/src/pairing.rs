@@ -0,0 +1,543 @@
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Datelike, Utc};
+use rcgen::{CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, PKCS_ECDSA_P256_SHA256};
+use reqwest::{Client, Identity};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::cmd::read_trimmed;
+use crate::{attestation, audit, credentials, instance, local_tls, outbound, scheduler, storage, AppState, Claims};
+
+// This machine's identity: generated on first run, registered with the server
+// and confirmed by the user entering the pairing code in OhFixIt
+const DEVICE_FILE: &str = "device.json";
+// The device's secrets are kept in the credential store; device.json only has
+// the public parts unless the store is unavailable
+const KEY_SECRET: &str = "device_private_key";
+const TLS_KEY_SECRET: &str = "device_tls_key";
+const SESSION_SECRET: &str = "session_token";
+// Until the user confirms, registration is repeated so the server's pending
+// entry (and its code) doesn't expire
+const REGISTER_INTERVAL: Duration = Duration::from_secs(30);
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(10);
+// No 0/O or 1/I, so a code read off the screen can be typed back
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+// Client certificate presented on reports. Self-signed; the server pins its
+// fingerprint to this device when it's uploaded.
+const CERT_VALIDITY_DAYS: i64 = 365;
+const CERT_RENEW_DAYS: i64 = 30;
+// Every request to the status server carries the local session token, which
+// only this machine and the server it's paired with know (see policy::enforce)
+pub const SESSION_HEADER: &str = "x-ohfixit-session";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct Device {
+    device_id: String,
+    // PKCS#8 Ed25519 key, base64. Stays on this machine.
+    private_key: String,
+    public_key: String,
+    pairing_code: String,
+    paired_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    session_token: String,
+    // When the server accepted session_token
+    #[serde(default)]
+    session_shared_at: Option<DateTime<Utc>>,
+    // PEM; only kept once the server has accepted the certificate
+    #[serde(default)]
+    tls_key: Option<String>,
+    #[serde(default)]
+    tls_certificate: Option<String>,
+    #[serde(default)]
+    tls_expires_at: Option<DateTime<Utc>>,
+    // The fingerprint the server last accepted in a registration
+    #[serde(default)]
+    registered_fingerprint: Option<String>,
+    // Where execution output may be reported (see outbound.rs)
+    #[serde(default)]
+    server_origin: Option<String>,
+    #[serde(default)]
+    server_cert_sha256: Option<String>,
+    // The local TLS certificate the server last accepted (SHA-256)
+    #[serde(default)]
+    local_cert_sha256: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PairingStatus {
+    pub device_id: String,
+    pub paired: bool,
+    // Shown to the user until pairing is confirmed
+    pairing_code: Option<String>,
+    paired_at: Option<DateTime<Utc>>,
+}
+
+static DEVICE: Mutex<Option<Device>> = Mutex::new(None);
+static HARDWARE_UUID: OnceLock<String> = OnceLock::new();
+
+fn device() -> Device {
+    let mut cached = DEVICE.lock().unwrap();
+    if let Some(device) = cached.as_ref() {
+        return device.clone();
+    }
+    let mut device = match load() {
+        Ok(device) => device,
+        Err(e) => {
+            // Not cached, so the next call tries the store again. Regenerating
+            // here would lose the pairing.
+            log::error!("{}", e);
+            return storage::load_json(DEVICE_FILE);
+        }
+    };
+    if device.device_id.is_empty() || Ed25519KeyPair::from_pkcs8(&decode(&device.private_key)).is_err() {
+        device = generate();
+        if let Err(e) = save(&device) {
+            log::error!("Failed to persist device identity: {}", e);
+        }
+        log::info!("Generated device identity {}", device.device_id);
+    } else if device.session_token.is_empty() {
+        // Paired before session tokens existed
+        device.session_token = session_token_value(&SystemRandom::new());
+        if let Err(e) = save(&device) {
+            log::error!("Failed to persist session token: {}", e);
+        }
+    }
+    *cached = Some(device.clone());
+    device
+}
+
+fn load() -> Result<Device, String> {
+    let mut device: Device = storage::load_json(DEVICE_FILE);
+    if !device.private_key.is_empty() {
+        // Written before secrets moved to the credential store (or while it
+        // was unavailable): move them now
+        save(&device)?;
+        return Ok(device);
+    }
+    if device.device_id.is_empty() {
+        return Ok(device);
+    }
+    device.private_key = credentials::get(KEY_SECRET)?.unwrap_or_default();
+    device.tls_key = credentials::get(TLS_KEY_SECRET)?;
+    device.session_token = credentials::get(SESSION_SECRET)?.unwrap_or_default();
+    Ok(device)
+}
+
+// Without a credential store the secrets stay in device.json, so the device
+// keeps its identity and pairing
+fn save(device: &Device) -> Result<(), String> {
+    let stored = credentials::set(KEY_SECRET, &device.private_key)
+        .and_then(|_| credentials::set(SESSION_SECRET, &device.session_token))
+        .and_then(|_| device.tls_key.as_deref().map_or(Ok(()), |key| credentials::set(TLS_KEY_SECRET, key)));
+    let mut public = device.clone();
+    match stored {
+        Ok(()) => {
+            public.private_key.clear();
+            public.session_token.clear();
+            public.tls_key = None;
+        }
+        Err(e) => log::error!("Keeping device secrets in {}: {}", DEVICE_FILE, e),
+    }
+    storage::save_json(DEVICE_FILE, &public)
+}
+
+fn generate() -> Device {
+    let rng = SystemRandom::new();
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).expect("Ed25519 key generation failed");
+    let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).expect("generated key is valid");
+    Device {
+        device_id: uuid::Uuid::new_v4().to_string(),
+        private_key: general_purpose::STANDARD.encode(pkcs8.as_ref()),
+        public_key: general_purpose::STANDARD.encode(key_pair.public_key().as_ref()),
+        pairing_code: pairing_code(&rng),
+        session_token: session_token_value(&rng),
+        ..Default::default()
+    }
+}
+
+// "ABCD-EFGH"
+fn pairing_code(rng: &SystemRandom) -> String {
+    let mut bytes = [0u8; 8];
+    rng.fill(&mut bytes).expect("system randomness unavailable");
+    let chars: String = bytes.iter().map(|b| CODE_ALPHABET[*b as usize % CODE_ALPHABET.len()] as char).collect();
+    format!("{}-{}", &chars[..4], &chars[4..])
+}
+
+fn session_token_value(rng: &SystemRandom) -> String {
+    let mut bytes = [0u8; 32];
+    rng.fill(&mut bytes).expect("system randomness unavailable");
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn decode(value: &str) -> Vec<u8> {
+    general_purpose::STANDARD.decode(value).unwrap_or_default()
+}
+
+pub fn status() -> PairingStatus {
+    let device = device();
+    PairingStatus {
+        paired: device.paired_at.is_some(),
+        pairing_code: device.paired_at.is_none().then_some(device.pairing_code),
+        paired_at: device.paired_at,
+        device_id: device.device_id,
+    }
+}
+
+// Raw Ed25519 public key, base64, as registered with the server
+pub fn public_key() -> String {
+    device().public_key
+}
+
+// Base64 Ed25519 signature over `message` with the device key
+pub fn sign(message: &[u8]) -> Result<String, String> {
+    let device = device();
+    let key_pair = Ed25519KeyPair::from_pkcs8(&decode(&device.private_key))
+        .map_err(|e| format!("Invalid device key: {}", e))?;
+    Ok(general_purpose::STANDARD.encode(key_pair.sign(message).as_ref()))
+}
+
+// SHA-256 (hex) of the hardware UUID and this install's device id. A
+// device.json copied to another machine keeps the device id but not the
+// fingerprint, so approvals minted for the original don't work there.
+pub fn fingerprint() -> String {
+    let hardware_uuid = HARDWARE_UUID.get_or_init(|| {
+        hardware_uuid().unwrap_or_else(|| {
+            log::error!("Could not read the hardware UUID; the device fingerprint only covers the install");
+            String::new()
+        })
+    });
+    let digest = ring::digest::digest(&ring::digest::SHA256, format!("{}.{}", hardware_uuid, device().device_id).as_bytes());
+    digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hardware_uuid() -> Option<String> {
+    match std::env::consts::OS {
+        // "IOPlatformUUID" = "5C5B5F2E-..."
+        "macos" => read_trimmed("/usr/sbin/ioreg", &["-rd1", "-c", "IOPlatformExpertDevice"])?
+            .lines()
+            .find(|line| line.contains("IOPlatformUUID"))
+            .and_then(|line| line.split('"').nth(3))
+            .map(str::to_string),
+        // MachineGuid    REG_SZ    0f3c...
+        "windows" => read_trimmed("reg", &["query", r"HKLM\SOFTWARE\Microsoft\Cryptography", "/v", "MachineGuid"])?
+            .lines()
+            .find(|line| line.contains("MachineGuid"))
+            .and_then(|line| line.split_whitespace().last())
+            .map(str::to_string),
+        _ => ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+            .iter()
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .map(|id| id.trim().to_string())
+            .find(|id| !id.is_empty()),
+    }
+}
+
+// Approval tokens name the device they were minted for and its fingerprint; a
+// token for another machine (or any token before this one is paired)
+// authorizes nothing here
+pub fn check_binding(claims: &Claims) -> Result<(), String> {
+    let device = device();
+    if device.paired_at.is_none() {
+        return Err(format!(
+            "This helper isn't paired yet. Enter code {} in OhFixIt to pair it.",
+            device.pairing_code
+        ));
+    }
+    let fingerprint = fingerprint();
+    let bound = claims.device_id.as_deref() == Some(device.device_id.as_str())
+        && claims.device_fingerprint.as_deref() == Some(fingerprint.as_str());
+    if bound {
+        return Ok(());
+    }
+    audit::record("token_device_mismatch", serde_json::json!({
+        "action_id": claims.action_id,
+        "token_device_id": claims.device_id,
+        "token_fingerprint": claims.device_fingerprint,
+        "device_id": device.device_id,
+        "fingerprint": fingerprint,
+    }));
+    Err(format!("Token is not bound to this device ({})", device.device_id))
+}
+
+pub fn session_token() -> String {
+    device().session_token
+}
+
+pub fn valid_session(headers: &HeaderMap) -> bool {
+    let expected = session_token();
+    headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| !expected.is_empty() && instance::digest(v) == instance::digest(&expected))
+}
+
+#[tauri::command]
+pub fn pairing_status() -> PairingStatus {
+    status()
+}
+
+// HTTP client for talking to the server: presents the device certificate
+// once one has been enrolled, and exposes the server's certificate for
+// outbound::report_url to check against the pin
+pub fn client() -> Client {
+    let device = device();
+    let builder = Client::builder().tls_info(true);
+    let builder = match (device.tls_certificate, device.tls_key) {
+        (Some(cert), Some(key)) => match Identity::from_pkcs8_pem(cert.as_bytes(), key.as_bytes()) {
+            Ok(identity) => builder.identity(identity),
+            Err(e) => {
+                log::error!("Failed to load device certificate, reporting without it: {}", e);
+                builder
+            }
+        },
+        _ => builder,
+    };
+    builder.build().unwrap_or_else(|e| {
+        log::error!("Failed to build the server client: {}", e);
+        Client::new()
+    })
+}
+
+// The server reports may go to (origin, certificate SHA-256), pinned when
+// pairing completed
+pub fn pinned_server() -> Option<(String, Option<String>)> {
+    let device = device();
+    device.server_origin.map(|origin| (origin, device.server_cert_sha256))
+}
+
+pub fn pin_server(origin: String, cert_sha256: Option<String>) -> Result<(), String> {
+    let device = device();
+    let updated = {
+        let mut cached = DEVICE.lock().unwrap();
+        let device = cached.get_or_insert(device);
+        device.server_origin = Some(origin);
+        device.server_cert_sha256 = cert_sha256;
+        device.clone()
+    };
+    save(&updated)
+}
+
+// Registers until paired, shares the session token and local TLS certificate,
+// then attests the build hourly and keeps a client certificate enrolled
+pub fn spawn_registration(app: AppHandle) {
+    scheduler::spawn_job("pairing", REGISTER_INTERVAL, false, move || {
+        let app = app.clone();
+        async move {
+            let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+            // Also re-registers when the fingerprint changed or predates it
+            if device().paired_at.is_none() || device().registered_fingerprint != Some(fingerprint()) {
+                return register(&client).await;
+            }
+            if device().session_shared_at.is_none() {
+                return share_session(&client).await;
+            }
+            if let Some((certificate, sha256)) = local_tls::enabled().then(local_tls::certificate).flatten() {
+                if device().local_cert_sha256.as_ref() != Some(&sha256) {
+                    return share_local_certificate(&client, certificate, sha256).await;
+                }
+            }
+            let renew_after = Utc::now() + chrono::Duration::days(CERT_RENEW_DAYS);
+            if device().tls_expires_at.is_some_and(|expires| expires > renew_after) {
+                return if attestation::due() { attestation::report(&client).await } else { Ok(String::new()) };
+            }
+            let message = enroll_certificate(&client).await?;
+            app.state::<Mutex<AppState>>().lock().unwrap().client = self::client();
+            Ok(message)
+        }
+    });
+}
+
+#[derive(Debug, Deserialize)]
+struct RegisterResponse {
+    // "pending" until the user enters the code, then "paired"
+    status: String,
+    #[serde(alias = "pairedAt")]
+    paired_at: Option<DateTime<Utc>>,
+}
+
+// Announce this device's public key and pairing code. The signature proves
+// the registration comes from the holder of the key.
+pub async fn register(client: &Client) -> Result<String, String> {
+    let device = device();
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let fingerprint = fingerprint();
+    let signature = sign(format!("{}.{}.{}", device.device_id, device.pairing_code, fingerprint).as_bytes())?;
+
+    let response: RegisterResponse = client
+        .post(format!("{}/api/automation/helper/pair", server_url))
+        .timeout(REGISTER_TIMEOUT)
+        .json(&serde_json::json!({
+            "deviceId": device.device_id,
+            "publicKey": device.public_key,
+            "pairingCode": device.pairing_code,
+            "fingerprint": fingerprint,
+            "signature": signature,
+            "os": std::env::consts::OS,
+            "name": device_name(),
+        }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to register device: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Malformed pairing response: {}", e))?;
+
+    if response.status != "paired" {
+        return Ok(format!("Waiting for pairing code {} to be entered", device.pairing_code));
+    }
+    let newly_paired = device.paired_at.is_none();
+    let paired_at = response.paired_at.unwrap_or_else(Utc::now);
+    let updated = {
+        let mut cached = DEVICE.lock().unwrap();
+        let device = cached.get_or_insert(device);
+        device.paired_at = Some(paired_at);
+        device.registered_fingerprint = Some(fingerprint);
+        device.clone()
+    };
+    save(&updated)?;
+    if !newly_paired {
+        return Ok(format!("Registered fingerprint for device {}", updated.device_id));
+    }
+    audit::record("device_paired", serde_json::json!({ "device_id": updated.device_id }));
+    outbound::pin(client).await?;
+    Ok(format!("Paired as device {}", updated.device_id))
+}
+
+// Hand the session token to the server this device is paired with, so the
+// web app can call the status server. Signed like the registration.
+async fn share_session(client: &Client) -> Result<String, String> {
+    let device = device();
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let signature = sign(format!("{}.{}", device.device_id, device.session_token).as_bytes())?;
+
+    client
+        .post(format!("{}/api/automation/helper/pair/session", server_url))
+        .timeout(REGISTER_TIMEOUT)
+        .json(&serde_json::json!({
+            "deviceId": device.device_id,
+            "sessionToken": device.session_token,
+            "signature": signature,
+        }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to share session token: {}", e))?;
+
+    let updated = {
+        let mut cached = DEVICE.lock().unwrap();
+        let device = cached.get_or_insert(device);
+        device.session_shared_at = Some(Utc::now());
+        device.clone()
+    };
+    save(&updated)?;
+    audit::record("session_token_shared", serde_json::json!({ "device_id": updated.device_id }));
+    Ok("Shared session token with the server".to_string())
+}
+
+// Hand the status server's certificate to the paired server, which then only
+// talks to a local API presenting it. Signed like the registration.
+async fn share_local_certificate(client: &Client, certificate: String, sha256: String) -> Result<String, String> {
+    let device = device();
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let signature = sign(certificate.as_bytes())?;
+
+    client
+        .post(format!("{}/api/automation/helper/pair/local-certificate", server_url))
+        .timeout(REGISTER_TIMEOUT)
+        .json(&serde_json::json!({
+            "deviceId": device.device_id,
+            "certificate": certificate,
+            "signature": signature,
+        }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to share local TLS certificate: {}", e))?;
+
+    let updated = {
+        let mut cached = DEVICE.lock().unwrap();
+        let device = cached.get_or_insert(device);
+        device.local_cert_sha256 = Some(sha256.clone());
+        device.clone()
+    };
+    save(&updated)?;
+    audit::record("local_certificate_shared", serde_json::json!({ "device_id": updated.device_id, "sha256": sha256 }));
+    Ok(format!("Shared local TLS certificate {}", &sha256[..12]))
+}
+
+// Generate a key and self-signed client certificate (CN = device id) and
+// upload it signed with the device key, so the server can pin it
+async fn enroll_certificate(client: &Client) -> Result<String, String> {
+    let device = device();
+    let server_url = std::env::var("OHFIXIT_SERVER_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let (certificate, key, expires_at) = self_signed(&device.device_id)?;
+    let signature = sign(certificate.as_bytes())?;
+
+    client
+        .post(format!("{}/api/automation/helper/pair/certificate", server_url))
+        .timeout(REGISTER_TIMEOUT)
+        .json(&serde_json::json!({
+            "deviceId": device.device_id,
+            "certificate": certificate,
+            "signature": signature,
+        }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to enroll device certificate: {}", e))?;
+
+    let updated = {
+        let mut cached = DEVICE.lock().unwrap();
+        let device = cached.get_or_insert(device);
+        device.tls_key = Some(key);
+        device.tls_certificate = Some(certificate);
+        device.tls_expires_at = Some(expires_at);
+        device.clone()
+    };
+    save(&updated)?;
+    audit::record("device_certificate_enrolled", serde_json::json!({
+        "device_id": updated.device_id,
+        "expires_at": expires_at,
+    }));
+    Ok(format!("Enrolled client certificate valid until {}", expires_at.format("%Y-%m-%d")))
+}
+
+// (certificate PEM, PKCS#8 key PEM, expiry)
+fn self_signed(device_id: &str) -> Result<(String, String, DateTime<Utc>), String> {
+    let key_pair = rcgen::KeyPair::generate_for(&PKCS_ECDSA_P256_SHA256)
+        .map_err(|e| format!("Failed to generate certificate key: {}", e))?;
+    let mut params = CertificateParams::default();
+    let mut name = DistinguishedName::new();
+    name.push(DnType::CommonName, device_id);
+    name.push(DnType::OrganizationName, "OhFixIt Desktop Helper");
+    params.distinguished_name = name;
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::days(CERT_VALIDITY_DAYS);
+    params.not_before = rcgen::date_time_ymd(now.year(), now.month() as u8, now.day() as u8);
+    params.not_after = rcgen::date_time_ymd(expires_at.year(), expires_at.month() as u8, expires_at.day() as u8);
+    let certificate = params
+        .self_signed(&key_pair)
+        .map_err(|e| format!("Failed to create device certificate: {}", e))?;
+    Ok((certificate.pem(), key_pair.serialize_pem(), expires_at))
+}
+
+fn device_name() -> String {
+    match std::env::consts::OS {
+        "macos" => read_trimmed("/usr/sbin/scutil", &["--get", "ComputerName"]),
+        "windows" => std::env::var("COMPUTERNAME").ok(),
+        _ => read_trimmed("/bin/hostname", &[]),
+    }
+    .unwrap_or_else(|| format!("{} computer", std::env::consts::OS))
+}
@@ -0,0 +1,298 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+use crate::health::CheckResult;
+
+// The check behind GET /health/panics: kernel panics (bug checks on Windows)
+// and shutdowns that never happened cleanly in the last WINDOW, so an app that
+// keeps crashing can be told apart from a machine that keeps going down
+// (hardware, drivers, power). macOS reads its panic reports and pairs `last`'s
+// reboots with shutdowns; Windows reads Kernel-Power 41 and bug check events;
+// Linux reads pstore and kdump and pairs `last` the same way.
+const WINDOW: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+// This many kernel panics in WINDOW makes the check critical
+const CRITICAL_PANICS: usize = 3;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Event {
+    timestamp: Option<String>,
+    // "kernel_panic" or "unexpected_shutdown"
+    kind: String,
+    // The report, log or tool it came from
+    source: String,
+    // The panic string or bug check code, when there is one
+    detail: Option<String>,
+}
+
+pub fn check() -> CheckResult {
+    let mut events = match std::env::consts::OS {
+        "macos" => macos(),
+        "windows" => windows(),
+        _ => linux(),
+    };
+    let since = Utc::now() - chrono::Duration::from_std(WINDOW).unwrap_or_default();
+    events.retain(|event| {
+        event
+            .timestamp
+            .as_deref()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .map_or(true, |at| at >= since)
+    });
+    events.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+    let panics = events.iter().filter(|event| event.kind == "kernel_panic").count();
+    let shutdowns = events.iter().filter(|event| event.kind == "unexpected_shutdown").count();
+    let days = WINDOW.as_secs() / 86_400;
+    let (status, detail) = match (panics, shutdowns) {
+        (0, 0) => ("ok", format!("No kernel panics or unexpected shutdowns in the last {} days", days)),
+        (panics, shutdowns) => (
+            if panics >= CRITICAL_PANICS { "critical" } else { "warning" },
+            format!("{} kernel panic(s) and {} unexpected shutdown(s) in the last {} days", panics, shutdowns, days),
+        ),
+    };
+    let last_boot = DateTime::<Utc>::from_timestamp(sysinfo::System::boot_time() as i64, 0).map(|at| at.to_rfc3339());
+    CheckResult::new(status, detail).with_data(serde_json::json!({
+        "window_days": days,
+        "kernel_panics": panics,
+        "unexpected_shutdowns": shutdowns,
+        "last_boot": last_boot,
+        "events": events,
+    }))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn rfc3339(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339()
+}
+
+fn files(directory: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(directory)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default()
+}
+
+// Reboots `last` records without a shutdown before them. `last` lists the
+// newest first: "reboot    ~    Tue Oct 14 09:12" on macOS, "reboot   system
+// boot  6.8.0-45-generic Tue Oct 14 09:12:01 2025 - …" on Linux (-F).
+fn unexpected_reboots(output: &str) -> Vec<Event> {
+    let mut entries: Vec<(&str, Option<DateTime<Utc>>)> = output
+        .lines()
+        .filter_map(|line| {
+            let kind = line.split_whitespace().next()?;
+            (kind == "reboot" || kind == "shutdown").then(|| (kind, last_time(line)))
+        })
+        .collect();
+    entries.reverse();
+    let mut events = Vec::new();
+    // The oldest reboot has nothing before it to compare against
+    let mut clean = true;
+    for (kind, at) in entries {
+        if kind == "shutdown" {
+            clean = true;
+            continue;
+        }
+        if !clean {
+            events.push(Event {
+                timestamp: at.map(|at| at.to_rfc3339()),
+                kind: "unexpected_shutdown".to_string(),
+                source: "last".to_string(),
+                detail: Some("Started up again without a clean shutdown first".to_string()),
+            });
+        }
+        clean = false;
+    }
+    events
+}
+
+// The first time on a `last` line: "Tue Oct 14 09:12:01 2025" (Linux -F) or
+// "Tue Oct 14 09:12" (macOS, no year: the most recent such date)
+fn last_time(line: &str) -> Option<DateTime<Utc>> {
+    const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let start = words.iter().position(|word| WEEKDAYS.contains(word))?;
+    let (month, day, time) = (words.get(start + 1)?, words.get(start + 2)?, words.get(start + 3)?);
+    let year = words.get(start + 4).filter(|year| year.len() == 4 && year.chars().all(|c| c.is_ascii_digit()));
+    let parse = |year: &str| {
+        let time = if time.len() == 5 { format!("{}:00", time) } else { time.to_string() };
+        let naive = NaiveDateTime::parse_from_str(&format!("{} {} {} {}", month, day, time, year), "%b %d %H:%M:%S %Y").ok()?;
+        Local.from_local_datetime(&naive).earliest().map(|at| at.with_timezone(&Utc))
+    };
+    match year {
+        Some(year) => parse(year),
+        None => {
+            let this_year = Local::now().year();
+            parse(&this_year.to_string())
+                .filter(|at| *at <= Utc::now())
+                .or_else(|| parse(&(this_year - 1).to_string()))
+        }
+    }
+}
+
+// Panic reports are "panic-full-<date>.ips" (or "Kernel-<date>.panic" on
+// older systems), whose body holds the panic string
+fn macos() -> Vec<Event> {
+    let directories = ["/Library/Logs/DiagnosticReports", "/Library/Logs/DiagnosticReports/Retired"];
+    let mut events: Vec<Event> = directories
+        .iter()
+        .flat_map(|directory| files(Path::new(directory)))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            (name.starts_with("panic") && name.ends_with(".ips")) || name.ends_with(".panic")
+        })
+        .map(|path| {
+            let text = std::fs::read_to_string(&path).unwrap_or_default();
+            let (header, body) = text.split_once('\n').unwrap_or((&text, ""));
+            let header: serde_json::Value = serde_json::from_str(header).unwrap_or_default();
+            let body: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+            // "panic(cpu 2 caller 0xfffffe0012345678): watchdog timeout: …"
+            let detail = body["panicString"]
+                .as_str()
+                .unwrap_or(&text)
+                .lines()
+                .find(|line| line.contains("panic("))
+                .map(|line| line.trim().chars().take(300).collect());
+            let timestamp = header["timestamp"]
+                .as_str()
+                .and_then(|at| DateTime::parse_from_str(at, "%Y-%m-%d %H:%M:%S%.f %z").ok())
+                .map(|at| at.with_timezone(&Utc).to_rfc3339())
+                .or_else(|| modified(&path).map(rfc3339));
+            Event {
+                timestamp,
+                kind: "kernel_panic".to_string(),
+                source: path.to_string_lossy().into_owned(),
+                detail,
+            }
+        })
+        .collect();
+    if let Some(output) = read_output("/usr/bin/last", &["reboot", "shutdown"]) {
+        events.extend(unexpected_reboots(&output));
+    }
+    events
+}
+
+// Kernel-Power 41 is logged at the first boot after power was lost or the
+// machine hung or was held off; WER's 1001 after a blue screen carries the bug
+// check code
+fn windows() -> Vec<Event> {
+    let script = format!(
+        r#"
+$ErrorActionPreference = 'SilentlyContinue'
+$since = (Get-Date).AddDays(-{days})
+$events = Get-WinEvent -FilterHashtable @{{ LogName = 'System'; Id = 41, 1001; StartTime = $since }} |
+  Where-Object {{ $_.ProviderName -in 'Microsoft-Windows-Kernel-Power', 'Microsoft-Windows-WER-SystemErrorReporting' }} |
+  ForEach-Object {{ [ordered]@{{ id = $_.Id; at = $_.TimeCreated.ToUniversalTime().ToString('o'); message = ($_.Message -split "`n")[0] }} }}
+ConvertTo-Json -InputObject @($events) -Compress
+"#,
+        days = WINDOW.as_secs() / 86_400
+    );
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    let Some(output) = read_output(&powershell.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", &script]) else {
+        return Vec::new();
+    };
+    let Ok(serde_json::Value::Array(entries)) = serde_json::from_str(output.trim()) else { return Vec::new() };
+    let mut events: Vec<Event> = entries
+        .iter()
+        .map(|entry| {
+            let bugcheck = entry["id"].as_i64() == Some(1001);
+            Event {
+                timestamp: entry["at"].as_str().map(str::to_string),
+                kind: if bugcheck { "kernel_panic" } else { "unexpected_shutdown" }.to_string(),
+                source: if bugcheck { "WER-SystemErrorReporting 1001" } else { "Kernel-Power 41" }.to_string(),
+                detail: entry["message"].as_str().map(|message| message.trim().to_string()).filter(|message| !message.is_empty()),
+            }
+        })
+        .collect();
+
+    // A dump without an event (the log was cleared) still counts
+    let minidumps = system32().parent().map(|windows| windows.join("Minidump")).unwrap_or_default();
+    for dump in files(&minidumps).into_iter().filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dmp"))) {
+        let Some(at) = modified(&dump).map(DateTime::<Utc>::from) else { continue };
+        let near = |event: &Event| {
+            event
+                .timestamp
+                .as_deref()
+                .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+                .is_some_and(|timestamp| (timestamp.with_timezone(&Utc) - at).num_minutes().abs() < 30)
+        };
+        if !events.iter().any(|event| event.kind == "kernel_panic" && near(event)) {
+            events.push(Event {
+                timestamp: Some(at.to_rfc3339()),
+                kind: "kernel_panic".to_string(),
+                source: dump.to_string_lossy().into_owned(),
+                detail: None,
+            });
+        }
+    }
+    events
+}
+
+// systemd-pstore archives the kernel's last words after a panic into
+// /var/lib/systemd/pstore/<id>/; kdump leaves /var/crash/<date>/
+fn linux() -> Vec<Event> {
+    let mut events = Vec::new();
+    let pstore = files(Path::new("/var/lib/systemd/pstore")).into_iter().chain(files(Path::new("/sys/fs/pstore")));
+    let kdump = files(Path::new("/var/crash"))
+        .into_iter()
+        .filter(|path| path.is_dir() && files(path).iter().any(|file| file.file_name().is_some_and(|name| name.to_string_lossy().starts_with("dmesg"))));
+    for path in pstore.chain(kdump) {
+        let dmesg = if path.is_dir() {
+            files(&path).into_iter().find(|file| file.file_name().is_some_and(|name| name.to_string_lossy().starts_with("dmesg")))
+        } else {
+            Some(path.clone())
+        };
+        // "Kernel panic - not syncing: Fatal exception"
+        let detail = dmesg
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .and_then(|text| text.lines().find(|line| line.contains("Kernel panic")).map(|line| line.trim().chars().take(300).collect()));
+        events.push(Event {
+            timestamp: modified(&path).map(rfc3339),
+            kind: "kernel_panic".to_string(),
+            source: path.to_string_lossy().into_owned(),
+            detail,
+        });
+    }
+    if let Some(output) = read_output("last", &["-x", "-F", "reboot", "shutdown"]) {
+        events.extend(unexpected_reboots(&output));
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_time_reads_english_dates() {
+        let line = "reboot   system boot  6.8.0-45-generic Tue Oct 14 09:12:01 2025 - Wed Oct 15 10:00:00 2025  (1+00:47)";
+        let expected = Local.with_ymd_and_hms(2025, 10, 14, 9, 12, 1).unwrap().with_timezone(&Utc);
+        assert_eq!(last_time(line), Some(expected));
+    }
+
+    #[test]
+    fn last_time_refuses_localized_dates() {
+        // What `last` prints under de_DE instead of the C locale
+        assert_eq!(last_time("reboot   system boot  6.8.0-45-generic Di Okt 14 09:12:01 2025"), None);
+        assert_eq!(last_time("reboot   system boot  6.8.0-45-generic mar. oct. 14 09:12:01 2025"), None);
+    }
+
+    #[test]
+    fn reboot_without_a_shutdown_is_unexpected() {
+        let output = "reboot   system boot  6.8.0 Tue Oct 14 09:12:01 2025 - still running\n\
+                      reboot   system boot  6.8.0 Mon Oct 13 08:00:00 2025 - crash\n\
+                      shutdown system down  6.8.0 Sun Oct 12 23:00:00 2025 - Mon Oct 13 08:00:00 2025\n\
+                      reboot   system boot  6.8.0 Sun Oct 12 07:00:00 2025 - Sun Oct 12 23:00:00 2025\n";
+        let events = unexpected_reboots(output);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, "unexpected_shutdown");
+        let expected = Local.with_ymd_and_hms(2025, 10, 14, 9, 12, 1).unwrap().with_timezone(&Utc).to_rfc3339();
+        assert_eq!(events[0].timestamp.as_deref(), Some(expected.as_str()));
+    }
+}
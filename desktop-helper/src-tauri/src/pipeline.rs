@@ -0,0 +1,342 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::{audit, exec_context, history, receipt, redact, storage, Claims, Execution, RollbackPoint};
+
+// Append-only log of every execution's lifecycle, one JSON event per line.
+// Execution history, audit entries, server reports and UI events are all
+// derived from these events as they're appended (see Run::record).
+const EVENTS_FILE: &str = "execution_events.jsonl";
+// compact() trims the log to the newest events at startup
+const MAX_EVENTS: usize = 5000;
+
+// requested → validated → consented → running → step_completed… → finished,
+// or rolled_back for rollbacks. A request that never starts ends in rejected.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Requested,
+    Validated,
+    Rejected,
+    Consented,
+    Running,
+    StepCompleted,
+    Finished,
+    RolledBack,
+}
+
+impl Stage {
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Requested => "requested",
+            Stage::Validated => "validated",
+            Stage::Rejected => "rejected",
+            Stage::Consented => "consented",
+            Stage::Running => "running",
+            Stage::StepCompleted => "step_completed",
+            Stage::Finished => "finished",
+            Stage::RolledBack => "rolled_back",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Event {
+    pub execution_id: String,
+    pub action_id: String,
+    // "execute", "simulate" or "rollback"
+    pub kind: String,
+    pub stage: Stage,
+    pub at: DateTime<Utc>,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+// Data of the terminal event
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Outcome {
+    pub success: bool,
+    // The user stopped it with abort-all
+    #[serde(default)]
+    pub aborted: bool,
+    // The helper stopped (crash, quit) before it finished
+    #[serde(default)]
+    pub interrupted: bool,
+    // Status line shown to the user
+    pub message: String,
+    // Command output. Raw here so the report can scan it for secrets before
+    // redacting; only the redacted copy is stored (see Run::finish)
+    pub output: String,
+    #[serde(default)]
+    pub commands: Vec<exec_context::CommandRun>,
+    pub rollback_id: Option<String>,
+    pub rollback_point: Option<RollbackPoint>,
+    pub receipt: Option<receipt::Receipt>,
+}
+
+impl Outcome {
+    pub fn new(execution: Execution, message: String) -> Self {
+        Self {
+            success: execution.success,
+            message,
+            output: execution.output,
+            commands: execution.commands,
+            ..Default::default()
+        }
+    }
+}
+
+static EVENTS_LOCK: Mutex<()> = Mutex::new(());
+
+// One execution or rollback moving through the pipeline. Holds what the
+// projections need but mustn't be persisted (the approval token).
+pub struct Run {
+    app: AppHandle,
+    client: Client,
+    token: String,
+    id: String,
+    action_id: String,
+    kind: String,
+}
+
+impl Run {
+    pub fn requested(app: &AppHandle, client: &Client, token: &str, action_id: &str, kind: &str) -> Self {
+        let run = Self {
+            app: app.clone(),
+            client: client.clone(),
+            token: token.to_string(),
+            id: uuid::Uuid::new_v4().to_string(),
+            action_id: action_id.to_string(),
+            kind: kind.to_string(),
+        };
+        run.record(Stage::Requested, serde_json::Value::Null);
+        run
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn action_id(&self) -> &str {
+        &self.action_id
+    }
+
+    pub fn validated(&self, claims: &Claims) {
+        self.record(Stage::Validated, serde_json::json!({
+            "approval_id": claims.approval_id,
+            "chat_id": claims.chat_id,
+            "scope": claims.scope,
+        }));
+    }
+
+    // Returns `error` so callers can `return Err(run.rejected(e))`
+    pub fn rejected(&self, error: String) -> String {
+        self.record(Stage::Rejected, serde_json::json!({ "error": error }));
+        error
+    }
+
+    // The approval was consumed by this run
+    pub fn consented(&self, approval_id: &str) {
+        self.record(Stage::Consented, serde_json::json!({ "approval_id": approval_id }));
+    }
+
+    pub fn running(&self, message: &str) {
+        self.record(Stage::Running, serde_json::json!({ "message": message }));
+        let status_type = if self.kind == "rollback" { "rolling_back" } else { "executing" };
+        crate::emit_status(&self.app, message, status_type);
+    }
+
+    pub fn step_completed(&self, command: &str, run: &exec_context::CommandRun) {
+        self.record(Stage::StepCompleted, serde_json::json!({
+            "command": redact::redact(command).0,
+            "exit_code": run.exit_code,
+            "duration_ms": run.duration_ms,
+        }));
+    }
+
+    // Terminal event: the status line, the history entry and the server
+    // report all come from it
+    pub async fn finish(&self, outcome: Outcome) {
+        let stage = if self.kind == "rollback" { Stage::RolledBack } else { Stage::Finished };
+        let stored = Outcome { output: redact::redact(&outcome.output).0, ..outcome.clone() };
+        let event = self.record(stage, serde_json::to_value(&stored).unwrap_or_default());
+        crate::emit_status(&self.app, &outcome.message, if outcome.success { "success" } else { "error" });
+        project_terminal(&self.client, &self.token, &event, outcome).await;
+    }
+
+    fn record(&self, stage: Stage, data: serde_json::Value) -> Event {
+        let event = Event {
+            execution_id: self.id.clone(),
+            action_id: self.action_id.clone(),
+            kind: self.kind.clone(),
+            stage,
+            at: Utc::now(),
+            data,
+        };
+        append(&event);
+        let _ = self.app.emit("execution-event", &event);
+        event
+    }
+}
+
+// Close out a run the helper didn't live to finish, locally: the approval
+// token is gone, so watchdog::recover reports it to the server itself
+pub fn finish_interrupted(
+    execution_id: &str,
+    action_id: &str,
+    kind: &str,
+    rollback_id: Option<String>,
+    message: String,
+) {
+    let outcome = Outcome {
+        interrupted: true,
+        output: message.clone(),
+        message,
+        rollback_id,
+        ..Default::default()
+    };
+    let event = Event {
+        execution_id: execution_id.to_string(),
+        action_id: action_id.to_string(),
+        kind: kind.to_string(),
+        stage: if kind == "rollback" { Stage::RolledBack } else { Stage::Finished },
+        at: Utc::now(),
+        data: serde_json::to_value(&outcome).unwrap_or_default(),
+    };
+    append(&event);
+    record_history(&event, &outcome);
+}
+
+fn append(event: &Event) {
+    let _guard = EVENTS_LOCK.lock().unwrap();
+    let path = storage::data_dir().join(EVENTS_FILE);
+    let line = serde_json::to_string(event).unwrap_or_default();
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = result {
+        log::error!("Failed to append execution event to {}: {}", path.display(), e);
+    }
+    audit::record(&format!("execution_{}", event.stage.name()), audit_details(event));
+}
+
+// The audit log gets the lifecycle, not the command output
+fn audit_details(event: &Event) -> serde_json::Value {
+    let mut details = serde_json::json!({
+        "execution_id": event.execution_id,
+        "action_id": event.action_id,
+        "kind": event.kind,
+    });
+    let fields: &[&str] = match event.stage {
+        Stage::Finished | Stage::RolledBack => &["success", "aborted", "interrupted", "rollback_id"],
+        _ => &["approval_id", "chat_id", "scope", "error", "command", "exit_code"],
+    };
+    for field in fields {
+        if let Some(value) = event.data.get(*field).filter(|v| !v.is_null()) {
+            details[*field] = value.clone();
+        }
+    }
+    details
+}
+
+fn record_history(event: &Event, outcome: &Outcome) {
+    let started_at = events_for(&event.execution_id)
+        .iter()
+        .find(|e| e.stage == Stage::Running)
+        .map(|e| e.at)
+        .unwrap_or(event.at);
+    let mut record = history::ExecutionRecord::new(&event.action_id, &event.kind, started_at);
+    record.id = event.execution_id.clone();
+    record.finished_at = event.at;
+    record.success = outcome.success;
+    record.aborted = outcome.aborted;
+    record.interrupted = outcome.interrupted;
+    record.rollback_id = outcome.rollback_id.clone();
+    record.receipt = outcome.receipt.clone();
+    history::record(record);
+}
+
+async fn project_terminal(client: &Client, token: &str, event: &Event, outcome: Outcome) {
+    record_history(event, &outcome);
+
+    let execution = Execution { success: outcome.success, output: outcome.output, commands: outcome.commands };
+    let result = match (event.kind.as_str(), outcome.rollback_id) {
+        ("rollback", Some(rollback_id)) => {
+            crate::report_rollback_result(client, token, &event.action_id, &rollback_id, &execution).await
+        }
+        (kind, _) => {
+            crate::report_result(
+                client,
+                token,
+                &event.action_id,
+                &execution,
+                outcome.rollback_point,
+                outcome.receipt.as_ref(),
+                kind == "simulate",
+            )
+            .await
+        }
+    };
+    if let Err(e) = result {
+        log::error!("Failed to report {} of {}: {}", event.kind, event.action_id, e);
+    }
+}
+
+fn load() -> Vec<Event> {
+    let _guard = EVENTS_LOCK.lock().unwrap();
+    read_events()
+}
+
+fn read_events() -> Vec<Event> {
+    fs::read_to_string(storage::data_dir().join(EVENTS_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+pub fn events_for(execution_id: &str) -> Vec<Event> {
+    load().into_iter().filter(|e| e.execution_id == execution_id).collect()
+}
+
+// Drop the oldest events, keeping whole executions together
+pub fn compact() {
+    let _guard = EVENTS_LOCK.lock().unwrap();
+    let events = read_events();
+    if events.len() <= MAX_EVENTS {
+        return;
+    }
+    let cutoff = &events[events.len() - MAX_EVENTS].execution_id;
+    let first = events.iter().position(|e| &e.execution_id == cutoff).unwrap_or(0);
+    let kept: Vec<String> = events[first..].iter().filter_map(|e| serde_json::to_string(e).ok()).collect();
+
+    let path = storage::data_dir().join(EVENTS_FILE);
+    let tmp = path.with_extension("tmp");
+    let result = fs::write(&tmp, kept.join("\n") + "\n").and_then(|_| fs::rename(&tmp, &path));
+    match result {
+        Ok(()) => log::info!("Compacted execution events to {}", kept.len()),
+        Err(e) => log::error!("Failed to compact {}: {}", path.display(), e),
+    }
+}
+
+// GET /executions/{id}: every event recorded for one execution, oldest first
+pub async fn execution_events_handler(Path(id): Path<String>) -> Result<Json<Vec<Event>>, (StatusCode, String)> {
+    let events = tauri::async_runtime::spawn_blocking(move || events_for(&id))
+        .await
+        .unwrap_or_default();
+    if events.is_empty() {
+        return Err((StatusCode::NOT_FOUND, "Unknown execution".to_string()));
+    }
+    Ok(Json(events))
+}
@@ -0,0 +1,320 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+use wasmi::{Caller, Config, Engine, Extern, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::exec_context::{self, CommandRun};
+use crate::manifest::{self, SignedManifest};
+use crate::rollback::CommandContext;
+use crate::{storage, watchdog, ActionDefinition, AppState, Category, Execution, Risk};
+
+// Base64 raw Ed25519 key of the organization allowed to ship plugins, pinned at build time
+const PLUGIN_PUBLIC_KEY: Option<&str> = option_env!("OHFIXIT_PLUGIN_PUBLIC_KEY");
+const PLUGIN_DIR: &str = "plugins";
+
+// Bounds on guest work: instructions executed and linear memory
+const FUEL: u64 = 5_000_000_000;
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+const MAX_READ_BYTES: usize = 1024 * 1024;
+
+// A signed bundle (SignedManifest-shaped) holds one organization's plugins
+#[derive(Debug, Deserialize)]
+struct PluginBundle {
+    organization: String,
+    plugins: Vec<PluginSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginSpec {
+    id: String,
+    title: String,
+    os: String,
+    // Base64 WASM module exporting `memory` and `run() -> i32` (0 means success)
+    module: String,
+    // Absolute paths of the only binaries the module may run
+    #[serde(default)]
+    binaries: Vec<String>,
+    // Files or directories the module may read, {home}/{temp} allowed
+    #[serde(default)]
+    read_paths: Vec<String>,
+    #[serde(default = "default_risk")]
+    risk: Risk,
+    category: Category,
+    #[serde(default)]
+    estimated_time: Option<String>,
+    #[serde(default)]
+    requirements: Vec<String>,
+}
+
+fn default_risk() -> Risk {
+    Risk::Medium
+}
+
+struct Plugin {
+    organization: String,
+    definition: ActionDefinition,
+    module: Vec<u8>,
+    binaries: Vec<String>,
+    read_paths: Vec<PathBuf>,
+}
+
+static LOADED: Mutex<Vec<Plugin>> = Mutex::new(Vec::new());
+
+// Load every signed bundle from <data dir>/plugins and add its actions to the
+// allowlist. Built-in and manifest actions win over plugins with the same id.
+pub fn load(app: &AppHandle) {
+    let Some(public_key) = PLUGIN_PUBLIC_KEY else { return };
+    let Ok(entries) = std::fs::read_dir(storage::data_dir().join(PLUGIN_DIR)) else { return };
+
+    let mut loaded = Vec::new();
+    for path in entries.flatten().map(|e| e.path()).filter(|p| p.extension().is_some_and(|e| e == "json")) {
+        match read_bundle(&path, public_key) {
+            Ok(plugins) => loaded.extend(plugins),
+            Err(e) => log::error!("Ignoring plugin bundle {}: {}", path.display(), e),
+        }
+    }
+    if loaded.is_empty() {
+        return;
+    }
+
+    let state = app.state::<Mutex<AppState>>();
+    let mut state = state.lock().unwrap();
+    loaded.retain(|plugin| {
+        if state.actions.contains_key(&plugin.definition.id) {
+            log::error!("Plugin action '{}' clashes with an existing action, skipped", plugin.definition.id);
+            return false;
+        }
+        log::info!("Loaded plugin action '{}' from {}", plugin.definition.id, plugin.organization);
+        state.actions.insert(plugin.definition.id.clone(), plugin.definition.clone());
+        true
+    });
+    *LOADED.lock().unwrap() = loaded;
+}
+
+// Plugin actions, for re-adding after the allowlist is swapped
+pub fn definitions() -> Vec<ActionDefinition> {
+    LOADED.lock().unwrap().iter().map(|p| p.definition.clone()).collect()
+}
+
+fn read_bundle(path: &Path, public_key: &str) -> Result<Vec<Plugin>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let signed: SignedManifest = serde_json::from_str(&text).map_err(|e| format!("Malformed bundle: {}", e))?;
+    manifest::verify_signature(&signed, public_key)?;
+    let bundle: PluginBundle =
+        serde_json::from_str(&signed.payload).map_err(|e| format!("Invalid bundle: {}", e))?;
+
+    let engine = Engine::default();
+    bundle
+        .plugins
+        .into_iter()
+        .map(|spec| {
+            let module = general_purpose::STANDARD
+                .decode(&spec.module)
+                .map_err(|e| format!("Plugin '{}' module isn't base64: {}", spec.id, e))?;
+            Module::new(&engine, &module).map_err(|e| format!("Plugin '{}' isn't valid WASM: {}", spec.id, e))?;
+            if let Some(binary) = spec.binaries.iter().find(|b| !Path::new(b).is_absolute()) {
+                return Err(format!("Plugin '{}' declares non-absolute binary '{}'", spec.id, binary));
+            }
+
+            let digest = ring::digest::digest(&ring::digest::SHA256, &module);
+            let mut definition = ActionDefinition::new(&spec.id, &spec.title, &spec.os, vec![]).irreversible();
+            definition.plugin = Some(digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect());
+            definition.risk = spec.risk;
+            definition.category = spec.category;
+            definition.requirements = spec.requirements;
+            if let Some(estimated_time) = spec.estimated_time {
+                definition.estimated_time = estimated_time;
+            }
+
+            let context = CommandContext::default();
+            Ok(Plugin {
+                organization: bundle.organization.clone(),
+                definition,
+                module,
+                binaries: spec.binaries,
+                read_paths: spec.read_paths.iter().map(|p| PathBuf::from(context.expand(p))).collect(),
+            })
+        })
+        .collect()
+}
+
+// What the host API gives a running module
+struct HostState {
+    binaries: Vec<String>,
+    read_paths: Vec<PathBuf>,
+    execution_id: String,
+    output: String,
+    commands: Vec<CommandRun>,
+    limits: StoreLimits,
+}
+
+// Run a plugin action's module to completion. Blocks; call off the async executor.
+pub fn execute(action_id: &str, running: &watchdog::Running) -> Execution {
+    let host = {
+        let loaded = LOADED.lock().unwrap();
+        loaded.iter().find(|p| p.definition.id == action_id).map(|plugin| {
+            (
+                plugin.module.clone(),
+                HostState {
+                    binaries: plugin.binaries.clone(),
+                    read_paths: plugin.read_paths.clone(),
+                    execution_id: running.id(),
+                    output: String::new(),
+                    commands: vec![],
+                    limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).instances(1).build(),
+                },
+            )
+        })
+    };
+    let Some((module, host)) = host else {
+        return Execution { success: false, output: format!("Plugin '{}' is not loaded", action_id), commands: vec![] };
+    };
+
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let mut store = Store::new(&engine, host);
+    store.limiter(|host| &mut host.limits);
+
+    let outcome = store
+        .set_fuel(FUEL)
+        .map_err(|e| e.to_string())
+        .and_then(|_| instantiate_and_run(&engine, &mut store, &module));
+    let host = store.into_data();
+    let mut output = host.output;
+    let success = match outcome {
+        Ok(0) => true,
+        Ok(code) => {
+            output.push_str(&format!("Plugin exited with {}\n", code));
+            false
+        }
+        Err(e) => {
+            output.push_str(&format!("Plugin failed: {}\n", e));
+            false
+        }
+    };
+    Execution { success, output, commands: host.commands }
+}
+
+fn instantiate_and_run(engine: &Engine, store: &mut Store<HostState>, module: &[u8]) -> Result<i32, String> {
+    let module = Module::new(engine, module).map_err(|e| e.to_string())?;
+    let mut linker = <Linker<HostState>>::new(engine);
+    linker
+        .func_wrap("ohfixit", "log", host_log)
+        .and_then(|l| l.func_wrap("ohfixit", "run", host_run))
+        .and_then(|l| l.func_wrap("ohfixit", "read_file", host_read_file))
+        .map_err(|e| e.to_string())?;
+
+    let instance = linker
+        .instantiate(&mut *store, &module)
+        .and_then(|pre| pre.start(&mut *store))
+        .map_err(|e| e.to_string())?;
+    let run = instance
+        .get_typed_func::<(), i32>(&*store, "run")
+        .map_err(|e| format!("Module has no run() export: {}", e))?;
+    run.call(&mut *store, ()).map_err(|e| e.to_string())
+}
+
+// log(ptr, len): append UTF-8 text to the action output
+fn host_log(mut caller: Caller<'_, HostState>, ptr: i32, len: i32) {
+    if let Some(text) = read_guest(&caller, ptr, len) {
+        let text = String::from_utf8_lossy(&text).into_owned();
+        caller.data_mut().output.push_str(&text);
+        caller.data_mut().output.push('\n');
+    }
+}
+
+// run(argv_ptr, argv_len) -> exit code: argv is a JSON array whose first entry
+// must be one of the declared binaries. -1 when refused or not startable.
+fn host_run(mut caller: Caller<'_, HostState>, ptr: i32, len: i32) -> i32 {
+    let argv: Vec<String> = match read_guest(&caller, ptr, len).and_then(|b| serde_json::from_slice(&b).ok()) {
+        Some(argv) => argv,
+        None => return -1,
+    };
+    let Some((program, args)) = argv.split_first() else { return -1 };
+    if watchdog::is_aborted(&caller.data().execution_id) {
+        caller.data_mut().output.push_str(&format!("Aborted by user before: {}\n", program));
+        return -1;
+    }
+    if !caller.data().binaries.contains(program) {
+        caller.data_mut().output.push_str(&format!("Refused to run undeclared binary '{}'\n", program));
+        return -1;
+    }
+    if let Err(e) = exec_context::verify_binary(program) {
+        caller.data_mut().output.push_str(&format!("Refused to run {}\n", e));
+        return -1;
+    }
+
+    let mut run = CommandRun::new(program, false);
+    let started = Instant::now();
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let result = child.and_then(|child| {
+        watchdog::track(&caller.data().execution_id, child.id(), program);
+        child.wait_with_output()
+    });
+    run.duration_ms = Some(started.elapsed().as_millis() as u64);
+
+    let host = caller.data_mut();
+    let code = match result {
+        Ok(result) => {
+            host.output.push_str(&String::from_utf8_lossy(&result.stdout));
+            host.output.push_str(&String::from_utf8_lossy(&result.stderr));
+            result.status.code().unwrap_or(-1)
+        }
+        Err(e) => {
+            host.output.push_str(&format!("Failed to run {}: {}\n", program, e));
+            -1
+        }
+    };
+    run.exit_code = Some(code);
+    host.commands.push(run);
+    code
+}
+
+// read_file(path_ptr, path_len, buf_ptr, buf_cap) -> bytes copied, or -1 when
+// the path is outside the declared read paths or unreadable
+fn host_read_file(mut caller: Caller<'_, HostState>, path_ptr: i32, path_len: i32, buf_ptr: i32, buf_cap: i32) -> i32 {
+    let Some(path) = read_guest(&caller, path_ptr, path_len) else { return -1 };
+    let Ok(path) = Path::new(&*String::from_utf8_lossy(&path)).canonicalize() else { return -1 };
+    let allowed = caller
+        .data()
+        .read_paths
+        .iter()
+        .filter_map(|p| p.canonicalize().ok())
+        .any(|root| path.starts_with(root));
+    if !allowed {
+        caller.data_mut().output.push_str(&format!("Refused to read undeclared path '{}'\n", path.display()));
+        return -1;
+    }
+
+    let Ok(mut content) = std::fs::read(&path) else { return -1 };
+    content.truncate((buf_cap.max(0) as usize).min(MAX_READ_BYTES));
+    let Some(memory) = guest_memory(&caller) else { return -1 };
+    match memory.write(&mut caller, buf_ptr as usize, &content) {
+        Ok(()) => content.len() as i32,
+        Err(_) => -1,
+    }
+}
+
+fn guest_memory(caller: &Caller<'_, HostState>) -> Option<wasmi::Memory> {
+    caller.get_export("memory").and_then(Extern::into_memory)
+}
+
+fn read_guest(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+    if ptr < 0 || len < 0 || len as usize > MAX_READ_BYTES {
+        return None;
+    }
+    let mut buffer = vec![0; len as usize];
+    guest_memory(caller)?.read(caller, ptr as usize, &mut buffer).ok()?;
+    Some(buffer)
+}
@@ -0,0 +1,213 @@
+use std::sync::Mutex;
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{header, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tauri::{AppHandle, Manager};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::{auth, automation, freeze, instance, lockout, pairing, AppState, Claims};
+
+// What a caller on localhost must present to use a route
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    // Liveness and the kill switch: the session token but no approval token
+    Public,
+    // Reads machine state: a token with the diagnostics:read scope
+    Diagnostics,
+    // Changes machine state: a token with the automation:execute scope
+    Automation,
+    // Sees what's on the screen: a token with the screen:capture scope
+    Screen,
+    // Another helper process of the same user, proven by the token in the
+    // instance file (see instance::bind)
+    Instance,
+}
+
+impl Policy {
+    // The token scope the route needs (see auth::has_scope)
+    fn scope(self) -> Option<&'static str> {
+        match self {
+            Policy::Diagnostics => Some(auth::SCOPE_DIAGNOSTICS),
+            Policy::Automation => Some(auth::SCOPE_EXECUTE),
+            Policy::Screen => Some(auth::SCOPE_SCREEN),
+            Policy::Public | Policy::Instance => None,
+        }
+    }
+}
+
+// Every route the status server exposes. A route missing here is refused,
+// so a new endpoint can't ship without deciding who may call it.
+pub fn for_route(method: &Method, path: &str) -> Option<Policy> {
+    let policy = match (method.as_str(), path) {
+        ("GET", "/status") | ("GET", "/metrics") => Policy::Public,
+        ("GET", "/actions") => Policy::Public,
+        ("POST", "/automation/abort-all") | ("POST", "/automation/freeze") => Policy::Public,
+        ("POST", "/shutdown") | ("POST", "/instance/activate") => Policy::Instance,
+        ("GET", "/timeline")
+        | ("GET", "/updates")
+        | ("GET", "/scheduler")
+        | ("GET", "/simulation")
+        | ("GET", "/rollback-points")
+        | ("GET", "/executions/{id}")
+        | ("GET", "/audit/export")
+        | ("GET", "/capabilities/privileges")
+        | ("GET", "/benchmark")
+        | ("GET", "/transcripts/{chat_id}")
+        | ("GET", "/guided/{id}")
+        | ("POST", "/guided/{id}/verify")
+        | ("POST", "/probes/run")
+        | ("POST", "/diagnostics/query")
+        | ("POST", "/health/logs/query")
+        | ("GET", "/processes")
+        | ("POST", "/net/speedtest") => Policy::Diagnostics,
+        ("GET", p) if p.starts_with("/health/") || p.starts_with("/net/") => Policy::Diagnostics,
+        ("POST", "/screenshot") | ("POST", "/screenrecord") | ("GET", "/displays") => Policy::Screen,
+        // Saturates disk and CPU for ~15 seconds
+        ("POST", "/benchmark") => Policy::Automation,
+        ("POST", "/automation/execute") | ("POST", "/automation/execute-batch") | ("POST", "/guided") => {
+            Policy::Automation
+        }
+        _ => return None,
+    };
+    Some(policy)
+}
+
+// Route layer for the status server. Apart from another helper process, every
+// caller needs the local session token (see pairing::SESSION_HEADER); only
+// /status answers without it until the helper is paired, so the web app can
+// find it and prompt for the pairing code. Every approval token is consumed
+// here, so each request needs a fresh one. Handlers still apply their own finer checks (the
+// action a token was minted for, the chat a transcript belongs to).
+pub async fn enforce(
+    State(app): State<AppHandle>,
+    matched: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = matched.as_ref().map(|m| m.as_str()).unwrap_or_default();
+    let Some(policy) = for_route(request.method(), path) else {
+        log::error!("Refused {} {}: route has no auth policy", request.method(), path);
+        return refuse(StatusCode::FORBIDDEN, "Route has no auth policy");
+    };
+    match policy {
+        Policy::Instance if instance::valid_token(request.headers()) => return next.run(request).await,
+        Policy::Instance => return refuse(StatusCode::FORBIDDEN, "Not a helper instance"),
+        Policy::Public if path == "/status" && !pairing::status().paired => return next.run(request).await,
+        _ if !pairing::valid_session(request.headers()) => {
+            return refuse(StatusCode::UNAUTHORIZED, "Missing or invalid session token")
+        }
+        Policy::Public => return next.run(request).await,
+        Policy::Diagnostics | Policy::Automation | Policy::Screen => {}
+    }
+
+    let Some(token) = automation::bearer_token(request.headers()) else {
+        return refuse(StatusCode::UNAUTHORIZED, "Missing bearer token");
+    };
+    // While frozen, or when the source keeps presenting invalid tokens,
+    // reads still work but nothing can be started
+    let source = lockout::source(request.headers());
+    if policy == Policy::Automation {
+        if let Err(e) = freeze::check(&app) {
+            return refuse(StatusCode::LOCKED, &e);
+        }
+        if let Err(retry_after) = lockout::check(&source) {
+            let body = Json(serde_json::json!({
+                "error": lockout::locked_message(retry_after),
+                "retry_after": retry_after,
+            }));
+            return (StatusCode::LOCKED, [(header::RETRY_AFTER, retry_after.to_string())], body).into_response();
+        }
+    }
+    let client = app.state::<Mutex<AppState>>().lock().unwrap().client.clone();
+    let claims = match auth::validate_once(token, &client).await {
+        Ok(claims) => claims,
+        // Only routes that start something count towards a lockout
+        Err(rejection) => {
+            if rejection.invalid && policy == Policy::Automation {
+                lockout::record_failure(&app, &source, &rejection.message);
+            }
+            return refuse(StatusCode::UNAUTHORIZED, &rejection.message);
+        }
+    };
+    if let Err(e) = permits(policy, &claims) {
+        return refuse(StatusCode::FORBIDDEN, &e);
+    }
+    next.run(request).await
+}
+
+// Whether a validated token's scope covers the route. Scope-only tokens (a
+// health scan, a screenshot) carry no action or approval, and need none here.
+pub fn permits(policy: Policy, claims: &Claims) -> Result<(), String> {
+    match policy.scope().filter(|scope| !auth::has_scope(claims, scope)) {
+        Some(scope) => Err(format!("Token scope '{}' lacks {}", claims.scope, scope)),
+        None => Ok(()),
+    }
+}
+
+// Web origins allowed to call the status server from a browser: the
+// comma-separated OHFIXIT_ALLOWED_ORIGINS, or the OhFixIt web app's origin
+pub fn allowed_origins() -> Vec<String> {
+    let configured = std::env::var("OHFIXIT_ALLOWED_ORIGINS").unwrap_or_else(|_| {
+        std::env::var("OHFIXIT_SERVER_URL").unwrap_or_else(|_| "http://localhost:3000".to_string())
+    });
+    configured
+        .split(',')
+        .map(|origin| origin.trim().trim_end_matches('/').to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect()
+}
+
+pub fn cors() -> CorsLayer {
+    let origins: Vec<HeaderValue> = allowed_origins().iter().filter_map(|o| o.parse().ok()).collect();
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, HeaderName::from_static(pairing::SESSION_HEADER)])
+}
+
+// CORS only stops a page reading responses; a "simple" cross-site POST still
+// reaches the handler. Requests from a browser on any other site are refused
+// here. Requests without an Origin (the web app's server, other helper
+// processes) aren't from a browser page and pass.
+pub async fn check_origin(request: Request, next: Next) -> Response {
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        let origin = origin.to_str().unwrap_or_default();
+        if !allowed_origins().iter().any(|allowed| allowed == origin) {
+            log::info!("Refused {} {} from origin {}", request.method(), request.uri().path(), origin);
+            return refuse(StatusCode::FORBIDDEN, "Origin not allowed");
+        }
+    }
+    next.run(request).await
+}
+
+fn refuse(status: StatusCode, error: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": error }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::tests::scope_only;
+
+    #[test]
+    fn screen_token_reaches_the_screen_routes() {
+        let claims = scope_only(auth::SCOPE_SCREEN);
+        for (method, path) in [(Method::POST, "/screenshot"), (Method::GET, "/displays"), (Method::POST, "/screenrecord")] {
+            let policy = for_route(&method, path).unwrap();
+            assert_eq!(policy, Policy::Screen);
+            assert!(permits(policy, &claims).is_ok(), "{} {}", method, path);
+        }
+        assert!(permits(Policy::Diagnostics, &claims).is_err());
+        assert!(permits(Policy::Automation, &claims).is_err());
+    }
+
+    #[test]
+    fn diagnostics_token_reaches_only_the_diagnostics_routes() {
+        let claims = scope_only(auth::SCOPE_DIAGNOSTICS);
+        assert!(permits(for_route(&Method::GET, "/health/disks").unwrap(), &claims).is_ok());
+        assert!(permits(Policy::Screen, &claims).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+use std::process::{Child, Command, Stdio};
+
+use chrono::{DateTime, Utc};
+
+use crate::cmd::read_output;
+use crate::{audit, scheduler};
+
+// Below this (on battery) the machine may sleep before an action finishes
+const CRITICAL_BATTERY_PERCENT: u8 = 5;
+
+// Whether the user's session is currently locked
+pub fn screen_locked() -> bool {
+    match std::env::consts::OS {
+        // The root IORegistry entry carries the console session dictionary
+        "macos" => read_output("/usr/sbin/ioreg", &["-n", "Root", "-d1"])
+            .is_some_and(|out| out.contains("\"CGSSessionScreenIsLocked\"=Yes")),
+        // LogonUI only runs while the lock (or login) screen is up
+        "windows" => read_output("tasklist", &["/FI", "IMAGENAME eq LogonUI.exe", "/NH"])
+            .is_some_and(|out| out.contains("LogonUI.exe")),
+        _ => {
+            let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+            read_output("loginctl", &["show-session", &session, "-p", "LockedHint"])
+                .is_some_and(|out| out.trim() == "LockedHint=yes")
+        }
+    }
+}
+
+// A discharging battery this low means the OS is about to force sleep
+pub fn sleep_imminent() -> bool {
+    let power = scheduler::power_state();
+    power.on_battery && power.battery_percent.is_some_and(|p| p < CRITICAL_BATTERY_PERCENT)
+}
+
+// Keeps the machine from idle-sleeping until dropped. Acquisition and release
+// are recorded in the audit trail.
+pub struct KeepAwake {
+    inhibitor: Inhibitor,
+    reason: String,
+    acquired_at: DateTime<Utc>,
+}
+
+enum Inhibitor {
+    None,
+    // systemd-inhibit holding a logind sleep lock
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    Child(Child),
+    #[cfg(target_os = "macos")]
+    IoKit(iokit::AssertionId),
+    // SetThreadExecutionState is per thread, so a dedicated thread holds it
+    // until the sender is dropped
+    #[cfg(target_os = "windows")]
+    Thread(std::sync::mpsc::Sender<()>, std::thread::JoinHandle<()>),
+}
+
+impl Inhibitor {
+    fn mechanism(&self) -> &'static str {
+        match self {
+            Inhibitor::None => "none",
+            #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+            Inhibitor::Child(_) => "systemd-inhibit",
+            #[cfg(target_os = "macos")]
+            Inhibitor::IoKit(_) => "iokit",
+            #[cfg(target_os = "windows")]
+            Inhibitor::Thread(..) => "SetThreadExecutionState",
+        }
+    }
+}
+
+impl KeepAwake {
+    pub fn acquire(reason: &str) -> Self {
+        let inhibitor = match inhibit(reason) {
+            Ok(inhibitor) => inhibitor,
+            Err(e) => {
+                log::error!("Failed to inhibit sleep: {}", e);
+                Inhibitor::None
+            }
+        };
+
+        log::info!("Holding sleep inhibitor ({}): {}", inhibitor.mechanism(), reason);
+        audit::record("power_assertion_acquired", serde_json::json!({
+            "reason": reason,
+            "mechanism": inhibitor.mechanism(),
+        }));
+        Self {
+            inhibitor,
+            reason: reason.to_string(),
+            acquired_at: Utc::now(),
+        }
+    }
+}
+
+impl Drop for KeepAwake {
+    fn drop(&mut self) {
+        let mechanism = self.inhibitor.mechanism();
+        match std::mem::replace(&mut self.inhibitor, Inhibitor::None) {
+            Inhibitor::None => {}
+            #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+            Inhibitor::Child(mut child) => {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            #[cfg(target_os = "macos")]
+            Inhibitor::IoKit(id) => iokit::release(id),
+            #[cfg(target_os = "windows")]
+            Inhibitor::Thread(sender, handle) => {
+                drop(sender);
+                let _ = handle.join();
+            }
+        }
+
+        log::info!("Released sleep inhibitor: {}", self.reason);
+        audit::record("power_assertion_released", serde_json::json!({
+            "reason": self.reason,
+            "mechanism": mechanism,
+            "held_ms": (Utc::now() - self.acquired_at).num_milliseconds(),
+        }));
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn inhibit(reason: &str) -> Result<Inhibitor, String> {
+    iokit::prevent_idle_sleep(reason).map(Inhibitor::IoKit)
+}
+
+#[cfg(target_os = "windows")]
+fn inhibit(_reason: &str) -> Result<Inhibitor, String> {
+    const ES_CONTINUOUS: u32 = 0x8000_0000;
+    const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+
+    let (sender, receiver) = std::sync::mpsc::channel::<()>();
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel::<bool>();
+    let handle = std::thread::spawn(move || {
+        // SAFETY: plain Win32 call with constant flags
+        let previous = unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+        let _ = ready_tx.send(previous != 0);
+        // Blocks until the KeepAwake drops its sender
+        let _ = receiver.recv();
+        unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+    });
+
+    if ready_rx.recv().unwrap_or(false) {
+        Ok(Inhibitor::Thread(sender, handle))
+    } else {
+        drop(sender);
+        let _ = handle.join();
+        Err("SetThreadExecutionState failed".to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn inhibit(reason: &str) -> Result<Inhibitor, String> {
+    Command::new("systemd-inhibit")
+        .args(["--what=sleep:idle", "--who=OhFixIt", &format!("--why={}", reason), "sleep", "infinity"])
+        .stdin(Stdio::null())
+        .spawn()
+        .map(Inhibitor::Child)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(target_os = "macos")]
+mod iokit {
+    use std::ffi::{c_char, c_void, CString};
+
+    pub type AssertionId = u32;
+    type CFStringRef = *const c_void;
+
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const K_IOPM_ASSERTION_LEVEL_ON: u32 = 255;
+    const K_IO_RETURN_SUCCESS: i32 = 0;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(alloc: *const c_void, c_str: *const c_char, encoding: u32) -> CFStringRef;
+        fn CFRelease(cf: *const c_void);
+    }
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOPMAssertionCreateWithName(
+            assertion_type: CFStringRef,
+            level: u32,
+            name: CFStringRef,
+            id: *mut AssertionId,
+        ) -> i32;
+        fn IOPMAssertionRelease(id: AssertionId) -> i32;
+    }
+
+    fn cf_string(value: &str) -> Option<CFStringRef> {
+        let c_string = CString::new(value).ok()?;
+        // SAFETY: c_string is a valid NUL-terminated buffer for the duration of the call
+        let cf = unsafe { CFStringCreateWithCString(std::ptr::null(), c_string.as_ptr(), K_CF_STRING_ENCODING_UTF8) };
+        (!cf.is_null()).then_some(cf)
+    }
+
+    pub fn prevent_idle_sleep(reason: &str) -> Result<AssertionId, String> {
+        let assertion_type = cf_string("PreventUserIdleSystemSleep").ok_or("CFString allocation failed")?;
+        let Some(name) = cf_string(&format!("OhFixIt: {}", reason)) else {
+            unsafe { CFRelease(assertion_type) };
+            return Err("CFString allocation failed".to_string());
+        };
+
+        let mut id: AssertionId = 0;
+        // SAFETY: both CFStrings are live and id points to a local
+        let result = unsafe { IOPMAssertionCreateWithName(assertion_type, K_IOPM_ASSERTION_LEVEL_ON, name, &mut id) };
+        unsafe {
+            CFRelease(assertion_type);
+            CFRelease(name);
+        }
+
+        if result == K_IO_RETURN_SUCCESS {
+            Ok(id)
+        } else {
+            Err(format!("IOPMAssertionCreateWithName returned {:#x}", result))
+        }
+    }
+
+    pub fn release(id: AssertionId) {
+        // SAFETY: id came from IOPMAssertionCreateWithName and is released once
+        let result = unsafe { IOPMAssertionRelease(id) };
+        if result != K_IO_RETURN_SUCCESS {
+            log::error!("IOPMAssertionRelease returned {:#x}", result);
+        }
+    }
+}
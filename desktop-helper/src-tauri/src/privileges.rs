@@ -0,0 +1,134 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::cmd::{read_output, read_trimmed};
+use crate::{startup, AppState};
+
+// What the helper could do right now if asked, so the server can leave out
+// actions and features that would fail halfway (no admin rights, a missing
+// macOS permission) instead of offering them
+#[derive(Debug, Serialize, Clone)]
+pub struct Privileges {
+    pub os: String,
+    // The helper itself runs as root / elevated
+    pub elevated: bool,
+    // Member of admin, sudo or wheel (Administrators on Windows), so an
+    // elevation prompt can be approved with the user's own password
+    pub admin_user: bool,
+    // sudo runs without asking for a password; None on Windows
+    pub sudo_cached: Option<bool>,
+    // osascript, pkexec or UAC is there to show the prompt
+    pub prompt_available: bool,
+    pub can_elevate: bool,
+    // macOS privacy permissions; None elsewhere or when it can't be told
+    pub full_disk_access: Option<bool>,
+    pub screen_recording: Option<bool>,
+    // Actions for this OS that need administrator rights it can't get
+    pub unavailable_actions: Vec<String>,
+}
+
+// GET /capabilities/privileges
+pub async fn privileges_handler(State(app): State<AppHandle>) -> Result<Json<Privileges>, (StatusCode, String)> {
+    startup::catalog_ready().await;
+    let mut privileges = tauri::async_runtime::spawn_blocking(check)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !privileges.can_elevate {
+        let state = app.state::<Mutex<AppState>>();
+        let state = state.lock().unwrap();
+        privileges.unavailable_actions = state
+            .actions
+            .values()
+            .filter(|a| a.os == std::env::consts::OS && a.elevated)
+            .map(|a| a.id.clone())
+            .collect();
+        privileges.unavailable_actions.sort();
+    }
+    Ok(Json(privileges))
+}
+
+fn check() -> Privileges {
+    let os = std::env::consts::OS;
+    let (elevated, admin_user) = match os {
+        // whoami lists Administrators (deny-only when not elevated) and the
+        // mandatory level, "High" when running elevated
+        "windows" => {
+            let groups = read_trimmed("whoami", &["/groups"]).unwrap_or_default();
+            (
+                groups.contains("S-1-16-12288") || groups.contains("S-1-16-16384"),
+                groups.contains("S-1-5-32-544"),
+            )
+        }
+        _ => {
+            let admin_groups: &[&str] = if os == "macos" { &["admin"] } else { &["sudo", "wheel", "admin"] };
+            let groups = read_trimmed("/usr/bin/id", &["-Gn"]).unwrap_or_default();
+            (
+                read_trimmed("/usr/bin/id", &["-u"]).as_deref() == Some("0"),
+                groups.split_whitespace().any(|group| admin_groups.contains(&group)),
+            )
+        }
+    };
+    // -n fails instead of prompting when a password would be needed
+    let sudo_cached = (os != "windows").then(|| read_output("/usr/bin/sudo", &["-n", "true"]).is_some());
+    let prompt_available = match os {
+        "macos" => Path::new("/usr/bin/osascript").exists(),
+        "windows" => true,
+        _ => ["/usr/bin/pkexec", "/bin/pkexec"].iter().any(|path| Path::new(path).exists()),
+    };
+    let (full_disk_access, screen_recording) = if os == "macos" {
+        (full_disk_access(), screen_recording())
+    } else {
+        (None, None)
+    };
+
+    Privileges {
+        os: os.to_string(),
+        can_elevate: elevated || (prompt_available && admin_user) || sudo_cached == Some(true),
+        elevated,
+        admin_user,
+        sudo_cached,
+        prompt_available,
+        full_disk_access,
+        screen_recording,
+        unavailable_actions: Vec::new(),
+    }
+}
+
+// The user's TCC database can only be opened with Full Disk Access
+fn full_disk_access() -> Option<bool> {
+    let tcc = dirs::home_dir()?.join("Library/Application Support/com.apple.TCC/TCC.db");
+    match std::fs::File::open(tcc) {
+        Ok(_) => Some(true),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => Some(false),
+        Err(_) => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn screen_recording() -> Option<bool> {
+    Some(screen_capture::preflight())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn screen_recording() -> Option<bool> {
+    None
+}
+
+// CGPreflightScreenCaptureAccess (macOS 10.15+) answers without prompting
+#[cfg(target_os = "macos")]
+mod screen_capture {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+    }
+
+    pub fn preflight() -> bool {
+        unsafe { CGPreflightScreenCaptureAccess() }
+    }
+}
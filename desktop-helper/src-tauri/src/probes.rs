@@ -0,0 +1,153 @@
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::{read_output, read_trimmed};
+use crate::{audit, transcript};
+
+const PORT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Read-only checks shared by action preflight, post-condition verification and
+// guided steps. Manifests reference them by `kind`, e.g.
+// {"kind": "process_running", "name": "Finder"}
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Probe {
+    PortOpen { host: String, port: u16 },
+    DnsResolves { host: String },
+    ProcessRunning { name: String },
+    ProcessStopped { name: String },
+    FileExists { path: String },
+    FileAbsent { path: String },
+    DefaultsValueEquals { domain: String, key: String, expected: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProbeResult {
+    pub probe: Probe,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProbeRequest {
+    probes: Vec<Probe>,
+    // Attributes the results to a chat transcript
+    #[serde(default, alias = "chatId")]
+    chat_id: Option<String>,
+}
+
+// POST /probes/run, lets server-side playbooks verify steps with the same checks
+pub async fn run_probes_handler(Json(request): Json<ProbeRequest>) -> Json<serde_json::Value> {
+    let probes = request.probes;
+    let results = tauri::async_runtime::spawn_blocking(move || run_all(&probes))
+        .await
+        .unwrap_or_default();
+    transcript::record_diagnostics(request.chat_id.as_deref(), "probes", &results);
+    audit::record("probes_run", serde_json::json!({
+        "chat_id": request.chat_id,
+        "probes": results.len(),
+        "failed": results.iter().filter(|r| !r.passed).count(),
+    }));
+
+    Json(serde_json::json!({
+        "passed": results.iter().all(|r| r.passed),
+        "results": results,
+    }))
+}
+
+impl Probe {
+    pub fn run(&self) -> ProbeResult {
+        let (passed, detail) = match self {
+            Probe::PortOpen { host, port } => {
+                let open = port_open(host, *port);
+                (open, format!("{}:{} is {}", host, port, if open { "open" } else { "closed" }))
+            }
+            Probe::DnsResolves { host } => match (host.as_str(), 0).to_socket_addrs() {
+                Ok(mut addrs) => match addrs.next() {
+                    Some(addr) => (true, format!("{} resolves to {}", host, addr.ip())),
+                    None => (false, format!("{} resolved to no addresses", host)),
+                },
+                Err(e) => (false, format!("{} does not resolve: {}", host, e)),
+            },
+            Probe::ProcessRunning { name } => {
+                let running = process_running(name);
+                (running, format!("{} is {}", name, if running { "running" } else { "not running" }))
+            }
+            Probe::ProcessStopped { name } => {
+                let running = process_running(name);
+                (!running, format!("{} is {}", name, if running { "still running" } else { "not running" }))
+            }
+            Probe::FileExists { path } => {
+                let exists = expand_home(path).exists();
+                (exists, format!("{} {}", path, if exists { "exists" } else { "does not exist" }))
+            }
+            Probe::FileAbsent { path } => {
+                let exists = expand_home(path).exists();
+                (!exists, format!("{} {}", path, if exists { "still exists" } else { "is gone" }))
+            }
+            Probe::DefaultsValueEquals { domain, key, expected } => {
+                if std::env::consts::OS != "macos" {
+                    (false, "defaults is only available on macOS".to_string())
+                } else {
+                    match read_trimmed("/usr/bin/defaults", &["read", domain, key]) {
+                        Some(value) => (
+                            value == *expected,
+                            format!("{} {} = {} (expected {})", domain, key, value, expected),
+                        ),
+                        None => (false, format!("{} {} is not set", domain, key)),
+                    }
+                }
+            }
+        };
+
+        ProbeResult {
+            probe: self.clone(),
+            passed,
+            detail,
+        }
+    }
+}
+
+pub fn run_all(probes: &[Probe]) -> Vec<ProbeResult> {
+    probes.iter().map(Probe::run).collect()
+}
+
+// Details of every failed probe, or None when all passed
+pub fn describe_failures(results: &[ProbeResult]) -> Option<String> {
+    let failures: Vec<&str> = results
+        .iter()
+        .filter(|r| !r.passed)
+        .map(|r| r.detail.as_str())
+        .collect();
+    (!failures.is_empty()).then(|| failures.join("; "))
+}
+
+fn port_open(host: &str, port: u16) -> bool {
+    let Ok(addrs) = (host, port).to_socket_addrs() else { return false };
+    addrs
+        .into_iter()
+        .any(|addr| TcpStream::connect_timeout(&addr, PORT_TIMEOUT).is_ok())
+}
+
+fn process_running(name: &str) -> bool {
+    match std::env::consts::OS {
+        "windows" => {
+            let filter = format!("IMAGENAME eq {}", name);
+            read_output("tasklist", &["/FI", &filter, "/NH"])
+                .is_some_and(|out| out.to_lowercase().contains(&name.to_lowercase()))
+        }
+        // pgrep exits non-zero when nothing matches
+        _ => read_output("/usr/bin/pgrep", &["-x", name]).is_some(),
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(path),
+    }
+}
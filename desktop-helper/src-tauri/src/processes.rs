@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind, Users, MINIMUM_CPU_UPDATE_INTERVAL};
+use tauri::AppHandle;
+use tokio::sync::Mutex;
+
+use crate::{audit, consent};
+
+// GET /processes?sort=cpu|mem&limit=&name=: what's running and what it costs,
+// for "what's eating my CPU?". Which apps someone runs is private, so the
+// user agrees in a dialog on this machine first; the answer holds for
+// GRANT_TTL so follow-up questions don't prompt again.
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 200;
+const GRANT_TTL: Duration = Duration::from_secs(30 * 60);
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+// When the user last allowed the list; held while the dialog is up, so
+// concurrent requests share one prompt
+static GRANTED: Mutex<Option<Instant>> = Mutex::const_new(None);
+
+#[derive(Debug, Deserialize)]
+pub struct ProcessQuery {
+    sort: Option<String>,
+    limit: Option<usize>,
+    name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    // 100 is one core fully busy, so a multi-threaded process can exceed it
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub user: Option<String>,
+    pub started_at: Option<String>,
+    #[serde(skip)]
+    pub exe: Option<PathBuf>,
+    // Read and written during the sample
+    #[serde(skip)]
+    pub disk_bytes: u64,
+}
+
+// GET /processes
+pub async fn processes_handler(
+    State(app): State<AppHandle>,
+    Query(query): Query<ProcessQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let sort = query.sort.as_deref().unwrap_or("cpu");
+    if sort != "cpu" && sort != "mem" {
+        return Err((StatusCode::BAD_REQUEST, format!("Unknown sort '{}': use cpu or mem", sort)));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    allowed(&app).await?;
+
+    let mut processes = tauri::async_runtime::spawn_blocking(|| sample(SAMPLE_INTERVAL))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Process list failed: {}", e)))?;
+    if let Some(name) = query.name.as_deref().map(str::to_lowercase).filter(|name| !name.is_empty()) {
+        processes.retain(|process| process.name.to_lowercase().contains(&name));
+    }
+    let total = processes.len();
+    match sort {
+        "mem" => processes.sort_by_key(|process| std::cmp::Reverse(process.rss_bytes)),
+        _ => processes.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent)),
+    }
+    processes.truncate(limit);
+    Ok(Json(serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "sort": sort,
+        "total": total,
+        "cpu_count": std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        "processes": processes,
+    })))
+}
+
+// Ok once the user has allowed seeing their processes (see GRANT_TTL)
+pub async fn allowed(app: &AppHandle) -> Result<(), (StatusCode, String)> {
+    let mut granted = GRANTED.lock().await;
+    if granted.is_some_and(|at| at.elapsed() < GRANT_TTL) {
+        return Ok(());
+    }
+    let message = format!(
+        "OhFixIt wants to see which programs are running on this computer and how much CPU and memory each uses.\n\nThis stays allowed for {} minutes.",
+        GRANT_TTL.as_secs() / 60
+    );
+    let answer = consent::ask(app, "Allow OhFixIt to list running programs?".to_string(), message, "Allow", "Don't allow").await;
+    audit::record(
+        if answer == Some(true) { "process_list_granted" } else { "process_list_declined" },
+        serde_json::json!({ "timed_out": answer.is_none() }),
+    );
+    if answer != Some(true) {
+        return Err((StatusCode::FORBIDDEN, "Listing processes was not allowed on this computer".to_string()));
+    }
+    *granted = Some(Instant::now());
+    Ok(())
+}
+
+// Every process, with CPU use and disk I/O measured over `interval`
+pub fn sample(interval: Duration) -> Vec<ProcessInfo> {
+    // Threads stay out: on Linux they'd be listed as processes of their own
+    let refresh = ProcessRefreshKind::nothing()
+        .with_cpu()
+        .with_memory()
+        .with_disk_usage()
+        .with_user(UpdateKind::OnlyIfNotSet)
+        .with_exe(UpdateKind::OnlyIfNotSet)
+        .without_tasks();
+    let mut system = System::new();
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+    std::thread::sleep(interval.max(MINIMUM_CPU_UPDATE_INTERVAL));
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+
+    let users = Users::new_with_refreshed_list();
+    system
+        .processes()
+        .values()
+        .map(|process| ProcessInfo {
+            pid: process.pid().as_u32(),
+            name: process.name().to_string_lossy().into_owned(),
+            cpu_percent: (process.cpu_usage() as f64 * 10.0).round() / 10.0,
+            rss_bytes: process.memory(),
+            user: process.user_id().and_then(|uid| users.get_user_by_id(uid)).map(|user| user.name().to_string()),
+            started_at: DateTime::from_timestamp(process.start_time() as i64, 0).map(|at| at.to_rfc3339()),
+            exe: process.exe().map(PathBuf::from),
+            disk_bytes: process.disk_usage().read_bytes + process.disk_usage().written_bytes,
+        })
+        .collect()
+}
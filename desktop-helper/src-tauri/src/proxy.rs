@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+
+use axum::Json;
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::cmd::{read_output, read_trimmed};
+use crate::exec_context::system32;
+
+// GET /net/proxy: every proxy this machine's traffic may be sent through, so
+// a stray one left behind by a VPN client, a dev tool or adware (the usual
+// cause of "every site fails but ping works") can be spotted. macOS reads
+// the effective settings (scutil) and each network service's own
+// (networksetup); Windows reads the user's WinINet settings and WinHTTP;
+// Linux reads GNOME's settings. Proxy environment variables the helper was
+// started with count everywhere.
+const ENV_VARS: &[&str] = &["http_proxy", "https_proxy", "all_proxy", "ftp_proxy"];
+const PROXY_KINDS: &[(&str, &str)] = &[("HTTP", "http"), ("HTTPS", "https"), ("SOCKS", "socks"), ("FTP", "ftp")];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Proxy {
+    // Where it's set: "system", "service:Wi-Fi", "wininet", "winhttp",
+    // "gnome" or "environment"
+    scope: String,
+    // "http", "https", "socks", "ftp", "pac" or "auto_discovery"
+    kind: &'static str,
+    server: Option<String>,
+    port: Option<u16>,
+    // The PAC file, for kind "pac"
+    url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct ProxySettings {
+    proxies: Vec<Proxy>,
+    // Hosts that bypass the proxy
+    bypass: Vec<String>,
+    // "local_proxy" (a proxy on this machine: a dev tool, a VPN client, or
+    // adware), "pac_file" (a PAC script on disk rather than a server) and
+    // "environment_only" (set for command-line tools but not the system)
+    warnings: Vec<&'static str>,
+    errors: Vec<String>,
+}
+
+// GET /net/proxy
+pub async fn proxy_handler() -> Json<serde_json::Value> {
+    let settings = tauri::async_runtime::spawn_blocking(read).await.unwrap_or_else(|e| ProxySettings {
+        errors: vec![format!("Proxy check failed: {}", e)],
+        ..Default::default()
+    });
+    Json(serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "proxy": settings,
+    }))
+}
+
+fn read() -> ProxySettings {
+    let mut settings = ProxySettings::default();
+    match std::env::consts::OS {
+        "macos" => macos(&mut settings),
+        "windows" => windows(&mut settings),
+        _ => gnome(&mut settings),
+    }
+    environment(&mut settings);
+    assess(&mut settings);
+    settings
+}
+
+fn proxy(scope: &str, kind: &'static str, server: Option<String>, port: Option<u16>) -> Proxy {
+    Proxy { scope: scope.to_string(), kind, server, port, url: None }
+}
+
+fn pac(scope: &str, url: String) -> Proxy {
+    Proxy { scope: scope.to_string(), kind: "pac", server: None, port: None, url: Some(url) }
+}
+
+fn assess(settings: &mut ProxySettings) {
+    let local = settings.proxies.iter().any(|proxy| {
+        proxy.server.as_deref().is_some_and(|server| {
+            let server = server.trim_start_matches("http://").trim_start_matches("https://");
+            server == "localhost" || server.starts_with("127.") || server == "::1" || server == "[::1]"
+        })
+    });
+    if local {
+        settings.warnings.push("local_proxy");
+    }
+    if settings.proxies.iter().any(|proxy| proxy.url.as_deref().is_some_and(|url| url.starts_with("file:"))) {
+        settings.warnings.push("pac_file");
+    }
+    let system = settings.proxies.iter().any(|proxy| proxy.scope != "environment");
+    if !system && !settings.proxies.is_empty() {
+        settings.warnings.push("environment_only");
+    }
+}
+
+// "host:port", "http://host:port/" or just "host"
+fn split_address(address: &str) -> (Option<String>, Option<u16>) {
+    let address = address.trim().trim_end_matches('/');
+    let without_scheme = address.split_once("://").map(|(_, rest)| rest).unwrap_or(address);
+    // Credentials stay out of the report
+    let host_port = without_scheme.rsplit_once('@').map(|(_, rest)| rest).unwrap_or(without_scheme);
+    match host_port.rsplit_once(':') {
+        Some((host, port)) if !host.ends_with(':') => match port.parse() {
+            Ok(port) => (Some(host.to_string()), Some(port)),
+            Err(_) => (Some(host_port.to_string()), None),
+        },
+        _ => (Some(host_port.to_string()).filter(|host| !host.is_empty()), None),
+    }
+}
+
+fn environment(settings: &mut ProxySettings) {
+    for name in ENV_VARS {
+        let value = std::env::var(name).or_else(|_| std::env::var(name.to_uppercase()));
+        let Ok(value) = value else { continue };
+        if value.trim().is_empty() {
+            continue;
+        }
+        let (server, port) = split_address(&value);
+        let kind = match *name {
+            "https_proxy" => "https",
+            "all_proxy" if value.starts_with("socks") => "socks",
+            "ftp_proxy" => "ftp",
+            _ => "http",
+        };
+        settings.proxies.push(proxy("environment", kind, server, port));
+    }
+    if let Ok(no_proxy) = std::env::var("no_proxy").or_else(|_| std::env::var("NO_PROXY")) {
+        settings.bypass.extend(no_proxy.split(',').map(str::trim).filter(|host| !host.is_empty()).map(str::to_string));
+    }
+}
+
+fn macos(settings: &mut ProxySettings) {
+    match read_output("/usr/sbin/scutil", &["--proxy"]) {
+        Some(output) => {
+            let (values, exceptions) = parse_scutil(&output);
+            settings.proxies.extend(scutil_proxies("system", &values));
+            settings.bypass.extend(exceptions);
+        }
+        None => settings.errors.push("scutil --proxy failed".to_string()),
+    }
+
+    // A service's own settings apply whenever that service is the active one
+    let Some(services) = read_output("/usr/sbin/networksetup", &["-listallnetworkservices"]) else { return };
+    // The first line explains the asterisk that marks disabled services
+    for service in services.lines().skip(1).filter(|service| !service.starts_with('*') && !service.is_empty()) {
+        let scope = format!("service:{}", service);
+        for (flag, kind) in [("-getwebproxy", "http"), ("-getsecurewebproxy", "https"), ("-getsocksfirewallproxy", "socks")] {
+            let Some(output) = read_output("/usr/sbin/networksetup", &[flag, service]) else { continue };
+            let values = fields(&output);
+            if values.get("enabled").map(String::as_str) == Some("Yes") {
+                let port = values.get("port").and_then(|port| port.parse().ok()).filter(|port| *port != 0);
+                settings.proxies.push(proxy(&scope, kind, values.get("server").cloned(), port));
+            }
+        }
+        if let Some(output) = read_output("/usr/sbin/networksetup", &["-getautoproxyurl", service]) {
+            let values = fields(&output);
+            if let (Some("Yes"), Some(url)) = (values.get("enabled").map(String::as_str), values.get("url")) {
+                settings.proxies.push(pac(&scope, url.clone()));
+            }
+        }
+    }
+}
+
+// "Key : value" lines, keys lowercased; a URL's colons stay in the value
+fn fields(output: &str) -> BTreeMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+        .collect()
+}
+
+// scutil's dictionary: top-level "Key : value" pairs, and the entries of
+// the ExceptionsList array. Per-interface settings (__SCOPED__, listed last)
+// come from networksetup instead.
+fn parse_scutil(output: &str) -> (BTreeMap<String, String>, Vec<String>) {
+    let mut values = BTreeMap::new();
+    let mut exceptions = Vec::new();
+    let mut in_exceptions = false;
+    for line in output.lines().map(str::trim) {
+        if line.starts_with("__SCOPED__") {
+            break;
+        }
+        if line.starts_with("ExceptionsList") {
+            in_exceptions = true;
+        } else if line == "}" {
+            in_exceptions = false;
+        } else if let Some((key, value)) = line.split_once(" : ") {
+            if in_exceptions {
+                exceptions.push(value.trim().to_string());
+            } else {
+                values.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+    (values, exceptions)
+}
+
+fn scutil_proxies(scope: &str, values: &BTreeMap<String, String>) -> Vec<Proxy> {
+    let enabled = |key: &str| values.get(key).map(String::as_str) == Some("1");
+    let mut proxies = Vec::new();
+    for (prefix, kind) in PROXY_KINDS {
+        if enabled(&format!("{}Enable", prefix)) {
+            let port = values.get(&format!("{}Port", prefix)).and_then(|port| port.parse().ok());
+            proxies.push(proxy(scope, kind, values.get(&format!("{}Proxy", prefix)).cloned(), port));
+        }
+    }
+    if enabled("ProxyAutoConfigEnable") {
+        if let Some(url) = values.get("ProxyAutoConfigURLString") {
+            proxies.push(pac(scope, url.clone()));
+        }
+    }
+    if enabled("ProxyAutoDiscoveryEnable") {
+        proxies.push(proxy(scope, "auto_discovery", None, None));
+    }
+    proxies
+}
+
+fn windows(settings: &mut ProxySettings) {
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    let script = "Get-ItemProperty 'HKCU:\\Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings' | \
+                  Select-Object ProxyEnable,ProxyServer,ProxyOverride,AutoConfigURL,AutoDetect | ConvertTo-Json";
+    let wininet = read_output(&powershell.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", script])
+        .and_then(|output| serde_json::from_str::<serde_json::Value>(&output).ok());
+    match wininet {
+        Some(values) => {
+            if values["ProxyEnable"].as_i64() == Some(1) {
+                if let Some(server) = values["ProxyServer"].as_str() {
+                    settings.proxies.extend(parse_proxy_server("wininet", server));
+                }
+            }
+            if let Some(url) = values["AutoConfigURL"].as_str().filter(|url| !url.is_empty()) {
+                settings.proxies.push(pac("wininet", url.to_string()));
+            }
+            if values["AutoDetect"].as_i64() == Some(1) {
+                settings.proxies.push(proxy("wininet", "auto_discovery", None, None));
+            }
+            if let Some(overrides) = values["ProxyOverride"].as_str() {
+                settings.bypass.extend(overrides.split(';').map(str::trim).filter(|host| !host.is_empty()).map(str::to_string));
+            }
+        }
+        None => settings.errors.push("Failed to read the Internet Settings registry key".to_string()),
+    }
+
+    // WinHTTP (services, Windows Update) has its own setting
+    let netsh = system32().join("netsh.exe");
+    if let Some(output) = read_output(&netsh.to_string_lossy(), &["winhttp", "show", "proxy"]) {
+        let server = output.lines().find_map(|line| line.trim().strip_prefix("Proxy Server(s)").and_then(|rest| rest.split_once(':')));
+        if let Some((_, server)) = server {
+            settings.proxies.extend(parse_proxy_server("winhttp", server.trim()));
+        }
+    }
+}
+
+// "host:port" for every protocol, or "http=host:port;https=host:port;socks=…"
+fn parse_proxy_server(scope: &str, server: &str) -> Vec<Proxy> {
+    if !server.contains('=') {
+        let (host, port) = split_address(server);
+        return vec![proxy(scope, "http", host, port)];
+    }
+    server
+        .split(';')
+        .filter_map(|entry| entry.split_once('='))
+        .filter_map(|(protocol, address)| {
+            let kind = PROXY_KINDS.iter().find(|(_, kind)| kind.eq_ignore_ascii_case(protocol.trim()))?.1;
+            let (host, port) = split_address(address);
+            Some(proxy(scope, kind, host, port))
+        })
+        .collect()
+}
+
+fn gnome(settings: &mut ProxySettings) {
+    let get = |schema: &str, key: &str| {
+        read_trimmed("gsettings", &["get", schema, key]).map(|value| value.trim_matches('\'').to_string())
+    };
+    let Some(mode) = get("org.gnome.system.proxy", "mode") else { return };
+    match mode.as_str() {
+        "manual" => {
+            for kind in ["http", "https", "socks", "ftp"] {
+                let schema = format!("org.gnome.system.proxy.{}", kind);
+                let Some(host) = get(&schema, "host").filter(|host| !host.is_empty()) else { continue };
+                let port = get(&schema, "port").and_then(|port| port.parse().ok()).filter(|port| *port != 0);
+                settings.proxies.push(proxy("gnome", kind, Some(host), port));
+            }
+            // ['localhost', '127.0.0.0/8']
+            if let Some(hosts) = get("org.gnome.system.proxy", "ignore-hosts") {
+                settings.bypass.extend(
+                    hosts
+                        .trim_matches(['[', ']'])
+                        .split(',')
+                        .map(|host| host.trim().trim_matches('\'').to_string())
+                        .filter(|host| !host.is_empty()),
+                );
+            }
+        }
+        "auto" => match get("org.gnome.system.proxy", "autoconfig-url").filter(|url| !url.is_empty()) {
+            Some(url) => settings.proxies.push(pac("gnome", url)),
+            None => settings.proxies.push(proxy("gnome", "auto_discovery", None, None)),
+        },
+        _ => {}
+    }
+}
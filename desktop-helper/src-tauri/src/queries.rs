@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+use crate::{redact, transcript};
+
+// Raw output returned alongside the parsed result is cut to this size
+const MAX_RAW_BYTES: usize = 16 * 1024;
+
+// The only questions the web app may ask the machine. Each maps to a fixed
+// read-only command line per OS and a parser; the only caller-supplied value
+// is a validated service name.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "query", rename_all = "snake_case")]
+pub enum Query {
+    Whoami,
+    OsVersion,
+    DnsConfig,
+    ProxyConfig,
+    DiskUsage,
+    Uptime,
+    ServiceStatus { name: String },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueryRequest {
+    queries: Vec<Query>,
+    // Attributes the results to a chat transcript
+    #[serde(default, alias = "chatId")]
+    chat_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueryResult {
+    query: Query,
+    // False when this OS has no mapping for the query
+    supported: bool,
+    data: Option<serde_json::Value>,
+    raw: Option<String>,
+    error: Option<String>,
+}
+
+// POST /diagnostics/query
+pub async fn query_handler(Json(request): Json<QueryRequest>) -> Json<serde_json::Value> {
+    let queries = request.queries;
+    let results = tauri::async_runtime::spawn_blocking(move || queries.into_iter().map(run).collect::<Vec<_>>())
+        .await
+        .unwrap_or_default();
+    transcript::record_diagnostics(request.chat_id.as_deref(), "queries", &results);
+    Json(serde_json::json!({ "results": results }))
+}
+
+pub fn run(query: Query) -> QueryResult {
+    let os = std::env::consts::OS;
+    let outcome = match (&query, os) {
+        (Query::ServiceStatus { name }, _) if !valid_service_name(name) => {
+            Some(Err(format!("Invalid service name '{}'", name)))
+        }
+        (Query::Whoami, "windows") => Some(command(&sys32("whoami.exe"), &[], parse_trimmed("user"))),
+        (Query::Whoami, _) => Some(command("/usr/bin/whoami", &[], parse_trimmed("user"))),
+        (Query::OsVersion, "macos") => Some(command("/usr/bin/sw_vers", &[], parse_key_values(':'))),
+        (Query::OsVersion, "windows") => Some(command(&sys32("cmd.exe"), &["/C", "ver"], parse_trimmed("version"))),
+        (Query::OsVersion, _) => Some(file("/etc/os-release", parse_os_release)),
+        (Query::DnsConfig, "macos") => Some(command("/usr/sbin/scutil", &["--dns"], parse_scutil_dns)),
+        (Query::DnsConfig, "windows") => Some(powershell(
+            "Get-DnsClientServerAddress -AddressFamily IPv4 | Select-Object InterfaceAlias,ServerAddresses | ConvertTo-Json",
+            parse_json,
+        )),
+        (Query::DnsConfig, _) => Some(file("/etc/resolv.conf", parse_resolv_conf)),
+        (Query::ProxyConfig, "macos") => Some(command("/usr/sbin/scutil", &["--proxy"], parse_key_values(':'))),
+        (Query::ProxyConfig, "windows") => Some(powershell(
+            "Get-ItemProperty 'HKCU:\\Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings' | \
+             Select-Object ProxyEnable,ProxyServer,ProxyOverride,AutoConfigURL | ConvertTo-Json",
+            parse_json,
+        )),
+        (Query::ProxyConfig, _) => None,
+        (Query::DiskUsage, "windows") => Some(powershell(
+            "Get-CimInstance Win32_LogicalDisk -Filter 'DriveType=3' | Select-Object DeviceID,Size,FreeSpace | ConvertTo-Json",
+            parse_json,
+        )),
+        (Query::DiskUsage, _) => Some(command("/bin/df", &["-kP"], parse_df)),
+        (Query::Uptime, "macos") => Some(command("/usr/sbin/sysctl", &["-n", "kern.boottime"], parse_boottime)),
+        (Query::Uptime, "windows") => Some(powershell(
+            "[int]((Get-Date) - (Get-CimInstance Win32_OperatingSystem).LastBootUpTime).TotalSeconds",
+            parse_number("uptime_secs"),
+        )),
+        (Query::Uptime, _) => Some(file("/proc/uptime", parse_proc_uptime)),
+        (Query::ServiceStatus { name }, "windows") => Some(powershell(
+            &format!("(Get-Service -Name '{}' -ErrorAction Stop).Status.ToString()", name),
+            parse_trimmed("status"),
+        )),
+        (Query::ServiceStatus { name }, "linux") => {
+            // is-active exits non-zero for inactive units, so its output can't go through read_output
+            let output = crate::cmd::diagnostic("/usr/bin/systemctl").args(["is-active", name]).output();
+            Some(match output {
+                Ok(output) => {
+                    let raw = String::from_utf8_lossy(&output.stdout).into_owned();
+                    parse_trimmed("status")(&raw).map(|data| (data, raw))
+                }
+                Err(e) => Err(format!("Failed to run systemctl: {}", e)),
+            })
+        }
+        (Query::ServiceStatus { .. }, _) => None,
+    };
+
+    match outcome {
+        None => QueryResult { query, supported: false, data: None, raw: None, error: None },
+        Some(Ok((data, raw))) => {
+            let (mut raw, _) = redact::redact(&raw);
+            if raw.len() > MAX_RAW_BYTES {
+                let cut = (0..=MAX_RAW_BYTES).rev().find(|i| raw.is_char_boundary(*i)).unwrap_or(0);
+                raw.truncate(cut);
+            }
+            QueryResult { query, supported: true, data: Some(data), raw: Some(raw), error: None }
+        }
+        Some(Err(e)) => QueryResult { query, supported: true, data: None, raw: None, error: Some(e) },
+    }
+}
+
+type Parsed = Result<(serde_json::Value, String), String>;
+type Parser = fn(&str) -> Result<serde_json::Value, String>;
+
+fn command(program: &str, args: &[&str], parse: impl Fn(&str) -> Result<serde_json::Value, String>) -> Parsed {
+    let raw = read_output(program, args).ok_or_else(|| format!("{} failed", program))?;
+    parse(&raw).map(|data| (data, raw))
+}
+
+fn powershell(script: &str, parse: impl Fn(&str) -> Result<serde_json::Value, String>) -> Parsed {
+    let program = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    command(&program.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", script], parse)
+}
+
+fn file(path: &str, parse: Parser) -> Parsed {
+    let raw = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    parse(&raw).map(|data| (data, raw))
+}
+
+fn sys32(program: &str) -> String {
+    system32().join(program).to_string_lossy().into_owned()
+}
+
+// Unit names like "NetworkManager", "systemd-resolved" or "getty@tty1"
+fn valid_service_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@'))
+}
+
+fn parse_trimmed(key: &'static str) -> impl Fn(&str) -> Result<serde_json::Value, String> {
+    move |raw| match raw.trim() {
+        "" => Err("Empty output".to_string()),
+        value => Ok(serde_json::json!({ key: value })),
+    }
+}
+
+fn parse_number(key: &'static str) -> impl Fn(&str) -> Result<serde_json::Value, String> {
+    move |raw| {
+        let value: u64 = raw.trim().parse().map_err(|_| format!("Expected a number, got '{}'", raw.trim()))?;
+        Ok(serde_json::json!({ key: value }))
+    }
+}
+
+// "ProductVersion:		14.5" / "HTTPEnable : 1"; nested scutil blocks are flattened
+fn parse_key_values(separator: char) -> impl Fn(&str) -> Result<serde_json::Value, String> {
+    move |raw| {
+        let values: BTreeMap<String, String> = raw
+            .lines()
+            .filter_map(|line| line.split_once(separator))
+            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+            .filter(|(k, v)| !k.is_empty() && !v.is_empty() && !v.ends_with('{'))
+            .collect();
+        Ok(serde_json::json!(values))
+    }
+}
+
+// NAME="Ubuntu" / VERSION_ID="24.04"
+fn parse_os_release(raw: &str) -> Result<serde_json::Value, String> {
+    let values: BTreeMap<&str, &str> = raw
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(k, v)| (k.trim(), v.trim().trim_matches('"')))
+        .collect();
+    Ok(serde_json::json!(values))
+}
+
+// "  nameserver[0] : 192.168.1.1" and "  search domain[0] : lan", across all resolvers
+fn parse_scutil_dns(raw: &str) -> Result<serde_json::Value, String> {
+    let mut nameservers: Vec<&str> = Vec::new();
+    let mut search_domains: Vec<&str> = Vec::new();
+    for (key, value) in raw.lines().filter_map(|l| l.split_once(" : ")) {
+        let list = match key.trim() {
+            k if k.starts_with("nameserver[") => &mut nameservers,
+            k if k.starts_with("search domain[") => &mut search_domains,
+            _ => continue,
+        };
+        if !list.contains(&value.trim()) {
+            list.push(value.trim());
+        }
+    }
+    Ok(serde_json::json!({ "nameservers": nameservers, "search_domains": search_domains }))
+}
+
+fn parse_resolv_conf(raw: &str) -> Result<serde_json::Value, String> {
+    let mut nameservers = Vec::new();
+    let mut search_domains = Vec::new();
+    for line in raw.lines().map(str::trim).filter(|l| !l.starts_with('#')) {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("nameserver") => nameservers.extend(words.next()),
+            Some("search") | Some("domain") => search_domains.extend(words),
+            _ => {}
+        }
+    }
+    Ok(serde_json::json!({ "nameservers": nameservers, "search_domains": search_domains }))
+}
+
+fn parse_json(raw: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(raw.trim()).map_err(|e| format!("Unexpected output: {}", e))
+}
+
+// POSIX df: "Filesystem 1024-blocks Used Available Capacity Mounted on"
+fn parse_df(raw: &str) -> Result<serde_json::Value, String> {
+    let volumes: Vec<serde_json::Value> = raw
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let kb = |i: usize| fields[i].parse::<u64>().ok();
+            Some(serde_json::json!({
+                "filesystem": fields[0],
+                "size_kb": kb(1)?,
+                "used_kb": kb(2)?,
+                "available_kb": kb(3)?,
+                "capacity": fields[4],
+                "mount": fields[5..].join(" "),
+            }))
+        })
+        .collect();
+    Ok(serde_json::json!({ "volumes": volumes }))
+}
+
+// "{ sec = 1718000000, usec = 0 } Mon Jun 10 ..."
+fn parse_boottime(raw: &str) -> Result<serde_json::Value, String> {
+    let boot: i64 = raw
+        .split_once("sec = ")
+        .and_then(|(_, rest)| rest.split(',').next())
+        .and_then(|s| s.trim().parse().ok())
+        .ok_or_else(|| format!("Unexpected boot time '{}'", raw.trim()))?;
+    Ok(serde_json::json!({ "uptime_secs": (chrono::Utc::now().timestamp() - boot).max(0) }))
+}
+
+// "12345.67 54321.00": seconds since boot, then idle time
+fn parse_proc_uptime(raw: &str) -> Result<serde_json::Value, String> {
+    let secs: f64 = raw
+        .split_whitespace()
+        .next()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| format!("Unexpected uptime '{}'", raw.trim()))?;
+    Ok(serde_json::json!({ "uptime_secs": secs as u64 }))
+}
+
+// Command output as it looks on non-English systems: parsers key on layout,
+// not on labels, or refuse rather than misread
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn df_with_german_header() {
+        let raw = "Dateisystem    1024-Blöcke   Benutzt Verfügbar Kapazität Eingehängt auf\n\
+                   /dev/disk3s1   488245288 401234567  61234567       87% /\n\
+                   /dev/disk4s1   976762584 100000000 876762584       11% /Volumes/Externe Platte\n";
+        let parsed = parse_df(raw).unwrap();
+        let volumes = parsed["volumes"].as_array().unwrap();
+        assert_eq!(volumes.len(), 2);
+        assert_eq!(volumes[0]["available_kb"], 61234567);
+        assert_eq!(volumes[0]["capacity"], "87%");
+        assert_eq!(volumes[1]["mount"], "/Volumes/Externe Platte");
+    }
+
+    #[test]
+    fn number_with_a_thousands_separator_is_refused() {
+        assert_eq!(parse_number("count")("1234\n").unwrap()["count"], 1234);
+        assert!(parse_number("count")("1 234").is_err());
+        assert!(parse_number("count")("1.234").is_err());
+    }
+
+    #[test]
+    fn scutil_dns_labels_are_not_localized() {
+        let raw = "DNS configuration\n\nresolver #1\n  search domain[0] : fritz.box\n  nameserver[0] : 192.168.178.1\n";
+        let parsed = parse_scutil_dns(raw).unwrap();
+        assert_eq!(parsed["nameservers"][0], "192.168.178.1");
+        assert_eq!(parsed["search_domains"][0], "fritz.box");
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request};
+use axum::http::{header, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+// Requests per minute each class of route accepts before answering 429, so a
+// misbehaving page or script can't hammer the executor or the probes.
+// Override with OHFIXIT_RATE_LIMITS, e.g. "execute=5,probes=20". A speed
+// test moves tens of megabytes, so it gets a limit of its own.
+const DEFAULT_LIMITS: &[(&str, u32)] =
+    &[("execute", 10), ("probes", 30), ("screen", 10), ("speedtest", 2), ("reads", 120)];
+const WINDOW: Duration = Duration::from_secs(60);
+
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+static WINDOWS: Mutex<BTreeMap<&'static str, Window>> = Mutex::new(BTreeMap::new());
+
+// Which limit a route counts against. The kill switch and helper-to-helper
+// calls are never limited, and neither is freezing.
+fn class(method: &Method, path: &str) -> Option<&'static str> {
+    match (method.as_str(), path) {
+        ("POST", "/automation/abort-all")
+        | ("POST", "/automation/freeze")
+        | ("POST", "/shutdown")
+        | ("POST", "/instance/activate") => None,
+        ("POST", "/automation/execute")
+        | ("POST", "/automation/execute-batch")
+        | ("POST", "/guided")
+        | ("POST", "/benchmark") => Some("execute"),
+        ("POST", "/probes/run")
+        | ("POST", "/diagnostics/query")
+        | ("POST", "/health/logs/query")
+        | ("POST", "/guided/{id}/verify") => Some("probes"),
+        // Samples CPU use for half a second
+        ("GET", "/processes") => Some("probes"),
+        ("GET", p) if p.starts_with("/health/") || p.starts_with("/net/") => Some("probes"),
+        ("POST", "/screenshot") | ("POST", "/screenrecord") => Some("screen"),
+        ("POST", "/net/speedtest") => Some("speedtest"),
+        _ => Some("reads"),
+    }
+}
+
+fn limit(class: &str) -> u32 {
+    let configured = std::env::var("OHFIXIT_RATE_LIMITS").unwrap_or_default();
+    configured
+        .split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .find(|(name, _)| name.trim() == class)
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .or_else(|| DEFAULT_LIMITS.iter().find(|(name, _)| *name == class).map(|(_, limit)| *limit))
+        .unwrap_or(u32::MAX)
+}
+
+// Counts the request against its class; Err is the seconds until the window resets
+fn take(class: &'static str) -> Result<(), u64> {
+    let mut windows = WINDOWS.lock().unwrap();
+    let window = windows.entry(class).or_insert_with(|| Window { started: Instant::now(), count: 0 });
+    if window.started.elapsed() >= WINDOW {
+        *window = Window { started: Instant::now(), count: 0 };
+    }
+    if window.count >= limit(class) {
+        return Err(WINDOW.saturating_sub(window.started.elapsed()).as_secs().max(1));
+    }
+    window.count += 1;
+    Ok(())
+}
+
+// Route layer for the status server, ahead of policy::enforce so rejected
+// floods don't cost token validation
+pub async fn enforce(matched: Option<MatchedPath>, request: Request, next: Next) -> Response {
+    let path = matched.as_ref().map(|m| m.as_str()).unwrap_or_default();
+    let Some(class) = class(request.method(), path) else {
+        return next.run(request).await;
+    };
+    if let Err(retry_after) = take(class) {
+        log::info!("Rate limited {} {} ({} limit)", request.method(), path, class);
+        let body = Json(serde_json::json!({
+            "error": format!("Too many {} requests, retry in {}s", class, retry_after),
+            "retry_after": retry_after,
+        }));
+        return (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, retry_after.to_string())], body)
+            .into_response();
+    }
+    next.run(request).await
+}
+
+// Limits and what's left of them this minute, for /status
+pub fn summary() -> serde_json::Value {
+    let windows = WINDOWS.lock().unwrap();
+    let classes: serde_json::Map<String, serde_json::Value> = DEFAULT_LIMITS
+        .iter()
+        .map(|(class, _)| {
+            let limit = limit(class);
+            let used = windows
+                .get(class)
+                .filter(|window| window.started.elapsed() < WINDOW)
+                .map_or(0, |window| window.count);
+            (class.to_string(), serde_json::json!({
+                "per_minute": limit,
+                "remaining": limit.saturating_sub(used),
+            }))
+        })
+        .collect();
+    serde_json::Value::Object(classes)
+}
@@ -0,0 +1,66 @@
+use chrono::Utc;
+use ring::{digest, hmac};
+use serde::{Deserialize, Serialize};
+
+use crate::{ActionDefinition, Claims};
+
+// Ties one execution to the approval that authorized it: which approval, the
+// exact text the user approved (as hashed by the server) and the commands run.
+// Signed with the approval secret so the server can tell it wasn't altered.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    pub approval_id: String,
+    // None for tokens minted before the server started hashing approvals
+    pub approval_text_hash: Option<String>,
+    pub action_id: String,
+    pub commands_hash: String,
+    pub success: bool,
+    pub executed_at: String,
+    pub signature: String,
+}
+
+pub fn issue(claims: &Claims, action: &ActionDefinition, success: bool, secret: &str) -> Receipt {
+    let mut receipt = Receipt {
+        approval_id: claims.approval_id.clone(),
+        approval_text_hash: claims.approval_text_hash.clone(),
+        action_id: action.id.clone(),
+        commands_hash: hex(digest::digest(&digest::SHA256, commands_text(action).as_bytes()).as_ref()),
+        success,
+        executed_at: Utc::now().to_rfc3339(),
+        signature: String::new(),
+    };
+    receipt.signature = sign(&signed_text(&receipt), secret);
+    receipt
+}
+
+// Hex HMAC-SHA256 of `text` with the approval secret the server shares
+pub fn sign(text: &str, secret: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hex(hmac::sign(&key, text.as_bytes()).as_ref())
+}
+
+// Plugin actions are identified by their module instead of command lines
+fn commands_text(action: &ActionDefinition) -> String {
+    match &action.plugin {
+        Some(module_hash) => format!("wasm:{}", module_hash),
+        None => action.commands.join("\n"),
+    }
+}
+
+// Must match receiptSigningText in lib/ohfixit/receipt.ts
+fn signed_text(receipt: &Receipt) -> String {
+    [
+        receipt.approval_id.as_str(),
+        receipt.approval_text_hash.as_deref().unwrap_or(""),
+        receipt.action_id.as_str(),
+        receipt.commands_hash.as_str(),
+        if receipt.success { "true" } else { "false" },
+        receipt.executed_at.as_str(),
+    ]
+    .join("\n")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,135 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+const REDACTED: &str = "[REDACTED]";
+// Random keys and tokens sit well above this (hex digests top out at 4.0)
+const MIN_SECRET_ENTROPY: f64 = 4.0;
+const MIN_SECRET_LEN: usize = 24;
+
+struct Patterns {
+    // Replaced entirely
+    whole: Vec<Regex>,
+    // `key=value` style, only the value (capture group "value") is replaced
+    assignment: Regex,
+    // The user's name inside home directory paths
+    home_user: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        whole: vec![
+            Regex::new(r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----").unwrap(),
+            Regex::new(r"(?i)\bbearer\s+[A-Za-z0-9\-._~+/]+=*").unwrap(),
+            Regex::new(r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+").unwrap(),
+            Regex::new(r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b").unwrap(),
+            Regex::new(r"\b(?:ghp|gho|ghu|ghs|github_pat|xox[abpr]|sk)_[A-Za-z0-9_-]{16,}").unwrap(),
+        ],
+        assignment: Regex::new(
+            r#"(?i)\b(?:password|passwd|pwd|passphrase|secret|client_secret|token|api[_-]?key|access[_-]?key|private[_-]?key|psk|wpa[_-]?psk)"?\s*[:=]\s*(?P<value>"[^"]*"|'[^']*'|[^\s,;]+)"#,
+        )
+        .unwrap(),
+        home_user: Regex::new(r"(?P<prefix>/Users/|/home/|[A-Za-z]:\\Users\\)(?P<user>[^/\\\s]+)").unwrap(),
+    })
+}
+
+// Strip secrets from command output before it leaves the machine. Returns the
+// redacted text and how many values were replaced.
+pub fn redact(text: &str) -> (String, usize) {
+    let patterns = patterns();
+    let mut count = 0;
+    let mut text = text.to_string();
+
+    for pattern in &patterns.whole {
+        count += pattern.find_iter(&text).count();
+        text = pattern.replace_all(&text, REDACTED).into_owned();
+    }
+
+    let mut assignments = 0;
+    text = patterns
+        .assignment
+        .replace_all(&text, |caps: &regex::Captures| {
+            let whole = &caps[0];
+            let value = caps.name("value").unwrap();
+            if value.as_str().contains(REDACTED) {
+                return whole.to_string();
+            }
+            assignments += 1;
+            let start = value.start() - caps.get(0).unwrap().start();
+            format!("{}{}", &whole[..start], REDACTED)
+        })
+        .into_owned();
+    count += assignments;
+
+    let mut users = 0;
+    text = patterns
+        .home_user
+        .replace_all(&text, |caps: &regex::Captures| {
+            if &caps["user"] == "Shared" || &caps["user"] == "Public" {
+                return caps[0].to_string();
+            }
+            users += 1;
+            format!("{}<user>", &caps["prefix"])
+        })
+        .into_owned();
+    count += users;
+
+    let (text, high_entropy) = redact_high_entropy(&text);
+    (text, count + high_entropy)
+}
+
+// Catch keys no pattern knows about: long runs of base64/url-safe characters
+// that look random
+fn redact_high_entropy(text: &str) -> (String, usize) {
+    let mut count = 0;
+    let mut output = String::with_capacity(text.len());
+    let mut word = String::new();
+
+    let mut flush = |word: &mut String, output: &mut String| {
+        if looks_random(word) {
+            count += 1;
+            output.push_str(REDACTED);
+        } else {
+            output.push_str(word);
+        }
+        word.clear();
+    };
+
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=' | '_' | '-') {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut output);
+            output.push(c);
+        }
+    }
+    flush(&mut word, &mut output);
+    (output, count)
+}
+
+fn looks_random(word: &str) -> bool {
+    if word.len() < MIN_SECRET_LEN || word.starts_with('/') {
+        return false;
+    }
+    let has_digit = word.chars().any(|c| c.is_ascii_digit());
+    let has_upper = word.chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = word.chars().any(|c| c.is_ascii_lowercase());
+    has_digit && has_upper && has_lower && shannon_entropy(word) > MIN_SECRET_ENTROPY
+}
+
+fn shannon_entropy(word: &str) -> f64 {
+    let mut counts = [0usize; 256];
+    for b in word.bytes() {
+        counts[b as usize] += 1;
+    }
+    let len = word.len() as f64;
+    counts
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
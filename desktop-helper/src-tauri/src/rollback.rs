@@ -0,0 +1,463 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::{diagnostic, read_trimmed};
+use crate::exec_context::system32;
+use crate::{audit, elevation, scheduler, storage};
+use crate::{ActionDefinition, RollbackPoint};
+
+const REGISTRY_FILE: &str = "rollback_points.json";
+const BACKUPS_DIR: &str = "backups";
+const DEFAULT_TTL_HOURS: i64 = 72;
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Everything needed to undo one specific execution of an action
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RollbackRecord {
+    pub rollback_id: String,
+    pub action_id: String,
+    pub created_at: DateTime<Utc>,
+    // Records written before retention existed expire at the next cleanup
+    #[serde(default = "Utc::now")]
+    pub expires_at: DateTime<Utc>,
+    pub method: String,
+    pub backup_dir: PathBuf,
+    pub prior_state: BTreeMap<String, String>,
+    #[serde(default)]
+    pub snapshot: Option<VolumeSnapshot>,
+}
+
+// APFS local snapshot (macOS) or VSS shadow copy (Windows) taken before a
+// risky action, plus the paths that should be restored from it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VolumeSnapshot {
+    pub kind: String,
+    pub id: String,
+    pub volume: String,
+    pub device_path: Option<String>,
+    pub paths: Vec<String>,
+}
+
+impl RollbackRecord {
+    pub fn context(&self) -> CommandContext {
+        CommandContext {
+            backup_dir: Some(self.backup_dir.clone()),
+            prior_state: self.prior_state.clone(),
+            snapshot_paths: self.snapshot.as_ref().map(|s| s.paths.clone()).unwrap_or_default(),
+        }
+    }
+
+    pub fn to_point(&self) -> RollbackPoint {
+        RollbackPoint {
+            method: self.method.clone(),
+            data: serde_json::json!({
+                "rollback_id": self.rollback_id,
+                "action_id": self.action_id,
+                "created_at": self.created_at.to_rfc3339(),
+                "expires_at": self.expires_at.to_rfc3339(),
+                "backup_dir": self.backup_dir,
+                "prior_state": self.prior_state,
+                "snapshot": self.snapshot,
+            }),
+        }
+    }
+
+    // Commands that copy the snapshotted paths back into place. Paths go in as
+    // placeholders, expanded per token, because the backup dir (under
+    // "Application Support" on macOS) and the paths themselves can contain
+    // spaces.
+    pub fn restore_commands(&self, action: &ActionDefinition) -> Vec<String> {
+        let Some(snapshot) = &self.snapshot else {
+            return action.rollback_commands.clone();
+        };
+
+        match snapshot.kind.as_str() {
+            "apfs" => {
+                let mut commands = vec![format!(
+                    "/sbin/mount_apfs -o nobrowse,rdonly -s com.apple.TimeMachine.{}.local {} {{backup_dir}}/snapshot",
+                    snapshot.id, snapshot.volume
+                )];
+                for index in 0..snapshot.paths.len() {
+                    commands.push(format!(
+                        "/usr/bin/rsync -a {{backup_dir}}/snapshot{{snapshot_path.{0}}}/ {{snapshot_path.{0}}}/",
+                        index
+                    ));
+                }
+                commands.push("/sbin/umount {backup_dir}/snapshot".to_string());
+                commands
+            }
+            "vss" => {
+                let device = snapshot.device_path.clone().unwrap_or_default();
+                snapshot
+                    .paths
+                    .iter()
+                    .map(|path| {
+                        // Shadow copies expose the volume root, so drop the drive letter
+                        let relative = path.split_once(':').map(|(_, rest)| rest).unwrap_or(path);
+                        format!(
+                            "{{system32}}\\WindowsPowerShell\\v1.0\\powershell.exe -NoProfile -Command Copy-Item -Path '{}{}\\*' -Destination '{}' -Recurse -Force",
+                            device, relative, path
+                        )
+                    })
+                    .collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+// Values substituted into action commands before they run:
+// {home}, {temp}, {system32}, {backup_dir}, {state.<key>} for captured prior state
+// and {snapshot_path.<n>} for the paths a volume snapshot covers
+#[derive(Debug, Default, Clone)]
+pub struct CommandContext {
+    pub backup_dir: Option<PathBuf>,
+    pub prior_state: BTreeMap<String, String>,
+    pub snapshot_paths: Vec<String>,
+}
+
+impl CommandContext {
+    pub fn expand(&self, token: &str) -> String {
+        if !token.contains('{') {
+            return token.to_string();
+        }
+
+        let mut expanded = token.to_string();
+        if let Some(home) = dirs::home_dir() {
+            expanded = expanded.replace("{home}", &home.to_string_lossy());
+        }
+        expanded = expanded.replace("{temp}", &std::env::temp_dir().to_string_lossy());
+        expanded = expanded.replace("{system32}", &crate::exec_context::system32().to_string_lossy());
+        if let Some(backup_dir) = &self.backup_dir {
+            expanded = expanded.replace("{backup_dir}", &backup_dir.to_string_lossy());
+        }
+        for (key, value) in &self.prior_state {
+            expanded = expanded.replace(&format!("{{state.{}}}", key), value);
+        }
+        for (index, path) in self.snapshot_paths.iter().enumerate() {
+            expanded = expanded.replace(&format!("{{snapshot_path.{}}}", index), path);
+        }
+        expanded
+    }
+}
+
+// Serializes read-modify-write cycles on the registry file
+static REGISTRY_LOCK: Mutex<()> = Mutex::new(());
+
+// Create the backup directory and capture prior state for an action that is
+// about to run. The record is only persisted once the action has executed.
+pub fn prepare(action: &ActionDefinition) -> Result<RollbackRecord, String> {
+    let rollback_id = uuid::Uuid::new_v4().to_string();
+    // Owner-only: backups hold copies of the user's caches and settings
+    let backups = storage::data_dir().join(BACKUPS_DIR);
+    storage::create_private_dir(&backups)?;
+    let backup_dir = backups.join(&rollback_id);
+    storage::create_private_dir(&backup_dir)?;
+
+    let mut prior_state = BTreeMap::new();
+    let context = CommandContext::default();
+    for (key, command) in &action.state_captures {
+        let value = capture_value(command, &context)
+            .ok_or_else(|| format!("Failed to capture prior state '{}' for rollback", key))?;
+        prior_state.insert(key.clone(), value);
+    }
+
+    // Risky actions that touch many files get a whole-volume snapshot instead
+    // of relying on command-sequence rollback alone
+    let snapshot = if action.snapshot_paths.is_empty() {
+        None
+    } else {
+        // Mount point used when restoring from an APFS snapshot
+        fs::create_dir_all(backup_dir.join("snapshot"))
+            .map_err(|e| format!("Failed to create snapshot mount point: {}", e))?;
+        Some(create_volume_snapshot(&action.snapshot_paths)?)
+    };
+    let method = match &snapshot {
+        Some(snapshot) => format!("{}_snapshot", snapshot.kind),
+        None => "command_sequence".to_string(),
+    };
+
+    let created_at = Utc::now();
+    Ok(RollbackRecord {
+        rollback_id,
+        action_id: action.id.clone(),
+        created_at,
+        expires_at: created_at + ttl(),
+        method,
+        backup_dir,
+        prior_state,
+        snapshot,
+    })
+}
+
+pub fn register(record: &RollbackRecord) -> Result<(), String> {
+    let _guard = REGISTRY_LOCK.lock().unwrap();
+    let mut registry: HashMap<String, RollbackRecord> = storage::load_json(REGISTRY_FILE);
+    registry.insert(record.rollback_id.clone(), record.clone());
+    storage::save_json(REGISTRY_FILE, &registry)
+}
+
+pub fn get(rollback_id: &str) -> Option<RollbackRecord> {
+    let _guard = REGISTRY_LOCK.lock().unwrap();
+    let registry: HashMap<String, RollbackRecord> = storage::load_json(REGISTRY_FILE);
+    registry.get(rollback_id).cloned()
+}
+
+pub fn list() -> Vec<RollbackRecord> {
+    let _guard = REGISTRY_LOCK.lock().unwrap();
+    let registry: HashMap<String, RollbackRecord> = storage::load_json(REGISTRY_FILE);
+    let mut records: Vec<RollbackRecord> = registry.into_values().collect();
+    records.sort_by_key(|r| r.expires_at);
+    records
+}
+
+// How long rollback points (and their backups) are kept (OHFIXIT_ROLLBACK_TTL_HOURS, default 72h)
+pub fn ttl() -> chrono::Duration {
+    let hours = std::env::var("OHFIXIT_ROLLBACK_TTL_HOURS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|h| *h > 0)
+        .unwrap_or(DEFAULT_TTL_HOURS);
+    chrono::Duration::hours(hours)
+}
+
+// Discard expired rollback points and wipe legacy /tmp backups older than the TTL
+pub fn cleanup_expired() -> usize {
+    // Backup dirs created before they were made owner-only
+    if let Err(e) = storage::create_private_dir(&storage::data_dir().join(BACKUPS_DIR)) {
+        log::error!("{}", e);
+    }
+    let now = Utc::now();
+    let expired: Vec<String> = list()
+        .into_iter()
+        .filter(|r| r.expires_at <= now)
+        .map(|r| r.rollback_id)
+        .collect();
+
+    let mut removed = 0;
+    for rollback_id in &expired {
+        match discard(rollback_id) {
+            Ok(()) => removed += 1,
+            Err(e) => log::error!("Failed to expire rollback point {}: {}", rollback_id, e),
+        }
+    }
+
+    // Older helper versions left backups in /tmp
+    let cutoff = std::time::SystemTime::now() - ttl().to_std().unwrap_or_default();
+    if let Ok(entries) = fs::read_dir("/tmp") {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !(name.starts_with("cache_backup_") || name == "wifi_state_backup.txt") {
+                continue;
+            }
+            let stale = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .map(|modified| modified < cutoff)
+                .unwrap_or(false);
+            if stale {
+                match storage::wipe(&entry.path()) {
+                    Ok(()) => removed += 1,
+                    Err(e) => log::error!("Failed to remove legacy backup: {}", e),
+                }
+            }
+        }
+    }
+
+    removed
+}
+
+pub fn spawn_cleanup_task() {
+    scheduler::spawn_job("rollback_cleanup", CLEANUP_INTERVAL, false, || async {
+        match tauri::async_runtime::spawn_blocking(cleanup_expired).await {
+            Ok(0) => Ok(String::new()),
+            Ok(removed) => Ok(format!("Expired {} rollback point(s)", removed)),
+            Err(e) => Err(format!("Rollback cleanup task failed: {}", e)),
+        }
+    });
+}
+
+// Summary of pending rollback points so the server/UI can warn before a window closes
+pub fn pending_summary() -> Vec<serde_json::Value> {
+    let now = Utc::now();
+    list()
+        .into_iter()
+        .map(|r| {
+            serde_json::json!({
+                "rollback_id": r.rollback_id,
+                "action_id": r.action_id,
+                "method": r.method,
+                "created_at": r.created_at.to_rfc3339(),
+                "expires_at": r.expires_at.to_rfc3339(),
+                "expires_in_seconds": (r.expires_at - now).num_seconds().max(0),
+            })
+        })
+        .collect()
+}
+
+// GET /rollback-points
+pub async fn rollback_points_handler() -> Json<serde_json::Value> {
+    let points = tauri::async_runtime::spawn_blocking(pending_summary)
+        .await
+        .unwrap_or_default();
+    Json(serde_json::json!({
+        "ttl_hours": ttl().num_hours(),
+        "rollback_points": points,
+    }))
+}
+
+// Drop a rollback point and the backups (and volume snapshot) it owns
+pub fn discard(rollback_id: &str) -> Result<(), String> {
+    let _guard = REGISTRY_LOCK.lock().unwrap();
+    let mut registry: HashMap<String, RollbackRecord> = storage::load_json(REGISTRY_FILE);
+    if let Some(record) = registry.remove(rollback_id) {
+        release(&record)?;
+    }
+    storage::save_json(REGISTRY_FILE, &registry)
+}
+
+// Delete the snapshot and securely wipe the backup files behind a record,
+// registered or not
+pub fn release(record: &RollbackRecord) -> Result<(), String> {
+    if let Some(snapshot) = &record.snapshot {
+        delete_volume_snapshot(snapshot)?;
+    }
+    storage::wipe(&record.backup_dir)?;
+    audit::record("rollback_backup_wiped", serde_json::json!({
+        "rollback_id": record.rollback_id,
+        "action_id": record.action_id,
+    }));
+    Ok(())
+}
+
+// Run a capture command and keep the last word of its output
+// (e.g. "Wi-Fi Power (en0): On" -> "On")
+fn capture_value(command: &str, context: &CommandContext) -> Option<String> {
+    let parts: Vec<String> = command.split_whitespace().map(|p| context.expand(p)).collect();
+    let (program, args) = parts.split_first()?;
+    if let Err(e) = crate::exec_context::verify_binary(program) {
+        log::error!("Skipping state capture: {}", e);
+        return None;
+    }
+    let output = diagnostic(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .last()
+        .map(str::to_string)
+}
+
+fn create_volume_snapshot(paths: &[String]) -> Result<VolumeSnapshot, String> {
+    let context = CommandContext::default();
+    let paths: Vec<String> = paths.iter().map(|p| context.expand(p)).collect();
+
+    match std::env::consts::OS {
+        "macos" => {
+            // "Created local snapshot with date: 2024-05-01-101112"
+            let output = read_trimmed("/usr/bin/tmutil", &["localsnapshot"])
+                .ok_or_else(|| "Failed to create APFS local snapshot".to_string())?;
+            let id = output
+                .rsplit("date: ")
+                .next()
+                .map(str::trim)
+                .filter(|d| !d.is_empty() && !d.contains(' '))
+                .ok_or_else(|| format!("Unexpected tmutil output: {}", output))?;
+            // User data lives on the Data volume since Catalina
+            let volume = if std::path::Path::new("/System/Volumes/Data").exists() {
+                "/System/Volumes/Data"
+            } else {
+                "/"
+            };
+            Ok(VolumeSnapshot {
+                kind: "apfs".to_string(),
+                id: id.to_string(),
+                volume: volume.to_string(),
+                device_path: None,
+                paths,
+            })
+        }
+        "windows" => {
+            let volume = paths
+                .first()
+                .and_then(|p| p.get(..3))
+                .unwrap_or("C:\\")
+                .to_string();
+            // "SHADOW {id} \\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy3"
+            let create = format!(
+                "$s = Invoke-CimMethod -ClassName Win32_ShadowCopy -MethodName Create -Arguments @{{Volume='{}'; Context='ClientAccessible'}}; \
+                 $c = Get-CimInstance Win32_ShadowCopy | Where-Object ID -eq $s.ShadowID; \
+                 Write-Output \"SHADOW $($s.ShadowID) $($c.DeviceObject)\"",
+                volume
+            );
+            let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe").to_string_lossy().into_owned();
+            let output = run_elevated(vec![powershell, "-NoProfile".to_string(), "-Command".to_string(), create])
+                .map_err(|e| format!("Failed to create VSS shadow copy: {}", e))?;
+            let mut fields = output
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("SHADOW "))
+                .ok_or_else(|| format!("Unexpected shadow copy output: {}", output.trim()))?
+                .split_whitespace();
+            let id = fields.next().unwrap_or_default().to_string();
+            let device_path = fields.next().map(str::to_string);
+            Ok(VolumeSnapshot {
+                kind: "vss".to_string(),
+                id,
+                volume,
+                device_path,
+                paths,
+            })
+        }
+        os => Err(format!("Volume snapshots are not supported on {}", os)),
+    }
+}
+
+// Both need administrator rights
+fn delete_volume_snapshot(snapshot: &VolumeSnapshot) -> Result<(), String> {
+    let argv = match snapshot.kind.as_str() {
+        "apfs" => vec!["/usr/bin/tmutil".to_string(), "deletelocalsnapshots".to_string(), snapshot.id.clone()],
+        "vss" => vec![
+            system32().join("vssadmin.exe").to_string_lossy().into_owned(),
+            "delete".to_string(),
+            "shadows".to_string(),
+            format!("/Shadow={}", snapshot.id),
+            "/quiet".to_string(),
+        ],
+        _ => return Ok(()),
+    };
+    run_elevated(argv)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to delete {} snapshot {}: {}", snapshot.kind, snapshot.id, e))
+}
+
+// One command through the OS elevation prompt; its output, or why it didn't run
+fn run_elevated(argv: Vec<String>) -> Result<String, String> {
+    let result = elevation::execute(&[argv]).map_err(|status| status.describe().to_string())?;
+    if result.success {
+        Ok(result.output)
+    } else {
+        Err(result.output.trim().to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn discard_rollback_point(rollback_id: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || discard(&rollback_id))
+        .await
+        .map_err(|e| format!("Discard task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn list_rollback_points() -> Result<Vec<serde_json::Value>, String> {
+    tauri::async_runtime::spawn_blocking(pending_summary)
+        .await
+        .map_err(|e| format!("Listing rollback points failed: {}", e))
+}
@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::cmd::read_output;
+
+// Spread runs by ±10% of their interval so a fleet of helpers doesn't hit the
+// server (or the disk) at the same moment
+const JITTER_FRACTION: f64 = 0.1;
+const MAX_STARTUP_DELAY_SECS: u64 = 60;
+// How soon a deferred heavy job checks conditions again
+const DEFER_RETRY: Duration = Duration::from_secs(15 * 60);
+const DEFAULT_BATTERY_THRESHOLD: u8 = 30;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct JobStatus {
+    pub name: String,
+    pub interval_secs: u64,
+    // Heavy jobs are deferred on low battery or thermal pressure
+    pub heavy: bool,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_success: Option<bool>,
+    pub last_deferred_reason: Option<String>,
+    pub deferred_count: u32,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct PowerState {
+    pub on_battery: bool,
+    pub battery_percent: Option<u8>,
+    pub thermal_pressure: bool,
+}
+
+static JOBS: Mutex<BTreeMap<String, JobStatus>> = Mutex::new(BTreeMap::new());
+
+// GET /scheduler
+pub async fn scheduler_handler() -> Json<serde_json::Value> {
+    let power = tauri::async_runtime::spawn_blocking(power_state)
+        .await
+        .unwrap_or_default();
+    let jobs: Vec<JobStatus> = JOBS.lock().unwrap().values().cloned().collect();
+    Json(serde_json::json!({
+        "power": power,
+        "battery_threshold": battery_threshold(),
+        "jobs": jobs,
+    }))
+}
+
+// Run `task` roughly every `interval`. The result message is logged; an
+// empty Ok message means there was nothing worth reporting.
+pub fn spawn_job<F, Fut>(name: &str, interval: Duration, heavy: bool, task: F)
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<String, String>> + Send,
+{
+    let name = name.to_string();
+    JOBS.lock().unwrap().insert(
+        name.clone(),
+        JobStatus {
+            name: name.clone(),
+            interval_secs: interval.as_secs(),
+            heavy,
+            next_run_at: None,
+            last_run_at: None,
+            last_success: None,
+            last_deferred_reason: None,
+            deferred_count: 0,
+        },
+    );
+
+    tauri::async_runtime::spawn(async move {
+        let mut delay = Duration::from_secs(random_below(MAX_STARTUP_DELAY_SECS + 1));
+        loop {
+            update(&name, |job| job.next_run_at = Some(Utc::now() + delay_as_chrono(delay)));
+            tokio::time::sleep(delay).await;
+
+            if heavy {
+                let reason = tauri::async_runtime::spawn_blocking(defer_reason)
+                    .await
+                    .unwrap_or(None);
+                if let Some(reason) = reason {
+                    log::info!("Deferring {}: {}", name, reason);
+                    update(&name, |job| {
+                        job.deferred_count += 1;
+                        job.last_deferred_reason = Some(reason);
+                    });
+                    delay = DEFER_RETRY.min(interval);
+                    continue;
+                }
+            }
+
+            let result = task().await;
+            update(&name, |job| {
+                job.last_run_at = Some(Utc::now());
+                job.last_success = Some(result.is_ok());
+            });
+            match result {
+                Ok(message) if !message.is_empty() => log::info!("{}", message),
+                Ok(_) => {}
+                Err(e) => log::error!("Scheduled job {} failed: {}", name, e),
+            }
+
+            delay = jittered(interval);
+        }
+    });
+}
+
+fn update(name: &str, f: impl FnOnce(&mut JobStatus)) {
+    if let Some(job) = JOBS.lock().unwrap().get_mut(name) {
+        f(job);
+    }
+}
+
+fn delay_as_chrono(delay: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero())
+}
+
+fn jittered(interval: Duration) -> Duration {
+    let spread = (interval.as_secs() as f64 * JITTER_FRACTION) as u64;
+    let offset = random_below(spread * 2 + 1);
+    Duration::from_secs((interval.as_secs() + offset).saturating_sub(spread).max(1))
+}
+
+// A v4 UUID is 122 random bits, plenty for scheduling jitter
+fn random_below(bound: u64) -> u64 {
+    if bound == 0 {
+        return 0;
+    }
+    (uuid::Uuid::new_v4().as_u128() % bound as u128) as u64
+}
+
+// OHFIXIT_BATTERY_THRESHOLD: minimum charge (percent) for heavy jobs on battery
+fn battery_threshold() -> u8 {
+    std::env::var("OHFIXIT_BATTERY_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse::<u8>().ok())
+        .filter(|p| *p <= 100)
+        .unwrap_or(DEFAULT_BATTERY_THRESHOLD)
+}
+
+fn defer_reason() -> Option<String> {
+    let power = power_state();
+    if power.thermal_pressure {
+        return Some("system is under thermal pressure".to_string());
+    }
+    match power.battery_percent {
+        Some(percent) if power.on_battery && percent < battery_threshold() => {
+            Some(format!("on battery at {}%", percent))
+        }
+        _ => None,
+    }
+}
+
+pub fn power_state() -> PowerState {
+    match std::env::consts::OS {
+        "macos" => {
+            // "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=...)	84%; discharging; ..."
+            let batt = read_output("/usr/bin/pmset", &["-g", "batt"]).unwrap_or_default();
+            let battery_percent = batt
+                .split(|c: char| c.is_whitespace() || c == ';')
+                .find_map(|word| word.strip_suffix('%'))
+                .and_then(|p| p.parse().ok());
+            // "CPU_Speed_Limit = 100"; anything lower means the CPU is being throttled
+            let therm = read_output("/usr/bin/pmset", &["-g", "therm"]).unwrap_or_default();
+            let thermal_pressure = therm.lines().any(|line| {
+                line.trim()
+                    .strip_prefix("CPU_Speed_Limit")
+                    .and_then(|rest| rest.trim_start_matches([' ', '=']).trim().parse::<u32>().ok())
+                    .is_some_and(|limit| limit < 100)
+            });
+            PowerState {
+                on_battery: batt.contains("'Battery Power'"),
+                battery_percent,
+                thermal_pressure,
+            }
+        }
+        "windows" => {
+            // BatteryStatus 1 means discharging
+            let output = read_output(
+                "powershell",
+                &[
+                    "-NoProfile",
+                    "-Command",
+                    "Get-CimInstance Win32_Battery | ForEach-Object { \"$($_.BatteryStatus) $($_.EstimatedChargeRemaining)\" }",
+                ],
+            )
+            .unwrap_or_default();
+            let mut fields = output.split_whitespace();
+            let status = fields.next();
+            PowerState {
+                on_battery: status == Some("1"),
+                battery_percent: fields.next().and_then(|p| p.parse().ok()),
+                thermal_pressure: false,
+            }
+        }
+        _ => {
+            let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+                return PowerState::default();
+            };
+            let battery = entries
+                .flatten()
+                .map(|e| e.path())
+                .find(|p| p.file_name().is_some_and(|n| n.to_string_lossy().starts_with("BAT")));
+            let Some(battery) = battery else { return PowerState::default() };
+            let read = |file: &str| std::fs::read_to_string(battery.join(file)).map(|s| s.trim().to_string()).ok();
+            PowerState {
+                on_battery: read("status").as_deref() == Some("Discharging"),
+                battery_percent: read("capacity").and_then(|p| p.parse().ok()),
+                thermal_pressure: false,
+            }
+        }
+    }
+}
@@ -0,0 +1,464 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::screenshot::{self, CaptureError, Dimensions, Region, Shot};
+use crate::{audit, automation, consent, exec_context, fingerprint, outbound, pairing, storage};
+
+// POST /screenrecord: a short clip of one display (or a region of it), for
+// glitches a screenshot can't show. Same token and same frame preparation as
+// POST /screenshot (sensitive windows blacked out), but the user has to agree
+// in a dialog on this machine first, a "Recording" indicator stays on screen
+// (with a Stop button) while it runs, and the clip is uploaded through the
+// report endpoint as a `screen_recording` artifact. Frames are encoded by
+// ffmpeg, which must resolve into a trusted directory (see ffmpeg_dirs), and
+// the clip is written to an owner-only directory in the data dir and wiped
+// once it's read.
+const MAX_DURATION_SECS: u64 = 30;
+const DEFAULT_DURATION_SECS: u64 = 10;
+const MAX_FPS: u32 = 15;
+const DEFAULT_FPS: u32 = 5;
+const INDICATOR_LABEL: &str = "recording";
+const ARTIFACT_TYPE: &str = "screen_recording";
+const RECORDINGS_DIR: &str = "recordings";
+// Admin-only homes for an ffmpeg installed for OhFixIt on systems that don't
+// ship one (%ProgramFiles%\OhFixIt on Windows)
+const MACOS_FFMPEG_DIR: &str = "/Library/Application Support/OhFixIt/bin";
+
+// One recording at a time; STOP ends it early (the indicator's Stop button)
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static STOP: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoFormat {
+    // VP8
+    #[default]
+    Webm,
+    // H.264
+    Mp4,
+}
+
+impl VideoFormat {
+    fn mime_type(self) -> &'static str {
+        match self {
+            VideoFormat::Webm => "video/webm",
+            VideoFormat::Mp4 => "video/mp4",
+        }
+    }
+
+    fn encoder_args(self) -> &'static [&'static str] {
+        match self {
+            VideoFormat::Webm => &["-c:v", "libvpx", "-b:v", "1M", "-deadline", "realtime", "-f", "webm"],
+            VideoFormat::Mp4 => &["-c:v", "libx264", "-preset", "veryfast", "-movflags", "+faststart", "-f", "mp4"],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenRecordRequest {
+    region: Option<Region>,
+    #[serde(default)]
+    display: usize,
+    #[serde(default)]
+    include_cursor: bool,
+    duration_secs: Option<u64>,
+    fps: Option<u32>,
+    #[serde(default)]
+    format: VideoFormat,
+    // Shown in the consent dialog: why the recording is wanted
+    reason: Option<String>,
+}
+
+// An app blacked out in the clip, and in how many frames
+#[derive(Debug, Serialize)]
+pub struct Redacted {
+    app: String,
+    reason: &'static str,
+    frames: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    redactions: Vec<Redacted>,
+    // False when windows couldn't be listed for some frame
+    windows_checked: bool,
+    // Ended from the indicator before the requested duration
+    stopped_early: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenRecordResponse {
+    success: bool,
+    // Base64 of the encoded clip
+    data: String,
+    format: VideoFormat,
+    mime_type: &'static str,
+    size: usize,
+    // SHA-256 (hex) of the clip, as reported with the artifact
+    hash: String,
+    dimensions: Dimensions,
+    duration_ms: u64,
+    frames: u64,
+    display: usize,
+    timestamp: String,
+    // Whether the report endpoint took the artifact
+    uploaded: bool,
+    metadata: Metadata,
+}
+
+struct Recording {
+    bytes: Vec<u8>,
+    width: u32,
+    height: u32,
+    frames: u64,
+    redactions: Vec<Redacted>,
+    windows_checked: bool,
+    stopped_early: bool,
+}
+
+pub async fn screenrecord_handler(
+    State(app): State<AppHandle>,
+    headers: HeaderMap,
+    Json(request): Json<ScreenRecordRequest>,
+) -> Response {
+    let Some(token) = automation::bearer_token(&headers).map(str::to_string) else {
+        return refuse(StatusCode::UNAUTHORIZED, "Missing bearer token", None);
+    };
+    let duration = Duration::from_secs(request.duration_secs.unwrap_or(DEFAULT_DURATION_SECS).clamp(1, MAX_DURATION_SECS));
+    let fps = request.fps.unwrap_or(DEFAULT_FPS).clamp(1, MAX_FPS);
+    if RECORDING.swap(true, Ordering::SeqCst) {
+        return refuse(StatusCode::CONFLICT, "A screen recording is already running", None);
+    }
+    let response = record_with_consent(&app, request, duration, fps, &token).await;
+    RECORDING.store(false, Ordering::SeqCst);
+    response
+}
+
+async fn record_with_consent(app: &AppHandle, request: ScreenRecordRequest, duration: Duration, fps: u32, token: &str) -> Response {
+    let seconds = duration.as_secs();
+    let reason = request.reason.clone().unwrap_or_else(|| "to see a problem as it happens".to_string());
+    let message = format!(
+        "OhFixIt wants to record display {} for up to {} seconds, {}.\n\nA \"Recording\" indicator stays on screen while it runs and can stop it. Password managers, banking apps and terminals showing secrets are blacked out. The clip is sent to OhFixIt.",
+        request.display + 1,
+        seconds,
+        reason,
+    );
+    let answer = consent::ask(app, "Allow OhFixIt to record your screen?".to_string(), message, "Record", "Don't record").await;
+    if answer != Some(true) {
+        audit::record("screen_recording_declined", serde_json::json!({
+            "display": request.display,
+            "duration_secs": seconds,
+            "timed_out": answer.is_none(),
+        }));
+        let details = if answer.is_none() { "Not answered on this computer in time" } else { "Declined on this computer" };
+        return refuse(StatusCode::FORBIDDEN, "Screen recording was not allowed", Some(details));
+    }
+
+    STOP.store(false, Ordering::SeqCst);
+    show_indicator(app, seconds);
+    let recorded = tokio::task::spawn_blocking(move || record(&request, duration, fps).map(|recording| (request, recording)))
+        .await
+        .unwrap_or_else(|e| Err(CaptureError::Failed(format!("Recording task failed: {}", e))));
+    hide_indicator(app);
+
+    let (request, recording) = match recorded {
+        Ok(recorded) => recorded,
+        Err(CaptureError::PermissionDenied(details)) => {
+            log::info!("Screen recording refused by the OS: {}", details);
+            let body = serde_json::json!({
+                "success": false,
+                "error": "Screen capture permission not granted",
+                "details": details,
+                "permission": "denied",
+            });
+            return (StatusCode::FORBIDDEN, Json(body)).into_response();
+        }
+        Err(CaptureError::Failed(e)) => {
+            log::error!("Screen recording failed: {}", e);
+            return refuse(StatusCode::INTERNAL_SERVER_ERROR, "Screen recording failed", Some(&e));
+        }
+    };
+
+    let digest = ring::digest::digest(&ring::digest::SHA256, &recording.bytes);
+    let hash: String = digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+    let data = general_purpose::STANDARD.encode(&recording.bytes);
+    let duration_ms = recording.frames * 1000 / fps as u64;
+    audit::record("screen_recording_captured", serde_json::json!({
+        "display": request.display,
+        "width": recording.width,
+        "height": recording.height,
+        "format": request.format,
+        "duration_ms": duration_ms,
+        "frames": recording.frames,
+        "sha256": hash,
+        "redactions": recording.redactions,
+        "windows_checked": recording.windows_checked,
+        "stopped_early": recording.stopped_early,
+    }));
+
+    let uploaded = match upload(token, request.format, &data, &hash, duration_ms).await {
+        Ok(()) => true,
+        Err(e) => {
+            log::error!("Failed to upload screen recording: {}", e);
+            false
+        }
+    };
+    Json(ScreenRecordResponse {
+        success: true,
+        size: recording.bytes.len(),
+        data,
+        format: request.format,
+        mime_type: request.format.mime_type(),
+        hash,
+        dimensions: Dimensions { width: recording.width, height: recording.height },
+        duration_ms,
+        frames: recording.frames,
+        display: request.display,
+        timestamp: Utc::now().to_rfc3339(),
+        uploaded,
+        metadata: Metadata {
+            redactions: recording.redactions,
+            windows_checked: recording.windows_checked,
+            stopped_early: recording.stopped_early,
+        },
+    })
+    .into_response()
+}
+
+fn refuse(status: StatusCode, error: &str, details: Option<&str>) -> Response {
+    (status, Json(serde_json::json!({ "success": false, "error": error, "details": details }))).into_response()
+}
+
+// Stop button on the recording indicator
+#[tauri::command]
+pub fn stop_screen_recording() {
+    STOP.store(true, Ordering::SeqCst);
+}
+
+// A small always-on-top window at the top of the main display, so a
+// recording is never silent
+fn show_indicator(app: &AppHandle, seconds: u64) {
+    let url = WebviewUrl::App(format!("recording.html?seconds={}", seconds).into());
+    let mut builder = WebviewWindowBuilder::new(app, INDICATOR_LABEL, url)
+        .title("OhFixIt is recording")
+        .inner_size(220.0, 44.0)
+        .decorations(false)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(false);
+    if let Ok(Some(monitor)) = app.primary_monitor() {
+        let size = monitor.size().to_logical::<f64>(monitor.scale_factor());
+        builder = builder.position((size.width - 220.0) / 2.0, 12.0);
+    }
+    if let Err(e) = builder.build() {
+        log::error!("Failed to show the recording indicator: {}", e);
+    }
+    let _ = app.emit("status-update", serde_json::json!({
+        "message": format!("🔴 Recording the screen for up to {}s", seconds),
+        "type": "waiting",
+    }));
+}
+
+fn hide_indicator(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(INDICATOR_LABEL) {
+        let _ = window.destroy();
+    }
+    let _ = app.emit("status-update", serde_json::json!({
+        "message": "Screen recording finished",
+        "type": "connected",
+    }));
+}
+
+// Where ffmpeg may live. Linux packages it in /usr/bin; macOS keeps /usr/bin
+// for Apple (SIP) and Windows has none in System32, so there it's an
+// admin-only directory for OhFixIt. Everywhere, a copy bundled next to the
+// helper's own binary (a sidecar) is as trusted as the helper itself.
+fn ffmpeg_dirs() -> Vec<PathBuf> {
+    let mut dirs = match std::env::consts::OS {
+        "macos" => vec![PathBuf::from(MACOS_FFMPEG_DIR)],
+        "windows" => {
+            let program_files = std::env::var("ProgramFiles").unwrap_or_else(|_| r"C:\Program Files".to_string());
+            vec![Path::new(&program_files).join("OhFixIt")]
+        }
+        _ => vec![PathBuf::from("/usr/bin"), PathBuf::from("/usr/local/bin")],
+    };
+    dirs.extend(std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)));
+    dirs
+}
+
+// Never looked up on the PATH: it's fed the raw screen frames. OHFIXIT_FFMPEG,
+// or the first ffmpeg in ffmpeg_dirs.
+fn ffmpeg() -> Result<PathBuf, CaptureError> {
+    let dirs = ffmpeg_dirs();
+    let name = if std::env::consts::OS == "windows" { "ffmpeg.exe" } else { "ffmpeg" };
+    let program = std::env::var("OHFIXIT_FFMPEG").ok().or_else(|| {
+        dirs.iter().map(|dir| dir.join(name)).find(|path| path.is_file()).map(|path| path.to_string_lossy().into_owned())
+    });
+    let listed = dirs.iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>().join(", ");
+    let Some(program) = program else {
+        return Err(CaptureError::Failed(format!("Screen recording needs ffmpeg, and there is none in {}", listed)));
+    };
+    exec_context::verify_binary_in(&program, &dirs).map_err(|e| {
+        CaptureError::Failed(format!("Screen recording needs ffmpeg in {}: {}", listed, e))
+    })
+}
+
+// An empty owner-only directory for the clip. Only one recording runs at a
+// time, so anything already in it was left by a recording the helper didn't
+// live to finish.
+fn recordings_dir() -> Result<PathBuf, CaptureError> {
+    let dir = storage::data_dir().join(RECORDINGS_DIR);
+    if let Err(e) = storage::wipe(&dir) {
+        log::error!("Failed to wipe leftover recordings: {}", e);
+    }
+    storage::create_private_dir(&dir).map_err(CaptureError::Failed)?;
+    Ok(dir)
+}
+
+// Capture `fps` frames a second into ffmpeg until `duration` is up or STOP is
+// set. A capture slower than the frame interval is repeated for the frames it
+// took, so the clip plays in real time.
+fn record(request: &ScreenRecordRequest, duration: Duration, fps: u32) -> Result<Recording, CaptureError> {
+    let first = screenshot::recording_frame(request.display, request.region, request.include_cursor)?;
+    // yuv420p needs even dimensions
+    let (width, height) = (first.image.width() & !1, first.image.height() & !1);
+    if width == 0 || height == 0 {
+        return Err(CaptureError::Failed("The area to record is too small".to_string()));
+    }
+
+    let ffmpeg = ffmpeg()?;
+    let path = recordings_dir()?.join(format!("ohfixit-recording-{}", uuid::Uuid::new_v4()));
+    let mut child = Command::new(ffmpeg)
+        .args(["-hide_banner", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba"])
+        .args(["-s", &format!("{}x{}", width, height), "-r", &fps.to_string(), "-i", "-"])
+        .args(request.format.encoder_args())
+        .args(["-pix_fmt", "yuv420p", "-y"])
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| CaptureError::Failed(format!("Failed to start ffmpeg: {}", e)))?;
+
+    let total = duration.as_secs() * fps as u64;
+    let interval = Duration::from_secs_f64(1.0 / fps as f64);
+    let started = Instant::now();
+    let mut redactions: Vec<Redacted> = Vec::new();
+    let mut windows_checked = true;
+    let mut written = 0;
+    let mut shot = Some(first);
+    let mut stopped_early = false;
+    let mut failure = None;
+    {
+        let Some(mut stdin) = child.stdin.take() else {
+            let _ = child.kill();
+            return Err(CaptureError::Failed("ffmpeg has no input".to_string()));
+        };
+        while written < total {
+            if STOP.load(Ordering::SeqCst) {
+                stopped_early = true;
+                break;
+            }
+            let Shot { image, redactions: frame_redactions, windows_checked: checked } = match shot.take() {
+                Some(shot) => shot,
+                None => match screenshot::recording_frame(request.display, request.region, request.include_cursor) {
+                    Ok(shot) => shot,
+                    Err(CaptureError::PermissionDenied(e) | CaptureError::Failed(e)) => {
+                        failure = Some(e);
+                        break;
+                    }
+                },
+            };
+            if image.width() < width || image.height() < height {
+                failure = Some("The display changed size during the recording".to_string());
+                break;
+            }
+            windows_checked &= checked;
+            for redaction in frame_redactions {
+                match redactions.iter_mut().find(|r| r.app == redaction.app && r.reason == redaction.reason) {
+                    Some(seen) => seen.frames += 1,
+                    None => redactions.push(Redacted { app: redaction.app, reason: redaction.reason, frames: 1 }),
+                }
+            }
+            let image = image::imageops::crop_imm(&image, 0, 0, width, height).to_image();
+            let due = ((started.elapsed().as_secs_f64() * fps as f64) as u64 + 1).min(total);
+            while written < due {
+                if let Err(e) = stdin.write_all(image.as_raw()) {
+                    failure = Some(format!("ffmpeg stopped taking frames: {}", e));
+                    break;
+                }
+                written += 1;
+            }
+            if failure.is_some() {
+                break;
+            }
+            if let Some(wait) = (interval * written as u32).checked_sub(started.elapsed()) {
+                std::thread::sleep(wait);
+            }
+        }
+        // Dropping stdin ends the input, so ffmpeg finishes the file
+    }
+
+    let output = child.wait_with_output().map_err(|e| CaptureError::Failed(format!("ffmpeg failed: {}", e)));
+    let bytes = std::fs::read(&path);
+    if let Err(e) = storage::wipe(&path) {
+        log::error!("Failed to wipe the recording: {}", e);
+    }
+    let output = output?;
+    if let Some(e) = failure {
+        return Err(CaptureError::Failed(e));
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CaptureError::Failed(format!("ffmpeg exited with {}: {}", output.status, stderr.trim())));
+    }
+    let bytes = bytes.map_err(|e| CaptureError::Failed(format!("Failed to read the recording: {}", e)))?;
+    Ok(Recording { bytes, width, height, frames: written, redactions, windows_checked, stopped_early })
+}
+
+// The clip as a report artifact, to the paired server only
+async fn upload(token: &str, format: VideoFormat, data: &str, hash: &str, duration_ms: u64) -> Result<(), String> {
+    let client = pairing::client();
+    let report_url = outbound::report_url(&client).await?;
+    let payload = serde_json::json!({
+        "actionId": ARTIFACT_TYPE,
+        "success": true,
+        "output": format!("Screen recording, {:.1}s", duration_ms as f64 / 1000.0),
+        "artifacts": [{
+            "type": ARTIFACT_TYPE,
+            "uri": format!("data:{};base64,{}", format.mime_type(), data),
+            "hash": hash,
+        }],
+        "environment": fingerprint::current(),
+        "timestamp": Utc::now().to_rfc3339(),
+    });
+    let response = client
+        .post(&report_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to report screen recording: {}", e))?;
+    if response.status().is_success() {
+        log::info!("Uploaded screen recording ({} bytes)", data.len());
+        Ok(())
+    } else {
+        Err(format!("Server returned status: {}", response.status()))
+    }
+}
@@ -0,0 +1,817 @@
+use std::io::Cursor;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{audit, consent};
+use crate::sensitive_windows::{self, Placement, Redaction};
+
+#[cfg(target_os = "macos")]
+use quartz as backend;
+#[cfg(not(any(target_os = "macos", windows)))]
+use xdg as backend;
+#[cfg(windows)]
+use gdi as backend;
+
+// POST /screenshot and GET /displays for the "show me your screen" flow
+// (app/api/desktop/screenshot and displays). Both need a token with the
+// screen:capture scope. Backends list displays and capture a whole one;
+// region, cursor and encoding are applied here, the same way on every OS.
+const DEFAULT_QUALITY: u8 = 90;
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct Region {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Png,
+    Jpeg,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenshotRequest {
+    region: Option<Region>,
+    // Index into the active displays, main display first
+    #[serde(default)]
+    display: usize,
+    #[serde(default)]
+    include_cursor: bool,
+    #[serde(default)]
+    format: Format,
+    quality: Option<u8>,
+    // Take it even where sensitive windows can't be blacked out (Wayland),
+    // once the user agrees in a dialog on this machine. Refused otherwise.
+    #[serde(default)]
+    allow_unredacted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Dimensions {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    // Sensitive windows blacked out, in the returned image's pixels
+    redactions: Vec<Redaction>,
+    // False when windows couldn't be listed (e.g. under Wayland), so nothing
+    // could be blacked out
+    windows_checked: bool,
+    // The user agreed on this machine to send it unredacted (allowUnredacted)
+    unredacted_allowed: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScreenshotResponse {
+    success: bool,
+    // Base64 of the encoded image
+    data: String,
+    format: Format,
+    size: usize,
+    dimensions: Dimensions,
+    display: usize,
+    timestamp: String,
+    metadata: Metadata,
+}
+
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct Bounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+// A display as GET /displays lists it, in the order ScreenshotRequest.display
+// indexes: main display first
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Display {
+    pub id: String,
+    pub name: String,
+    // Physical pixels, as captured
+    pub width: u32,
+    pub height: u32,
+    pub is_primary: bool,
+    pub scale_factor: f64,
+    // Position in the desktop's coordinates (points on macOS)
+    pub bounds: Bounds,
+}
+
+// One display as the backend captured it: top-down RGBA in physical pixels,
+// with the cursor position in the same pixels when it's on this display, and
+// where it sits among the desktop's windows when that's known
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    pub cursor: Option<(i64, i64)>,
+    pub placement: Option<Placement>,
+}
+
+pub enum CaptureError {
+    // The OS or the user refused; the string says how to allow it. Windows
+    // never asks.
+    #[cfg_attr(windows, allow(dead_code))]
+    PermissionDenied(String),
+    Failed(String),
+}
+
+pub async fn screenshot_handler(State(app): State<AppHandle>, Json(request): Json<ScreenshotRequest>) -> Response {
+    let shot = {
+        let request = request.clone();
+        tokio::task::spawn_blocking(move || shoot(&request))
+            .await
+            .unwrap_or_else(|e| Err(CaptureError::Failed(format!("Screenshot task failed: {}", e))))
+    };
+    let captured = match shot {
+        // Sensitive windows couldn't be blacked out: never sent without the
+        // user agreeing here
+        Ok(shot) if !shot.windows_checked => match unredacted_consent(&app, &request).await {
+            Ok(()) => finish(&request, shot, true),
+            Err(response) => return response,
+        },
+        Ok(shot) => finish(&request, shot, false),
+        Err(e) => Err(e),
+    };
+    match captured {
+        Ok(response) => Json(response).into_response(),
+        Err(CaptureError::PermissionDenied(details)) => {
+            log::info!("Screenshot refused by the OS: {}", details);
+            let body = serde_json::json!({
+                "success": false,
+                "error": "Screen capture permission not granted",
+                "details": details,
+                "permission": "denied",
+            });
+            (StatusCode::FORBIDDEN, Json(body)).into_response()
+        }
+        Err(CaptureError::Failed(e)) => {
+            log::error!("Screenshot failed: {}", e);
+            let body = serde_json::json!({ "success": false, "error": "Screenshot capture failed", "details": e });
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+        }
+    }
+}
+
+// Err is the response refusing an unredacted screenshot: 409 unless the
+// request allowed one, 403 unless the user then agreed
+async fn unredacted_consent(app: &AppHandle, request: &ScreenshotRequest) -> Result<(), Response> {
+    let refuse = |status: StatusCode, details: &str| {
+        let body = serde_json::json!({
+            "success": false,
+            "error": "Sensitive windows can't be blacked out on this screen",
+            "details": details,
+            "redaction": "unavailable",
+        });
+        (status, Json(body)).into_response()
+    };
+    if !request.allow_unredacted {
+        audit::record("screenshot_unredacted_refused", serde_json::json!({ "display": request.display }));
+        return Err(refuse(StatusCode::CONFLICT, "Windows can't be listed here; ask again with allowUnredacted to let the user decide"));
+    }
+    let message = format!(
+        "OhFixIt wants a screenshot of display {}, but can't black out password managers, banking apps or terminals on this screen.\n\nEverything visible will be sent to OhFixIt as it is.",
+        request.display + 1,
+    );
+    let answer = consent::ask(app, "Send an unredacted screenshot?".to_string(), message, "Send as is", "Don't send").await;
+    if answer != Some(true) {
+        audit::record("screenshot_unredacted_declined", serde_json::json!({
+            "display": request.display,
+            "timed_out": answer.is_none(),
+        }));
+        let details = if answer.is_none() { "Not answered on this computer in time" } else { "Declined on this computer" };
+        return Err(refuse(StatusCode::FORBIDDEN, details));
+    }
+    Ok(())
+}
+
+pub async fn displays_handler() -> Response {
+    match tokio::task::spawn_blocking(backend::displays).await {
+        Ok(Ok(displays)) => Json(serde_json::json!({ "success": true, "displays": displays })).into_response(),
+        Ok(Err(CaptureError::PermissionDenied(e)) | Err(CaptureError::Failed(e))) => {
+            let body = serde_json::json!({ "success": false, "error": "Failed to list displays", "details": e });
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+        }
+        Err(e) => {
+            let body = serde_json::json!({ "success": false, "error": "Failed to list displays", "details": e.to_string() });
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+        }
+    }
+}
+
+fn shoot(request: &ScreenshotRequest) -> Result<Shot, CaptureError> {
+    prepare(backend::capture(request.display)?, request.region, request.include_cursor)
+}
+
+fn finish(request: &ScreenshotRequest, shot: Shot, unredacted_allowed: bool) -> Result<ScreenshotResponse, CaptureError> {
+    let Shot { image, redactions, windows_checked } = shot;
+    let quality = request.quality.unwrap_or(DEFAULT_QUALITY).clamp(1, 100);
+    let bytes = encode(&image, request.format, quality).map_err(CaptureError::Failed)?;
+    audit::record("screenshot_captured", serde_json::json!({
+        "display": request.display,
+        "width": image.width(),
+        "height": image.height(),
+        "format": request.format,
+        "cursor": request.include_cursor,
+        "redactions": redactions,
+        "windows_checked": windows_checked,
+        "unredacted_allowed": unredacted_allowed,
+    }));
+    Ok(ScreenshotResponse {
+        success: true,
+        size: bytes.len(),
+        data: general_purpose::STANDARD.encode(&bytes),
+        format: request.format,
+        dimensions: Dimensions { width: image.width(), height: image.height() },
+        display: request.display,
+        timestamp: Utc::now().to_rfc3339(),
+        metadata: Metadata { redactions, windows_checked, unredacted_allowed },
+    })
+}
+
+// A display capture ready to leave the machine: sensitive windows blacked
+// out, then the pointer drawn on, then cropped to the region
+pub struct Shot {
+    pub image: RgbaImage,
+    pub redactions: Vec<Redaction>,
+    pub windows_checked: bool,
+}
+
+// One frame of a recording (see screenrecord.rs), prepared like a screenshot
+pub fn recording_frame(display: usize, region: Option<Region>, include_cursor: bool) -> Result<Shot, CaptureError> {
+    prepare(backend::recording_frame(display)?, region, include_cursor)
+}
+
+fn prepare(frame: Frame, region: Option<Region>, include_cursor: bool) -> Result<Shot, CaptureError> {
+    let mut image = RgbaImage::from_raw(frame.width, frame.height, frame.rgba)
+        .ok_or_else(|| CaptureError::Failed("Captured frame has the wrong size".to_string()))?;
+    let redacted = match frame.placement {
+        Some(placement) => sensitive_windows::redact(&mut image, placement),
+        None => Err("Window positions are unknown for this capture".to_string()),
+    };
+    let (mut redactions, windows_checked) = match redacted {
+        Ok(redactions) => (redactions, true),
+        Err(e) => {
+            log::debug!("Sensitive windows not checked: {}", e);
+            (Vec::new(), false)
+        }
+    };
+    if include_cursor {
+        if let Some((x, y)) = frame.cursor {
+            draw_cursor(&mut image, x, y);
+        }
+    }
+    if let Some(region) = region {
+        let (x, y, width, height) = clamp(region, image.width(), image.height())
+            .ok_or_else(|| CaptureError::Failed("Region is outside the display".to_string()))?;
+        image = image::imageops::crop_imm(&image, x, y, width, height).to_image();
+        redactions = redactions.into_iter().filter_map(|redaction| crop_redaction(redaction, x, y, width, height)).collect();
+    }
+    Ok(Shot { image, redactions, windows_checked })
+}
+
+// `redaction` relative to a crop at (x, y) of width x height, if it's inside
+fn crop_redaction(redaction: Redaction, x: u32, y: u32, width: u32, height: u32) -> Option<Redaction> {
+    let left = redaction.x.max(x);
+    let top = redaction.y.max(y);
+    let right = (redaction.x + redaction.width).min(x + width);
+    let bottom = (redaction.y + redaction.height).min(y + height);
+    (right > left && bottom > top).then(|| Redaction {
+        x: left - x,
+        y: top - y,
+        width: right - left,
+        height: bottom - top,
+        ..redaction
+    })
+}
+
+// The part of `region` (display pixels) inside a width x height display
+fn clamp(region: Region, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+    let left = region.x.max(0.0).round() as u32;
+    let top = region.y.max(0.0).round() as u32;
+    let right = ((region.x + region.width).round().max(0.0) as u32).min(width);
+    let bottom = ((region.y + region.height).round().max(0.0) as u32).min(height);
+    (right > left && bottom > top).then(|| (left, top, right - left, bottom - top))
+}
+
+fn encode(image: &RgbaImage, format: Format, quality: u8) -> Result<Vec<u8>, String> {
+    let mut bytes = Cursor::new(Vec::new());
+    match format {
+        Format::Png => image.write_to(&mut bytes, ImageFormat::Png),
+        // JPEG has no alpha channel
+        Format::Jpeg => JpegEncoder::new_with_quality(&mut bytes, quality)
+            .encode_image(&DynamicImage::ImageRgba8(image.clone()).into_rgb8()),
+    }
+    .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
+    Ok(bytes.into_inner())
+}
+
+// Rows of 32-bit BGRA/BGRX pixels `stride` bytes apart, as opaque RGBA
+fn bgra_to_rgba(bytes: &[u8], width: usize, height: usize, stride: usize) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for row in bytes.chunks(stride).take(height) {
+        for pixel in row[..width * 4].chunks_exact(4) {
+            rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+        }
+    }
+    rgba
+}
+
+// Display captures leave the pointer out, so it's drawn on: a black-edged
+// white arrow with its tip at (x, y)
+const CURSOR: [&str; 16] = [
+    "X",
+    "XX",
+    "X.X",
+    "X..X",
+    "X...X",
+    "X....X",
+    "X.....X",
+    "X......X",
+    "X.......X",
+    "X........X",
+    "X.....XXXXX",
+    "X..X..X",
+    "X.X X..X",
+    "XX  X..X",
+    "X    X..X",
+    "     XXX",
+];
+
+fn draw_cursor(image: &mut RgbaImage, x: i64, y: i64) {
+    for (row, line) in CURSOR.iter().enumerate() {
+        for (column, cell) in line.chars().enumerate() {
+            let color = match cell {
+                'X' => image::Rgba([0, 0, 0, 255]),
+                '.' => image::Rgba([255, 255, 255, 255]),
+                _ => continue,
+            };
+            let (px, py) = (x + column as i64, y + row as i64);
+            if px >= 0 && py >= 0 && (px as u32) < image.width() && (py as u32) < image.height() {
+                image.put_pixel(px as u32, py as u32, color);
+            }
+        }
+    }
+}
+
+// Quartz display capture. Needs the Screen Recording permission (macOS 10.15+);
+// without it the OS hands back an image of the desktop background only.
+#[cfg(target_os = "macos")]
+mod quartz {
+    use core_graphics::display::CGDisplay;
+    use core_graphics::event::CGEvent;
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    use super::{Bounds, CaptureError, Display, Frame, Placement};
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    fn active() -> Result<Vec<CGDisplay>, CaptureError> {
+        let ids = CGDisplay::active_displays()
+            .map_err(|e| CaptureError::Failed(format!("Failed to list displays: error {}", e)))?;
+        Ok(ids.into_iter().map(CGDisplay::new).collect())
+    }
+
+    pub fn displays() -> Result<Vec<Display>, CaptureError> {
+        Ok(active()?
+            .iter()
+            .enumerate()
+            .map(|(index, display)| {
+                let bounds = display.bounds();
+                let width = display.pixels_wide() as u32;
+                Display {
+                    id: display.id.to_string(),
+                    name: if display.is_builtin() { "Built-in display".to_string() } else { format!("Display {}", index + 1) },
+                    width,
+                    height: display.pixels_high() as u32,
+                    is_primary: display.is_main(),
+                    scale_factor: width as f64 / bounds.size.width,
+                    bounds: Bounds {
+                        x: bounds.origin.x,
+                        y: bounds.origin.y,
+                        width: bounds.size.width,
+                        height: bounds.size.height,
+                    },
+                }
+            })
+            .collect())
+    }
+
+    pub fn capture(index: usize) -> Result<Frame, CaptureError> {
+        // SAFETY: no arguments; both only read (or prompt for) the permission
+        if !unsafe { CGPreflightScreenCaptureAccess() } {
+            // Adds the helper to the list in System Settings, prompting once
+            unsafe { CGRequestScreenCaptureAccess() };
+            return Err(CaptureError::PermissionDenied(
+                "Allow OhFixIt Desktop Helper under System Settings > Privacy & Security > Screen Recording, then restart the helper".to_string(),
+            ));
+        }
+        let displays = active()?;
+        let display = displays
+            .get(index)
+            .ok_or_else(|| CaptureError::Failed(format!("No display {} ({} active)", index, displays.len())))?;
+        let image = display
+            .image()
+            .ok_or_else(|| CaptureError::Failed(format!("Failed to capture display {}", index)))?;
+        if image.bits_per_pixel() != 32 {
+            return Err(CaptureError::Failed(format!("Unexpected {}-bit display image", image.bits_per_pixel())));
+        }
+
+        // BGRA rows, possibly padded past width * 4
+        let (width, height) = (image.width(), image.height());
+        let rgba = super::bgra_to_rgba(image.data().bytes(), width, height, image.bytes_per_row());
+
+        // The pointer in global points, scaled to this display's pixels
+        let bounds = display.bounds();
+        let cursor = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+            .and_then(CGEvent::new)
+            .ok()
+            .map(|event| event.location())
+            .filter(|point| bounds.contains(point))
+            .map(|point| {
+                let scale = width as f64 / bounds.size.width;
+                (((point.x - bounds.origin.x) * scale) as i64, ((point.y - bounds.origin.y) * scale) as i64)
+            });
+        let placement = Placement { x: bounds.origin.x, y: bounds.origin.y, scale: width as f64 / bounds.size.width };
+        Ok(Frame { width: width as u32, height: height as u32, rgba, cursor, placement: Some(placement) })
+    }
+
+    // Quartz is fast enough to repeat for every frame
+    pub fn recording_frame(index: usize) -> Result<Frame, CaptureError> {
+        capture(index)
+    }
+}
+
+// GDI: BitBlt from the screen DC, which covers every monitor. The process is
+// per-monitor DPI aware (Tauri sets it at startup), so monitor rectangles and
+// the cursor are in physical pixels.
+#[cfg(windows)]
+mod gdi {
+    use std::ptr::null_mut;
+
+    use windows_sys::Win32::Foundation::{BOOL, LPARAM, POINT, RECT};
+    use windows_sys::Win32::Graphics::Gdi::{
+        BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, EnumDisplayMonitors, GetDC,
+        GetDIBits, GetMonitorInfoW, ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, CAPTUREBLT,
+        DIB_RGB_COLORS, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW, SRCCOPY,
+    };
+    use windows_sys::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetCursorPos, MONITORINFOF_PRIMARY};
+
+    use super::{Bounds, CaptureError, Display, Frame, Placement};
+
+    struct Monitor {
+        name: String,
+        rect: RECT,
+        primary: bool,
+        scale: f64,
+    }
+
+    unsafe extern "system" fn collect(monitor: HMONITOR, _: HDC, _: *mut RECT, data: LPARAM) -> BOOL {
+        let monitors = &mut *(data as *mut Vec<Monitor>);
+        let mut info: MONITORINFOEXW = std::mem::zeroed();
+        info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+        if GetMonitorInfoW(monitor, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO) != 0 {
+            let (mut dpi_x, mut dpi_y) = (96, 96);
+            GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y);
+            let name = String::from_utf16_lossy(&info.szDevice);
+            monitors.push(Monitor {
+                name: name.trim_end_matches('\0').to_string(),
+                rect: info.monitorInfo.rcMonitor,
+                primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+                scale: dpi_x as f64 / 96.0,
+            });
+        }
+        1
+    }
+
+    // Primary monitor first, then left to right
+    fn monitors() -> Vec<Monitor> {
+        let mut monitors: Vec<Monitor> = Vec::new();
+        // SAFETY: the callback only runs during the call, with our pointer
+        unsafe { EnumDisplayMonitors(null_mut(), std::ptr::null(), Some(collect), &mut monitors as *mut _ as LPARAM) };
+        monitors.sort_by_key(|monitor| (!monitor.primary, monitor.rect.left, monitor.rect.top));
+        monitors
+    }
+
+    pub fn displays() -> Result<Vec<Display>, CaptureError> {
+        Ok(monitors()
+            .into_iter()
+            .map(|monitor| {
+                let (width, height) = (monitor.rect.right - monitor.rect.left, monitor.rect.bottom - monitor.rect.top);
+                Display {
+                    id: monitor.name.clone(),
+                    name: monitor.name,
+                    width: width as u32,
+                    height: height as u32,
+                    is_primary: monitor.primary,
+                    scale_factor: monitor.scale,
+                    bounds: Bounds {
+                        x: monitor.rect.left as f64,
+                        y: monitor.rect.top as f64,
+                        width: width as f64,
+                        height: height as f64,
+                    },
+                }
+            })
+            .collect())
+    }
+
+    pub fn capture(index: usize) -> Result<Frame, CaptureError> {
+        let monitors = monitors();
+        let rect = monitors
+            .get(index)
+            .map(|monitor| monitor.rect)
+            .ok_or_else(|| CaptureError::Failed(format!("No display {} ({} active)", index, monitors.len())))?;
+        let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+
+        let mut bgra = vec![0u8; width as usize * height as usize * 4];
+        // SAFETY: every handle is checked and released below; GetDIBits writes
+        // at most height rows of width 32-bit pixels into bgra
+        let copied = unsafe {
+            let screen = GetDC(null_mut());
+            if screen.is_null() {
+                return Err(CaptureError::Failed("Failed to open the screen".to_string()));
+            }
+            let memory = CreateCompatibleDC(screen);
+            let bitmap = CreateCompatibleBitmap(screen, width, height);
+            let previous = SelectObject(memory, bitmap);
+            // CAPTUREBLT includes layered (translucent) windows
+            let blitted = BitBlt(memory, 0, 0, width, height, screen, rect.left, rect.top, SRCCOPY | CAPTUREBLT) != 0;
+            SelectObject(memory, previous);
+            let mut info: BITMAPINFO = std::mem::zeroed();
+            info.bmiHeader = BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                // Negative: top-down rows
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB,
+                ..std::mem::zeroed()
+            };
+            let rows = if blitted {
+                GetDIBits(memory, bitmap, 0, height as u32, bgra.as_mut_ptr().cast(), &mut info, DIB_RGB_COLORS)
+            } else {
+                0
+            };
+            DeleteObject(bitmap);
+            DeleteDC(memory);
+            ReleaseDC(null_mut(), screen);
+            rows == height
+        };
+        if !copied {
+            return Err(CaptureError::Failed(format!("Failed to capture display {}", index)));
+        }
+
+        let mut point = POINT { x: 0, y: 0 };
+        // SAFETY: writes one POINT
+        let cursor = (unsafe { GetCursorPos(&mut point) } != 0)
+            .then_some((point.x, point.y))
+            .filter(|(x, y)| *x >= rect.left && *x < rect.right && *y >= rect.top && *y < rect.bottom)
+            .map(|(x, y)| ((x - rect.left) as i64, (y - rect.top) as i64));
+        let rgba = super::bgra_to_rgba(&bgra, width as usize, height as usize, width as usize * 4);
+        let placement = Placement { x: rect.left as f64, y: rect.top as f64, scale: 1.0 };
+        Ok(Frame { width: width as u32, height: height as u32, rgba, cursor, placement: Some(placement) })
+    }
+
+    pub fn recording_frame(index: usize) -> Result<Frame, CaptureError> {
+        capture(index)
+    }
+}
+
+// The screenshot portal first: it's the only way in under Wayland, and asks
+// the user the first time. The portal shoots the whole desktop, so X11
+// geometry (XWayland included) picks one display out of it. Without a portal,
+// X11 captures directly.
+#[cfg(not(any(target_os = "macos", windows)))]
+mod xdg {
+    use ashpd::desktop::screenshot::Screenshot;
+    use ashpd::desktop::ResponseError;
+
+    use super::{Bounds, CaptureError, Display, Frame};
+
+    pub fn displays() -> Result<Vec<Display>, CaptureError> {
+        let (screens, _) = x11::screens().map_err(CaptureError::Failed)?;
+        Ok(screens
+            .iter()
+            .enumerate()
+            .map(|(index, screen)| Display {
+                id: index.to_string(),
+                name: format!("Screen {}", index + 1),
+                width: screen.width,
+                height: screen.height,
+                is_primary: index == 0,
+                scale_factor: 1.0,
+                bounds: Bounds {
+                    x: screen.x as f64,
+                    y: screen.y as f64,
+                    width: screen.width as f64,
+                    height: screen.height as f64,
+                },
+            })
+            .collect())
+    }
+
+    pub fn capture(index: usize) -> Result<Frame, CaptureError> {
+        let geometry = x11::screens().ok();
+        let desktop = match portal() {
+            Ok(desktop) => desktop,
+            Err(Portal::Refused) => {
+                return Err(CaptureError::PermissionDenied(
+                    "The screenshot request was declined; allow OhFixIt Desktop Helper to take screenshots when the desktop asks".to_string(),
+                ))
+            }
+            Err(Portal::Unavailable(e)) => {
+                log::info!("Screenshot portal unavailable ({}); capturing through X11", e);
+                return x11::capture(index).map_err(CaptureError::Failed);
+            }
+        };
+
+        let (width, height) = desktop.dimensions();
+        // Only when the portal image is the X desktop, not scaled, do X
+        // coordinates (screens, pointer, windows) apply to it
+        let mapped = matches!(&geometry, Some((_, root)) if *root == (width, height));
+        let screen = match &geometry {
+            Some((screens, _)) if mapped => screens.get(index).copied(),
+            _ if index == 0 => Some(x11::Screen { x: 0, y: 0, width, height }),
+            _ => None,
+        }
+        .ok_or_else(|| CaptureError::Failed(format!("No display {} on this desktop", index)))?;
+        let image = image::imageops::crop_imm(&desktop, screen.x as u32, screen.y as u32, screen.width, screen.height).to_image();
+        let cursor = if mapped { x11::pointer(&screen) } else { None };
+        let placement = mapped.then(|| x11::placement(&screen));
+        Ok(Frame { width: image.width(), height: image.height(), rgba: image.into_raw(), cursor, placement })
+    }
+
+    // The portal takes one screenshot per request (and may ask every time),
+    // so recordings capture through X11, which sees nothing under Wayland
+    pub fn recording_frame(index: usize) -> Result<Frame, CaptureError> {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            return Err(CaptureError::Failed("Screen recording isn't supported under Wayland".to_string()));
+        }
+        x11::capture(index).map_err(CaptureError::Failed)
+    }
+
+    enum Portal {
+        // The user declined, or the desktop denied it
+        Refused,
+        Unavailable(String),
+    }
+
+    fn portal() -> Result<image::RgbaImage, Portal> {
+        let response = tauri::async_runtime::block_on(async {
+            Screenshot::request().interactive(false).modal(false).send().await?.response()
+        });
+        let uri = match response {
+            Ok(screenshot) => screenshot.uri().clone(),
+            Err(ashpd::Error::Response(ResponseError::Cancelled)) => return Err(Portal::Refused),
+            Err(e) => return Err(Portal::Unavailable(e.to_string())),
+        };
+        let path = uri.to_file_path().map_err(|_| Portal::Unavailable(format!("Unexpected screenshot location {}", uri)))?;
+        let image = image::open(&path).map(|image| image.to_rgba8());
+        // The portal saves into the user's pictures; the capture shouldn't stay there
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::error!("Failed to remove portal screenshot {}: {}", path.display(), e);
+        }
+        image.map_err(|e| Portal::Unavailable(format!("Failed to read the portal screenshot: {}", e)))
+    }
+
+    // Xlib, loaded at runtime so a Wayland-only desktop without libX11 still
+    // gets the portal
+    mod x11 {
+        use std::os::raw::{c_int, c_uint, c_ulong};
+        use std::ptr::null;
+
+        use x11_dl::xinerama;
+        use x11_dl::xlib::{self, Xlib};
+
+        use crate::screenshot::Frame;
+        use crate::sensitive_windows::Placement;
+
+        const ALL_PLANES: c_ulong = !0;
+
+        #[derive(Debug, Clone, Copy)]
+        pub struct Screen {
+            pub x: i32,
+            pub y: i32,
+            pub width: u32,
+            pub height: u32,
+        }
+
+        fn with_display<T>(f: impl FnOnce(&Xlib, *mut xlib::Display) -> Result<T, String>) -> Result<T, String> {
+            let xlib = Xlib::open().map_err(|e| format!("X11 unavailable: {}", e))?;
+            // SAFETY: the display is closed below and not used after
+            unsafe {
+                let display = (xlib.XOpenDisplay)(null());
+                if display.is_null() {
+                    return Err("No X11 display to capture".to_string());
+                }
+                let result = f(&xlib, display);
+                (xlib.XCloseDisplay)(display);
+                result
+            }
+        }
+
+        // Xinerama screens (or the one root screen) and the root size
+        pub fn screens() -> Result<(Vec<Screen>, (u32, u32)), String> {
+            with_display(|xlib, display| unsafe {
+                let default = (xlib.XDefaultScreen)(display);
+                let root = ((xlib.XDisplayWidth)(display, default) as u32, (xlib.XDisplayHeight)(display, default) as u32);
+                let mut screens = Vec::new();
+                if let Ok(xinerama) = xinerama::Xlib::open() {
+                    if (xinerama.XineramaIsActive)(display) != 0 {
+                        let mut count: c_int = 0;
+                        let info = (xinerama.XineramaQueryScreens)(display, &mut count);
+                        if !info.is_null() {
+                            for screen in std::slice::from_raw_parts(info, count.max(0) as usize) {
+                                screens.push(Screen {
+                                    x: screen.x_org as i32,
+                                    y: screen.y_org as i32,
+                                    width: screen.width as u32,
+                                    height: screen.height as u32,
+                                });
+                            }
+                            (xlib.XFree)(info.cast());
+                        }
+                    }
+                }
+                if screens.is_empty() {
+                    screens.push(Screen { x: 0, y: 0, width: root.0, height: root.1 });
+                }
+                Ok((screens, root))
+            })
+        }
+
+        // The pointer relative to `screen`, when it's on it
+        pub fn pointer(screen: &Screen) -> Option<(i64, i64)> {
+            with_display(|xlib, display| unsafe { Ok(query_pointer(xlib, display)) })
+                .ok()
+                .flatten()
+                .filter(|(x, y)| {
+                    *x >= screen.x && *y >= screen.y && *x < screen.x + screen.width as i32 && *y < screen.y + screen.height as i32
+                })
+                .map(|(x, y)| ((x - screen.x) as i64, (y - screen.y) as i64))
+        }
+
+        unsafe fn query_pointer(xlib: &Xlib, display: *mut xlib::Display) -> Option<(i32, i32)> {
+            let root = (xlib.XDefaultRootWindow)(display);
+            let (mut root_return, mut child) = (0, 0);
+            let (mut x, mut y, mut window_x, mut window_y, mut mask): (c_int, c_int, c_int, c_int, c_uint) = (0, 0, 0, 0, 0);
+            let found = (xlib.XQueryPointer)(
+                display, root, &mut root_return, &mut child, &mut x, &mut y, &mut window_x, &mut window_y, &mut mask,
+            );
+            (found != 0).then_some((x, y))
+        }
+
+        pub fn capture(index: usize) -> Result<Frame, String> {
+            let (screens, _) = screens()?;
+            let screen = *screens.get(index).ok_or_else(|| format!("No display {} ({} active)", index, screens.len()))?;
+            let rgba = with_display(|xlib, display| unsafe {
+                let root = (xlib.XDefaultRootWindow)(display);
+                let image = (xlib.XGetImage)(
+                    display, root, screen.x, screen.y, screen.width, screen.height, ALL_PLANES, xlib::ZPixmap,
+                );
+                if image.is_null() {
+                    return Err(format!("Failed to capture display {}", index));
+                }
+                let (bits, stride) = ((*image).bits_per_pixel, (*image).bytes_per_line as usize);
+                let rgba = (bits == 32).then(|| {
+                    let bytes = std::slice::from_raw_parts((*image).data as *const u8, stride * screen.height as usize);
+                    crate::screenshot::bgra_to_rgba(bytes, screen.width as usize, screen.height as usize, stride)
+                });
+                (xlib.XDestroyImage)(image);
+                rgba.ok_or_else(|| format!("Unexpected {}-bit X11 image", bits))
+            })?;
+            let cursor = pointer(&screen);
+            Ok(Frame { width: screen.width, height: screen.height, rgba, cursor, placement: Some(placement(&screen)) })
+        }
+
+        pub fn placement(screen: &Screen) -> Placement {
+            Placement { x: screen.x as f64, y: screen.y as f64, scale: 1.0 }
+        }
+    }
+}
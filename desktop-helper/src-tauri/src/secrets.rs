@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::{audit, redact};
+
+// Last check on everything a report uploads. Output is scanned for secrets
+// before redaction, so the audit log can say what was withheld (the kind and
+// count, never the value). OHFIXIT_SECRETS_POLICY=block withholds any artifact
+// with a finding outright; the default redacts the findings and uploads the
+// rest.
+const REDACTED: &str = "[REDACTED]";
+
+struct Rule {
+    kind: &'static str,
+    // Replaced entirely, or only its "value" capture group when it has one
+    pattern: Regex,
+}
+
+fn rules() -> &'static [Rule] {
+    static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        let rule = |kind, pattern| Rule { kind, pattern: Regex::new(pattern).unwrap() };
+        vec![
+            // Also a block cut short by output truncation
+            rule("private_key", r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?(?:-----END [A-Z ]*PRIVATE KEY-----|\z)"),
+            rule("aws_access_key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+            rule(
+                "aws_secret_key",
+                r#"(?i)\baws_?secret_?(?:access_?)?key"?\s*[:=]\s*"?(?P<value>[A-Za-z0-9/+=]{40})"#,
+            ),
+            rule("oauth_token", r"\bya29\.[0-9A-Za-z_-]{20,}"),
+            rule("oauth_token", r"\b(?:ghp|gho|ghu|ghs|github_pat)_[A-Za-z0-9_]{16,}"),
+            rule("oauth_token", r"\bxox[abpr]-[A-Za-z0-9-]{10,}"),
+            rule("oauth_token", r"(?i)\bbearer\s+(?P<value>[A-Za-z0-9\-._~+/]{16,}=*)"),
+            rule(
+                "oauth_token",
+                r#"(?i)\b(?:access|refresh|id)_token"?\s*[:=]\s*"?(?P<value>[A-Za-z0-9\-._~+/]{16,}=*)"#,
+            ),
+            // A prompt with what was typed at it echoed on the same line
+            rule(
+                "password_prompt",
+                r"(?im)^[^\n]*\b(?:password|passphrase|passcode)(?: for [^:\n]+)?:[ \t]*(?P<value>[^\s\n][^\n]*)$",
+            ),
+        ]
+    })
+}
+
+pub enum Policy {
+    Redact,
+    Block,
+}
+
+pub fn policy() -> Policy {
+    match std::env::var("OHFIXIT_SECRETS_POLICY").unwrap_or_default().to_lowercase().as_str() {
+        "block" => Policy::Block,
+        _ => Policy::Redact,
+    }
+}
+
+// Secrets found in `text`, counted by kind
+pub fn scan(text: &str) -> BTreeMap<&'static str, usize> {
+    let mut findings = BTreeMap::new();
+    for rule in rules() {
+        let count = rule.pattern.find_iter(text).count();
+        if count > 0 {
+            *findings.entry(rule.kind).or_insert(0) += count;
+        }
+    }
+    findings
+}
+
+// Replace whatever the scanner still finds after redact::redact
+fn scrub(text: &str) -> (String, usize) {
+    let mut count = 0;
+    let mut text = text.to_string();
+    for rule in rules() {
+        text = rule
+            .pattern
+            .replace_all(&text, |caps: &regex::Captures| {
+                let whole = &caps[0];
+                match caps.name("value") {
+                    Some(value) if value.as_str() == REDACTED => whole.to_string(),
+                    Some(value) => {
+                        count += 1;
+                        let start = value.start() - caps.get(0).unwrap().start();
+                        format!("{}{}{}", &whole[..start], REDACTED, &whole[start + value.len()..])
+                    }
+                    None => {
+                        count += 1;
+                        REDACTED.to_string()
+                    }
+                }
+            })
+            .into_owned();
+    }
+    (text, count)
+}
+
+// Screens the artifacts of one report and remembers what it withheld
+pub struct Screen {
+    action_id: String,
+    policy: Policy,
+    withheld: Vec<serde_json::Value>,
+}
+
+impl Screen {
+    pub fn new(action_id: &str) -> Self {
+        Screen { action_id: action_id.to_string(), policy: policy(), withheld: Vec::new() }
+    }
+
+    // For artifacts shown in the helper window: redacted, never blocked, and
+    // not audited since nothing leaves the machine
+    pub fn local() -> Self {
+        Screen { action_id: String::new(), policy: Policy::Redact, withheld: Vec::new() }
+    }
+
+    // The text to upload as `artifact` and how many values were redacted from
+    // it; None when the policy blocks it
+    pub fn text(&mut self, artifact: &str, text: &str) -> Option<(String, usize)> {
+        let findings = scan(text);
+        let blocked = !findings.is_empty() && matches!(self.policy, Policy::Block);
+        if !findings.is_empty() {
+            self.withheld.push(serde_json::json!({
+                "artifact": artifact,
+                "findings": findings,
+                "blocked": blocked,
+            }));
+        }
+        if blocked {
+            return None;
+        }
+        let (text, redactions) = redact::redact(text);
+        let (text, scrubbed) = scrub(&text);
+        Some((text, redactions + scrubbed))
+    }
+
+    // Audit what was withheld, once per report
+    pub fn finish(self) {
+        if self.withheld.is_empty() {
+            return;
+        }
+        let policy = match self.policy {
+            Policy::Redact => "redact",
+            Policy::Block => "block",
+        };
+        log::info!("Withheld secrets from {} report ({}): {}", self.action_id, policy, serde_json::Value::from(self.withheld.clone()));
+        audit::record("secrets_withheld", serde_json::json!({
+            "action_id": self.action_id,
+            "policy": policy,
+            "artifacts": self.withheld,
+        }));
+    }
+}
+
+// What a report carries in place of a blocked artifact
+pub fn blocked_notice(artifact: &str) -> String {
+    format!("[WITHHELD: the {} contained secrets and OHFIXIT_SECRETS_POLICY=block]", artifact)
+}
@@ -0,0 +1,409 @@
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+
+#[cfg(target_os = "macos")]
+use quartz as platform;
+#[cfg(not(any(target_os = "macos", windows)))]
+use xlib as platform;
+#[cfg(windows)]
+use win32 as platform;
+
+// Screenshots leave the machine, so windows of password managers, banking
+// apps and terminals that look like they're showing secrets are blacked out
+// before encoding. Names are matched case-insensitively against the app name,
+// its bundle id / executable / WM_CLASS, and (for banking and terminals) the
+// window title. OHFIXIT_SENSITIVE_APPS adds comma-separated names that are
+// always blacked out.
+const PASSWORD_MANAGERS: &[&str] = &[
+    "1password",
+    "bitwarden",
+    "keepass",
+    "lastpass",
+    "dashlane",
+    "keeper",
+    "enpass",
+    "nordpass",
+    "roboform",
+    "proton pass",
+    "keychain access",
+    "com.apple.passwords",
+    "seahorse",
+];
+const BANKING: &[&str] = &["bank", "paypal", "venmo", "revolut", "coinbase"];
+const TERMINALS: &[&str] = &[
+    "terminal",
+    "iterm",
+    "warp",
+    "alacritty",
+    "kitty",
+    "wezterm",
+    "konsole",
+    "xterm",
+    "tilix",
+    "cmd.exe",
+    "powershell",
+    "pwsh",
+    "conhost",
+];
+// A terminal title with one of these is likely showing or asking for a secret
+const SECRET_HINTS: &[&str] = &[
+    "password",
+    "passwd",
+    "passphrase",
+    "secret",
+    "token",
+    "sudo",
+    "ssh",
+    "gpg",
+    "vault",
+    ".env",
+    "credential",
+    "private key",
+    "aws",
+];
+
+// An on-screen window, in the desktop coordinates the platform lists it in
+pub struct Window {
+    pub app: String,
+    // Bundle id, executable path or WM_CLASS
+    pub identity: String,
+    pub title: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+// Where a captured display sits on the desktop: its origin in window
+// coordinates, and image pixels per window unit
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    pub x: f64,
+    pub y: f64,
+    pub scale: f64,
+}
+
+// A blacked-out area, in the captured display's pixels. The title stays out:
+// it can be as sensitive as the window.
+#[derive(Debug, Serialize, Clone)]
+pub struct Redaction {
+    pub app: String,
+    pub reason: &'static str,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn configured() -> Vec<String> {
+    std::env::var("OHFIXIT_SENSITIVE_APPS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+// Why `window` must not appear in a screenshot, if it mustn't
+fn reason(window: &Window, configured: &[String]) -> Option<&'static str> {
+    let app = format!("{} {}", window.app, window.identity).to_lowercase();
+    let title = window.title.to_lowercase();
+    if configured.iter().any(|name| app.contains(name.as_str())) {
+        return Some("configured");
+    }
+    if PASSWORD_MANAGERS.iter().any(|name| app.contains(name)) {
+        return Some("password_manager");
+    }
+    if BANKING.iter().any(|name| app.contains(name) || title.contains(name)) {
+        return Some("banking");
+    }
+    if TERMINALS.iter().any(|name| app.contains(name)) && SECRET_HINTS.iter().any(|hint| title.contains(hint)) {
+        return Some("terminal_secrets");
+    }
+    None
+}
+
+// Black out every sensitive window on `image` (a display at `placement`).
+// Err when windows can't be listed on this desktop.
+pub fn redact(image: &mut RgbaImage, placement: Placement) -> Result<Vec<Redaction>, String> {
+    let configured = configured();
+    let mut redactions = Vec::new();
+    for window in platform::windows()? {
+        let Some(reason) = reason(&window, &configured) else { continue };
+        let left = ((window.x - placement.x) * placement.scale).max(0.0);
+        let top = ((window.y - placement.y) * placement.scale).max(0.0);
+        let right = ((window.x + window.width - placement.x) * placement.scale).min(image.width() as f64);
+        let bottom = ((window.y + window.height - placement.y) * placement.scale).min(image.height() as f64);
+        if right <= left || bottom <= top {
+            continue;
+        }
+        let (x, y) = (left.floor() as u32, top.floor() as u32);
+        let (width, height) = (right.ceil() as u32 - x, bottom.ceil() as u32 - y);
+        for py in y..y + height {
+            for px in x..x + width {
+                image.put_pixel(px, py, Rgba([0, 0, 0, 255]));
+            }
+        }
+        redactions.push(Redaction { app: window.app, reason, x, y, width, height });
+    }
+    Ok(redactions)
+}
+
+// Quartz window list: normal (layer 0) on-screen windows, in points. Bundle
+// ids come from each owner's .app.
+#[cfg(target_os = "macos")]
+mod quartz {
+    use std::collections::BTreeMap;
+
+    use core_foundation::base::{CFType, TCFType};
+    use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+    use core_foundation::number::CFNumber;
+    use core_foundation::string::CFString;
+    use core_graphics::geometry::CGRect;
+    use core_graphics::window::{
+        copy_window_info, kCGNullWindowID, kCGWindowBounds, kCGWindowLayer, kCGWindowListExcludeDesktopElements,
+        kCGWindowListOptionOnScreenOnly, kCGWindowName, kCGWindowOwnerName, kCGWindowOwnerPID,
+    };
+
+    use super::Window;
+    use crate::cmd::read_output;
+
+    pub fn windows() -> Result<Vec<Window>, String> {
+        let list = copy_window_info(kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements, kCGNullWindowID)
+            .ok_or("Failed to list on-screen windows")?;
+        let mut found = Vec::new();
+        for item in list.iter() {
+            // SAFETY: CGWindowListCopyWindowInfo returns an array of dictionaries
+            let info: CFDictionary<CFString, CFType> =
+                unsafe { CFDictionary::wrap_under_get_rule(*item as CFDictionaryRef) };
+            // SAFETY: the key constants are immutable CFStrings
+            let get = |key| info.find(unsafe { CFString::wrap_under_get_rule(key) }).map(|value| value.clone());
+            let number = |key| get(key).and_then(|v| v.downcast::<CFNumber>()).and_then(|n| n.to_i64());
+            let text = |key| get(key).and_then(|v| v.downcast::<CFString>()).map(|s| s.to_string()).unwrap_or_default();
+            if number(unsafe { kCGWindowLayer }) != Some(0) {
+                continue;
+            }
+            let Some(bounds) = get(unsafe { kCGWindowBounds })
+                .and_then(|v| v.downcast::<CFDictionary>())
+                .and_then(|dict| CGRect::from_dict_representation(&dict))
+            else {
+                continue;
+            };
+            found.push((
+                number(unsafe { kCGWindowOwnerPID }).unwrap_or_default(),
+                Window {
+                    app: text(unsafe { kCGWindowOwnerName }),
+                    identity: String::new(),
+                    // Only present with the Screen Recording permission, which capture needs anyway
+                    title: text(unsafe { kCGWindowName }),
+                    x: bounds.origin.x,
+                    y: bounds.origin.y,
+                    width: bounds.size.width,
+                    height: bounds.size.height,
+                },
+            ));
+        }
+
+        let identities = bundle_ids(found.iter().map(|(pid, _)| *pid));
+        Ok(found
+            .into_iter()
+            .map(|(pid, mut window)| {
+                window.identity = identities.get(&pid).cloned().unwrap_or_default();
+                window
+            })
+            .collect())
+    }
+
+    // pid -> "<bundle id> <executable>", with one ps for all of them
+    fn bundle_ids(pids: impl Iterator<Item = i64>) -> BTreeMap<i64, String> {
+        let pids: Vec<String> = pids.map(|pid| pid.to_string()).collect();
+        let Some(listing) = read_output("/bin/ps", &["-o", "pid=,comm=", "-p", &pids.join(",")]) else {
+            return BTreeMap::new();
+        };
+        let mut bundles: BTreeMap<String, String> = BTreeMap::new();
+        listing
+            .lines()
+            .filter_map(|line| {
+                let (pid, executable) = line.trim().split_once(' ')?;
+                let executable = executable.trim();
+                let bundle = executable.find(".app/").map(|end| &executable[..end + 4]);
+                let id = bundle
+                    .map(|bundle| {
+                        bundles
+                            .entry(bundle.to_string())
+                            .or_insert_with(|| {
+                                read_output("/usr/bin/defaults", &["read", &format!("{}/Contents/Info", bundle), "CFBundleIdentifier"])
+                                    .unwrap_or_default()
+                                    .trim()
+                                    .to_string()
+                            })
+                            .clone()
+                    })
+                    .unwrap_or_default();
+                Some((pid.parse().ok()?, format!("{} {}", id, executable)))
+            })
+            .collect()
+    }
+}
+
+// Top-level visible windows with their executables, in physical pixels
+#[cfg(windows)]
+mod win32 {
+    use windows_sys::Win32::Foundation::{CloseHandle, BOOL, HWND, LPARAM, RECT};
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindowRect, GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindowVisible,
+    };
+
+    use super::Window;
+
+    unsafe extern "system" fn collect(hwnd: HWND, data: LPARAM) -> BOOL {
+        let windows = &mut *(data as *mut Vec<Window>);
+        if IsWindowVisible(hwnd) == 0 || IsIconic(hwnd) != 0 {
+            return 1;
+        }
+        let mut rect: RECT = std::mem::zeroed();
+        if GetWindowRect(hwnd, &mut rect) == 0 || rect.right <= rect.left || rect.bottom <= rect.top {
+            return 1;
+        }
+        let mut title = [0u16; 512];
+        let length = GetWindowTextW(hwnd, title.as_mut_ptr(), title.len() as i32).max(0) as usize;
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, &mut pid);
+        let executable = executable(pid).unwrap_or_default();
+        let app = executable.rsplit('\\').next().unwrap_or_default().to_string();
+        windows.push(Window {
+            app,
+            identity: executable,
+            title: String::from_utf16_lossy(&title[..length]),
+            x: rect.left as f64,
+            y: rect.top as f64,
+            width: (rect.right - rect.left) as f64,
+            height: (rect.bottom - rect.top) as f64,
+        });
+        1
+    }
+
+    unsafe fn executable(pid: u32) -> Option<String> {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return None;
+        }
+        let mut path = [0u16; 1024];
+        let mut size = path.len() as u32;
+        let ok = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, path.as_mut_ptr(), &mut size) != 0;
+        CloseHandle(process);
+        ok.then(|| String::from_utf16_lossy(&path[..size as usize]))
+    }
+
+    pub fn windows() -> Result<Vec<Window>, String> {
+        let mut windows: Vec<Window> = Vec::new();
+        // SAFETY: the callback only runs during the call, with our pointer
+        if unsafe { EnumWindows(Some(collect), &mut windows as *mut _ as LPARAM) } == 0 {
+            return Err("Failed to list windows".to_string());
+        }
+        Ok(windows)
+    }
+}
+
+// X11 client windows (XWayland's too), in root pixels. A Wayland session
+// doesn't let other apps see its windows, so there nothing can be checked.
+#[cfg(not(any(target_os = "macos", windows)))]
+mod xlib {
+    use std::ffi::CStr;
+    use std::os::raw::{c_int, c_uchar, c_ulong};
+    use std::ptr::{null, null_mut};
+
+    use x11_dl::xlib::{self, Xlib};
+
+    use super::Window;
+
+    pub fn windows() -> Result<Vec<Window>, String> {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            return Err("Wayland doesn't let other apps see its windows".to_string());
+        }
+        let xlib = Xlib::open().map_err(|e| format!("X11 unavailable: {}", e))?;
+        // SAFETY: the display is closed below and not used after; every
+        // property and hint Xlib allocates is freed
+        unsafe {
+            let display = (xlib.XOpenDisplay)(null());
+            if display.is_null() {
+                return Err("No X11 display".to_string());
+            }
+            let windows = clients(&xlib, display);
+            (xlib.XCloseDisplay)(display);
+            windows
+        }
+    }
+
+    unsafe fn clients(xlib: &Xlib, display: *mut xlib::Display) -> Result<Vec<Window>, String> {
+        let root = (xlib.XDefaultRootWindow)(display);
+        let ids: Vec<c_ulong> = property(xlib, display, root, "_NET_CLIENT_LIST")
+            .ok_or("The window manager doesn't list its windows")?
+            .chunks_exact(std::mem::size_of::<c_ulong>())
+            .map(|bytes| c_ulong::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect();
+        let mut windows = Vec::new();
+        for id in ids {
+            let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+            if (xlib.XGetWindowAttributes)(display, id, &mut attributes) == 0 || attributes.map_state != xlib::IsViewable {
+                continue;
+            }
+            let (mut x, mut y, mut child) = (0, 0, 0);
+            (xlib.XTranslateCoordinates)(display, id, root, 0, 0, &mut x, &mut y, &mut child);
+
+            let mut hint: xlib::XClassHint = std::mem::zeroed();
+            let mut class = String::new();
+            if (xlib.XGetClassHint)(display, id, &mut hint) != 0 {
+                for part in [hint.res_name, hint.res_class] {
+                    if !part.is_null() {
+                        class.push_str(&CStr::from_ptr(part).to_string_lossy());
+                        class.push(' ');
+                        (xlib.XFree)(part.cast());
+                    }
+                }
+            }
+            let title = property(xlib, display, id, "_NET_WM_NAME")
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                .unwrap_or_default();
+            windows.push(Window {
+                app: class.split_whitespace().last().unwrap_or_default().to_string(),
+                identity: class.trim().to_string(),
+                title,
+                x: x as f64,
+                y: y as f64,
+                width: attributes.width as f64,
+                height: attributes.height as f64,
+            });
+        }
+        Ok(windows)
+    }
+
+    // A window property's raw bytes (32-bit items come back as longs)
+    unsafe fn property(xlib: &Xlib, display: *mut xlib::Display, window: c_ulong, name: &str) -> Option<Vec<u8>> {
+        let name = std::ffi::CString::new(name).ok()?;
+        let atom = (xlib.XInternAtom)(display, name.as_ptr(), xlib::True);
+        if atom == 0 {
+            return None;
+        }
+        let (mut actual_type, mut format, mut items, mut remaining): (c_ulong, c_int, c_ulong, c_ulong) = (0, 0, 0, 0);
+        let mut data: *mut c_uchar = null_mut();
+        let status = (xlib.XGetWindowProperty)(
+            display, window, atom, 0, 1 << 16, xlib::False, xlib::AnyPropertyType as c_ulong,
+            &mut actual_type, &mut format, &mut items, &mut remaining, &mut data,
+        );
+        if status != xlib::Success as c_int || data.is_null() {
+            return None;
+        }
+        let item_size = match format {
+            32 => std::mem::size_of::<c_ulong>(),
+            16 => 2,
+            _ => 1,
+        };
+        let bytes = std::slice::from_raw_parts(data, items as usize * item_size).to_vec();
+        (xlib.XFree)(data.cast());
+        Some(bytes)
+    }
+}
@@ -0,0 +1,234 @@
+use axum::extract::State;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use tauri::{AppHandle, Emitter};
+
+use crate::{actions, apps, audit, auth, automation, batch, benchmark, browser_extensions, browsers, crashes, dns, freeze, guided, health, hogs, instance, licenses, local_tls, lockout, logs, net, pairing, pipeline, policy, privileges, probes, processes, proxy, queries, rate_limit, rollback, scheduler, screenrecord, screenshot, simulation, sockets, speedtest, startup, timeline, transcript, transport, updates, vpn, wifi};
+
+// Feature areas this helper supports, advertised on /status
+pub const CAPABILITIES: &[&str] = &[
+    "actions",
+    "rollback",
+    "config_snapshots",
+    "timeline",
+    "updates",
+    "licenses",
+    "guided",
+    "probes",
+    "scheduler",
+    "simulation",
+    "batch",
+    "diagnostic_queries",
+    "transcripts",
+    "benchmark",
+    "abort_all",
+    "metrics",
+    "health",
+    "pairing",
+    "execution_events",
+    "rate_limits",
+    "audit_chain",
+    "deep_links",
+    "privileges",
+    "token_scopes",
+    "attestation",
+    "local_tls",
+    "token_lockout",
+    "freeze",
+    "screenshot",
+    "screenrecord",
+    "net_ping",
+    "net_dns",
+    "net_traceroute",
+    "net_speedtest",
+    "net_wifi",
+    "net_proxy",
+    "net_vpn",
+    "health_battery",
+    "health_disk_smart",
+    "health_thermals",
+    "process_list",
+    "health_hogs",
+    "net_sockets",
+    "health_startup_items",
+    "health_apps",
+    "health_browsers",
+    "health_browser_extensions",
+    "health_crashes",
+    "health_panics",
+    "health_logs_query",
+    "health_disks",
+    "health_cpu",
+    "health_gpu",
+];
+
+// Port the web app expects the helper on (see lib/ohfixit/helper-endpoint.ts)
+pub const STATUS_PORT: u16 = 8765;
+// Tried in order when the status port is taken and can't be reclaimed, before
+// an ephemeral port (see instance::bind)
+pub const FALLBACK_PORTS: &[u16] = &[8766, 8767];
+
+// OHFIXIT_STATUS_PORT overrides STATUS_PORT
+pub fn status_port() -> u16 {
+    std::env::var("OHFIXIT_STATUS_PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .filter(|port| *port != 0)
+        .unwrap_or(STATUS_PORT)
+}
+
+// Local HTTP API the OhFixIt web app talks to while the helper is running
+pub fn spawn_status_server(app: AppHandle) {
+    let started = std::time::Instant::now();
+    tauri::async_runtime::spawn(async move {
+        let app_handle = app.clone();
+        let router = Router::new()
+            .route("/status", get(status_handler))
+            .route("/metrics", get(startup::metrics_handler))
+            .route("/shutdown", post(instance::shutdown_handler))
+            .route("/instance/activate", post(instance::activate_handler))
+            .route("/actions", get(actions::actions_handler))
+            .route("/capabilities/privileges", get(privileges::privileges_handler))
+            .route("/timeline", get(timeline::timeline_handler))
+            .route("/updates", get(updates::updates_handler))
+            .route("/scheduler", get(scheduler::scheduler_handler))
+            .route("/simulation", get(simulation::simulation_handler))
+            .route("/rollback-points", get(rollback::rollback_points_handler))
+            .route("/executions/{id}", get(pipeline::execution_events_handler))
+            .route("/audit/export", get(audit::export_handler))
+            .route("/health/licenses", get(licenses::licenses_handler))
+            .route("/health/firewall", get(health::firewall_handler))
+            .route("/health/antivirus", get(health::antivirus_handler))
+            .route("/health/filevault", get(health::filevault_handler))
+            .route("/health/time-machine", get(health::time_machine_handler))
+            .route("/health/sip", get(health::sip_handler))
+            .route("/health/battery", get(health::battery_handler))
+            .route("/health/disks", get(health::disks_handler))
+            .route("/health/disks/smart", get(health::smart_handler))
+            .route("/health/thermals", get(health::thermals_handler))
+            .route("/health/cpu", get(health::cpu_handler))
+            .route("/health/gpu", get(health::gpu_handler))
+            .route("/health/hogs", get(hogs::hogs_handler))
+            .route("/health/startup-items", get(health::startup_items_handler))
+            .route("/health/apps", get(apps::apps_handler))
+            .route("/health/browsers", get(browsers::browsers_handler))
+            .route("/health/browser-extensions", get(browser_extensions::extensions_handler))
+            .route("/health/crashes", get(crashes::crashes_handler))
+            .route("/health/panics", get(health::panics_handler))
+            .route("/health/logs/query", post(logs::logs_query_handler))
+            .route("/health/all", get(health::all_handler))
+            .route("/automation/execute", post(automation::automation_execute_handler))
+            .route("/automation/abort-all", post(automation::abort_all_handler))
+            .route("/automation/freeze", post(freeze::freeze_handler))
+            .route("/automation/execute-batch", post(batch::execute_batch_handler))
+            .route("/probes/run", post(probes::run_probes_handler))
+            .route("/diagnostics/query", post(queries::query_handler))
+            .route("/processes", get(processes::processes_handler))
+            .route("/net/ping", get(net::ping_handler))
+            .route("/net/dns", get(dns::dns_handler))
+            .route("/net/traceroute", get(net::traceroute_handler))
+            .route("/net/speedtest", post(speedtest::speedtest_handler))
+            .route("/net/wifi", get(wifi::wifi_handler))
+            .route("/net/proxy", get(proxy::proxy_handler))
+            .route("/net/vpn", get(vpn::vpn_handler))
+            .route("/net/sockets", get(sockets::sockets_handler))
+            .route("/screenshot", post(screenshot::screenshot_handler))
+            .route("/displays", get(screenshot::displays_handler))
+            .route("/screenrecord", post(screenrecord::screenrecord_handler))
+            .route("/benchmark", get(benchmark::last_benchmark_handler).post(benchmark::run_benchmark_handler))
+            .route("/transcripts/{chat_id}", get(transcript::transcript_handler))
+            .route("/guided", post(guided::create_guided_handler))
+            .route("/guided/{id}", get(guided::get_guided_handler))
+            .route("/guided/{id}/verify", post(guided::verify_guided_handler))
+            .route_layer(axum::middleware::from_fn_with_state(app.clone(), policy::enforce))
+            .route_layer(axum::middleware::from_fn(rate_limit::enforce))
+            .layer(axum::middleware::from_fn(policy::check_origin))
+            .layer(policy::cors())
+            .with_state(app);
+
+        let mode = transport::mode();
+        if mode.socket() {
+            let router = router.clone();
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                match transport::serve(router).await {
+                    Err(transport::Stopped::InUse) if !mode.tcp() => {
+                        log::info!("Exiting: another helper instance is already serving {}", transport::address());
+                        app_handle.exit(0);
+                    }
+                    Err(transport::Stopped::InUse) => log::error!("{} is served by another helper", transport::address()),
+                    Err(transport::Stopped::Failed(e)) => log::error!("Local socket server stopped: {}", e),
+                    Ok(()) => {}
+                }
+            });
+        }
+        if !mode.tcp() {
+            startup::record("status_server_bind", started);
+            return;
+        }
+
+        let (listener, port) = match instance::bind().await {
+            instance::Bound::Listening(listener, port) => (listener, port),
+            instance::Bound::AlreadyRunning => {
+                log::info!("Exiting: another helper instance is already running");
+                app_handle.exit(0);
+                return;
+            }
+            instance::Bound::Unavailable => {
+                log::error!("Status server not started: no port available");
+                return;
+            }
+        };
+
+        let tls = match local_tls::enabled().then(local_tls::identity) {
+            Some(Ok(identity)) => match transport::tls(listener, identity) {
+                Ok(listener) => Ok(listener),
+                Err(e) => {
+                    log::error!("Status server not started: {}", e);
+                    return;
+                }
+            },
+            Some(Err(e)) => {
+                log::error!("Serving the local API without TLS: {}", e);
+                Err(listener)
+            }
+            None => Err(listener),
+        };
+        let scheme = if tls.is_ok() { "https" } else { "http" };
+        log::info!("Status server listening on {}://127.0.0.1:{} for {:?}", scheme, port, policy::allowed_origins());
+        instance::publish(port, tls.is_ok());
+        // The web client learns the port from the well-known file or this event
+        let _ = app_handle.emit("status-server", instance::status_server());
+        startup::record("status_server_bind", started);
+        let served = match tls {
+            Ok(listener) => axum::serve(listener, router).await,
+            Err(listener) => axum::serve(listener, router).await,
+        };
+        if let Err(e) = served {
+            log::error!("Status server stopped: {}", e);
+        }
+    });
+}
+
+async fn status_handler(State(app): State<AppHandle>) -> Json<serde_json::Value> {
+    let pairing = pairing::status();
+    Json(serde_json::json!({
+        "status": "ok",
+        "app": instance::APP_NAME,
+        "version": env!("CARGO_PKG_VERSION"),
+        "capabilities": CAPABILITIES,
+        // The web app binds approval tokens to this device once it's paired
+        "device_id": pairing.device_id,
+        "paired": pairing.paired,
+        // Socket path or pipe name when the API is also served there
+        "socket": transport::mode().socket().then(transport::address),
+        "rate_limits": rate_limit::summary(),
+        // Sources refused executions after repeated invalid tokens
+        "lockouts": lockout::summary(),
+        // Set while execution and rollback are refused (see freeze.rs)
+        "frozen": freeze::current(&app),
+        // Scopes approval tokens can carry; each route needs one
+        "token_scopes": auth::SCOPES,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
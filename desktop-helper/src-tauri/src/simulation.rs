@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{rollback, storage, ActionDefinition};
+
+const LOG_FILE: &str = "simulation_log.json";
+const FIXTURES_FILE: &str = "simulation_fixtures.json";
+const MAX_LOG_ENTRIES: usize = 200;
+
+// Canned output per action, overriding the generated one
+// (data_dir/simulation_fixtures.json: {"<action_id>": {"success": false, "output": "..."}})
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Fixture {
+    pub success: bool,
+    pub output: String,
+}
+
+// What a simulated execution would have run
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimulatedRun {
+    pub action_id: String,
+    pub argv: Vec<Vec<String>>,
+    pub elevated: bool,
+    pub success: bool,
+    pub simulated_at: DateTime<Utc>,
+}
+
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+// OHFIXIT_SIMULATE=1 simulates every execution
+pub fn enabled() -> bool {
+    std::env::var("OHFIXIT_SIMULATE")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+// Produce the result an action would plausibly have, without running anything.
+// Placeholders that only exist at run time (like {backup_dir}) stay unexpanded.
+pub fn run(action: &ActionDefinition) -> (bool, String) {
+    let context = rollback::CommandContext::default();
+    let argv: Vec<Vec<String>> = action
+        .commands
+        .iter()
+        .map(|c| crate::command_argv(c, &context))
+        .filter(|argv| !argv.is_empty())
+        .collect();
+
+    let fixtures: HashMap<String, Fixture> = storage::load_json(FIXTURES_FILE);
+    let (success, output) = match fixtures.get(&action.id) {
+        Some(fixture) => (fixture.success, fixture.output.clone()),
+        None => {
+            let mut output = String::new();
+            for command in &argv {
+                output.push_str(&format!("Command: {}\n", command.join(" ")));
+                if let Some(canned) = canned_output(command) {
+                    output.push_str(&format!("Output: {}\n", canned));
+                }
+            }
+            (true, output)
+        }
+    };
+
+    log::info!("Simulated {} ({} command(s))", action.id, argv.len());
+    record(SimulatedRun {
+        action_id: action.id.clone(),
+        argv,
+        elevated: action.elevated,
+        success,
+        simulated_at: Utc::now(),
+    });
+
+    (success, format!("[simulated, nothing was executed]\n{}", output))
+}
+
+// GET /simulation: whether simulation is forced on, and what simulated runs would have executed
+pub async fn simulation_handler() -> Json<serde_json::Value> {
+    let runs: Vec<SimulatedRun> = storage::load_json(LOG_FILE);
+    Json(serde_json::json!({
+        "enabled": enabled(),
+        "runs": runs,
+    }))
+}
+
+fn record(run: SimulatedRun) {
+    let _guard = LOG_LOCK.lock().unwrap();
+    let mut runs: Vec<SimulatedRun> = storage::load_json(LOG_FILE);
+    runs.push(run);
+    if runs.len() > MAX_LOG_ENTRIES {
+        let excess = runs.len() - MAX_LOG_ENTRIES;
+        runs.drain(..excess);
+    }
+    if let Err(e) = storage::save_json(LOG_FILE, &runs) {
+        log::error!("Failed to persist simulation log: {}", e);
+    }
+}
+
+// What the real tools print on success, for commands where output matters
+fn canned_output(argv: &[String]) -> Option<&'static str> {
+    let program = argv.first()?.rsplit(['/', '\\']).next()?;
+    let program = program.strip_suffix(".exe").unwrap_or(program);
+    let first_arg = argv.get(1).map(String::as_str).unwrap_or("");
+    match (program, first_arg) {
+        ("ipconfig", _) => Some("Windows IP Configuration\n\nSuccessfully flushed the DNS Resolver Cache."),
+        ("netsh", "winsock") => Some(
+            "Sucessfully reset the Winsock Catalog.\nYou must restart the computer in order to complete the reset.",
+        ),
+        ("softwareupdate", _) => Some("Software Update Tool\n\nNo updates are available."),
+        ("journalctl", _) => Some("Vacuuming done, freed 0B of archived journals from /var/log/journal."),
+        ("networksetup", "-getairportpower") => Some("Wi-Fi Power (en0): On"),
+        ("brew", _) | ("mas", _) => Some("Already up-to-date."),
+        _ => None,
+    }
+}
@@ -0,0 +1,229 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::cmd::{diagnostic, read_output};
+use crate::exec_context::system32;
+use crate::health::CheckResult;
+use crate::updates::homebrew_bin;
+
+// The drive check behind GET /health/disks/smart: SMART status, reallocated
+// and pending sectors and wear per drive, with a verdict of "ok", "warning",
+// "failing" or "unknown". smartctl (smartmontools 7+, for its JSON output)
+// is used when installed; it needs root on Linux and an administrator on
+// Windows. Without it, macOS falls back to system_profiler's SMART status and
+// Windows to the storage reliability counters.
+// Percentage of rated endurance used (NVMe) past which a drive is worn
+const WORN_PERCENT: u64 = 80;
+
+#[derive(Debug, Serialize, Default)]
+pub struct Drive {
+    name: String,
+    model: Option<String>,
+    // "smartctl", "system_profiler" or "storage_reliability"
+    source: &'static str,
+    smart_passed: Option<bool>,
+    reallocated_sectors: Option<u64>,
+    pending_sectors: Option<u64>,
+    media_errors: Option<u64>,
+    // Share of rated endurance used, for SSDs
+    wear_percent: Option<u64>,
+    power_on_hours: Option<u64>,
+    temperature_c: Option<i64>,
+    verdict: &'static str,
+    reasons: Vec<String>,
+}
+
+pub fn check() -> CheckResult {
+    let drives = match smartctl() {
+        Some(drives) if !drives.is_empty() => drives,
+        _ => match std::env::consts::OS {
+            "macos" => system_profiler(),
+            "windows" => reliability_counters(),
+            _ => Vec::new(),
+        },
+    };
+    if drives.is_empty() {
+        return CheckResult::new("unknown", "No drive health data (smartctl isn't installed or needs more privileges)");
+    }
+    let failing = drives.iter().filter(|drive| drive.verdict == "failing").count();
+    let warning = drives.iter().filter(|drive| drive.verdict == "warning").count();
+    let (status, detail) = if failing > 0 {
+        ("critical", format!("{} of {} drive(s) failing: back up now", failing, drives.len()))
+    } else if warning > 0 {
+        ("warning", format!("{} of {} drive(s) showing wear or errors", warning, drives.len()))
+    } else if drives.iter().all(|drive| drive.verdict == "unknown") {
+        ("unknown", format!("{} drive(s) found, health couldn't be read", drives.len()))
+    } else {
+        ("ok", format!("{} drive(s) healthy", drives.len()))
+    };
+    CheckResult::new(status, detail).with_data(serde_json::json!({ "drives": drives }))
+}
+
+fn assess(drive: &mut Drive) {
+    if drive.smart_passed == Some(false) {
+        drive.reasons.push("SMART overall health check failed".to_string());
+    }
+    for (count, what) in [
+        (drive.reallocated_sectors, "reallocated sectors"),
+        (drive.pending_sectors, "sectors pending reallocation"),
+        (drive.media_errors, "media errors"),
+    ] {
+        if let Some(count) = count.filter(|count| *count > 0) {
+            drive.reasons.push(format!("{} {}", count, what));
+        }
+    }
+    if let Some(wear) = drive.wear_percent.filter(|wear| *wear >= WORN_PERCENT) {
+        drive.reasons.push(format!("{}% of rated endurance used", wear));
+    }
+    drive.verdict = if drive.smart_passed == Some(false) || drive.wear_percent.is_some_and(|wear| wear >= 100) {
+        "failing"
+    } else if !drive.reasons.is_empty() {
+        "warning"
+    } else if drive.smart_passed.is_none() {
+        "unknown"
+    } else {
+        "ok"
+    };
+}
+
+fn smartctl_path() -> String {
+    let candidates = match std::env::consts::OS {
+        "macos" => vec![homebrew_bin("smartctl"), "/usr/local/sbin/smartctl".to_string()],
+        "windows" => vec![r"C:\Program Files\smartmontools\bin\smartctl.exe".to_string()],
+        _ => vec!["/usr/sbin/smartctl".to_string(), "/sbin/smartctl".to_string()],
+    };
+    candidates
+        .into_iter()
+        .find(|path| std::path::Path::new(path).exists())
+        .unwrap_or_else(|| "smartctl".to_string())
+}
+
+// smartctl's exit status is a bit mask that flags a failing drive too, so
+// its JSON is read whatever the status
+fn smartctl_json(program: &str, args: &[&str]) -> Option<Value> {
+    let output = diagnostic(program).args(args).output().ok()?;
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+fn smartctl() -> Option<Vec<Drive>> {
+    let program = smartctl_path();
+    let scan = smartctl_json(&program, &["--scan", "-j"])?;
+    let devices = scan["devices"].as_array()?;
+    let drives = devices
+        .iter()
+        .filter_map(|device| {
+            let name = device["name"].as_str()?;
+            let report = smartctl_json(&program, &["-a", "-j", name])?;
+            Some(parse_smartctl(name, &report))
+        })
+        .collect();
+    Some(drives)
+}
+
+fn parse_smartctl(name: &str, report: &Value) -> Drive {
+    let mut drive = Drive {
+        name: name.to_string(),
+        model: report["model_name"].as_str().map(str::to_string),
+        source: "smartctl",
+        smart_passed: report["smart_status"]["passed"].as_bool(),
+        power_on_hours: report["power_on_time"]["hours"].as_u64(),
+        temperature_c: report["temperature"]["current"].as_i64(),
+        ..Default::default()
+    };
+    let nvme = &report["nvme_smart_health_information_log"];
+    if nvme.is_object() {
+        drive.media_errors = nvme["media_errors"].as_u64();
+        drive.wear_percent = nvme["percentage_used"].as_u64();
+        if let Some(warning) = nvme["critical_warning"].as_u64().filter(|warning| *warning != 0) {
+            drive.reasons.push(format!("NVMe critical warning 0x{:02x}", warning));
+        }
+    }
+    // ATA attributes by id: 5 reallocated, 197 pending; 177 (Samsung) and
+    // 233 (Intel and others) count wear down from 100
+    let attributes = report["ata_smart_attributes"]["table"].as_array().cloned().unwrap_or_default();
+    let attribute = |id: u64| attributes.iter().find(|attribute| attribute["id"].as_u64() == Some(id));
+    drive.reallocated_sectors = attribute(5).and_then(|attribute| attribute["raw"]["value"].as_u64());
+    drive.pending_sectors = attribute(197).and_then(|attribute| attribute["raw"]["value"].as_u64());
+    if drive.wear_percent.is_none() {
+        drive.wear_percent = attribute(177)
+            .or_else(|| attribute(233))
+            .and_then(|attribute| attribute["value"].as_u64())
+            .map(|remaining| 100u64.saturating_sub(remaining));
+    }
+    assess(&mut drive);
+    drive
+}
+
+// "smart_status": "Verified" / "Failing" / "Not Supported" for each SATA and
+// NVMe drive
+fn system_profiler() -> Vec<Drive> {
+    let Some(output) = read_output("/usr/sbin/system_profiler", &["SPNVMeDataType", "SPSerialATADataType", "-json"]) else {
+        return Vec::new();
+    };
+    let Ok(report) = serde_json::from_str::<Value>(&output) else { return Vec::new() };
+    let mut drives = Vec::new();
+    for data_type in ["SPNVMeDataType", "SPSerialATADataType"] {
+        let controllers = report[data_type].as_array().cloned().unwrap_or_default();
+        // Each controller lists its drives under _items
+        let items = controllers.iter().flat_map(|item| match item["_items"].as_array() {
+            Some(children) => children.clone(),
+            None => vec![item.clone()],
+        });
+        for item in items {
+            let Some(status) = item["smart_status"].as_str() else { continue };
+            let mut drive = Drive {
+                name: item["bsd_name"].as_str().or(item["_name"].as_str()).unwrap_or_default().to_string(),
+                model: item["device_model"].as_str().or(item["_name"].as_str()).map(|model| model.trim().to_string()),
+                source: "system_profiler",
+                smart_passed: match status {
+                    "Verified" => Some(true),
+                    "Failing" => Some(false),
+                    _ => None,
+                },
+                ..Default::default()
+            };
+            assess(&mut drive);
+            drives.push(drive);
+        }
+    }
+    drives
+}
+
+// Get-PhysicalDisk's HealthStatus ("Healthy", "Warning", "Unhealthy") and its
+// reliability counters (Wear is the share of endurance used)
+fn reliability_counters() -> Vec<Drive> {
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    let script = "ConvertTo-Json -Compress -InputObject @(Get-PhysicalDisk | ForEach-Object { $r = $_ | Get-StorageReliabilityCounter -ErrorAction SilentlyContinue; \
+                  [pscustomobject]@{ Name = $_.DeviceId; Model = $_.FriendlyName; Health = \"$($_.HealthStatus)\"; \
+                  Wear = $r.Wear; ReadErrors = $r.ReadErrorsUncorrected; Hours = $r.PowerOnHours; Temperature = $r.Temperature } })";
+    let Some(output) = read_output(&powershell.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", script]) else {
+        return Vec::new();
+    };
+    let Ok(Value::Array(disks)) = serde_json::from_str::<Value>(output.trim()) else { return Vec::new() };
+    disks
+        .iter()
+        .map(|disk| {
+            let health = disk["Health"].as_str().unwrap_or_default();
+            let mut drive = Drive {
+                name: format!("PhysicalDrive{}", disk["Name"].as_str().unwrap_or_default()),
+                model: disk["Model"].as_str().map(str::to_string),
+                source: "storage_reliability",
+                smart_passed: match health {
+                    "Healthy" | "Warning" => Some(true),
+                    "Unhealthy" => Some(false),
+                    _ => None,
+                },
+                media_errors: disk["ReadErrors"].as_u64(),
+                wear_percent: disk["Wear"].as_u64(),
+                power_on_hours: disk["Hours"].as_u64(),
+                temperature_c: disk["Temperature"].as_i64().filter(|temperature| *temperature > 0),
+                ..Default::default()
+            };
+            if health == "Warning" {
+                drive.reasons.push("Windows reports the drive's health as Warning".to_string());
+            }
+            assess(&mut drive);
+            drive
+        })
+        .collect()
+}
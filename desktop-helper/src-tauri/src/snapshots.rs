@@ -0,0 +1,429 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::{read_output, read_trimmed};
+use crate::{scheduler, storage};
+
+const SNAPSHOTS_FILE: &str = "config_snapshots.json";
+const MAX_SNAPSHOTS: usize = 60;
+const DEFAULT_INTERVAL_MINUTES: u64 = 360;
+
+const NETWORKSETUP: &str = "/usr/sbin/networksetup";
+
+// Key/value view of one configuration surface (network, login items, ...)
+type Surface = BTreeMap<String, String>;
+
+// Point-in-time capture of the configuration surfaces we watch for drift
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigSnapshot {
+    pub id: String,
+    pub taken_at: DateTime<Utc>,
+    pub surfaces: BTreeMap<String, Surface>,
+}
+
+// A single setting that was added, removed or changed between two snapshots
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigChange {
+    pub surface: String,
+    pub key: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnapshotDiff {
+    pub from_id: String,
+    pub from_taken_at: DateTime<Utc>,
+    pub to_id: String,
+    pub to_taken_at: DateTime<Utc>,
+    pub changes: Vec<ConfigChange>,
+}
+
+// Serializes read-modify-write cycles on the snapshot file
+static SNAPSHOT_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn capture() -> ConfigSnapshot {
+    let mut surfaces = BTreeMap::new();
+    surfaces.insert("network".to_string(), network_surface());
+    surfaces.insert("login_items".to_string(), login_items_surface());
+    surfaces.insert("browser_defaults".to_string(), browser_defaults_surface());
+    surfaces.insert("security".to_string(), security_surface());
+
+    ConfigSnapshot {
+        id: uuid::Uuid::new_v4().to_string(),
+        taken_at: Utc::now(),
+        surfaces,
+    }
+}
+
+// Capture a snapshot and append it to the on-disk history
+pub fn take_and_store() -> Result<ConfigSnapshot, String> {
+    let snapshot = capture();
+
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    let mut snapshots: Vec<ConfigSnapshot> = storage::load_json(SNAPSHOTS_FILE);
+    snapshots.push(snapshot.clone());
+    if snapshots.len() > MAX_SNAPSHOTS {
+        let excess = snapshots.len() - MAX_SNAPSHOTS;
+        snapshots.drain(..excess);
+    }
+    storage::save_json(SNAPSHOTS_FILE, &snapshots)?;
+
+    Ok(snapshot)
+}
+
+pub fn list() -> Vec<ConfigSnapshot> {
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    storage::load_json(SNAPSHOTS_FILE)
+}
+
+pub fn diff(from: &ConfigSnapshot, to: &ConfigSnapshot) -> SnapshotDiff {
+    let mut changes = Vec::new();
+    let empty = Surface::new();
+
+    let surface_names: std::collections::BTreeSet<&String> =
+        from.surfaces.keys().chain(to.surfaces.keys()).collect();
+
+    for surface in surface_names {
+        let before = from.surfaces.get(surface).unwrap_or(&empty);
+        let after = to.surfaces.get(surface).unwrap_or(&empty);
+        let keys: std::collections::BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+
+        for key in keys {
+            let old = before.get(key);
+            let new = after.get(key);
+            if old != new {
+                changes.push(ConfigChange {
+                    surface: surface.clone(),
+                    key: key.clone(),
+                    before: old.cloned(),
+                    after: new.cloned(),
+                });
+            }
+        }
+    }
+
+    SnapshotDiff {
+        from_id: from.id.clone(),
+        from_taken_at: from.taken_at,
+        to_id: to.id.clone(),
+        to_taken_at: to.taken_at,
+        changes,
+    }
+}
+
+// Latest snapshot taken at or before `since`, or the oldest one we have
+pub fn baseline(snapshots: &[ConfigSnapshot], since: Option<DateTime<Utc>>) -> Option<&ConfigSnapshot> {
+    match since {
+        Some(since) => snapshots
+            .iter()
+            .rev()
+            .find(|s| s.taken_at <= since)
+            .or_else(|| snapshots.first()),
+        None => snapshots.first(),
+    }
+}
+
+// Take snapshots periodically (OHFIXIT_SNAPSHOT_INTERVAL_MINUTES, default 6h)
+pub fn spawn_periodic_snapshots() {
+    let minutes = std::env::var("OHFIXIT_SNAPSHOT_INTERVAL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|m| *m > 0)
+        .unwrap_or(DEFAULT_INTERVAL_MINUTES);
+
+    scheduler::spawn_job("config_snapshots", Duration::from_secs(minutes * 60), true, || async {
+        match tauri::async_runtime::spawn_blocking(take_and_store).await {
+            Ok(Ok(snapshot)) => Ok(format!("Captured config snapshot {}", snapshot.id)),
+            Ok(Err(e)) => Err(format!("Failed to store config snapshot: {}", e)),
+            Err(e) => Err(format!("Config snapshot task failed: {}", e)),
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn take_config_snapshot() -> Result<ConfigSnapshot, String> {
+    tauri::async_runtime::spawn_blocking(take_and_store)
+        .await
+        .map_err(|e| format!("Snapshot task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn list_config_snapshots() -> Result<Vec<serde_json::Value>, String> {
+    Ok(list()
+        .iter()
+        .map(|s| serde_json::json!({ "id": s.id, "taken_at": s.taken_at }))
+        .collect())
+}
+
+// Diff the current configuration against the snapshot in effect at `since`
+// (RFC 3339), answering "what changed since things last worked"
+#[tauri::command]
+pub async fn get_config_drift(since: Option<String>) -> Result<SnapshotDiff, String> {
+    let since = since
+        .map(|s| {
+            DateTime::parse_from_rfc3339(&s)
+                .map(|d| d.with_timezone(&Utc))
+                .map_err(|e| format!("Invalid 'since' timestamp: {}", e))
+        })
+        .transpose()?;
+
+    let history = list();
+    let current = take_config_snapshot().await?;
+    let base = baseline(&history, since)
+        .ok_or_else(|| "No earlier configuration snapshot to compare against".to_string())?;
+
+    Ok(diff(base, &current))
+}
+
+fn network_surface() -> Surface {
+    let mut surface = Surface::new();
+
+    match std::env::consts::OS {
+        "macos" => {
+            if let Some(services) = read_output(NETWORKSETUP, &["-listallnetworkservices"]) {
+                // First line is an informational banner; '*' marks disabled services
+                for line in services.lines().skip(1) {
+                    let name = line.trim_start_matches('*');
+                    surface.insert(format!("{}.enabled", name), (!line.starts_with('*')).to_string());
+                    if let Some(dns) = read_trimmed(NETWORKSETUP, &["-getdnsservers", name]) {
+                        surface.insert(format!("{}.dns", name), dns.lines().collect::<Vec<_>>().join(","));
+                    }
+                    if let Some(pac) = read_trimmed(NETWORKSETUP, &["-getautoproxyurl", name]) {
+                        surface.insert(format!("{}.auto_proxy", name), pac.lines().collect::<Vec<_>>().join(" "));
+                    }
+                }
+            }
+            if let Some(proxy) = read_output("/usr/sbin/scutil", &["--proxy"]) {
+                for (key, value) in parse_scutil_dictionary(&proxy) {
+                    surface.insert(format!("proxy.{}", key), value);
+                }
+            }
+        }
+        "linux" => {
+            if let Ok(resolv) = fs::read_to_string("/etc/resolv.conf") {
+                let servers: Vec<&str> = resolv
+                    .lines()
+                    .filter_map(|l| l.strip_prefix("nameserver"))
+                    .map(str::trim)
+                    .collect();
+                surface.insert("dns".to_string(), servers.join(","));
+            }
+            if let Some(active) = read_output("/usr/bin/nmcli", &["-t", "-f", "NAME,TYPE,DEVICE", "connection", "show", "--active"]) {
+                for line in active.lines() {
+                    let mut parts = line.splitn(2, ':');
+                    if let (Some(name), Some(rest)) = (parts.next(), parts.next()) {
+                        surface.insert(format!("connection.{}", name), rest.to_string());
+                    }
+                }
+            }
+            if let Some(mode) = read_trimmed("/usr/bin/gsettings", &["get", "org.gnome.system.proxy", "mode"]) {
+                surface.insert("proxy.mode".to_string(), mode);
+            }
+        }
+        "windows" => {
+            if let Some(proxy) = read_output("reg", &["query", r"HKCU\Software\Microsoft\Windows\CurrentVersion\Internet Settings"]) {
+                for (name, value) in parse_reg_values(&proxy) {
+                    if name.starts_with("Proxy") || name == "AutoConfigURL" {
+                        surface.insert(format!("proxy.{}", name), value);
+                    }
+                }
+            }
+            if let Some(dns) = read_output("netsh", &["interface", "ip", "show", "dnsservers"]) {
+                surface.insert("dns".to_string(), normalize_whitespace(&dns));
+            }
+        }
+        _ => {}
+    }
+
+    surface
+}
+
+fn login_items_surface() -> Surface {
+    let mut surface = Surface::new();
+
+    match std::env::consts::OS {
+        "macos" => {
+            if let Some(home) = dirs::home_dir() {
+                record_dir_entries(&mut surface, &home.join("Library/LaunchAgents"));
+            }
+            record_dir_entries(&mut surface, Path::new("/Library/LaunchAgents"));
+            record_dir_entries(&mut surface, Path::new("/Library/LaunchDaemons"));
+        }
+        "linux" => {
+            if let Some(config) = dirs::config_dir() {
+                record_dir_entries(&mut surface, &config.join("autostart"));
+            }
+            record_dir_entries(&mut surface, Path::new("/etc/xdg/autostart"));
+        }
+        "windows" => {
+            for hive in ["HKCU", "HKLM"] {
+                let key = format!(r"{}\Software\Microsoft\Windows\CurrentVersion\Run", hive);
+                if let Some(output) = read_output("reg", &["query", &key]) {
+                    for (name, value) in parse_reg_values(&output) {
+                        surface.insert(format!("{}.{}", hive, name), value);
+                    }
+                }
+            }
+            if let Some(startup) = dirs::config_dir() {
+                record_dir_entries(&mut surface, &startup.join(r"Microsoft\Windows\Start Menu\Programs\Startup"));
+            }
+        }
+        _ => {}
+    }
+
+    surface
+}
+
+fn browser_defaults_surface() -> Surface {
+    let mut surface = Surface::new();
+
+    match std::env::consts::OS {
+        "macos" => {
+            let Some(home) = dirs::home_dir() else { return surface };
+            let plist = home.join("Library/Preferences/com.apple.LaunchServices/com.apple.launchservices.secure.plist");
+            let plist = plist.to_string_lossy();
+            let Some(json) = read_output("/usr/bin/plutil", &["-convert", "json", "-o", "-", &plist]) else {
+                return surface;
+            };
+            let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&json) else { return surface };
+
+            for handler in parsed["LSHandlers"].as_array().into_iter().flatten() {
+                let target = handler["LSHandlerURLScheme"]
+                    .as_str()
+                    .or_else(|| handler["LSHandlerContentType"].as_str());
+                let bundle = handler["LSHandlerRoleAll"]
+                    .as_str()
+                    .or_else(|| handler["LSHandlerRoleViewer"].as_str());
+                if let (Some(target), Some(bundle)) = (target, bundle) {
+                    if matches!(target, "http" | "https" | "mailto" | "public.html" | "com.adobe.pdf") {
+                        surface.insert(target.to_string(), bundle.to_string());
+                    }
+                }
+            }
+        }
+        "linux" => {
+            if let Some(browser) = read_trimmed("/usr/bin/xdg-settings", &["get", "default-web-browser"]) {
+                surface.insert("http".to_string(), browser);
+            }
+            if let Some(mail) = read_trimmed("/usr/bin/xdg-mime", &["query", "default", "x-scheme-handler/mailto"]) {
+                surface.insert("mailto".to_string(), mail);
+            }
+        }
+        "windows" => {
+            for scheme in ["http", "https", "mailto"] {
+                let key = format!(
+                    r"HKCU\Software\Microsoft\Windows\Shell\Associations\UrlAssociations\{}\UserChoice",
+                    scheme
+                );
+                if let Some(output) = read_output("reg", &["query", &key, "/v", "ProgId"]) {
+                    if let Some((_, prog_id)) = parse_reg_values(&output).into_iter().next() {
+                        surface.insert(scheme.to_string(), prog_id);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+
+    surface
+}
+
+fn security_surface() -> Surface {
+    let mut surface = Surface::new();
+    let mut record = |key: &str, value: Option<String>| {
+        if let Some(value) = value {
+            surface.insert(key.to_string(), normalize_whitespace(&value));
+        }
+    };
+
+    match std::env::consts::OS {
+        "macos" => {
+            record("firewall", read_trimmed("/usr/libexec/ApplicationFirewall/socketfilterfw", &["--getglobalstate"]));
+            record("gatekeeper", read_trimmed("/usr/sbin/spctl", &["--status"]));
+            record("filevault", read_trimmed("/usr/bin/fdesetup", &["status"]));
+            record("sip", read_trimmed("/usr/bin/csrutil", &["status"]));
+        }
+        "linux" => {
+            record("firewalld", read_trimmed("/usr/bin/systemctl", &["is-active", "firewalld"]));
+            record("ufw", read_trimmed("/usr/bin/systemctl", &["is-active", "ufw"]));
+            record("selinux", read_trimmed("/usr/sbin/getenforce", &[]));
+        }
+        "windows" => {
+            record("firewall", read_trimmed("netsh", &["advfirewall", "show", "allprofiles", "state"]));
+            record(
+                "defender_realtime",
+                read_trimmed(
+                    "powershell",
+                    &["-NoProfile", "-Command", "(Get-MpComputerStatus).RealTimeProtectionEnabled"],
+                ),
+            );
+        }
+        _ => {}
+    }
+
+    surface
+}
+
+// Record each file in `dir` with its modification time, so additions,
+// removals and rewrites all show up as changes
+fn record_dir_entries(surface: &mut Surface, dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let modified = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .map(|t| DateTime::<Utc>::from(t).to_rfc3339())
+            .unwrap_or_default();
+        surface.insert(entry.path().to_string_lossy().into_owned(), modified);
+    }
+}
+
+// Flatten `scutil` dictionary output ("Key : Value", nested "<array> { 0 : x }")
+fn parse_scutil_dictionary(text: &str) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    let mut array: Option<String> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line == "}" {
+            array = None;
+            continue;
+        }
+        let Some((key, value)) = line.split_once(" : ") else { continue };
+        if value.starts_with("<array>") {
+            array = Some(key.to_string());
+        } else if let Some(array) = &array {
+            entries.push((format!("{}.{}", array, key), value.to_string()));
+        } else {
+            entries.push((key.to_string(), value.to_string()));
+        }
+    }
+
+    entries
+}
+
+// Parse `reg query` output lines of the form "    Name    REG_SZ    Value"
+fn parse_reg_values(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.trim().splitn(3, "    ");
+            let name = parts.next()?.trim();
+            let kind = parts.next()?.trim();
+            if !kind.starts_with("REG_") {
+                return None;
+            }
+            Some((name.to_string(), parts.next().unwrap_or("").trim().to_string()))
+        })
+        .collect()
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+use crate::processes;
+
+// GET /net/sockets?state=listening|established|all&port=: listening ports
+// and established connections with the process behind each, for "something
+// is already using port 3000" and "what is this connecting to?". macOS parses
+// lsof, Linux ss, Windows netstat with tasklist for the names. It shows what
+// the user runs, so it needs the same local consent as /processes.
+#[derive(Debug, Deserialize)]
+pub struct SocketsQuery {
+    state: Option<String>,
+    port: Option<u16>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Socket {
+    // "tcp" or "udp"
+    protocol: String,
+    local_address: String,
+    local_port: Option<u16>,
+    remote_address: Option<String>,
+    remote_port: Option<u16>,
+    // "LISTEN", "ESTABLISHED", … as the OS names it; bound UDP sockets have none
+    state: Option<String>,
+    pid: Option<u32>,
+    process: Option<String>,
+}
+
+impl Socket {
+    fn listening(&self) -> bool {
+        self.state.as_deref() == Some("LISTEN") || (self.protocol == "udp" && self.remote_port.is_none())
+    }
+
+    fn established(&self) -> bool {
+        self.state.as_deref() == Some("ESTABLISHED")
+    }
+}
+
+// GET /net/sockets
+pub async fn sockets_handler(
+    State(app): State<AppHandle>,
+    Query(query): Query<SocketsQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let state = query.state.as_deref().unwrap_or("all");
+    if !["listening", "established", "all"].contains(&state) {
+        return Err((StatusCode::BAD_REQUEST, format!("Unknown state '{}': use listening, established or all", state)));
+    }
+    processes::allowed(&app).await?;
+    let (sockets, source) = tauri::async_runtime::spawn_blocking(list)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Socket list failed: {}", e)))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let sockets: Vec<Socket> = match query.port {
+        Some(port) => sockets.into_iter().filter(|s| s.local_port == Some(port) || s.remote_port == Some(port)).collect(),
+        None => sockets,
+    };
+    let listening: Vec<&Socket> = sockets.iter().filter(|s| s.listening()).collect();
+    let established: Vec<&Socket> = sockets.iter().filter(|s| s.established()).collect();
+    let mut body = serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "source": source,
+    });
+    if state != "established" {
+        body["listening"] = serde_json::json!(listening);
+    }
+    if state != "listening" {
+        body["established"] = serde_json::json!(established);
+    }
+    // Without root, lsof and ss only name the user's own processes
+    if std::env::consts::OS != "windows" && sockets.iter().any(|s| s.pid.is_none()) {
+        body["notes"] = serde_json::json!(["Sockets owned by other users' processes are shown without a process"]);
+    }
+    Ok(Json(body))
+}
+
+fn list() -> Result<(Vec<Socket>, &'static str), String> {
+    match std::env::consts::OS {
+        "macos" => read_output("/usr/sbin/lsof", &["-nP", "-iTCP", "-iUDP", "-FpcPnT"])
+            .map(|output| (parse_lsof(&output), "lsof"))
+            .ok_or_else(|| "lsof failed".to_string()),
+        "windows" => windows().map(|sockets| (sockets, "netstat")),
+        _ => read_output("ss", &["-tunapH"])
+            .map(|output| (parse_ss(&output), "ss"))
+            .ok_or_else(|| "ss failed (is iproute2 installed?)".to_string()),
+    }
+}
+
+// "127.0.0.1:3000", "[::1]:3000", "*:53", "127.0.0.53%lo:53"
+fn split_endpoint(endpoint: &str) -> (String, Option<u16>) {
+    let Some((address, port)) = endpoint.rsplit_once(':') else {
+        return (endpoint.to_string(), None);
+    };
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    let address = address.split('%').next().unwrap_or(address);
+    (address.to_string(), port.parse().ok())
+}
+
+fn socket(protocol: &str, local: &str, remote: Option<&str>, state: Option<&str>) -> Socket {
+    let (local_address, local_port) = split_endpoint(local);
+    let (remote_address, remote_port) = match remote.map(split_endpoint) {
+        // "*:*" and "0.0.0.0:0" mean no peer
+        Some((address, port)) if port.is_some_and(|port| port != 0) => (Some(address), port),
+        _ => (None, None),
+    };
+    Socket {
+        protocol: protocol.to_lowercase(),
+        local_address,
+        local_port,
+        remote_address,
+        remote_port,
+        state: state.map(str::to_string),
+        pid: None,
+        process: None,
+    }
+}
+
+// lsof -F: a "p<pid>" line and a "c<command>" line start each process, then
+// per file "P<protocol>", "n<local>[-><remote>]" and "TST=<state>"
+fn parse_lsof(output: &str) -> Vec<Socket> {
+    let mut sockets: Vec<Socket> = Vec::new();
+    let (mut pid, mut command, mut protocol) = (None, None, String::new());
+    for line in output.lines() {
+        let (field, value) = line.split_at(line.len().min(1));
+        match field {
+            "p" => pid = value.parse().ok(),
+            "c" => command = Some(value.to_string()),
+            "P" => protocol = value.to_string(),
+            "n" => {
+                let (local, remote) = match value.split_once("->") {
+                    Some((local, remote)) => (local, Some(remote)),
+                    None => (value, None),
+                };
+                let mut socket = socket(&protocol, local, remote, None);
+                socket.pid = pid;
+                socket.process = command.clone();
+                sockets.push(socket);
+            }
+            "T" => {
+                if let (Some(state), Some(socket)) = (value.strip_prefix("ST="), sockets.last_mut()) {
+                    socket.state = Some(state.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    sockets
+}
+
+// "tcp LISTEN 0 4096 127.0.0.1:631 0.0.0.0:* users:(("cupsd",pid=812,fd=7))"
+fn parse_ss(output: &str) -> Vec<Socket> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (protocol, state, local, remote) = (fields.first()?, fields.get(1)?, fields.get(4)?, fields.get(5)?);
+            let state = match *state {
+                // Bound UDP sockets; a connected one reads ESTAB
+                "UNCONN" => None,
+                "ESTAB" => Some("ESTABLISHED"),
+                state => Some(state),
+            };
+            let mut socket = socket(protocol, local, Some(remote), state);
+            if let Some(users) = fields.get(6) {
+                socket.process = users.split('"').nth(1).map(str::to_string);
+                socket.pid = users
+                    .split_once("pid=")
+                    .and_then(|(_, rest)| rest.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok());
+            }
+            Some(socket)
+        })
+        .collect()
+}
+
+// "  TCP    0.0.0.0:135    0.0.0.0:0    LISTENING    1012" and
+// "  UDP    0.0.0.0:5353   *:*                       2320"
+fn windows() -> Result<Vec<Socket>, String> {
+    let netstat = system32().join("netstat.exe");
+    let output = read_output(&netstat.to_string_lossy(), &["-ano"]).ok_or_else(|| "netstat failed".to_string())?;
+    let tasklist = system32().join("tasklist.exe");
+    let names: BTreeMap<u32, String> = read_output(&tasklist.to_string_lossy(), &["/FO", "CSV", "/NH"])
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split("\",\"").collect();
+            Some((fields.get(1)?.parse().ok()?, fields.first()?.trim_start_matches('"').to_string()))
+        })
+        .collect();
+    let sockets = output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let protocol = *fields.first()?;
+            if protocol != "TCP" && protocol != "UDP" {
+                return None;
+            }
+            let (state, pid) = match fields.len() {
+                5 => (Some(fields[3]), fields[4]),
+                4 => (None, fields[3]),
+                _ => return None,
+            };
+            let state = state.map(|state| if state == "LISTENING" { "LISTEN" } else { state });
+            let mut socket = socket(protocol, fields[1], Some(fields[2]), state);
+            socket.pid = pid.parse().ok();
+            socket.process = socket.pid.and_then(|pid| names.get(&pid).cloned());
+            Some(socket)
+        })
+        .collect();
+    Ok(sockets)
+}
@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use crate::rollback::CommandContext;
+
+// Total size of what a cleanup action would delete. Each target is a directory,
+// optionally ending in `*<suffix>` to count only matching files (recursively),
+// and may use the {home}/{temp} placeholders.
+pub fn estimate(targets: &[String]) -> u64 {
+    let context = CommandContext::default();
+    targets
+        .iter()
+        .map(|target| {
+            let target = context.expand(target);
+            let (dir, suffix) = match target.rsplit_once(['/', '\\']) {
+                Some((dir, last)) if last.starts_with('*') => (dir.to_string(), Some(last[1..].to_string())),
+                _ => (target, None),
+            };
+            dir_size(Path::new(&dir), suffix.as_deref())
+        })
+        .sum()
+}
+
+// "~2.3 GB" style, for messages
+pub fn describe(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("~{} B", bytes)
+    } else {
+        format!("~{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// Like `du`: unreadable entries are skipped and symlinks aren't followed
+fn dir_size(dir: &Path, suffix: Option<&str>) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else { return 0 };
+    entries
+        .flatten()
+        .map(|entry| {
+            let Ok(file_type) = entry.file_type() else { return 0 };
+            if file_type.is_dir() {
+                dir_size(&entry.path(), suffix)
+            } else if file_type.is_file()
+                && suffix.map_or(true, |s| entry.file_name().to_string_lossy().ends_with(s))
+            {
+                entry.metadata().map(|m| m.len()).unwrap_or(0)
+            } else {
+                0
+            }
+        })
+        .sum()
+}
@@ -0,0 +1,237 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use chrono::Utc;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{audit, automation, consent, outbound, pairing};
+
+// POST /net/speedtest: a bounded download and upload against
+// OHFIXIT_SPEEDTEST_URL, or the OhFixIt server's
+// /api/automation/helper/speedtest. Either must answer GET ?bytes=N with N
+// bytes and take a POST body. It uses the user's data (and a metered
+// connection's allowance), so the user agrees in a dialog on this machine
+// first, and the route has its own rate limit. The request's token goes only
+// to the pinned OhFixIt server over HTTPS, never to a configured endpoint.
+const SPEEDTEST_PATH: &str = "/api/automation/helper/speedtest";
+const DEFAULT_DOWNLOAD_MB: u64 = 10;
+const DEFAULT_UPLOAD_MB: u64 = 5;
+const MAX_MB: u64 = 25;
+// Each direction stops here and counts what got through
+const PHASE_LIMIT: Duration = Duration::from_secs(10);
+const LATENCY_INTERVAL: Duration = Duration::from_millis(250);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Deserialize, Default)]
+pub struct SpeedtestRequest {
+    #[serde(default, alias = "downloadMb")]
+    download_mb: Option<u64>,
+    #[serde(default, alias = "uploadMb")]
+    upload_mb: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct Transfer {
+    bytes: u64,
+    duration_ms: u64,
+    mbps: Option<f64>,
+    // Stopped at PHASE_LIMIT before all bytes went through
+    capped: bool,
+    error: Option<String>,
+}
+
+// POST /net/speedtest
+pub async fn speedtest_handler(
+    State(app): State<AppHandle>,
+    headers: HeaderMap,
+    request: Option<Json<SpeedtestRequest>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let download_mb = request.download_mb.unwrap_or(DEFAULT_DOWNLOAD_MB).clamp(1, MAX_MB);
+    let upload_mb = request.upload_mb.unwrap_or(DEFAULT_UPLOAD_MB).clamp(1, MAX_MB);
+    let server_client = pairing::client();
+    let (url, own_server) = endpoint(&server_client).await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let token = automation::bearer_token(&headers).filter(|_| own_server).map(str::to_string);
+
+    let message = format!(
+        "OhFixIt wants to measure your connection speed against {}.\n\nThis downloads about {} MB and uploads about {} MB, which counts against a metered or capped connection.",
+        url.host_str().unwrap_or_default(),
+        download_mb,
+        upload_mb,
+    );
+    let answer = consent::ask(&app, "Allow a connection speed test?".to_string(), message, "Run test", "Don't run").await;
+    if answer != Some(true) {
+        audit::record("speedtest_declined", serde_json::json!({ "endpoint": url.as_str(), "timed_out": answer.is_none() }));
+        return Err((StatusCode::FORBIDDEN, "The speed test was not allowed on this computer".to_string()));
+    }
+
+    // The device certificate, like the token, is for the OhFixIt server only
+    let client = if own_server { server_client } else { Client::new() };
+    let (host, port) = (url.host_str().unwrap_or_default().to_string(), url.port_or_known_default().unwrap_or(443));
+    let latency_idle_ms = median(latency_samples(&host, port, 3).await);
+    let sampler = LatencySampler::start(host, port);
+    let download = download(&client, &url, token.as_deref(), download_mb * 1_000_000).await;
+    let upload = upload(&client, &url, token.as_deref(), upload_mb * 1_000_000).await;
+    let latency_loaded_ms = median(sampler.stop());
+
+    audit::record("speedtest_run", serde_json::json!({
+        "endpoint": url.as_str(),
+        "download_bytes": download.bytes,
+        "upload_bytes": upload.bytes,
+        "download_mbps": download.mbps,
+        "upload_mbps": upload.mbps,
+    }));
+    Ok(Json(serde_json::json!({
+        "endpoint": url.as_str(),
+        "timestamp": Utc::now().to_rfc3339(),
+        "download_mbps": download.mbps,
+        "upload_mbps": upload.mbps,
+        "latency_idle_ms": latency_idle_ms,
+        // Connect time while the transfers run; far above idle means
+        // bufferbloat
+        "latency_loaded_ms": latency_loaded_ms,
+        "download": download,
+        "upload": upload,
+    })))
+}
+
+// The test URL, and whether it's the OhFixIt server's. That one is the pinned
+// server's (see outbound::pinned_url), and only over HTTPS: it gets the token.
+async fn endpoint(client: &Client) -> Result<(Url, bool), String> {
+    if let Some(url) = std::env::var("OHFIXIT_SPEEDTEST_URL").ok().filter(|url| !url.is_empty()) {
+        let url = Url::parse(&url).map_err(|e| format!("Invalid speed test URL '{}': {}", url, e))?;
+        return Ok((url, false));
+    }
+    let url = outbound::pinned_url(client, SPEEDTEST_PATH).await?;
+    let url = Url::parse(&url).map_err(|e| format!("Invalid speed test URL '{}': {}", url, e))?;
+    if !outbound::https_or_loopback(&url) {
+        return Err(format!("Refusing to send the token to {}: not HTTPS", url));
+    }
+    Ok((url, true))
+}
+
+async fn download(client: &Client, url: &Url, token: Option<&str>, bytes: u64) -> Transfer {
+    let mut transfer = Transfer::default();
+    let started = Instant::now();
+    let mut request = client.get(url.clone()).query(&[("bytes", bytes)]);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .timeout(PHASE_LIMIT + CONNECT_TIMEOUT)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    let mut response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            transfer.error = Some(format!("Download failed: {}", e));
+            return transfer;
+        }
+    };
+    loop {
+        if started.elapsed() >= PHASE_LIMIT {
+            transfer.capped = true;
+            break;
+        }
+        match response.chunk().await {
+            Ok(Some(chunk)) => transfer.bytes += chunk.len() as u64,
+            Ok(None) => break,
+            Err(e) => {
+                // A timeout mid-body still measured something
+                transfer.capped = e.is_timeout();
+                if !e.is_timeout() {
+                    transfer.error = Some(format!("Download interrupted: {}", e));
+                }
+                break;
+            }
+        }
+    }
+    finish(transfer, started)
+}
+
+async fn upload(client: &Client, url: &Url, token: Option<&str>, bytes: u64) -> Transfer {
+    let mut transfer = Transfer::default();
+    let started = Instant::now();
+    let mut request = client.post(url.clone());
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .header("Content-Type", "application/octet-stream")
+        .body(vec![0u8; bytes as usize])
+        .timeout(PHASE_LIMIT)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    match response {
+        Ok(_) => transfer.bytes = bytes,
+        Err(e) if e.is_timeout() => transfer.capped = true,
+        Err(e) => transfer.error = Some(format!("Upload failed: {}", e)),
+    }
+    // A capped upload's progress isn't visible, so it has no rate
+    finish(transfer, started)
+}
+
+fn finish(mut transfer: Transfer, started: Instant) -> Transfer {
+    let elapsed = started.elapsed();
+    transfer.duration_ms = elapsed.as_millis() as u64;
+    if transfer.bytes > 0 && !elapsed.is_zero() {
+        let mbps = transfer.bytes as f64 * 8.0 / elapsed.as_secs_f64() / 1_000_000.0;
+        transfer.mbps = Some((mbps * 100.0).round() / 100.0);
+    }
+    transfer
+}
+
+// TCP connect times to the test endpoint, every LATENCY_INTERVAL until stopped
+struct LatencySampler {
+    stop: Arc<AtomicBool>,
+    samples: Arc<Mutex<Vec<f64>>>,
+}
+
+impl LatencySampler {
+    fn start(host: String, port: u16) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let (stop_flag, collected) = (stop.clone(), samples.clone());
+        tauri::async_runtime::spawn(async move {
+            while !stop_flag.load(Ordering::SeqCst) {
+                let sample = latency_samples(&host, port, 1).await;
+                collected.lock().unwrap().extend(sample);
+                tokio::time::sleep(LATENCY_INTERVAL).await;
+            }
+        });
+        Self { stop, samples }
+    }
+
+    fn stop(self) -> Vec<f64> {
+        self.stop.store(true, Ordering::SeqCst);
+        let samples = self.samples.lock().unwrap();
+        samples.clone()
+    }
+}
+
+async fn latency_samples(host: &str, port: u16, count: usize) -> Vec<f64> {
+    let mut samples = Vec::new();
+    for _ in 0..count {
+        let started = Instant::now();
+        if let Ok(Ok(_)) = tokio::time::timeout(CONNECT_TIMEOUT, tokio::net::TcpStream::connect((host, port))).await {
+            samples.push(started.elapsed().as_secs_f64() * 1000.0);
+        }
+    }
+    samples
+}
+
+fn median(mut samples: Vec<f64>) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+    samples.sort_by(f64::total_cmp);
+    Some((samples[samples.len() / 2] * 100.0).round() / 100.0)
+}
@@ -0,0 +1,84 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use axum::Json;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+// How long each startup step took, relative to process start
+#[derive(Debug, Serialize, Clone)]
+pub struct Span {
+    name: String,
+    started_ms: u64,
+    duration_ms: u64,
+}
+
+static PROCESS_START: OnceLock<Instant> = OnceLock::new();
+static SPANS: Mutex<Vec<Span>> = Mutex::new(Vec::new());
+
+// The action catalog (cached manifest, plugins) loads after the status server
+// is up; anything that looks actions up waits for it
+static CATALOG_LOADED: AtomicBool = AtomicBool::new(false);
+static CATALOG_NOTIFY: Notify = Notify::const_new();
+
+// First thing in main
+pub fn begin() {
+    PROCESS_START.get_or_init(Instant::now);
+}
+
+fn since_start(at: Instant) -> u64 {
+    let start = *PROCESS_START.get_or_init(Instant::now);
+    at.saturating_duration_since(start).as_millis() as u64
+}
+
+pub fn span<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    record(name, started);
+    result
+}
+
+// A step that started at `started` and finished now
+pub fn record(name: &str, started: Instant) {
+    let span = Span {
+        name: name.to_string(),
+        started_ms: since_start(started),
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    log::info!("Startup: {} took {}ms (at {}ms)", span.name, span.duration_ms, span.started_ms);
+    SPANS.lock().unwrap().push(span);
+}
+
+// A milestone: the span from process start to now
+pub fn mark(name: &str) {
+    record(name, *PROCESS_START.get_or_init(Instant::now));
+}
+
+pub fn spans() -> Vec<Span> {
+    SPANS.lock().unwrap().clone()
+}
+
+pub fn catalog_loaded() {
+    CATALOG_LOADED.store(true, Ordering::SeqCst);
+    CATALOG_NOTIFY.notify_waiters();
+}
+
+pub async fn catalog_ready() {
+    loop {
+        let notified = CATALOG_NOTIFY.notified();
+        if CATALOG_LOADED.load(Ordering::SeqCst) {
+            return;
+        }
+        notified.await;
+    }
+}
+
+// GET /metrics
+pub async fn metrics_handler() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "uptime_ms": since_start(Instant::now()),
+        "catalog_loaded": CATALOG_LOADED.load(Ordering::SeqCst),
+        "startup": spans(),
+    }))
+}
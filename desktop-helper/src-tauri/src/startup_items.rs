@@ -0,0 +1,269 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::{diagnostic, read_output};
+use crate::exec_context::system32;
+use crate::health::CheckResult;
+
+// The inventory behind GET /health/startup-items: everything that starts on
+// its own at boot or login, for "it boots slowly" and malware triage. macOS
+// reads launchd plists and System Events' login items (which asks the user
+// once for permission to control System Events); Windows the Run keys and
+// Startup folders; Linux XDG autostart entries and enabled systemd user
+// units. Items whose program isn't signed, or that appeared (or changed) in
+// the last RECENT_DAYS, are flagged.
+const RECENT_DAYS: u64 = 7;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct StartupItem {
+    name: String,
+    // "launch_agent", "launch_daemon", "login_item", "run_key",
+    // "startup_folder", "autostart" or "systemd_user"
+    kind: String,
+    // "user" or "system"
+    scope: String,
+    // The plist, registry key, shortcut or unit it's defined in
+    location: String,
+    command: Option<String>,
+    enabled: Option<bool>,
+    // None where signatures don't apply (Linux) or the program is missing
+    signed: Option<bool>,
+    signer: Option<String>,
+    added_at: Option<String>,
+    // "unsigned", "recently_added", "missing_program"
+    #[serde(default)]
+    flags: Vec<String>,
+}
+
+pub fn check() -> CheckResult {
+    let mut items = match std::env::consts::OS {
+        "macos" => macos(),
+        "windows" => windows(),
+        _ => linux(),
+    };
+    for item in &mut items {
+        flag(item);
+    }
+    let unsigned = items.iter().filter(|item| item.flags.iter().any(|flag| flag == "unsigned")).count();
+    let recent = items.iter().filter(|item| item.flags.iter().any(|flag| flag == "recently_added")).count();
+    let mut detail = format!("{} startup item(s)", items.len());
+    if unsigned > 0 {
+        detail.push_str(&format!(", {} unsigned", unsigned));
+    }
+    if recent > 0 {
+        detail.push_str(&format!(", {} added in the last {} days", recent, RECENT_DAYS));
+    }
+    let status = if unsigned > 0 || recent > 0 { "warning" } else { "ok" };
+    CheckResult::new(status, detail).with_data(serde_json::json!({ "items": items }))
+}
+
+fn flag(item: &mut StartupItem) {
+    if item.signed == Some(false) {
+        item.flags.push("unsigned".to_string());
+    }
+    let recent = item
+        .added_at
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .is_some_and(|at| Utc::now().signed_duration_since(at).num_days() < RECENT_DAYS as i64);
+    if recent {
+        item.flags.push("recently_added".to_string());
+    }
+}
+
+// A definition file's last change stands in for when the item was added
+fn modified(path: &Path) -> Option<String> {
+    let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()?;
+    Some(DateTime::<Utc>::from(modified).to_rfc3339())
+}
+
+fn macos() -> Vec<StartupItem> {
+    let home = dirs::home_dir().unwrap_or_default();
+    let directories = [
+        (home.join("Library/LaunchAgents"), "launch_agent", "user"),
+        (PathBuf::from("/Library/LaunchAgents"), "launch_agent", "system"),
+        (PathBuf::from("/Library/LaunchDaemons"), "launch_daemon", "system"),
+    ];
+    let mut items = Vec::new();
+    for (directory, kind, scope) in directories {
+        let Ok(entries) = std::fs::read_dir(&directory) else { continue };
+        for path in entries.flatten().map(|entry| entry.path()).filter(|path| path.extension().is_some_and(|ext| ext == "plist")) {
+            items.push(launchd_item(&path, kind, scope));
+        }
+    }
+
+    // "/Applications/Dropbox.app, /Applications/Rectangle.app"
+    let script = "tell application \"System Events\" to get the path of every login item";
+    if let Some(output) = read_output("/usr/bin/osascript", &["-e", script]) {
+        for path in output.trim().split(", ").filter(|path| !path.is_empty()) {
+            let path = PathBuf::from(path.trim_end_matches('/'));
+            let (signed, signer) = codesign(&path);
+            items.push(StartupItem {
+                name: path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default(),
+                kind: "login_item".to_string(),
+                scope: "user".to_string(),
+                location: path.to_string_lossy().into_owned(),
+                command: Some(path.to_string_lossy().into_owned()),
+                enabled: Some(true),
+                signed,
+                signer,
+                ..Default::default()
+            });
+        }
+    }
+    items
+}
+
+fn launchd_item(path: &Path, kind: &str, scope: &str) -> StartupItem {
+    let plist = read_output("/usr/bin/plutil", &["-convert", "json", "-o", "-", &path.to_string_lossy()])
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .unwrap_or_default();
+    let program = plist["Program"]
+        .as_str()
+        .or_else(|| plist["ProgramArguments"][0].as_str())
+        .map(str::to_string);
+    let command = match plist["ProgramArguments"].as_array() {
+        Some(arguments) => Some(arguments.iter().filter_map(|argument| argument.as_str()).collect::<Vec<_>>().join(" ")),
+        None => program.clone(),
+    };
+    let mut item = StartupItem {
+        name: plist["Label"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().into_owned()),
+        kind: kind.to_string(),
+        scope: scope.to_string(),
+        location: path.to_string_lossy().into_owned(),
+        command,
+        enabled: Some(!plist["Disabled"].as_bool().unwrap_or(false)),
+        added_at: modified(path),
+        ..Default::default()
+    };
+    match program.map(PathBuf::from) {
+        Some(program) if program.exists() => (item.signed, item.signer) = codesign(&program),
+        Some(_) => item.flags.push("missing_program".to_string()),
+        None => {}
+    }
+    item
+}
+
+// Whether the code at `path` (an app or a binary) has a valid signature, and
+// the first signing authority ("Developer ID Application: …")
+fn codesign(path: &Path) -> (Option<bool>, Option<String>) {
+    let path = path.to_string_lossy();
+    let Ok(verify) = diagnostic("/usr/bin/codesign").args(["--verify", "--strict", &path]).output() else {
+        return (None, None);
+    };
+    if !verify.status.success() {
+        return (Some(false), None);
+    }
+    // codesign -dv writes its details to stderr
+    let signer = diagnostic("/usr/bin/codesign").args(["-dv", "--verbose=2", &path]).output().ok().and_then(|details| {
+        String::from_utf8_lossy(&details.stderr)
+            .lines()
+            .find_map(|line| line.strip_prefix("Authority=").map(str::to_string))
+    });
+    (Some(true), signer)
+}
+
+// One PowerShell pass over the Run keys and Startup folders, with each
+// program's Authenticode signature. Run keys have no creation time; a
+// Startup shortcut's is its file's.
+fn windows() -> Vec<StartupItem> {
+    let script = r#"
+$ErrorActionPreference = 'SilentlyContinue'
+$shell = New-Object -ComObject WScript.Shell
+function Program($command) {
+  $command = [Environment]::ExpandEnvironmentVariables($command)
+  if ($command -match '^"([^"]+)"') { return $Matches[1] }
+  if ($command -match '^(.+?\.exe)') { return $Matches[1] }
+  return ($command -split ' ')[0]
+}
+function Signature($item, $program) {
+  if ($program -and (Test-Path -LiteralPath $program)) {
+    $s = Get-AuthenticodeSignature -LiteralPath $program
+    $item.signed = ($s.Status -eq 'Valid')
+    if ($s.SignerCertificate) { $item.signer = $s.SignerCertificate.GetNameInfo('SimpleName', $false) }
+  } else { $item.flags = @('missing_program') }
+  $item
+}
+$items = @()
+$keys = @(
+  @('HKCU:\Software\Microsoft\Windows\CurrentVersion\Run', 'user'),
+  @('HKCU:\Software\Microsoft\Windows\CurrentVersion\RunOnce', 'user'),
+  @('HKLM:\Software\Microsoft\Windows\CurrentVersion\Run', 'system'),
+  @('HKLM:\Software\Microsoft\Windows\CurrentVersion\RunOnce', 'system'),
+  @('HKLM:\Software\WOW6432Node\Microsoft\Windows\CurrentVersion\Run', 'system')
+)
+foreach ($key in $keys) {
+  $values = Get-ItemProperty -Path $key[0]
+  if (-not $values) { continue }
+  foreach ($p in $values.PSObject.Properties | Where-Object { $_.Name -notlike 'PS*' }) {
+    $item = [ordered]@{ name = $p.Name; kind = 'run_key'; scope = $key[1]; location = $key[0]; command = [string]$p.Value; enabled = $true; signed = $null; signer = $null; added_at = $null; flags = @() }
+    $items += Signature $item (Program $item.command)
+  }
+}
+$folders = @(
+  @([Environment]::GetFolderPath('Startup'), 'user'),
+  @([Environment]::GetFolderPath('CommonStartup'), 'system')
+)
+foreach ($folder in $folders) {
+  foreach ($file in Get-ChildItem -LiteralPath $folder[0] -File | Where-Object { $_.Name -ne 'desktop.ini' }) {
+    $target = if ($file.Extension -eq '.lnk') { $shell.CreateShortcut($file.FullName).TargetPath } else { $file.FullName }
+    $item = [ordered]@{ name = $file.BaseName; kind = 'startup_folder'; scope = $folder[1]; location = $file.FullName; command = $target; enabled = $true; signed = $null; signer = $null; added_at = $file.CreationTimeUtc.ToString('o'); flags = @() }
+    $items += Signature $item $target
+  }
+}
+ConvertTo-Json -InputObject @($items) -Compress -Depth 3
+"#;
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    read_output(&powershell.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", script])
+        .and_then(|output| serde_json::from_str(output.trim()).ok())
+        .unwrap_or_default()
+}
+
+fn linux() -> Vec<StartupItem> {
+    let config = dirs::config_dir().unwrap_or_default();
+    let directories = [(config.join("autostart"), "user"), (PathBuf::from("/etc/xdg/autostart"), "system")];
+    let mut items: Vec<StartupItem> = Vec::new();
+    for (directory, scope) in directories {
+        let Ok(entries) = std::fs::read_dir(&directory) else { continue };
+        for path in entries.flatten().map(|entry| entry.path()).filter(|path| path.extension().is_some_and(|ext| ext == "desktop")) {
+            let Ok(contents) = std::fs::read_to_string(&path) else { continue };
+            let value = |key: &str| {
+                contents.lines().find_map(|line| line.strip_prefix(key)?.strip_prefix('=').map(|value| value.trim().to_string()))
+            };
+            // A user entry with the same file name overrides the system one
+            let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            if scope == "system" && items.iter().any(|item| item.location.ends_with(&format!("/{}", file_name))) {
+                continue;
+            }
+            items.push(StartupItem {
+                name: value("Name").unwrap_or_else(|| file_name.trim_end_matches(".desktop").to_string()),
+                kind: "autostart".to_string(),
+                scope: scope.to_string(),
+                location: path.to_string_lossy().into_owned(),
+                command: value("Exec"),
+                enabled: Some(value("Hidden").as_deref() != Some("true") && value("X-GNOME-Autostart-enabled").as_deref() != Some("false")),
+                added_at: modified(&path),
+                ..Default::default()
+            });
+        }
+    }
+
+    // "pipewire.service enabled enabled"
+    let units = read_output("systemctl", &["--user", "list-unit-files", "--state=enabled", "--no-legend", "--type=service"]);
+    for unit in units.unwrap_or_default().lines().filter_map(|line| line.split_whitespace().next()) {
+        items.push(StartupItem {
+            name: unit.to_string(),
+            kind: "systemd_user".to_string(),
+            scope: "user".to_string(),
+            location: unit.to_string(),
+            enabled: Some(true),
+            ..Default::default()
+        });
+    }
+    items
+}
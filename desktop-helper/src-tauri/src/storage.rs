@@ -0,0 +1,124 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// Matches the bundle identifier in tauri.conf.json so files land in the same
+// directory Tauri resolves as the app data dir.
+const APP_IDENTIFIER: &str = "com.ohfixit.desktophelper";
+
+// Root directory for everything the helper persists between runs
+pub fn data_dir() -> PathBuf {
+    let dir = dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(APP_IDENTIFIER);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        log::error!("Failed to create data dir {}: {}", dir.display(), e);
+    }
+    dir
+}
+
+// Load a JSON document from the data dir, falling back to the default value
+// when the file is missing or unreadable
+pub fn load_json<T: DeserializeOwned + Default>(name: &str) -> T {
+    let path = data_dir().join(name);
+    match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            log::error!("Failed to parse {}: {}", path.display(), e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+// Write a JSON document to the data dir atomically (write temp file, then rename)
+pub fn save_json<T: Serialize>(name: &str, value: &T) -> Result<(), String> {
+    let path = data_dir().join(name);
+    let tmp = path.with_extension("tmp");
+    let bytes = serde_json::to_vec_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+    fs::write(&tmp, bytes).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+// Create `dir` (and parents) readable only by the current user. On Windows
+// the data dir already inherits the user-only ACL of %APPDATA%.
+pub fn create_private_dir(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to restrict {}: {}", dir.display(), e))?;
+    }
+    Ok(())
+}
+
+// Overwrite every file under `path` with zeros before deleting it, so backups
+// of caches and settings don't linger in free blocks. Best effort on SSDs and
+// copy-on-write filesystems, which may put the zeros elsewhere. Symlinks are
+// removed, never followed. Backups keep the read-only modes rsync copied, so
+// files and directories are made writable by their owner first; a file that
+// still can't be overwritten is just removed, and one entry failing doesn't
+// stop the rest from going.
+pub fn wipe(path: &Path) -> Result<(), String> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to inspect {}: {}", path.display(), e)),
+    };
+    if metadata.is_dir() {
+        make_writable(path, &metadata);
+        let entries = fs::read_dir(path).map_err(|e| format!("Failed to list {}: {}", path.display(), e))?;
+        let errors: Vec<String> = entries.flatten().filter_map(|entry| wipe(&entry.path()).err()).collect();
+        let removed = fs::remove_dir(path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e));
+        return match (errors.is_empty(), removed) {
+            (true, removed) => removed,
+            (false, _) => Err(errors.join("; ")),
+        };
+    }
+    if metadata.is_file() {
+        make_writable(path, &metadata);
+        if let Err(e) = overwrite(path, metadata.len()) {
+            log::error!("Failed to overwrite {}, removing it as is: {}", path.display(), e);
+        }
+    }
+    fs::remove_file(path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))
+}
+
+// u+w (and u+rx on directories, to list and empty them); on Windows, clear
+// the read-only attribute, which also blocks deleting
+fn make_writable(path: &Path, metadata: &fs::Metadata) {
+    #[cfg(unix)]
+    let permissions = {
+        use std::os::unix::fs::PermissionsExt;
+        let extra = if metadata.is_dir() { 0o700 } else { 0o200 };
+        fs::Permissions::from_mode(metadata.permissions().mode() | extra)
+    };
+    #[cfg(not(unix))]
+    #[allow(clippy::permissions_set_readonly_false)]
+    let permissions = {
+        let mut permissions = metadata.permissions();
+        permissions.set_readonly(false);
+        permissions
+    };
+    if permissions != metadata.permissions() {
+        if let Err(e) = fs::set_permissions(path, permissions) {
+            log::error!("Failed to make {} writable: {}", path.display(), e);
+        }
+    }
+}
+
+fn overwrite(path: &Path, len: u64) -> std::io::Result<()> {
+    let zeros = [0u8; 64 * 1024];
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all()
+}
@@ -0,0 +1,192 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+use crate::health::CheckResult;
+
+// The check behind GET /health/thermals: temperatures, fan speeds and whether
+// the OS is slowing the CPU down to cool it, so "slow because it's
+// overheating" can be told apart from software problems. macOS reads the SMC
+// and thermal pressure through powermetrics (root only) and otherwise just
+// pmset's CPU speed limit; Windows reads ACPI thermal zones (often empty or
+// admin-only) and the processor's performance limit; Linux reads hwmon and
+// the thermal zones.
+const HOT_CELSIUS: f64 = 90.0;
+const CRITICAL_CELSIUS: f64 = 100.0;
+// A CPU limited below this share of its maximum speed is being throttled
+const THROTTLED_PERCENT: u32 = 80;
+
+#[derive(Debug, Serialize)]
+pub struct Sensor {
+    name: String,
+    // "cpu", "gpu" or "other"
+    kind: &'static str,
+    celsius: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Fan {
+    name: String,
+    rpm: u32,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct Thermals {
+    sensors: Vec<Sensor>,
+    fans: Vec<Fan>,
+    // macOS's thermal pressure: "nominal", "moderate", "heavy", "trapping"
+    // or "sleeping"
+    pressure: Option<String>,
+    // How far the OS or firmware lets the CPU run, as a share of its
+    // maximum (100 is unrestricted); a low clock at idle doesn't count
+    cpu_speed_limit_percent: Option<u32>,
+    throttling: bool,
+    // Which readings were unavailable, and why
+    notes: Vec<String>,
+}
+
+pub fn check() -> CheckResult {
+    let mut thermals = Thermals::default();
+    match std::env::consts::OS {
+        "macos" => macos(&mut thermals),
+        "windows" => windows(&mut thermals),
+        _ => linux(&mut thermals),
+    }
+    let pressure_high = thermals.pressure.as_deref().is_some_and(|pressure| pressure != "nominal" && pressure != "moderate");
+    thermals.throttling = pressure_high || thermals.cpu_speed_limit_percent.is_some_and(|speed| speed < THROTTLED_PERCENT);
+
+    let hottest = thermals
+        .sensors
+        .iter()
+        .filter(|sensor| sensor.kind != "other")
+        .max_by(|a, b| a.celsius.total_cmp(&b.celsius));
+    let status = match hottest {
+        Some(sensor) if sensor.celsius >= CRITICAL_CELSIUS => "critical",
+        Some(sensor) if sensor.celsius >= HOT_CELSIUS => "warning",
+        _ if thermals.throttling => "warning",
+        None if thermals.pressure.is_none() && thermals.cpu_speed_limit_percent.is_none() => "unknown",
+        _ => "ok",
+    };
+    let mut detail = match hottest {
+        Some(sensor) => format!("Hottest sensor {} at {:.0}°C", sensor.name, sensor.celsius),
+        None => "No temperature sensors readable".to_string(),
+    };
+    if thermals.throttling {
+        detail.push_str("; the CPU is being held below full speed");
+    }
+    CheckResult::new(status, detail).with_data(serde_json::to_value(&thermals).unwrap_or_default())
+}
+
+fn sensor_kind(name: &str) -> &'static str {
+    let name = name.to_lowercase();
+    if ["cpu", "core", "package", "tctl", "tdie", "coretemp", "k10temp", "x86_pkg"].iter().any(|part| name.contains(part)) {
+        "cpu"
+    } else if ["gpu", "amdgpu", "radeon", "nouveau"].iter().any(|part| name.contains(part)) {
+        "gpu"
+    } else {
+        "other"
+    }
+}
+
+fn macos(thermals: &mut Thermals) {
+    // "CPU_Speed_Limit = 100"
+    let therm = read_output("/usr/bin/pmset", &["-g", "therm"]).unwrap_or_default();
+    thermals.cpu_speed_limit_percent = therm.lines().find_map(|line| {
+        let limit = line.trim().strip_prefix("CPU_Speed_Limit")?;
+        limit.trim_start_matches([' ', '=']).trim().parse().ok()
+    });
+
+    // The smc sampler only exists on Intel Macs; Apple silicon reports
+    // thermal pressure alone
+    let samplers = if std::env::consts::ARCH == "aarch64" { "thermal" } else { "smc,thermal" };
+    let Some(output) = read_output("/usr/bin/powermetrics", &["-n", "1", "-i", "200", "--samplers", samplers]) else {
+        thermals.notes.push("Temperatures and fans need powermetrics, which only runs as root".to_string());
+        return;
+    };
+    for line in output.lines().map(str::trim) {
+        // "CPU die temperature: 61.40 C", "Fan: 1792.33 rpm",
+        // "Current pressure level: Nominal"
+        let Some((label, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        if let Some(celsius) = value.strip_suffix(" C").and_then(|celsius| celsius.trim().parse::<f64>().ok()) {
+            let name = label.trim_end_matches(" temperature").to_string();
+            thermals.sensors.push(Sensor { kind: sensor_kind(&name), name, celsius });
+        } else if let Some(rpm) = value.strip_suffix(" rpm").and_then(|rpm| rpm.trim().parse::<f64>().ok()) {
+            thermals.fans.push(Fan { name: label.to_string(), rpm: rpm.round() as u32 });
+        } else if label == "Current pressure level" {
+            thermals.pressure = Some(value.to_lowercase());
+        }
+    }
+    if std::env::consts::ARCH == "aarch64" {
+        thermals.notes.push("Apple silicon doesn't expose temperatures or fan speeds to powermetrics".to_string());
+    }
+}
+
+fn windows(thermals: &mut Thermals) {
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    // Thermal zones are in tenths of a kelvin
+    let script = "Get-CimInstance -Namespace root\\wmi -ClassName MSAcpi_ThermalZoneTemperature -ErrorAction SilentlyContinue | \
+                  ForEach-Object { \"zone`t$($_.InstanceName)`t$($_.CurrentTemperature)\" }; \
+                  Get-CimInstance Win32_PerfFormattedData_Counters_ProcessorInformation -Filter \"Name='_Total'\" -ErrorAction SilentlyContinue | \
+                  ForEach-Object { \"limit`t$($_.PercentPerformanceLimit)\" }";
+    let output = read_output(&powershell.to_string_lossy(), &["-NoProfile", "-NonInteractive", "-Command", script]).unwrap_or_default();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.trim().split('\t').collect();
+        match fields[..] {
+            ["zone", name, tenths] => {
+                let Ok(tenths) = tenths.parse::<f64>() else { continue };
+                let celsius = ((tenths / 10.0 - 273.15) * 10.0).round() / 10.0;
+                // Thermal zones mostly track the CPU package
+                let name = name.rsplit('\\').next().unwrap_or(name).to_string();
+                thermals.sensors.push(Sensor { kind: "cpu", name, celsius });
+            }
+            ["limit", percent] => thermals.cpu_speed_limit_percent = percent.parse().ok(),
+            _ => {}
+        }
+    }
+    if thermals.sensors.is_empty() {
+        thermals.notes.push("This PC's firmware doesn't report temperatures to Windows (or it needs an administrator)".to_string());
+    }
+    thermals.notes.push("Windows has no standard interface for fan speeds".to_string());
+}
+
+fn linux(thermals: &mut Thermals) {
+    let read = |path: &Path| std::fs::read_to_string(path).map(|s| s.trim().to_string()).ok();
+    let chips = std::fs::read_dir("/sys/class/hwmon").map(|entries| entries.flatten().collect::<Vec<_>>()).unwrap_or_default();
+    for chip in chips {
+        let path = chip.path();
+        let chip_name = read(&path.join("name")).unwrap_or_default();
+        let Ok(files) = std::fs::read_dir(&path) else { continue };
+        let mut inputs: Vec<String> = files.flatten().map(|file| file.file_name().to_string_lossy().into_owned()).collect();
+        inputs.sort();
+        for input in inputs.iter().filter(|file| file.ends_with("_input")) {
+            let base = input.trim_end_matches("_input");
+            let label = read(&path.join(format!("{}_label", base)));
+            let name = match &label {
+                Some(label) => format!("{} {}", chip_name, label),
+                None => format!("{} {}", chip_name, base),
+            };
+            let Some(value) = read(&path.join(input)).and_then(|value| value.parse::<i64>().ok()) else { continue };
+            if base.starts_with("temp") {
+                // Millidegrees
+                thermals.sensors.push(Sensor { kind: sensor_kind(&name), name, celsius: value as f64 / 1000.0 });
+            } else if base.starts_with("fan") {
+                thermals.fans.push(Fan { name, rpm: value.max(0) as u32 });
+            }
+        }
+    }
+    // Thermal zones fill in when no hwmon driver is loaded
+    if thermals.sensors.is_empty() {
+        let zones = std::fs::read_dir("/sys/class/thermal").map(|entries| entries.flatten().collect::<Vec<_>>()).unwrap_or_default();
+        for zone in zones.iter().filter(|zone| zone.file_name().to_string_lossy().starts_with("thermal_zone")) {
+            let name = read(&zone.path().join("type")).unwrap_or_else(|| zone.file_name().to_string_lossy().into_owned());
+            let Some(millidegrees) = read(&zone.path().join("temp")).and_then(|temp| temp.parse::<i64>().ok()) else { continue };
+            thermals.sensors.push(Sensor { kind: sensor_kind(&name), name, celsius: millidegrees as f64 / 1000.0 });
+        }
+    }
+    if thermals.sensors.is_empty() {
+        thermals.notes.push("No hwmon or thermal zone sensors (common in virtual machines)".to_string());
+    }
+}
@@ -0,0 +1,229 @@
+use std::path::Path;
+
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::{read_output, read_plist};
+use crate::{history, snapshots};
+
+const DEFAULT_WINDOW_DAYS: i64 = 7;
+const DEFAULT_LIMIT: usize = 200;
+
+// A single dated entry in the "what changed recently" timeline
+#[derive(Debug, Serialize, Clone)]
+pub struct TimelineEvent {
+    pub timestamp: DateTime<Utc>,
+    pub source: String,
+    pub title: String,
+    pub details: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimelineQuery {
+    since: Option<String>,
+    limit: Option<usize>,
+}
+
+// GET /timeline?since=<rfc3339>&limit=<n>
+pub async fn timeline_handler(
+    Query(query): Query<TimelineQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let since = match query.since {
+        Some(since) => DateTime::parse_from_rfc3339(&since)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "error": format!("Invalid 'since' timestamp: {}", e) })),
+                )
+            })?,
+        None => Utc::now() - Duration::days(DEFAULT_WINDOW_DAYS),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+
+    let events = tauri::async_runtime::spawn_blocking(move || build_timeline(since, limit))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": format!("Timeline task failed: {}", e) })),
+            )
+        })?;
+
+    Ok(Json(serde_json::json!({
+        "since": since.to_rfc3339(),
+        "events": events,
+    })))
+}
+
+// Newest-first merge of install history, config drift and helper executions
+pub fn build_timeline(since: DateTime<Utc>, limit: usize) -> Vec<TimelineEvent> {
+    let mut events = Vec::new();
+    events.extend(install_events(since));
+    events.extend(drift_events(since));
+    events.extend(action_events(since));
+
+    events.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    events.truncate(limit);
+    events
+}
+
+fn drift_events(since: DateTime<Utc>) -> Vec<TimelineEvent> {
+    snapshots::list()
+        .windows(2)
+        .filter(|pair| pair[1].taken_at >= since)
+        .filter_map(|pair| {
+            let diff = snapshots::diff(&pair[0], &pair[1]);
+            if diff.changes.is_empty() {
+                return None;
+            }
+            let surfaces: std::collections::BTreeSet<&str> =
+                diff.changes.iter().map(|c| c.surface.as_str()).collect();
+            Some(TimelineEvent {
+                timestamp: diff.to_taken_at,
+                source: "config_drift".to_string(),
+                title: format!(
+                    "{} configuration change(s) in {}",
+                    diff.changes.len(),
+                    surfaces.into_iter().collect::<Vec<_>>().join(", ")
+                ),
+                details: serde_json::json!({ "changes": diff.changes }),
+            })
+        })
+        .collect()
+}
+
+fn action_events(since: DateTime<Utc>) -> Vec<TimelineEvent> {
+    history::since(since)
+        .into_iter()
+        .map(|entry| TimelineEvent {
+            timestamp: entry.started_at,
+            source: "helper_action".to_string(),
+            title: format!(
+                "{} {} {}",
+                match entry.kind.as_str() {
+                    "rollback" => "Rolled back",
+                    "simulate" => "Simulated",
+                    _ => "Ran",
+                },
+                entry.action_id,
+                if entry.interrupted {
+                    "(interrupted)"
+                } else if entry.success {
+                    "successfully"
+                } else {
+                    "(failed)"
+                }
+            ),
+            details: serde_json::to_value(&entry).unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn install_events(since: DateTime<Utc>) -> Vec<TimelineEvent> {
+    match std::env::consts::OS {
+        "macos" => macos_install_events(since),
+        "linux" => dpkg_install_events(since),
+        "windows" => windows_update_events(since),
+        _ => vec![],
+    }
+}
+
+fn macos_install_events(since: DateTime<Utc>) -> Vec<TimelineEvent> {
+    // Every entry has a <date>, which plutil won't convert to JSON
+    let Some(serde_json::Value::Array(entries)) = read_plist(Path::new("/Library/Receipts/InstallHistory.plist")) else {
+        return vec![];
+    };
+
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let timestamp = DateTime::parse_from_rfc3339(entry["date"].as_str()?)
+                .ok()?
+                .with_timezone(&Utc);
+            if timestamp < since {
+                return None;
+            }
+            let name = entry["displayName"].as_str().unwrap_or("Unknown package");
+            let version = entry["displayVersion"].as_str().unwrap_or("");
+            Some(TimelineEvent {
+                timestamp,
+                source: "install".to_string(),
+                title: format!("Installed {} {}", name, version).trim().to_string(),
+                details: serde_json::json!({
+                    "name": name,
+                    "version": version,
+                    "installer": entry["processName"],
+                }),
+            })
+        })
+        .collect()
+}
+
+// /var/log/dpkg.log lines: "2024-05-01 10:11:12 upgrade libfoo:amd64 1.0 1.1"
+fn dpkg_install_events(since: DateTime<Utc>) -> Vec<TimelineEvent> {
+    let Ok(log) = std::fs::read_to_string("/var/log/dpkg.log") else {
+        return vec![];
+    };
+
+    log.lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 6 || !matches!(parts[2], "install" | "upgrade" | "remove") {
+                return None;
+            }
+            let naive = NaiveDateTime::parse_from_str(&format!("{} {}", parts[0], parts[1]), "%Y-%m-%d %H:%M:%S").ok()?;
+            let timestamp = Local.from_local_datetime(&naive).single()?.with_timezone(&Utc);
+            if timestamp < since {
+                return None;
+            }
+            Some(TimelineEvent {
+                timestamp,
+                source: "install".to_string(),
+                title: format!("{} {} {}", parts[2], parts[3], parts[5]),
+                details: serde_json::json!({
+                    "operation": parts[2],
+                    "package": parts[3],
+                    "from_version": parts[4],
+                    "to_version": parts[5],
+                }),
+            })
+        })
+        .collect()
+}
+
+fn windows_update_events(since: DateTime<Utc>) -> Vec<TimelineEvent> {
+    let script = "Get-CimInstance Win32_QuickFixEngineering | \
+        Select-Object HotFixID,Description,@{n='InstalledOn';e={ if ($_.InstalledOn) { $_.InstalledOn.ToUniversalTime().ToString('o') } }} | \
+        ConvertTo-Json";
+    let Some(json) = read_output("powershell", &["-NoProfile", "-Command", script]) else {
+        return vec![];
+    };
+    let entries = match serde_json::from_str::<serde_json::Value>(&json) {
+        Ok(serde_json::Value::Array(entries)) => entries,
+        Ok(single @ serde_json::Value::Object(_)) => vec![single],
+        _ => return vec![],
+    };
+
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let timestamp = DateTime::parse_from_rfc3339(entry["InstalledOn"].as_str()?)
+                .ok()?
+                .with_timezone(&Utc);
+            if timestamp < since {
+                return None;
+            }
+            let id = entry["HotFixID"].as_str().unwrap_or("update");
+            Some(TimelineEvent {
+                timestamp,
+                source: "install".to_string(),
+                title: format!("Installed {}", id),
+                details: entry,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,114 @@
+use std::sync::Mutex;
+
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{auth, receipt, redact, storage, ActionResult, AppState};
+
+const TRANSCRIPT_FILE: &str = "chat_transcripts.json";
+const MAX_EVENTS: usize = 2000;
+
+// One thing the helper did on behalf of a chat
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct TranscriptEvent {
+    chat_id: String,
+    at: DateTime<Utc>,
+    // "execute", "simulate", "rollback" or "diagnostics"
+    kind: String,
+    detail: serde_json::Value,
+}
+
+static TRANSCRIPT_LOCK: Mutex<()> = Mutex::new(());
+
+fn record(chat_id: Option<&str>, kind: &str, detail: serde_json::Value) {
+    let Some(chat_id) = chat_id.filter(|c| !c.is_empty()) else { return };
+    let _guard = TRANSCRIPT_LOCK.lock().unwrap();
+    let mut events: Vec<TranscriptEvent> = storage::load_json(TRANSCRIPT_FILE);
+    events.push(TranscriptEvent { chat_id: chat_id.to_string(), at: Utc::now(), kind: kind.to_string(), detail });
+    if events.len() > MAX_EVENTS {
+        let excess = events.len() - MAX_EVENTS;
+        events.drain(..excess);
+    }
+    if let Err(e) = storage::save_json(TRANSCRIPT_FILE, &events) {
+        log::error!("Failed to persist chat transcript: {}", e);
+    }
+}
+
+// An action run, rollback or simulation, with its artifacts but not their contents
+pub fn record_action(chat_id: Option<&str>, kind: &str, action_id: &str, approval_id: &str, result: &ActionResult) {
+    let artifacts: Vec<serde_json::Value> = result
+        .artifacts
+        .iter()
+        .flatten()
+        .map(|a| serde_json::json!({ "type": a.artifact_type, "hash": a.hash }))
+        .collect();
+    record(chat_id, kind, serde_json::json!({
+        "action_id": action_id,
+        "approval_id": approval_id,
+        "success": result.success,
+        "message": redact::redact(&result.message).0,
+        "rollback_id": result.rollback_id,
+        "artifacts": artifacts,
+    }));
+}
+
+// Diagnostic data the helper handed out (probes, queries)
+pub fn record_diagnostics(chat_id: Option<&str>, source: &str, results: &impl Serialize) {
+    let results = serde_json::to_value(results).unwrap_or_default();
+    let (redacted, _) = redact::redact(&results.to_string());
+    let results = serde_json::from_str(&redacted).unwrap_or(serde_json::Value::String(redacted));
+    record(chat_id, "diagnostics", serde_json::json!({ "source": source, "results": results }));
+}
+
+// The transcript is signed like execution receipts: HMAC-SHA256 with the
+// approval secret over the exact payload string
+#[derive(Debug, Serialize)]
+pub struct SignedTranscript {
+    payload: String,
+    signature: String,
+}
+
+// GET /transcripts/{chat_id} (Authorization: Bearer <token minted for that chat>),
+// called by the server when a chat closes
+pub async fn transcript_handler(
+    State(app): State<AppHandle>,
+    Path(chat_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<SignedTranscript>, (StatusCode, String)> {
+    let (jwt_secret, client) = {
+        let state = app.state::<Mutex<AppState>>();
+        let state = state.lock().unwrap();
+        (state.jwt_secret.clone(), state.client.clone())
+    };
+    let token = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or((StatusCode::UNAUTHORIZED, "Missing bearer token".to_string()))?;
+    let claims = auth::validate_token(token, &client).await.map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+    let jwt_secret = jwt_secret.ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "No transcript signing key: the credential store is unavailable".to_string(),
+    ))?;
+    if claims.chat_id.as_deref() != Some(chat_id.as_str()) {
+        return Err((StatusCode::FORBIDDEN, format!("Token was not issued for chat '{}'", chat_id)));
+    }
+
+    let events: Vec<TranscriptEvent> = {
+        let _guard = TRANSCRIPT_LOCK.lock().unwrap();
+        let events: Vec<TranscriptEvent> = storage::load_json(TRANSCRIPT_FILE);
+        events.into_iter().filter(|e| e.chat_id == chat_id).collect()
+    };
+    let payload = serde_json::json!({
+        "chat_id": chat_id,
+        "generated_at": Utc::now().to_rfc3339(),
+        "events": events,
+    })
+    .to_string();
+    let signature = receipt::sign(&payload, &jwt_secret);
+    Ok(Json(SignedTranscript { payload, signature }))
+}
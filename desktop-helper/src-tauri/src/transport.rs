@@ -0,0 +1,251 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use axum::Router;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_native_tls::TlsStream;
+
+use crate::storage;
+
+// The status server listens on TCP 127.0.0.1, which any local process can
+// reach. OHFIXIT_LOCAL_TRANSPORT=socket serves it on a Unix domain socket
+// (macOS/Linux) or named pipe (Windows) that only this user can open instead;
+// "both" keeps TCP as well. Browser pages then reach the helper through the
+// Tauri webview rather than over HTTP.
+const SOCKET_FILE: &str = "helper.sock";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Tcp,
+    Socket,
+    Both,
+}
+
+impl Mode {
+    pub fn tcp(self) -> bool {
+        self != Mode::Socket
+    }
+
+    pub fn socket(self) -> bool {
+        self != Mode::Tcp
+    }
+}
+
+pub fn mode() -> Mode {
+    match std::env::var("OHFIXIT_LOCAL_TRANSPORT").unwrap_or_default().to_lowercase().as_str() {
+        "socket" | "pipe" => Mode::Socket,
+        "both" => Mode::Both,
+        _ => Mode::Tcp,
+    }
+}
+
+// Socket path or pipe name, advertised on /status
+pub fn address() -> String {
+    match std::env::consts::OS {
+        // Pipe names are machine-wide, so they carry the user name
+        "windows" => format!(
+            r"\\.\pipe\ohfixit-helper-{}",
+            std::env::var("USERNAME").unwrap_or_else(|_| "user".to_string())
+        ),
+        _ => storage::data_dir().join(SOCKET_FILE).display().to_string(),
+    }
+}
+
+#[derive(Debug)]
+pub enum Stopped {
+    // Another helper already serves the socket or pipe
+    InUse,
+    Failed(String),
+}
+
+// Serve `router` on the socket or pipe until the helper exits
+pub async fn serve(router: Router) -> Result<(), Stopped> {
+    let address = address();
+    #[cfg(unix)]
+    let listener = socket::bind(&address).await?;
+    #[cfg(windows)]
+    let listener = pipe::bind(&address)?;
+    log::info!("Status server listening on {}", address);
+    axum::serve(listener, router).await.map_err(|e| Stopped::Failed(e.to_string()))
+}
+
+// A client gets this long to finish the TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+// Handshaken connections waiting for the server to pick them up
+const TLS_BACKLOG: usize = 64;
+
+// TCP with TLS on top. Handshakes run in their own tasks, so a client that
+// connects and then stalls doesn't hold up anyone else.
+pub struct TlsListener {
+    incoming: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(connection) => connection,
+            // The accept loop only stops with the runtime
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+// Serve TLS with `identity` on an already bound listener. TLS 1.2 at least.
+pub fn tls(listener: TcpListener, identity: native_tls::Identity) -> Result<TlsListener, String> {
+    let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+    let acceptor = native_tls::TlsAcceptor::builder(identity)
+        .min_protocol_version(Some(native_tls::Protocol::Tlsv12))
+        .build()
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?;
+    let acceptor = tokio_native_tls::TlsAcceptor::from(acceptor);
+    let (sender, incoming) = mpsc::channel(TLS_BACKLOG);
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    log::error!("Failed to accept on {}: {}", local_addr, e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let acceptor = acceptor.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        let _ = sender.send((stream, peer)).await;
+                    }
+                    Ok(Err(e)) => log::info!("TLS handshake with {} failed: {}", peer, e),
+                    Err(_) => log::info!("TLS handshake with {} timed out", peer),
+                }
+            });
+        }
+    });
+    Ok(TlsListener { incoming, local_addr })
+}
+
+#[cfg(unix)]
+mod socket {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    use tokio::net::{UnixListener, UnixStream};
+
+    use super::Stopped;
+
+    pub async fn bind(path: &str) -> Result<UnixListener, Stopped> {
+        // A socket file left by a helper that crashed blocks bind; one that
+        // still accepts connections belongs to a running helper
+        if Path::new(path).exists() {
+            if UnixStream::connect(path).await.is_ok() {
+                return Err(Stopped::InUse);
+            }
+            let _ = std::fs::remove_file(path);
+        }
+        let listener = UnixListener::bind(path).map_err(|e| Stopped::Failed(e.to_string()))?;
+        // Owner only: other users on the machine can't connect
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| Stopped::Failed(format!("Failed to restrict {}: {}", path, e)))?;
+        Ok(listener)
+    }
+}
+
+#[cfg(windows)]
+mod pipe {
+    use std::time::Duration;
+
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+    use windows_sys::Win32::Foundation::{LocalFree, ERROR_ACCESS_DENIED};
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows_sys::Win32::Security::{PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES};
+
+    use super::Stopped;
+
+    // Protected DACL granting access to the owner (this user) alone
+    const OWNER_ONLY: &str = "D:P(A;;GA;;;OW)";
+    const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+    // A named pipe serves one client per instance, so a fresh instance is
+    // created each time one connects
+    pub struct PipeListener {
+        address: String,
+        next: NamedPipeServer,
+    }
+
+    impl axum::serve::Listener for PipeListener {
+        type Io = NamedPipeServer;
+        type Addr = String;
+
+        async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+            loop {
+                if let Err(e) = self.next.connect().await {
+                    log::error!("Failed to accept on {}: {}", self.address, e);
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    if let Ok(next) = create(&self.address, false) {
+                        self.next = next;
+                    }
+                    continue;
+                }
+                match create(&self.address, false) {
+                    Ok(next) => return (std::mem::replace(&mut self.next, next), self.address.clone()),
+                    Err(e) => {
+                        log::error!("Failed to create pipe instance {}: {}", self.address, e);
+                        tokio::time::sleep(RETRY_DELAY).await;
+                    }
+                }
+            }
+        }
+
+        fn local_addr(&self) -> std::io::Result<Self::Addr> {
+            Ok(self.address.clone())
+        }
+    }
+
+    pub fn bind(address: &str) -> Result<PipeListener, Stopped> {
+        // The first instance must be ours, so no other process can squat the name
+        let next = create(address, true).map_err(|e| match e.raw_os_error() {
+            Some(code) if code == ERROR_ACCESS_DENIED as i32 => Stopped::InUse,
+            _ => Stopped::Failed(e.to_string()),
+        })?;
+        Ok(PipeListener { address: address.to_string(), next })
+    }
+
+    fn create(address: &str, first: bool) -> std::io::Result<NamedPipeServer> {
+        let sddl: Vec<u16> = OWNER_ONLY.encode_utf16().chain(Some(0)).collect();
+        unsafe {
+            let mut descriptor: PSECURITY_DESCRIPTOR = std::ptr::null_mut();
+            if ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                sddl.as_ptr(),
+                SDDL_REVISION_1,
+                &mut descriptor,
+                std::ptr::null_mut(),
+            ) == 0
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+            let mut attributes = SECURITY_ATTRIBUTES {
+                nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: descriptor,
+                bInheritHandle: 0,
+            };
+            let pipe = ServerOptions::new()
+                .first_pipe_instance(first)
+                .reject_remote_clients(true)
+                .create_with_security_attributes_raw(address, &mut attributes as *mut _ as *mut std::ffi::c_void);
+            LocalFree(descriptor);
+            pipe
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{App, AppHandle};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::{automation, freeze};
+
+const TRAY_ID: &str = "main";
+const ABORT_ALL_ID: &str = "abort-all";
+const FREEZE_ID: &str = "freeze";
+const TOOLTIP: &str = "OhFixIt Desktop Helper";
+
+// Menu bar / system tray icon, so everything can be stopped (or frozen) with one click
+// even when the helper window is closed
+pub fn setup(app: &App) -> tauri::Result<()> {
+    let abort_all = MenuItem::with_id(app, ABORT_ALL_ID, "Stop all running fixes", true, None::<&str>)?;
+    let freeze = MenuItem::with_id(app, FREEZE_ID, "Freeze all fixes", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&abort_all, &freeze])?;
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(TOOLTIP)
+        .menu(&menu)
+        .on_menu_event(|app, event| {
+            let app = app.clone();
+            if event.id() == ABORT_ALL_ID {
+                tauri::async_runtime::spawn(async move {
+                    automation::abort_all(&app).await;
+                });
+            } else if event.id() == FREEZE_ID {
+                tauri::async_runtime::spawn(async move {
+                    freeze::freeze(&app, freeze::SOURCE_LOCAL, "Frozen from the tray menu").await;
+                });
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    Ok(())
+}
+
+// Something the user should know about even with the window closed: shown in
+// the tray tooltip until cleared, and once in a warning dialog that doesn't
+// wait for an answer
+pub fn alert(app: &AppHandle, title: &str, message: &str) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!("{} – {}", TOOLTIP, title)));
+    }
+    app.dialog().message(message).title(title).kind(MessageDialogKind::Warning).show(|_| {});
+}
+
+pub fn clear_alert(app: &AppHandle) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(TOOLTIP));
+    }
+}
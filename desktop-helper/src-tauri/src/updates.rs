@@ -0,0 +1,309 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use axum::extract::Query;
+use axum::Json;
+use serde::Serialize;
+
+use crate::cache::{CacheQuery, TtlCache};
+use crate::cmd::read_output;
+
+const SOFTWAREUPDATE: &str = "/usr/sbin/softwareupdate";
+const APPCAST_TIMEOUT: Duration = Duration::from_secs(5);
+// Feeds fetched at once, and the budget for all of them together
+const APPCAST_CONCURRENCY: usize = 8;
+const APPCAST_DEADLINE: Duration = Duration::from_secs(15);
+
+// softwareupdate -l alone can take a minute
+static REPORT_CACHE: TtlCache = TtlCache::new(Duration::from_secs(15 * 60));
+
+// One pending update from any source, with the allowlisted action that
+// installs it (when one exists)
+#[derive(Debug, Serialize, Clone)]
+pub struct PendingUpdate {
+    pub source: String,
+    pub name: String,
+    pub current_version: Option<String>,
+    pub available_version: Option<String>,
+    pub update_action: Option<String>,
+    pub note: Option<String>,
+}
+
+// Result of checking a single source; `available` is false when the tool is missing
+#[derive(Debug, Serialize, Clone)]
+pub struct SourceReport {
+    pub source: String,
+    pub available: bool,
+    pub updates: Vec<PendingUpdate>,
+}
+
+impl SourceReport {
+    fn unavailable(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            available: false,
+            updates: vec![],
+        }
+    }
+}
+
+// Homebrew lives in a different prefix on Apple Silicon and Intel Macs
+pub fn homebrew_bin(tool: &str) -> String {
+    if std::env::consts::ARCH == "aarch64" {
+        format!("/opt/homebrew/bin/{}", tool)
+    } else {
+        format!("/usr/local/bin/{}", tool)
+    }
+}
+
+// GET /updates[?refresh=true]
+pub async fn updates_handler(Query(query): Query<CacheQuery>) -> Json<serde_json::Value> {
+    Json(cached_report(query.refresh).await)
+}
+
+pub async fn cached_report(refresh: bool) -> serde_json::Value {
+    REPORT_CACHE
+        .get_or_compute("updates", refresh, pending_updates_report)
+        .await
+}
+
+pub async fn pending_updates_report() -> serde_json::Value {
+    let blocking = tauri::async_runtime::spawn_blocking(|| {
+        vec![system_updates(), app_store_updates(), homebrew_updates()]
+    });
+    let sparkle = sparkle_updates().await;
+
+    let mut sources = blocking.await.unwrap_or_default();
+    sources.push(sparkle);
+
+    let updates: Vec<&PendingUpdate> = sources.iter().flat_map(|s| s.updates.iter()).collect();
+    serde_json::json!({
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "total": updates.len(),
+        "updates": updates,
+        "sources": sources,
+    })
+}
+
+// `softwareupdate -l` lists each update as
+//   * Label: macOS Sonoma 14.5-23F79
+//     Title: macOS Sonoma 14.5, Version: 14.5, Size: ..., Recommended: YES,
+fn system_updates() -> SourceReport {
+    if std::env::consts::OS != "macos" {
+        return SourceReport::unavailable("system");
+    }
+    let Some(output) = read_output(SOFTWAREUPDATE, &["-l"]) else {
+        return SourceReport::unavailable("system");
+    };
+
+    let updates = output
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| line.strip_prefix("Title:"))
+        .map(|details| {
+            let fields: Vec<(&str, &str)> = details
+                .split(',')
+                .filter_map(|f| f.split_once(':'))
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .collect();
+            let title = details.split(',').next().unwrap_or(details).trim();
+            let version = fields.iter().find(|(k, _)| *k == "Version").map(|(_, v)| v.to_string());
+            let restart = fields.iter().any(|(k, v)| *k == "Action" && *v == "restart");
+            PendingUpdate {
+                source: "system".to_string(),
+                name: title.to_string(),
+                current_version: None,
+                available_version: version,
+                update_action: Some("install-macos-updates".to_string()),
+                note: restart.then(|| "Requires restart".to_string()),
+            }
+        })
+        .collect();
+
+    SourceReport {
+        source: "system".to_string(),
+        available: true,
+        updates,
+    }
+}
+
+// `mas outdated` lines: "497799835 Xcode (15.0 -> 15.1)"
+fn app_store_updates() -> SourceReport {
+    let mas = homebrew_bin("mas");
+    if std::env::consts::OS != "macos" || !Path::new(&mas).exists() {
+        return SourceReport::unavailable("app_store");
+    }
+    let Some(output) = read_output(&mas, &["outdated"]) else {
+        return SourceReport::unavailable("app_store");
+    };
+
+    let updates = output
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.trim().split_once(' ')?;
+            let (name, versions) = rest.rsplit_once(" (")?;
+            let (current, available) = versions.trim_end_matches(')').split_once(" -> ")?;
+            Some(PendingUpdate {
+                source: "app_store".to_string(),
+                name: name.trim().to_string(),
+                current_version: Some(current.trim().to_string()),
+                available_version: Some(available.trim().to_string()),
+                update_action: Some("upgrade-app-store-apps".to_string()),
+                note: None,
+            })
+        })
+        .collect();
+
+    SourceReport {
+        source: "app_store".to_string(),
+        available: true,
+        updates,
+    }
+}
+
+fn homebrew_updates() -> SourceReport {
+    let brew = homebrew_bin("brew");
+    if std::env::consts::OS != "macos" || !Path::new(&brew).exists() {
+        return SourceReport::unavailable("homebrew");
+    }
+    let Some(output) = read_output(&brew, &["outdated", "--json=v2"]) else {
+        return SourceReport::unavailable("homebrew");
+    };
+    let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&output) else {
+        return SourceReport::unavailable("homebrew");
+    };
+
+    let updates = ["formulae", "casks"]
+        .iter()
+        .flat_map(|kind| parsed[*kind].as_array().cloned().unwrap_or_default())
+        .map(|entry| PendingUpdate {
+            source: "homebrew".to_string(),
+            name: entry["name"].as_str().unwrap_or("unknown").to_string(),
+            current_version: entry["installed_versions"][0].as_str().map(str::to_string),
+            available_version: entry["current_version"].as_str().map(str::to_string),
+            update_action: Some("upgrade-homebrew-packages".to_string()),
+            note: None,
+        })
+        .collect();
+
+    SourceReport {
+        source: "homebrew".to_string(),
+        available: true,
+        updates,
+    }
+}
+
+// Apps that embed Sparkle advertise their appcast via SUFeedURL in Info.plist;
+// compare the newest appcast version with the installed bundle version
+async fn sparkle_updates() -> SourceReport {
+    if std::env::consts::OS != "macos" {
+        return SourceReport::unavailable("sparkle");
+    }
+
+    let apps = tauri::async_runtime::spawn_blocking(sparkle_apps).await.unwrap_or_default();
+    let client = match reqwest::Client::builder().timeout(APPCAST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(_) => return SourceReport::unavailable("sparkle"),
+    };
+
+    let permits = std::sync::Arc::new(tokio::sync::Semaphore::new(APPCAST_CONCURRENCY));
+    let mut fetches = tokio::task::JoinSet::new();
+    for (name, installed, feed_url) in apps {
+        let (client, permits) = (client.clone(), permits.clone());
+        fetches.spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            let appcast = client.get(&feed_url).send().await.ok()?.text().await.ok()?;
+            let latest = latest_appcast_version(&appcast)?;
+            (compare_versions(&latest, &installed) == std::cmp::Ordering::Greater).then(|| PendingUpdate {
+                source: "sparkle".to_string(),
+                name,
+                current_version: Some(installed),
+                available_version: Some(latest),
+                update_action: None,
+                note: Some("Open the app and choose \"Check for Updates…\"".to_string()),
+            })
+        });
+    }
+
+    // Feeds still outstanding at the deadline are skipped (and aborted when the set drops)
+    let deadline = tokio::time::Instant::now() + APPCAST_DEADLINE;
+    let mut updates = Vec::new();
+    while let Ok(Some(joined)) = tokio::time::timeout_at(deadline, fetches.join_next()).await {
+        if let Ok(Some(update)) = joined {
+            updates.push(update);
+        }
+    }
+    updates.sort_by(|a, b| a.name.cmp(&b.name));
+
+    SourceReport {
+        source: "sparkle".to_string(),
+        available: true,
+        updates,
+    }
+}
+
+// (app name, installed version, feed url) for every Sparkle-enabled app in /Applications
+fn sparkle_apps() -> Vec<(String, String, String)> {
+    let Ok(entries) = fs::read_dir("/Applications") else { return vec![] };
+
+    entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "app"))
+        .filter_map(|entry| {
+            let plist = entry.path().join("Contents/Info.plist");
+            let json = read_output("/usr/bin/plutil", &["-convert", "json", "-o", "-", &plist.to_string_lossy()])?;
+            let info: serde_json::Value = serde_json::from_str(&json).ok()?;
+            let feed = info["SUFeedURL"].as_str()?.to_string();
+            let version = info["CFBundleShortVersionString"]
+                .as_str()
+                .or_else(|| info["CFBundleVersion"].as_str())?
+                .to_string();
+            let name = entry.path().file_stem()?.to_string_lossy().into_owned();
+            Some((name, version, feed))
+        })
+        .collect()
+}
+
+// Highest sparkle:shortVersionString (or sparkle:version) advertised in an appcast,
+// whether given as an enclosure attribute or as an element
+fn latest_appcast_version(appcast: &str) -> Option<String> {
+    let mut versions = Vec::new();
+    for tag in ["sparkle:shortVersionString", "sparkle:version"] {
+        for (index, _) in appcast.match_indices(tag) {
+            let rest = &appcast[index + tag.len()..];
+            let value = if let Some(attr) = rest.strip_prefix("=\"") {
+                attr.split('"').next()
+            } else if let Some(element) = rest.strip_prefix('>') {
+                element.split('<').next()
+            } else {
+                None
+            };
+            if let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) {
+                versions.push(value.to_string());
+            }
+        }
+        if !versions.is_empty() {
+            break;
+        }
+    }
+    versions.into_iter().max_by(|a, b| compare_versions(a, b))
+}
+
+// Compare dotted version strings numerically ("1.10" > "1.9")
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parse = |v: &str| -> Vec<u64> {
+        v.split(['.', '-', ' '])
+            .map(|part| part.chars().take_while(|c| c.is_ascii_digit()).collect::<String>())
+            .map(|digits| digits.parse().unwrap_or(0))
+            .collect()
+    };
+    let (a, b) = (parse(a), parse(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != std::cmp::Ordering::Equal {
+            return ordering;
+        }
+    }
+    std::cmp::Ordering::Equal
+}
@@ -0,0 +1,379 @@
+use axum::Json;
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::cmd::{read_output, read_trimmed};
+use crate::exec_context::system32;
+
+// GET /net/vpn: whether a VPN is up and whether internet traffic goes through
+// it. A VPN's own DNS servers and routes are behind most "only some sites
+// work" and captive-portal reports, so the assistant checks this before
+// suggesting DNS or Wi-Fi fixes. Three signals: tunnel interfaces with an
+// address, running VPN clients, and the interface the route to a public
+// address leaves through (which catches split routes like OpenVPN's
+// 0.0.0.0/1 + 128.0.0.0/1 as well as a replaced default route).
+const ROUTE_PROBE: &str = "1.1.1.1";
+// Process names (lowercase, without .exe) of common VPN clients
+const CLIENTS: &[(&str, &str)] = &[
+    ("openvpn", "OpenVPN"),
+    ("openvpn-gui", "OpenVPN"),
+    ("openvpnconnect", "OpenVPN Connect"),
+    ("openconnect", "OpenConnect"),
+    ("wireguard", "WireGuard"),
+    ("wireguard-go", "WireGuard"),
+    ("tailscaled", "Tailscale"),
+    ("tailscale-ipn", "Tailscale"),
+    ("zerotier-one", "ZeroTier"),
+    ("warp-svc", "Cloudflare WARP"),
+    ("cloudflare warp", "Cloudflare WARP"),
+    ("nordvpnd", "NordVPN"),
+    ("nordvpn", "NordVPN"),
+    ("nordvpn-service", "NordVPN"),
+    ("expressvpnd", "ExpressVPN"),
+    ("expressvpn", "ExpressVPN"),
+    ("expressvpn.daemon", "ExpressVPN"),
+    ("protonvpn", "Proton VPN"),
+    ("protonvpnservice", "Proton VPN"),
+    ("mullvad-daemon", "Mullvad"),
+    ("surfshark", "Surfshark"),
+    ("surfshark.service", "Surfshark"),
+    ("pia-daemon", "Private Internet Access"),
+    ("vpnagentd", "Cisco AnyConnect"),
+    ("vpnagent", "Cisco AnyConnect"),
+    ("csc_vpnagent", "Cisco Secure Client"),
+    ("pangps", "GlobalProtect"),
+    ("pangpa", "GlobalProtect"),
+    ("globalprotect", "GlobalProtect"),
+    ("forticlient", "FortiClient"),
+    ("fortitray", "FortiClient"),
+    ("dsaccessservice", "Ivanti Secure Access"),
+    ("pulsesecure", "Ivanti Secure Access"),
+    ("charon", "strongSwan"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct Tunnel {
+    name: String,
+    // "wireguard", "tun", "tap", "ppp", "ipsec" or "adapter" (a Windows
+    // adapter whose driver is a VPN's)
+    kind: &'static str,
+    description: Option<String>,
+    addresses: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Client {
+    name: &'static str,
+    process: String,
+}
+
+// A VPN connection the OS itself manages (macOS and Windows VPN settings,
+// NetworkManager)
+#[derive(Debug, Serialize)]
+pub struct Service {
+    name: String,
+    status: String,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct Vpn {
+    active: bool,
+    tunnels: Vec<Tunnel>,
+    clients: Vec<Client>,
+    services: Vec<Service>,
+    // The interface traffic to ROUTE_PROBE leaves through
+    route_interface: Option<String>,
+    // That interface is one of the tunnels
+    full_tunnel: bool,
+    diagnosis: String,
+}
+
+// GET /net/vpn
+pub async fn vpn_handler() -> Json<serde_json::Value> {
+    let vpn = tauri::async_runtime::spawn_blocking(detect).await.unwrap_or_else(|e| Vpn {
+        diagnosis: format!("VPN check failed: {}", e),
+        ..Default::default()
+    });
+    Json(serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "vpn": vpn,
+    }))
+}
+
+fn detect() -> Vpn {
+    let mut vpn = Vpn::default();
+    match std::env::consts::OS {
+        "macos" => {
+            vpn.tunnels = read_output("/sbin/ifconfig", &[]).map(|output| parse_ifconfig(&output)).unwrap_or_default();
+            vpn.services = read_output("/usr/sbin/scutil", &["--nc", "list"]).map(|output| parse_scutil_nc(&output)).unwrap_or_default();
+            vpn.route_interface = read_output("/sbin/route", &["-n", "get", ROUTE_PROBE]).and_then(|output| {
+                output.lines().find_map(|line| line.trim().strip_prefix("interface:").map(|name| name.trim().to_string()))
+            });
+        }
+        "windows" => windows(&mut vpn),
+        _ => linux(&mut vpn),
+    }
+    vpn.clients = clients();
+    vpn.full_tunnel = vpn
+        .route_interface
+        .as_deref()
+        .is_some_and(|route| vpn.tunnels.iter().any(|tunnel| tunnel.name == route));
+    let connected_service = vpn.services.iter().any(connected);
+    vpn.active = !vpn.tunnels.is_empty() || connected_service;
+    vpn.diagnosis = diagnose(&vpn).to_string();
+    vpn
+}
+
+// "Connected" on macOS and Windows, "activated" from NetworkManager
+fn connected(service: &Service) -> bool {
+    service.status.eq_ignore_ascii_case("connected") || service.status == "activated"
+}
+
+fn diagnose(vpn: &Vpn) -> &'static str {
+    if vpn.full_tunnel {
+        "All internet traffic goes through the VPN: its DNS servers and exit point apply to every site, and captive portals (hotels, airports) can't show until it's disconnected"
+    } else if vpn.active {
+        "A VPN is up but internet traffic bypasses it (split tunnel): internal names and addresses may still resolve through the VPN's DNS"
+    } else if !vpn.clients.is_empty() {
+        "A VPN client is running without a tunnel up: it may be reconnecting, or its kill switch may be blocking traffic"
+    } else {
+        "No VPN detected"
+    }
+}
+
+fn tunnel_kind(name: &str) -> Option<&'static str> {
+    let kinds = [
+        ("wg", "wireguard"),
+        ("utun", "tun"),
+        ("tun", "tun"),
+        ("tailscale", "tun"),
+        ("zt", "tun"),
+        ("tap", "tap"),
+        ("ppp", "ppp"),
+        ("ipsec", "ipsec"),
+        ("vti", "ipsec"),
+    ];
+    kinds.iter().find(|(prefix, _)| name.starts_with(prefix)).map(|(_, kind)| *kind)
+}
+
+// Tunnel interfaces that are up with a routable address. macOS keeps a few
+// utun interfaces for iCloud and Continuity that only carry link-local IPv6,
+// so those don't count.
+fn parse_ifconfig(output: &str) -> Vec<Tunnel> {
+    let mut tunnels = Vec::new();
+    let mut current: Option<(Tunnel, bool)> = None;
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            tunnels.extend(current.take().filter(|(tunnel, up)| *up && !tunnel.addresses.is_empty()).map(|(tunnel, _)| tunnel));
+            let Some((name, rest)) = line.split_once(": ") else { continue };
+            let Some(kind) = tunnel_kind(name) else { continue };
+            let tunnel = Tunnel { name: name.to_string(), kind, description: None, addresses: Vec::new() };
+            current = Some((tunnel, rest.contains("<UP")));
+            continue;
+        }
+        let Some((tunnel, _)) = current.as_mut() else { continue };
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("inet"), Some(address)) => tunnel.addresses.push(address.to_string()),
+            (Some("inet6"), Some(address)) if !address.starts_with("fe80") => {
+                tunnel.addresses.push(address.split('%').next().unwrap_or(address).to_string())
+            }
+            _ => {}
+        }
+    }
+    tunnels.extend(current.filter(|(tunnel, up)| *up && !tunnel.addresses.is_empty()).map(|(tunnel, _)| tunnel));
+    tunnels
+}
+
+// `* (Connected)  <id> PPP --> L2TP  "Work VPN"  [PPP:L2TP]`
+fn parse_scutil_nc(output: &str) -> Vec<Service> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let status = line.split_once('(')?.1.split_once(')')?.0;
+            let name = line.split_once('"')?.1.split_once('"')?.0;
+            Some(Service { name: name.to_string(), status: status.to_string() })
+        })
+        .collect()
+}
+
+fn windows(vpn: &mut Vpn) {
+    let powershell = system32().join(r"WindowsPowerShell\v1.0\powershell.exe");
+    let powershell = powershell.to_string_lossy();
+    let run = |script: &str| read_output(&powershell, &["-NoProfile", "-NonInteractive", "-Command", script]);
+
+    // Adapters whose driver belongs to a VPN, by description
+    let drivers = ["TAP-Windows", "WireGuard", "Wintun", "Tailscale", "ZeroTier", "AnyConnect", "PANGP", "Fortinet", "Juniper", "Pulse", "VPN"];
+    let adapters = run(
+        "Get-NetAdapter | Where-Object Status -eq 'Up' | ForEach-Object { \
+         \"$($_.Name)`t$($_.InterfaceDescription)`t$((Get-NetIPAddress -InterfaceIndex $_.ifIndex -ErrorAction SilentlyContinue).IPAddress -join ',')\" }",
+    );
+    for line in adapters.unwrap_or_default().lines() {
+        let fields: Vec<&str> = line.trim().split('\t').collect();
+        let [name, description, addresses] = fields[..] else { continue };
+        let lower = description.to_lowercase();
+        if !drivers.iter().any(|driver| lower.contains(&driver.to_lowercase())) {
+            continue;
+        }
+        let kind = if lower.contains("wireguard") {
+            "wireguard"
+        } else if lower.contains("tap") {
+            "tap"
+        } else {
+            "adapter"
+        };
+        vpn.tunnels.push(Tunnel {
+            name: name.to_string(),
+            kind,
+            description: Some(description.to_string()),
+            addresses: addresses.split(',').filter(|address| !address.is_empty()).map(str::to_string).collect(),
+        });
+    }
+
+    let connections = run("Get-VpnConnection | ForEach-Object { \"$($_.Name)`t$($_.ConnectionStatus)\" }");
+    vpn.services = connections
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.trim().split_once('\t'))
+        .map(|(name, status)| Service { name: name.to_string(), status: status.to_string() })
+        .collect();
+    // A connected built-in VPN shows up as a PPP adapter rather than one of
+    // the drivers above
+    vpn.route_interface = read_trimmed(
+        &powershell,
+        &[
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            &format!("(Find-NetRoute -RemoteIPAddress {} | Select-Object -First 1).InterfaceAlias", ROUTE_PROBE),
+        ],
+    );
+    if let Some(route) = vpn.route_interface.as_deref() {
+        let service = vpn.services.iter().any(|service| service.name == route && connected(service));
+        if service && !vpn.tunnels.iter().any(|tunnel| tunnel.name == route) {
+            vpn.tunnels.push(Tunnel { name: route.to_string(), kind: "ppp", description: None, addresses: Vec::new() });
+        }
+    }
+}
+
+fn linux(vpn: &mut Vpn) {
+    let interfaces = std::fs::read_dir("/sys/class/net").map(|entries| entries.flatten().collect::<Vec<_>>()).unwrap_or_default();
+    for entry in interfaces {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        let uevent = std::fs::read_to_string(path.join("uevent")).unwrap_or_default();
+        let kind = if uevent.contains("DEVTYPE=wireguard") {
+            Some("wireguard")
+        } else if path.join("tun_flags").exists() {
+            // tun_flags carries IFF_TAP (0x2) for tap devices
+            let flags = std::fs::read_to_string(path.join("tun_flags")).unwrap_or_default();
+            let flags = u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16).unwrap_or(0);
+            Some(if flags & 0x2 != 0 { "tap" } else { "tun" })
+        } else {
+            tunnel_kind(&name)
+        };
+        let Some(kind) = kind else { continue };
+        // Tunnels report "unknown" rather than "up"
+        let state = std::fs::read_to_string(path.join("operstate")).unwrap_or_default();
+        if state.trim() == "down" {
+            continue;
+        }
+        let addresses: Vec<String> = read_output("ip", &["-o", "addr", "show", "dev", &name])
+            .map(|output| {
+                output
+                    .lines()
+                    .filter_map(|line| {
+                        let mut words = line.split_whitespace().skip_while(|word| *word != "inet" && *word != "inet6");
+                        words.next()?;
+                        let address = words.next()?.split('/').next()?;
+                        (!address.starts_with("fe80")).then(|| address.to_string())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if addresses.is_empty() {
+            continue;
+        }
+        vpn.tunnels.push(Tunnel { name, kind, description: None, addresses });
+    }
+
+    vpn.services = read_output("nmcli", &["-t", "-f", "NAME,TYPE,STATE", "connection", "show", "--active"])
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            // nmcli -t escapes colons in names as "\:"
+            let fields: Vec<String> = line.replace("\\:", "\u{0}").split(':').map(|field| field.replace('\u{0}', ":")).collect();
+            let [name, kind, state] = &fields[..] else { return None };
+            matches!(kind.as_str(), "vpn" | "wireguard").then(|| Service { name: name.clone(), status: state.clone() })
+        })
+        .collect();
+
+    // `ip route get` follows policy routing (wg-quick's fwmark table);
+    // /proc/net/route only has the main table
+    vpn.route_interface = read_output("ip", &["route", "get", ROUTE_PROBE])
+        .and_then(|output| {
+            let mut words = output.split_whitespace().skip_while(|word| *word != "dev");
+            words.next()?;
+            words.next().map(str::to_string)
+        })
+        .or_else(proc_route_interface);
+}
+
+// The most specific route in /proc/net/route covering ROUTE_PROBE
+fn proc_route_interface() -> Option<String> {
+    let probe = u32::from_le_bytes(ROUTE_PROBE.parse::<std::net::Ipv4Addr>().ok()?.octets());
+    let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+    routes
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let destination = u32::from_str_radix(fields.get(1)?, 16).ok()?;
+            let mask = u32::from_str_radix(fields.get(7)?, 16).ok()?;
+            let metric: u32 = fields.get(6)?.parse().ok()?;
+            (probe & mask == destination).then(|| (mask.count_ones(), std::cmp::Reverse(metric), fields[0].to_string()))
+        })
+        .max()
+        .map(|(_, _, interface)| interface)
+}
+
+// Running processes that belong to a known VPN client, one per client
+fn clients() -> Vec<Client> {
+    let mut clients: Vec<Client> = Vec::new();
+    for process in process_names() {
+        let key = process.to_lowercase();
+        let key = key.strip_suffix(".exe").unwrap_or(&key);
+        let Some((_, name)) = CLIENTS.iter().find(|(known, _)| *known == key) else { continue };
+        if !clients.iter().any(|client| client.name == *name) {
+            clients.push(Client { name, process });
+        }
+    }
+    clients
+}
+
+fn process_names() -> Vec<String> {
+    match std::env::consts::OS {
+        // -c gives the executable name rather than the full path
+        "macos" => read_output("/bin/ps", &["-axco", "comm="])
+            .map(|output| output.lines().map(|line| line.trim().to_string()).collect())
+            .unwrap_or_default(),
+        "windows" => {
+            let tasklist = system32().join("tasklist.exe");
+            read_output(&tasklist.to_string_lossy(), &["/FO", "CSV", "/NH"])
+                .map(|output| {
+                    output.lines().filter_map(|line| line.split('"').nth(1).map(str::to_string)).collect()
+                })
+                .unwrap_or_default()
+        }
+        _ => std::fs::read_dir("/proc")
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|entry| entry.file_name().to_string_lossy().chars().all(|c| c.is_ascii_digit()))
+                    .filter_map(|entry| std::fs::read_to_string(entry.path().join("comm")).ok())
+                    .map(|name| name.trim().to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
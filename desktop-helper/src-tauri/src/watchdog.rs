@@ -0,0 +1,268 @@
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::read_output;
+use crate::{audit, outbound, pairing, pipeline, storage};
+
+// Executions in flight; anything still listed at startup was cut short by a crash
+const JOURNAL_FILE: &str = "running_executions.json";
+const INTERRUPTED_PATH: &str = "/api/automation/helper/interrupted";
+const REPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct TrackedProcess {
+    pid: u32,
+    program: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct RunningExecution {
+    id: String,
+    action_id: String,
+    kind: String,
+    started_at: DateTime<Utc>,
+    rollback_id: Option<String>,
+    // The interruption is reported against the same approval. Never the
+    // approval token: it mustn't be persisted, and it has usually expired
+    // by the time the helper is back. Entries from before this was kept
+    // have none and are only closed out locally.
+    #[serde(default)]
+    approval_id: String,
+    processes: Vec<TrackedProcess>,
+}
+
+static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+// Executions the user stopped; checked before each command so nothing new starts
+static ABORTED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+// What abort_all stopped
+#[derive(Debug, Serialize, Clone)]
+pub struct AbortedExecution {
+    pub action_id: String,
+    pub kind: String,
+    pub started_at: DateTime<Utc>,
+    pub killed_pids: Vec<u32>,
+}
+
+// Journal entry for one execution, removed when dropped
+pub struct Running {
+    id: String,
+}
+
+// `execution_id` is the pipeline run's, so a recovered entry closes out the same run
+pub fn begin(execution_id: &str, action_id: &str, kind: &str, approval_id: &str, rollback_id: Option<&str>) -> Running {
+    let entry = RunningExecution {
+        id: execution_id.to_string(),
+        action_id: action_id.to_string(),
+        kind: kind.to_string(),
+        started_at: Utc::now(),
+        rollback_id: rollback_id.map(str::to_string),
+        approval_id: approval_id.to_string(),
+        processes: vec![],
+    };
+    let id = entry.id.clone();
+    update(|entries| entries.push(entry));
+    Running { id }
+}
+
+impl Running {
+    pub fn track(&self, pid: u32, program: &str) {
+        track(&self.id, pid, program);
+    }
+
+    // For callers that can't hold on to the guard itself (plugin host calls)
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    pub fn aborted(&self) -> bool {
+        is_aborted(&self.id)
+    }
+}
+
+pub fn has_running() -> bool {
+    let _guard = JOURNAL_LOCK.lock().unwrap();
+    !storage::load_json::<Vec<RunningExecution>>(JOURNAL_FILE).is_empty()
+}
+
+pub fn is_aborted(execution_id: &str) -> bool {
+    ABORTED.lock().unwrap().contains(execution_id)
+}
+
+// Remember a child so it can be cleaned up if the helper dies first
+pub fn track(execution_id: &str, pid: u32, program: &str) {
+    update(|entries| {
+        if let Some(entry) = entries.iter_mut().find(|e| e.id == execution_id) {
+            entry.processes.push(TrackedProcess { pid, program: program.to_string() });
+        }
+    });
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        update(|entries| entries.retain(|e| e.id != self.id));
+        ABORTED.lock().unwrap().remove(&self.id);
+    }
+}
+
+fn update(f: impl FnOnce(&mut Vec<RunningExecution>)) {
+    let _guard = JOURNAL_LOCK.lock().unwrap();
+    let mut entries: Vec<RunningExecution> = storage::load_json(JOURNAL_FILE);
+    f(&mut entries);
+    if let Err(e) = storage::save_json(JOURNAL_FILE, &entries) {
+        log::error!("Failed to persist execution journal: {}", e);
+    }
+}
+
+// Stop every execution in flight: flag it so its remaining commands are
+// skipped, then kill the children it already started
+pub fn abort_all() -> Vec<AbortedExecution> {
+    let entries: Vec<RunningExecution> = {
+        let _guard = JOURNAL_LOCK.lock().unwrap();
+        storage::load_json(JOURNAL_FILE)
+    };
+    ABORTED.lock().unwrap().extend(entries.iter().map(|e| e.id.clone()));
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let killed_pids: Vec<u32> = entry
+                .processes
+                .iter()
+                .filter(|p| still_running(p))
+                .filter(|p| kill(p.pid))
+                .map(|p| p.pid)
+                .collect();
+            audit::record("execution_aborted", serde_json::json!({
+                "action_id": entry.action_id,
+                "kind": entry.kind,
+                "started_at": entry.started_at.to_rfc3339(),
+                "killed_pids": killed_pids,
+            }));
+            AbortedExecution {
+                action_id: entry.action_id,
+                kind: entry.kind,
+                started_at: entry.started_at,
+                killed_pids,
+            }
+        })
+        .collect()
+}
+
+// At startup: kill children left behind by executions that never finished,
+// then close out their runs as interrupted, which records them in history and
+// tells the server (signed with the device key) so their approvals resolve
+pub fn recover(client: reqwest::Client) {
+    let stale: Vec<RunningExecution> = {
+        let _guard = JOURNAL_LOCK.lock().unwrap();
+        let entries = storage::load_json(JOURNAL_FILE);
+        if let Err(e) = storage::save_json(JOURNAL_FILE, &Vec::<RunningExecution>::new()) {
+            log::error!("Failed to reset execution journal: {}", e);
+        }
+        entries
+    };
+    if stale.is_empty() {
+        return;
+    }
+
+    log::info!("Recovering {} interrupted execution(s)", stale.len());
+    let interrupted: Vec<(RunningExecution, Vec<u32>)> = stale
+        .into_iter()
+        .map(|entry| {
+            let killed: Vec<u32> = entry
+                .processes
+                .iter()
+                .filter(|p| still_running(p))
+                .filter(|p| kill(p.pid))
+                .map(|p| p.pid)
+                .collect();
+            (entry, killed)
+        })
+        .collect();
+
+    tauri::async_runtime::spawn(async move {
+        for (entry, killed) in interrupted {
+            let mut message = format!(
+                "Interrupted: the helper stopped before {} of {} (started {}) finished",
+                entry.kind,
+                entry.action_id,
+                entry.started_at.to_rfc3339()
+            );
+            if !killed.is_empty() {
+                message.push_str(&format!("; stopped leftover process(es) {:?}", killed));
+            }
+            pipeline::finish_interrupted(&entry.id, &entry.action_id, &entry.kind, entry.rollback_id.clone(), message.clone());
+            if entry.approval_id.is_empty() || !pairing::status().paired {
+                continue;
+            }
+            if let Err(e) = report_interrupted(&client, &entry, &message).await {
+                log::error!("Failed to report interrupted {} of {}: {}", entry.kind, entry.action_id, e);
+            }
+        }
+    });
+}
+
+// Signed with the device key like the other reports from a paired helper,
+// and sent only to the pinned server
+async fn report_interrupted(client: &reqwest::Client, entry: &RunningExecution, message: &str) -> Result<(), String> {
+    let url = outbound::pinned_url(client, INTERRUPTED_PATH).await?;
+    let device_id = pairing::status().device_id;
+    let payload = serde_json::json!({
+        "device_id": device_id,
+        "event": "execution_interrupted",
+        "approval_id": entry.approval_id,
+        "action_id": entry.action_id,
+        "kind": entry.kind,
+        "execution_id": entry.id,
+        "rollback_id": entry.rollback_id,
+        "started_at": entry.started_at.to_rfc3339(),
+        "message": message,
+    })
+    .to_string();
+    let signature = pairing::sign(payload.as_bytes())?;
+
+    client
+        .post(url)
+        .timeout(REPORT_TIMEOUT)
+        .json(&serde_json::json!({
+            "deviceId": device_id,
+            "payload": payload,
+            "signature": signature,
+        }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// The pid must still belong to the program we started; pids get reused
+fn still_running(process: &TrackedProcess) -> bool {
+    let name = std::path::Path::new(&process.program)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let pid = process.pid.to_string();
+    let listing = match std::env::consts::OS {
+        "windows" => read_output("tasklist", &["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"]),
+        _ => read_output("ps", &["-p", &pid, "-o", "comm="]),
+    };
+    listing.is_some_and(|out| !name.is_empty() && out.to_lowercase().contains(&name))
+}
+
+fn kill(pid: u32) -> bool {
+    let pid = pid.to_string();
+    let killed = match std::env::consts::OS {
+        "windows" => read_output("taskkill", &["/PID", &pid, "/T", "/F"]).is_some(),
+        _ => read_output("kill", &["-TERM", &pid]).is_some(),
+    };
+    if killed {
+        log::info!("Killed process {}", pid);
+    }
+    killed
+}
@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+
+use axum::Json;
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::cmd::read_output;
+use crate::exec_context::system32;
+
+// GET /net/wifi: the current Wi-Fi link, so the assistant can spot a weak
+// signal or a crowded 2.4 GHz channel before suggesting fixes. macOS asks
+// wdutil (root only), then airport (gone since macOS 14.4), then
+// system_profiler; Windows parses `netsh wlan show interfaces` (English
+// labels); Linux asks nmcli, and iw for the signal in dBm.
+const AIRPORT: &str = "/System/Library/PrivateFrameworks/Apple80211.framework/Versions/Current/Resources/airport";
+// Below this the link is weak enough to drop packets
+const WEAK_RSSI_DBM: i32 = -70;
+const LOW_SNR_DB: i32 = 20;
+
+#[derive(Debug, Serialize, Default)]
+pub struct Wifi {
+    connected: bool,
+    interface: Option<String>,
+    ssid: Option<String>,
+    bssid: Option<String>,
+    channel: Option<u32>,
+    // "2.4GHz", "5GHz" or "6GHz"
+    band: Option<String>,
+    channel_width_mhz: Option<u32>,
+    rssi_dbm: Option<i32>,
+    noise_dbm: Option<i32>,
+    snr_db: Option<i32>,
+    // Windows and nmcli report signal as a percentage; rssi_dbm is then
+    // estimated from it
+    signal_percent: Option<u32>,
+    rssi_estimated: bool,
+    tx_rate_mbps: Option<f64>,
+    security: Option<String>,
+    phy_mode: Option<String>,
+    // Which tool answered
+    source: Option<&'static str>,
+    // "weak_signal", "low_snr", "band_2_4ghz"
+    warnings: Vec<&'static str>,
+    error: Option<String>,
+}
+
+// GET /net/wifi
+pub async fn wifi_handler() -> Json<serde_json::Value> {
+    let wifi = tauri::async_runtime::spawn_blocking(current).await.unwrap_or_else(|e| Wifi {
+        error: Some(format!("Wi-Fi check failed: {}", e)),
+        ..Default::default()
+    });
+    Json(serde_json::json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "wifi": wifi,
+    }))
+}
+
+fn current() -> Wifi {
+    let found = match std::env::consts::OS {
+        "macos" => read_output("/usr/bin/wdutil", &["info"])
+            .and_then(|output| parse_wdutil(&output))
+            .or_else(|| read_output(AIRPORT, &["-I"]).and_then(|output| parse_airport(&output)))
+            .or_else(|| {
+                read_output("/usr/sbin/system_profiler", &["SPAirPortDataType", "-json"])
+                    .and_then(|output| parse_system_profiler(&output))
+            }),
+        "windows" => {
+            let netsh = system32().join("netsh.exe");
+            read_output(&netsh.to_string_lossy(), &["wlan", "show", "interfaces"]).and_then(|output| parse_netsh(&output))
+        }
+        _ => read_output("nmcli", &["-t", "-f", "ACTIVE,SSID,BSSID,CHAN,FREQ,RATE,SIGNAL,SECURITY", "device", "wifi", "list", "--rescan", "no"])
+            .and_then(|output| parse_nmcli(&output))
+            .map(with_iw_signal),
+    };
+    let mut wifi = found.unwrap_or_else(|| Wifi {
+        error: Some("No Wi-Fi connection found (or no tool to ask on this system)".to_string()),
+        ..Default::default()
+    });
+    assess(&mut wifi);
+    wifi
+}
+
+fn assess(wifi: &mut Wifi) {
+    if let (Some(rssi), Some(noise)) = (wifi.rssi_dbm, wifi.noise_dbm) {
+        wifi.snr_db = Some(rssi - noise);
+    }
+    if wifi.band.is_none() {
+        wifi.band = wifi.channel.map(|channel| if channel <= 14 { "2.4GHz" } else { "5GHz" }.to_string());
+    }
+    if wifi.rssi_dbm.is_some_and(|rssi| rssi < WEAK_RSSI_DBM) {
+        wifi.warnings.push("weak_signal");
+    }
+    if wifi.snr_db.is_some_and(|snr| snr < LOW_SNR_DB) {
+        wifi.warnings.push("low_snr");
+    }
+    if wifi.band.as_deref() == Some("2.4GHz") {
+        wifi.warnings.push("band_2_4ghz");
+    }
+}
+
+// "Key : value" lines, keys trimmed and lowercased; the first occurrence
+// wins. Values keep any later separators (a BSSID's colons).
+fn fields(output: &str, separator: char) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    for line in output.lines() {
+        if let Some((key, value)) = line.split_once(separator) {
+            fields.entry(key.trim().to_lowercase()).or_insert_with(|| value.trim().to_string());
+        }
+    }
+    fields
+}
+
+fn leading_number<T: std::str::FromStr>(value: &str) -> Option<T> {
+    let number: String = value.trim().chars().take_while(|c| c.is_ascii_digit() || *c == '-' || *c == '.').collect();
+    number.parse().ok()
+}
+
+fn present(value: Option<&String>) -> Option<String> {
+    value.filter(|value| !value.is_empty() && value.as_str() != "None").cloned()
+}
+
+// The WIFI section of `wdutil info`; "Channel : 5g36/80"
+fn parse_wdutil(output: &str) -> Option<Wifi> {
+    let section: String = output
+        .lines()
+        .skip_while(|line| line.trim() != "WIFI")
+        .skip(1)
+        .take_while(|line| !line.trim().starts_with("————") && !line.trim().chars().all(|c| c.is_ascii_uppercase()))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    let fields = fields(&section, ':');
+    let ssid = present(fields.get("ssid"))?;
+    let mut wifi = Wifi {
+        connected: true,
+        interface: present(fields.get("interface name")),
+        ssid: Some(ssid),
+        bssid: present(fields.get("bssid")),
+        rssi_dbm: fields.get("rssi").and_then(|v| leading_number(v)),
+        noise_dbm: fields.get("noise").and_then(|v| leading_number(v)),
+        tx_rate_mbps: fields.get("tx rate").and_then(|v| leading_number(v)),
+        security: present(fields.get("security")),
+        phy_mode: present(fields.get("phy mode")),
+        source: Some("wdutil"),
+        ..Default::default()
+    };
+    if let Some(channel) = fields.get("channel") {
+        let (band, rest) = channel.split_at(channel.find(|c: char| c.is_ascii_alphabetic()).map(|i| i + 1).unwrap_or(0));
+        wifi.band = match band {
+            "2g" => Some("2.4GHz".to_string()),
+            "5g" => Some("5GHz".to_string()),
+            "6g" => Some("6GHz".to_string()),
+            _ => None,
+        };
+        let (number, width) = rest.split_once('/').unwrap_or((rest, ""));
+        wifi.channel = number.parse().ok();
+        wifi.channel_width_mhz = width.parse().ok();
+    }
+    Some(wifi)
+}
+
+// `airport -I`; "channel: 36,80" (older versions put a width code, not MHz,
+// after the comma)
+fn parse_airport(output: &str) -> Option<Wifi> {
+    let fields = fields(output, ':');
+    let ssid = present(fields.get("ssid"))?;
+    let (channel, width) = fields.get("channel").map(|c| c.split_once(',').unwrap_or((c, "20"))).unwrap_or_default();
+    Some(Wifi {
+        connected: true,
+        ssid: Some(ssid),
+        bssid: present(fields.get("bssid")),
+        channel: channel.parse().ok(),
+        channel_width_mhz: width.parse().ok().filter(|width| *width >= 20),
+        rssi_dbm: fields.get("agrctlrssi").and_then(|v| leading_number(v)),
+        noise_dbm: fields.get("agrctlnoise").and_then(|v| leading_number(v)),
+        tx_rate_mbps: fields.get("lasttxrate").and_then(|v| leading_number(v)),
+        security: present(fields.get("link auth")),
+        source: Some("airport"),
+        ..Default::default()
+    })
+}
+
+// spairport_current_network_information of the first interface that has one
+fn parse_system_profiler(output: &str) -> Option<Wifi> {
+    let report: serde_json::Value = serde_json::from_str(output).ok()?;
+    let interfaces = report["SPAirPortDataType"][0]["spairport_airport_interfaces"].as_array()?;
+    let (interface, network) = interfaces
+        .iter()
+        .find_map(|interface| interface.get("spairport_current_network_information").map(|network| (interface, network)))?;
+    let text = |key: &str| network[key].as_str().map(str::to_string);
+    // "36 (5GHz, 80MHz)"
+    let channel = network["spairport_network_channel"].as_str().unwrap_or_default();
+    let details: Vec<&str> = channel
+        .split_once('(')
+        .map(|(_, rest)| rest.trim_end_matches(')').split(',').map(str::trim).collect())
+        .unwrap_or_default();
+    // "-55 dBm / -92 dBm"
+    let (signal, noise) = network["spairport_signal_noise"].as_str().and_then(|s| s.split_once('/')).unwrap_or_default();
+    Some(Wifi {
+        connected: true,
+        interface: interface["_name"].as_str().map(str::to_string),
+        // Redacted by macOS without Location Services permission
+        ssid: text("_name").filter(|ssid| ssid != "<redacted>"),
+        channel: leading_number(channel),
+        band: details.first().map(|band| band.to_string()).filter(|band| band.ends_with("GHz")),
+        channel_width_mhz: details.get(1).and_then(|width| leading_number(width)),
+        rssi_dbm: leading_number(signal),
+        noise_dbm: leading_number(noise),
+        tx_rate_mbps: network["spairport_network_rate"].as_f64(),
+        security: text("spairport_security_mode").map(|mode| {
+            mode.trim_start_matches("spairport_security_mode_").replace('_', " ")
+        }),
+        phy_mode: text("spairport_network_phymode"),
+        source: Some("system_profiler"),
+        ..Default::default()
+    })
+}
+
+// `netsh wlan show interfaces`, first connected interface
+fn parse_netsh(output: &str) -> Option<Wifi> {
+    let block = output.split("\r\n\r\n").chain(output.split("\n\n")).find(|block| {
+        fields(block, ':').get("state").is_some_and(|state| state == "connected")
+    })?;
+    let fields = fields(block, ':');
+    let signal_percent: Option<u32> = fields.get("signal").and_then(|v| leading_number(v));
+    Some(Wifi {
+        connected: true,
+        interface: present(fields.get("name")),
+        ssid: present(fields.get("ssid")),
+        bssid: present(fields.get("bssid").or(fields.get("ap bssid"))),
+        channel: fields.get("channel").and_then(|v| leading_number(v)),
+        band: fields.get("band").map(|band| band.replace(' ', "")),
+        signal_percent,
+        // Windows maps -100..-50 dBm onto 0..100%
+        rssi_dbm: signal_percent.map(|percent| percent as i32 / 2 - 100),
+        rssi_estimated: signal_percent.is_some(),
+        tx_rate_mbps: fields.get("transmit rate (mbps)").and_then(|v| leading_number(v)),
+        security: present(fields.get("authentication")),
+        phy_mode: present(fields.get("radio type")),
+        source: Some("netsh"),
+        ..Default::default()
+    })
+}
+
+// nmcli -t escapes the colons inside BSSIDs as "\:"
+fn parse_nmcli(output: &str) -> Option<Wifi> {
+    let line = output.lines().find(|line| line.starts_with("yes:"))?;
+    let placeholder = '\u{1}';
+    let unescaped = line.replace("\\:", &placeholder.to_string());
+    let columns: Vec<String> = unescaped.split(':').map(|column| column.replace(placeholder, ":")).collect();
+    let column = |index: usize| columns.get(index).filter(|value| !value.is_empty()).cloned();
+    let frequency: Option<u32> = column(4).and_then(|f| leading_number(&f));
+    let signal_percent: Option<u32> = column(6).and_then(|s| leading_number(&s));
+    Some(Wifi {
+        connected: true,
+        ssid: column(1),
+        bssid: column(2),
+        channel: column(3).and_then(|c| leading_number(&c)),
+        band: frequency.map(|mhz| match mhz {
+            0..=2999 => "2.4GHz",
+            3000..=5924 => "5GHz",
+            _ => "6GHz",
+        }.to_string()),
+        tx_rate_mbps: column(5).and_then(|r| leading_number(&r)),
+        signal_percent,
+        rssi_dbm: signal_percent.map(|percent| percent as i32 / 2 - 100),
+        rssi_estimated: signal_percent.is_some(),
+        security: column(7),
+        source: Some("nmcli"),
+        ..Default::default()
+    })
+}
+
+// The measured signal from `iw dev <interface> link`, when iw is installed
+fn with_iw_signal(mut wifi: Wifi) -> Wifi {
+    let Some(devices) = read_output("iw", &["dev"]) else { return wifi };
+    let interfaces = devices.lines().filter_map(|line| line.trim().strip_prefix("Interface ").map(str::to_string));
+    for interface in interfaces {
+        let Some(link) = read_output("iw", &["dev", &interface, "link"]) else { continue };
+        if !link.starts_with("Connected") {
+            continue;
+        }
+        let fields = fields(&link, ':');
+        if let Some(signal) = fields.get("signal").and_then(|v| leading_number(v)) {
+            wifi.rssi_dbm = Some(signal);
+            wifi.rssi_estimated = false;
+        }
+        wifi.interface = Some(interface);
+        break;
+    }
+    wifi
+}
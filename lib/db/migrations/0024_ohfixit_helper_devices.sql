@@ -0,0 +1,16 @@
+-- OhFixIt: Desktop helper pairing
+
+CREATE TABLE IF NOT EXISTS "HelperDevice" (
+  "id" uuid PRIMARY KEY NOT NULL,
+  "userId" uuid REFERENCES "User"("id"),
+  "publicKey" text NOT NULL,
+  "pairingCode" varchar(16),
+  "os" varchar(32) NOT NULL,
+  "name" varchar(128) NOT NULL,
+  "pairedAt" timestamp,
+  "lastSeenAt" timestamp NOT NULL DEFAULT now(),
+  "createdAt" timestamp NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS "HelperDevice_pairingCode_idx" ON "HelperDevice" ("pairingCode");
+CREATE INDEX IF NOT EXISTS "HelperDevice_userId_idx" ON "HelperDevice" ("userId");
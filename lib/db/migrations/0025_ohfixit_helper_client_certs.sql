@@ -0,0 +1,4 @@
+-- OhFixIt: Desktop helper client certificates
+
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "clientCertFingerprint" varchar(64);
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "clientCertExpiresAt" timestamp;
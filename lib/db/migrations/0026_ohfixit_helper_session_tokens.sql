@@ -0,0 +1,3 @@
+-- OhFixIt: Desktop helper local API session tokens
+
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "sessionToken" text;
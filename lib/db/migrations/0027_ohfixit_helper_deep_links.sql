@@ -0,0 +1,13 @@
+-- OhFixIt: Desktop helper deep links
+
+CREATE TABLE IF NOT EXISTS "HelperDeepLink" (
+  "nonce" varchar(64) PRIMARY KEY NOT NULL,
+  "userId" uuid NOT NULL REFERENCES "User"("id"),
+  "kind" varchar(16) NOT NULL,
+  "actionId" varchar(64),
+  "deviceId" uuid REFERENCES "HelperDevice"("id"),
+  "helperToken" text,
+  "expiresAt" timestamp NOT NULL,
+  "redeemedAt" timestamp,
+  "createdAt" timestamp NOT NULL DEFAULT now()
+);
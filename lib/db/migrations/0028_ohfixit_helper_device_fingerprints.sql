@@ -0,0 +1,3 @@
+-- OhFixIt: Desktop helper hardware fingerprints
+
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "fingerprint" varchar(64);
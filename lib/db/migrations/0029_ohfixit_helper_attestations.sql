@@ -0,0 +1,6 @@
+-- OhFixIt: Desktop helper build attestations
+
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "helperVersion" varchar(32);
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "binarySha256" varchar(64);
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "codeSignature" varchar(16);
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "attestedAt" timestamp;
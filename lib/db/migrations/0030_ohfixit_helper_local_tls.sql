@@ -0,0 +1,4 @@
+-- OhFixIt: Pinned certificate of the desktop helper's local API
+
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "localCertificate" text;
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "localCertFingerprint" varchar(64);
@@ -0,0 +1,5 @@
+-- OhFixIt: Desktop helper lockouts after repeated invalid approval tokens
+
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "lockedOutUntil" timestamp;
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "lockoutSource" varchar(128);
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "lockoutFailures" integer;
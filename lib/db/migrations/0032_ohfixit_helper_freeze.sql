@@ -0,0 +1,4 @@
+-- OhFixIt: Server-side execution freeze for desktop helpers
+
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "frozenAt" timestamp;
+ALTER TABLE "HelperDevice" ADD COLUMN IF NOT EXISTS "frozenReason" varchar(256);
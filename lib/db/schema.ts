@@ -239,6 +239,59 @@ export const deviceProfile = pgTable('DeviceProfile', {
 
 export type DeviceProfile = InferSelectModel<typeof deviceProfile>;
 
+// Desktop helpers paired with an account. A row is created (userId null) when
+// a helper registers its key and pairing code, and bound to the user who
+// enters that code. Approval tokens name the deviceId they were minted for.
+export const helperDevice = pgTable('HelperDevice', {
+  id: uuid('id').primaryKey().notNull(), // generated by the helper
+  userId: uuid('userId').references(() => user.id), // null until paired
+  publicKey: text('publicKey').notNull(), // Ed25519, base64
+  pairingCode: varchar('pairingCode', { length: 16 }),
+  os: varchar('os', { length: 32 }).notNull(),
+  name: varchar('name', { length: 128 }).notNull(),
+  pairedAt: timestamp('pairedAt'),
+  clientCertFingerprint: varchar('clientCertFingerprint', { length: 64 }), // SHA-256 of the pinned client certificate
+  clientCertExpiresAt: timestamp('clientCertExpiresAt'),
+  sessionToken: text('sessionToken'), // sent to the helper's local API (X-OhFixIt-Session)
+  localCertificate: text('localCertificate'), // PEM the helper's local API serves (desktop-helper local_tls.rs)
+  localCertFingerprint: varchar('localCertFingerprint', { length: 64 }), // its SHA-256, checked on every local call
+  fingerprint: varchar('fingerprint', { length: 64 }), // SHA-256 of hardware UUID + install id, copied into approval tokens
+  // Last signed build attestation (desktop-helper attestation.rs)
+  helperVersion: varchar('helperVersion', { length: 32 }),
+  binarySha256: varchar('binarySha256', { length: 64 }),
+  codeSignature: varchar('codeSignature', { length: 16 }), // 'valid' | 'unsigned' | 'invalid' | 'unsupported'
+  attestedAt: timestamp('attestedAt'),
+  // Last time the helper locked out a source for repeated invalid tokens (desktop-helper lockout.rs)
+  lockedOutUntil: timestamp('lockedOutUntil'),
+  lockoutSource: varchar('lockoutSource', { length: 128 }),
+  lockoutFailures: integer('lockoutFailures'),
+  // Set while the owner has frozen execution on this helper (desktop-helper freeze.rs)
+  frozenAt: timestamp('frozenAt'),
+  frozenReason: varchar('frozenReason', { length: 256 }),
+  lastSeenAt: timestamp('lastSeenAt').notNull().defaultNow(),
+  createdAt: timestamp('createdAt').notNull().defaultNow(),
+});
+
+export type HelperDevice = InferSelectModel<typeof helperDevice>;
+
+// ohfixit:// links the web app hands out. The helper checks the nonce here
+// before asking the user, and redeems it (once) after they agree.
+export const helperDeepLink = pgTable('HelperDeepLink', {
+  nonce: varchar('nonce', { length: 64 }).primaryKey().notNull(),
+  userId: uuid('userId')
+    .notNull()
+    .references(() => user.id),
+  kind: varchar('kind', { length: 16 }).notNull(), // 'action' | 'pair'
+  actionId: varchar('actionId', { length: 64 }),
+  deviceId: uuid('deviceId').references(() => helperDevice.id), // null for pair links
+  helperToken: text('helperToken'), // approval token handed to the helper on redemption
+  expiresAt: timestamp('expiresAt').notNull(),
+  redeemedAt: timestamp('redeemedAt'),
+  createdAt: timestamp('createdAt').notNull().defaultNow(),
+});
+
+export type HelperDeepLink = InferSelectModel<typeof helperDeepLink>;
+
 // Playbook execution tables
 
 export const playbookRun = pgTable('PlaybookRun', {
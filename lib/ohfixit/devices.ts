@@ -0,0 +1,684 @@
+import 'server-only';
+
+import { X509Certificate, createHash, createPublicKey, randomBytes, verify } from 'node:crypto';
+import { and, desc, eq, gt, isNotNull, isNull } from 'drizzle-orm';
+import { db } from '@/lib/db/client';
+import { actionLog, helperDeepLink, helperDevice, type HelperDevice } from '@/lib/db/schema';
+import { HELPER_SESSION_HEADER, type PinnedCertificate } from '@/lib/ohfixit/helper-endpoint';
+import { signAutomationToken, tokenScope } from '@/lib/ohfixit/jwt';
+
+// The helper re-registers every 30 seconds while unpaired; a code it stopped
+// announcing is no longer accepted
+const PAIRING_CODE_TTL_MS = 10 * 60 * 1000;
+// Where the TLS terminator puts the client certificate it received, URL-encoded
+// PEM (nginx: proxy_set_header X-Client-Cert $ssl_client_escaped_cert)
+const CLIENT_CERT_HEADER = process.env.OHFIXIT_CLIENT_CERT_HEADER || 'x-client-cert';
+// Long enough to click a link right after it's shown
+const DEEP_LINK_TTL_MS = 5 * 60 * 1000;
+// The helper attests hourly; older than this counts as no attestation
+const ATTESTATION_MAX_AGE_MS = 24 * 60 * 60 * 1000;
+// How far a helper's freeze poll timestamp may be from the server's clock
+const FREEZE_POLL_SKEW_MS = 5 * 60 * 1000;
+
+export type DeviceRegistration = {
+  deviceId: string;
+  publicKey: string; // raw Ed25519 key, base64
+  pairingCode: string;
+  fingerprint: string; // SHA-256 hex of the hardware UUID and deviceId
+  signature: string; // over `${deviceId}.${pairingCode}.${fingerprint}` (desktop-helper pairing.rs)
+  os: string;
+  name: string;
+};
+
+// "abcd efgh" and "ABCD-EFGH" are the same code
+export function normalizePairingCode(code: string): string {
+  const chars = code.toUpperCase().replace(/[^A-Z0-9]/g, '');
+  return chars.length === 8 ? `${chars.slice(0, 4)}-${chars.slice(4)}` : chars;
+}
+
+export type SessionShare = {
+  deviceId: string;
+  sessionToken: string;
+  signature: string; // over `${deviceId}.${sessionToken}`
+};
+
+// Header the desktop helper's local API requires on every request
+export { HELPER_SESSION_HEADER };
+
+export type LocalCertificateShare = {
+  deviceId: string;
+  certificate: string; // the helper's local API certificate, PEM
+  signature: string; // device key signature over the PEM
+};
+
+export type CertificateEnrollment = {
+  deviceId: string;
+  certificate: string; // self-signed client certificate, PEM
+  signature: string; // device key signature over the PEM
+};
+
+function verifyDeviceSignature(publicKey: string, message: string, signature: string): boolean {
+  try {
+    const key = createPublicKey({
+      key: { kty: 'OKP', crv: 'Ed25519', x: Buffer.from(publicKey, 'base64').toString('base64url') },
+      format: 'jwk',
+    });
+    return verify(null, Buffer.from(message), key, Buffer.from(signature, 'base64'));
+  } catch {
+    return false;
+  }
+}
+
+// The registration must be signed by the key it registers
+export function verifyRegistrationSignature(registration: DeviceRegistration): boolean {
+  return verifyDeviceSignature(
+    registration.publicKey,
+    `${registration.deviceId}.${registration.pairingCode}.${registration.fingerprint}`,
+    registration.signature,
+  );
+}
+
+export type AuditExport = {
+  payload: string; // JSON: { device_id, exported_at, from, to, chain, entries }
+  signature: string;
+  algorithm: 'Ed25519';
+  public_key: string;
+};
+
+// A helper's signed audit export (GET /audit/export on the helper), checked
+// against the key the device registered rather than the one it ships with.
+// Returns the parsed payload, or null if it wasn't signed by that device.
+export function verifyAuditExport(exported: AuditExport, registeredPublicKey: string): Record<string, any> | null {
+  if (!verifyDeviceSignature(registeredPublicKey, exported.payload, exported.signature)) return null;
+  try {
+    return JSON.parse(exported.payload);
+  } catch {
+    return null;
+  }
+}
+
+// SHA-256 of the DER encoding, lowercase hex
+export function certificateFingerprint(cert: X509Certificate): string {
+  return createHash('sha256').update(cert.raw).digest('hex');
+}
+
+// A device certificate names the device (CN), is self-signed and currently valid
+export function parseDeviceCertificate(pem: string, deviceId: string): X509Certificate | null {
+  try {
+    const cert = new X509Certificate(pem);
+    const commonName = cert.subject
+      .split('\n')
+      .find((part) => part.startsWith('CN='))
+      ?.slice(3);
+    const now = Date.now();
+    if (commonName !== deviceId) return null;
+    if (Date.parse(cert.validFrom) > now || Date.parse(cert.validTo) < now) return null;
+    return cert.verify(cert.publicKey) ? cert : null;
+  } catch {
+    return null;
+  }
+}
+
+// The client certificate the request's TLS connection presented, if the
+// terminator forwarded one
+export function forwardedClientCertificate(headers: Headers): string | null {
+  const value = headers.get(CLIENT_CERT_HEADER);
+  if (!value) return null;
+  try {
+    return decodeURIComponent(value);
+  } catch {
+    return null;
+  }
+}
+
+// Record (or refresh) a helper's registration. A paired device keeps its key
+// and hardware: a different key or fingerprint for the same id is refused
+// rather than silently re-bound. Devices paired before fingerprints existed
+// record theirs on the next registration.
+export async function registerDevice(
+  registration: DeviceRegistration,
+): Promise<{ status: 'pending' | 'paired' | 'conflict'; pairedAt: Date | null }> {
+  const [existing] = await db.select().from(helperDevice).where(eq(helperDevice.id, registration.deviceId)).limit(1);
+  const now = new Date();
+  if (existing?.pairedAt) {
+    if (existing.publicKey !== registration.publicKey) return { status: 'conflict', pairedAt: null };
+    if (existing.fingerprint && existing.fingerprint !== registration.fingerprint) {
+      return { status: 'conflict', pairedAt: null };
+    }
+    await db
+      .update(helperDevice)
+      .set({ lastSeenAt: now, fingerprint: registration.fingerprint })
+      .where(eq(helperDevice.id, existing.id));
+    return { status: 'paired', pairedAt: existing.pairedAt };
+  }
+
+  const values = {
+    publicKey: registration.publicKey,
+    pairingCode: normalizePairingCode(registration.pairingCode),
+    fingerprint: registration.fingerprint,
+    os: registration.os.slice(0, 32),
+    name: registration.name.slice(0, 128),
+    lastSeenAt: now,
+  };
+  if (existing) {
+    await db.update(helperDevice).set(values).where(eq(helperDevice.id, existing.id));
+  } else {
+    await db.insert(helperDevice).values({ id: registration.deviceId, ...values });
+  }
+  return { status: 'pending', pairedAt: null };
+}
+
+// Bind the helper currently announcing `code` to `userId`
+export async function confirmPairing(code: string, userId: string): Promise<HelperDevice | null> {
+  const since = new Date(Date.now() - PAIRING_CODE_TTL_MS);
+  const [device] = await db
+    .select()
+    .from(helperDevice)
+    .where(
+      and(
+        eq(helperDevice.pairingCode, normalizePairingCode(code)),
+        isNull(helperDevice.pairedAt),
+        gt(helperDevice.lastSeenAt, since),
+      ),
+    )
+    .limit(1);
+  if (!device) return null;
+
+  const pairedAt = new Date();
+  await db
+    .update(helperDevice)
+    .set({ userId, pairedAt, pairingCode: null })
+    .where(eq(helperDevice.id, device.id));
+  return { ...device, userId, pairedAt, pairingCode: null };
+}
+
+// The device an approval token is minted for: the one the caller names if
+// it's paired to them, otherwise their most recently seen helper. Tokens carry
+// both its id and fingerprint (deviceId, deviceFingerprint).
+export async function resolveDevice(
+  userId: string | null,
+  requested?: string | null,
+): Promise<(AttestedBuild & FrozenDevice & { id: string; fingerprint: string | null }) | null> {
+  if (!userId) return null;
+  const rows = await db
+    .select({
+      id: helperDevice.id,
+      fingerprint: helperDevice.fingerprint,
+      helperVersion: helperDevice.helperVersion,
+      binarySha256: helperDevice.binarySha256,
+      codeSignature: helperDevice.codeSignature,
+      attestedAt: helperDevice.attestedAt,
+      frozenAt: helperDevice.frozenAt,
+      frozenReason: helperDevice.frozenReason,
+    })
+    .from(helperDevice)
+    .where(
+      and(
+        eq(helperDevice.userId, userId),
+        isNotNull(helperDevice.pairedAt),
+        ...(requested ? [eq(helperDevice.id, requested)] : []),
+      ),
+    )
+    .orderBy(desc(helperDevice.lastSeenAt))
+    .limit(1);
+  return rows[0] ?? null;
+}
+
+// Pin the client certificate a paired helper presents on its reports. Uploads
+// are signed with the device key, so only the paired helper can replace it.
+export async function enrollCertificate(
+  enrollment: CertificateEnrollment,
+): Promise<{ status: 'enrolled' | 'unpaired' | 'invalid'; expiresAt: Date | null }> {
+  const [device] = await db.select().from(helperDevice).where(eq(helperDevice.id, enrollment.deviceId)).limit(1);
+  if (!device?.pairedAt) return { status: 'unpaired', expiresAt: null };
+  if (!verifyDeviceSignature(device.publicKey, enrollment.certificate, enrollment.signature)) {
+    return { status: 'invalid', expiresAt: null };
+  }
+  const cert = parseDeviceCertificate(enrollment.certificate, device.id);
+  if (!cert) return { status: 'invalid', expiresAt: null };
+
+  const expiresAt = new Date(cert.validTo);
+  await db
+    .update(helperDevice)
+    .set({ clientCertFingerprint: certificateFingerprint(cert), clientCertExpiresAt: expiresAt, lastSeenAt: new Date() })
+    .where(eq(helperDevice.id, device.id));
+  return { status: 'enrolled', expiresAt };
+}
+
+// Whether `pem` is the certificate pinned for `deviceId`
+export async function verifyClientCertificate(pem: string | null, deviceId: string | undefined): Promise<boolean> {
+  if (!pem || !deviceId) return false;
+  const cert = parseDeviceCertificate(pem, deviceId);
+  if (!cert) return false;
+  const [device] = await db
+    .select({ fingerprint: helperDevice.clientCertFingerprint })
+    .from(helperDevice)
+    .where(eq(helperDevice.id, deviceId))
+    .limit(1);
+  return !!device?.fingerprint && device.fingerprint === certificateFingerprint(cert);
+}
+
+// Store the token a paired helper requires on its local API
+export async function shareSessionToken(share: SessionShare): Promise<'stored' | 'unpaired' | 'invalid'> {
+  const [device] = await db.select().from(helperDevice).where(eq(helperDevice.id, share.deviceId)).limit(1);
+  if (!device?.pairedAt) return 'unpaired';
+  if (!verifyDeviceSignature(device.publicKey, `${share.deviceId}.${share.sessionToken}`, share.signature)) {
+    return 'invalid';
+  }
+  await db
+    .update(helperDevice)
+    .set({ sessionToken: share.sessionToken, lastSeenAt: new Date() })
+    .where(eq(helperDevice.id, device.id));
+  return 'stored';
+}
+
+// The helper's local API certificate must be self-signed, currently valid
+// and name localhost
+export function parseLocalCertificate(pem: string): X509Certificate | null {
+  try {
+    const cert = new X509Certificate(pem);
+    const now = Date.now();
+    if (Date.parse(cert.validFrom) > now || Date.parse(cert.validTo) < now) return null;
+    if (!cert.checkHost('localhost')) return null;
+    return cert.verify(cert.publicKey) ? cert : null;
+  } catch {
+    return null;
+  }
+}
+
+// Pin the certificate a paired helper's local API presents. Like the session
+// token it's signed with the device key, so only the helper can replace it.
+export async function shareLocalCertificate(
+  share: LocalCertificateShare,
+): Promise<'stored' | 'unpaired' | 'invalid'> {
+  const [device] = await db.select().from(helperDevice).where(eq(helperDevice.id, share.deviceId)).limit(1);
+  if (!device?.pairedAt) return 'unpaired';
+  if (!verifyDeviceSignature(device.publicKey, share.certificate, share.signature)) return 'invalid';
+  const cert = parseLocalCertificate(share.certificate);
+  if (!cert) return 'invalid';
+  await db
+    .update(helperDevice)
+    .set({ localCertificate: share.certificate, localCertFingerprint: certificateFingerprint(cert), lastSeenAt: new Date() })
+    .where(eq(helperDevice.id, device.id));
+  return 'stored';
+}
+
+// The certificate the user's paired helper serves its local API with, for
+// helperFetch. Null when they have none or it hasn't shared one yet.
+export async function helperPinnedCertificate(userId: string | null | undefined): Promise<PinnedCertificate | null> {
+  if (!userId) return null;
+  const [device] = await db
+    .select({ certificate: helperDevice.localCertificate, fingerprint: helperDevice.localCertFingerprint })
+    .from(helperDevice)
+    .where(and(eq(helperDevice.userId, userId), isNotNull(helperDevice.sessionToken)))
+    .orderBy(desc(helperDevice.lastSeenAt))
+    .limit(1);
+  return device?.certificate && device.fingerprint
+    ? { certificate: device.certificate, fingerprint: device.fingerprint }
+    : null;
+}
+
+// Headers for calling the user's paired helper on localhost. Empty when they
+// have none, which only gets an unpaired helper's /status.
+export async function helperSessionHeaders(userId: string | null | undefined): Promise<Record<string, string>> {
+  if (!userId) return {};
+  const [device] = await db
+    .select({ sessionToken: helperDevice.sessionToken })
+    .from(helperDevice)
+    .where(and(eq(helperDevice.userId, userId), isNotNull(helperDevice.sessionToken)))
+    .orderBy(desc(helperDevice.lastSeenAt))
+    .limit(1);
+  return device?.sessionToken ? { [HELPER_SESSION_HEADER]: device.sessionToken } : {};
+}
+
+export type DeepLinkKind = 'action' | 'pair';
+
+export type DeepLinkRedemption = {
+  deviceId: string;
+  nonce: string;
+  kind: DeepLinkKind;
+  actionId?: string | null;
+  signature: string; // over `${deviceId}.${nonce}`
+  consume: boolean; // false only checks the link, before the helper asks the user
+};
+
+// The URL the helper's deep-link parser accepts (desktop-helper deep_link.rs):
+// ohfixit://action/<actionId>?nonce=... or ohfixit://pair?nonce=...
+export function deepLinkUrl(kind: DeepLinkKind, nonce: string, actionId?: string): string {
+  const path = kind === 'action' ? `action/${actionId}` : kind;
+  return `ohfixit://${path}?nonce=${nonce}`;
+}
+
+// A single-use link for `userId`. Action links carry the approval token the
+// helper gets back when it redeems them, and only work on the device it's for.
+export async function issueDeepLink(link: {
+  userId: string;
+  kind: DeepLinkKind;
+  actionId?: string;
+  deviceId?: string;
+  helperToken?: string;
+}): Promise<{ url: string; nonce: string; expiresAt: Date }> {
+  const nonce = randomBytes(24).toString('base64url');
+  const expiresAt = new Date(Date.now() + DEEP_LINK_TTL_MS);
+  await db.insert(helperDeepLink).values({
+    nonce,
+    userId: link.userId,
+    kind: link.kind,
+    actionId: link.actionId ?? null,
+    deviceId: link.deviceId ?? null,
+    helperToken: link.helperToken ?? null,
+    expiresAt,
+  });
+  return { url: deepLinkUrl(link.kind, nonce, link.actionId), nonce, expiresAt };
+}
+
+// Check (consume: false) or redeem a link a helper was opened with. Redeeming
+// a pair link pairs the helper to the user who issued it.
+export async function redeemDeepLink(
+  redemption: DeepLinkRedemption,
+): Promise<{ status: 'valid' | 'redeemed' | 'unknown' | 'invalid' | 'conflict'; helperToken?: string | null }> {
+  const [device] = await db.select().from(helperDevice).where(eq(helperDevice.id, redemption.deviceId)).limit(1);
+  if (!device || !verifyDeviceSignature(device.publicKey, `${device.id}.${redemption.nonce}`, redemption.signature)) {
+    return { status: 'invalid' };
+  }
+  const [link] = await db
+    .select()
+    .from(helperDeepLink)
+    .where(
+      and(
+        eq(helperDeepLink.nonce, redemption.nonce),
+        isNull(helperDeepLink.redeemedAt),
+        gt(helperDeepLink.expiresAt, new Date()),
+      ),
+    )
+    .limit(1);
+  // Only as what it was issued for, on the device it was issued to
+  if (
+    !link ||
+    link.kind !== redemption.kind ||
+    (link.actionId ?? null) !== (redemption.actionId ?? null) ||
+    (link.deviceId && link.deviceId !== device.id)
+  ) {
+    return { status: 'unknown' };
+  }
+  if (link.kind === 'pair' && device.pairedAt && device.userId !== link.userId) return { status: 'conflict' };
+  if (!redemption.consume) return { status: 'valid' };
+
+  const claimed = await db
+    .update(helperDeepLink)
+    .set({ redeemedAt: new Date(), deviceId: device.id })
+    .where(and(eq(helperDeepLink.nonce, link.nonce), isNull(helperDeepLink.redeemedAt)))
+    .returning({ nonce: helperDeepLink.nonce });
+  if (claimed.length === 0) return { status: 'unknown' };
+  if (link.kind === 'pair' && !device.pairedAt) {
+    await db
+      .update(helperDevice)
+      .set({ userId: link.userId, pairedAt: new Date(), pairingCode: null })
+      .where(and(eq(helperDevice.id, device.id), isNull(helperDevice.pairedAt)));
+  }
+  return { status: 'redeemed', helperToken: link.helperToken };
+}
+
+export type AttestedBuild = Pick<HelperDevice, 'helperVersion' | 'binarySha256' | 'codeSignature' | 'attestedAt'>;
+
+export type AttestationReport = {
+  deviceId: string;
+  payload: string; // JSON: { device_id, attestation: { version, binary_sha256, code_signature, ... } }
+  signature: string; // device key signature over payload
+};
+
+// Store a paired helper's signed build attestation
+export async function recordAttestation(report: AttestationReport): Promise<'recorded' | 'unpaired' | 'invalid'> {
+  const [device] = await db.select().from(helperDevice).where(eq(helperDevice.id, report.deviceId)).limit(1);
+  if (!device?.pairedAt) return 'unpaired';
+  if (!verifyDeviceSignature(device.publicKey, report.payload, report.signature)) return 'invalid';
+  let attestation: Record<string, any>;
+  try {
+    const parsed = JSON.parse(report.payload);
+    if (parsed?.device_id !== device.id) return 'invalid';
+    attestation = parsed.attestation ?? {};
+  } catch {
+    return 'invalid';
+  }
+  await db
+    .update(helperDevice)
+    .set({
+      helperVersion: String(attestation.version ?? '').slice(0, 32) || null,
+      binarySha256: String(attestation.binary_sha256 ?? '').slice(0, 64) || null,
+      codeSignature: String(attestation.code_signature ?? '').slice(0, 16) || null,
+      attestedAt: new Date(),
+      lastSeenAt: new Date(),
+    })
+    .where(eq(helperDevice.id, device.id));
+  return 'recorded';
+}
+
+export type SecurityEventReport = {
+  deviceId: string;
+  payload: string; // JSON: { device_id, event: 'token_lockout', details: { source, failures, locked_until, ... } }
+  signature: string; // device key signature over payload
+};
+
+export type TokenLockout = { source: string; failures: number; lockedUntil: Date };
+
+// The lockout a helper's security event describes, or null when the payload
+// isn't one for `deviceId`
+export function parseTokenLockout(payload: string, deviceId: string): TokenLockout | null {
+  try {
+    const parsed = JSON.parse(payload);
+    if (parsed?.device_id !== deviceId || parsed.event !== 'token_lockout') return null;
+    const lockedUntil = new Date(parsed.details?.locked_until);
+    const failures = Number(parsed.details?.failures);
+    if (Number.isNaN(lockedUntil.getTime()) || !Number.isInteger(failures)) return null;
+    return { source: String(parsed.details?.source ?? 'unknown').slice(0, 128), failures, lockedUntil };
+  } catch {
+    return null;
+  }
+}
+
+// A paired helper locked out a local source after repeated invalid approval
+// tokens. Kept on the device so it shows up next to its pairing.
+export async function recordSecurityEvent(report: SecurityEventReport): Promise<'recorded' | 'unpaired' | 'invalid'> {
+  const [device] = await db.select().from(helperDevice).where(eq(helperDevice.id, report.deviceId)).limit(1);
+  if (!device?.pairedAt) return 'unpaired';
+  if (!verifyDeviceSignature(device.publicKey, report.payload, report.signature)) return 'invalid';
+  const lockout = parseTokenLockout(report.payload, device.id);
+  if (!lockout) return 'invalid';
+  console.warn(
+    `Helper ${device.id} locked out ${lockout.source} after ${lockout.failures} invalid tokens (user ${device.userId})`,
+  );
+  await db
+    .update(helperDevice)
+    .set({
+      lockedOutUntil: lockout.lockedUntil,
+      lockoutSource: lockout.source,
+      lockoutFailures: lockout.failures,
+      lastSeenAt: new Date(),
+    })
+    .where(eq(helperDevice.id, device.id));
+  return 'recorded';
+}
+
+export type InterruptionReport = {
+  deviceId: string;
+  payload: string; // JSON: { device_id, event: 'execution_interrupted', approval_id, action_id, kind, execution_id, message, ... }
+  signature: string; // device key signature over payload
+};
+
+export type Interruption = { approvalId: string; actionId: string; kind: string; executionId: string; message: string };
+
+// The interrupted execution a helper's report describes, or null when the
+// payload isn't one for `deviceId`
+export function parseInterruption(payload: string, deviceId: string): Interruption | null {
+  try {
+    const parsed = JSON.parse(payload);
+    if (parsed?.device_id !== deviceId || parsed.event !== 'execution_interrupted') return null;
+    if (typeof parsed.approval_id !== 'string' || !parsed.approval_id) return null;
+    return {
+      approvalId: parsed.approval_id.slice(0, 64),
+      actionId: String(parsed.action_id ?? '').slice(0, 128),
+      kind: String(parsed.kind ?? 'execute').slice(0, 32),
+      executionId: String(parsed.execution_id ?? '').slice(0, 64),
+      message: String(parsed.message ?? '').slice(0, 1024),
+    };
+  } catch {
+    return null;
+  }
+}
+
+// A paired helper restarted after an execution it never finished. The helper
+// no longer holds the approval token by then, so the report is signed with
+// the device key and closes out the approval's latest action log as a failure.
+export async function recordInterruption(report: InterruptionReport): Promise<'recorded' | 'unknown' | 'unpaired' | 'invalid'> {
+  const [device] = await db.select().from(helperDevice).where(eq(helperDevice.id, report.deviceId)).limit(1);
+  if (!device?.pairedAt) return 'unpaired';
+  if (!verifyDeviceSignature(device.publicKey, report.payload, report.signature)) return 'invalid';
+  const interruption = parseInterruption(report.payload, device.id);
+  if (!interruption) return 'invalid';
+  const recent = await db
+    .select()
+    .from(actionLog)
+    .where(eq(actionLog.actionType, 'script_recommendation'))
+    .orderBy(desc(actionLog.createdAt))
+    .limit(100);
+  const matched = recent.find((row) => {
+    const payload = (row.payload ?? {}) as Record<string, any>;
+    return payload.approvalId === interruption.approvalId && (!row.userId || row.userId === device.userId);
+  });
+  if (!matched) return 'unknown';
+  await db
+    .update(actionLog)
+    .set({
+      status: 'executed',
+      outcome: 'failure',
+      executionHost: 'desktop-helper',
+      payload: {
+        ...((matched.payload ?? {}) as Record<string, unknown>),
+        success: false,
+        interrupted: true,
+        output: interruption.message,
+        executionId: interruption.executionId,
+        kind: interruption.kind,
+      },
+    })
+    .where(eq(actionLog.id, matched.id));
+  await db.update(helperDevice).set({ lastSeenAt: new Date() }).where(eq(helperDevice.id, device.id));
+  return 'recorded';
+}
+
+export type FrozenDevice = Pick<HelperDevice, 'frozenAt' | 'frozenReason'>;
+
+export type FreezeState = { frozen: boolean; reason: string | null };
+
+// Whether a helper should refuse all execution: frozen by its owner, or every
+// helper at once with OHFIXIT_FREEZE_HELPERS=<reason> during an incident
+export function freezeState(device: FrozenDevice, env: NodeJS.ProcessEnv = process.env): FreezeState {
+  const global = env.OHFIXIT_FREEZE_HELPERS?.trim();
+  if (global) return { frozen: true, reason: global };
+  if (device.frozenAt) return { frozen: true, reason: device.frozenReason ?? 'Frozen by the account owner' };
+  return { frozen: false, reason: null };
+}
+
+export type FreezePoll = {
+  deviceId: string;
+  timestamp: number; // seconds since the epoch
+  signature: string; // over `${deviceId}.${timestamp}`
+};
+
+// Answer a helper's signed freeze poll (desktop-helper freeze.rs)
+export async function pollFreeze(poll: FreezePoll): Promise<FreezeState | 'unpaired' | 'invalid'> {
+  const [device] = await db.select().from(helperDevice).where(eq(helperDevice.id, poll.deviceId)).limit(1);
+  if (!device?.pairedAt) return 'unpaired';
+  if (Math.abs(poll.timestamp * 1000 - Date.now()) > FREEZE_POLL_SKEW_MS) return 'invalid';
+  if (!verifyDeviceSignature(device.publicKey, `${poll.deviceId}.${poll.timestamp}`, poll.signature)) return 'invalid';
+  await db.update(helperDevice).set({ lastSeenAt: new Date() }).where(eq(helperDevice.id, device.id));
+  return freezeState(device);
+}
+
+// Freeze or unfreeze the user's paired helpers (one, or all of them). Returns
+// how many were changed. Helpers pick it up within a minute.
+export async function setFreeze(
+  userId: string,
+  change: { frozen: boolean; reason?: string | null; deviceId?: string | null },
+): Promise<number> {
+  const updated = await db
+    .update(helperDevice)
+    .set(
+      change.frozen
+        ? { frozenAt: new Date(), frozenReason: change.reason?.slice(0, 256) || null }
+        : { frozenAt: null, frozenReason: null },
+    )
+    .where(
+      and(
+        eq(helperDevice.userId, userId),
+        isNotNull(helperDevice.pairedAt),
+        ...(change.deviceId ? [eq(helperDevice.id, change.deviceId)] : []),
+      ),
+    )
+    .returning({ id: helperDevice.id });
+  return updated.length;
+}
+
+// A screen:capture token (one minute by default) for the user's paired
+// helper, for its /screenshot, /displays and /screenrecord routes, or why
+// there can't be one
+export async function helperScreenToken(
+  userId: string | null | undefined,
+  ttlSeconds = 60,
+): Promise<{ token: string } | { error: string; status: number }> {
+  const device = await resolveDevice(userId ?? null);
+  if (!device) return { error: 'No paired desktop helper', status: 409 };
+  const buildProblem = attestationProblem(device);
+  if (buildProblem) return { error: buildProblem, status: 403 };
+  const token = await signAutomationToken(
+    {
+      chatId: null,
+      userId: userId ?? null,
+      anonymousId: null,
+      deviceId: device.id,
+      deviceFingerprint: device.fingerprint ?? undefined,
+      scope: tokenScope(['screen:capture']),
+    },
+    ttlSeconds,
+  );
+  return { token };
+}
+
+// -1, 0 or 1 comparing dotted versions ("0.10.2" > "0.9.9")
+export function compareVersions(a: string, b: string): number {
+  const left = a.split(/[.+-]/).map((part) => Number.parseInt(part, 10) || 0);
+  const right = b.split(/[.+-]/).map((part) => Number.parseInt(part, 10) || 0);
+  for (let i = 0; i < Math.max(left.length, right.length); i++) {
+    const diff = (left[i] ?? 0) - (right[i] ?? 0);
+    if (diff !== 0) return Math.sign(diff);
+  }
+  return 0;
+}
+
+// Why the helper's last attestation rules out issuing it execution tokens, or
+// null if nothing does. OHFIXIT_REQUIRE_ATTESTATION=true refuses helpers that
+// haven't attested in a day; OHFIXIT_MIN_HELPER_VERSION and
+// OHFIXIT_HELPER_BUILD_HASHES (comma-separated SHA-256s of release binaries)
+// refuse outdated or unknown builds.
+export function attestationProblem(
+  build: AttestedBuild,
+  env: NodeJS.ProcessEnv = process.env,
+  now: number = Date.now(),
+): string | null {
+  if (!build.attestedAt || now - build.attestedAt.getTime() > ATTESTATION_MAX_AGE_MS) {
+    return env.OHFIXIT_REQUIRE_ATTESTATION === 'true' ? 'Desktop helper has not attested its build recently' : null;
+  }
+  if (build.codeSignature === 'invalid') return 'Desktop helper binary failed its code signature check';
+  const minVersion = env.OHFIXIT_MIN_HELPER_VERSION;
+  if (minVersion && compareVersions(build.helperVersion ?? '0', minVersion) < 0) {
+    return `Desktop helper ${build.helperVersion ?? '(unknown)'} is older than the required ${minVersion}; please update it`;
+  }
+  const knownBuilds = (env.OHFIXIT_HELPER_BUILD_HASHES ?? '')
+    .split(',')
+    .map((hash) => hash.trim().toLowerCase())
+    .filter(Boolean);
+  if (knownBuilds.length > 0 && !knownBuilds.includes(build.binarySha256 ?? '')) {
+    return 'Desktop helper build is not a known release';
+  }
+  return null;
+}
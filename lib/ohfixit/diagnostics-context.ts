@@ -15,6 +15,12 @@ function fmtNum(n?: number, digits = 0) {
   return n.toFixed(digits);
 }
 
+function fmtOffset(minutes: number) {
+  const sign = minutes < 0 ? '-' : '+';
+  const abs = Math.abs(minutes);
+  return `${sign}${String(Math.floor(abs / 60)).padStart(2, '0')}:${String(abs % 60).padStart(2, '0')}`;
+}
+
 function safeJoin(items: Array<string | undefined | null>, sep = ', ') {
   return items.filter(Boolean).join(sep);
 }
@@ -71,6 +77,15 @@ export async function buildDiagnosticsContext(
           d.battery.charging !== undefined ? `charging=${d.battery.charging ? 'yes' : 'no'}` : undefined,
         ]) || 'unknown'
       : 'unknown';
+    const locale = d.locale
+      ? safeJoin([
+          d.locale.locale,
+          d.locale.utcOffsetMinutes !== undefined ? `UTC${fmtOffset(d.locale.utcOffsetMinutes)}` : undefined,
+          d.locale.hourCycle ? `hourCycle=${d.locale.hourCycle}` : undefined,
+          d.locale.clockSkewMs !== undefined ? `clockSkew=${fmtNum(d.locale.clockSkewMs / 1000)}s` : undefined,
+        ]) || 'unknown'
+      : 'unknown';
+    const keyboard = d.keyboard && d.keyboard.layouts.length ? d.keyboard.layouts.join(', ') : 'unknown';
     const winSize = d.window
       ? `${d.window.innerWidth}x${d.window.innerHeight}`
       : 'unknown';
@@ -80,6 +95,8 @@ export async function buildDiagnosticsContext(
     parts.push(`- platform: ${d.platform ?? 'unknown'}`);
     parts.push(`- languages: ${langs}`);
     parts.push(`- timeZone: ${d.timeZone ?? 'unknown'}`);
+    parts.push(`- locale: ${locale}`);
+    parts.push(`- keyboard: ${keyboard}`);
     parts.push(`- screen: ${screen}`);
     parts.push(`- device: ${device}`);
     parts.push(`- network: ${net}`);
@@ -109,6 +126,9 @@ export async function buildDiagnosticsContext(
     } else if (rec.client?.data?.network?.saveData) {
       parts.push('- Data Saver is ON. Prefer concise text and avoid large media.');
     }
+    if (rec.client?.data?.locale?.clockSkewMs !== undefined && Math.abs(rec.client.data.locale.clockSkewMs) > 5 * 60 * 1000) {
+      parts.push('- The device clock is off by more than 5 minutes; this breaks sign-ins and certificate checks. Suggest syncing the clock first.');
+    }
     if (osFamily === 'iOS' || osFamily === 'Android') {
       parts.push('- Mobile browser constraints apply. Prefer simple, step-by-step guidance.');
     }
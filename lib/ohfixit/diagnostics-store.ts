@@ -13,6 +13,15 @@ export type ClientDiagnostics = {
     platform?: string;
     languages?: string[];
     timeZone?: string;
+    locale?: {
+      locale?: string; // resolved Intl locale, e.g. "de-DE"
+      utcOffsetMinutes?: number;
+      hourCycle?: string;
+      calendar?: string;
+      numberingSystem?: string;
+      clockSkewMs?: number; // client clock minus server clock, set by the server
+    };
+    keyboard?: { layouts: string[]; source: 'layout-map' | 'unavailable' };
     screen?: { width: number; height: number; dpr: number };
     device?: { memoryGB?: number; cores?: number };
     network?: {
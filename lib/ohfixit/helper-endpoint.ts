@@ -0,0 +1,121 @@
+import 'server-only';
+
+import { readFile } from 'node:fs/promises';
+import http from 'node:http';
+import https from 'node:https';
+import { homedir } from 'node:os';
+import path from 'node:path';
+
+// Where the desktop helper's local API listens. It prefers 8765 but moves to a
+// fallback or ephemeral port when that's taken, and advertises the port in
+// status_server.json in its data dir (desktop-helper instance.rs).
+export const DEFAULT_HELPER_URL = 'http://localhost:8765';
+
+const APP_IDENTIFIER = 'com.ohfixit.desktophelper';
+// Header the desktop helper's local API requires on every request
+export const HELPER_SESSION_HEADER = 'X-OhFixIt-Session';
+const WELL_KNOWN_FILE = 'status_server.json';
+
+// The helper's data dir, as the Rust `dirs::data_dir` resolves it
+export function helperDataDir(
+  platform: NodeJS.Platform = process.platform,
+  env: NodeJS.ProcessEnv = process.env,
+  home: string = homedir(),
+): string {
+  switch (platform) {
+    case 'darwin':
+      return path.posix.join(home, 'Library', 'Application Support', APP_IDENTIFIER);
+    case 'win32':
+      return path.win32.join(env.APPDATA || path.win32.join(home, 'AppData', 'Roaming'), APP_IDENTIFIER);
+    default:
+      return path.posix.join(env.XDG_DATA_HOME || path.posix.join(home, '.local', 'share'), APP_IDENTIFIER);
+  }
+}
+
+// Base URL of the helper: OHFIXIT_HELPER_URL, else the port (and scheme) the
+// helper on this machine advertised, else the default port
+export async function helperBaseUrl(): Promise<string> {
+  if (process.env.OHFIXIT_HELPER_URL) return process.env.OHFIXIT_HELPER_URL.replace(/\/$/, '');
+  try {
+    const advertised = JSON.parse(await readFile(path.join(helperDataDir(), WELL_KNOWN_FILE), 'utf8'));
+    const scheme = advertised?.tls === true ? 'https' : 'http';
+    if (Number.isInteger(advertised?.port)) return `${scheme}://localhost:${advertised.port}`;
+  } catch {
+    // Not running on this machine, or never started
+  }
+  return DEFAULT_HELPER_URL;
+}
+
+// The helper's local TLS certificate as its paired device shared it
+// (desktop-helper local_tls.rs): PEM and SHA-256 of the DER, lowercase hex
+export type PinnedCertificate = { certificate: string; fingerprint: string };
+
+// Node reports fingerprint256 as colon-separated uppercase hex
+export function normalizeFingerprint(fingerprint: string): string {
+  return fingerprint.replace(/:/g, '').toLowerCase();
+}
+
+// Only an unpaired helper's /status may be read without its certificate
+function unpinnedAllowed(url: URL): boolean {
+  return url.pathname === '/status';
+}
+
+// fetch for the helper's local API. Over HTTPS the helper must present the
+// certificate pinned for the user's device, so another local process on the
+// port can neither read the session token nor answer in the helper's place.
+// Without a pin only /status is read, and without the session header. A
+// helper with a pinned certificate that advertises plain HTTP is refused.
+export async function helperFetch(
+  input: string,
+  init: { method?: string; headers?: Record<string, string>; body?: string; signal?: AbortSignal } = {},
+  pinned: PinnedCertificate | null = null,
+): Promise<Response> {
+  const url = new URL(input);
+  const secure = url.protocol === 'https:';
+  let headers = init.headers ?? {};
+  if (!pinned || !secure) {
+    if (pinned) throw new Error('Desktop helper is serving plain HTTP but its TLS certificate is pinned');
+    if (secure && !unpinnedAllowed(url)) throw new Error('Desktop helper certificate has not been shared yet');
+    if (secure) {
+      headers = Object.fromEntries(
+        Object.entries(headers).filter(([name]) => name.toLowerCase() !== HELPER_SESSION_HEADER.toLowerCase()),
+      );
+    }
+  }
+
+  return new Promise((resolve, reject) => {
+    // No shared agent: a resumed TLS session would skip checkServerIdentity
+    const options: https.RequestOptions = { method: init.method ?? 'GET', headers, signal: init.signal, agent: false };
+    if (secure && pinned) {
+      options.ca = pinned.certificate;
+      options.checkServerIdentity = (_host, cert) =>
+        normalizeFingerprint(cert.fingerprint256) === pinned.fingerprint
+          ? undefined
+          : new Error('Desktop helper presented a certificate other than the pinned one');
+    } else if (secure) {
+      options.rejectUnauthorized = false;
+    }
+    const request = (secure ? https : http).request(url, options, (response) => {
+      const chunks: Buffer[] = [];
+      response.on('data', (chunk: Buffer) => chunks.push(chunk));
+      response.on('error', reject);
+      response.on('end', () => {
+        const responseHeaders = new Headers();
+        for (const [name, value] of Object.entries(response.headers)) {
+          if (typeof value === 'string') responseHeaders.set(name, value);
+          else if (Array.isArray(value)) for (const item of value) responseHeaders.append(name, item);
+        }
+        resolve(
+          // 204 and 304 can't carry a body
+          new Response([204, 304].includes(response.statusCode ?? 0) ? null : Buffer.concat(chunks), {
+            status: response.statusCode ?? 502,
+            statusText: response.statusMessage,
+            headers: responseHeaders,
+          }),
+        );
+      });
+    });
+    request.on('error', reject);
+    request.end(init.body);
+  });
+}
@@ -1,47 +1,132 @@
 import 'server-only';
 
+import { createPublicKey, randomUUID } from 'node:crypto';
 import * as jose from 'jose';
 
-const ALG = 'HS256';
-const ISS = 'ohfixit-helper';
+// Deployments sharing a signing key set distinct OHFIXIT_JWT_ISSUER values
+// (the helper must be configured with the same one)
+const issuer = () => process.env.OHFIXIT_JWT_ISSUER || 'ohfixit-helper';
 const AUD = 'desktop-helper';
+// The helper accepts only these; HS256 would let anyone holding the helper's
+// copy of a shared secret mint approvals
+const ALGS = ['ES256', 'RS256'] as const;
+type Alg = (typeof ALGS)[number];
 
-function getSecret(): Uint8Array {
+// HMAC secret for execution receipts and transcripts (lib/ohfixit/receipt.ts).
+// Approval tokens are signed with the asymmetric key below instead.
+export function getSecret(): Uint8Array {
   const secret = process.env.OHFIXIT_JWT_SECRET || process.env.NEXTAUTH_SECRET || '';
   if (!secret) throw new Error('Missing OHFIXIT_JWT_SECRET');
   return new TextEncoder().encode(secret);
 }
 
+type SigningKey = { alg: Alg; kid: string; privateKey: jose.KeyLike; publicJwk: jose.JWK };
+
+let cachedKey: { pem: string; key: Promise<SigningKey> } | null = null;
+
+// OHFIXIT_JWT_PRIVATE_KEY: PKCS#8 PEM (literal "\n" escapes allowed);
+// OHFIXIT_JWT_ALG: ES256 (default) or RS256; OHFIXIT_JWT_KID: optional key id,
+// defaults to the key's JWK thumbprint
+function getSigningKey(): Promise<SigningKey> {
+  const pem = (process.env.OHFIXIT_JWT_PRIVATE_KEY || '').replace(/\\n/g, '\n');
+  if (!pem) throw new Error('Missing OHFIXIT_JWT_PRIVATE_KEY');
+  if (cachedKey?.pem !== pem) {
+    cachedKey = { pem, key: loadSigningKey(pem) };
+  }
+  return cachedKey.key;
+}
+
+async function loadSigningKey(pem: string): Promise<SigningKey> {
+  const alg = (process.env.OHFIXIT_JWT_ALG || 'ES256') as Alg;
+  if (!ALGS.includes(alg)) throw new Error(`Unsupported OHFIXIT_JWT_ALG '${alg}'`);
+  const privateKey = await jose.importPKCS8(pem, alg);
+  const publicJwk = await jose.exportJWK(createPublicKey(pem));
+  const kid = process.env.OHFIXIT_JWT_KID || (await jose.calculateJwkThumbprint(publicJwk));
+  return { alg, kid, privateKey, publicJwk: { ...publicJwk, kid, alg, use: 'sig' } };
+}
+
+// Served at /.well-known/jwks.json for the helper. Keys being rotated out stay
+// listed via OHFIXIT_JWT_PREVIOUS_JWKS (a JSON array of public JWKs with kids)
+// until tokens signed with them have expired.
+export async function getPublicJwks(): Promise<{ keys: jose.JWK[] }> {
+  const { publicJwk } = await getSigningKey();
+  const previous: jose.JWK[] = process.env.OHFIXIT_JWT_PREVIOUS_JWKS
+    ? JSON.parse(process.env.OHFIXIT_JWT_PREVIOUS_JWKS)
+    : [];
+  return { keys: [publicJwk, ...previous.filter((k) => k.kid && k.kid !== publicJwk.kid)] };
+}
+
+// Scopes a helper token can carry, space-separated in `scope`. The helper
+// requires one per route (desktop-helper policy.rs), so a token minted for a
+// health scan can't run commands or capture the screen.
+export const TOKEN_SCOPES = ['diagnostics:read', 'automation:execute', 'screen:capture'] as const;
+export type TokenScope = (typeof TOKEN_SCOPES)[number];
+// What servers minted before granular scopes; the helper still accepts them
+export type LegacyScope = 'execute' | 'report' | 'both';
+
+// The `scope` claim for a set of scopes
+export function tokenScope(scopes: readonly TokenScope[]): string {
+  return [...new Set(scopes)].sort().join(' ');
+}
+
+// The scopes a `scope` claim grants, reading legacy values the way the helper
+// does (auth.rs has_scope): execute/both are execution plus diagnostics,
+// report is diagnostics alone
+export function grantedScopes(scope: string | undefined): TokenScope[] {
+  const granted = new Set<TokenScope>();
+  for (const value of (scope ?? '').split(/\s+/)) {
+    if (value === 'execute' || value === 'both') {
+      granted.add('automation:execute');
+      granted.add('diagnostics:read');
+    } else if (value === 'report') {
+      granted.add('diagnostics:read');
+    } else if ((TOKEN_SCOPES as readonly string[]).includes(value)) {
+      granted.add(value as TokenScope);
+    }
+  }
+  return TOKEN_SCOPES.filter((s) => granted.has(s));
+}
+
 export type AutomationTokenClaims = {
   chatId: string | null;
   userId: string | null;
   anonymousId: string | null;
   actionId?: string;
   approvalId?: string;
-  scope?: 'execute' | 'report' | 'both';
+  // SHA-256 of the approval text shown to the user (see lib/ohfixit/receipt.ts)
+  approvalTextHash?: string;
+  // The paired desktop helper this token is for (lib/ohfixit/devices.ts); the
+  // helper rejects tokens bound to any other device
+  deviceId?: string;
+  // That device's hardware fingerprint; checked by the helper along with deviceId
+  deviceFingerprint?: string;
+  scope?: LegacyScope | string; // see tokenScope
 };
 
 export async function signAutomationToken(
   claims: AutomationTokenClaims,
   ttlSeconds: number = 60 * 10,
 ): Promise<string> {
-  const secret = getSecret();
+  const { alg, kid, privateKey } = await getSigningKey();
   const jwt = await new jose.SignJWT({ ...claims })
-    .setProtectedHeader({ alg: ALG })
-    .setIssuer(ISS)
+    .setProtectedHeader({ alg, kid })
+    .setIssuer(issuer())
     .setAudience(AUD)
+    // The helper accepts each jti once
+    .setJti(randomUUID())
     .setIssuedAt()
     .setExpirationTime(`${ttlSeconds} seconds`)
-    .sign(secret);
+    .sign(privateKey);
   return jwt;
 }
 
 export async function verifyAutomationToken(token: string): Promise<AutomationTokenClaims> {
-  const secret = getSecret();
-  const { payload } = await jose.jwtVerify(token, secret, {
-    issuer: ISS,
+  const jwks = jose.createLocalJWKSet(await getPublicJwks());
+  const { payload } = await jose.jwtVerify(token, jwks, {
+    issuer: issuer(),
     audience: AUD,
-    algorithms: [ALG],
+    algorithms: [...ALGS],
+    requiredClaims: ['iss', 'aud', 'exp', 'jti'],
   });
   // Basic shape validation
   return {
@@ -50,6 +135,9 @@ export async function verifyAutomationToken(token: string): Promise<AutomationTo
     anonymousId: (payload as any).anonymousId ?? null,
     actionId: (payload as any).actionId,
     approvalId: (payload as any).approvalId,
+    approvalTextHash: (payload as any).approvalTextHash,
+    deviceId: (payload as any).deviceId,
+    deviceFingerprint: (payload as any).deviceFingerprint,
     scope: ((payload as any).scope as any) ?? 'both',
   };
 }
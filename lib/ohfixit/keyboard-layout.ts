@@ -0,0 +1,35 @@
+// Browsers don't expose the OS layout name, but the Keyboard API maps physical
+// key codes to the characters they produce, which is enough to tell the common
+// layouts apart (Chromium only; other browsers report nothing).
+
+export type KeyboardLayoutMapLike = { get(code: string): string | undefined };
+
+export function guessKeyboardLayout(map: KeyboardLayoutMapLike): string {
+  const keys = ['KeyQ', 'KeyW', 'KeyE', 'KeyR', 'KeyT', 'KeyY']
+    .map((code) => (map.get(code) ?? '').toLowerCase())
+    .join('');
+  const keyA = (map.get('KeyA') ?? '').toLowerCase();
+
+  if (keys === 'qwerty') return 'QWERTY';
+  if (keys === 'qwertz') return 'QWERTZ';
+  if (keys === 'azerty') return 'AZERTY';
+  if (keys.startsWith("',.py")) return 'Dvorak';
+  if (keys === 'qwfpgj' || keys === 'qwfpbj') return 'Colemak';
+  if (keyA && !/^[a-z]$/.test(keyA)) return `non-Latin (KeyA=${keyA})`;
+  return keys ? `unrecognized (${keys})` : 'unknown';
+}
+
+export type KeyboardDiagnostics = { layouts: string[]; source: 'layout-map' | 'unavailable' };
+
+export async function collectKeyboardLayouts(): Promise<KeyboardDiagnostics> {
+  try {
+    const keyboard = (navigator as any)?.keyboard;
+    if (typeof keyboard?.getLayoutMap !== 'function') {
+      return { layouts: [], source: 'unavailable' };
+    }
+    const map: KeyboardLayoutMapLike = await keyboard.getLayoutMap();
+    return { layouts: [guessKeyboardLayout(map)], source: 'layout-map' };
+  } catch {
+    return { layouts: [], source: 'unavailable' };
+  }
+}
@@ -0,0 +1,65 @@
+import 'server-only';
+
+import { createHash, createHmac, timingSafeEqual } from 'node:crypto';
+import { getSecret } from '@/lib/ohfixit/jwt';
+import type { ActionPreview } from '@/lib/ohfixit/allowlist';
+
+// Signed by the desktop helper after each execution (desktop-helper receipt.rs)
+export type ExecutionReceipt = {
+  approvalId: string;
+  approvalTextHash: string | null;
+  actionId: string;
+  commandsHash: string;
+  success: boolean;
+  executedAt: string;
+  signature: string;
+};
+
+// Hash of exactly what the user was shown when approving, carried in the helper
+// token so the receipt can prove which text authorized the run
+export function approvalTextHash(preview: ActionPreview): string {
+  return createHash('sha256').update(JSON.stringify(preview)).digest('hex');
+}
+
+// Must match signed_text in desktop-helper receipt.rs
+export function receiptSigningText(receipt: Omit<ExecutionReceipt, 'signature'>): string {
+  return [
+    receipt.approvalId,
+    receipt.approvalTextHash ?? '',
+    receipt.actionId,
+    receipt.commandsHash,
+    receipt.success ? 'true' : 'false',
+    receipt.executedAt,
+  ].join('\n');
+}
+
+export function signReceipt(receipt: Omit<ExecutionReceipt, 'signature'>): string {
+  return createHmac('sha256', getSecret()).update(receiptSigningText(receipt)).digest('hex');
+}
+
+// Valid when the signature checks out and the receipt belongs to the approval
+// the reporting token was minted for
+export function verifyExecutionReceipt(
+  receipt: ExecutionReceipt,
+  expected: { approvalId?: string; approvalTextHash?: string },
+): boolean {
+  const signature = Buffer.from(signReceipt(receipt), 'hex');
+  const given = Buffer.from(receipt.signature, 'hex');
+  if (given.length !== signature.length || !timingSafeEqual(given, signature)) return false;
+  if (expected.approvalId && receipt.approvalId !== expected.approvalId) return false;
+  if (expected.approvalTextHash && receipt.approvalTextHash !== expected.approvalTextHash) return false;
+  return true;
+}
+
+// Chat transcripts from the helper's GET /transcripts/{chatId} are signed the
+// same way, over the exact payload string. Returns the parsed transcript or null.
+export function verifyTranscript(signed: { payload: string; signature: string }): Record<string, any> | null {
+  const expected = Buffer.from(createHmac('sha256', getSecret()).update(signed.payload).digest('hex'), 'hex');
+  const given = Buffer.from(signed.signature, 'hex');
+  if (given.length !== expected.length || !timingSafeEqual(given, expected)) return null;
+  try {
+    return JSON.parse(signed.payload);
+  } catch {
+    return null;
+  }
+}
@@ -25,6 +25,8 @@ describe('buildDiagnosticsContext', () => {
         platform: 'MacIntel',
         languages: ['en-US', 'en'],
         timeZone: 'America/Los_Angeles',
+        locale: { locale: 'en-US', utcOffsetMinutes: -420, hourCycle: 'h12', clockSkewMs: 600000 },
+        keyboard: { layouts: ['QWERTY'], source: 'layout-map' },
         screen: { width: 1440, height: 900, dpr: 2 },
         device: { memoryGB: 16, cores: 8 },
         network: { effectiveType: '4g', downlink: 10, rtt: 50, saveData: false },
@@ -51,6 +53,9 @@ describe('buildDiagnosticsContext', () => {
     expect(s).toContain('userAgent: Mozilla/5.0');
     expect(s).toContain('screen: 1440x900 @2.00x');
     expect(s).toContain('device: 16 GB RAM, 8 cores');
+    expect(s).toContain('locale: en-US, UTC-07:00, hourCycle=h12, clockSkew=600s');
+    expect(s).toContain('keyboard: QWERTY');
+    expect(s).toContain('device clock is off');
     expect(s).toContain('Network Checks:');
     expect(s).toContain('https://example.com/ping: OK (200) 120ms');
     expect(s).toContain('https://api.example.com: FAIL (503)');
@@ -0,0 +1,63 @@
+import { mkdtemp, mkdir, rm, writeFile } from 'node:fs/promises';
+import { tmpdir } from 'node:os';
+import path from 'node:path';
+import { afterEach, describe, it, expect } from 'vitest';
+import {
+  helperBaseUrl,
+  helperDataDir,
+  helperFetch,
+  normalizeFingerprint,
+} from '@/lib/ohfixit/helper-endpoint';
+
+describe('helper endpoint discovery', () => {
+  afterEach(() => {
+    delete process.env.OHFIXIT_HELPER_URL;
+    delete process.env.XDG_DATA_HOME;
+  });
+
+  it('resolves the helper data dir like the Rust dirs crate', () => {
+    expect(helperDataDir('darwin', {}, '/Users/ann')).toBe(
+      '/Users/ann/Library/Application Support/com.ohfixit.desktophelper',
+    );
+    expect(helperDataDir('linux', {}, '/home/ann')).toBe('/home/ann/.local/share/com.ohfixit.desktophelper');
+    expect(helperDataDir('linux', { XDG_DATA_HOME: '/data' }, '/home/ann')).toBe('/data/com.ohfixit.desktophelper');
+    expect(helperDataDir('win32', { APPDATA: 'C:\\Users\\ann\\AppData\\Roaming' }, 'C:\\Users\\ann')).toBe(
+      'C:\\Users\\ann\\AppData\\Roaming\\com.ohfixit.desktophelper',
+    );
+  });
+
+  it('prefers an explicitly configured helper URL', async () => {
+    process.env.OHFIXIT_HELPER_URL = 'http://localhost:9000/';
+    expect(await helperBaseUrl()).toBe('http://localhost:9000');
+  });
+
+  it.skipIf(process.platform !== 'linux')('uses https when the helper advertises TLS', async () => {
+    const dataHome = await mkdtemp(path.join(tmpdir(), 'ohfixit-'));
+    try {
+      process.env.XDG_DATA_HOME = dataHome;
+      await mkdir(helperDataDir(), { recursive: true });
+      await writeFile(path.join(helperDataDir(), 'status_server.json'), JSON.stringify({ port: 8766, tls: true }));
+      expect(await helperBaseUrl()).toBe('https://localhost:8766');
+      await writeFile(path.join(helperDataDir(), 'status_server.json'), JSON.stringify({ port: 8767 }));
+      expect(await helperBaseUrl()).toBe('http://localhost:8767');
+    } finally {
+      await rm(dataHome, { recursive: true, force: true });
+    }
+  });
+});
+
+describe('pinned helper connections', () => {
+  const pinned = { certificate: '-----BEGIN CERTIFICATE-----', fingerprint: 'ab'.repeat(32) };
+
+  it('compares fingerprints the way the helper writes them', () => {
+    expect(normalizeFingerprint('AB:CD:EF:01')).toBe('abcdef01');
+  });
+
+  it('only reads /status from a helper whose certificate is not pinned', async () => {
+    await expect(helperFetch('https://localhost:8765/displays')).rejects.toThrow(/not been shared/);
+  });
+
+  it('refuses plain HTTP once a certificate is pinned', async () => {
+    await expect(helperFetch('http://localhost:8765/status', {}, pinned)).rejects.toThrow(/plain HTTP/);
+  });
+});
@@ -0,0 +1,196 @@
+import { describe, it, expect, vi } from 'vitest';
+import { generateKeyPairSync, sign } from 'node:crypto';
+
+vi.mock('@/lib/db/client', () => ({ db: {} }));
+
+import {
+  attestationProblem,
+  certificateFingerprint,
+  compareVersions,
+  deepLinkUrl,
+  forwardedClientCertificate,
+  freezeState,
+  helperSessionHeaders,
+  normalizePairingCode,
+  parseDeviceCertificate,
+  parseInterruption,
+  parseTokenLockout,
+  verifyAuditExport,
+  verifyRegistrationSignature,
+} from '@/lib/ohfixit/devices';
+
+// Self-signed P-256 client certificate for the test device, as pairing.rs
+// generates (CN = device id), valid until 2126
+const DEVICE_CERT = `-----BEGIN CERTIFICATE-----
+MIIB+DCCAZ2gAwIBAgIUfgZn/aDUbTr4DJ5VYqZMxmJTcyYwCgYIKoZIzj0EAwIw
+UDEtMCsGA1UEAwwkNmYxYzFkMzYtM2M1ZS00ZjFlLTlmNTgtMGQ1YzhmM2YyYTEx
+MR8wHQYDVQQKDBZPaEZpeEl0IERlc2t0b3AgSGVscGVyMCAXDTI2MTAxNTA2MzQ0
+MFoYDzIxMjYwOTIxMDYzNDQwWjBQMS0wKwYDVQQDDCQ2ZjFjMWQzNi0zYzVlLTRm
+MWUtOWY1OC0wZDVjOGYzZjJhMTExHzAdBgNVBAoMFk9oRml4SXQgRGVza3RvcCBI
+ZWxwZXIwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAARd/RQG3Ti82OxvdS2PpGYy
+M0U1q+UYUovTOUxlLrN4Iz9XE+DoB/h9vUmQrQ2+Lq+rkC6+OKmxdBjQ1MDdxCMj
+o1MwUTAdBgNVHQ4EFgQUZzSn8taYWfHp164kOJBWFMnlAnkwHwYDVR0jBBgwFoAU
+ZzSn8taYWfHp164kOJBWFMnlAnkwDwYDVR0TAQH/BAUwAwEB/zAKBggqhkjOPQQD
+AgNJADBGAiEAzj1v7P/nKOJLxi09+f4kaXx0beCKeCBrul5o6T0mwkMCIQCLJBg9
+ok7yNqpz9cbOq7jDV/5ukcntrMtPWum+M7qUUQ==
+-----END CERTIFICATE-----`;
+
+// Same shape desktop-helper pairing.rs sends
+function registration(overrides: Record<string, string> = {}) {
+  const { publicKey, privateKey } = generateKeyPairSync('ed25519');
+  const raw = Buffer.from(publicKey.export({ format: 'jwk' }).x as string, 'base64url');
+  const deviceId = '6f1c1d36-3c5e-4f1e-9f58-0d5c8f3f2a11';
+  const pairingCode = 'ABCD-EF23';
+  const fingerprint = 'a'.repeat(64);
+  return {
+    deviceId,
+    publicKey: raw.toString('base64'),
+    pairingCode,
+    fingerprint,
+    signature: sign(null, Buffer.from(`${deviceId}.${pairingCode}.${fingerprint}`), privateKey).toString('base64'),
+    os: 'macos',
+    name: 'Test Mac',
+    ...overrides,
+  };
+}
+
+describe('helper pairing', () => {
+  it('normalizes pairing codes as typed by users', () => {
+    expect(normalizePairingCode('abcd ef23')).toBe('ABCD-EF23');
+    expect(normalizePairingCode('ABCD-EF23')).toBe('ABCD-EF23');
+    expect(normalizePairingCode('abcdef23')).toBe('ABCD-EF23');
+  });
+
+  it('accepts a registration signed by the registered key', () => {
+    expect(verifyRegistrationSignature(registration())).toBe(true);
+  });
+
+  it('rejects a registration signed for another code, fingerprint or key', () => {
+    expect(verifyRegistrationSignature(registration({ pairingCode: 'ZZZZ-ZZZZ' }))).toBe(false);
+    expect(verifyRegistrationSignature({ ...registration(), fingerprint: 'b'.repeat(64) })).toBe(false);
+    const other = registration();
+    expect(verifyRegistrationSignature({ ...registration(), publicKey: other.publicKey })).toBe(false);
+  });
+
+  it('accepts a valid self-signed certificate naming the device', () => {
+    const cert = parseDeviceCertificate(DEVICE_CERT, '6f1c1d36-3c5e-4f1e-9f58-0d5c8f3f2a11');
+    expect(cert).not.toBeNull();
+    expect(certificateFingerprint(cert!)).toMatch(/^[0-9a-f]{64}$/);
+  });
+
+  it('rejects a certificate for another device or that is not a certificate', () => {
+    expect(parseDeviceCertificate(DEVICE_CERT, '00000000-0000-4000-8000-000000000000')).toBeNull();
+    expect(parseDeviceCertificate('not a certificate', '6f1c1d36-3c5e-4f1e-9f58-0d5c8f3f2a11')).toBeNull();
+  });
+
+  it('reads the client certificate forwarded by the TLS terminator', () => {
+    const headers = new Headers({ 'x-client-cert': encodeURIComponent(DEVICE_CERT) });
+    expect(forwardedClientCertificate(headers)).toBe(DEVICE_CERT);
+    expect(forwardedClientCertificate(new Headers())).toBeNull();
+  });
+
+  it('sends no session token for callers without an account', async () => {
+    expect(await helperSessionHeaders(null)).toEqual({});
+    expect(await helperSessionHeaders(undefined)).toEqual({});
+  });
+
+  it('verifies an audit export signed by the registered device key', () => {
+    const { publicKey, privateKey } = generateKeyPairSync('ed25519');
+    const raw = Buffer.from(publicKey.export({ format: 'jwk' }).x as string, 'base64url').toString('base64');
+    const payload = JSON.stringify({ device_id: 'dev-1', entries: [{ event: 'execution_finished' }] });
+    const exported = {
+      payload,
+      signature: sign(null, Buffer.from(payload), privateKey).toString('base64'),
+      algorithm: 'Ed25519' as const,
+      public_key: raw,
+    };
+    expect(verifyAuditExport(exported, raw)?.entries).toHaveLength(1);
+    expect(verifyAuditExport({ ...exported, payload: payload.replace('finished', 'aborted') }, raw)).toBeNull();
+    expect(verifyAuditExport(exported, registration().publicKey)).toBeNull();
+  });
+
+  it('builds deep links in the form the helper parses', () => {
+    expect(deepLinkUrl('action', 'n0nce-value_1234567', 'flush-dns')).toBe(
+      'ohfixit://action/flush-dns?nonce=n0nce-value_1234567',
+    );
+    expect(deepLinkUrl('pair', 'n0nce-value_1234567')).toBe('ohfixit://pair?nonce=n0nce-value_1234567');
+  });
+
+  it('compares helper versions numerically', () => {
+    expect(compareVersions('0.10.0', '0.9.9')).toBe(1);
+    expect(compareVersions('1.2', '1.2.0')).toBe(0);
+    expect(compareVersions('1.2.3', '1.3.0')).toBe(-1);
+  });
+
+  it('refuses tampered, outdated or unknown helper builds', () => {
+    const now = Date.parse('2026-10-15T12:00:00Z');
+    const build = {
+      helperVersion: '0.2.0',
+      binarySha256: 'b'.repeat(64),
+      codeSignature: 'valid',
+      attestedAt: new Date(now - 60 * 60 * 1000),
+    };
+    expect(attestationProblem(build, {}, now)).toBeNull();
+    expect(attestationProblem({ ...build, codeSignature: 'invalid' }, {}, now)).toMatch(/code signature/);
+    expect(attestationProblem(build, { OHFIXIT_MIN_HELPER_VERSION: '0.3.0' }, now)).toMatch(/older than/);
+    expect(attestationProblem(build, { OHFIXIT_HELPER_BUILD_HASHES: 'c'.repeat(64) }, now)).toMatch(/known release/);
+    expect(attestationProblem(build, { OHFIXIT_HELPER_BUILD_HASHES: `${'c'.repeat(64)}, ${'B'.repeat(64)}` }, now)).toBeNull();
+  });
+
+  it('only requires a recent attestation when configured to', () => {
+    const now = Date.now();
+    const stale = { helperVersion: null, binarySha256: null, codeSignature: null, attestedAt: null };
+    expect(attestationProblem(stale, {}, now)).toBeNull();
+    expect(attestationProblem(stale, { OHFIXIT_REQUIRE_ATTESTATION: 'true' }, now)).toMatch(/attested/);
+  });
+
+  it('reads token lockouts only for the device that reported them', () => {
+    const deviceId = '6f1c1d36-3c5e-4f1e-9f58-0d5c8f3f2a11';
+    const payload = JSON.stringify({
+      device_id: deviceId,
+      event: 'token_lockout',
+      details: { source: 'local API', failures: 5, locked_until: '2026-10-15T12:15:00Z' },
+    });
+    expect(parseTokenLockout(payload, deviceId)).toEqual({
+      source: 'local API',
+      failures: 5,
+      lockedUntil: new Date('2026-10-15T12:15:00Z'),
+    });
+    expect(parseTokenLockout(payload, 'another-device')).toBeNull();
+    expect(parseTokenLockout(JSON.stringify({ device_id: deviceId, event: 'other' }), deviceId)).toBeNull();
+    expect(parseTokenLockout('not json', deviceId)).toBeNull();
+  });
+
+  it('parses an interruption report only for the device that signed it', () => {
+    const deviceId = '6f1c1d36-3c5e-4f1e-9f58-0d5c8f3f2a11';
+    const payload = JSON.stringify({
+      device_id: deviceId,
+      event: 'execution_interrupted',
+      approval_id: 'a1b2c3',
+      action_id: 'flush-dns',
+      kind: 'execute',
+      execution_id: 'e1',
+      message: 'Interrupted',
+    });
+    expect(parseInterruption(payload, deviceId)).toEqual({
+      approvalId: 'a1b2c3',
+      actionId: 'flush-dns',
+      kind: 'execute',
+      executionId: 'e1',
+      message: 'Interrupted',
+    });
+    expect(parseInterruption(payload, 'another-device')).toBeNull();
+    expect(parseInterruption(JSON.stringify({ device_id: deviceId, event: 'execution_interrupted' }), deviceId)).toBeNull();
+    expect(parseInterruption('not json', deviceId)).toBeNull();
+  });
+
+  it('freezes a helper by its owner or every helper from the environment', () => {
+    const thawed = { frozenAt: null, frozenReason: null };
+    const frozen = { frozenAt: new Date(), frozenReason: 'Investigating' };
+    expect(freezeState(thawed, {})).toEqual({ frozen: false, reason: null });
+    expect(freezeState(frozen, {})).toEqual({ frozen: true, reason: 'Investigating' });
+    expect(freezeState({ ...frozen, frozenReason: null }, {}).reason).toMatch(/owner/);
+    expect(freezeState(thawed, { OHFIXIT_FREEZE_HELPERS: 'Incident 42' })).toEqual({ frozen: true, reason: 'Incident 42' });
+    expect(freezeState(thawed, { OHFIXIT_FREEZE_HELPERS: ' ' }).frozen).toBe(false);
+  });
+});
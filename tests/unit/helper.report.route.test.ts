@@ -1,4 +1,5 @@
 import { describe, it, expect, vi, beforeAll, afterEach } from 'vitest';
+import * as jose from 'jose';
 
 // Mock the DB client to avoid real DB calls
 vi.mock('@/lib/db/client', () => {
@@ -20,9 +21,11 @@ vi.mock('@/lib/db/client', () => {
   };
 });
 
-// Ensure env secret is present before importing route code
-beforeAll(() => {
+// Ensure env secret and signing key are present before importing route code
+beforeAll(async () => {
   process.env.OHFIXIT_JWT_SECRET = 'test-secret-123';
+  const { privateKey } = await jose.generateKeyPair('ES256', { extractable: true });
+  process.env.OHFIXIT_JWT_PRIVATE_KEY = await jose.exportPKCS8(privateKey);
 });
 
 // Import after mocks are set up
@@ -1,9 +1,12 @@
 import { describe, it, expect, vi, beforeAll } from 'vitest';
+import * as jose from 'jose';
 import { verifyAutomationToken, signAutomationToken } from '@/lib/ohfixit/jwt';
 
 describe('helper token basic behavior', () => {
-  beforeAll(() => {
+  beforeAll(async () => {
     process.env.OHFIXIT_JWT_SECRET = 'test-secret-123';
+    const { privateKey } = await jose.generateKeyPair('ES256', { extractable: true });
+    process.env.OHFIXIT_JWT_PRIVATE_KEY = await jose.exportPKCS8(privateKey);
   });
 
   it('rejects invalid token', async () => {
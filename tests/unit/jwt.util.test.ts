@@ -1,9 +1,11 @@
 import { describe, it, expect, beforeAll } from 'vitest';
-import { signAutomationToken, verifyAutomationToken } from '@/lib/ohfixit/jwt';
+import * as jose from 'jose';
+import { getPublicJwks, signAutomationToken, verifyAutomationToken } from '@/lib/ohfixit/jwt';
 
 describe('ohfixit jwt utils', () => {
-  beforeAll(() => {
-    process.env.OHFIXIT_JWT_SECRET = 'test-secret-123';
+  beforeAll(async () => {
+    const { privateKey } = await jose.generateKeyPair('ES256', { extractable: true });
+    process.env.OHFIXIT_JWT_PRIVATE_KEY = await jose.exportPKCS8(privateKey);
   });
 
   it('signs and verifies a token with expected claims', async () => {
@@ -23,4 +25,79 @@ describe('ohfixit jwt utils', () => {
     expect(claims.approvalId).toBe('approv-1');
     expect(claims.scope).toBe('both');
   });
+
+  it('signs with an asymmetric key the published JWKS can verify', async () => {
+    const token = await signAutomationToken({ chatId: null, userId: null, anonymousId: 'anon-1' }, 60);
+    const header = jose.decodeProtectedHeader(token);
+    expect(header.alg).toBe('ES256');
+
+    const jwks = await getPublicJwks();
+    expect(jwks.keys.map((k) => k.kid)).toContain(header.kid);
+    expect(jwks.keys.every((k) => !('d' in k))).toBe(true);
+    const { payload } = await jose.jwtVerify(token, jose.createLocalJWKSet(jwks));
+    expect(payload.anonymousId).toBe('anon-1');
+  });
+
+  it('rejects HS256 tokens signed with a shared secret', async () => {
+    const forged = await new jose.SignJWT({ chatId: null, userId: null, anonymousId: null, scope: 'execute' })
+      .setProtectedHeader({ alg: 'HS256' })
+      .setIssuer('ohfixit-helper')
+      .setAudience('desktop-helper')
+      .setIssuedAt()
+      .setExpirationTime('60 seconds')
+      .sign(new TextEncoder().encode('default-secret-change-in-production'));
+    await expect(verifyAutomationToken(forged)).rejects.toBeTruthy();
+  });
+
+  it('rejects tokens for another audience or issuer', async () => {
+    const { privateKey } = await jose.generateKeyPair('ES256', { extractable: true });
+    process.env.OHFIXIT_JWT_PRIVATE_KEY = await jose.exportPKCS8(privateKey);
+    const { keys } = await getPublicJwks();
+    const key = await jose.importPKCS8(process.env.OHFIXIT_JWT_PRIVATE_KEY, 'ES256');
+    const mint = (iss: string, aud: string) =>
+      new jose.SignJWT({ chatId: null, userId: null, anonymousId: null })
+        .setProtectedHeader({ alg: 'ES256', kid: keys[0].kid })
+        .setIssuer(iss)
+        .setAudience(aud)
+        .setJti(crypto.randomUUID())
+        .setIssuedAt()
+        .setExpirationTime('60 seconds')
+        .sign(key);
+
+    await expect(verifyAutomationToken(await mint('ohfixit-helper', 'web-session'))).rejects.toBeTruthy();
+    await expect(verifyAutomationToken(await mint('other-deployment', 'desktop-helper'))).rejects.toBeTruthy();
+    await expect(verifyAutomationToken(await mint('ohfixit-helper', 'desktop-helper'))).resolves.toBeTruthy();
+  });
+
+  it('gives every token a unique jti and requires one', async () => {
+    const claims = { chatId: null, userId: null, anonymousId: null, actionId: 'flush-dns-macos' };
+    const first = jose.decodeJwt(await signAutomationToken(claims, 60));
+    const second = jose.decodeJwt(await signAutomationToken(claims, 60));
+    expect(first.jti).toBeTruthy();
+    expect(first.jti).not.toBe(second.jti);
+
+    const { keys } = await getPublicJwks();
+    const key = await jose.importPKCS8(process.env.OHFIXIT_JWT_PRIVATE_KEY!, 'ES256');
+    const withoutJti = await new jose.SignJWT(claims)
+      .setProtectedHeader({ alg: 'ES256', kid: keys[0].kid })
+      .setIssuer('ohfixit-helper')
+      .setAudience('desktop-helper')
+      .setIssuedAt()
+      .setExpirationTime('60 seconds')
+      .sign(key);
+    await expect(verifyAutomationToken(withoutJti)).rejects.toBeTruthy();
+  });
+
+  it('keeps previous keys published during rotation', async () => {
+    const { publicKey } = await jose.generateKeyPair('ES256', { extractable: true });
+    const previous = { ...(await jose.exportJWK(publicKey)), kid: 'old-key', alg: 'ES256' };
+    process.env.OHFIXIT_JWT_PREVIOUS_JWKS = JSON.stringify([previous]);
+    try {
+      const jwks = await getPublicJwks();
+      expect(jwks.keys).toHaveLength(2);
+      expect(jwks.keys[1].kid).toBe('old-key');
+    } finally {
+      delete process.env.OHFIXIT_JWT_PREVIOUS_JWKS;
+    }
+  });
 });
@@ -0,0 +1,26 @@
+import { describe, it, expect } from 'vitest';
+import { guessKeyboardLayout } from '@/lib/ohfixit/keyboard-layout';
+
+function layoutMap(chars: Record<string, string>) {
+  return new Map(Object.entries(chars));
+}
+
+describe('keyboard-layout', () => {
+  it('recognizes QWERTY, QWERTZ and AZERTY from the top letter row', () => {
+    expect(guessKeyboardLayout(layoutMap({ KeyQ: 'q', KeyW: 'w', KeyE: 'e', KeyR: 'r', KeyT: 't', KeyY: 'y', KeyA: 'a' }))).toBe('QWERTY');
+    expect(guessKeyboardLayout(layoutMap({ KeyQ: 'q', KeyW: 'w', KeyE: 'e', KeyR: 'r', KeyT: 't', KeyY: 'z', KeyA: 'a' }))).toBe('QWERTZ');
+    expect(guessKeyboardLayout(layoutMap({ KeyQ: 'a', KeyW: 'z', KeyE: 'e', KeyR: 'r', KeyT: 't', KeyY: 'y', KeyA: 'q' }))).toBe('AZERTY');
+  });
+
+  it('recognizes Dvorak', () => {
+    expect(guessKeyboardLayout(layoutMap({ KeyQ: "'", KeyW: ',', KeyE: '.', KeyR: 'p', KeyT: 'y', KeyY: 'f', KeyA: 'a' }))).toBe('Dvorak');
+  });
+
+  it('flags non-Latin layouts', () => {
+    expect(guessKeyboardLayout(layoutMap({ KeyQ: 'й', KeyW: 'ц', KeyE: 'у', KeyR: 'к', KeyT: 'е', KeyY: 'н', KeyA: 'ф' }))).toBe('non-Latin (KeyA=ф)');
+  });
+
+  it('returns unknown for an empty map', () => {
+    expect(guessKeyboardLayout(layoutMap({}))).toBe('unknown');
+  });
+});
@@ -1,19 +1,31 @@
 import { describe, it, expect, beforeAll } from 'vitest';
-import { signAutomationToken, verifyAutomationToken } from '@/lib/ohfixit/jwt';
+import * as jose from 'jose';
+import { grantedScopes, signAutomationToken, tokenScope, verifyAutomationToken } from '@/lib/ohfixit/jwt';
 
 describe('OhFixIt helper JWT', () => {
-  beforeAll(() => {
-    process.env.OHFIXIT_JWT_SECRET = process.env.OHFIXIT_JWT_SECRET || 'test-secret';
+  beforeAll(async () => {
+    const { privateKey } = await jose.generateKeyPair('ES256', { extractable: true });
+    process.env.OHFIXIT_JWT_PRIVATE_KEY = await jose.exportPKCS8(privateKey);
   });
 
   it('signs and verifies tokens', async () => {
-    const token = await signAutomationToken({ chatId: 'c1', userId: 'u1', anonymousId: null, actionId: 'flush-dns-macos', approvalId: 'a1', scope: 'both' }, 60);
+    const token = await signAutomationToken({ chatId: 'c1', userId: 'u1', anonymousId: null, actionId: 'flush-dns-macos', approvalId: 'a1', deviceId: 'd1', scope: 'both' }, 60);
     expect(typeof token).toBe('string');
     const claims = await verifyAutomationToken(token);
     expect(claims.chatId).toBe('c1');
     expect(claims.userId).toBe('u1');
     expect(claims.actionId).toBe('flush-dns-macos');
     expect(claims.approvalId).toBe('a1');
+    expect(claims.deviceId).toBe('d1');
     expect(claims.scope).toBe('both');
   });
+
+  it('reads granular and legacy scopes the way the helper does', () => {
+    expect(tokenScope(['diagnostics:read', 'automation:execute', 'diagnostics:read'])).toBe('automation:execute diagnostics:read');
+    expect(grantedScopes('diagnostics:read')).toEqual(['diagnostics:read']);
+    expect(grantedScopes('both')).toEqual(['diagnostics:read', 'automation:execute']);
+    expect(grantedScopes('report')).toEqual(['diagnostics:read']);
+    expect(grantedScopes('screen:capture bogus')).toEqual(['screen:capture']);
+    expect(grantedScopes(undefined)).toEqual([]);
+  });
 });
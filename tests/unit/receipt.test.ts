@@ -0,0 +1,49 @@
+import { describe, it, expect, beforeAll } from 'vitest';
+import { createHmac } from 'node:crypto';
+import { approvalTextHash, signReceipt, verifyExecutionReceipt, verifyTranscript } from '@/lib/ohfixit/receipt';
+
+describe('ohfixit execution receipts', () => {
+  beforeAll(() => {
+    process.env.OHFIXIT_JWT_SECRET = 'test-secret-123';
+  });
+
+  const unsigned = {
+    approvalId: 'ap-1',
+    approvalTextHash: 'abc123',
+    actionId: 'flush-dns-macos',
+    commandsHash: 'def456',
+    success: true,
+    executedAt: '2026-01-01T00:00:00+00:00',
+  };
+
+  it('hashes the approval text deterministically', () => {
+    const preview = { description: 'Flush DNS', commands: ['dscacheutil -flushcache'], risks: [], reversible: true, estimatedTime: '5s', requirements: [] } as any;
+    expect(approvalTextHash(preview)).toBe(approvalTextHash({ ...preview }));
+    expect(approvalTextHash(preview)).toMatch(/^[0-9a-f]{64}$/);
+    expect(approvalTextHash({ ...preview, commands: ['rm -rf /'] })).not.toBe(approvalTextHash(preview));
+  });
+
+  it('accepts a receipt signed for the expected approval', () => {
+    const receipt = { ...unsigned, signature: signReceipt(unsigned) };
+    expect(verifyExecutionReceipt(receipt, { approvalId: 'ap-1', approvalTextHash: 'abc123' })).toBe(true);
+  });
+
+  it('rejects tampered receipts', () => {
+    const receipt = { ...unsigned, signature: signReceipt(unsigned) };
+    expect(verifyExecutionReceipt({ ...receipt, commandsHash: 'other' }, {})).toBe(false);
+    expect(verifyExecutionReceipt({ ...receipt, signature: 'zz' }, {})).toBe(false);
+  });
+
+  it('rejects receipts for a different approval', () => {
+    const receipt = { ...unsigned, signature: signReceipt(unsigned) };
+    expect(verifyExecutionReceipt(receipt, { approvalId: 'ap-2' })).toBe(false);
+    expect(verifyExecutionReceipt(receipt, { approvalId: 'ap-1', approvalTextHash: 'other' })).toBe(false);
+  });
+
+  it('verifies signed chat transcripts', () => {
+    const payload = JSON.stringify({ chat_id: 'c1', generated_at: '2026-01-01T00:00:00Z', events: [] });
+    const signature = createHmac('sha256', 'test-secret-123').update(payload).digest('hex');
+    expect(verifyTranscript({ payload, signature })?.chat_id).toBe('c1');
+    expect(verifyTranscript({ payload: payload.replace('c1', 'c2'), signature })).toBeNull();
+  });
+});